@@ -62,7 +62,8 @@ use syn::parse_macro_input;
 ///     announcement. `M` must be either a number, or an expression that evaluates to `Option<u32>`.
 ///   - `C` is the set of communities present in the route, and is optional. Similar to `P`, it can
 ///     also either take a single number, an array of numbers, or any other arbitrary expression
-///     that evaluates to `impl Iterator<Item = I> where I: Into<u32>`.
+///     that evaluates to `impl IntoIterator<Item = Community>`. A number `x` is the RFC 1997
+///     encoding of a community, i.e., `0:x` for small numbers (see `bgpsim::types::Community`).
 ///
 /// - `Prefix`: The type of the prefix. Choose either `SinglePrefix`, `SimplePrefix`, or
 ///   `Ipv4Prefix` here (optional).
@@ -147,7 +148,7 @@ use syn::parse_macro_input;
 ///             Ipv4Net::new(Ipv4Addr::new(10, 0, 0, 0),8).unwrap(),
 ///             [1, 3, 4],
 ///             Some(100),
-///             [20],
+///             [::bgpsim::types::Community::from(20)],
 ///         ).unwrap();
 ///     _net.advertise_external_route(
 ///             e1,
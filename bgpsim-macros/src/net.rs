@@ -245,7 +245,9 @@ impl Net {
                 } else {
                     quote!(None)
                 });
-                let communities = r.communities.quote(|c| quote!([#(#c),*]));
+                let communities = r
+                    .communities
+                    .quote(|c| quote!([#(::bgpsim::types::Community::from(#c)),*]));
                 quote! {
                     _net.advertise_external_route(#source, #prefix, #as_path, #med, #communities).unwrap();
                 }
@@ -21,7 +21,7 @@ use bgpsim::{
     bgp::BgpRoute,
     formatter::NetworkFormatter,
     prelude::BgpSessionType,
-    types::{AsId, RouterId},
+    types::{AsId, Community, RouterId},
 };
 use itertools::join;
 use yew::prelude::*;
@@ -107,7 +107,7 @@ pub fn ExternalRouterCfg(props: &Properties) -> Html {
         };
         prefix_input_correct.set(false);
         Dispatch::<Net>::new().reduce_mut(move |net| {
-            let _ = net.net_mut().advertise_external_route::<Option<AsId>, Option<u32>>(id, p, None, None, None);
+            let _ = net.net_mut().advertise_external_route::<Option<AsId>, Option<Community>>(id, p, None, None, None);
         });
     });
     let on_route_update = callback!(move |(prefix, route): (Pfx, BgpRoute<Pfx>)| {
@@ -120,7 +120,7 @@ pub fn ExternalRouterCfg(props: &Properties) -> Html {
                 route.prefix,
                 route.as_path,
                 route.med,
-                route.community,
+                route.communities,
             );
         });
     });
@@ -295,22 +295,22 @@ fn AdvertisedRouteCfg(props: &AdvertisedRouteProperties) -> Html {
         on_update.emit((prefix, route));
     });
 
-    let community_text = join(route.community.iter(), "; ");
+    let community_text = join(route.communities.iter(), "; ");
     let on_community_change = callback!(community_input_correct -> move |new_c: String| {
         community_input_correct.set(new_c
             .split(';')
             .flat_map(|s| s.split(','))
             .map(|s| s.trim())
-            .map(|s| s.parse::<u32>())
+            .map(|s| s.parse::<Community>())
             .all(|r| r.is_ok()));
     });
     let on_community_set = callback!(route, on_update -> move |new_c: String| {
         let mut route = route.clone();
-        route.community = new_c
+        route.communities = new_c
             .split(';')
             .flat_map(|s| s.split(','))
             .map(|s| s.trim())
-            .filter_map(|s| s.parse::<u32>().ok())
+            .filter_map(|s| s.parse::<Community>().ok())
             .collect();
         on_update.emit((prefix, route));
     });
@@ -19,7 +19,7 @@ use std::{collections::HashSet, rc::Rc};
 
 use bgpsim::{
     route_map::{RouteMap, RouteMapFlow, RouteMapMatch, RouteMapSet, RouteMapState},
-    types::RouterId,
+    types::{Community, RouterId},
 };
 use yew::prelude::*;
 
@@ -84,14 +84,16 @@ impl Component for RouteMapCfg {
 
         let add_match = {
             let n = ctx.props().map.conds.len();
-            ctx.link()
-                .callback(move |_| Msg::UpdateMatch((n, Some(RouteMapMatch::Community(0)))))
+            ctx.link().callback(move |_| {
+                Msg::UpdateMatch((n, Some(RouteMapMatch::Community(Community::from(0)))))
+            })
         };
 
         let add_set = {
             let n = ctx.props().map.set.len();
-            ctx.link()
-                .callback(move |_| Msg::UpdateSet((n, Some(RouteMapSet::SetCommunity(0)))))
+            ctx.link().callback(move |_| {
+                Msg::UpdateSet((n, Some(RouteMapSet::SetCommunity(Community::from(0)))))
+            })
         };
 
         let on_remove = {
@@ -21,7 +21,7 @@ use bgpsim::{
     formatter::NetworkFormatter,
     prefix,
     route_map::{RouteMapMatch, RouteMapMatchAsPath, RouteMapMatchClause},
-    types::RouterId,
+    types::{Community, RouterId},
 };
 use itertools::Itertools;
 use yew::prelude::*;
@@ -163,6 +163,7 @@ impl RouteMapMatchCfg {
 enum MatchValue {
     None,
     Integer(u32),
+    Community(Community),
     Router(RouterId),
     List(BTreeSet<u32>),
     PrefixList(BTreeSet<Pfx>),
@@ -177,6 +178,9 @@ impl MatchValue {
         if let Ok(x) = s.parse::<u32>() {
             return Some(Self::Integer(x));
         }
+        if let Ok(c) = s.parse::<Community>() {
+            return Some(Self::Community(c));
+        }
         if let Some(vs) = s
             .split([',', ';'])
             .map(|x| Pfx::from_str(x.trim()).ok())
@@ -204,6 +208,7 @@ impl MatchValue {
         match self {
             MatchValue::None => String::new(),
             MatchValue::Integer(x) => x.to_string(),
+            MatchValue::Community(x) => x.to_string(),
             MatchValue::Router(r) => r.fmt(&net.net()).to_string(),
             MatchValue::List(x) => x.iter().join("; "),
             MatchValue::PrefixList(x) => x.iter().join("; "),
@@ -220,6 +225,8 @@ fn match_kind_text(m: &RouteMapMatch<Pfx>) -> &'static str {
         RouteMapMatch::NextHop(_) => "Next-Hop is",
         RouteMapMatch::Community(_) => "Has community",
         RouteMapMatch::DenyCommunity(_) => "Deny community",
+        RouteMapMatch::AsPathList(_) => "Path in list",
+        RouteMapMatch::Tag(_) => "Has tag",
     }
 }
 
@@ -231,8 +238,9 @@ fn match_kind_options() -> Vec<(RouteMapMatch<Pfx>, String)> {
             1, 10,
         ))),
         RouteMapMatch::NextHop(0.into()),
-        RouteMapMatch::Community(0),
-        RouteMapMatch::DenyCommunity(0),
+        RouteMapMatch::Community(Community::from(0)),
+        RouteMapMatch::DenyCommunity(Community::from(0)),
+        RouteMapMatch::Tag(0),
     ]
     .map(|kind| {
         let text = match_kind_text(&kind).to_string();
@@ -253,8 +261,9 @@ fn match_values(m: &RouteMapMatch<Pfx>) -> MatchValue {
             MatchValue::Range(*v1 as u32, *v2 as u32)
         }
         RouteMapMatch::NextHop(v) => MatchValue::Router(*v),
-        RouteMapMatch::Community(v) => MatchValue::Integer(*v),
-        RouteMapMatch::DenyCommunity(v) => MatchValue::Integer(*v),
+        RouteMapMatch::Community(v) => MatchValue::Community(*v),
+        RouteMapMatch::DenyCommunity(v) => MatchValue::Community(*v),
+        RouteMapMatch::Tag(v) => MatchValue::Integer(*v),
         _ => MatchValue::None,
     }
 }
@@ -278,10 +287,15 @@ fn match_update(m: &RouteMapMatch<Pfx>, val: MatchValue) -> Option<RouteMapMatch
             )))
         }
         (RouteMapMatch::NextHop(_), MatchValue::Router(r)) => RouteMapMatch::NextHop(r),
-        (RouteMapMatch::Community(_), MatchValue::Integer(x)) => RouteMapMatch::Community(x),
+        (RouteMapMatch::Community(_), MatchValue::Integer(x)) => RouteMapMatch::Community(x.into()),
+        (RouteMapMatch::Community(_), MatchValue::Community(x)) => RouteMapMatch::Community(x),
         (RouteMapMatch::DenyCommunity(_), MatchValue::Integer(x)) => {
+            RouteMapMatch::DenyCommunity(x.into())
+        }
+        (RouteMapMatch::DenyCommunity(_), MatchValue::Community(x)) => {
             RouteMapMatch::DenyCommunity(x)
         }
+        (RouteMapMatch::Tag(_), MatchValue::Integer(x)) => RouteMapMatch::Tag(x),
         _ => return None,
     })
 }
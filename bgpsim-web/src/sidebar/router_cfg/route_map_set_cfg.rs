@@ -17,7 +17,11 @@
 
 use std::rc::Rc;
 
-use bgpsim::{formatter::NetworkFormatter, route_map::RouteMapSet, types::RouterId};
+use bgpsim::{
+    formatter::NetworkFormatter,
+    route_map::RouteMapSet,
+    types::{Community, RouterId},
+};
 use yew::prelude::*;
 use yewdux::prelude::*;
 
@@ -154,6 +158,7 @@ enum SetValue {
     None,
     Integer(u32),
     Float(f64),
+    Community(Community),
     Router(RouterId),
 }
 
@@ -163,6 +168,7 @@ impl SetValue {
             .map(Self::Integer)
             .ok()
             .or_else(|| s.parse::<f64>().map(Self::Float).ok())
+            .or_else(|| s.parse::<Community>().map(Self::Community).ok())
     }
 
     fn fmt(&self, net: &Net) -> String {
@@ -170,6 +176,7 @@ impl SetValue {
             SetValue::None => String::new(),
             SetValue::Integer(x) => x.to_string(),
             SetValue::Float(x) => x.to_string(),
+            SetValue::Community(x) => x.to_string(),
             SetValue::Router(r) => r.fmt(&net.net()).to_string(),
         }
     }
@@ -187,6 +194,12 @@ fn set_kind_text(set: &RouteMapSet) -> &'static str {
         RouteMapSet::DelCommunity(_) => "del community",
         RouteMapSet::Weight(Some(_)) => "set weight",
         RouteMapSet::Weight(None) => "clear weight",
+        RouteMapSet::ClampLocalPref(_, _) => "clamp Local Pref",
+        RouteMapSet::ClampMed(_, _) => "clamp MED",
+        RouteMapSet::AsPathPrepend(_, _) => "prepend AS path",
+        RouteMapSet::Tag(_) => "set tag",
+        RouteMapSet::LocalPrefFromIgpCost(_, _) => "Local Pref from IGP",
+        RouteMapSet::Dampen => "dampen",
     }
 }
 
@@ -198,10 +211,12 @@ fn set_kind_options(router: RouterId) -> Vec<(RouteMapSet, String)> {
         RouteMapSet::Med(Some(100)),
         RouteMapSet::Med(None),
         RouteMapSet::IgpCost(1.0),
-        RouteMapSet::SetCommunity(0),
-        RouteMapSet::DelCommunity(0),
+        RouteMapSet::SetCommunity(Community::from(0)),
+        RouteMapSet::DelCommunity(Community::from(0)),
         RouteMapSet::Weight(Some(100)),
         RouteMapSet::Weight(None),
+        RouteMapSet::Tag(0),
+        RouteMapSet::Dampen,
     ]
     .map(|kind| {
         let text = set_kind_text(&kind).to_string();
@@ -219,10 +234,15 @@ fn set_value(set: &RouteMapSet) -> SetValue {
         RouteMapSet::Med(Some(x)) => SetValue::Integer(*x),
         RouteMapSet::Med(None) => SetValue::None,
         RouteMapSet::IgpCost(x) => SetValue::Float(*x),
-        RouteMapSet::SetCommunity(x) => SetValue::Integer(*x),
-        RouteMapSet::DelCommunity(x) => SetValue::Integer(*x),
+        RouteMapSet::SetCommunity(x) => SetValue::Community(*x),
+        RouteMapSet::DelCommunity(x) => SetValue::Community(*x),
         RouteMapSet::Weight(Some(x)) => SetValue::Integer(*x),
         RouteMapSet::Weight(None) => SetValue::None,
+        RouteMapSet::ClampLocalPref(_, _) | RouteMapSet::ClampMed(_, _) => SetValue::None,
+        RouteMapSet::AsPathPrepend(_, _) => SetValue::None,
+        RouteMapSet::LocalPrefFromIgpCost(_, _) => SetValue::None,
+        RouteMapSet::Dampen => SetValue::None,
+        RouteMapSet::Tag(x) => SetValue::Integer(*x),
     }
 }
 
@@ -235,10 +255,14 @@ fn set_update(set: &RouteMapSet, val: SetValue) -> Option<RouteMapSet> {
         (RouteMapSet::Med(None), SetValue::None) => RouteMapSet::Med(None),
         (RouteMapSet::IgpCost(_), SetValue::Float(x)) => RouteMapSet::IgpCost(x),
         (RouteMapSet::IgpCost(_), SetValue::Integer(x)) => RouteMapSet::IgpCost(x as f64),
-        (RouteMapSet::SetCommunity(_), SetValue::Integer(x)) => RouteMapSet::SetCommunity(x),
-        (RouteMapSet::DelCommunity(_), SetValue::Integer(x)) => RouteMapSet::DelCommunity(x),
+        (RouteMapSet::SetCommunity(_), SetValue::Integer(x)) => RouteMapSet::SetCommunity(x.into()),
+        (RouteMapSet::SetCommunity(_), SetValue::Community(x)) => RouteMapSet::SetCommunity(x),
+        (RouteMapSet::DelCommunity(_), SetValue::Integer(x)) => RouteMapSet::DelCommunity(x.into()),
+        (RouteMapSet::DelCommunity(_), SetValue::Community(x)) => RouteMapSet::DelCommunity(x),
         (RouteMapSet::Weight(Some(_)), SetValue::Integer(x)) => RouteMapSet::Weight(Some(x)),
         (RouteMapSet::Weight(None), SetValue::None) => RouteMapSet::Weight(None),
+        (RouteMapSet::Tag(_), SetValue::Integer(x)) => RouteMapSet::Tag(x),
+        (RouteMapSet::Dampen, SetValue::None) => RouteMapSet::Dampen,
         _ => return None,
     })
 }
@@ -474,8 +474,8 @@ pub fn RouteTable(props: &RouteTableProps) -> Html {
                 } else { html!{} }
             }
             {
-                if !props.route.community.is_empty() {
-                    html!{<tr> <td class="italic text-main-ia"> {"Communities: "} </td> <td> {join(props.route.community.iter(), ", ")} </td> </tr>}
+                if !props.route.communities.is_empty() {
+                    html!{<tr> <td class="italic text-main-ia"> {"Communities: "} </td> <td> {join(props.route.communities.iter(), ", ")} </td> </tr>}
                 } else { html!{} }
             }
         </table>
@@ -522,7 +522,7 @@ pub fn rib_table(props: &RibTableProps) -> Html {
                             <td> {r.route.local_pref.map(|x| x.to_string()).unwrap_or_default()} </td>
                             <td> {r.route.med.map(|x| x.to_string()).unwrap_or_default()} </td>
                             <td> {r.igp_cost.map(|x| x.to_string()).unwrap_or_default()} </td>
-                            <td> {r.route.community.iter().join(", ")} </td>
+                            <td> {r.route.communities.iter().join(", ")} </td>
                         </tr>
                     }
                 }).collect::<Html>()
@@ -0,0 +1,107 @@
+// BgpSim: BGP Network Simulator written in Rust
+// Copyright 2022-2024 Tibor Schneider <sctibor@ethz.ch>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Count the number of heap allocations performed while building the network, while the network
+//! converges, and while computing and cloning the forwarding state. All random choices are seeded,
+//! so the numbers are reproducible.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+mod common;
+use bgpsim::builder::*;
+use bgpsim::prelude::*;
+use bgpsim::topology_zoo::TopologyZoo;
+use common::*;
+use rand::prelude::*;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Run `f` and return the number of allocations and the number of allocated bytes.
+fn count<T>(f: impl FnOnce() -> T) -> (T, usize, usize) {
+    let allocs = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = BYTES.load(Ordering::Relaxed);
+    let result = f();
+    (
+        result,
+        ALLOCATIONS.load(Ordering::Relaxed) - allocs,
+        BYTES.load(Ordering::Relaxed) - bytes,
+    )
+}
+
+/// Build the topology with external routers and BGP sessions, but without any advertisement. All
+/// random choices use `rng`, such that the number of allocations is reproducible.
+fn build_net<P: Prefix>(rng: &mut StdRng) -> Network<P, BasicEventQueue<P>> {
+    let mut net = TopologyZoo::Bellsouth.build(basic_queue());
+    net.set_msg_limit(Some(1_000_000));
+    net.build_connected_graph();
+    net.build_external_routers(extend_to_k_external_routers_seeded, (&mut *rng, 5))
+        .unwrap();
+    net.build_link_weights_seeded(rng, uniform_integer_link_weight_seeded, (10, 100))
+        .unwrap();
+    net.build_ibgp_route_reflection(k_highest_degree_nodes_seeded, (&mut *rng, 3))
+        .unwrap();
+    net.build_ebgp_sessions().unwrap();
+    net
+}
+
+fn measure<P: Prefix>(name: &str) {
+    let mut rng = StdRng::seed_from_u64(42);
+    let (mut net, allocs, bytes) = count(|| build_net::<P>(&mut rng));
+    println!("{name} setup:    {allocs:>10} allocations, {bytes:>12} bytes");
+    let (prefs, allocs, bytes) = count(|| {
+        net.build_advertisements(P::from(0), unique_preferences_seeded, (&mut rng, 5))
+            .unwrap()
+    });
+    println!("{name} converge: {allocs:>10} allocations, {bytes:>12} bytes");
+    // withdraw the most preferred route, such that all routers select a new one.
+    let (_, allocs, bytes) = count(|| net.withdraw_external_route(prefs[0][0], P::from(0)));
+    println!("{name} withdraw: {allocs:>10} allocations, {bytes:>12} bytes");
+    let (_, allocs, bytes) = count(|| net.clone());
+    println!("{name} clone:    {allocs:>10} allocations, {bytes:>12} bytes");
+    let (fw_state, allocs, bytes) = count(|| net.get_forwarding_state());
+    println!("{name} fw state: {allocs:>10} allocations, {bytes:>12} bytes");
+    let (_, allocs, bytes) = count(|| fw_state.clone());
+    println!("{name} fw clone: {allocs:>10} allocations, {bytes:>12} bytes");
+}
+
+fn main() {
+    measure::<SinglePrefix>("SinglePrefix");
+    measure::<SimplePrefix>("SimplePrefix");
+}
@@ -17,10 +17,12 @@ use std::time::Duration;
 use std::time::Instant;
 
 use criterion::black_box;
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
 
 mod common;
+use bgpsim::bgp::BgpRoute;
 use bgpsim::prelude::*;
+use bgpsim::types::PrefixMap;
 use common::*;
 
 pub fn benchmark_generation<P: Prefix>(c: &mut Criterion) {
@@ -34,6 +36,65 @@ pub fn benchmark_clone<P: Prefix>(c: &mut Criterion) {
     c.bench_function("clone", |b| b.iter(|| black_box(net.clone())));
 }
 
+pub fn benchmark_warm_start<P: Prefix>(c: &mut Criterion) {
+    use bgpsim::builder::*;
+
+    let mut net = setup_net::<P, _>(basic_queue()).unwrap();
+    for i in 1..50 {
+        net.build_advertisements(P::from(i), unique_preferences, 5)
+            .unwrap();
+    }
+
+    // the target state withdraws a single prefix from the first external router.
+    let e1 = net.external_indices().next().unwrap();
+    let mut target = net.clone();
+    target.manual_simulation();
+    target.withdraw_external_route(e1, P::from(0)).unwrap();
+    let routes: Vec<(RouterId, BgpRoute<P>)> = target
+        .external_routers()
+        .flat_map(|r| {
+            r.get_advertised_routes()
+                .iter()
+                .map(move |(_, route)| (r.router_id(), route.clone()))
+        })
+        .collect();
+
+    c.bench_function("cold_start", |b| {
+        b.iter_batched(
+            || {
+                let mut cold = net.clone();
+                cold.reset();
+                cold
+            },
+            |mut cold| {
+                for (r, route) in routes.iter() {
+                    cold.advertise_external_route(
+                        *r,
+                        route.prefix,
+                        route.as_path.clone(),
+                        route.med,
+                        route.communities.iter().copied(),
+                    )
+                    .unwrap();
+                }
+                black_box(cold)
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    c.bench_function("warm_start", |b| {
+        b.iter_batched(
+            || target.clone(),
+            |mut warm| {
+                warm.warm_start(&net).unwrap();
+                warm.simulate().unwrap();
+                black_box(warm)
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
 pub fn setup_measure<P, Q, F>(iters: u64, queue: Q, function: F) -> Duration
 where
     P: Prefix,
@@ -56,5 +117,6 @@ criterion_group!(
     benchmark_generation::<SimplePrefix>,
     benchmark_clone::<SinglePrefix>,
     benchmark_clone::<SimplePrefix>,
+    benchmark_warm_start::<SimplePrefix>,
 );
 criterion_main!(benches);
@@ -0,0 +1,72 @@
+// BgpSim: BGP Network Simulator written in Rust
+// Copyright 2022-2024 Tibor Schneider <sctibor@ethz.ch>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use criterion::black_box;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+mod common;
+use bgpsim::policies::*;
+use bgpsim::prelude::*;
+use common::*;
+
+/// Compare re-checking ~200 policies after a single link failure, either by evaluating all
+/// policies on both states, or by only evaluating the affected ones.
+pub fn benchmark_recheck<P: Prefix>(c: &mut Criterion) {
+    let mut net = setup_net::<P, _>(basic_queue()).unwrap();
+    let prefix = P::from(0);
+    let routers: Vec<RouterId> = net.internal_indices().collect();
+    let policies: Vec<FwPolicy<P>> = routers
+        .iter()
+        .flat_map(|r| {
+            [
+                FwPolicy::Reachable(*r, prefix),
+                FwPolicy::LoopFree(*r, prefix),
+                FwPolicy::LoadBalancing(*r, prefix, 1),
+                FwPolicy::PathCondition(*r, prefix, PathCondition::Node(routers[0])),
+            ]
+        })
+        .collect();
+
+    // fail the first link on the path of the first router.
+    let prev_state = net.get_forwarding_state();
+    let path = prev_state.clone().get_paths(routers[0], prefix).unwrap()[0].clone();
+    net.remove_link(path[0], path[1]).unwrap();
+    let new_state = net.get_forwarding_state();
+
+    c.bench_function("full recheck", |b| {
+        b.iter(|| {
+            let old = check_all(&mut prev_state.clone(), &policies);
+            let new = check_all(&mut new_state.clone(), &policies);
+            black_box(
+                old.into_iter()
+                    .zip(new)
+                    .filter(|(a, b)| a.is_ok() != b.is_ok())
+                    .count(),
+            )
+        })
+    });
+    c.bench_function("incremental recheck", |b| {
+        b.iter(|| {
+            black_box(recheck(
+                &mut prev_state.clone(),
+                &mut new_state.clone(),
+                &policies,
+            ))
+        })
+    });
+}
+
+criterion_group!(benches, benchmark_recheck::<SimplePrefix>);
+criterion_main!(benches);
@@ -0,0 +1,46 @@
+// BgpSim: BGP Network Simulator written in Rust
+// Copyright 2022-2024 Tibor Schneider <sctibor@ethz.ch>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Explore a small network interactively. Commands are read from `stdin`, e.g.:
+//!
+//! ```text
+//! advertise E1 100.0.0.0/24 100
+//! trace R2 100.0.0.0/24
+//! fail R1 E1
+//! check 100.0.0.0/24
+//! ```
+
+use std::io;
+
+use bgpsim::{interactive::repl::run, prelude::*};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut net: Network<Ipv4Prefix, _, GlobalOspf> = Network::default();
+    let r1 = net.add_router("R1");
+    let r2 = net.add_router("R2");
+    let e1 = net.add_external_router("E1", 100);
+    let e2 = net.add_external_router("E2", 200);
+    net.add_link(r1, r2)?;
+    net.add_link(r1, e1)?;
+    net.add_link(r2, e2)?;
+    net.set_link_weight(r1, r2, 1.0)?;
+    net.set_link_weight(r2, r1, 1.0)?;
+    net.set_bgp_session(r1, r2, Some(BgpSessionType::IBgpPeer))?;
+    net.set_bgp_session(r1, e1, Some(BgpSessionType::EBgp))?;
+    net.set_bgp_session(r2, e2, Some(BgpSessionType::EBgp))?;
+
+    run(&mut net, io::stdin().lock(), io::stdout())?;
+    Ok(())
+}
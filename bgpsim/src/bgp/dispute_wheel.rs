@@ -0,0 +1,223 @@
+// BgpSim: BGP Network Simulator written in Rust
+// Copyright 2022-2024 Tibor Schneider <sctibor@ethz.ch>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This module contains a static analysis that searches for dispute wheels, as described by
+//! Griffin, Shepherd and Wilfong in "The Stable Paths Problem and Interdomain Routing". If the
+//! configuration contains no dispute wheel, BGP is guaranteed to converge to a unique stable state.
+//! A dispute wheel, on the other hand, indicates that the network may oscillate (the canonical
+//! example is the BAD GADGET).
+
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    iter::once,
+};
+
+use itertools::Itertools;
+
+use crate::{
+    bgp::BgpRibEntry,
+    network::Network,
+    ospf::OspfImpl,
+    types::{Prefix, RouterId},
+};
+
+/// A dispute wheel for a single prefix. Each pivot `pivots[i]` has a spoke path `spokes[i]`
+/// towards the external router that advertises the route, and a rim path `rims[i]` towards the
+/// next pivot `pivots[(i + 1) % k]`. Each pivot prefers the route along its rim and the spoke of
+/// the next pivot over the route along its own spoke.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisputeWheel<P: Prefix> {
+    /// The prefix for which the dispute wheel exists.
+    pub prefix: P,
+    /// The pivot routers of the wheel.
+    pub pivots: Vec<RouterId>,
+    /// The spoke path of each pivot, starting at the pivot and ending at the external router that
+    /// advertises the route.
+    pub spokes: Vec<Vec<RouterId>>,
+    /// The rim path of each pivot, starting at the pivot and ending at the next pivot.
+    pub rims: Vec<Vec<RouterId>>,
+}
+
+impl<P: Prefix> DisputeWheel<P> {
+    /// Search for a dispute wheel for `prefix` in the current configuration of the network. This
+    /// function enumerates all paths along which the routes advertised by external routers can
+    /// propagate (considering the BGP sessions, route-maps and the current IGP state), and compares
+    /// them using the BGP decision process of each router. The network is not modified.
+    ///
+    /// The number of paths can grow exponentially with the size of the network. This analysis is
+    /// meant for small (gadget-sized) networks.
+    pub fn from_net<Q, Ospf: OspfImpl>(net: &Network<P, Q, Ospf>, prefix: P) -> Option<Self> {
+        let paths = permitted_paths(net, prefix);
+        let index: HashMap<&[RouterId], usize> = paths
+            .iter()
+            .enumerate()
+            .map(|(i, (path, _))| (path.as_slice(), i))
+            .collect();
+
+        let mut at_router: BTreeMap<RouterId, Vec<usize>> = BTreeMap::new();
+        for (i, (path, _)) in paths.iter().enumerate() {
+            at_router.entry(path[0]).or_default().push(i);
+        }
+
+        // Build the graph of spokes. An edge from `s` to `q` (with witness `x`) exists if the head
+        // of `q` prefers `x` over `q`, where `x` is the path along some rim, followed by `s`.
+        let mut edges: Vec<Vec<(usize, usize)>> = vec![Vec::new(); paths.len()];
+        for (router, ids) in at_router {
+            let Ok(r) = net.get_internal_router(router) else {
+                continue;
+            };
+            let as_path_ignore = r.bgp.get_bestpath_as_path_ignore();
+            for (&x, &q) in ids.iter().cartesian_product(ids.iter()) {
+                if paths[x].1.cmp_bestpath(&paths[q].1, as_path_ignore) != Ordering::Greater {
+                    continue;
+                }
+                let x_path = &paths[x].0;
+                for j in 1..x_path.len() - 1 {
+                    if let Some(&s) = index.get(&x_path[j..]) {
+                        edges[s].push((q, x));
+                    }
+                }
+            }
+        }
+
+        let cycle = find_cycle(&edges)?;
+
+        // each edge `s -> q` describes the pivot at the head of `q`, whose rim leads to the head of
+        // `s`. Hence, the edges of the cycle are traversed in reverse.
+        let mut wheel = Self {
+            prefix,
+            pivots: Vec::new(),
+            spokes: Vec::new(),
+            rims: Vec::new(),
+        };
+        for (s, q, x) in cycle.into_iter().rev() {
+            let spoke = paths[q].0.clone();
+            let x_path = &paths[x].0;
+            let rim = x_path[..=x_path.len() - paths[s].0.len()].to_vec();
+            wheel.pivots.push(spoke[0]);
+            wheel.spokes.push(spoke);
+            wheel.rims.push(rim);
+        }
+
+        Some(wheel)
+    }
+
+    /// Get all routers that participate in the dispute wheel, including the external routers at
+    /// the end of the spokes.
+    pub fn routers(&self) -> BTreeSet<RouterId> {
+        self.spokes
+            .iter()
+            .chain(self.rims.iter())
+            .flatten()
+            .copied()
+            .collect()
+    }
+}
+
+/// Enumerate all paths along which routes for `prefix` can propagate from an external router, and
+/// return each path (starting at the receiving router) together with the processed route at the
+/// first router of the path.
+fn permitted_paths<P: Prefix, Q, Ospf: OspfImpl>(
+    net: &Network<P, Q, Ospf>,
+    prefix: P,
+) -> Vec<(Vec<RouterId>, BgpRibEntry<P>)> {
+    let mut stack = Vec::new();
+    for ext in net.external_routers().sorted_by_key(|r| r.router_id()) {
+        let Some(route) = ext.get_advertised_route(prefix) else {
+            continue;
+        };
+        for r in ext.get_bgp_sessions().iter().sorted() {
+            let Ok(router) = net.get_internal_router(*r) else {
+                continue;
+            };
+            if let Some(entry) = router
+                .bgp
+                .preview_incoming_route(route.clone(), ext.router_id())
+            {
+                stack.push((vec![*r, ext.router_id()], entry));
+            }
+        }
+    }
+
+    let mut paths = Vec::new();
+    while let Some((path, entry)) = stack.pop() {
+        let u = path[0];
+        if let Ok(router) = net.get_internal_router(u) {
+            for v in router.bgp.get_sessions().keys().sorted() {
+                if path.contains(v) {
+                    continue;
+                }
+                let Ok(peer) = net.get_internal_router(*v) else {
+                    continue;
+                };
+                if let Some(received) = router
+                    .bgp
+                    .preview_outgoing_route(&entry, *v)
+                    .and_then(|route| peer.bgp.preview_incoming_route(route, u))
+                {
+                    stack.push((once(*v).chain(path.iter().copied()).collect(), received));
+                }
+            }
+        }
+        paths.push((path, entry));
+    }
+
+    paths
+}
+
+/// Find a cycle in the directed graph given by `edges`, where each edge carries a witness. The
+/// cycle is returned as a sequence of edges `(from, to, witness)`.
+fn find_cycle(edges: &[Vec<(usize, usize)>]) -> Option<Vec<(usize, usize, usize)>> {
+    // 0: not visited, 1: on the stack, 2: done
+    let mut state = vec![0u8; edges.len()];
+    for start in 0..edges.len() {
+        if state[start] != 0 {
+            continue;
+        }
+        state[start] = 1;
+        // stack of nodes, together with the index of the next edge to explore.
+        let mut stack: Vec<(usize, usize)> = vec![(start, 0)];
+        while let Some((v, i)) = stack.last().copied() {
+            let Some(&(w, _)) = edges[v].get(i) else {
+                state[v] = 2;
+                stack.pop();
+                continue;
+            };
+            stack.last_mut().unwrap().1 += 1;
+            match state[w] {
+                0 => {
+                    state[w] = 1;
+                    stack.push((w, 0));
+                }
+                1 => {
+                    // found a cycle. Reconstruct it from the stack, starting at `w`.
+                    let pos = stack.iter().position(|(n, _)| *n == w).unwrap();
+                    return Some(
+                        stack[pos..]
+                            .iter()
+                            .map(|(n, i)| {
+                                let (to, witness) = edges[*n][*i - 1];
+                                (*n, to, witness)
+                            })
+                            .collect(),
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+    None
+}
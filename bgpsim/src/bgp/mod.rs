@@ -15,17 +15,23 @@
 
 //! Module containing definitions for BGP
 
+mod dispute_wheel;
+mod rpki;
 mod state;
+pub use dispute_wheel::*;
+pub use rpki::*;
 pub use state::*;
 
 use crate::{
     ospf::LinkWeight,
-    types::{AsId, Prefix, RouterId},
+    route_map::RouteMap,
+    types::{AsId, Community, Prefix, PrefixSet, RouterId},
 };
 
+use itertools::Itertools;
 use ordered_float::NotNan;
 use serde::{Deserialize, Serialize};
-use std::{cmp::Ordering, collections::BTreeSet, hash::Hash};
+use std::{cmp::Ordering, collections::BTreeSet, hash::Hash, net::Ipv4Addr};
 
 /// Bgp Route
 /// The following attributes are omitted
@@ -45,12 +51,14 @@ pub struct BgpRoute<P: Prefix> {
     pub local_pref: Option<u32>,
     /// MED (Multi-Exit Discriminator)
     pub med: Option<u32>,
-    /// Community
-    pub community: BTreeSet<u32>,
+    /// Communities
+    #[serde(alias = "community")]
+    pub communities: BTreeSet<Community>,
     /// Optional field ORIGINATOR_ID
     pub originator_id: Option<RouterId>,
-    /// Optional field CLUSTER_LIST
-    pub cluster_list: Vec<RouterId>,
+    /// Optional field CLUSTER_LIST, containing the cluster IDs of all route reflectors that
+    /// reflected the route (see [`crate::network::Network::set_cluster_id`]).
+    pub cluster_list: Vec<u32>,
 }
 
 impl<P: Prefix> BgpRoute<P> {
@@ -60,12 +68,12 @@ impl<P: Prefix> BgpRoute<P> {
         prefix: impl Into<P>,
         as_path: A,
         med: Option<u32>,
-        community: C,
+        communities: C,
     ) -> Self
     where
         A: IntoIterator,
         A::Item: Into<AsId>,
-        C: IntoIterator<Item = u32>,
+        C: IntoIterator<Item = Community>,
     {
         let as_path: Vec<AsId> = as_path.into_iter().map(|id| id.into()).collect();
         Self {
@@ -74,7 +82,7 @@ impl<P: Prefix> BgpRoute<P> {
             next_hop,
             local_pref: None,
             med,
-            community: community.into_iter().collect(),
+            communities: communities.into_iter().collect(),
             originator_id: None,
             cluster_list: Vec::new(),
         }
@@ -97,12 +105,22 @@ impl<P: Prefix> BgpRoute<P> {
             next_hop: self.next_hop,
             local_pref: Some(self.local_pref.unwrap_or(100)),
             med: Some(self.med.unwrap_or(0)),
-            community: self.community.clone(),
+            communities: self.communities.clone(),
             originator_id: self.originator_id,
             cluster_list: self.cluster_list.clone(),
         }
     }
 
+    /// Get the LOCAL-PREF of the route, or its default value (100) if it is not set.
+    fn local_pref_or_default(&self) -> u32 {
+        self.local_pref.unwrap_or(100)
+    }
+
+    /// Get the MED of the route, or its default value (0) if it is not set.
+    fn med_or_default(&self) -> u32 {
+        self.med.unwrap_or(0)
+    }
+
     /// Change the prefix type of the route.
     pub fn with_prefix<P2: Prefix>(self, prefix: P2) -> BgpRoute<P2> {
         BgpRoute {
@@ -111,7 +129,7 @@ impl<P: Prefix> BgpRoute<P> {
             next_hop: self.next_hop,
             local_pref: self.local_pref,
             med: self.med,
-            community: self.community,
+            communities: self.communities,
             originator_id: self.originator_id,
             cluster_list: self.cluster_list,
         }
@@ -120,25 +138,22 @@ impl<P: Prefix> BgpRoute<P> {
 
 impl<P: Prefix> PartialEq for BgpRoute<P> {
     fn eq(&self, other: &Self) -> bool {
-        let s = self.clone_default();
-        let o = other.clone_default();
-        s.prefix == o.prefix
-            && s.as_path == other.as_path
-            && s.next_hop == o.next_hop
-            && s.local_pref == o.local_pref
-            && s.med == o.med
-            && s.community == o.community
-            && s.originator_id == o.originator_id
-            && s.cluster_list == o.cluster_list
+        self.prefix == other.prefix
+            && self.as_path == other.as_path
+            && self.next_hop == other.next_hop
+            && self.local_pref_or_default() == other.local_pref_or_default()
+            && self.med_or_default() == other.med_or_default()
+            && self.communities == other.communities
+            && self.originator_id == other.originator_id
+            && self.cluster_list == other.cluster_list
     }
 }
 
 impl<P: Prefix> Ord for BgpRoute<P> {
     fn cmp(&self, other: &Self) -> Ordering {
-        let s = self.clone_default();
-        let o = other.clone_default();
+        let (s, o) = (self, other);
 
-        match s.local_pref.unwrap().cmp(&o.local_pref.unwrap()) {
+        match s.local_pref_or_default().cmp(&o.local_pref_or_default()) {
             Ordering::Equal => {}
             o => return o,
         }
@@ -150,7 +165,7 @@ impl<P: Prefix> Ord for BgpRoute<P> {
         }
 
         if s.as_path.first() == o.as_path.first() {
-            match s.med.unwrap().cmp(&o.med.unwrap()) {
+            match s.med_or_default().cmp(&o.med_or_default()) {
                 Ordering::Equal => {}
                 Ordering::Greater => return Ordering::Less,
                 Ordering::Less => return Ordering::Greater,
@@ -179,18 +194,199 @@ impl<P: Prefix> PartialOrd for BgpRoute<P> {
 
 impl<P: Prefix> Hash for BgpRoute<P> {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        let s = self.clone_default();
-        s.prefix.hash(state);
-        s.as_path.hash(state);
-        s.next_hop.hash(state);
-        s.local_pref.hash(state);
-        s.med.hash(state);
-        s.community.hash(state);
+        self.prefix.hash(state);
+        self.as_path.hash(state);
+        self.next_hop.hash(state);
+        Some(self.local_pref_or_default()).hash(state);
+        Some(self.med_or_default()).hash(state);
+        self.communities.hash(state);
+    }
+}
+
+/// Well-known community `GRACEFUL_SHUTDOWN` (65535:0, RFC 8326). Routers lower the LOCAL-PREF of
+/// all routes carrying this community to 0, such that traffic is drained to alternative paths
+/// before a session is shut down for maintenance.
+pub const GRACEFUL_SHUTDOWN: Community = Community(65535, 0);
+
+/// Well-known community `NO_EXPORT` (65535:65281, RFC 1997). Routes carrying this community are
+/// not advertised over any eBGP session.
+pub const NO_EXPORT: Community = Community(65535, 65281);
+
+/// Well-known community `NO_ADVERTISE` (65535:65282, RFC 1997). Routes carrying this community are
+/// not advertised to any BGP neighbor.
+pub const NO_ADVERTISE: Community = Community(65535, 65282);
+
+/// Default number of recursive lookups a router performs to resolve the next-hop of a BGP route
+/// (see [`crate::network::Network::set_next_hop_resolution`]).
+pub const DEFAULT_RECURSION_LIMIT: usize = 8;
+
+/// Default attributes of a BGP session. They are applied to all routes received over that session
+/// which do not carry the attribute themselves, before applying any incoming route-map. This
+/// models, e.g., a provider that always sends a specific MED.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BgpSessionDefaults {
+    /// Default LOCAL-PREF
+    pub local_pref: Option<u32>,
+    /// Default MED (Multi-Exit Discriminator)
+    pub med: Option<u32>,
+}
+
+impl BgpSessionDefaults {
+    /// Fill all unset attributes of `route` with the defaults.
+    pub fn apply<P: Prefix>(&self, route: &mut BgpRoute<P>) {
+        if route.local_pref.is_none() {
+            route.local_pref = self.local_pref;
+        }
+        if route.med.is_none() {
+            route.med = self.med;
+        }
+    }
+}
+
+/// Prefix filter of a BGP session in one direction (`distribute-list`). The filter is applied
+/// before the route-maps of that direction. If `permit` is set, only routes for the listed
+/// prefixes pass the filter. Otherwise, routes for the listed prefixes are dropped.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "P: for<'a> serde::Deserialize<'a>"))]
+pub struct DistributeList<P: Prefix> {
+    /// The listed prefixes. They must match the prefix of a route exactly.
+    pub prefixes: P::Set,
+    /// Whether the listed prefixes are permitted (and all others denied), or denied.
+    pub permit: bool,
+}
+
+impl<P: Prefix> DistributeList<P> {
+    /// Create a new distribute-list.
+    pub fn new(prefixes: impl IntoIterator<Item = P>, permit: bool) -> Self {
+        Self {
+            prefixes: prefixes.into_iter().collect(),
+            permit,
+        }
+    }
+
+    /// Returns `true` if routes for `prefix` pass the filter.
+    pub fn allows(&self, prefix: &P) -> bool {
+        self.prefixes.contains(prefix) == self.permit
+    }
+}
+
+impl<P: Prefix> Hash for DistributeList<P> {
+    /// Hash the distribute-list. Prefixes are hashed in sorted order, which is consistent with
+    /// `PartialEq`.
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.prefixes.iter().sorted().for_each(|p| p.hash(state));
+        self.permit.hash(state);
+    }
+}
+
+/// A VRF (virtual routing and forwarding instance) on a router, with its own BGP table. Routes
+/// learned from the neighbors attached to the VRF are not used in the global BGP table, but only
+/// within the VRF. Routes are leaked between the VRFs of the same router using route targets:
+/// Routes selected in a VRF are tagged with its `export` targets (as communities), and every other
+/// VRF imports those routes that carry at least one of its `import` targets.
+#[derive(Debug, Clone, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(bound(deserialize = "P: for<'a> serde::Deserialize<'a>"))]
+pub struct Vrf<P: Prefix> {
+    /// BGP neighbors attached to the VRF.
+    pub neighbors: BTreeSet<RouterId>,
+    /// Route targets imported by the VRF.
+    pub import: BTreeSet<u32>,
+    /// Route targets with which routes of the VRF are exported.
+    pub export: BTreeSet<u32>,
+    /// Route-maps applied on all routes entering the VRF, i.e., on routes learned from its
+    /// neighbors and on routes leaked from other VRFs.
+    pub route_maps: Vec<RouteMap<P>>,
+}
+
+impl<P: Prefix> Default for Vrf<P> {
+    fn default() -> Self {
+        Self {
+            neighbors: Default::default(),
+            import: Default::default(),
+            export: Default::default(),
+            route_maps: Default::default(),
+        }
+    }
+}
+
+/// Attributes that a router propagates over a BGP session. By default, they follow the RFCs (see
+/// [`AttributePropagation::for_session`]), but they can be overwritten for each session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AttributePropagation {
+    /// Propagate the MED. If not set, the MED is cleared before applying the outgoing route-map,
+    /// such that the route-map can still set a new MED.
+    pub med: bool,
+    /// Propagate the LOCAL-PREF. If not set, the LOCAL-PREF is removed after applying the outgoing
+    /// route-map.
+    pub local_pref: bool,
+    /// Propagate the communities. If not set, all communities are removed after applying the
+    /// outgoing route-map.
+    pub communities: bool,
+}
+
+impl AttributePropagation {
+    /// Get the default attribute propagation for a session of the given type. On iBGP sessions,
+    /// all attributes are propagated. On eBGP sessions, both the MED and the LOCAL-PREF are
+    /// non-transitive, while communities are propagated.
+    pub fn for_session(session_type: BgpSessionType) -> Self {
+        let ibgp = session_type.is_ibgp();
+        Self {
+            med: ibgp,
+            local_pref: ibgp,
+            communities: true,
+        }
+    }
+}
+
+/// Options of a BGP session that do not affect the simulation, like authentication or the MTU
+/// of the link towards the neighbor. They are only passed through to the exported configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BgpSessionOptions {
+    /// Password used to authenticate the session (TCP MD5 signature).
+    pub password: Option<String>,
+    /// MTU of the interface towards the neighbor. This is only exported if the neighbor is
+    /// directly connected.
+    pub mtu: Option<u32>,
+}
+
+/// Template of a BGP peer-group, i.e., settings that are shared by many BGP sessions (see
+/// [`crate::network::Network::define_peer_group`]). Each member session is configured with the
+/// session type, the route-maps, and the options of the template. Changing the template updates
+/// all member sessions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "P: for<'a> serde::Deserialize<'a>"))]
+pub struct BgpPeerGroup<P: Prefix> {
+    /// Type of the member sessions, as seen from the router that configures them.
+    pub session_type: BgpSessionType,
+    /// Incoming route-maps of all member sessions.
+    pub route_maps_in: Vec<RouteMap<P>>,
+    /// Outgoing route-maps of all member sessions.
+    pub route_maps_out: Vec<RouteMap<P>>,
+    /// Whether next-hop-self is enabled on the member sessions (only affects iBGP sessions).
+    pub next_hop_self: bool,
+    /// Default attributes of routes received over the member sessions.
+    pub defaults: Option<BgpSessionDefaults>,
+    /// Options of the member sessions that do not affect the simulation.
+    pub options: Option<BgpSessionOptions>,
+}
+
+impl<P: Prefix> BgpPeerGroup<P> {
+    /// Create a new peer-group template for sessions of the given type, without any route-map,
+    /// with next-hop-self enabled, and without any defaults or options.
+    pub fn new(session_type: BgpSessionType) -> Self {
+        Self {
+            session_type,
+            route_maps_in: Vec::new(),
+            route_maps_out: Vec::new(),
+            next_hop_self: true,
+            defaults: None,
+            options: None,
+        }
     }
 }
 
 /// Type of a BGP session
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum BgpSessionType {
     /// iBGP session with a peer (or from a client with a Route Reflector)
     IBgpPeer,
@@ -280,31 +476,87 @@ pub struct BgpRibEntry<P: Prefix> {
     pub igp_cost: Option<NotNan<LinkWeight>>,
     /// Local weight of that route, which is the most preferred metric of the entire route.
     pub weight: u32,
+    /// BGP router-id of the originator of the route (if the route carries an ORIGINATOR_ID), or
+    /// of the neighbor from which the route was learned. It is set when processing incoming
+    /// routes. If it is not set, [`default_bgp_id`] is used instead.
+    #[serde(default)]
+    pub bgp_id: Option<Ipv4Addr>,
+    /// Local tag of the route, which route-maps can set and match (see
+    /// [`RouteMapSet::Tag`](crate::route_map::RouteMapSet::Tag)). A tag set by an incoming
+    /// route-map is visible to the outgoing route-maps of the same router, but it is never
+    /// advertised to any neighbor. The default tag is 0.
+    #[serde(default)]
+    pub tag: u32,
+    /// Whether the route is subject to route flap damping, as marked by an incoming route-map
+    /// (see [`RouteMapSet::Dampen`](crate::route_map::RouteMapSet::Dampen)). Like the tag, this
+    /// flag is never advertised to any neighbor.
+    #[serde(default)]
+    pub dampable: bool,
+    /// Result of the origin validation, which is only set if the router performs RPKI origin
+    /// validation (see [`Network::set_rpki`](crate::network::Network::set_rpki)). Like the tag,
+    /// this value is local to the router and never advertised.
+    #[serde(default)]
+    pub rpki: Option<RpkiValidation>,
+}
+
+impl<P: Prefix> BgpRibEntry<P> {
+    /// Get the BGP router-id of the originator (or the neighbor) of the route, which is used as
+    /// the last tiebreaker in the decision process.
+    pub fn get_bgp_id(&self) -> Ipv4Addr {
+        self.bgp_id
+            .unwrap_or_else(|| default_bgp_id(self.route.originator_id.unwrap_or(self.from_id)))
+    }
+}
+
+/// The BGP router-id of a router that has no router-id configured explicitly. It is derived from
+/// the [`RouterId`], such that router-ids are unique in the network.
+pub fn default_bgp_id(router: RouterId) -> Ipv4Addr {
+    Ipv4Addr::from(router.index() as u32)
 }
 
 impl<P: Prefix> Ord for BgpRibEntry<P> {
     fn cmp(&self, other: &Self) -> Ordering {
-        let s = self.route.clone_default();
-        let o = other.route.clone_default();
+        self.cmp_bestpath(other, false)
+    }
+}
+
+impl<P: Prefix> BgpRibEntry<P> {
+    /// Compare two routes according to the BGP decision process. If `as_path_ignore` is set, the
+    /// length of the AS path is not considered (`bgp bestpath as-path ignore`), and routes that
+    /// only differ in their AS path length are compared using the subsequent tiebreakers.
+    pub fn cmp_bestpath(&self, other: &Self, as_path_ignore: bool) -> Ordering {
+        match self.cmp_bestpath_attributes(other, as_path_ignore) {
+            Ordering::Equal => self.cmp_bestpath_tiebreak(other),
+            o => o,
+        }
+    }
+
+    /// Compare two routes according to the steps of the BGP decision process up to (and
+    /// including) the IGP cost towards the next-hop. Routes that compare equal are only
+    /// distinguished by the final tiebreakers, starting with the BGP router-id.
+    pub(crate) fn cmp_bestpath_attributes(&self, other: &Self, as_path_ignore: bool) -> Ordering {
+        let (s, o) = (&self.route, &other.route);
 
         match self.weight.cmp(&other.weight) {
             Ordering::Equal => {}
             o => return o,
         }
 
-        match s.local_pref.unwrap().cmp(&o.local_pref.unwrap()) {
+        match s.local_pref_or_default().cmp(&o.local_pref_or_default()) {
             Ordering::Equal => {}
             o => return o,
         }
 
-        match s.as_path.len().cmp(&o.as_path.len()) {
-            Ordering::Equal => {}
-            Ordering::Greater => return Ordering::Less,
-            Ordering::Less => return Ordering::Greater,
+        if !as_path_ignore {
+            match s.as_path.len().cmp(&o.as_path.len()) {
+                Ordering::Equal => {}
+                Ordering::Greater => return Ordering::Less,
+                Ordering::Less => return Ordering::Greater,
+            }
         }
 
         if s.as_path.first() == o.as_path.first() {
-            match s.med.unwrap().cmp(&o.med.unwrap()) {
+            match s.med_or_default().cmp(&o.med_or_default()) {
                 Ordering::Equal => {}
                 Ordering::Greater => return Ordering::Less,
                 Ordering::Less => return Ordering::Greater,
@@ -323,21 +575,27 @@ impl<P: Prefix> Ord for BgpRibEntry<P> {
             Some(Ordering::Less) => return Ordering::Greater,
         }
 
-        match s.next_hop.cmp(&o.next_hop) {
+        Ordering::Equal
+    }
+
+    /// Compare two routes using the final tiebreakers of the BGP decision process, i.e., the BGP
+    /// router-id, the cluster list length, the next-hop, and the neighbor.
+    fn cmp_bestpath_tiebreak(&self, other: &Self) -> Ordering {
+        let (s, o) = (&self.route, &other.route);
+
+        match self.get_bgp_id().cmp(&other.get_bgp_id()) {
             Ordering::Equal => {}
             Ordering::Greater => return Ordering::Less,
             Ordering::Less => return Ordering::Greater,
         }
 
-        let s_from = s.originator_id.unwrap_or(self.from_id);
-        let o_from = o.originator_id.unwrap_or(other.from_id);
-        match s_from.cmp(&o_from) {
+        match s.cluster_list.len().cmp(&o.cluster_list.len()) {
             Ordering::Equal => {}
             Ordering::Greater => return Ordering::Less,
             Ordering::Less => return Ordering::Greater,
         }
 
-        match s.cluster_list.len().cmp(&o.cluster_list.len()) {
+        match s.next_hop.cmp(&o.next_hop) {
             Ordering::Equal => {}
             Ordering::Greater => return Ordering::Less,
             Ordering::Less => return Ordering::Greater,
@@ -359,6 +617,10 @@ impl<P: Prefix> PartialEq for BgpRibEntry<P> {
             && self.from_id == other.from_id
             && self.weight == other.weight
             && self.igp_cost.unwrap_or_default() == other.igp_cost.unwrap_or_default()
+            && self.get_bgp_id() == other.get_bgp_id()
+            && self.tag == other.tag
+            && self.dampable == other.dampable
+            && self.rpki == other.rpki
     }
 }
 
@@ -0,0 +1,143 @@
+// BgpSim: BGP Network Simulator written in Rust
+// Copyright 2022-2024 Tibor Schneider <sctibor@ethz.ch>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Module containing the RPKI origin validation (RFC 6811).
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::{AsId, Community, Prefix};
+
+/// Table of Route Origin Authorizations (ROAs). Each ROA authorizes an AS to originate a prefix,
+/// and all prefixes contained in it. The maximum length of ROAs is not modelled.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(bound(deserialize = "P: for<'a> serde::Deserialize<'a>"))]
+pub struct RoaTable<P: Prefix> {
+    roas: Vec<(P, AsId)>,
+}
+
+impl<P: Prefix> Default for RoaTable<P> {
+    fn default() -> Self {
+        Self { roas: Vec::new() }
+    }
+}
+
+impl<P: Prefix> RoaTable<P> {
+    /// Create an empty ROA table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Authorize `origin` to announce `prefix` (and all more-specific prefixes).
+    pub fn add(&mut self, prefix: impl Into<P>, origin: impl Into<AsId>) -> &mut Self {
+        let roa = (prefix.into(), origin.into());
+        if !self.roas.contains(&roa) {
+            self.roas.push(roa);
+        }
+        self
+    }
+
+    /// Remove the ROA that authorizes `origin` to announce `prefix`. Returns `true` if the ROA
+    /// existed.
+    pub fn remove(&mut self, prefix: impl Into<P>, origin: impl Into<AsId>) -> bool {
+        let roa = (prefix.into(), origin.into());
+        let len = self.roas.len();
+        self.roas.retain(|x| x != &roa);
+        self.roas.len() != len
+    }
+
+    /// Iterate over all ROAs.
+    pub fn iter(&self) -> impl Iterator<Item = &(P, AsId)> {
+        self.roas.iter()
+    }
+
+    /// Get the number of ROAs.
+    pub fn len(&self) -> usize {
+        self.roas.len()
+    }
+
+    /// Returns `true` if the table contains no ROA.
+    pub fn is_empty(&self) -> bool {
+        self.roas.is_empty()
+    }
+
+    /// Validate the origin of a route for `prefix`. The route is `NotFound` if no ROA covers the
+    /// prefix, `Valid` if any covering ROA authorizes `origin`, and `Invalid` otherwise.
+    pub fn validate(&self, prefix: P, origin: AsId) -> RpkiValidation {
+        let mut covered = false;
+        for (_, asn) in self.roas.iter().filter(|(p, _)| p.contains(&prefix)) {
+            if *asn == origin {
+                return RpkiValidation::Valid;
+            }
+            covered = true;
+        }
+        if covered {
+            RpkiValidation::Invalid
+        } else {
+            RpkiValidation::NotFound
+        }
+    }
+}
+
+impl<P: Prefix, A: Into<AsId>> FromIterator<(P, A)> for RoaTable<P> {
+    fn from_iter<T: IntoIterator<Item = (P, A)>>(iter: T) -> Self {
+        let mut table = Self::new();
+        iter.into_iter().for_each(|(p, a)| {
+            table.add(p, a);
+        });
+        table
+    }
+}
+
+/// Result of the origin validation of a route.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RpkiValidation {
+    /// A ROA covers the prefix and authorizes the origin AS.
+    Valid,
+    /// At least one ROA covers the prefix, but none authorizes the origin AS.
+    Invalid,
+    /// No ROA covers the prefix.
+    NotFound,
+}
+
+impl std::fmt::Display for RpkiValidation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RpkiValidation::Valid => write!(f, "valid"),
+            RpkiValidation::Invalid => write!(f, "invalid"),
+            RpkiValidation::NotFound => write!(f, "not-found"),
+        }
+    }
+}
+
+/// Policy describing how a router treats routes after origin validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RpkiPolicy {
+    /// Only tag the routes with their validation state, but do not change them.
+    Tag,
+    /// Drop all `Invalid` routes.
+    DropInvalid,
+    /// Set the local-pref of all `Invalid` routes to the given value. This overwrites any
+    /// local-pref set by incoming route-maps.
+    DepreferInvalid(u32),
+    /// Lower the local-pref of all `Invalid` routes by `delta` (saturating at 0), and tag them with
+    /// the given `community`. The local-pref is lowered after applying the incoming route-maps.
+    /// `Invalid` routes are thus still used if no other route is available.
+    SoftInvalid {
+        /// Amount by which the local-pref is reduced.
+        delta: u32,
+        /// Community added to all `Invalid` routes.
+        community: Community,
+    },
+}
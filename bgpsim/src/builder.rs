@@ -31,7 +31,7 @@ use crate::{
     ospf::{LinkWeight, OspfImpl},
     prelude::{BgpSessionType, GlobalOspf},
     route_map::{RouteMapBuilder, RouteMapDirection},
-    types::{AsId, IndexType, NetworkError, Prefix, RouterId},
+    types::{AsId, Community, IndexType, NetworkError, Prefix, RouterId},
 };
 
 /// Trait for generating random configurations quickly. The following example shows how you can
@@ -1248,12 +1248,12 @@ pub enum GaoRexfordPeerType {
 
 impl GaoRexfordPeerType {
     /// Return the community associated with that kind.
-    pub fn community(&self) -> u32 {
+    pub fn community(&self) -> Community {
         match self {
-            GaoRexfordPeerType::Customer => 501,
-            GaoRexfordPeerType::Peer => 502,
-            GaoRexfordPeerType::Provider => 503,
-            GaoRexfordPeerType::Ignore => 500,
+            GaoRexfordPeerType::Customer => Community::from(501),
+            GaoRexfordPeerType::Peer => Community::from(502),
+            GaoRexfordPeerType::Provider => Community::from(503),
+            GaoRexfordPeerType::Ignore => Community::from(500),
         }
     }
 
@@ -1267,6 +1267,18 @@ impl GaoRexfordPeerType {
         }
     }
 
+    /// Returns `true` if routes received from an external network of type `self` may be exported
+    /// to an external network of type `to` (see
+    /// [`NetworkBuilder::build_gao_rexford_policies`]). Routes are never exported from or to an
+    /// external network that is `Ignore`.
+    pub fn exports_to(&self, to: Self) -> bool {
+        match (self, to) {
+            (Self::Ignore, _) | (_, Self::Ignore) => false,
+            (Self::Customer, _) | (_, Self::Customer) => true,
+            (Self::Peer | Self::Provider, Self::Peer | Self::Provider) => false,
+        }
+    }
+
     /// Sample a random peer type. The argument `probability` describes the probability of returning
     /// a `Customer`, and the probability of a `Peer`. The probability of a `Provider` is then
     /// `1 - probability.0 - probability.1`.
@@ -25,6 +25,8 @@
 //! - [`ConfigModifier`]: A modification of a single [`ConfigExpr`] in a configuration. A
 //!   modification can either be an insertion of a new expression, a removal of an existing
 //!   expression, or a moification of an existing expression.
+//! - [`PolicyLibrary`]: Named route-maps, prefix-lists, and AS-path access-lists that are
+//!   independent of a specific network, and can be attached to sessions of any network by name.
 //!
 //! # Example Usage
 //!
@@ -72,19 +74,24 @@
 use log::debug;
 
 use crate::{
-    bgp::BgpSessionType,
+    bgp::{BgpSessionType, DistributeList},
     event::EventQueue,
     formatter::NetworkFormatter,
     network::Network,
     ospf::{LinkWeight, OspfArea, OspfImpl, DEFAULT_LINK_WEIGHT},
-    route_map::{RouteMap, RouteMapDirection},
+    route_map::{AsPathList, RouteMap, RouteMapDirection, RouteMapMatch},
     router::StaticRoute,
-    types::{ConfigError, NetworkDeviceRef, NetworkError, Prefix, PrefixMap, RouterId},
+    types::{
+        ConfigError, NetworkDeviceRef, NetworkError, Prefix, PrefixMap, PrefixSet, RouterId,
+        StableHasher,
+    },
 };
 
+use itertools::Itertools;
 use petgraph::algo::FloatMeasure;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 use std::ops::Index;
 
 /// # Network Configuration
@@ -455,6 +462,51 @@ impl<P: Prefix + PartialEq> PartialEq for ConfigExpr<P> {
 }
 
 impl<P: Prefix> ConfigExpr<P> {
+    /// Compute a stable hash of the expression (see [`Network::config_hash`]). Symmetric BGP
+    /// sessions (iBGP peers and eBGP) are hashed independently of their direction.
+    pub(crate) fn stable_hash(&self) -> u64 {
+        let mut state = StableHasher::default();
+        std::mem::discriminant(self).hash(&mut state);
+        match self {
+            ConfigExpr::IgpLinkWeight {
+                source,
+                target,
+                weight,
+            } => (source, target, weight.to_bits()).hash(&mut state),
+            ConfigExpr::OspfArea {
+                source,
+                target,
+                area,
+            } => (source, target, area).hash(&mut state),
+            ConfigExpr::BgpSession {
+                source,
+                target,
+                session_type,
+            } => {
+                let (a, b) = match session_type {
+                    BgpSessionType::IBgpClient => (source, target),
+                    BgpSessionType::IBgpPeer | BgpSessionType::EBgp => {
+                        (source.min(target), source.max(target))
+                    }
+                };
+                (a, b, session_type).hash(&mut state)
+            }
+            ConfigExpr::BgpRouteMap {
+                router,
+                neighbor,
+                direction,
+                map,
+            } => (router, neighbor, direction, map).hash(&mut state),
+            ConfigExpr::StaticRoute {
+                router,
+                prefix,
+                target,
+            } => (router, prefix, target).hash(&mut state),
+            ConfigExpr::LoadBalancing { router } => router.hash(&mut state),
+        }
+        state.finish()
+    }
+
     /// Returns the key of the config expression. The idea behind the key is that the `ConfigExpr`
     /// cannot be hashed and used as a key for a `HashMap`. But `ConfigExprKey` implements `Hash`,
     /// and can therefore be used as a key.
@@ -773,6 +825,201 @@ impl<P: Prefix> ConfigPatch<P> {
     }
 }
 
+/// # Policy Library
+/// A named collection of route-maps, prefix-lists, and AS-path access-lists that does not depend
+/// on a specific network. Policies are authored once (or captured from a session of an existing
+/// network), serialized, and attached to sessions of any other network by their name. Route-maps
+/// reference AS-path access-lists by name. When attaching a route-map, all AS-path access-lists of
+/// the library that it references are defined on the target network as well.
+///
+/// Prefix-lists are attached as distribute-lists (see [`Network::set_distribute_list`]). They are
+/// applied before the route-maps of the same session and direction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "P: for<'a> serde::Deserialize<'a>"))]
+pub struct PolicyLibrary<P: Prefix> {
+    /// Named route-maps. Each route-map consists of several items, ordered by their order.
+    pub route_maps: BTreeMap<String, Vec<RouteMap<P>>>,
+    /// Named prefix-lists.
+    pub prefix_lists: BTreeMap<String, DistributeList<P>>,
+    /// Named AS-path access-lists.
+    pub as_path_lists: BTreeMap<String, AsPathList>,
+}
+
+impl<P: Prefix> Default for PolicyLibrary<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: Prefix> PolicyLibrary<P> {
+    /// Create an empty library
+    pub fn new() -> Self {
+        Self {
+            route_maps: BTreeMap::new(),
+            prefix_lists: BTreeMap::new(),
+            as_path_lists: BTreeMap::new(),
+        }
+    }
+
+    /// Add a named route-map to the library, replacing any route-map with the same name. The old
+    /// route-map is returned.
+    pub fn add_route_map(
+        &mut self,
+        name: impl Into<String>,
+        items: impl IntoIterator<Item = RouteMap<P>>,
+    ) -> Option<Vec<RouteMap<P>>> {
+        let mut items: Vec<_> = items.into_iter().collect();
+        items.sort_by_key(|x| x.order);
+        self.route_maps.insert(name.into(), items)
+    }
+
+    /// Add a named prefix-list to the library, replacing any prefix-list with the same name. The
+    /// old prefix-list is returned.
+    pub fn add_prefix_list(
+        &mut self,
+        name: impl Into<String>,
+        list: DistributeList<P>,
+    ) -> Option<DistributeList<P>> {
+        self.prefix_lists.insert(name.into(), list)
+    }
+
+    /// Add a named AS-path access-list to the library, replacing any list with the same name. The
+    /// old list is returned.
+    pub fn add_as_path_list(
+        &mut self,
+        name: impl Into<String>,
+        list: AsPathList,
+    ) -> Option<AsPathList> {
+        self.as_path_lists.insert(name.into(), list)
+    }
+
+    /// Capture the policy that `router` applies on the session with `neighbor` in the given
+    /// `direction`, and store it in the library under `name`. The route-maps are stored as a
+    /// route-map, and the distribute-list (if any) as a prefix-list, both with the same name. All
+    /// AS-path access-lists referenced by the route-maps are stored with their original name.
+    pub fn capture_session<Q, Ospf: OspfImpl>(
+        &mut self,
+        name: impl Into<String>,
+        net: &Network<P, Q, Ospf>,
+        router: RouterId,
+        neighbor: RouterId,
+        direction: RouteMapDirection,
+    ) -> Result<(), NetworkError> {
+        let name = name.into();
+        let bgp = &net.get_internal_router(router)?.bgp;
+        let maps = bgp.get_route_maps(neighbor, direction);
+        for list in referenced_as_path_lists(maps) {
+            if let Some(entries) = bgp.get_as_path_list(&list) {
+                self.as_path_lists.insert(list, entries.clone());
+            }
+        }
+        if let Some(list) = bgp.get_distribute_list(neighbor, direction) {
+            self.prefix_lists.insert(name.clone(), list.clone());
+        }
+        self.route_maps.insert(name, maps.to_vec());
+        Ok(())
+    }
+
+    /// Attach the route-map `name` to the session of `router` with `neighbor` in the given
+    /// `direction`. The route-map replaces all route-map items configured on that session before.
+    /// All AS-path access-lists of the library that the route-map references are defined on all
+    /// internal routers (see [`Network::define_as_path_list`]). This function returns
+    /// [`NetworkError::UnknownPolicy`] if the library does not contain the route-map, and runs the
+    /// simulation after updating the network.
+    pub fn attach_route_map<Q: EventQueue<P>, Ospf: OspfImpl>(
+        &self,
+        net: &mut Network<P, Q, Ospf>,
+        router: RouterId,
+        neighbor: RouterId,
+        direction: RouteMapDirection,
+        name: &str,
+    ) -> Result<(), NetworkError> {
+        let maps = self
+            .route_maps
+            .get(name)
+            .ok_or_else(|| NetworkError::UnknownPolicy(name.to_string()))?;
+        let old_maps = net
+            .get_internal_router(router)?
+            .bgp
+            .get_route_maps(neighbor, direction)
+            .to_vec();
+
+        for list in referenced_as_path_lists(maps) {
+            if let Some(entries) = self.as_path_lists.get(&list) {
+                net.define_as_path_list(list, entries.entries.iter().cloned())?;
+            }
+        }
+
+        let edits = old_maps
+            .into_iter()
+            .map(|old| RouteMapEdit {
+                neighbor,
+                direction,
+                old: Some(old),
+                new: None,
+            })
+            .chain(maps.iter().map(|new| RouteMapEdit {
+                neighbor,
+                direction,
+                old: None,
+                new: Some(new.clone()),
+            }))
+            .collect::<Vec<_>>();
+        net.batch_update_route_maps(router, &edits)
+    }
+
+    /// Attach the prefix-list `name` as a distribute-list to the session of `router` with
+    /// `neighbor` in the given `direction`, replacing any distribute-list configured before. This
+    /// function returns [`NetworkError::UnknownPolicy`] if the library does not contain the
+    /// prefix-list, and runs the simulation after updating the network.
+    pub fn attach_prefix_list<Q: EventQueue<P>, Ospf: OspfImpl>(
+        &self,
+        net: &mut Network<P, Q, Ospf>,
+        router: RouterId,
+        neighbor: RouterId,
+        direction: RouteMapDirection,
+        name: &str,
+    ) -> Result<(), NetworkError> {
+        let list = self
+            .prefix_lists
+            .get(name)
+            .ok_or_else(|| NetworkError::UnknownPolicy(name.to_string()))?;
+        net.set_distribute_list(
+            router,
+            neighbor,
+            direction,
+            list.prefixes.iter().copied(),
+            list.permit,
+        )?;
+        Ok(())
+    }
+
+    /// Serialize the library into a json string.
+    pub fn as_json_str(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// Deserialize a library from a json string, created by [`PolicyLibrary::as_json_str`].
+    pub fn from_json_str(s: &str) -> Result<Self, NetworkError>
+    where
+        P: for<'a> Deserialize<'a>,
+    {
+        Ok(serde_json::from_str(s)?)
+    }
+}
+
+/// Get the names of all AS-path access-lists referenced by the route-map items.
+fn referenced_as_path_lists<P: Prefix>(maps: &[RouteMap<P>]) -> Vec<String> {
+    maps.iter()
+        .flat_map(|map| map.conds.iter())
+        .filter_map(|c| match c {
+            RouteMapMatch::AsPathList(name) => Some(name.clone()),
+            _ => None,
+        })
+        .unique()
+        .collect()
+}
+
 /// Trait to manage the network using configurations, patches, and modifiers.
 pub trait NetworkConfig<P: Prefix> {
     /// Set the provided network-wide configuration. The network first computes the patch from the
@@ -790,6 +1037,14 @@ pub trait NetworkConfig<P: Prefix> {
     /// network is in an undefined state.
     fn apply_patch(&mut self, patch: &ConfigPatch<P>) -> Result<(), NetworkError>;
 
+    /// Apply a batch of configuration changes as a single commit. All modifiers are applied in
+    /// the order in which they appear in `changes`, without letting the network converge in
+    /// between. Only after the last modifier is applied, the network processes all messages (as
+    /// in [`crate::network::Network::simulate`]). If the network is in manual simulation mode,
+    /// then all events of the batch remain enqueued. The process fails if any of the modifiers
+    /// cannot be applied. In that case, the network is in an undefined state.
+    fn apply_config_changes(&mut self, changes: &[ConfigModifier<P>]) -> Result<(), NetworkError>;
+
     /// Apply a single configuration modification. The modification must be applicable to the
     /// current configuration. All messages are exchanged. The process fails, then the network is
     /// in an undefined state, and it should be rebuilt.
@@ -829,12 +1084,22 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> NetworkConfig<P> for Network<P
     /// while applying a modifier and letting the network converge. If the process fails, the
     /// network is in an undefined state.
     fn apply_patch(&mut self, patch: &ConfigPatch<P>) -> Result<(), NetworkError> {
+        self.apply_config_changes(&patch.modifiers)
+    }
+
+    /// Apply a batch of configuration changes as a single commit. All modifiers are applied in
+    /// the order in which they appear in `changes`, without letting the network converge in
+    /// between. Only after the last modifier is applied, the network processes all messages (as
+    /// in [`crate::network::Network::simulate`]). If the network is in manual simulation mode,
+    /// then all events of the batch remain enqueued. The process fails if any of the modifiers
+    /// cannot be applied. In that case, the network is in an undefined state.
+    fn apply_config_changes(&mut self, changes: &[ConfigModifier<P>]) -> Result<(), NetworkError> {
         // apply every modifier in order
+        let skip_queue = self.skip_queue;
         self.skip_queue = true;
-        for modifier in patch.modifiers.iter() {
-            self.apply_modifier(modifier)?;
-        }
-        self.skip_queue = false;
+        let result = changes.iter().try_for_each(|m| self.apply_modifier(m));
+        self.skip_queue = skip_queue;
+        result?;
         self.do_queue_maybe_skip()
     }
 
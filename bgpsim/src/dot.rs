@@ -0,0 +1,155 @@
+// BgpSim: BGP Network Simulator written in Rust
+// Copyright 2022-2024 Tibor Schneider <sctibor@ethz.ch>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This module contains functions to export the forwarding state of the network as a Graphviz
+//! (DOT) graph.
+
+use std::{collections::HashMap, fmt::Write};
+
+use itertools::Itertools;
+
+use crate::{
+    network::Network,
+    ospf::OspfImpl,
+    types::{Prefix, RouterId},
+};
+
+/// Colors assigned to the egress routers in the annotated graph (in order).
+const EGRESS_COLORS: [&str; 8] = [
+    "red",
+    "blue",
+    "darkgreen",
+    "orange",
+    "purple",
+    "brown",
+    "cyan4",
+    "magenta",
+];
+
+impl<P: Prefix, Q, Ospf: OspfImpl> Network<P, Q, Ospf> {
+    /// Export the physical topology and the forwarding state for `prefix` as a Graphviz graph in
+    /// the DOT language. Physical links are drawn as gray, undirected edges labeled with their
+    /// link weights, and the next hops towards `prefix` are drawn as bold, directed edges.
+    pub fn forwarding_dot(&self, prefix: P) -> String {
+        self.dot(prefix, false)
+    }
+
+    /// Export the converged state for `prefix` as a Graphviz graph in the DOT language, explaining
+    /// both the IGP and the BGP decisions. The graph is the same as [`Self::forwarding_dot`], but
+    /// each forwarding edge is labeled with the LOCAL-PREF and the AS-path length of the route
+    /// selected by the router, and each internal router is colored by the egress router through
+    /// which it forwards traffic towards `prefix`.
+    pub fn forwarding_dot_annotated(&self, prefix: P) -> String {
+        self.dot(prefix, true)
+    }
+
+    fn dot(&self, prefix: P, annotate: bool) -> String {
+        let mut fw_state = self.get_forwarding_state();
+        let devices = self
+            .devices()
+            .sorted_by_key(|r| r.router_id())
+            .collect_vec();
+
+        // the egress of each internal router is the last internal router on its path.
+        let egress: HashMap<RouterId, RouterId> = if annotate {
+            self.internal_indices()
+                .filter_map(|r| {
+                    let path = fw_state.get_paths(r, prefix).ok()?.into_iter().next()?;
+                    let e = path
+                        .into_iter()
+                        .take_while(|x| self.get_internal_router(*x).is_ok())
+                        .last()?;
+                    Some((r, e))
+                })
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        let colors: HashMap<RouterId, &str> = egress
+            .values()
+            .copied()
+            .unique()
+            .sorted()
+            .zip(EGRESS_COLORS.iter().copied().cycle())
+            .collect();
+
+        let mut s = String::from("digraph {\n");
+
+        // nodes
+        for r in devices.iter() {
+            let id = r.router_id().index();
+            let name = r.name().replace('"', "\\\"");
+            let shape = if r.is_internal() { "ellipse" } else { "box" };
+            let color = egress
+                .get(&r.router_id())
+                .and_then(|e| colors.get(e))
+                .map(|c| format!(", style=filled, fillcolor={c}"))
+                .unwrap_or_default();
+            writeln!(s, "  {id} [label=\"{name}\", shape={shape}{color}];").unwrap();
+        }
+
+        // physical links
+        let ospf = self.ospf_network();
+        for (a, b) in self
+            .get_topology()
+            .edge_indices()
+            .filter_map(|e| self.get_topology().edge_endpoints(e))
+            .map(|(a, b)| if a < b { (a, b) } else { (b, a) })
+            .sorted()
+        {
+            let label =
+                if self.get_internal_router(a).is_ok() && self.get_internal_router(b).is_ok() {
+                    let (w_ab, w_ba) = (ospf.get_weight(a, b), ospf.get_weight(b, a));
+                    if w_ab == w_ba {
+                        format!(", label=\"{w_ab}\"")
+                    } else {
+                        format!(", label=\"{w_ab} / {w_ba}\"")
+                    }
+                } else {
+                    String::new()
+                };
+            writeln!(
+                s,
+                "  {} -> {} [dir=none, color=gray{label}];",
+                a.index(),
+                b.index()
+            )
+            .unwrap();
+        }
+
+        // forwarding edges
+        for r in devices.iter().filter(|r| r.is_internal()) {
+            let r = r.router_id();
+            let route = self
+                .get_internal_router(r)
+                .ok()
+                .and_then(|x| x.bgp.get_route(prefix));
+            for nh in fw_state.get_next_hops(r, prefix) {
+                let label = match route.filter(|_| annotate) {
+                    Some(e) => format!(
+                        ", label=\"local-pref: {}\\nas-path: {}\"",
+                        e.route.local_pref.unwrap_or(100),
+                        e.route.as_path.len()
+                    ),
+                    None => String::new(),
+                };
+                writeln!(s, "  {} -> {} [penwidth=2{label}];", r.index(), nh.index()).unwrap();
+            }
+        }
+
+        s.push_str("}\n");
+        s
+    }
+}
@@ -0,0 +1,109 @@
+// BgpSim: BGP Network Simulator written in Rust
+// Copyright 2022-2024 Tibor Schneider <sctibor@ethz.ch>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Module defining high-level convergence events, which can be observed by registering a callback
+//! with [`Network::on_event`](crate::network::Network::on_event).
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bgp::{BgpRibEntry, BgpSessionType},
+    types::{Prefix, RouterId},
+};
+
+/// Semantic milestone reached while the network converges. In contrast to [`Event`](super::Event),
+/// which describes a single message exchanged between two routers, a `ConvergenceEvent` describes
+/// the effect of such messages (or of a configuration change).
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "P: for<'a> Deserialize<'a>"))]
+pub enum ConvergenceEvent<P: Prefix> {
+    /// An internal router selected a new best route for a prefix.
+    BestRouteChanged {
+        /// The router that selected a new route
+        router: RouterId,
+        /// The affected prefix
+        prefix: P,
+        /// The previously selected route, or `None` if the prefix was unreachable.
+        old: Option<BgpRibEntry<P>>,
+        /// The newly selected route, or `None` if the prefix is now unreachable.
+        new: Option<BgpRibEntry<P>>,
+    },
+    /// A BGP session was established, as both routers can reach each other.
+    SessionEstablished {
+        /// The router with the smaller ID
+        source: RouterId,
+        /// The router with the larger ID
+        target: RouterId,
+        /// Type of the session from the perspective of `source`.
+        session_type: BgpSessionType,
+    },
+    /// A BGP session broke down, either because it was removed, or because the routers can no
+    /// longer reach each other.
+    SessionDown {
+        /// The router with the smaller ID
+        source: RouterId,
+        /// The router with the larger ID
+        target: RouterId,
+    },
+    /// The IGP was recomputed after changing the weight of some links.
+    IgpRecomputed {
+        /// All (directed) links whose weight was changed.
+        links: Vec<(RouterId, RouterId)>,
+    },
+}
+
+/// Callback that is invoked for every [`ConvergenceEvent`].
+pub type ConvergenceCallback<P> = Box<dyn FnMut(&ConvergenceEvent<P>) + Send + Sync>;
+
+/// The set of callbacks registered on a network. Callbacks are neither cloned, compared, nor
+/// serialized.
+pub(crate) struct ConvergenceCallbacks<P: Prefix>(Vec<ConvergenceCallback<P>>);
+
+impl<P: Prefix> Default for ConvergenceCallbacks<P> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<P: Prefix> std::fmt::Debug for ConvergenceCallbacks<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ConvergenceCallbacks({})", self.0.len())
+    }
+}
+
+impl<P: Prefix> ConvergenceCallbacks<P> {
+    /// Returns `true` if no callback is registered.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Register a new callback.
+    pub(crate) fn push(&mut self, callback: ConvergenceCallback<P>) {
+        self.0.push(callback)
+    }
+
+    /// Remove all callbacks.
+    pub(crate) fn clear(&mut self) {
+        self.0.clear()
+    }
+
+    /// Call all registered callbacks with `event`.
+    pub(crate) fn emit(&mut self, event: ConvergenceEvent<P>) {
+        for callback in self.0.iter_mut() {
+            callback(&event)
+        }
+    }
+}
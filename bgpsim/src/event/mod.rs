@@ -19,6 +19,9 @@ use std::hash::Hash;
 
 use serde::{Deserialize, Serialize};
 
+mod convergence;
+pub(crate) use convergence::ConvergenceCallbacks;
+pub use convergence::{ConvergenceCallback, ConvergenceEvent};
 mod queue;
 pub use queue::{BasicEventQueue, EventQueue, FmtPriority};
 #[cfg(feature = "rand_queue")]
@@ -113,6 +116,22 @@ impl<P: Prefix, T> Event<P, T> {
         }
     }
 
+    /// Replace the priority of this event.
+    pub fn with_priority<U>(self, p: U) -> Event<P, U> {
+        match self {
+            Event::Bgp { src, dst, e, .. } => Event::Bgp { p, src, dst, e },
+            Event::Ospf {
+                src, dst, area, e, ..
+            } => Event::Ospf {
+                p,
+                src,
+                dst,
+                area,
+                e,
+            },
+        }
+    }
+
     /// Returns true if the event is a bgp message
     pub fn is_bgp_event(&self) -> bool {
         matches!(self, Event::Bgp { .. })
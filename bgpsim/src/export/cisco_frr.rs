@@ -27,17 +27,17 @@ use ipnet::Ipv4Net;
 use itertools::Itertools;
 
 use crate::{
-    bgp::BgpRoute,
+    bgp::{BgpRoute, BgpSessionOptions},
     config::{ConfigExpr, ConfigModifier},
     network::{Network, INTERNAL_AS},
     ospf::{InternalEdge, OspfArea, OspfImpl, OspfProcess},
     prelude::BgpSessionType,
     route_map::{
-        RouteMap, RouteMapDirection as RmDir, RouteMapFlow, RouteMapMatch, RouteMapMatchAsPath,
-        RouteMapSet, RouteMapState,
+        AsPathLists, RouteMap, RouteMapDirection as RmDir, RouteMapFlow, RouteMapMatch,
+        RouteMapMatchAsPath, RouteMapSet, RouteMapState,
     },
     router::{Router, StaticRoute},
-    types::{AsId, Prefix, PrefixMap, PrefixSet, RouterId},
+    types::{AsId, Community, Prefix, PrefixMap, PrefixSet, RouterId},
 };
 
 use super::{
@@ -238,6 +238,10 @@ impl<P: Prefix> CiscoFrrCfgGen<P> {
                 iface.mac_address(*mac);
             }
 
+            if let Some(mtu) = self.session_options(n, net).and_then(|o| o.mtu) {
+                iface.mtu(mtu);
+            }
+
             let (weight, area) = match edge.internal() {
                 Some(InternalEdge { weight, area, .. }) => (weight, area),
                 _ => (1.0, OspfArea::BACKBONE),
@@ -351,6 +355,18 @@ impl<P: Prefix> CiscoFrrCfgGen<P> {
         router_bgp.router_id(addressor.router_address(r)?);
         router_bgp.network(addressor.internal_network());
 
+        // create all peer-groups used by the sessions of the router
+        for group in router
+            .bgp
+            .get_sessions()
+            .keys()
+            .filter_map(|n| net.get_peer_group_of(r, *n))
+            .sorted()
+            .dedup()
+        {
+            router_bgp.neighbor(self.bgp_peer_group_config(net, group)?);
+        }
+
         // create each neighbor
         for (n, ty) in router.bgp.get_sessions().iter().sorted_by_key(|(x, _)| *x) {
             let rm_name = rm_name(net, *n);
@@ -381,6 +397,33 @@ impl<P: Prefix> CiscoFrrCfgGen<P> {
         Ok(config)
     }
 
+    /// Create the configuration for a BGP peer-group. The peer-group only contains the settings of
+    /// its template that are independent of the neighbor. Route-maps are still configured for each
+    /// member individually.
+    fn bgp_peer_group_config<Q, Ospf: OspfImpl>(
+        &self,
+        net: &Network<P, Q, Ospf>,
+        name: &str,
+    ) -> Result<RouterBgpNeighbor, ExportError> {
+        let template = net.get_peer_group(name).ok_or_else(|| {
+            ExportError::InternalCfgGenError(
+                self.router,
+                format!("Peer-group {name} is not defined"),
+            )
+        })?;
+        let mut group = RouterBgpNeighbor::new_peer_group(name);
+        if template.next_hop_self {
+            group.next_hop_self();
+        }
+        if template.session_type == BgpSessionType::IBgpClient {
+            group.route_reflector_client();
+        }
+        if let Some(pw) = template.options.as_ref().and_then(|o| o.password.as_ref()) {
+            group.password(pw);
+        }
+        Ok(group)
+    }
+
     /// Create the configuration for a BGP neighbor
     fn bgp_neigbor_config<A: Addressor<P>, Q, Ospf: OspfImpl>(
         &self,
@@ -407,6 +450,15 @@ impl<P: Prefix> CiscoFrrCfgGen<P> {
         bgp_neighbor.route_map_out(format!("{rm_name}-out"));
         bgp_neighbor.next_hop_self();
         bgp_neighbor.soft_reconfiguration_inbound();
+        if let Some(group) = net.get_peer_group_of(r, n) {
+            bgp_neighbor.peer_group(group);
+        }
+        if let Some(pw) = self
+            .session_options(n, net)
+            .and_then(|o| o.password.as_ref())
+        {
+            bgp_neighbor.password(pw);
+        }
         match ty {
             BgpSessionType::IBgpPeer => {}
             BgpSessionType::IBgpClient => {
@@ -554,19 +606,23 @@ impl<P: Prefix> CiscoFrrCfgGen<P> {
         if let Some((communities, deny_communities)) = rm_match_community_list(rm) {
             let mut cl = CommunityList::new(format!("{name}-{ord}-cl"));
             for c in communities {
-                cl.community(INTERNAL_AS, c);
+                cl.community(c.0, c.1);
             }
             for c in deny_communities {
-                cl.deny(INTERNAL_AS, c);
+                cl.deny(c.0, c.1);
             }
             route_map_item.match_community_list(cl);
         }
 
         // AsPath match
-        if let Some(as_id) = rm_match_as_path_list(rm) {
-            route_map_item.match_as_path_list(
-                AsPathList::new(format!("{name}-{ord}-asl")).contains_as(as_id),
-            );
+        let as_path_lists = net
+            .get_internal_router(self.router)?
+            .bgp
+            .get_as_path_lists();
+        if let Some(asl) = rm_match_as_path_list(rm, format!("{name}-{ord}-asl"), as_path_lists)
+            .map_err(|e| ExportError::InternalCfgGenError(self.router, e))?
+        {
+            route_map_item.match_as_path_list(asl);
         }
 
         // match on the next-hop
@@ -577,11 +633,18 @@ impl<P: Prefix> CiscoFrrCfgGen<P> {
             );
         }
 
+        // match on the tag
+        if let Some(tag) =
+            rm_match_tag(rm).map_err(|e| ExportError::InternalCfgGenError(self.router, e))?
+        {
+            route_map_item.match_tag(tag);
+        }
+
         // unset all communities using a single community list
         if let Some(communities) = rm_delete_community_list(rm) {
             let mut cl = CommunityList::new(format!("{name}-{ord}-del-cl"));
             for c in communities {
-                cl.community(INTERNAL_AS, c);
+                cl.community(c.0, c.1);
             }
             route_map_item.delete_community_list(cl);
         }
@@ -601,8 +664,32 @@ impl<P: Prefix> CiscoFrrCfgGen<P> {
                 RouteMapSet::IgpCost(_) => {
                     unimplemented!("Changing the IGP cost is not implemented yet!")
                 }
-                RouteMapSet::SetCommunity(c) => route_map_item.set_community(INTERNAL_AS, *c),
+                RouteMapSet::SetCommunity(c) => route_map_item.set_community(c.0, c.1),
                 RouteMapSet::DelCommunity(_) => &mut route_map_item, // nothing to do, already done!
+                RouteMapSet::ClampLocalPref(_, _) | RouteMapSet::ClampMed(_, _) => {
+                    return Err(ExportError::InternalCfgGenError(
+                        self.router,
+                        String::from("Clamping attributes in route-maps cannot be exported"),
+                    ))
+                }
+                RouteMapSet::AsPathPrepend(asn, n) => {
+                    route_map_item.prepend_as_path(std::iter::repeat_n(*asn, *n))
+                }
+                RouteMapSet::Tag(t) => route_map_item.set_tag(*t),
+                RouteMapSet::LocalPrefFromIgpCost(_, _) => {
+                    return Err(ExportError::InternalCfgGenError(
+                        self.router,
+                        String::from(
+                            "Deriving the local-pref from the IGP cost cannot be exported",
+                        ),
+                    ))
+                }
+                RouteMapSet::Dampen => {
+                    return Err(ExportError::InternalCfgGenError(
+                        self.router,
+                        String::from("Route flap damping cannot be exported"),
+                    ))
+                }
             };
         }
 
@@ -656,6 +743,21 @@ impl<P: Prefix> CiscoFrrCfgGen<P> {
         }
     }
 
+    /// Get the options of the session between `self.router` and `n`. For external routers, the
+    /// options configured on the internal neighbor are used, such that both sides match.
+    fn session_options<'a, Q, Ospf: OspfImpl>(
+        &self,
+        n: RouterId,
+        net: &'a Network<P, Q, Ospf>,
+    ) -> Option<&'a BgpSessionOptions> {
+        if let Some(r) = net.get_device(self.router).ok()?.internal() {
+            r.bgp.get_session_options(n)
+        } else {
+            let n = net.get_device(n).ok()?.internal()?;
+            n.bgp.get_session_options(self.router)
+        }
+    }
+
     /// Gewt the interface name of a loopback address. If it does not exist yet, then it will be
     /// added.
     fn get_loopback_iface(&mut self, addr: Ipv4Net) -> Result<String, ExportError> {
@@ -980,14 +1082,21 @@ impl<P: Prefix, A: Addressor<P>, Ospf: OspfImpl, Q> ExternalCfgGen<P, Q, Ospf, A
         let mut router_bgp = RouterBgp::new(self.as_id);
         router_bgp.router_id(addressor.router_address(self.router)?);
         for neighbor in router.neighbors.iter() {
-            router_bgp.neighbor(
-                RouterBgpNeighbor::new(self.router_id_to_ip(*neighbor, net, addressor)?)
-                    .update_source(self.iface(self.router, *neighbor, addressor)?)
-                    .remote_as(INTERNAL_AS)
-                    .next_hop_self()
-                    .route_map_in(EXTERNAL_RM_IN)
-                    .route_map_out(EXTERNAL_RM_OUT),
-            );
+            let mut bgp_neighbor =
+                RouterBgpNeighbor::new(self.router_id_to_ip(*neighbor, net, addressor)?);
+            bgp_neighbor
+                .update_source(self.iface(self.router, *neighbor, addressor)?)
+                .remote_as(INTERNAL_AS)
+                .next_hop_self()
+                .route_map_in(EXTERNAL_RM_IN)
+                .route_map_out(EXTERNAL_RM_OUT);
+            if let Some(pw) = self
+                .session_options(*neighbor, net)
+                .and_then(|o| o.password.as_ref())
+            {
+                bgp_neighbor.password(pw);
+            }
+            router_bgp.neighbor(bgp_neighbor);
         }
         // announce the internal prefix (for now).
         router_bgp.network(addressor.router_network(self.router)?);
@@ -1066,8 +1175,8 @@ impl<P: Prefix, A: Addressor<P>, Ospf: OspfImpl, Q> ExternalCfgGen<P, Q, Ospf, A
         route_map.match_prefix_list(prefix_list);
         route_map.prepend_as_path(route.as_path.iter().skip(1));
         route_map.set_med(route.med.unwrap_or(0));
-        for c in route.community.iter() {
-            route_map.set_community(INTERNAL_AS, *c);
+        for c in route.communities.iter() {
+            route_map.set_community(c.0, c.1);
         }
         config.push_str(&route_map.build(self.target));
 
@@ -1125,15 +1234,22 @@ impl<P: Prefix, A: Addressor<P>, Ospf: OspfImpl, Q> ExternalCfgGen<P, Q, Ospf, A
         addressor: &mut A,
         neighbor: RouterId,
     ) -> Result<String, ExportError> {
+        let mut bgp_neighbor =
+            RouterBgpNeighbor::new(self.router_id_to_ip(neighbor, net, addressor)?);
+        bgp_neighbor
+            .update_source(self.iface(self.router, neighbor, addressor)?)
+            .remote_as(INTERNAL_AS)
+            .next_hop_self()
+            .route_map_in(EXTERNAL_RM_IN)
+            .route_map_out(EXTERNAL_RM_OUT);
+        if let Some(pw) = self
+            .session_options(neighbor, net)
+            .and_then(|o| o.password.as_ref())
+        {
+            bgp_neighbor.password(pw);
+        }
         Ok(RouterBgp::new(self.as_id)
-            .neighbor(
-                RouterBgpNeighbor::new(self.router_id_to_ip(neighbor, net, addressor)?)
-                    .update_source(self.iface(self.router, neighbor, addressor)?)
-                    .remote_as(INTERNAL_AS)
-                    .next_hop_self()
-                    .route_map_in(EXTERNAL_RM_IN)
-                    .route_map_out(EXTERNAL_RM_OUT),
-            )
+            .neighbor(bgp_neighbor)
             .build(self.target))
     }
 
@@ -1175,7 +1291,9 @@ fn rm_match_prefix_list<P: Prefix>(rm: &RouteMap<P>) -> Option<P::Set> {
 
 /// Extract the set of communities that must be present in the route, and those that must be absent,
 /// such that it matches
-fn rm_match_community_list<P: Prefix>(rm: &RouteMap<P>) -> Option<(HashSet<u32>, HashSet<u32>)> {
+fn rm_match_community_list<P: Prefix>(
+    rm: &RouteMap<P>,
+) -> Option<(HashSet<Community>, HashSet<Community>)> {
     let mut communities = HashSet::new();
     let mut deny_communities = HashSet::new();
 
@@ -1194,22 +1312,65 @@ fn rm_match_community_list<P: Prefix>(rm: &RouteMap<P>) -> Option<(HashSet<u32>,
     }
 }
 
-/// TODO this is not implemented yet. It only works if there is a single AS that must be present in
-/// the path. Otherwise, it will simply panic!
-fn rm_match_as_path_list<P: Prefix>(rm: &RouteMap<P>) -> Option<AsId> {
-    let mut contained_ases = Vec::new();
-
-    for cond in rm.conds.iter() {
-        if let RouteMapMatch::AsPath(RouteMapMatchAsPath::Contains(as_id)) = cond {
-            contained_ases.push(as_id)
-        };
-    }
+/// Extract the AS-path access-list `name` that is matched in the route-map. This either requires a
+/// single AS to be present in the path, or it contains the entries of a named AS-path access-list
+/// (looked up in `as_path_lists`). Only a single AS-path condition per route-map is supported, and
+/// named AS-path access-lists may only contain entries that match on a single AS.
+fn rm_match_as_path_list<P: Prefix>(
+    rm: &RouteMap<P>,
+    name: String,
+    as_path_lists: &AsPathLists,
+) -> Result<Option<AsPathList>, String> {
+    let conds = rm
+        .conds
+        .iter()
+        .filter(|cond| {
+            matches!(
+                cond,
+                RouteMapMatch::AsPath(RouteMapMatchAsPath::Contains(_))
+                    | RouteMapMatch::AsPathList(_)
+            )
+        })
+        .collect_vec();
 
-    match contained_ases.as_slice() {
-        [] => None,
-        [as_id] => Some(**as_id),
-        _ => unimplemented!("More complex AS path constraints are not implemented yet!"),
+    let mut asl = AsPathList::new(name);
+    match conds.as_slice() {
+        [] => return Ok(None),
+        [RouteMapMatch::AsPath(RouteMapMatchAsPath::Contains(as_id))] => {
+            asl.contains_as(*as_id);
+        }
+        [RouteMapMatch::AsPathList(list)] => {
+            let entries = as_path_lists
+                .get(list)
+                .map(|l| l.entries.as_slice())
+                .unwrap_or_default();
+            for (state, clause) in entries {
+                match (state, clause) {
+                    (RouteMapState::Allow, RouteMapMatchAsPath::Contains(as_id)) => {
+                        asl.contains_as(*as_id)
+                    }
+                    (RouteMapState::Deny, RouteMapMatchAsPath::Contains(as_id)) => {
+                        asl.deny_as(*as_id)
+                    }
+                    (_, RouteMapMatchAsPath::Length(_)) => {
+                        return Err(format!(
+                            "AS-path access-list {list} matches on the path length"
+                        ))
+                    }
+                };
+            }
+            // an undefined or empty list does not match any route.
+            if entries.is_empty() {
+                asl.deny_any();
+            }
+        }
+        _ => {
+            return Err(String::from(
+                "Multiple AS-path conditions in a single route-map",
+            ))
+        }
     }
+    Ok(Some(asl))
 }
 
 /// Extrat the prefix list that is matched in the route-map
@@ -1229,8 +1390,28 @@ fn rm_match_next_hop<P: Prefix>(rm: &RouteMap<P>) -> Option<RouterId> {
     next_hop
 }
 
+/// Extract the tag that is matched in the route-map. A route-map that matches multiple different
+/// tags never matches, which cannot be expressed with a single `match tag` statement.
+fn rm_match_tag<P: Prefix>(rm: &RouteMap<P>) -> Result<Option<u32>, String> {
+    let mut tag: Option<u32> = None;
+
+    for cond in rm.conds.iter() {
+        if let RouteMapMatch::Tag(t) = cond {
+            if tag.is_none() {
+                tag = Some(*t);
+            } else if tag != Some(*t) {
+                return Err(String::from(
+                    "Multiple different tags matched in a route-map",
+                ));
+            }
+        }
+    }
+
+    Ok(tag)
+}
+
 /// Extract the set of communities that must be present in the route such that it matches
-fn rm_delete_community_list<P: Prefix>(rm: &RouteMap<P>) -> Option<HashSet<u32>> {
+fn rm_delete_community_list<P: Prefix>(rm: &RouteMap<P>) -> Option<HashSet<Community>> {
     let mut communities = HashSet::new();
 
     for set in rm.set.iter() {
@@ -51,6 +51,7 @@ pub struct Interface {
     no_hello_interval: bool,
     mac_address: Option<[u8; 6]>,
     no_mac_address: bool,
+    mtu: Option<u32>,
     shutdown: Option<bool>,
     switchport: Option<bool>,
 }
@@ -73,6 +74,7 @@ impl Interface {
             no_hello_interval: false,
             mac_address: None,
             no_mac_address: false,
+            mtu: None,
         }
     }
 
@@ -389,6 +391,29 @@ impl Interface {
         self
     }
 
+    /// Set the MTU of the interface. This option is not available on `Target::FRR`, where the MTU
+    /// is configured in the kernel.
+    ///
+    /// ```
+    /// # use bgpsim::export::cisco_frr_generators::{Interface, Target};
+    /// assert_eq!(
+    ///     Interface::new("Ethernet4/1").mtu(9000).build(Target::CiscoNexus7000),
+    ///     "\
+    /// interface Ethernet4/1
+    ///   mtu 9000
+    /// exit
+    /// "
+    /// );
+    /// assert_eq!(
+    ///     Interface::new("eth1").mtu(9000).build(Target::Frr),
+    ///     "interface eth1\nexit\n"
+    /// );
+    /// ```
+    pub fn mtu(&mut self, mtu: u32) -> &mut Self {
+        self.mtu = Some(mtu);
+        self
+    }
+
     /// Disable the interface by setting the `shutdown` command.
     ///
     /// ```
@@ -499,7 +524,7 @@ impl Interface {
         format!(
             "\
         interface {iface}\
-{switchport}{addr}{cost}{area}{dead}{hello}{mac}{shutdown}
+{switchport}{addr}{cost}{area}{dead}{hello}{mac}{mtu}{shutdown}
 exit
 ",
             switchport = match (target, self.switchport) {
@@ -549,6 +574,10 @@ exit
                 (_, true) if target != Target::Frr => String::from("\n  no mac-address"),
                 _ => String::new(),
             },
+            mtu = match self.mtu {
+                Some(mtu) if target != Target::Frr => format!("\n  mtu {mtu}"),
+                _ => String::new(),
+            },
             shutdown = match self.shutdown {
                 Some(true) => "\n  shutdown",
                 Some(false) => "\n  no shutdown",
@@ -1051,12 +1080,16 @@ exit
 /// BGP Router neighbor configuration for Cisco-like routers
 #[derive(Debug, Clone)]
 pub struct RouterBgpNeighbor {
-    neighbor_id: Ipv4Addr,
+    neighbor_id: String,
+    is_peer_group: bool,
+    peer_group: Option<String>,
     remote_as: Option<AsId>,
     weight: Option<u16>,
     no_weight: bool,
     update_source: Option<String>,
     no_update_source: bool,
+    password: Option<String>,
+    no_password: bool,
     next_hop_self: Option<bool>,
     route_reflector_client: Option<bool>,
     route_map_in: Option<String>,
@@ -1071,12 +1104,16 @@ impl RouterBgpNeighbor {
     /// Create a new BGP Neighbor builder.
     pub fn new(neighbor_id: Ipv4Addr) -> Self {
         Self {
-            neighbor_id,
+            neighbor_id: neighbor_id.to_string(),
+            is_peer_group: false,
+            peer_group: Default::default(),
             remote_as: Default::default(),
             weight: Default::default(),
             no_weight: Default::default(),
             update_source: Default::default(),
             no_update_source: Default::default(),
+            password: Default::default(),
+            no_password: Default::default(),
             next_hop_self: Default::default(),
             route_reflector_client: Default::default(),
             route_map_in: Default::default(),
@@ -1088,6 +1125,44 @@ impl RouterBgpNeighbor {
         }
     }
 
+    /// Create a new builder for a peer-group (or a peer template on Cisco) with the given name.
+    /// All settings of the builder are applied to the peer-group instead of a single neighbor.
+    ///
+    /// ```
+    /// # use bgpsim::export::cisco_frr_generators::{RouterBgpNeighbor, Target};
+    /// assert_eq!(
+    ///     RouterBgpNeighbor::new_peer_group("customers")
+    ///         .next_hop_self()
+    ///         .build(Target::CiscoNexus7000),
+    /// #   "  ".to_owned() +
+    ///     "\
+    ///   template peer customers
+    ///     address-family ipv4 unicast
+    ///       next-hop-self
+    ///     exit
+    ///   exit
+    /// "
+    /// );
+    /// assert_eq!(
+    ///     RouterBgpNeighbor::new_peer_group("customers")
+    ///         .next_hop_self()
+    ///         .build(Target::Frr),
+    /// #   "  ".to_owned() +
+    ///     "\
+    ///   neighbor customers peer-group
+    ///   address-family ipv4 unicast
+    ///     neighbor customers next-hop-self
+    ///   exit
+    /// "
+    /// );
+    /// ```
+    pub fn new_peer_group(name: impl Into<String>) -> Self {
+        let mut group = Self::new(Ipv4Addr::UNSPECIFIED);
+        group.neighbor_id = name.into();
+        group.is_peer_group = true;
+        group
+    }
+
     /// Remove the neighbor from the configuration.
     ///
     /// ```
@@ -1129,6 +1204,36 @@ impl RouterBgpNeighbor {
         self
     }
 
+    /// Make the neighbor a member of a peer-group (or let it inherit a peer template on Cisco),
+    /// created with [`RouterBgpNeighbor::new_peer_group`].
+    ///
+    /// ```
+    /// # use bgpsim::export::cisco_frr_generators::{RouterBgpNeighbor, Target};
+    /// # use std::net::Ipv4Addr;
+    /// let neighbor_addr: Ipv4Addr = "20.0.0.1".parse().unwrap();
+    /// assert_eq!(
+    ///     RouterBgpNeighbor::new(neighbor_addr)
+    ///         .peer_group("customers")
+    ///         .build(Target::CiscoNexus7000),
+    /// #   "  ".to_owned() +
+    ///     "\
+    ///   neighbor 20.0.0.1
+    ///     inherit peer customers
+    ///   exit
+    /// "
+    /// );
+    /// assert_eq!(
+    ///     RouterBgpNeighbor::new(neighbor_addr)
+    ///         .peer_group("customers")
+    ///         .build(Target::Frr),
+    ///     "  neighbor 20.0.0.1 peer-group customers\n"
+    /// );
+    /// ```
+    pub fn peer_group(&mut self, name: impl Into<String>) -> &mut Self {
+        self.peer_group = Some(name.into());
+        self
+    }
+
     /// Set the default weight.
     ///
     /// ```
@@ -1254,6 +1359,53 @@ impl RouterBgpNeighbor {
         self
     }
 
+    /// Set the password used to authenticate the session (TCP MD5 signature).
+    ///
+    /// ```
+    /// # use bgpsim::export::cisco_frr_generators::{RouterBgpNeighbor, Target};
+    /// # use std::net::Ipv4Addr;
+    /// let neighbor_addr: Ipv4Addr = "20.0.0.1".parse().unwrap();
+    /// assert_eq!(
+    ///     RouterBgpNeighbor::new(neighbor_addr)
+    ///         .password("secret")
+    ///         .build(Target::CiscoNexus7000),
+    /// #   "  ".to_owned() +
+    ///     "\
+    ///   neighbor 20.0.0.1
+    ///     password secret
+    ///   exit
+    /// "
+    /// );
+    /// assert_eq!(
+    ///     RouterBgpNeighbor::new(neighbor_addr)
+    ///         .password("secret")
+    ///         .build(Target::Frr),
+    ///     "  neighbor 20.0.0.1 password secret\n"
+    /// );
+    /// ```
+    pub fn password(&mut self, password: impl Into<String>) -> &mut Self {
+        self.password = Some(password.into());
+        self
+    }
+
+    /// Remove the password used to authenticate the session.
+    ///
+    /// ```
+    /// # use bgpsim::export::cisco_frr_generators::{RouterBgpNeighbor, Target};
+    /// # use std::net::Ipv4Addr;
+    /// let neighbor_addr: Ipv4Addr = "20.0.0.1".parse().unwrap();
+    /// assert_eq!(
+    ///     RouterBgpNeighbor::new(neighbor_addr)
+    ///         .no_password()
+    ///         .build(Target::Frr),
+    ///     "  no neighbor 20.0.0.1 password\n"
+    /// );
+    /// ```
+    pub fn no_password(&mut self) -> &mut Self {
+        self.no_password = true;
+        self
+    }
+
     /// Set the `next-hop-self` attribute for all routes that are received by this neighbor, and
     /// sent to that neighbor.
     ///
@@ -1704,8 +1856,8 @@ impl RouterBgpNeighbor {
 
     /// Generate the configuration lines
     pub fn build(&self, target: Target) -> String {
-        let (mut cfg, pre, tab, finish) = match target {
-            Target::CiscoNexus7000 => (
+        let (mut cfg, pre, tab, finish) = match (target, self.is_peer_group) {
+            (Target::CiscoNexus7000, false) => (
                 match self.remote_as {
                     Some(id) => format!("  neighbor {} remote-as {}", self.neighbor_id, id.0),
                     None => format!("  neighbor {}", self.neighbor_id),
@@ -1714,7 +1866,19 @@ impl RouterBgpNeighbor {
                 "  ",
                 "\n  exit\n",
             ),
-            Target::Frr => (
+            (Target::CiscoNexus7000, true) => (
+                match self.remote_as {
+                    Some(id) => format!(
+                        "  template peer {}\n    remote-as {}",
+                        self.neighbor_id, id.0
+                    ),
+                    None => format!("  template peer {}", self.neighbor_id),
+                },
+                String::new(),
+                "  ",
+                "\n  exit\n",
+            ),
+            (Target::Frr, false) => (
                 match self.remote_as {
                     Some(id) => format!("  neighbor {} remote-as {}", self.neighbor_id, id.0),
                     None => String::new(),
@@ -1723,8 +1887,29 @@ impl RouterBgpNeighbor {
                 "",
                 "\n",
             ),
+            (Target::Frr, true) => (
+                match self.remote_as {
+                    Some(id) => format!(
+                        "  neighbor {name} peer-group\n  neighbor {name} remote-as {}",
+                        id.0,
+                        name = self.neighbor_id
+                    ),
+                    None => format!("  neighbor {} peer-group", self.neighbor_id),
+                },
+                format!("neighbor {} ", self.neighbor_id),
+                "",
+                "\n",
+            ),
         };
 
+        // peer-group
+        if let Some(group) = self.peer_group.as_ref() {
+            match target {
+                Target::CiscoNexus7000 => cfg.push_str(&format!("\n    inherit peer {group}")),
+                Target::Frr => cfg.push_str(&format!("\n  {pre}peer-group {group}")),
+            }
+        }
+
         // create the af config
         let mut af = String::new();
 
@@ -1770,6 +1955,13 @@ impl RouterBgpNeighbor {
             (None, false) => {}
         }
 
+        // password
+        match (self.password.as_ref(), self.no_password) {
+            (Some(pw), false) => cfg.push_str(&format!("\n  {tab}{pre}password {pw}")),
+            (_, true) => cfg.push_str(&format!("\n  {tab}no {pre}password")),
+            (None, false) => {}
+        }
+
         // send-community
         let both = if matches!(target, Target::CiscoNexus7000) {
             " both"
@@ -1976,10 +2168,12 @@ pub struct RouteMapItem {
     match_community_list: Vec<(CommunityList, bool)>,
     match_as_path_list: Vec<(AsPathList, bool)>,
     match_next_hop_pl: Vec<(PrefixList, bool)>,
+    match_tag: Option<(u32, bool)>,
     set_next_hop: Option<(Ipv4Addr, bool)>,
     set_weight: Option<(u16, bool)>,
     set_local_pref: Option<(u32, bool)>,
     set_med: Option<(u32, bool)>,
+    set_tag: Option<(u32, bool)>,
     set_community: Vec<(String, bool)>,
     delete_community: Vec<(CommunityList, bool)>,
     prepend_as_path: Option<(Vec<AsId>, bool)>,
@@ -1998,10 +2192,12 @@ impl RouteMapItem {
             match_community_list: Default::default(),
             match_as_path_list: Default::default(),
             match_next_hop_pl: Default::default(),
+            match_tag: Default::default(),
             set_next_hop: Default::default(),
             set_weight: Default::default(),
             set_local_pref: Default::default(),
             set_med: Default::default(),
+            set_tag: Default::default(),
             set_community: Default::default(),
             delete_community: Default::default(),
             prepend_as_path: Default::default(),
@@ -2322,6 +2518,42 @@ impl RouteMapItem {
         self
     }
 
+    /// Match on the tag of the route.
+    ///
+    /// ```
+    /// # use bgpsim::export::cisco_frr_generators::{RouteMapItem, Target};
+    /// assert_eq!(
+    ///     RouteMapItem::new("test", 10, true).match_tag(7).build(Target::Frr),
+    ///     "\
+    /// route-map test permit 10
+    ///   match tag 7
+    /// exit
+    /// "
+    /// );
+    /// ```
+    pub fn match_tag(&mut self, tag: u32) -> &mut Self {
+        self.match_tag = Some((tag, true));
+        self
+    }
+
+    /// Remove the match on the tag of the route.
+    ///
+    /// ```
+    /// # use bgpsim::export::cisco_frr_generators::{RouteMapItem, Target};
+    /// assert_eq!(
+    ///     RouteMapItem::new("test", 10, true).no_match_tag().build(Target::Frr),
+    ///     "\
+    /// route-map test permit 10
+    ///   no match tag
+    /// exit
+    /// "
+    /// );
+    /// ```
+    pub fn no_match_tag(&mut self) -> &mut Self {
+        self.match_tag = Some((0, false));
+        self
+    }
+
     /// Set the next-hop field to a specific value.
     ///
     /// ```
@@ -2468,6 +2700,42 @@ impl RouteMapItem {
         self
     }
 
+    /// Set the tag of the route.
+    ///
+    /// ```
+    /// # use bgpsim::export::cisco_frr_generators::{RouteMapItem, Target};
+    /// assert_eq!(
+    ///     RouteMapItem::new("test", 10, true).set_tag(7).build(Target::Frr),
+    ///     "\
+    /// route-map test permit 10
+    ///   set tag 7
+    /// exit
+    /// "
+    /// );
+    /// ```
+    pub fn set_tag(&mut self, tag: u32) -> &mut Self {
+        self.set_tag = Some((tag, true));
+        self
+    }
+
+    /// Remove the set of the tag.
+    ///
+    /// ```
+    /// # use bgpsim::export::cisco_frr_generators::{RouteMapItem, Target};
+    /// assert_eq!(
+    ///     RouteMapItem::new("test", 10, true).no_set_tag().build(Target::Frr),
+    ///     "\
+    /// route-map test permit 10
+    ///   no set tag
+    /// exit
+    /// "
+    /// );
+    /// ```
+    pub fn no_set_tag(&mut self) -> &mut Self {
+        self.set_tag = Some((0, false));
+        self
+    }
+
     /// Set a specific community tag
     ///
     /// ```
@@ -2785,6 +3053,12 @@ impl RouteMapItem {
             cfg.push_str(if *mode { "  " } else { "  no " });
             cfg.push_str(&format!("match ip next-hop prefix-list {}\n", pl.name));
         }
+        // match_tag: Option<(u32, bool)>,
+        match self.match_tag {
+            Some((x, true)) => cfg.push_str(&format!("  match tag {x}\n")),
+            Some((_, false)) => cfg.push_str("  no match tag\n"),
+            None => {}
+        }
         // set_next_hop: Option<(Ipv4Addr, bool)>,
         match self.set_next_hop {
             Some((x, true)) => cfg.push_str(&format!("  set ip next-hop {x}\n")),
@@ -2809,6 +3083,12 @@ impl RouteMapItem {
             Some((_, false)) => cfg.push_str("  no set metric\n"),
             None => {}
         }
+        // set_tag: Option<(u32, bool)>,
+        match self.set_tag {
+            Some((x, true)) => cfg.push_str(&format!("  set tag {x}\n")),
+            Some((_, false)) => cfg.push_str("  no set tag\n"),
+            None => {}
+        }
         // add the word `additive` only to cisco devices.
         let additive = match target {
             Target::CiscoNexus7000 => "additive ",
@@ -3086,7 +3366,7 @@ impl From<&mut CommunityList> for CommunityList {
 #[derive(Debug, Clone)]
 pub struct AsPathList {
     name: String,
-    regex: String,
+    entries: Vec<(bool, String)>,
 }
 
 impl AsPathList {
@@ -3094,7 +3374,7 @@ impl AsPathList {
     pub fn new(name: impl Into<String>) -> Self {
         Self {
             name: name.into(),
-            regex: String::new(),
+            entries: Vec::new(),
         }
     }
 
@@ -3124,7 +3404,37 @@ impl AsPathList {
     /// );
     /// ```
     pub fn contains_as(&mut self, as_id: impl Into<AsId>) -> &mut Self {
-        self.regex = format!("_{}_", as_id.into().0);
+        self.entries.push((true, format!("_{}_", as_id.into().0)));
+        self
+    }
+
+    /// Add an entry that denies all AS paths containing a specific AS. Entries are checked in the
+    /// order in which they are added.
+    /// ```
+    /// # use bgpsim::export::cisco_frr_generators::{AsPathList, Target};
+    /// assert_eq!(
+    ///     AsPathList::new("test").deny_as(10).contains_as(20).build(Target::CiscoNexus7000),
+    ///     "\
+    /// ip as-path access-list test deny _10_
+    /// ip as-path access-list test permit _20_
+    /// "
+    /// );
+    /// ```
+    pub fn deny_as(&mut self, as_id: impl Into<AsId>) -> &mut Self {
+        self.entries.push((false, format!("_{}_", as_id.into().0)));
+        self
+    }
+
+    /// Add an entry that denies all AS paths.
+    /// ```
+    /// # use bgpsim::export::cisco_frr_generators::{AsPathList, Target};
+    /// assert_eq!(
+    ///     AsPathList::new("test").deny_any().build(Target::Frr),
+    ///     "bgp as-path access-list test deny .*\n"
+    /// );
+    /// ```
+    pub fn deny_any(&mut self) -> &mut Self {
+        self.entries.push((false, String::from(".*")));
         self
     }
 
@@ -3134,10 +3444,16 @@ impl AsPathList {
             Target::CiscoNexus7000 => "ip",
             Target::Frr => "bgp",
         };
-        format!(
-            "{} as-path access-list {} permit {}\n",
-            root, self.name, self.regex
-        )
+        self.entries
+            .iter()
+            .map(|(permit, regex)| {
+                let action = if *permit { "permit" } else { "deny" };
+                format!(
+                    "{root} as-path access-list {} {action} {regex}\n",
+                    self.name
+                )
+            })
+            .join("")
     }
 }
 
@@ -322,19 +322,29 @@ impl<P: Prefix> ExaBgpCfgGen<P> {
         Ok(script)
     }
 
-    /// Generate the configuration for a single neighbor
-    fn generate_neighbor_cfg<A: Addressor<P>>(
+    /// Generate the configuration for a single neighbor. The session password is taken from the
+    /// options configured on the internal neighbor.
+    fn generate_neighbor_cfg<A: Addressor<P>, Q, Ospf: OspfImpl>(
         &self,
+        net: &Network<P, Q, Ospf>,
         addressor: &mut A,
         neighbor: RouterId,
     ) -> Result<String, ExportError> {
+        let password = net
+            .get_device(neighbor)
+            .ok()
+            .and_then(|n| n.internal())
+            .and_then(|n| n.bgp.get_session_options(self.router))
+            .and_then(|o| o.password.as_ref())
+            .map(|pw| format!("\n    md5-password \"{pw}\";"))
+            .unwrap_or_default();
         Ok(format!(
             "\
 neighbor {} {{
     router-id {};
     local-address {};
     local-as {};
-    peer-as {};
+    peer-as {};{password}
     family {{ ipv4 unicast; }}
     capability {{ route-refresh; }}
 }}",
@@ -363,16 +373,12 @@ pub fn announce_route<P: Prefix>(route: &BgpRoute<P>) -> String {
         } else {
             String::new()
         },
-        if route.community.is_empty() {
+        if route.communities.is_empty() {
             String::new()
         } else {
             format!(
                 " extended-community [{}]",
-                route
-                    .community
-                    .iter()
-                    .map(|x| format!("{}:{}", INTERNAL_AS.0, x))
-                    .join(", ")
+                route.communities.iter().join(", ")
             )
         },
     )
@@ -388,13 +394,13 @@ impl<P: Prefix, A: Addressor<P>, Q, Ospf: OspfImpl> ExternalCfgGen<P, Q, Ospf, A
 {
     fn generate_config(
         &mut self,
-        _net: &Network<P, Q, Ospf>,
+        net: &Network<P, Q, Ospf>,
         addressor: &mut A,
     ) -> Result<String, ExportError> {
         Ok(self
             .neighbors
             .iter()
-            .map(|x| self.generate_neighbor_cfg(addressor, *x))
+            .map(|x| self.generate_neighbor_cfg(net, addressor, *x))
             .collect::<Result<Vec<String>, ExportError>>()?
             .into_iter()
             .join("\n"))
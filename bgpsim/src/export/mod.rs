@@ -35,10 +35,16 @@ mod cisco_frr;
 pub mod cisco_frr_generators;
 mod default;
 pub mod exabgp;
+mod networkx;
+mod reachability;
 
 pub use cisco_frr::CiscoFrrCfgGen;
 pub use default::{DefaultAddressor, DefaultAddressorBuilder};
 pub use exabgp::ExaBgpCfgGen;
+pub use networkx::networkx_json;
+pub use reachability::{
+    reachability_spec, reachability_spec_json, ExpectedReachability, ReachabilityAssertion,
+};
 
 /// Link index used in the IP addressor.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -0,0 +1,101 @@
+// BgpSim: BGP Network Simulator written in Rust
+// Copyright 2022-2024 Tibor Schneider <sctibor@ethz.ch>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Export the IGP topology as node-link JSON for [NetworkX](https://networkx.org).
+
+use serde_json::{json, Value};
+
+use crate::{
+    network::Network,
+    ospf::OspfImpl,
+    types::{NetworkDeviceRef, Prefix},
+};
+
+/// Export the IGP topology of the network as node-link JSON, which can be loaded by
+/// `networkx.readwrite.json_graph.node_link_graph`. The resulting graph is directed, and contains
+/// one edge in each direction of every link. The edges are stored in the field `edges` (the
+/// default since NetworkX 3.6). For older versions, pass `edges="edges"` (or `link="edges"` before
+/// NetworkX 3.4) to `node_link_graph`.
+///
+/// Each node is identified by the index of its [`crate::types::RouterId`], and has the attributes
+/// `name`, `role` (either `"internal"` or `"external"`), `as_id`, and `route_reflector` (only for
+/// internal routers). Each edge has the attributes `weight` (the link weight from `source` towards
+/// `target`) and `area` (the OSPF area). Infinite link weights are exported as `null`.
+///
+/// ```python
+/// import json
+/// import networkx as nx
+///
+/// with open("network.json") as f:
+///     g = nx.node_link_graph(json.load(f))
+/// ```
+pub fn networkx_json<P: Prefix, Q, Ospf: OspfImpl>(net: &Network<P, Q, Ospf>) -> String {
+    let mut nodes: Vec<(usize, Value)> = net
+        .devices()
+        .map(|r| match r {
+            NetworkDeviceRef::InternalRouter(r) => (
+                r.router_id().index(),
+                json!({
+                    "id": r.router_id().index(),
+                    "name": r.name(),
+                    "role": "internal",
+                    "as_id": r.as_id().0,
+                    "route_reflector": r.bgp.is_route_reflector(),
+                }),
+            ),
+            NetworkDeviceRef::ExternalRouter(r) => (
+                r.router_id().index(),
+                json!({
+                    "id": r.router_id().index(),
+                    "name": r.name(),
+                    "role": "external",
+                    "as_id": r.as_id().0,
+                }),
+            ),
+        })
+        .collect();
+    nodes.sort_by_key(|(id, _)| *id);
+
+    let topo = net.get_topology();
+    let mut links: Vec<(usize, usize, Value)> = topo
+        .edge_indices()
+        .filter_map(|e| topo.edge_endpoints(e))
+        .flat_map(|(a, b)| [(a, b), (b, a)])
+        .map(|(src, dst)| {
+            let weight = net.get_link_weight(src, dst).ok().filter(|w| w.is_finite());
+            let area = net.get_ospf_area(src, dst).ok().map(|a| a.num());
+            (
+                src.index(),
+                dst.index(),
+                json!({
+                    "source": src.index(),
+                    "target": dst.index(),
+                    "weight": weight,
+                    "area": area,
+                }),
+            )
+        })
+        .collect();
+    links.sort_by_key(|(src, dst, _)| (*src, *dst));
+
+    json!({
+        "directed": true,
+        "multigraph": false,
+        "graph": {},
+        "nodes": nodes.into_iter().map(|(_, n)| n).collect::<Vec<_>>(),
+        "edges": links.into_iter().map(|(_, _, l)| l).collect::<Vec<_>>(),
+    })
+    .to_string()
+}
@@ -0,0 +1,145 @@
+// BgpSim: BGP Network Simulator written in Rust
+// Copyright 2022-2024 Tibor Schneider <sctibor@ethz.ch>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Export the simulated forwarding state as a reachability test suite, to validate the deployed
+//! configurations against the simulation.
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    network::Network,
+    ospf::OspfImpl,
+    types::{NetworkError, Prefix, RouterId},
+};
+
+/// A single expectation on the data plane: traffic from `router` towards `prefix` should behave
+/// as described by `expect`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "P: for<'a> serde::Deserialize<'a>"))]
+pub struct ReachabilityAssertion<P: Prefix> {
+    /// Name of the router that sends the traffic.
+    pub router: String,
+    /// Destination of the traffic.
+    pub prefix: P,
+    /// The expected behavior.
+    pub expect: ExpectedReachability,
+}
+
+/// Expected behavior of the traffic in a [`ReachabilityAssertion`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExpectedReachability {
+    /// The traffic reaches the prefix.
+    Reachable {
+        /// Names of the routers at which the traffic leaves the network (or at which the prefix
+        /// is originated), sorted and without duplicates. There are multiple egress points if the
+        /// traffic is load-balanced.
+        egress: Vec<String>,
+        /// Names of all routers along each forwarding path, starting at the source router.
+        paths: Vec<Vec<String>>,
+    },
+    /// The traffic is dropped at the router with the given name.
+    BlackHole {
+        /// The router that drops the traffic.
+        at: String,
+    },
+    /// The traffic is caught in a forwarding loop.
+    Loop {
+        /// Names of the routers that form the loop.
+        routers: Vec<String>,
+    },
+}
+
+/// Generate the expected reachability of every known prefix from every internal router, based on
+/// the current forwarding state of the network (see [`Network::get_forwarding_state`]). The
+/// result contains exactly one assertion for each pair of internal router and prefix, ordered by
+/// the router-id and the prefix. The egress of a path is the last internal router on that path,
+/// i.e., the router that forwards traffic to an external neighbor, or that originates the prefix.
+///
+/// Call this function on a converged network. Use [`reachability_spec_json`] to obtain the same
+/// assertions in a machine-readable format.
+pub fn reachability_spec<P: Prefix, Q, Ospf: OspfImpl>(
+    net: &Network<P, Q, Ospf>,
+) -> Vec<ReachabilityAssertion<P>> {
+    let name = |r: RouterId| {
+        net.get_device(r)
+            .map(|d| d.name().to_string())
+            .unwrap_or_else(|_| r.index().to_string())
+    };
+    let mut fw = net.get_forwarding_state();
+    let prefixes = net.get_known_prefixes().copied().sorted().collect_vec();
+
+    net.internal_indices()
+        .sorted()
+        .flat_map(|r| prefixes.iter().map(move |p| (r, *p)))
+        .map(|(router, prefix)| {
+            let expect = match fw.get_paths(router, prefix) {
+                Ok(paths) => ExpectedReachability::Reachable {
+                    egress: paths
+                        .iter()
+                        .filter_map(|path| {
+                            path.iter()
+                                .rev()
+                                .find(|r| net.get_device(**r).is_ok_and(|d| d.is_internal()))
+                        })
+                        .copied()
+                        .sorted()
+                        .dedup()
+                        .map(name)
+                        .collect(),
+                    paths: paths
+                        .into_iter()
+                        .map(|path| path.into_iter().map(name).collect())
+                        .collect(),
+                },
+                Err(NetworkError::ForwardingLoop { first_loop, .. }) => {
+                    ExpectedReachability::Loop {
+                        routers: first_loop.into_iter().map(name).collect(),
+                    }
+                }
+                Err(NetworkError::ForwardingBlackHole(path)) => ExpectedReachability::BlackHole {
+                    at: path.last().map(|r| name(*r)).unwrap_or_default(),
+                },
+                Err(_) => ExpectedReachability::BlackHole { at: name(router) },
+            };
+            ReachabilityAssertion {
+                router: name(router),
+                prefix,
+                expect,
+            }
+        })
+        .collect()
+}
+
+/// Generate the reachability assertions of [`reachability_spec`], and serialize them as a JSON
+/// array. Each assertion is an object with the fields `router`, `prefix`, and `expect`, where
+/// `expect` has a field `kind` that is either `"reachable"` (with the fields `egress` and
+/// `paths`), `"black_hole"` (with the field `at`), or `"loop"` (with the field `routers`).
+///
+/// ```json
+/// [
+///   {
+///     "router": "R1",
+///     "prefix": "10.0.0.0/24",
+///     "expect": { "kind": "reachable", "egress": ["R2"], "paths": [["R1", "R2", "E2"]] }
+///   }
+/// ]
+/// ```
+pub fn reachability_spec_json<P: Prefix, Q, Ospf: OspfImpl>(
+    net: &Network<P, Q, Ospf>,
+) -> Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(&reachability_spec(net))
+}
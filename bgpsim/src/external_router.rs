@@ -21,11 +21,12 @@
 use crate::{
     bgp::{BgpEvent, BgpRoute},
     event::{Event, EventOutcome},
-    types::{AsId, DeviceError, Prefix, PrefixMap, RouterId, StepUpdate},
+    types::{AsId, Community, DeviceError, Prefix, PrefixMap, RouterId, StepUpdate},
 };
 
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 /// Struct representing an external router
 /// NOTE: We use vectors, for both the neighbors and active routes. The reason is the following:
@@ -45,6 +46,17 @@ pub struct ExternalRouter<P: Prefix> {
     as_id: AsId,
     pub(crate) neighbors: HashSet<RouterId>,
     pub(crate) active_routes: P::Map<BgpRoute<P>>,
+    /// Whether the router is a route collector that only receives routes, but never advertises any.
+    #[serde(default)]
+    pub(crate) receive_only: bool,
+    /// Routes received from each neighbor (only maintained if the router is receive-only or a
+    /// route server).
+    #[serde(default)]
+    pub(crate) received_routes: HashMap<RouterId, P::Map<BgpRoute<P>>>,
+    /// Whether the router is a route server that transparently reflects the routes received from
+    /// each neighbor to all other neighbors.
+    #[serde(default)]
+    pub(crate) route_server: bool,
 }
 
 impl<P: Prefix> PartialEq for ExternalRouter<P> {
@@ -54,6 +66,9 @@ impl<P: Prefix> PartialEq for ExternalRouter<P> {
             && self.as_id == other.as_id
             && self.neighbors == other.neighbors
             && self.active_routes.eq(&other.active_routes)
+            && self.receive_only == other.receive_only
+            && self.received_routes == other.received_routes
+            && self.route_server == other.route_server
     }
 }
 
@@ -65,6 +80,9 @@ impl<P: Prefix> Clone for ExternalRouter<P> {
             as_id: self.as_id,
             neighbors: self.neighbors.clone(),
             active_routes: self.active_routes.clone(),
+            receive_only: self.receive_only,
+            received_routes: self.received_routes.clone(),
+            route_server: self.route_server,
         }
     }
 }
@@ -78,18 +96,88 @@ impl<P: Prefix> ExternalRouter<P> {
             as_id,
             neighbors: HashSet::new(),
             active_routes: Default::default(),
+            receive_only: false,
+            received_routes: HashMap::new(),
+            route_server: false,
         }
     }
 
-    /// Handle an `Event` and produce the necessary result. Always returns Ok((false, vec![])), to
-    /// tell that the forwarding state has not changed.
-    pub(crate) fn handle_event<T>(
+    /// Handle an `Event` and produce the necessary result. Always returns `StepUpdate::Unchanged`,
+    /// to tell that the forwarding state has not changed. A receive-only router or a route server
+    /// stores all received routes. A route server further generates the updates for all other
+    /// neighbors.
+    pub(crate) fn handle_event<T: Default>(
         &mut self,
-        _event: Event<P, T>,
+        event: Event<P, T>,
     ) -> Result<EventOutcome<P, T>, DeviceError> {
+        if let (true, Event::Bgp { src, e, .. }) = (self.receive_only || self.route_server, event) {
+            if !self.neighbors.contains(&src) {
+                log::warn!("Received a bgp event from a non-neighbor! Ignore event!");
+                return Ok((StepUpdate::Unchanged, vec![]));
+            }
+            let prefix = e.prefix();
+            let before = self.route_server_selection(prefix);
+            let table = self.received_routes.entry(src).or_default();
+            match e {
+                BgpEvent::Update(route) => {
+                    table.insert(route.prefix, route);
+                }
+                BgpEvent::Withdraw(prefix) => {
+                    table.remove(&prefix);
+                }
+            }
+            return Ok((
+                StepUpdate::Unchanged,
+                self.route_server_updates(prefix, before),
+            ));
+        }
         Ok((StepUpdate::Unchanged, vec![]))
     }
 
+    /// Get the route that a route server advertises to `neighbor` for `prefix`. This is the route
+    /// with the shortest AS path among all routes received from other neighbors. Ties are broken
+    /// by the lowest router-id of the sender. Returns `None` if the router is not a route server.
+    fn route_server_best(&self, neighbor: RouterId, prefix: P) -> Option<&BgpRoute<P>> {
+        if !self.route_server {
+            return None;
+        }
+        self.received_routes
+            .iter()
+            .filter(|(src, _)| **src != neighbor)
+            .filter_map(|(src, table)| table.get(&prefix).map(|r| (*src, r)))
+            .min_by_key(|(src, r)| (r.as_path.len(), *src))
+            .map(|(_, r)| r)
+    }
+
+    /// Get the route that a route server currently advertises to each neighbor for `prefix`.
+    fn route_server_selection(&self, prefix: P) -> HashMap<RouterId, BgpRoute<P>> {
+        self.neighbors
+            .iter()
+            .filter_map(|n| Some((*n, self.route_server_best(*n, prefix)?.clone())))
+            .collect()
+    }
+
+    /// Generate the events for all neighbors whose selected route for `prefix` has changed
+    /// compared to `before`.
+    fn route_server_updates<T: Default>(
+        &self,
+        prefix: P,
+        mut before: HashMap<RouterId, BgpRoute<P>>,
+    ) -> Vec<Event<P, T>> {
+        let mut events = Vec::new();
+        for n in self.neighbors.iter() {
+            let old = before.remove(n);
+            let event = match (old, self.route_server_best(*n, prefix)) {
+                (Some(old), Some(new)) if &old == new => continue,
+                (_, Some(new)) => BgpEvent::Update(new.clone()),
+                (Some(_), None) => BgpEvent::Withdraw(prefix),
+                (None, None) => continue,
+            };
+            events.push(Event::bgp(T::default(), self.router_id, *n, event));
+        }
+        events
+    }
+
     /// Return the ID of the network device
     pub fn router_id(&self) -> RouterId {
         self.router_id
@@ -112,15 +200,24 @@ impl<P: Prefix> ExternalRouter<P> {
 
     /// Start advertizing a specific route. All neighbors (including future neighbors) will get an
     /// update message with the route.
-    pub(crate) fn advertise_prefix<T: Default, I: IntoIterator<Item = u32>>(
+    pub(crate) fn advertise_prefix<T: Default, I: IntoIterator<Item = Community>>(
         &mut self,
         prefix: P,
         as_path: Vec<AsId>,
         med: Option<u32>,
-        community: I,
+        communities: I,
     ) -> (BgpRoute<P>, Vec<Event<P, T>>) {
-        let route = BgpRoute::new(self.router_id, prefix, as_path, med, community);
+        let route = BgpRoute::new(self.router_id, prefix, as_path, med, communities);
+        self.advertise_route(route)
+    }
 
+    /// Start advertising a specific route, whose attributes (including the next-hop) are already
+    /// set. All neighbors (including future neighbors) will get an update message with the route.
+    pub(crate) fn advertise_route<T: Default>(
+        &mut self,
+        route: BgpRoute<P>,
+    ) -> (BgpRoute<P>, Vec<Event<P, T>>) {
+        let prefix = route.prefix;
         let old_route = self.active_routes.insert(prefix, route.clone());
 
         if old_route.as_ref() == Some(&route) {
@@ -155,6 +252,33 @@ impl<P: Prefix> ExternalRouter<P> {
         }
     }
 
+    /// Forget all advertised and received routes without sending any withdraw messages.
+    pub(crate) fn reset(&mut self) {
+        self.active_routes = Default::default();
+        self.received_routes.clear();
+    }
+
+    /// Configure whether the router is a receive-only route collector. When enabling it, the
+    /// router withdraws all routes it currently advertises. When disabling it, the router forgets
+    /// all received routes. This function returns the old value and all events triggered by this
+    /// action.
+    pub(crate) fn set_receive_only<T: Default>(
+        &mut self,
+        receive_only: bool,
+    ) -> (bool, Vec<Event<P, T>>) {
+        let old = std::mem::replace(&mut self.receive_only, receive_only);
+        let mut events = Vec::new();
+        if receive_only {
+            let prefixes: Vec<P> = self.active_routes.keys().copied().collect();
+            for prefix in prefixes {
+                events.extend(self.withdraw_prefix(prefix));
+            }
+        } else if !self.route_server {
+            self.received_routes.clear();
+        }
+        (old, events)
+    }
+
     /// Add an ebgp session with an internal router. Generate all events.
     pub(crate) fn establish_ebgp_session<T: Default>(
         &mut self,
@@ -163,10 +287,18 @@ impl<P: Prefix> ExternalRouter<P> {
         // if the session does not yet exist, push the new router into the list
         Ok(if self.neighbors.insert(router) {
             // session did not exist.
-            // send all prefixes to this router
+            // send all prefixes to this router, including the routes reflected by a route server
+            let reflected = self
+                .received_routes
+                .values()
+                .flat_map(|table| table.keys())
+                .unique()
+                .filter_map(|p| self.route_server_best(router, *p));
             self.active_routes
                 .iter()
-                .map(|(_, r)| {
+                .map(|(_, r)| r)
+                .chain(reflected)
+                .map(|r| {
                     Event::bgp(
                         T::default(),
                         self.router_id,
@@ -180,10 +312,28 @@ impl<P: Prefix> ExternalRouter<P> {
         })
     }
 
-    /// Close an existing eBGP session with an internal router.
-    pub(crate) fn close_ebgp_session(&mut self, router: RouterId) -> Result<(), DeviceError> {
+    /// Close an existing eBGP session with an internal router. A route server updates all other
+    /// neighbors that received a route from `router`, and returns those events.
+    pub(crate) fn close_ebgp_session<T: Default>(
+        &mut self,
+        router: RouterId,
+    ) -> Result<Vec<Event<P, T>>, DeviceError> {
         self.neighbors.remove(&router);
-        Ok(())
+        let prefixes: Vec<P> = self
+            .received_routes
+            .get(&router)
+            .map(|table| table.keys().copied().collect())
+            .unwrap_or_default();
+        let before: Vec<_> = prefixes
+            .iter()
+            .map(|p| self.route_server_selection(*p))
+            .collect();
+        self.received_routes.remove(&router);
+        Ok(prefixes
+            .into_iter()
+            .zip(before)
+            .flat_map(|(p, before)| self.route_server_updates(p, before))
+            .collect())
     }
 
     /// Checks if both routers advertise the same routes.
@@ -207,6 +357,30 @@ impl<P: Prefix> ExternalRouter<P> {
         &self.active_routes
     }
 
+    /// Returns `true` if the router is a route collector that only receives routes, but never
+    /// advertises any (see [`Network::set_receive_only`](crate::network::Network::set_receive_only)).
+    pub fn is_receive_only(&self) -> bool {
+        self.receive_only
+    }
+
+    /// Returns all routes that a receive-only router currently holds from `neighbor` (its
+    /// Adj-RIB-In). Returns `None` if the router has not received any route from `neighbor`, or if
+    /// it is not receive-only.
+    pub fn get_received_routes(&self, neighbor: RouterId) -> Option<&P::Map<BgpRoute<P>>> {
+        self.received_routes.get(&neighbor)
+    }
+
+    /// Returns the route for `prefix` that a receive-only router currently holds from `neighbor`.
+    pub fn get_received_route(&self, neighbor: RouterId, prefix: P) -> Option<&BgpRoute<P>> {
+        self.received_routes.get(&neighbor)?.get(&prefix)
+    }
+
+    /// Returns `true` if the router is a route server that transparently reflects routes between
+    /// its neighbors (see [`Network::add_route_server`](crate::network::Network::add_route_server)).
+    pub fn is_route_server(&self) -> bool {
+        self.route_server
+    }
+
     /// Returns a reference to the hashset containing all BGP sessions.
     pub fn get_bgp_sessions(&self) -> &HashSet<RouterId> {
         &self.neighbors
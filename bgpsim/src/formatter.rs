@@ -27,7 +27,7 @@ use crate::{
     config::{Config, ConfigExpr, ConfigExprKey, ConfigModifier, ConfigPatch, RouteMapEdit},
     event::{BasicEventQueue, Event, FmtPriority},
     forwarding_state::{ForwardingState, TO_DST},
-    network::Network,
+    network::{Network, NetworkSummary},
     ospf::{
         global::GlobalOspfProcess,
         local::{LocalOspfProcess, OspfRibEntry},
@@ -305,10 +305,10 @@ impl<'a, 'n, P: Prefix, Q, Ospf: OspfImpl> NetworkFormatter<'a, 'n, P, Q, Ospf>
             } else {
                 String::new()
             },
-            if self.community.is_empty() {
+            if self.communities.is_empty() {
                 String::new()
             } else {
-                format!(", community: {}", join(self.community.iter(), ";"))
+                format!(", community: {}", join(self.communities.iter(), ";"))
             },
         )
     }
@@ -332,10 +332,10 @@ impl<'a, 'n, P: Prefix, Q, Ospf: OspfImpl> NetworkFormatter<'a, 'n, P, Q, Ospf>
             cost = self.igp_cost.unwrap_or_default(),
             nh = self.route.next_hop.fmt(net),
             next = self.from_id.fmt(net),
-            comm = if self.route.community.is_empty() {
+            comm = if self.route.communities.is_empty() {
                 String::from("")
             } else {
-                format!(", communities = [{}]", self.route.community.iter().join(", "))
+                format!(", communities = [{}]", self.route.communities.iter().join(", "))
             },
         )
     }
@@ -359,6 +359,8 @@ impl<'a, 'n, P: Prefix, Q, Ospf: OspfImpl> NetworkFormatter<'a, 'n, P, Q, Ospf>
             RouteMapMatch::NextHop(nh) => format!("NextHop == {}", nh.fmt(net)),
             RouteMapMatch::Community(c) => format!("Community {c}"),
             RouteMapMatch::DenyCommunity(c) => format!("Deny Community {c}"),
+            RouteMapMatch::AsPathList(name) => format!("AsPath in list {name}"),
+            RouteMapMatch::Tag(t) => format!("Tag {t}"),
         }
     }
 }
@@ -378,6 +380,14 @@ impl<'a, 'n, P: Prefix, Q, Ospf: OspfImpl> NetworkFormatter<'a, 'n, P, Q, Ospf>
             RouteMapSet::IgpCost(w) => format!("IgpCost = {w:.2}"),
             RouteMapSet::SetCommunity(c) => format!("Set community {c}"),
             RouteMapSet::DelCommunity(c) => format!("Remove community {c}"),
+            RouteMapSet::ClampLocalPref(min, max) => format!("LocalPref in [{min}, {max}]"),
+            RouteMapSet::ClampMed(min, max) => format!("MED in [{min}, {max}]"),
+            RouteMapSet::AsPathPrepend(asn, n) => format!("Prepend {asn} {n} times"),
+            RouteMapSet::Tag(t) => format!("Tag = {t}"),
+            RouteMapSet::LocalPrefFromIgpCost(base, factor) => {
+                format!("LocalPref = {base} - {factor} * IgpCost")
+            }
+            RouteMapSet::Dampen => "Dampen".to_string(),
         }
     }
 }
@@ -783,6 +793,36 @@ impl<'a, 'n, P: Prefix, Q, Ospf: OspfImpl> NetworkFormatter<'a, 'n, P, Q, Ospf>
     }
 }
 
+impl<'a, 'n, P: Prefix, Q, Ospf: OspfImpl> NetworkFormatter<'a, 'n, P, Q, Ospf> for NetworkSummary {
+    type Formatter = String;
+
+    fn fmt(&'a self, _net: &'n Network<P, Q, Ospf>) -> Self::Formatter {
+        let mut result = String::new();
+        let f = &mut result;
+        writeln!(
+            f,
+            "routers:     {} internal, {} external",
+            self.num_internal_routers, self.num_external_routers
+        )
+        .unwrap();
+        writeln!(f, "links:       {}", self.num_links).unwrap();
+        writeln!(
+            f,
+            "sessions:    {} eBGP, {} iBGP peer, {} iBGP client",
+            self.num_ebgp_sessions, self.num_ibgp_peer_sessions, self.num_ibgp_client_sessions
+        )
+        .unwrap();
+        writeln!(f, "route-maps:  {}", self.num_route_maps).unwrap();
+        writeln!(f, "prefixes:    {}", self.num_prefixes).unwrap();
+        if self.is_converged() {
+            write!(f, "state:       converged").unwrap();
+        } else {
+            write!(f, "state:       {} events queued", self.num_queued_events).unwrap();
+        }
+        result
+    }
+}
+
 impl<'a, 'n, P: Prefix, Q, Ospf: OspfImpl> NetworkFormatter<'a, 'n, P, Q, Ospf> for NetworkError {
     type Formatter = String;
 
@@ -803,6 +843,16 @@ impl<'a, 'n, P: Prefix, Q, Ospf: OspfImpl> NetworkFormatter<'a, 'n, P, Q, Ospf>
                 src.fmt(net),
                 dst.fmt(net)
             ),
+            NetworkError::EmptyLinkBundle(src, dst) => format!(
+                "The link bundle between {} and {} has no members!",
+                src.fmt(net),
+                dst.fmt(net)
+            ),
+            NetworkError::LinkBundleMemberNotFound(src, dst, member) => format!(
+                "The link bundle between {} and {} has no member {member}!",
+                src.fmt(net),
+                dst.fmt(net)
+            ),
             NetworkError::ForwardingLoop {
                 to_loop,
                 first_loop,
@@ -823,10 +873,41 @@ impl<'a, 'n, P: Prefix, Q, Ospf: OspfImpl> NetworkFormatter<'a, 'n, P, Q, Ospf>
                 src.fmt(net),
                 dst.fmt(net)
             ),
+            NetworkError::NotARouteReflector(r) => {
+                format!("{} is not a route reflector!", r.fmt(net))
+            }
+            NetworkError::ReceiveOnly(r) => {
+                format!(
+                    "{} is receive-only and cannot advertise routes!",
+                    r.fmt(net)
+                )
+            }
+            NetworkError::RouteServer(r) => {
+                format!(
+                    "{} is a route server and cannot originate routes!",
+                    r.fmt(net)
+                )
+            }
+            NetworkError::CommunityMapTooLarge(n) => {
+                format!("The community to LOCAL-PREF map has too many entries: {n}")
+            }
+            NetworkError::ReservedRouteMapOrder(n, order) => format!(
+                "Route-map item {order} on the session with {} uses an order reserved for community maps",
+                n.fmt(net)
+            ),
+            NetworkError::UnknownPolicy(name) => {
+                format!("Policy {name} is not defined in the library!")
+            }
+            NetworkError::UnknownPeerGroup(name) => {
+                format!("Peer-group {name} is not defined!")
+            }
             NetworkError::NoConvergence => String::from("Network could not converge!"),
             NetworkError::InvalidBgpTable(r) => {
                 format!("Router {} has an invalid BGP table!", r.fmt(net))
             }
+            NetworkError::InvalidWarmStart => String::from(
+                "Cannot warm-start from a network with a different topology or configuration!",
+            ),
             NetworkError::JsonError(e) => format!("Json error occurred: {e}"),
             NetworkError::CannotConnectExternalRouters(a, b) => format!(
                 "Cannot connect two external routers: {} and {}.",
@@ -841,6 +922,17 @@ impl<'a, 'n, P: Prefix, Q, Ospf: OspfImpl> NetworkFormatter<'a, 'n, P, Q, Ospf>
             NetworkError::InconsistentOspfState(k) => {
                 format!("OSPF state is inconsistent for key {}", k.fmt(net))
             }
+            NetworkError::CannotConnectToItself(r) => {
+                format!("Cannot connect {} to itself.", r.fmt(net))
+            }
+            NetworkError::InvalidLinkWeight(a, b, w) => format!(
+                "Invalid link weight {w} for the link from {} to {}.",
+                a.fmt(net),
+                b.fmt(net)
+            ),
+            NetworkError::BackboneAreaType(t) => {
+                format!("The backbone area cannot be a {t} area.")
+            }
         }
     }
 }
@@ -870,6 +962,7 @@ impl<'a, 'n, P: Prefix, Q, Ospf: OspfImpl> NetworkFormatter<'a, 'n, P, Q, Ospf>
                     r.fmt(net)
                 )
             }
+            DeviceError::VrfNotFound(name) => format!("VRF {name} does not exist!"),
         }
     }
 }
@@ -973,3 +1066,117 @@ impl<'a, 'n, P: Prefix, Q, Ospf: OspfImpl<Process = LocalOspfProcess>>
         OspfProcess::fmt(self, net)
     }
 }
+
+//
+// Vendor-style show commands
+//
+
+/// Format the BGP table of `router` similar to the output of `show ip bgp` on a Cisco device. For
+/// each prefix, all known routes (after applying the incoming route-maps) are listed in the order
+/// of the BGP decision process, starting with the most preferred one. The selected route is marked
+/// with `>`, and routes learned over iBGP are marked with `i`. The metric is the MED.
+pub fn show_ip_bgp<P: Prefix, Q, Ospf: OspfImpl>(
+    net: &Network<P, Q, Ospf>,
+    router: RouterId,
+) -> Result<String, NetworkError> {
+    let bgp = &net.get_internal_router(router)?.bgp;
+    let prefixes: BTreeSet<P> = bgp
+        .get_rib_in()
+        .keys()
+        .chain(bgp.get_rib().keys())
+        .copied()
+        .collect();
+
+    let mut s = format!(
+        "BGP table of {}\nStatus codes: * valid, > best, i - internal\n\n",
+        router.fmt(net)
+    );
+    writeln!(
+        &mut s,
+        "   {:<18} {:<18} {:>6} {:>6} {:>6} Path",
+        "Network", "Next Hop", "Metric", "LocPrf", "Weight"
+    )
+    .unwrap();
+    for prefix in prefixes {
+        let best = bgp.get_exact(prefix);
+        for (i, entry) in bgp.rank_routes(prefix).into_iter().enumerate() {
+            let route = entry.route.clone_default();
+            writeln!(
+                &mut s,
+                "*{}{}{:<18} {:<18} {:>6} {:>6} {:>6} {}",
+                if Some(&entry) == best { ">" } else { " " },
+                if entry.from_type.is_ibgp() { "i" } else { " " },
+                if i == 0 {
+                    prefix.to_string()
+                } else {
+                    String::new()
+                },
+                route.next_hop.fmt(net),
+                route.med.unwrap_or_default(),
+                route.local_pref.unwrap_or_default(),
+                entry.weight,
+                route
+                    .as_path
+                    .iter()
+                    .map(|x| x.0.to_string())
+                    .chain(Some("i".to_string()))
+                    .join(" "),
+            )
+            .unwrap();
+        }
+    }
+    Ok(s)
+}
+
+/// Format the forwarding table of `router` similar to the output of `show ip route` on a Cisco
+/// device. Each prefix is listed with its code (`B` for BGP, `S` for static routes), the
+/// administrative distance and metric (the MED for BGP routes), and all next-hops.
+pub fn show_ip_route<P: Prefix, Q, Ospf: OspfImpl>(
+    net: &Network<P, Q, Ospf>,
+    router: RouterId,
+) -> Result<String, NetworkError> {
+    let r = net.get_internal_router(router)?;
+    let prefixes: BTreeSet<P> =
+        r.sr.get_table()
+            .keys()
+            .chain(r.bgp.get_rib().keys())
+            .copied()
+            .collect();
+
+    let mut s = format!(
+        "Routing table of {}\nCodes: B - BGP, S - static\n\n",
+        router.fmt(net)
+    );
+    for prefix in prefixes {
+        let (code, distance, metric) = match (r.sr.get_exact(prefix), r.bgp.get_exact(prefix)) {
+            (Some(StaticRoute::Drop), _) => {
+                writeln!(&mut s, "S     {prefix} is directly connected, Null0").unwrap();
+                continue;
+            }
+            (Some(_), _) => ("S", 1, 0),
+            (None, Some(e)) if e.from_type.is_ebgp() => ("B", 20, e.route.med.unwrap_or(0)),
+            (None, Some(e)) => ("B", 200, e.route.med.unwrap_or(0)),
+            (None, None) => continue,
+        };
+        let nhs = r.get_next_hop(prefix);
+        if nhs.is_empty() {
+            continue;
+        }
+        let head = format!("{code}     {prefix} [{distance}/{metric}]");
+        for (i, nh) in nhs.into_iter().enumerate() {
+            if i == 0 {
+                writeln!(&mut s, "{head} via {}", nh.fmt(net)).unwrap();
+            } else {
+                writeln!(
+                    &mut s,
+                    "{:width$} via {}",
+                    "",
+                    nh.fmt(net),
+                    width = head.len()
+                )
+                .unwrap();
+            }
+        }
+    }
+    Ok(s)
+}
@@ -23,10 +23,14 @@ use crate::{
     record::FwDelta,
     types::{NetworkError, Prefix, PrefixMap, RouterId, SimplePrefix, SinglePrefix},
 };
+use ipnet::Ipv4Net;
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    hash::{Hash, Hasher},
+};
 
 lazy_static! {
     static ref EMPTY_SET: HashSet<RouterId> = HashSet::new();
@@ -41,12 +45,22 @@ lazy_static! {
 /// We use indices to refer to specific routers (their ID), and to prefixes. This improves
 /// performance. However, we know that the network cannot delete any router, so the generated
 /// routers will have monotonically increasing indices. Thus, we simply use that.
+///
+/// Two forwarding states are equal (and hash to the same value) if they contain the same
+/// forwarding entries, irrespective of how they were computed. This allows using the forwarding
+/// state as a key to deduplicate equivalent outcomes, e.g., of a [`crate::sweep`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ForwardingState<P: Prefix> {
     /// The forwarding state
     pub(crate) state: HashMap<RouterId, P::Map<Vec<RouterId>>>,
     /// The reversed forwarding state.
     pub(crate) reversed: HashMap<RouterId, P::Map<HashSet<RouterId>>>,
+    /// Backup next-hops of internal routers (BGP Prefix-Independent Convergence).
+    #[serde(default)]
+    pub(crate) backup: HashMap<RouterId, P::Map<RouterId>>,
+    /// Number of active members of each link bundle (in both directions).
+    #[serde(default)]
+    pub(crate) bundles: HashMap<RouterId, HashMap<RouterId, usize>>,
     /// Cached paths.
     #[serde(skip)]
     pub(self) cache: HashMap<RouterId, P::Map<CacheResult>>,
@@ -79,6 +93,25 @@ impl<P: Prefix> PartialEq for ForwardingState<P> {
     }
 }
 
+impl<P: Prefix> Eq for ForwardingState<P> {}
+
+impl<P: Prefix> Hash for ForwardingState<P> {
+    /// Hash the forwarding state independently of the order of the underlying maps. Only non-empty
+    /// forwarding entries are considered, which is consistent with `PartialEq`.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.state
+            .iter()
+            .flat_map(|(r, table)| {
+                table
+                    .iter()
+                    .filter(|(_, nhs)| !nhs.is_empty())
+                    .map(move |(p, nhs)| (*r, *p, nhs))
+            })
+            .sorted_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)))
+            .for_each(|entry| entry.hash(state));
+    }
+}
+
 impl<P: Prefix> ForwardingState<P> {
     /// Extracts the forwarding state from the network.
     pub fn from_net<Q, Ospf: OspfImpl>(net: &Network<P, Q, Ospf>) -> Self {
@@ -87,12 +120,18 @@ impl<P: Prefix> ForwardingState<P> {
             HashMap::with_capacity(net.num_devices());
         let mut reversed: HashMap<RouterId, P::Map<HashSet<RouterId>>> =
             HashMap::with_capacity(net.num_devices());
+        let mut backup: HashMap<RouterId, P::Map<RouterId>> = HashMap::new();
 
         // initialize state
         for r in net.internal_routers() {
             let rid = r.router_id();
             let fib = r.get_fib();
 
+            let backup_fib = r.get_backup_fib();
+            if backup_fib.iter().next().is_some() {
+                backup.insert(rid, backup_fib);
+            }
+
             for (prefix, nhs) in fib.iter() {
                 for nh in nhs {
                     reversed
@@ -106,6 +145,13 @@ impl<P: Prefix> ForwardingState<P> {
             state.insert(rid, fib);
         }
 
+        let mut bundles: HashMap<RouterId, HashMap<RouterId, usize>> = HashMap::new();
+        for ((a, b), bundle) in net.link_bundles.iter() {
+            let members = bundle.num_active();
+            bundles.entry(*a).or_default().insert(*b, members);
+            bundles.entry(*b).or_default().insert(*a, members);
+        }
+
         // collect the external routers, and chagne the forwarding state such that we remember which
         // prefix they know a route to.
         for r in net.external_routers() {
@@ -123,6 +169,8 @@ impl<P: Prefix> ForwardingState<P> {
         Self {
             state,
             reversed,
+            backup,
+            bundles,
             cache: Default::default(),
         }
     }
@@ -135,6 +183,8 @@ impl<P: Prefix> ForwardingState<P> {
         Self {
             state,
             reversed,
+            backup: Default::default(),
+            bundles: Default::default(),
             cache: Default::default(),
         }
     }
@@ -151,6 +201,23 @@ impl<P: Prefix> ForwardingState<P> {
         self.get_paths_recursive(prefix, source, &mut visited, &mut path)
     }
 
+    /// Returns the physical links traversed by traffic from `source` towards `prefix`, one list of
+    /// links for each forwarding path. The forwarding state resolves the BGP next-hop of each
+    /// router over the IGP, so for an iBGP-learned route, the path follows the IGP towards the
+    /// egress router and then leaves the network over the link to the external neighbor. This
+    /// function returns an error if the traffic encounters a forwarding loop or a black hole.
+    pub fn physical_path(
+        &mut self,
+        source: RouterId,
+        prefix: P,
+    ) -> Result<Vec<Vec<(RouterId, RouterId)>>, NetworkError> {
+        Ok(self
+            .get_paths(source, prefix)?
+            .into_iter()
+            .map(|path| path.into_iter().tuple_windows().collect())
+            .collect())
+    }
+
     /// Returns the set of forwarding paths from the source router to a specific prefix.
     #[inline(always)]
     #[deprecated(note = "use get_paths instead!")]
@@ -339,6 +406,389 @@ impl<P: Prefix> ForwardingState<P> {
         result
     }
 
+    /// Get all forwarding entries (router and prefix) whose next-hops differ between `self` and
+    /// `other`. In contrast to `diff`, this function is available for all prefix types.
+    pub fn changed_entries(&self, other: &Self) -> HashSet<(RouterId, P)> {
+        let mut result = HashSet::new();
+        let routers = self.state.keys().chain(other.state.keys()).unique();
+        for router in routers {
+            let self_state = self.state.get(router);
+            let other_state = other.state.get(router);
+            let prefixes = self_state
+                .into_iter()
+                .flat_map(|x| x.keys())
+                .chain(other_state.into_iter().flat_map(|x| x.keys()))
+                .unique();
+            for prefix in prefixes {
+                let self_target = self_state
+                    .and_then(|x| x.get(prefix))
+                    .map(|x| x.as_slice())
+                    .unwrap_or_default();
+                let other_target = other_state
+                    .and_then(|x| x.get(prefix))
+                    .map(|x| x.as_slice())
+                    .unwrap_or_default();
+                if self_target != other_target {
+                    result.insert((*router, *prefix));
+                }
+            }
+        }
+        result
+    }
+
+    /// Get the backup next-hop of a router for a specific prefix, used for BGP Prefix-Independent
+    /// Convergence. The backup next-hop is the IGP next-hop towards the BGP next-hop of the
+    /// second-best route (see [`Network::local_protect`]). Returns `None` if the router does not
+    /// know any backup route.
+    pub fn backup_next_hop(&self, router: RouterId, prefix: P) -> Option<RouterId> {
+        self.backup
+            .get(&router)
+            .and_then(|fib| fib.get(&prefix))
+            .copied()
+    }
+
+    /// Get the share of traffic towards `prefix` that `router` sends to each of its next-hops.
+    /// The traffic is split equally among all active members of the links towards the next-hops
+    /// (see [`Network::add_link_bundle`]), where a regular link has exactly one member. The result
+    /// is empty if the router drops the traffic, or if the router is an external router that
+    /// advertises the prefix.
+    pub fn get_traffic_share(&self, router: RouterId, prefix: P) -> Vec<(RouterId, f64)> {
+        let next_hops = self.get_next_hops(router, prefix);
+        let members = |nh: &RouterId| {
+            self.bundles
+                .get(&router)
+                .and_then(|x| x.get(nh))
+                .copied()
+                .unwrap_or(1)
+        };
+        let total: usize = next_hops.iter().map(members).sum();
+        if total == 0 {
+            return Vec::new();
+        }
+        next_hops
+            .iter()
+            .map(|nh| (*nh, members(nh) as f64 / total as f64))
+            .collect()
+    }
+
+    /// Compute the load of each directed link, given a set of traffic `demands`. Each demand
+    /// `(source, prefix, volume)` is routed along the forwarding paths from `source` towards
+    /// `prefix`, and split among the next-hops according to [`ForwardingState::get_traffic_share`].
+    /// The result maps each directed link `(a, b)` that carries any traffic to the sum of the load
+    /// of all demands. Traffic that is dropped in a black hole only loads the links up to that
+    /// router. This function returns an error if the traffic of any demand encounters a forwarding
+    /// loop.
+    pub fn link_loads(
+        &mut self,
+        demands: &[(RouterId, P, f64)],
+    ) -> Result<HashMap<(RouterId, RouterId), f64>, NetworkError> {
+        let mut loads: HashMap<(RouterId, RouterId), f64> = HashMap::new();
+        for (source, prefix, volume) in demands.iter().copied() {
+            match self.get_paths(source, prefix) {
+                Ok(_) | Err(NetworkError::ForwardingBlackHole(_)) => {}
+                Err(e) => return Err(e),
+            }
+
+            // count the number of predecessors of each router on the paths from source.
+            let nodes = self.get_nodes_along_paths(source, prefix);
+            let mut num_prev: HashMap<RouterId, usize> = HashMap::new();
+            for r in nodes.iter() {
+                for nh in self.get_next_hops(*r, prefix) {
+                    *num_prev.entry(*nh).or_default() += 1;
+                }
+            }
+
+            // push the traffic along the paths in topological order
+            let mut traffic: HashMap<RouterId, f64> = HashMap::from([(source, volume)]);
+            let mut to_visit = vec![source];
+            while let Some(r) = to_visit.pop() {
+                let incoming = traffic.get(&r).copied().unwrap_or_default();
+                for (nh, share) in self.get_traffic_share(r, prefix) {
+                    *loads.entry((r, nh)).or_default() += incoming * share;
+                    *traffic.entry(nh).or_default() += incoming * share;
+                    let prev = num_prev.entry(nh).or_default();
+                    *prev -= 1;
+                    if *prev == 0 {
+                        to_visit.push(nh);
+                    }
+                }
+            }
+        }
+        Ok(loads)
+    }
+
+    /// Returns all forwarding entries of `router`, i.e., each prefix together with its (sorted)
+    /// next-hops. Entries that drop the traffic have no next-hop. The entries are sorted by their
+    /// prefix.
+    pub fn fib_entries(&self, router: RouterId) -> Vec<(P, Vec<RouterId>)> {
+        self.state
+            .get(&router)
+            .map(|fib| {
+                fib.keys()
+                    .map(|p| {
+                        (
+                            *p,
+                            self.get_next_hops(router, *p)
+                                .iter()
+                                .copied()
+                                .sorted()
+                                .collect(),
+                        )
+                    })
+                    .sorted()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Estimate how many forwarding entries of `router` could be removed without changing the
+    /// forwarding behavior under longest prefix matching (see [`ForwardingState::fib_entries`]).
+    /// The FIB is compressed greedily (inspired by ORTC), by repeating the following steps until
+    /// nothing changes:
+    ///
+    /// 1. An entry is removed if the most specific entry covering it has the same next-hops.
+    /// 2. Two sibling entries (e.g., two adjacent /24 prefixes) with the same next-hops are
+    ///    replaced by their parent prefix (e.g., the /23 prefix), if the parent has no entry.
+    ///
+    /// Siblings are only merged if the prefix type can represent the parent prefix, and if it
+    /// contains both siblings. For prefix types without a hierarchy (like `SimplePrefix`), only
+    /// covered entries are removed. The result is a conservative estimate, i.e., an optimal
+    /// algorithm may be able to remove more entries.
+    pub fn compressible_entries(&self, router: RouterId) -> usize {
+        let entries = self.fib_entries(router);
+        let mut fib: BTreeMap<P, Vec<RouterId>> = entries.iter().cloned().collect();
+
+        loop {
+            let mut changed = false;
+
+            // remove entries that are covered by a less-specific entry with the same next-hops.
+            let prefixes: Vec<P> = fib.keys().copied().collect();
+            for p in prefixes {
+                let parent = fib
+                    .keys()
+                    .filter(|q| **q != p && q.contains(&p))
+                    .max_by_key(|q| Into::<Ipv4Net>::into(**q).prefix_len());
+                if parent.is_some_and(|q| fib.get(q) == fib.get(&p)) {
+                    fib.remove(&p);
+                    changed = true;
+                }
+            }
+
+            // merge siblings with the same next-hops into their parent.
+            let prefixes: Vec<P> = fib.keys().copied().collect();
+            for p in prefixes {
+                let Some(nhs) = fib.get(&p) else {
+                    continue;
+                };
+                let net: Ipv4Net = p.into();
+                let Some(parent_net) = net.supernet() else {
+                    continue;
+                };
+                let Some(sibling_net) = parent_net
+                    .subnets(net.prefix_len())
+                    .into_iter()
+                    .flatten()
+                    .find(|x| *x != net)
+                else {
+                    continue;
+                };
+                let sibling = P::from(sibling_net);
+                let parent = P::from(parent_net);
+                if net.trunc() == net
+                    && Into::<Ipv4Net>::into(sibling) == sibling_net
+                    && parent.contains(&p)
+                    && parent.contains(&sibling)
+                    && !fib.contains_key(&parent)
+                    && fib.get(&sibling) == Some(nhs)
+                {
+                    let nhs = fib.remove(&p).unwrap();
+                    fib.remove(&sibling);
+                    fib.insert(parent, nhs);
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        entries.len() - fib.len()
+    }
+
+    /// Compute whether the forwarding paths from `source` towards `prefix_a` and the forwarding
+    /// paths towards `prefix_b` share any router or link. If `source` load-balances traffic, the
+    /// result contains all routers and links that are shared by any pair of paths. The `source`
+    /// itself is never reported as a shared router. This function returns an error if the traffic
+    /// towards either prefix encounters a forwarding loop or a black hole.
+    pub fn paths_disjoint(
+        &mut self,
+        source: RouterId,
+        prefix_a: P,
+        prefix_b: P,
+    ) -> Result<Disjointness, NetworkError> {
+        self.paths_srlg_disjoint(source, prefix_a, prefix_b, &HashMap::new())
+    }
+
+    /// Compute whether the forwarding paths from `source` towards `prefix_a` and `prefix_b` are
+    /// disjoint, like [`ForwardingState::paths_disjoint`], but also consider the shared risk link
+    /// groups (SRLGs) of each link (see
+    /// [`Network::get_srlgs`](crate::network::Network::get_srlgs)). Two paths that traverse
+    /// different links in the same SRLG are not truly disjoint, as both links may fail together.
+    /// Such groups are reported in [`Disjointness::shared_srlgs`]. The links in `srlgs` must be
+    /// stored with the smaller router-id first.
+    pub fn paths_srlg_disjoint(
+        &mut self,
+        source: RouterId,
+        prefix_a: P,
+        prefix_b: P,
+        srlgs: &HashMap<(RouterId, RouterId), BTreeSet<u32>>,
+    ) -> Result<Disjointness, NetworkError> {
+        let paths_a = self.get_paths(source, prefix_a)?;
+        let paths_b = self.get_paths(source, prefix_b)?;
+
+        let nodes = |paths: &Vec<Vec<RouterId>>| -> BTreeSet<RouterId> {
+            paths
+                .iter()
+                .flatten()
+                .copied()
+                .filter(|r| *r != source)
+                .collect()
+        };
+        let links = |paths: &Vec<Vec<RouterId>>| -> BTreeSet<(RouterId, RouterId)> {
+            paths
+                .iter()
+                .flat_map(|path| path.iter().copied().tuple_windows())
+                .collect()
+        };
+        let groups = |paths: &Vec<Vec<RouterId>>| -> BTreeSet<u32> {
+            links(paths)
+                .into_iter()
+                .filter_map(|(a, b)| srlgs.get(&(a.min(b), a.max(b))))
+                .flatten()
+                .copied()
+                .collect()
+        };
+
+        Ok(Disjointness {
+            shared_nodes: nodes(&paths_a)
+                .intersection(&nodes(&paths_b))
+                .copied()
+                .collect(),
+            shared_links: links(&paths_a)
+                .intersection(&links(&paths_b))
+                .copied()
+                .collect(),
+            shared_srlgs: groups(&paths_a)
+                .intersection(&groups(&paths_b))
+                .copied()
+                .collect(),
+        })
+    }
+
+    /// Compute the maximum number of link-disjoint forwarding paths from `source` to `egress` for
+    /// traffic towards `prefix`. Only links on which `source` or any downstream router actually
+    /// forwards traffic towards `prefix` are considered, so the result is larger than 1 only if
+    /// routers load-balance traffic. The result is computed as the max-flow in the forwarding
+    /// graph, in which every link has unit capacity. See
+    /// [`Network::min_cut_to_prefix`](crate::network::Network::min_cut_to_prefix) for the number of
+    /// link-disjoint paths towards any egress. The function returns `0` if `egress` is not on any
+    /// forwarding path from `source`, or if `source` and `egress` are the same router.
+    pub fn max_disjoint_paths(&self, source: RouterId, prefix: P, egress: RouterId) -> usize {
+        if source == egress {
+            return 0;
+        }
+        self.max_flow(source, prefix, |r| r == egress)
+    }
+
+    /// Compute the minimum number of links that must fail to disconnect `source` from all
+    /// egresses of `prefix` (i.e., from all external routers that receive the traffic) in the
+    /// forwarding graph of `prefix`. This is the number of link-disjoint forwarding paths from
+    /// `source` towards any of the egresses.
+    pub(crate) fn min_cut_to_prefix(&self, source: RouterId, prefix: P) -> usize {
+        let is_egress = |r: RouterId| {
+            self.state
+                .get(&r)
+                .and_then(|fib| fib.get(&prefix))
+                .is_some_and(|nhs| nhs == &[*TO_DST])
+        };
+        if is_egress(source) {
+            return 0;
+        }
+        self.max_flow(source, prefix, is_egress)
+    }
+
+    /// Compute the max-flow from `source` to any router for which `is_sink` returns `true` in the
+    /// forwarding graph of `prefix`, in which every link has unit capacity.
+    fn max_flow(&self, source: RouterId, prefix: P, is_sink: impl Fn(RouterId) -> bool) -> usize {
+        // flow along each directed link of the forwarding graph.
+        let mut flow: HashMap<(RouterId, RouterId), i32> = HashMap::new();
+        let mut paths = 0;
+        loop {
+            // find an augmenting path using BFS, either along a forwarding link with spare
+            // capacity, or backwards along a link that carries flow.
+            let mut parent: HashMap<RouterId, RouterId> = HashMap::new();
+            let mut queue = VecDeque::from([source]);
+            let mut sink = None;
+            while let Some(r) = queue.pop_front() {
+                if is_sink(r) {
+                    sink = Some(r);
+                    break;
+                }
+                let forward = self
+                    .get_next_hops(r, prefix)
+                    .iter()
+                    .copied()
+                    .filter(|n| flow.get(&(r, *n)).copied().unwrap_or_default() < 1);
+                let backward = self
+                    .get_prev_hops(r, prefix)
+                    .iter()
+                    .copied()
+                    .filter(|n| flow.get(&(*n, r)).copied().unwrap_or_default() > 0);
+                for n in forward.chain(backward).collect_vec() {
+                    if n == source || parent.contains_key(&n) {
+                        continue;
+                    }
+                    parent.insert(n, r);
+                    queue.push_back(n);
+                }
+            }
+
+            // augment the flow along that path
+            let Some(mut r) = sink else {
+                break;
+            };
+            while let Some(&p) = parent.get(&r) {
+                if flow.get(&(r, p)).copied().unwrap_or_default() > 0 {
+                    *flow.entry((r, p)).or_default() -= 1;
+                } else {
+                    *flow.entry((p, r)).or_default() += 1;
+                }
+                r = p;
+            }
+            paths += 1;
+        }
+
+        paths
+    }
+
+    /// Returns all known prefixes (i.e., prefixes with a forwarding entry on any router) towards
+    /// which `router` has no valid forwarding path, i.e., for which traffic from `router`
+    /// encounters a black hole or a forwarding loop. The result is sorted. Paths are cached across
+    /// prefixes and routers, so computing the list for many routers only traverses each forwarding
+    /// entry once.
+    pub fn unreachable_prefixes(&mut self, router: RouterId) -> Vec<P> {
+        let prefixes: BTreeSet<P> = self
+            .state
+            .values()
+            .flat_map(|fib| fib.keys().copied())
+            .collect();
+        prefixes
+            .into_iter()
+            .filter(|p| self.get_paths(router, *p).is_err())
+            .collect()
+    }
+
     /// Returns `true` if the router drops packets for that destination.
     pub fn is_black_hole(&self, router: RouterId, prefix: P) -> bool {
         self.get_next_hops(router, prefix).is_empty()
@@ -489,6 +939,38 @@ impl ForwardingState<SimplePrefix> {
     }
 }
 
+/// Routers, links, and shared risk link groups that are shared by the forwarding paths towards two
+/// prefixes, computed by [`ForwardingState::paths_disjoint`] or
+/// [`ForwardingState::paths_srlg_disjoint`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Disjointness {
+    /// Routers (other than the source) that are traversed towards both prefixes.
+    pub shared_nodes: BTreeSet<RouterId>,
+    /// Directed links that are traversed towards both prefixes.
+    pub shared_links: BTreeSet<(RouterId, RouterId)>,
+    /// Shared risk link groups that contain links traversed towards both prefixes. This is only
+    /// computed by [`ForwardingState::paths_srlg_disjoint`].
+    #[serde(default)]
+    pub shared_srlgs: BTreeSet<u32>,
+}
+
+impl Disjointness {
+    /// Returns `true` if the paths do not share any router (other than the source).
+    pub fn is_node_disjoint(&self) -> bool {
+        self.shared_nodes.is_empty()
+    }
+
+    /// Returns `true` if the paths do not share any link.
+    pub fn is_link_disjoint(&self) -> bool {
+        self.shared_links.is_empty()
+    }
+
+    /// Returns `true` if the paths do not share any link, nor any shared risk link group.
+    pub fn is_srlg_disjoint(&self) -> bool {
+        self.is_link_disjoint() && self.shared_srlgs.is_empty()
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 enum CacheResult {
     Path(Vec<Vec<RouterId>>),
@@ -632,6 +1114,8 @@ mod test {
         ForwardingState {
             state,
             reversed,
+            backup: Default::default(),
+            bundles: Default::default(),
             cache: Default::default(),
         }
     }
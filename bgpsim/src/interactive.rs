@@ -14,7 +14,9 @@
 // limitations under the License.
 
 //! This module contains an extension trait that allows you to interact with the simulator on a
-//! per-message level.
+//! per-message level. The submodule [`repl`] provides a simple command loop on top of it.
+
+pub mod repl;
 
 use log::debug;
 
@@ -168,25 +170,7 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> InteractiveNetwork<P, Q, Ospf>
     fn simulate_step(
         &mut self,
     ) -> Result<Option<(StepUpdate<P>, Event<P, Q::Priority>)>, NetworkError> {
-        if let Some(event) = self.queue.pop() {
-            // log the job
-            log::trace!("{}", event.fmt(self));
-            // execute the event
-            let (step_update, events) = match self
-                .routers
-                .get_mut(&event.router())
-                .ok_or(NetworkError::DeviceNotFound(event.router()))?
-            {
-                NetworkDevice::InternalRouter(r) => r.handle_event(event.clone()),
-                NetworkDevice::ExternalRouter(r) => r.handle_event(event.clone()),
-            }?;
-
-            self.enqueue_events(events);
-
-            Ok(Some((step_update, event)))
-        } else {
-            Ok(None)
-        }
+        self.process_next_event(Event::clone)
     }
 
     fn queue(&self) -> &Q {
@@ -208,8 +192,8 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> InteractiveNetwork<P, Q, Ospf>
                     }
                     remaining_iter = Some(rem - 1);
                 }
-                let step_update = self.simulate_step()?;
-                if matches!(step_update, Some((_, Event::Ospf { .. }))) {
+                let is_ospf = self.process_next_event(|e| matches!(e, Event::Ospf { .. }))?;
+                if matches!(is_ospf, Some((_, true))) {
                     // OSPF event received! Check the BGP session state
                     self.refresh_bgp_sessions()?;
                 }
@@ -218,8 +202,9 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> InteractiveNetwork<P, Q, Ospf>
             // trigger the next timeout event if it exists.
             let trigger_router = self.trigger_timeout()?;
 
-            // if no timeout was triggered, break out of the loop. We are converged!
-            if trigger_router.is_none() {
+            // if no timeout was triggered and no link recovers, break out of the loop. We are
+            // converged!
+            if trigger_router.is_none() && !self.recover_scheduled_links(true)? {
                 break 'timeout;
             }
         }
@@ -242,6 +227,8 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> InteractiveNetwork<P, Q, Ospf>
         'timeout: loop {
             // While there are events in the queue
             while let Some(event) = self.queue_mut().pop() {
+                self.processed_events += 1;
+                self.recover_scheduled_links(false)?;
                 let event_clone = event.clone();
 
                 // Straddle the trigger_event function with the pre- and post-event hooks
@@ -263,8 +250,9 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> InteractiveNetwork<P, Q, Ospf>
             // trigger the next timeout event if it exists.
             let trigger_router = self.trigger_timeout()?;
 
-            // if no timeout was triggered, break out of the loop. We are converged!
-            if trigger_router.is_none() {
+            // if no timeout was triggered and no link recovers, break out of the loop. We are
+            // converged!
+            if trigger_router.is_none() && !self.recover_scheduled_links(true)? {
                 break 'timeout;
             }
         }
@@ -342,6 +330,60 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> InteractiveNetwork<P, Q, Ospf>
     }
 }
 
+impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Network<P, Q, Ospf> {
+    /// Pop and execute the next event in the queue. The function `keep` extracts from the event
+    /// whatever the caller needs before it is handed over to the router. This way, `simulate`
+    /// does not need to clone every event only to check its type.
+    fn process_next_event<T>(
+        &mut self,
+        keep: impl FnOnce(&Event<P, Q::Priority>) -> T,
+    ) -> Result<Option<(StepUpdate<P>, T)>, NetworkError> {
+        if self.queue.is_empty() {
+            // advance the logical time to the next link recovery (if any).
+            self.recover_scheduled_links(true)?;
+        }
+        if let Some(mut event) = self.queue.pop() {
+            self.processed_events += 1;
+            // recover temporarily failed links whose outage is over.
+            self.recover_scheduled_links(false)?;
+            // deliver the latest update if more updates were coalesced into this one (MRAI)
+            if let Event::Bgp { src, dst, e, .. } = &mut event {
+                if let Some(latest) = self.mrai_pending.remove(&(*src, *dst, e.prefix())) {
+                    *e = latest;
+                }
+            }
+            let kept = keep(&event);
+            let router = event.router();
+            // hold back the event if the router is frozen
+            if self.frozen.contains_key(&router) {
+                log::trace!("hold back {}", event.fmt(self));
+                let held = event.with_priority(());
+                self.frozen.entry(router).or_default().push(held);
+                return Ok(Some((StepUpdate::default(), kept)));
+            }
+            // log the job
+            log::trace!("{}", event.fmt(self));
+            let observed = self.observe_best_routes([router], event.prefix());
+            // execute the event
+            let (step_update, events) = match self
+                .routers
+                .get_mut(&router)
+                .ok_or(NetworkError::DeviceNotFound(router))?
+            {
+                NetworkDevice::InternalRouter(r) => r.handle_event(event),
+                NetworkDevice::ExternalRouter(r) => r.handle_event(event),
+            }?;
+
+            self.emit_best_route_changes(observed);
+            self.enqueue_events(events);
+
+            Ok(Some((step_update, kept)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
 /// Builder interface to partially clone the source network while moving values from the conquered
 /// network. most of the functions in this structure are `unsafe`, because the caller must guarantee
 /// that the source and the conquered network share the exact same state for those values that you
@@ -507,6 +549,9 @@ impl<'a, P: Prefix, Q> PartialClone<'a, P, Q> {
         } else {
             new.queue.clone_from(&source.queue);
         }
+        new.mrai_pending.clone_from(&source.mrai_pending);
+        new.router_data.clone_from(&source.router_data);
+        new.link_data.clone_from(&source.link_data);
 
         // handle all external routers
         for r in new.external_routers_mut() {
@@ -535,6 +580,40 @@ impl<'a, P: Prefix, Q> PartialClone<'a, P, Q> {
                 r.bgp
                     .route_maps_out
                     .clone_from(&r_source.bgp.route_maps_out);
+                r.bgp
+                    .distribute_lists_in
+                    .clone_from(&r_source.bgp.distribute_lists_in);
+                r.bgp
+                    .distribute_lists_out
+                    .clone_from(&r_source.bgp.distribute_lists_out);
+                r.bgp.rpki.clone_from(&r_source.bgp.rpki);
+                r.bgp.local_as.clone_from(&r_source.bgp.local_as);
+                r.bgp.allowas_in.clone_from(&r_source.bgp.allowas_in);
+                r.bgp.route_reflector = r_source.bgp.route_reflector;
+                r.bgp.as_path_ignore = r_source.bgp.as_path_ignore;
+                r.bgp.prefer_oldest = r_source.bgp.prefer_oldest;
+                r.bgp
+                    .next_hop_resolution
+                    .clone_from(&r_source.bgp.next_hop_resolution);
+                r.bgp.recursion_limit = r_source.bgp.recursion_limit;
+                r.bgp
+                    .no_next_hop_self
+                    .clone_from(&r_source.bgp.no_next_hop_self);
+                r.bgp
+                    .graceful_shutdown
+                    .clone_from(&r_source.bgp.graceful_shutdown);
+                r.bgp.as_path_lists.clone_from(&r_source.bgp.as_path_lists);
+                r.bgp
+                    .attribute_propagation
+                    .clone_from(&r_source.bgp.attribute_propagation);
+                r.bgp.bgp_ids.clone_from(&r_source.bgp.bgp_ids);
+                r.bgp
+                    .session_defaults
+                    .clone_from(&r_source.bgp.session_defaults);
+                r.bgp
+                    .session_options
+                    .clone_from(&r_source.bgp.session_options);
+                r.bgp.mrai.clone_from(&r_source.bgp.mrai);
             }
 
             if !self.reuse_igp_state {
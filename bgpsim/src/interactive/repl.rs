@@ -0,0 +1,288 @@
+// BgpSim: BGP Network Simulator written in Rust
+// Copyright 2022-2024 Tibor Schneider <sctibor@ethz.ch>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This module contains a simple command loop to explore a network without writing code. Each
+//! line of the input is parsed into a [`Command`], which is then executed on the network using
+//! [`execute`]. The function [`run`] reads commands from any input (e.g., `stdin`) and writes the
+//! results to any output. The following commands are supported (routers are referenced by their
+//! name):
+//!
+//! - `advertise <router> <prefix> <as> [<as> ...]`: Advertise a route from an external router.
+//! - `withdraw <router> <prefix>`: Withdraw a route from an external router.
+//! - `fail <router> <router>`: Remove the link between two routers.
+//! - `step [<n>]`: Process the next `n` (default 1) events of the queue.
+//! - `trace <router> <prefix>`: Print the forwarding paths of a router towards a prefix.
+//! - `rib <router>`: Print the selected BGP routes of a router.
+//! - `check <prefix>`: Print all routers that black-hole the prefix or forward it in a loop.
+//!
+//! Set the network to [manual simulation](super::InteractiveNetwork::manual_simulation) to
+//! observe the individual events using `step`. Otherwise, each change is simulated until the
+//! network converges.
+//!
+//! ```
+//! # use bgpsim::prelude::*;
+//! # use bgpsim::interactive::repl::run;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut net: Network<SimplePrefix, _, GlobalOspf> = Network::default();
+//! let r = net.add_router("R");
+//! let e = net.add_external_router("E", 100);
+//! net.add_link(r, e)?;
+//! net.set_bgp_session(r, e, Some(BgpSessionType::EBgp))?;
+//!
+//! let input = "advertise E 100.0.0.0/24 100\ntrace R 100.0.0.0/24\n";
+//! let mut output = Vec::new();
+//! run(&mut net, input.as_bytes(), &mut output)?;
+//! assert!(String::from_utf8(output)?.contains("R -> E"));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    io::{self, BufRead, Write},
+    str::FromStr,
+};
+
+use itertools::Itertools;
+use thiserror::Error;
+
+use super::InteractiveNetwork;
+use crate::{
+    event::EventQueue,
+    formatter::NetworkFormatter,
+    network::Network,
+    ospf::OspfImpl,
+    types::{AsId, NetworkError, Prefix, PrefixMap, StepUpdate},
+};
+
+/// A single command of the command loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command<P> {
+    /// `advertise <router> <prefix> <as> [<as> ...]`: Advertise a route from an external router.
+    Advertise {
+        /// Name of the external router
+        router: String,
+        /// The advertised prefix
+        prefix: P,
+        /// The AS path of the route
+        as_path: Vec<AsId>,
+    },
+    /// `withdraw <router> <prefix>`: Withdraw a route from an external router.
+    Withdraw {
+        /// Name of the external router
+        router: String,
+        /// The withdrawn prefix
+        prefix: P,
+    },
+    /// `fail <router> <router>`: Remove the link between two routers.
+    Fail(String, String),
+    /// `step [<n>]`: Process the next `n` events of the queue.
+    Step(usize),
+    /// `trace <router> <prefix>`: Print the forwarding paths of a router towards a prefix.
+    Trace {
+        /// Name of the router
+        router: String,
+        /// The destination prefix
+        prefix: P,
+    },
+    /// `rib <router>`: Print the selected BGP routes of a router.
+    Rib(String),
+    /// `check <prefix>`: Print all routers that black-hole the prefix or forward it in a loop.
+    Check(P),
+}
+
+impl<P: Prefix> FromStr for Command<P> {
+    type Err = ReplError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut args = s.split_whitespace();
+        let Some(cmd) = args.next() else {
+            return Err(ReplError::EmptyCommand);
+        };
+        let cmd = match cmd {
+            "advertise" => Command::Advertise {
+                router: arg(&mut args, "advertise", "router")?.to_string(),
+                prefix: parse_prefix(arg(&mut args, "advertise", "prefix")?)?,
+                as_path: args
+                    .by_ref()
+                    .map(|x| parse_num(x).map(AsId))
+                    .collect::<Result<_, _>>()?,
+            },
+            "withdraw" => Command::Withdraw {
+                router: arg(&mut args, "withdraw", "router")?.to_string(),
+                prefix: parse_prefix(arg(&mut args, "withdraw", "prefix")?)?,
+            },
+            "fail" => Command::Fail(
+                arg(&mut args, "fail", "router")?.to_string(),
+                arg(&mut args, "fail", "router")?.to_string(),
+            ),
+            "step" => Command::Step(args.next().map(parse_num).transpose()?.unwrap_or(1)),
+            "trace" => Command::Trace {
+                router: arg(&mut args, "trace", "router")?.to_string(),
+                prefix: parse_prefix(arg(&mut args, "trace", "prefix")?)?,
+            },
+            "rib" => Command::Rib(arg(&mut args, "rib", "router")?.to_string()),
+            "check" => Command::Check(parse_prefix(arg(&mut args, "check", "prefix")?)?),
+            cmd => return Err(ReplError::UnknownCommand(cmd.to_string())),
+        };
+        match args.next() {
+            Some(x) => Err(ReplError::UnexpectedArgument(x.to_string())),
+            None => Ok(cmd),
+        }
+    }
+}
+
+/// Get the next argument of a command.
+fn arg<'s>(
+    args: &mut impl Iterator<Item = &'s str>,
+    cmd: &'static str,
+    name: &'static str,
+) -> Result<&'s str, ReplError> {
+    args.next().ok_or(ReplError::MissingArgument(cmd, name))
+}
+
+fn parse_prefix<P: Prefix>(s: &str) -> Result<P, ReplError> {
+    s.parse()
+        .map_err(|_| ReplError::InvalidPrefix(s.to_string()))
+}
+
+fn parse_num<T: FromStr>(s: &str) -> Result<T, ReplError> {
+    s.parse()
+        .map_err(|_| ReplError::InvalidNumber(s.to_string()))
+}
+
+/// Execute a command on the network, and return the text to print. Changes to the network are
+/// simulated only if automatic simulation is enabled.
+pub fn execute<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl>(
+    net: &mut Network<P, Q, Ospf>,
+    cmd: &Command<P>,
+) -> Result<String, ReplError> {
+    Ok(match cmd {
+        Command::Advertise {
+            router,
+            prefix,
+            as_path,
+        } => {
+            let r = net.get_router_id(router)?;
+            net.advertise_external_route(r, *prefix, as_path.clone(), None, None)?;
+            format!("{} events queued", net.queue().len())
+        }
+        Command::Withdraw { router, prefix } => {
+            let r = net.get_router_id(router)?;
+            net.withdraw_external_route(r, *prefix)?;
+            format!("{} events queued", net.queue().len())
+        }
+        Command::Fail(a, b) => {
+            let (a, b) = (net.get_router_id(a)?, net.get_router_id(b)?);
+            net.remove_link(a, b)?;
+            format!("{} events queued", net.queue().len())
+        }
+        Command::Step(n) => {
+            let mut lines = Vec::new();
+            for _ in 0..*n {
+                let Some((update, event)) = net.simulate_step()? else {
+                    lines.push("The queue is empty.".to_string());
+                    break;
+                };
+                lines.push(event.fmt(net));
+                if let StepUpdate::Single(delta) = update {
+                    lines.push(format!("  {}", delta.fmt(net)));
+                }
+            }
+            lines.join("\n")
+        }
+        Command::Trace { router, prefix } => {
+            let r = net.get_router_id(router)?;
+            net.get_forwarding_state().get_paths(r, *prefix).fmt(net)
+        }
+        Command::Rib(router) => {
+            let r = net.get_internal_router(net.get_router_id(router)?)?;
+            r.bgp
+                .get_rib()
+                .iter()
+                .sorted_by_key(|(p, _)| **p)
+                .map(|(_, entry)| entry.fmt(net))
+                .join("\n")
+        }
+        Command::Check(prefix) => {
+            let mut fw_state = net.get_forwarding_state();
+            let errors = net
+                .internal_indices()
+                .sorted()
+                .filter_map(|r| match fw_state.get_paths(r, *prefix) {
+                    Ok(_) => None,
+                    Err(e) => Some(format!("{}: {}", r.fmt(net), e.fmt(net))),
+                })
+                .collect_vec();
+            if errors.is_empty() {
+                format!("All routers reach {prefix}.")
+            } else {
+                errors.join("\n")
+            }
+        }
+    })
+}
+
+/// Read commands line by line from `input`, execute them on the network, and write the results
+/// (or errors) to `output`. Empty lines and lines starting with `#` are ignored. The loop stops at
+/// the end of the input, or at the command `exit`.
+pub fn run<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl>(
+    net: &mut Network<P, Q, Ospf>,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line == "exit" {
+            break;
+        }
+        match line.parse().and_then(|cmd| execute(net, &cmd)) {
+            Ok(s) if s.is_empty() => {}
+            Ok(s) => writeln!(output, "{s}")?,
+            Err(ReplError::Network(e)) => writeln!(output, "Error: {}", e.fmt(net))?,
+            Err(e) => writeln!(output, "Error: {e}")?,
+        }
+    }
+    Ok(())
+}
+
+/// Error while parsing or executing a command.
+#[derive(Debug, PartialEq, Error)]
+pub enum ReplError {
+    /// The line contains no command.
+    #[error("Empty command")]
+    EmptyCommand,
+    /// The command is not known.
+    #[error("Unknown command: {0}")]
+    UnknownCommand(String),
+    /// An argument of the command is missing.
+    #[error("Command {0} expects an argument {1}")]
+    MissingArgument(&'static str, &'static str),
+    /// The command got more arguments than expected.
+    #[error("Unexpected argument: {0}")]
+    UnexpectedArgument(String),
+    /// Cannot parse a prefix.
+    #[error("Invalid prefix: {0}")]
+    InvalidPrefix(String),
+    /// Cannot parse a number.
+    #[error("Invalid number: {0}")]
+    InvalidNumber(String),
+    /// Error while executing the command on the network.
+    #[error("{0}")]
+    Network(#[from] NetworkError),
+}
@@ -70,6 +70,7 @@
 //!   this crate. Enabling this significantly impact build times.
 //! - `topology_zoo`: This adds the module `topology_zoo` including a `*.graphml` parser, and a
 //!   prepared list of all Topologies in topology zoo.
+//! - `rayon`: This feature enables [`sweep::sweep`] to run independent scenarios in parallel.
 //! - `layout`: Utilities to automatically create a layout of the network.
 //!
 //! ## Example usage
@@ -216,6 +217,7 @@
 pub mod bgp;
 pub mod builder;
 pub mod config;
+mod dot;
 pub mod event;
 #[cfg(feature = "export")]
 #[cfg_attr(docsrs, doc(cfg(feature = "export")))]
@@ -232,6 +234,8 @@ pub mod record;
 pub mod route_map;
 pub mod router;
 mod serde;
+pub mod snapshot;
+pub mod sweep;
 #[cfg(feature = "topology_zoo")]
 #[cfg_attr(docsrs, doc(cfg(feature = "topology_zoo")))]
 pub mod topology_zoo;
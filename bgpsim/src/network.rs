@@ -19,25 +19,48 @@
 //! network.
 
 use crate::{
-    bgp::{BgpSessionType, BgpState, BgpStateRef},
-    config::{NetworkConfig, RouteMapEdit},
-    event::{BasicEventQueue, Event, EventQueue},
+    bgp::{
+        AttributePropagation, BgpEvent, BgpPeerGroup, BgpRibEntry, BgpRoute, BgpSessionDefaults,
+        BgpSessionOptions, BgpSessionType, BgpState, BgpStateRef, DisputeWheel, DistributeList,
+        RoaTable, RpkiPolicy, Vrf, DEFAULT_RECURSION_LIMIT,
+    },
+    builder::GaoRexfordPeerType,
+    config::{ConfigModifier, NetworkConfig, RouteMapEdit},
+    event::{
+        BasicEventQueue, ConvergenceCallback, ConvergenceCallbacks, ConvergenceEvent, Event,
+        EventQueue,
+    },
     external_router::ExternalRouter,
+    formatter::NetworkFormatter,
     forwarding_state::ForwardingState,
     interactive::InteractiveNetwork,
-    ospf::{global::GlobalOspf, LinkWeight, LocalOspf, OspfArea, OspfImpl, OspfNetwork},
-    route_map::{RouteMap, RouteMapDirection},
+    ospf::{
+        global::GlobalOspf, ExternalMetricType, LinkWeight, LocalOspf, OspfArea, OspfAreaType,
+        OspfImpl, OspfNetwork, OspfProcess,
+    },
+    policies::Policy,
+    record::EventTimeline,
+    route_map::{
+        is_community_local_pref_item, is_community_prepend_item, AsPathList, RouteMap,
+        RouteMapBuilder, RouteMapDirection, RouteMapMatchAsPath, RouteMapState,
+        COMMUNITY_LOCAL_PREF_ORDER_END, COMMUNITY_PREPEND_ORDER_END,
+    },
     router::{Router, StaticRoute},
     types::{
-        AsId, NetworkDevice, NetworkDeviceRef, NetworkError, NetworkErrorOption, PhysicalNetwork,
-        Prefix, PrefixSet, RouterId, SimplePrefix,
+        AsId, Community, DeviceError, NetworkDevice, NetworkDeviceRef, NetworkError,
+        NetworkErrorOption, PhysicalNetwork, Prefix, PrefixMap, PrefixSet, RouterId, SimplePrefix,
+        StableHasher,
     },
 };
 
+use itertools::Itertools;
 use log::*;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_with::serde_as;
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    net::Ipv4Addr,
+};
 
 static DEFAULT_STOP_AFTER: usize = 1_000_000;
 /// The AS number assigned to internal routers.
@@ -96,10 +119,50 @@ pub struct Network<
     pub(crate) stop_after: Option<usize>,
     pub(crate) queue: Q,
     pub(crate) skip_queue: bool,
+    #[serde(skip)]
+    pub(crate) callbacks: ConvergenceCallbacks<P>,
+    #[serde_as(as = "Vec<(_, _)>")]
+    #[serde(default)]
+    pub(crate) link_bundles: HashMap<(RouterId, RouterId), LinkBundle>,
+    #[serde_as(as = "Vec<(_, _)>")]
+    #[serde(default)]
+    pub(crate) frozen: HashMap<RouterId, Vec<Event<P, ()>>>,
+    /// Latest update of all BGP messages in the queue on sessions with MRAI enabled.
+    #[serde_as(as = "Vec<(_, _)>")]
+    #[serde(default)]
+    pub(crate) mrai_pending: HashMap<(RouterId, RouterId, P), BgpEvent<P>>,
+    #[serde(skip)]
+    pub(crate) event_timeline: EventTimeline,
+    /// User-defined data attached to routers.
+    #[serde_as(as = "Vec<(_, _)>")]
+    #[serde(default)]
+    pub(crate) router_data: HashMap<RouterId, serde_json::Value>,
+    /// User-defined data attached to links. The first router is always the smaller one.
+    #[serde_as(as = "Vec<(_, _)>")]
+    #[serde(default)]
+    pub(crate) link_data: HashMap<(RouterId, RouterId), serde_json::Value>,
+    /// Number of events processed since the network was created.
+    #[serde(default)]
+    pub(crate) processed_events: usize,
+    /// Links that failed temporarily, and that recover at a later logical time.
+    #[serde(default)]
+    pub(crate) scheduled_recoveries: Vec<ScheduledRecovery>,
+    /// Shared risk link groups of each link. The first router is always the smaller one.
+    #[serde_as(as = "Vec<(_, _)>")]
+    #[serde(default)]
+    pub(crate) srlgs: HashMap<(RouterId, RouterId), BTreeSet<u32>>,
+    /// Templates of all peer-groups.
+    #[serde(default)]
+    pub(crate) peer_groups: BTreeMap<String, BgpPeerGroup<P>>,
+    /// Peer-group of each session `(router, neighbor)` that was configured from a peer-group.
+    #[serde_as(as = "Vec<(_, _)>")]
+    #[serde(default)]
+    pub(crate) peer_group_members: HashMap<(RouterId, RouterId), String>,
 }
 
 impl<P: Prefix, Q: Clone, Ospf: OspfImpl> Clone for Network<P, Q, Ospf> {
-    /// Cloning the network does not clone the event history.
+    /// Cloning the network does not clone the event history, the event timeline (see
+    /// [`Network::event_timeline`]), nor the registered callbacks (see [`Network::on_event`]).
     fn clone(&self) -> Self {
         log::debug!("Cloning the network!");
         // for the new queue, remove the history of all enqueued events
@@ -112,6 +175,18 @@ impl<P: Prefix, Q: Clone, Ospf: OspfImpl> Clone for Network<P, Q, Ospf> {
             stop_after: self.stop_after,
             queue: self.queue.clone(),
             skip_queue: self.skip_queue,
+            callbacks: Default::default(),
+            link_bundles: self.link_bundles.clone(),
+            frozen: self.frozen.clone(),
+            mrai_pending: self.mrai_pending.clone(),
+            event_timeline: Default::default(),
+            router_data: self.router_data.clone(),
+            link_data: self.link_data.clone(),
+            processed_events: self.processed_events,
+            scheduled_recoveries: self.scheduled_recoveries.clone(),
+            srlgs: self.srlgs.clone(),
+            peer_groups: self.peer_groups.clone(),
+            peer_group_members: self.peer_group_members.clone(),
         }
     }
 }
@@ -134,6 +209,18 @@ impl<P: Prefix, Q, Ospf: OspfImpl> Network<P, Q, Ospf> {
             stop_after: Some(DEFAULT_STOP_AFTER),
             queue,
             skip_queue: false,
+            callbacks: Default::default(),
+            link_bundles: HashMap::new(),
+            frozen: HashMap::new(),
+            mrai_pending: HashMap::new(),
+            event_timeline: Default::default(),
+            router_data: HashMap::new(),
+            link_data: HashMap::new(),
+            processed_events: 0,
+            scheduled_recoveries: Vec::new(),
+            srlgs: HashMap::new(),
+            peer_groups: BTreeMap::new(),
+            peer_group_members: HashMap::new(),
         }
     }
 
@@ -141,7 +228,17 @@ impl<P: Prefix, Q, Ospf: OspfImpl> Network<P, Q, Ospf> {
     /// function returns the ID of the router, which can be used to reference it while confiugring
     /// the network.
     pub fn add_router(&mut self, name: impl Into<String>) -> RouterId {
-        let new_router = Router::new(name.into(), self.net.add_node(()), INTERNAL_AS);
+        let mut new_router: Router<P, Ospf::Process> =
+            Router::new(name.into(), self.net.add_node(()), INTERNAL_AS);
+        // the new router must know all configured BGP router-ids
+        if let Some(r) = self.internal_routers().next() {
+            new_router.bgp.bgp_ids.clone_from(&r.bgp.bgp_ids);
+            new_router
+                .bgp
+                .as_path_lists
+                .clone_from(&r.bgp.as_path_lists);
+            new_router.ospf_external.clone_from(&r.ospf_external);
+        }
         let router_id = new_router.router_id();
         self.routers.insert(router_id, new_router.into());
         self.ospf.add_router(router_id, true);
@@ -207,11 +304,199 @@ impl<P: Prefix, Q, Ospf: OspfImpl> Network<P, Q, Ospf> {
         BgpState::from_net(self, prefix)
     }
 
+    /// Get the timeline of all events processed during the last recorded run (see
+    /// [`RecordNetwork`](crate::record::RecordNetwork)). The timeline is empty if nothing was
+    /// recorded yet.
+    pub fn event_timeline(&self) -> &EventTimeline {
+        &self.event_timeline
+    }
+
+    /// Get the total number of BGP table entries in the network (see
+    /// [`BgpProcess::rib_size`](crate::router::BgpProcess::rib_size)) over the course of the last
+    /// recorded run (see [`RecordNetwork`](crate::record::RecordNetwork)). Each sample contains the
+    /// time (see [`TimelineEntry::time`](crate::record::TimelineEntry::time)) and the size after
+    /// processing the event at that time. The first sample at time 0 is the size right after the
+    /// recorded action. The timeline is empty if nothing was recorded yet.
+    pub fn state_size_timeline(&self) -> &[(f64, usize)] {
+        &self.event_timeline.state_size
+    }
+
+    /// Get the number of BGP table entries of each internal router (see
+    /// [`BgpProcess::rib_size`](crate::router::BgpProcess::rib_size)).
+    pub fn rib_sizes(&self) -> BTreeMap<RouterId, usize> {
+        self.internal_routers()
+            .map(|r| (r.router_id(), r.bgp.rib_size()))
+            .collect()
+    }
+
     /// Return the IGP network
     pub fn ospf_network(&self) -> &OspfNetwork<Ospf::Coordinator> {
         &self.ospf
     }
 
+    /// Compute the minimum number of link failures that disconnect `src` from all egresses of
+    /// `prefix`, i.e., from all external routers that receive traffic towards `prefix`. This is the
+    /// min-cut (computed using max-flow) in the current forwarding graph of `prefix`, in which every
+    /// link has unit capacity. Only links on which `src` or any downstream router forwards traffic
+    /// are considered, so ECMP next-hops (see [`Network::set_load_balancing`]) provide parallel
+    /// capacity, while paths that would only be used after the network reconverges do not. The
+    /// function returns `0` if traffic from `src` does not reach any egress.
+    pub fn min_cut_to_prefix(&self, src: RouterId, prefix: P) -> Result<usize, NetworkError> {
+        self.get_internal_router(src)?;
+        Ok(self.get_forwarding_state().min_cut_to_prefix(src, prefix))
+    }
+
+    /// Get the number of equal-cost next-hops that `root` has in the IGP towards the router `dst`
+    /// (ECMP width), i.e., the number of first hops of all shortest paths from `root` to `dst`.
+    /// The width does not depend on whether `root` actually load-balances traffic (see
+    /// [`Network::set_load_balancing`]). The function returns `0` if `dst` is not reachable or if
+    /// `root` and `dst` are the same router.
+    pub fn igp_ecmp_width(&self, root: RouterId, dst: RouterId) -> Result<usize, NetworkError> {
+        self.get_device(dst)?;
+        Ok(self
+            .get_internal_router(root)?
+            .ospf
+            .get_nhs_cost(dst)
+            .filter(|(_, cost)| cost.is_finite())
+            .map(|(nhs, _)| nhs.len())
+            .unwrap_or_default())
+    }
+
+    /// Compute the forwarding centrality of all routers and links, i.e., how many forwarding paths
+    /// from any internal router towards any of the given `prefixes` traverse each router and each
+    /// link. Unlike topological betweenness, this reflects the actual routing decisions of BGP
+    /// and the IGP. Each pair of source and prefix is counted at most once per router and link,
+    /// even if traffic is load-balanced over multiple paths. A router only counts as traversed if
+    /// it is neither the source nor the last router of a path, while links are counted for every
+    /// hop (including the one towards the external router). Pairs for which traffic is dropped or
+    /// runs into a forwarding loop are ignored.
+    pub fn forwarding_centrality(
+        &self,
+        prefixes: impl IntoIterator<Item = P>,
+    ) -> ForwardingCentrality {
+        let mut fw_state = self.get_forwarding_state();
+        let mut centrality = ForwardingCentrality::default();
+        for prefix in prefixes {
+            for src in self.internal_indices() {
+                let Ok(paths) = fw_state.get_paths(src, prefix) else {
+                    continue;
+                };
+                if paths.is_empty() {
+                    continue;
+                }
+                centrality.num_paths += 1;
+                let mut routers = HashSet::new();
+                let mut links = HashSet::new();
+                for path in paths.iter() {
+                    if path.len() > 2 {
+                        routers.extend(path[1..path.len() - 1].iter().copied());
+                    }
+                    links.extend(path.iter().copied().tuple_windows::<(_, _)>());
+                }
+                for r in routers {
+                    *centrality.routers.entry(r).or_default() += 1;
+                }
+                for l in links {
+                    *centrality.links.entry(l).or_default() += 1;
+                }
+            }
+        }
+        centrality
+    }
+
+    /// Compute the path of traffic from `src` towards `prefix` across AS boundaries, grouped by
+    /// AS. Each entry contains the AS number and the routers traversed in that AS, in the order in
+    /// which they are traversed. The path follows the forwarding state until traffic leaves the
+    /// network at an external router. Beyond that router, the path follows the AS path of the
+    /// route selected by the last internal router up to the origin AS. As these ASes are not
+    /// simulated, their list of routers is empty. If traffic is load-balanced, only the first
+    /// path is considered.
+    ///
+    /// If traffic from `src` cannot reach `prefix`, this function returns the corresponding
+    /// [`NetworkError::ForwardingBlackHole`] or [`NetworkError::ForwardingLoop`].
+    pub fn inter_as_path(
+        &self,
+        src: RouterId,
+        prefix: P,
+    ) -> Result<Vec<(AsId, Vec<RouterId>)>, NetworkError> {
+        self.get_device(src)?;
+        let path = self
+            .get_forwarding_state()
+            .get_paths(src, prefix)?
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+
+        let mut result: Vec<(AsId, Vec<RouterId>)> = Vec::new();
+        for r in path.iter().copied() {
+            let as_id = self.get_device(r)?.as_id();
+            match result.last_mut() {
+                Some((last, routers)) if *last == as_id => routers.push(r),
+                _ => result.push((as_id, vec![r])),
+            }
+        }
+
+        // stitch the AS path of the route used to leave the network.
+        let egress = path
+            .iter()
+            .rev()
+            .find_map(|r| self.get_internal_router(*r).ok());
+        let leaves = path
+            .last()
+            .is_some_and(|r| self.get_external_router(*r).is_ok());
+        if let (Some(egress), true) = (egress, leaves) {
+            let as_path = egress
+                .bgp
+                .get_route(prefix)
+                .map(|e| e.route.as_path.as_slice())
+                .unwrap_or_default();
+            for as_id in as_path {
+                if result.last().map(|(last, _)| last) != Some(as_id) {
+                    result.push((*as_id, Vec::new()));
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Verify that the BGP state of the network is a fixed point, i.e., that processing all routes
+    /// again would not change anything. For each internal router and each prefix, this function
+    /// checks that the Adj-RIB-In contains exactly the routes advertised by its neighbors, that the
+    /// selected route is the best route in the Adj-RIB-In, and that the Adj-RIB-Out contains the
+    /// selected route as it is exported to each peer. The function returns all pairs of router
+    /// and prefix, for which the check fails (ordered by router and prefix).
+    ///
+    /// On a converged network, this function should return an empty vector. Any BGP event that
+    /// still waits in the queue causes a mismatch at its receiver.
+    pub fn verify_fixed_point(&self) -> Vec<(RouterId, P)> {
+        let mut violations = Vec::new();
+        for r in self.internal_routers() {
+            let id = r.router_id();
+            for prefix in self.known_prefixes.iter().copied() {
+                let received = |neighbor: RouterId| match self.routers.get(&neighbor) {
+                    Some(NetworkDevice::InternalRouter(n)) => n
+                        .bgp
+                        .rib_out
+                        .get(&prefix)
+                        .and_then(|x| x.get(&id))
+                        .map(|e| e.route.clone()),
+                    Some(NetworkDevice::ExternalRouter(n))
+                        if n.get_bgp_sessions().contains(&id) =>
+                    {
+                        n.get_advertised_route(prefix).cloned()
+                    }
+                    _ => None,
+                };
+                if !r.bgp.is_fixed_point(prefix, received) {
+                    violations.push((id, prefix));
+                }
+            }
+        }
+        violations.sort();
+        violations
+    }
+
     /// Generate a forwarding state that represents the OSPF routing state. Each router with
     /// [`RouterId`] `id` advertises its own prefix `id.index().into()`. The stored paths represent
     /// the routing decisions performed by OSPF.
@@ -385,6 +670,220 @@ impl<P: Prefix, Q, Ospf: OspfImpl> Network<P, Q, Ospf> {
         self.known_prefixes.iter()
     }
 
+    /// Get all candidate routes of `router` for `prefix`, ranked by the BGP decision process from
+    /// the most preferred to the least preferred route (see
+    /// [`BgpProcess::rank_routes`](crate::router::BgpProcess::rank_routes)). The first route is the
+    /// selected one, while the remaining ones show the order of backup routes.
+    pub fn rank_routes(
+        &self,
+        router: RouterId,
+        prefix: P,
+    ) -> Result<Vec<BgpRibEntry<P>>, NetworkError> {
+        Ok(self.get_internal_router(router)?.bgp.rank_routes(prefix))
+    }
+
+    /// Compare two variants of the BGP decision process on the current RIBs, without
+    /// reconverging the network. For every internal router and every known prefix, both
+    /// comparators select the best route among all candidate routes (after applying the incoming
+    /// route-maps and computing the IGP cost, see
+    /// [`BgpProcess::get_known_routes`](crate::router::BgpProcess::get_known_routes)). A
+    /// comparator must return `Ordering::Greater` if the first route is preferred over the second
+    /// one, just like the [`Ord`] implementation of [`BgpRibEntry`].
+    ///
+    /// The function returns all `(router, prefix, route_a, route_b)` where the two comparators
+    /// select a different route, sorted by router and prefix. Routes are considered different if
+    /// they were learned from different neighbors.
+    ///
+    /// Since the network is not reconverged, the result only isolates the local effect of the
+    /// decision process. Routes that would only be advertised because of a different decision
+    /// elsewhere are not considered.
+    pub fn compare_decision_processes<A, B>(
+        &self,
+        a: A,
+        b: B,
+    ) -> Vec<(RouterId, P, BgpRibEntry<P>, BgpRibEntry<P>)>
+    where
+        A: Fn(&BgpRibEntry<P>, &BgpRibEntry<P>) -> std::cmp::Ordering,
+        B: Fn(&BgpRibEntry<P>, &BgpRibEntry<P>) -> std::cmp::Ordering,
+    {
+        let prefixes = self.known_prefixes.iter().copied().sorted().collect_vec();
+        let mut result = Vec::new();
+        for r in self.internal_routers().sorted_by_key(|r| r.router_id()) {
+            for prefix in prefixes.iter().copied() {
+                let Ok(routes) = r.bgp.get_known_routes(prefix) else {
+                    continue;
+                };
+                let best_a = routes.iter().max_by(|x, y| a(x, y));
+                let best_b = routes.iter().max_by(|x, y| b(x, y));
+                if let (Some(best_a), Some(best_b)) = (best_a, best_b) {
+                    if best_a.from_id != best_b.from_id {
+                        result.push((r.router_id(), prefix, best_a.clone(), best_b.clone()));
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Count how many routes matched each route-map item of `router` for the session with
+    /// `neighbor` in the given `direction`. The counts reflect the routes present after the last
+    /// convergence, and hence, they are recomputed whenever the network converges again. The result
+    /// contains the order of each route-map item together with its number of hits, sorted by the
+    /// order. Items that never match (e.g., because they are shadowed by a previous item) have
+    /// zero hits. See [`BgpProcess::route_map_hits`](crate::router::BgpProcess::route_map_hits).
+    pub fn route_map_hits(
+        &self,
+        router: RouterId,
+        neighbor: RouterId,
+        direction: RouteMapDirection,
+    ) -> Result<Vec<(i16, usize)>, NetworkError> {
+        Ok(self
+            .get_internal_router(router)?
+            .bgp
+            .route_map_hits(neighbor, direction))
+    }
+
+    /// Send a probe from `src` towards `prefix` that follows the current forwarding state, and
+    /// record each hop along the way. In addition to the router, each hop contains the BGP route
+    /// selected by that router (using longest-prefix matching), which shows where the decision was
+    /// made by BGP policy, and where the traffic simply follows the IGP towards the BGP next-hop.
+    /// If there are multiple forwarding paths (e.g., due to ECMP), then the probe follows the
+    /// first one (see [`ForwardingState::get_paths`]).
+    ///
+    /// This function returns an error if the probe encounters a forwarding loop or a black hole.
+    pub fn probe(
+        &self,
+        src: RouterId,
+        prefix: impl Into<P>,
+    ) -> Result<Vec<ProbeHop<P>>, NetworkError> {
+        let prefix = prefix.into();
+        self.get_device(src)?;
+        let path = self
+            .get_forwarding_state()
+            .get_paths(src, prefix)?
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        Ok(path
+            .iter()
+            .enumerate()
+            .map(|(i, router)| ProbeHop {
+                router: *router,
+                route: self
+                    .get_internal_router(*router)
+                    .ok()
+                    .and_then(|r| r.bgp.get_route(prefix))
+                    .cloned(),
+                next_hop: path.get(i + 1).copied(),
+            })
+            .collect())
+    }
+
+    /// Extract the part of the network that influences the best-path selection for `prefix`. The
+    /// subgraph is computed by tracing the advertisements of `prefix`, starting at all external
+    /// routers that advertise it, along all BGP sessions over which a route is advertised to an
+    /// internal router. A session is included even if the route is later filtered by a route-map
+    /// on that session. The result contains all routers on these sessions, the sessions
+    /// themselves, and all route-maps that are configured on them. Routers and sessions that never
+    /// see a route for `prefix` are pruned.
+    ///
+    /// The subgraph only describes the BGP dependencies in the current state. It does not include
+    /// the IGP, which may still influence the decision through the IGP cost.
+    pub fn policy_subgraph(&self, prefix: impl Into<P>) -> PolicySubgraph<P> {
+        let prefix = prefix.into();
+        let mut sub = PolicySubgraph {
+            prefix,
+            routers: HashSet::new(),
+            sessions: HashSet::new(),
+            route_maps: HashSet::new(),
+        };
+
+        // start at all egresses that advertise the prefix
+        let mut queue: VecDeque<RouterId> = self
+            .external_routers()
+            .filter(|r| r.has_active_route(prefix))
+            .map(|r| r.router_id())
+            .collect();
+        sub.routers.extend(queue.iter().copied());
+
+        while let Some(u) = queue.pop_front() {
+            // all peers to which `u` advertises a route for the prefix
+            let receivers = match self.routers.get(&u) {
+                Some(NetworkDevice::ExternalRouter(r)) => {
+                    r.get_bgp_sessions().iter().copied().collect_vec()
+                }
+                Some(NetworkDevice::InternalRouter(r)) => r
+                    .bgp
+                    .get_sessions()
+                    .keys()
+                    .filter(|v| r.bgp.would_advertise(prefix, **v))
+                    .copied()
+                    .collect_vec(),
+                None => Vec::new(),
+            };
+            for v in receivers {
+                // routes sent to external routers do not influence the decision of the network.
+                let Ok(rv) = self.get_internal_router(v) else {
+                    continue;
+                };
+                sub.sessions.insert((u, v));
+                if !rv
+                    .bgp
+                    .get_route_maps(u, RouteMapDirection::Incoming)
+                    .is_empty()
+                {
+                    sub.route_maps.insert((v, u, RouteMapDirection::Incoming));
+                }
+                if let Ok(ru) = self.get_internal_router(u) {
+                    if !ru
+                        .bgp
+                        .get_route_maps(v, RouteMapDirection::Outgoing)
+                        .is_empty()
+                    {
+                        sub.route_maps.insert((u, v, RouteMapDirection::Outgoing));
+                    }
+                }
+                if sub.routers.insert(v) {
+                    queue.push_back(v);
+                }
+            }
+        }
+
+        sub
+    }
+
+    /// Get the egress (BGP next-hop) that `router` would use for `prefix` if its current egress
+    /// failed. This is the next-hop of the best known route whose next-hop differs from the
+    /// selected one (see [`BgpProcess::get_backup_route`](crate::router::BgpProcess::get_backup_route)).
+    /// Only routes that were advertised to `router` are considered. If a route reflector only
+    /// advertises its best route, then `router` may not know any alternative, in which case this
+    /// function returns `None`.
+    pub fn alternative_egress(
+        &self,
+        router: RouterId,
+        prefix: P,
+    ) -> Result<Option<RouterId>, NetworkError> {
+        Ok(self
+            .get_internal_router(router)?
+            .bgp
+            .get_backup_route(prefix)
+            .map(|e| e.route.next_hop))
+    }
+
+    /// Statically analyze the configuration for a dispute wheel, i.e., a cyclic structure of
+    /// routing preferences that may prevent BGP from converging (like the BAD GADGET). The analysis
+    /// considers all known prefixes, and returns the first dispute wheel that it finds (see
+    /// [`DisputeWheel::from_net`]). If no dispute wheel exists, then the network is guaranteed to
+    /// converge. In contrast to [`Network::set_msg_limit`], this function does not simulate the
+    /// network, and it detects configurations that only oscillate for some message orderings.
+    pub fn detect_dispute_wheel(&self) -> Option<DisputeWheel<P>> {
+        self.known_prefixes
+            .iter()
+            .copied()
+            .sorted()
+            .find_map(|prefix| DisputeWheel::from_net(self, prefix))
+    }
+
     /// Configure the topology to pause the queue and return after a certain number of queue have
     /// been executed. The job queue will remain active. If set to None, the queue will continue
     /// running until converged.
@@ -392,6 +891,166 @@ impl<P: Prefix, Q, Ospf: OspfImpl> Network<P, Q, Ospf> {
         self.stop_after = stop_after;
     }
 
+    /// Register a callback that is invoked for every [`ConvergenceEvent`], i.e., whenever a router
+    /// selects a new best route while processing an event, a BGP session is established or breaks
+    /// down, or the IGP is recomputed after changing link weights. In contrast to the
+    /// [`record`](crate::record) module, these events describe high-level milestones of the
+    /// convergence process, e.g., to drive progress bars or visualizations.
+    ///
+    /// Callbacks are not cloned together with the network, and they are not serialized.
+    pub fn on_event(&mut self, callback: ConvergenceCallback<P>) {
+        self.callbacks.push(callback);
+    }
+
+    /// Remove all callbacks registered with [`Network::on_event`].
+    pub fn clear_event_callbacks(&mut self) {
+        self.callbacks.clear();
+    }
+
+    /// Get the link bundle between `a` and `b`, or `None` if the routers are not connected by a
+    /// link bundle (see [`Network::add_link_bundle`]).
+    pub fn get_link_bundle(&self, a: RouterId, b: RouterId) -> Option<&LinkBundle> {
+        self.link_bundles.get(&bundle_key(a, b))
+    }
+
+    /// Attach user-defined `data` to `router`, replacing any data attached before. The data is
+    /// stored as JSON, such that it is kept when cloning or serializing the network. It does not
+    /// affect the simulation. Use [`Network::get_router_data`] to read it back.
+    pub fn set_router_data<T: Serialize>(
+        &mut self,
+        router: RouterId,
+        data: &T,
+    ) -> Result<(), NetworkError> {
+        self.get_device(router)?;
+        self.router_data.insert(router, serde_json::to_value(data)?);
+        Ok(())
+    }
+
+    /// Get the user-defined data attached to `router` (see [`Network::set_router_data`]), or
+    /// `None` if no data is attached. An error is returned if the data cannot be deserialized as
+    /// `T`.
+    pub fn get_router_data<T: DeserializeOwned>(
+        &self,
+        router: RouterId,
+    ) -> Result<Option<T>, NetworkError> {
+        Ok(self
+            .router_data
+            .get(&router)
+            .map(|x| T::deserialize(x))
+            .transpose()?)
+    }
+
+    /// Remove the user-defined data attached to `router`, and return it.
+    pub fn remove_router_data(&mut self, router: RouterId) -> Option<serde_json::Value> {
+        self.router_data.remove(&router)
+    }
+
+    /// Attach user-defined `data` to the link between `a` and `b` (in both directions), replacing
+    /// any data attached before. Like [`Network::set_router_data`], the data is stored as JSON.
+    /// The data is removed together with the link.
+    pub fn set_link_data<T: Serialize>(
+        &mut self,
+        a: RouterId,
+        b: RouterId,
+        data: &T,
+    ) -> Result<(), NetworkError> {
+        self.net
+            .find_edge(a, b)
+            .ok_or(NetworkError::LinkNotFound(a, b))?;
+        self.link_data
+            .insert(bundle_key(a, b), serde_json::to_value(data)?);
+        Ok(())
+    }
+
+    /// Get the user-defined data attached to the link between `a` and `b` (see
+    /// [`Network::set_link_data`]), or `None` if no data is attached. An error is returned if the
+    /// data cannot be deserialized as `T`.
+    pub fn get_link_data<T: DeserializeOwned>(
+        &self,
+        a: RouterId,
+        b: RouterId,
+    ) -> Result<Option<T>, NetworkError> {
+        Ok(self
+            .link_data
+            .get(&bundle_key(a, b))
+            .map(|x| T::deserialize(x))
+            .transpose()?)
+    }
+
+    /// Remove the user-defined data attached to the link between `a` and `b`, and return it.
+    pub fn remove_link_data(&mut self, a: RouterId, b: RouterId) -> Option<serde_json::Value> {
+        self.link_data.remove(&bundle_key(a, b))
+    }
+
+    /// Set the shared risk link groups (SRLGs) of the link between `a` and `b` (in both
+    /// directions), replacing the groups set before. Links in the same SRLG share a common risk
+    /// (e.g., a fiber conduit), and may fail together (see [`Network::fail_srlg`]). An empty set
+    /// removes the link from all groups. Like [`Network::set_link_data`], the groups are removed
+    /// together with the link. This function returns the old groups of the link.
+    pub fn set_link_srlgs(
+        &mut self,
+        a: RouterId,
+        b: RouterId,
+        srlgs: impl IntoIterator<Item = u32>,
+    ) -> Result<BTreeSet<u32>, NetworkError> {
+        self.net
+            .find_edge(a, b)
+            .ok_or(NetworkError::LinkNotFound(a, b))?;
+        let srlgs: BTreeSet<u32> = srlgs.into_iter().collect();
+        let old = if srlgs.is_empty() {
+            self.srlgs.remove(&bundle_key(a, b))
+        } else {
+            self.srlgs.insert(bundle_key(a, b), srlgs)
+        };
+        Ok(old.unwrap_or_default())
+    }
+
+    /// Get the shared risk link groups of the link between `a` and `b` (see
+    /// [`Network::set_link_srlgs`]). The set is empty if the link is in no group, or if it does
+    /// not exist.
+    pub fn get_link_srlgs(&self, a: RouterId, b: RouterId) -> BTreeSet<u32> {
+        self.srlgs
+            .get(&bundle_key(a, b))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Get the shared risk link groups of all links, indexed by the link (with the smaller
+    /// router-id first). Links that are in no group are omitted. Use this to check whether
+    /// forwarding paths are disjoint with respect to SRLGs (see
+    /// [`ForwardingState::paths_srlg_disjoint`]).
+    pub fn get_srlgs(&self) -> &HashMap<(RouterId, RouterId), BTreeSet<u32>> {
+        &self.srlgs
+    }
+
+    /// Get all links (with the smaller router-id first) that are in the shared risk link group
+    /// `srlg`, ordered by their endpoints.
+    pub fn get_srlg_links(&self, srlg: u32) -> Vec<(RouterId, RouterId)> {
+        self.srlgs
+            .iter()
+            .filter(|(_, groups)| groups.contains(&srlg))
+            .map(|(link, _)| *link)
+            .sorted()
+            .collect()
+    }
+
+    /// Get the template of the peer-group `name`.
+    pub fn get_peer_group(&self, name: &str) -> Option<&BgpPeerGroup<P>> {
+        self.peer_groups.get(name)
+    }
+
+    /// Get the templates of all defined peer-groups.
+    pub fn get_peer_groups(&self) -> &BTreeMap<String, BgpPeerGroup<P>> {
+        &self.peer_groups
+    }
+
+    /// Get the peer-group from which the session from `router` to `neighbor` was configured.
+    pub fn get_peer_group_of(&self, router: RouterId, neighbor: RouterId) -> Option<&str> {
+        self.peer_group_members
+            .get(&(router, neighbor))
+            .map(|x| x.as_str())
+    }
+
     /// Get the link weight of a specific link (directed). This function will raise a
     /// `NetworkError::LinkNotFound` if the link does not exist.
     pub fn get_link_weight(
@@ -446,12 +1105,488 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Network<P, Q, Ospf> {
             stop_after: self.stop_after,
             queue,
             skip_queue: self.skip_queue,
+            callbacks: self.callbacks,
+            link_bundles: self.link_bundles,
+            frozen: self.frozen,
+            mrai_pending: self.mrai_pending,
+            event_timeline: self.event_timeline,
+            router_data: self.router_data,
+            link_data: self.link_data,
+            processed_events: self.processed_events,
+            scheduled_recoveries: self.scheduled_recoveries,
+            srlgs: self.srlgs,
+            peer_groups: self.peer_groups,
+            peer_group_members: self.peer_group_members,
         })
     }
 
+    /// Enumerate all configured BGP sessions in the network, each session exactly once. The
+    /// sessions are ordered by their endpoints. For sessions between a route reflector and its
+    /// client, the route reflector is the `source`, and the `session_type` is
+    /// [`BgpSessionType::IBgpClient`]. For all other sessions, `source` is the endpoint with the
+    /// smaller [`RouterId`].
+    pub fn sessions(&self) -> impl Iterator<Item = SessionInfo<P>> + '_ {
+        let is_up = |a: RouterId, b: RouterId| match self.routers.get(&a) {
+            Some(NetworkDevice::InternalRouter(r)) => r.bgp.get_session_type(b).is_some(),
+            Some(NetworkDevice::ExternalRouter(r)) => r.get_bgp_sessions().contains(&b),
+            _ => false,
+        };
+        let route_maps =
+            |a: RouterId, b: RouterId, dir: RouteMapDirection| match self.routers.get(&a) {
+                Some(NetworkDevice::InternalRouter(r)) => r.bgp.get_route_maps(b, dir).to_vec(),
+                _ => Vec::new(),
+            };
+
+        self.bgp_sessions
+            .iter()
+            .filter_map(|((source, target), ty)| {
+                let ty = (*ty)?;
+                let rev = self
+                    .bgp_sessions
+                    .get(&(*target, *source))
+                    .copied()
+                    .flatten();
+                match (ty, rev) {
+                    (BgpSessionType::IBgpClient, _) => {}
+                    (_, Some(BgpSessionType::IBgpClient)) => return None,
+                    _ if source > target => return None,
+                    _ => {}
+                }
+                let (source, target) = (*source, *target);
+                Some(SessionInfo {
+                    source,
+                    target,
+                    session_type: ty,
+                    established: is_up(source, target) && is_up(target, source),
+                    source_incoming: route_maps(source, target, RouteMapDirection::Incoming),
+                    source_outgoing: route_maps(source, target, RouteMapDirection::Outgoing),
+                    target_incoming: route_maps(target, source, RouteMapDirection::Incoming),
+                    target_outgoing: route_maps(target, source, RouteMapDirection::Outgoing),
+                })
+            })
+            .sorted_by_key(|s| (s.source, s.target))
+    }
+
+    /// Get the progress of the simulation as `(processed, pending)`, where `processed` is the
+    /// number of events processed since the network was created, and `pending` is the number of
+    /// events currently waiting in the queue. Use [`Network::simulate_progress`] to obtain a
+    /// snapshot after each step of the simulation, e.g., to render a progress bar.
+    pub fn progress(&self) -> (usize, usize) {
+        (self.processed_events, self.queue.len())
+    }
+
+    /// Simulate the network step by step, and yield the progress after each processed event (see
+    /// [`Network::progress`]). Once the queue is empty, the iterator triggers all timeouts like
+    /// [`InteractiveNetwork::simulate`], and stops once the network has converged. The iterator
+    /// yields [`NetworkError::NoConvergence`] if more events are processed than allowed by
+    /// [`Network::set_msg_limit`].
+    pub fn simulate_progress(&mut self) -> SimulationProgress<'_, P, Q, Ospf> {
+        SimulationProgress {
+            remaining_iter: self.stop_after,
+            net: self,
+            done: false,
+        }
+    }
+
+    /// Simulate the network step by step (like [`Network::simulate_progress`]), and stop as soon as
+    /// `predicate` holds for the forwarding state. The predicate is evaluated on the current
+    /// forwarding state, and again after each event that changes the forwarding state. If it
+    /// holds, this function returns the number of events processed by this call (`0` if the
+    /// predicate holds already before simulating any event), together with that forwarding state.
+    /// All remaining events stay in the queue, such that the simulation can be resumed with
+    /// [`InteractiveNetwork::simulate`]. If the network converges without the predicate ever
+    /// holding, this function returns `None`.
+    ///
+    /// Use this function to capture transient states, e.g., the exact moment when a forwarding
+    /// loop or a black hole forms during convergence.
+    pub fn simulate_until<F>(
+        &mut self,
+        mut predicate: F,
+    ) -> Result<Option<(usize, ForwardingState<P>)>, NetworkError>
+    where
+        F: FnMut(&mut ForwardingState<P>) -> bool,
+    {
+        let mut fw_state = self.get_forwarding_state();
+        if predicate(&mut fw_state) {
+            return Ok(Some((0, fw_state)));
+        }
+
+        let mut progress = self.simulate_progress();
+        let mut step = 0;
+        while let Some(changed) = progress.next_step()? {
+            step += 1;
+            if !changed {
+                continue;
+            }
+            let mut fw_state = progress.net.get_forwarding_state();
+            if predicate(&mut fw_state) {
+                return Ok(Some((step, fw_state)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Create a summary of the network, containing the number of routers, links, BGP sessions,
+    /// route-maps and prefixes, and whether the network has converged. Use the
+    /// [`NetworkFormatter`](crate::formatter::NetworkFormatter) to render it as a human-readable
+    /// report.
+    pub fn summary(&self) -> NetworkSummary {
+        let mut summary = NetworkSummary {
+            num_internal_routers: self.internal_indices().count(),
+            num_external_routers: self.external_indices().count(),
+            num_links: self.net.edge_count(),
+            num_prefixes: self.known_prefixes.iter().count(),
+            num_queued_events: self.queue.len(),
+            ..Default::default()
+        };
+
+        // count each configured session once.
+        for ((source, target), ty) in self.bgp_sessions.iter() {
+            if source > target || ty.is_none() {
+                continue;
+            }
+            let rev = self
+                .bgp_sessions
+                .get(&(*target, *source))
+                .copied()
+                .flatten();
+            match (*ty, rev) {
+                (Some(BgpSessionType::EBgp), _) | (_, Some(BgpSessionType::EBgp)) => {
+                    summary.num_ebgp_sessions += 1
+                }
+                (Some(BgpSessionType::IBgpClient), _) | (_, Some(BgpSessionType::IBgpClient)) => {
+                    summary.num_ibgp_client_sessions += 1
+                }
+                _ => summary.num_ibgp_peer_sessions += 1,
+            }
+        }
+
+        summary.num_route_maps = self
+            .internal_routers()
+            .flat_map(|r| {
+                r.bgp
+                    .route_maps_in
+                    .values()
+                    .chain(r.bgp.route_maps_out.values())
+            })
+            .map(|maps| maps.len())
+            .sum();
+
+        summary
+    }
+
+    /// Compute a hash over the topology of the network, i.e., over all routers (their ID, name, AS
+    /// number, and whether they are internal or external) and all links. The hash neither depends
+    /// on the order in which links were added, nor on the platform, so it can be stored to check
+    /// later that a network matches, e.g., when loading a snapshot. Routers are identified by their
+    /// ID, which is assigned in the order in which routers are added. Hence, two networks only have
+    /// the same hash if their routers were added in the same order.
+    pub fn topology_hash(&self) -> u64 {
+        let routers = self
+            .devices()
+            .map(|r| (r.router_id(), r.name(), r.as_id(), r.is_external()))
+            .sorted()
+            .collect_vec();
+        let links = self
+            .net
+            .edge_indices()
+            .filter_map(|e| self.net.edge_endpoints(e))
+            .map(|(a, b)| if a < b { (a, b) } else { (b, a) })
+            .sorted()
+            .collect_vec();
+        StableHasher::hash_one(&(routers, links))
+    }
+
+    /// Compute a hash over the configuration of the network, i.e., over the configuration returned
+    /// by [`NetworkConfig::get_config`], and over all other BGP settings of internal routers (e.g.,
+    /// the local AS, RPKI, or multipath). The hash neither depends on the order in which the
+    /// configuration was applied, nor on the platform.
+    pub fn config_hash(&self) -> Result<u64, NetworkError> {
+        let exprs = self
+            .get_config()?
+            .iter()
+            .map(|expr| expr.stable_hash())
+            .sorted()
+            .collect_vec();
+        let bgp = self
+            .internal_routers()
+            .map(|r| (r.router_id(), r.bgp.config_hash()))
+            .sorted()
+            .collect_vec();
+        Ok(StableHasher::hash_one(&(exprs, bgp)))
+    }
+
+    /// Compute the canonical form of the network. It describes all routers, links, BGP sessions,
+    /// route-maps, static routes, and all other BGP settings, and refers to routers by their name
+    /// instead of their ID.
+    /// Each section is sorted, so the canonical form neither depends on the order in which the
+    /// network was constructed, nor on the router IDs. Compare two canonical forms using `==`, or
+    /// use [`CanonicalNetwork::diff`] to get a readable list of differences.
+    pub fn canonicalize(&self) -> CanonicalNetwork {
+        let name = |r: RouterId| r.fmt(self).to_string();
+        let mut canonical = CanonicalNetwork::default();
+
+        for r in self.devices() {
+            let kind = if r.is_internal() {
+                "internal"
+            } else {
+                "external"
+            };
+            let mut line = format!("{}: {kind}, {}", r.name(), r.as_id());
+            if let Some(r) = r.internal() {
+                if r.get_load_balancing() {
+                    line.push_str(", load balancing");
+                }
+                if r.bgp.is_route_reflector() {
+                    line.push_str(", route reflector");
+                }
+            }
+            canonical.routers.push(line);
+        }
+
+        for (a, b) in self
+            .net
+            .edge_indices()
+            .filter_map(|e| self.net.edge_endpoints(e))
+        {
+            if self.get_internal_router(a).is_ok() && self.get_internal_router(b).is_ok() {
+                for (src, dst) in [(a, b), (b, a)] {
+                    canonical.links.push(format!(
+                        "{} -> {}: weight {}, {}",
+                        name(src),
+                        name(dst),
+                        self.ospf.get_weight(src, dst),
+                        self.ospf.get_area(src, dst).unwrap_or_default(),
+                    ));
+                }
+            } else {
+                let (a, b) = sorted_pair(name(a), name(b));
+                canonical.links.push(format!("{a} -- {b}: external"));
+            }
+        }
+
+        for ((src, dst), ty) in self.bgp_sessions.iter() {
+            let Some(ty) = ty else { continue };
+            let line = match ty {
+                BgpSessionType::IBgpClient => format!("{} -> {}: {ty}", name(*src), name(*dst)),
+                _ => {
+                    let (a, b) = sorted_pair(name(*src), name(*dst));
+                    format!("{a} -- {b}: {ty}")
+                }
+            };
+            canonical.sessions.push(line);
+        }
+
+        for r in self.internal_routers() {
+            for (direction, maps) in [
+                (RouteMapDirection::Incoming, &r.bgp.route_maps_in),
+                (RouteMapDirection::Outgoing, &r.bgp.route_maps_out),
+            ] {
+                for (neighbor, map) in maps.iter().flat_map(|(n, m)| m.iter().map(move |x| (n, x)))
+                {
+                    canonical.route_maps.push(format!(
+                        "{} {} {} [{}]: {}",
+                        r.name(),
+                        match direction {
+                            RouteMapDirection::Incoming => "from",
+                            RouteMapDirection::Outgoing => "to",
+                        },
+                        name(*neighbor),
+                        map.order,
+                        map.fmt(self)
+                    ));
+                }
+            }
+            for (prefix, target) in r.sr.get_table().iter() {
+                canonical.static_routes.push(format!(
+                    "{}: {prefix} via {}",
+                    r.name(),
+                    target.fmt(self)
+                ));
+            }
+
+            let bgp = &r.bgp;
+            let mut lines = Vec::new();
+            for (direction, lists) in [
+                ("from", &bgp.distribute_lists_in),
+                ("to", &bgp.distribute_lists_out),
+            ] {
+                for (n, list) in lists {
+                    lines.push(format!(
+                        "distribute-list {direction} {}: {} [{}]",
+                        name(*n),
+                        if list.permit { "permit" } else { "deny" },
+                        list.prefixes.iter().sorted().join(", ")
+                    ));
+                }
+            }
+            if let Some((roas, policy)) = &bgp.rpki {
+                let mut roas = roas.iter().map(|(p, a)| format!("{p} {a}")).sorted();
+                lines.push(format!("rpki {policy:?}: [{}]", roas.join(", ")));
+            }
+            lines.extend(
+                bgp.local_as
+                    .iter()
+                    .map(|(n, a)| format!("local-as {a} to {}", name(*n))),
+            );
+            lines.extend(
+                bgp.allowas_in
+                    .iter()
+                    .map(|(n, x)| format!("allowas-in {x} from {}", name(*n))),
+            );
+            lines.extend(
+                bgp.ebgp_multihop
+                    .iter()
+                    .map(|(n, ttl)| format!("ebgp-multihop {ttl} to {}", name(*n))),
+            );
+            lines.extend(
+                bgp.orr
+                    .iter()
+                    .map(|(n, root)| format!("orr root {} for {}", name(*root), name(*n))),
+            );
+            lines.extend(bgp.cluster_id.map(|id| format!("cluster-id {id}")));
+            lines.extend(
+                bgp.bgp_ids
+                    .iter()
+                    .map(|(n, id)| format!("bgp-id {id} of {}", name(*n))),
+            );
+            lines.extend(
+                bgp.session_defaults
+                    .iter()
+                    .map(|(n, d)| format!("defaults from {}: {d:?}", name(*n))),
+            );
+            lines.extend(
+                bgp.session_options
+                    .iter()
+                    .map(|(n, o)| format!("options of {}: {o:?}", name(*n))),
+            );
+            lines.extend(bgp.mrai.iter().map(|n| format!("mrai to {}", name(*n))));
+            lines.extend(
+                [
+                    (bgp.as_path_ignore, "as-path ignore"),
+                    (bgp.prefer_oldest, "prefer oldest"),
+                    (bgp.multipath, "multipath"),
+                    (bgp.multipath_same_as, "multipath same-as"),
+                ]
+                .into_iter()
+                .filter(|(enabled, _)| *enabled)
+                .map(|(_, line)| line.to_string()),
+            );
+            lines.extend(
+                bgp.no_next_hop_self
+                    .iter()
+                    .map(|n| format!("no next-hop-self to {}", name(*n))),
+            );
+            lines.extend(
+                bgp.graceful_shutdown
+                    .iter()
+                    .map(|n| format!("graceful shutdown of {}", name(*n))),
+            );
+            for (list_name, list) in bgp.as_path_lists.iter() {
+                let mut entries = list.entries.iter().map(|(state, clause)| {
+                    format!(
+                        "{} {clause}",
+                        if state.is_allow() { "permit" } else { "deny" }
+                    )
+                });
+                lines.push(format!(
+                    "as-path list {list_name}: [{}]",
+                    entries.join(", ")
+                ));
+            }
+            lines.extend(
+                bgp.attribute_propagation
+                    .iter()
+                    .map(|(n, a)| format!("propagation to {}: {a:?}", name(*n))),
+            );
+            lines.extend(
+                bgp.next_hop_resolution
+                    .iter()
+                    .map(|(nh, p)| format!("next-hop {} via {p}", name(*nh))),
+            );
+            if bgp.recursion_limit != DEFAULT_RECURSION_LIMIT {
+                lines.push(format!("recursion limit {}", bgp.recursion_limit));
+            }
+            lines.extend(bgp.damping.map(|n| format!("damping after {n} flaps")));
+            for (vrf_name, vrf) in bgp.vrfs.iter() {
+                lines.push(format!(
+                    "vrf {vrf_name}: neighbors [{}], import {:?}, export {:?}",
+                    vrf.neighbors.iter().map(|n| name(*n)).sorted().join(", "),
+                    vrf.import,
+                    vrf.export,
+                ));
+                for map in vrf.route_maps.iter() {
+                    canonical.route_maps.push(format!(
+                        "{} vrf {vrf_name} [{}]: {}",
+                        r.name(),
+                        map.order,
+                        map.fmt(self)
+                    ));
+                }
+            }
+            canonical
+                .bgp
+                .extend(lines.into_iter().map(|l| format!("{}: {l}", r.name())));
+        }
+
+        for ((r, n), group) in self.peer_group_members.iter() {
+            canonical
+                .bgp
+                .push(format!("{}: peer-group {group} for {}", name(*r), name(*n)));
+        }
+
+        canonical.sort();
+        canonical
+    }
+
+    /// Detect route leaks, i.e., routes advertised to an external router in violation of the
+    /// valley-free export rules (see [`GaoRexfordPeerType::exports_to`]). The `relationships`
+    /// assign a [`GaoRexfordPeerType`] to external routers, e.g., as returned by
+    /// [`build_gao_rexford_policies`](crate::builder::NetworkBuilder::build_gao_rexford_policies).
+    /// A route was learned from the first AS on its advertised AS path that is not the own AS. It
+    /// is leaked if the relationship with that AS forbids exporting the route to the external
+    /// router (e.g., a route from a provider advertised to a peer). External routers and ASes
+    /// without a relationship are ignored.
+    ///
+    /// This function inspects the Adj-RIB-Out of all internal routers, and returns a sorted list
+    /// of all leaked routes, as `(router, neighbor, prefix)`.
+    pub fn detect_route_leaks(
+        &self,
+        relationships: &HashMap<RouterId, GaoRexfordPeerType>,
+    ) -> Vec<(RouterId, RouterId, P)> {
+        let as_relationships: HashMap<AsId, GaoRexfordPeerType> = relationships
+            .iter()
+            .filter_map(|(ext, kind)| Some((self.get_external_router(*ext).ok()?.as_id(), *kind)))
+            .collect();
+
+        let mut leaks = Vec::new();
+        for r in self.internal_routers() {
+            for (prefix, rib_out) in r.bgp.get_rib_out().iter() {
+                for (neighbor, entry) in rib_out {
+                    let Some(to) = relationships.get(neighbor) else {
+                        continue;
+                    };
+                    let own_as = [r.as_id(), r.bgp.get_local_as(*neighbor)];
+                    let from = entry
+                        .route
+                        .as_path
+                        .iter()
+                        .find(|asn| !own_as.contains(asn))
+                        .and_then(|asn| as_relationships.get(asn));
+                    if from.is_some_and(|from| !from.exports_to(*to)) {
+                        leaks.push((r.router_id(), *neighbor, *prefix));
+                    }
+                }
+            }
+        }
+        leaks.sort();
+        leaks
+    }
+
     /// This function creates an link in the network. The link will have weight fo 100.0 for both
     /// directions and area 0 (backbone). If the link does already exist, this function will do
-    /// nothing! After adding the link, the network simulation is executed.
+    /// nothing! After adding the link, the network simulation is executed. A router cannot be
+    /// connected to itself, and two external routers cannot be connected.
     ///
     /// ```rust
     /// # use bgpsim::prelude::*;
@@ -467,13 +1602,7 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Network<P, Q, Ospf> {
     /// ```
     pub fn add_link(&mut self, a: RouterId, b: RouterId) -> Result<(), NetworkError> {
         if !self.net.contains_edge(a, b) {
-            // ensure that an external router is only ever connected to a single internal one
-            let a_external = self.routers.get(&a).or_router_not_found(a)?.is_external();
-            let b_external = self.routers.get(&b).or_router_not_found(b)?.is_external();
-            if a_external && b_external {
-                return Err(NetworkError::CannotConnectExternalRouters(a, b));
-            }
-
+            self.check_new_link(a, b)?;
             self.net.add_edge(a, b, ());
             let events = self.ospf.add_link(a, b, &mut self.routers)?;
             self.enqueue_events(events);
@@ -501,6 +1630,11 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Network<P, Q, Ospf> {
             })
             .collect::<HashSet<_>>();
 
+        // check all links before modifying the network graph
+        for (a, b) in links.iter() {
+            self.check_new_link(*a, *b)?;
+        }
+
         // add all edges to the network graph
         for (a, b) in links.iter() {
             self.net.add_edge(*a, *b, ());
@@ -516,10 +1650,88 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Network<P, Q, Ospf> {
         Ok(())
     }
 
-    /// Setup a BGP session between source and target. If `session_type` is `None`, then any
-    /// existing session will be removed. Otherwise, any existing session will be replaced by the
-    /// `session_type`.
-    pub fn set_bgp_session(
+    /// Connect two internal routers with a bundle of parallel links (link aggregation), where
+    /// `members` contains the capacity of each member link. The bundle forms a single IGP
+    /// adjacency (see [`Network::add_link`]). Failing a member (see
+    /// [`Network::set_link_bundle_member`]) reduces the capacity of the bundle and its share of the
+    /// traffic (see [`ForwardingState::get_traffic_share`]) without affecting the adjacency. If the
+    /// routers are already connected, the link is turned into a bundle.
+    pub fn add_link_bundle(
+        &mut self,
+        a: RouterId,
+        b: RouterId,
+        members: impl IntoIterator<Item = f64>,
+    ) -> Result<(), NetworkError> {
+        let capacities: Vec<f64> = members.into_iter().collect();
+        if capacities.is_empty() {
+            return Err(NetworkError::EmptyLinkBundle(a, b));
+        }
+        for r in [a, b] {
+            if self.get_device(r)?.is_external() {
+                return Err(NetworkError::DeviceIsExternalRouter(r));
+            }
+        }
+
+        let bundle = LinkBundle {
+            active: vec![true; capacities.len()],
+            capacities,
+            weights: None,
+        };
+        let old = self.link_bundles.insert(bundle_key(a, b), bundle);
+        self.add_link(a, b)?;
+
+        // restore the link weights if all members of the old bundle have failed.
+        if let Some((w_ab, w_ba)) = old.and_then(|x| x.weights) {
+            self.set_link_weights_from([(a, b, w_ab), (b, a, w_ba)])?;
+        }
+        Ok(())
+    }
+
+    /// Enable or disable a single member link of the bundle between `a` and `b` (see
+    /// [`Network::add_link_bundle`]). The IGP adjacency remains up as long as at least one member
+    /// is active. If all members fail, then the link weight is set to infinity in both directions.
+    /// As soon as one member becomes active again, the previous link weights are restored. This
+    /// function returns the old state of that member.
+    pub fn set_link_bundle_member(
+        &mut self,
+        a: RouterId,
+        b: RouterId,
+        member: usize,
+        active: bool,
+    ) -> Result<bool, NetworkError> {
+        let weights = (self.get_link_weight(a, b)?, self.get_link_weight(b, a)?);
+        let bundle = self
+            .link_bundles
+            .get_mut(&bundle_key(a, b))
+            .ok_or(NetworkError::LinkNotFound(a, b))?;
+        let old = std::mem::replace(
+            bundle
+                .active
+                .get_mut(member)
+                .ok_or(NetworkError::LinkBundleMemberNotFound(a, b, member))?,
+            active,
+        );
+
+        if bundle.num_active() == 0 && bundle.weights.is_none() {
+            // the last member has failed. Tear down the adjacency.
+            bundle.weights = Some(weights);
+            self.set_link_weights_from([
+                (a, b, LinkWeight::INFINITY),
+                (b, a, LinkWeight::INFINITY),
+            ])?;
+        } else if bundle.num_active() > 0 {
+            if let Some((w_ab, w_ba)) = bundle.weights.take() {
+                self.set_link_weights_from([(a, b, w_ab), (b, a, w_ba)])?;
+            }
+        }
+
+        Ok(old)
+    }
+
+    /// Setup a BGP session between source and target. If `session_type` is `None`, then any
+    /// existing session will be removed. Otherwise, any existing session will be replaced by the
+    /// `session_type`.
+    pub fn set_bgp_session(
         &mut self,
         source: RouterId,
         target: RouterId,
@@ -527,194 +1739,1703 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Network<P, Q, Ospf> {
     ) -> Result<(), NetworkError> {
         self._set_bgp_session(source, target, session_type)?;
 
-        // refresh the active BGP sessions in the network
-        self.refresh_bgp_sessions()?;
-        self.do_queue_maybe_skip()
+        // refresh the active BGP sessions in the network
+        self.refresh_bgp_sessions()?;
+        self.do_queue_maybe_skip()
+    }
+
+    /// Set BGP sessions from an iterator.
+    pub fn set_bgp_session_from<I>(&mut self, sessions: I) -> Result<(), NetworkError>
+    where
+        I: IntoIterator<Item = (RouterId, RouterId, Option<BgpSessionType>)>,
+    {
+        for (source, target, session_type) in sessions.into_iter() {
+            self._set_bgp_session(source, target, session_type)?;
+        }
+
+        // refresh the active BGP sessions in the network
+        self.refresh_bgp_sessions()?;
+        self.do_queue_maybe_skip()
+    }
+
+    /// Establish a multihop eBGP session between the internal `router` and the `external` router,
+    /// which need not be directly connected. The session is only established while `external` is
+    /// reachable from `router` over the IGP using at most `ttl` hops (links). Routes learned over
+    /// the session have `external` as next-hop, which is resolved over the IGP (with the
+    /// corresponding IGP cost). Once the path towards `external` is cut, the session breaks down,
+    /// and all routes learned over it are invalidated. In contrast, sessions configured with
+    /// [`Network::set_bgp_session`] are not limited by a TTL.
+    pub fn add_ebgp_multihop_session(
+        &mut self,
+        router: RouterId,
+        external: RouterId,
+        ttl: u8,
+    ) -> Result<(), NetworkError> {
+        if self.get_device(external)?.is_internal() {
+            return Err(NetworkError::InvalidBgpSessionType(
+                router,
+                external,
+                BgpSessionType::EBgp,
+            ));
+        }
+        let (_, events) = self
+            .get_internal_router_mut(router)?
+            .bgp
+            .set_ebgp_multihop(external, Some(ttl))?;
+        self.enqueue_events(events);
+        self.set_bgp_session(router, external, Some(BgpSessionType::EBgp))
+    }
+
+    /// Bring up the BGP sessions in `order` one after the other, and let the network converge after
+    /// each of them. Each pair `(a, b)` must refer to a session that is already configured (see
+    /// [`Network::set_bgp_session`]), and its session type is kept. This function first takes all
+    /// listed sessions down at once (and lets the network converge), and then establishes them in
+    /// the given order. In manual simulation mode, the events are only enqueued in this order.
+    ///
+    /// This allows to study how the order in which sessions come up affects convergence. For
+    /// safe policies (e.g., policies following the Gao-Rexford conditions), the converged state
+    /// does not depend on the order. However, policies with multiple stable states (like the
+    /// DISAGREE gadget, where two routers prefer each other's route) may converge to a different
+    /// state for different orders.
+    pub fn establish_sessions_in_order(
+        &mut self,
+        order: &[(RouterId, RouterId)],
+    ) -> Result<(), NetworkError> {
+        // get the configured type of each session, as seen by the router that configures it.
+        let sessions = order
+            .iter()
+            .map(|(a, b)| {
+                let ab = self.bgp_sessions.get(&(*a, *b)).copied().flatten();
+                let ba = self.bgp_sessions.get(&(*b, *a)).copied().flatten();
+                match (ab, ba) {
+                    (_, Some(BgpSessionType::IBgpClient)) => {
+                        Ok((*b, *a, BgpSessionType::IBgpClient))
+                    }
+                    (Some(ty), _) => Ok((*a, *b, ty)),
+                    (None, Some(ty)) => Ok((*b, *a, ty)),
+                    (None, None) => Err(DeviceError::NoBgpSession(*b).into()),
+                }
+            })
+            .collect::<Result<Vec<_>, NetworkError>>()?;
+
+        // take all sessions down
+        for (a, b, _) in sessions.iter() {
+            self._set_bgp_session(*a, *b, None)?;
+        }
+        self.refresh_bgp_sessions()?;
+        self.do_queue_maybe_skip()?;
+
+        // establish them one after the other
+        for (a, b, ty) in sessions {
+            self._set_bgp_session(a, b, Some(ty))?;
+            self.refresh_bgp_sessions()?;
+            self.do_queue_maybe_skip()?;
+        }
+        Ok(())
+    }
+
+    /// Add a route server to the network, e.g., to model an IXP. A route server is an external
+    /// router in AS `as_id` that establishes an eBGP session with each of the `peers`. It
+    /// transparently reflects the routes received from each peer to all other peers: it neither
+    /// inserts its own AS number into the AS path, nor sets itself as the next-hop. Hence, it never
+    /// appears as an egress in the forwarding state. If a route server knows multiple routes for
+    /// the same prefix, it selects the one with the shortest AS path (ties are broken by the lowest
+    /// router-id of the sender).
+    ///
+    /// The peers must be able to reach the route server (e.g., by a direct link, see
+    /// [`Network::add_link`]) for the sessions to come up, and they must be able to reach each
+    /// other to use the reflected routes. A route server cannot originate any route (see
+    /// [`NetworkError::RouteServer`]). This function returns the ID of the new router, and runs
+    /// the simulation after establishing the sessions.
+    pub fn add_route_server(
+        &mut self,
+        name: impl Into<String>,
+        as_id: impl Into<AsId>,
+        peers: impl IntoIterator<Item = RouterId>,
+    ) -> Result<RouterId, NetworkError> {
+        let router_id = self.add_external_router(name, as_id);
+        self.get_external_router_mut(router_id)?.route_server = true;
+        self.set_bgp_session_from(
+            peers
+                .into_iter()
+                .map(|peer| (router_id, peer, Some(BgpSessionType::EBgp))),
+        )?;
+        Ok(router_id)
+    }
+
+    /// Configure whether `router` ignores the AS path length when selecting the best route
+    /// (`bgp bestpath as-path ignore`). If set, routes that only differ in their AS path length
+    /// are compared using the subsequent steps of the decision process, i.e., the MED, the session
+    /// type, the IGP cost, and the router-id. By default, the AS path length is considered. This
+    /// function returns the old value, and runs the simulation after updating the router.
+    pub fn set_bestpath_as_path_ignore(
+        &mut self,
+        router: RouterId,
+        as_path_ignore: bool,
+    ) -> Result<bool, NetworkError> {
+        let (old, events) = self
+            .get_internal_router_mut(router)?
+            .bgp
+            .set_bestpath_as_path_ignore(as_path_ignore)?;
+
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()?;
+        Ok(old)
+    }
+
+    /// Configure whether `router` prefers the oldest of two equally good eBGP routes. If set, a
+    /// route learned over eBGP does not displace the currently selected eBGP route if both routes
+    /// only differ in the final tiebreakers (starting with the BGP router-id). This reduces churn,
+    /// but the selected route depends on the order in which routes were received. By default, the
+    /// router compares the BGP router-ids (`bgp bestpath compare-routerid`). This function returns
+    /// the old value, and runs the simulation after updating the router.
+    pub fn set_prefer_oldest(
+        &mut self,
+        router: RouterId,
+        prefer_oldest: bool,
+    ) -> Result<bool, NetworkError> {
+        let (old, events) = self
+            .get_internal_router_mut(router)?
+            .bgp
+            .set_prefer_oldest(prefer_oldest)?;
+
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()?;
+        Ok(old)
+    }
+
+    /// Configure route flap damping on `router`. Routes that are marked as dampable by an incoming
+    /// route-map (see [`RouteMapSet::Dampen`](crate::route_map::RouteMapSet::Dampen)) are
+    /// suppressed after they have been withdrawn `threshold` times by the same neighbor. This
+    /// allows damping only specific routes, e.g., those learned from customers, selected by
+    /// prefix or by community. Suppressed routes are ignored in the decision process until the
+    /// flaps are cleared with [`Network::clear_route_damping`]. Routes that are not dampable are
+    /// never suppressed. Passing `None` disables route flap damping. This function returns the
+    /// old value, and runs the simulation after updating the router.
+    pub fn set_route_damping(
+        &mut self,
+        router: RouterId,
+        threshold: Option<u32>,
+    ) -> Result<Option<u32>, NetworkError> {
+        let (old, events) = self
+            .get_internal_router_mut(router)?
+            .bgp
+            .set_route_damping(threshold)?;
+
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()?;
+        Ok(old)
+    }
+
+    /// Create the (empty) VRF `name` on `router`, if it does not exist yet. Attach BGP sessions to
+    /// the VRF using [`Network::set_vrf_neighbor`], and leak routes between VRFs using
+    /// [`Network::set_vrf_route_targets`] (see [`Vrf`]).
+    pub fn add_vrf(
+        &mut self,
+        router: RouterId,
+        name: impl Into<String>,
+    ) -> Result<(), NetworkError> {
+        let name = name.into();
+        if self
+            .get_internal_router(router)?
+            .bgp
+            .get_vrf(&name)
+            .is_none()
+        {
+            self.set_vrf(router, name, Some(Vrf::default()))?;
+        }
+        Ok(())
+    }
+
+    /// Create, replace, or remove (if `vrf` is `None`) the VRF `name` on `router`. This function
+    /// returns the old VRF, and runs the simulation after updating the router.
+    pub fn set_vrf(
+        &mut self,
+        router: RouterId,
+        name: impl Into<String>,
+        vrf: Option<Vrf<P>>,
+    ) -> Result<Option<Vrf<P>>, NetworkError> {
+        let (old, events) = self
+            .get_internal_router_mut(router)?
+            .bgp
+            .set_vrf(name, vrf)?;
+
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()?;
+        Ok(old)
+    }
+
+    /// Modify the existing VRF `name` on `router` using `f`, and run the simulation.
+    fn update_vrf(
+        &mut self,
+        router: RouterId,
+        name: &str,
+        f: impl FnOnce(&mut Vrf<P>),
+    ) -> Result<(), NetworkError> {
+        let mut vrf = self
+            .get_internal_router(router)?
+            .bgp
+            .get_vrf(name)
+            .ok_or_else(|| DeviceError::VrfNotFound(name.to_string()))?
+            .clone();
+        f(&mut vrf);
+        self.set_vrf(router, name, Some(vrf))?;
+        Ok(())
+    }
+
+    /// Attach the BGP session with `neighbor` to the VRF `name` on `router`. Routes learned from
+    /// `neighbor` are then only used within the VRF, and no routes of the global table are
+    /// advertised to `neighbor`. The session is detached from any other VRF.
+    pub fn set_vrf_neighbor(
+        &mut self,
+        router: RouterId,
+        name: &str,
+        neighbor: RouterId,
+    ) -> Result<(), NetworkError> {
+        if self
+            .get_internal_router(router)?
+            .bgp
+            .get_session_type(neighbor)
+            .is_none()
+        {
+            return Err(DeviceError::NoBgpSession(neighbor).into());
+        }
+        self.update_vrf(router, name, |vrf| {
+            vrf.neighbors.insert(neighbor);
+        })
+    }
+
+    /// Set the route targets that the VRF `name` on `router` imports and exports (see [`Vrf`]).
+    pub fn set_vrf_route_targets(
+        &mut self,
+        router: RouterId,
+        name: &str,
+        import: impl IntoIterator<Item = u32>,
+        export: impl IntoIterator<Item = u32>,
+    ) -> Result<(), NetworkError> {
+        self.update_vrf(router, name, |vrf| {
+            vrf.import = import.into_iter().collect();
+            vrf.export = export.into_iter().collect();
+        })
+    }
+
+    /// Set the route-maps applied on all routes entering the VRF `name` on `router`. The sequence
+    /// is sorted by the route-map order.
+    pub fn set_vrf_route_maps(
+        &mut self,
+        router: RouterId,
+        name: &str,
+        mut route_maps: Vec<RouteMap<P>>,
+    ) -> Result<(), NetworkError> {
+        route_maps.sort_by_key(|m| m.order);
+        self.update_vrf(router, name, |vrf| vrf.route_maps = route_maps)
+    }
+
+    /// Get all forwarding paths from `router` towards `prefix` in the VRF `vrf`. If `vrf` is
+    /// `None`, the paths are computed using the global table (see
+    /// [`ForwardingState::get_paths`]). Otherwise, `router` looks up the prefix in the VRF, and
+    /// traffic is tunneled over the IGP towards the next-hop of the selected route (as with MPLS).
+    /// If that next-hop is an internal router, it looks up the prefix in its VRF with the same name,
+    /// or in its global table if it has no such VRF.
+    pub fn get_vrf_paths(
+        &self,
+        router: RouterId,
+        prefix: impl Into<P>,
+        vrf: Option<&str>,
+    ) -> Result<Vec<Vec<RouterId>>, NetworkError> {
+        let prefix = prefix.into();
+        let Some(vrf) = vrf else {
+            return self.get_forwarding_state().get_paths(router, prefix);
+        };
+        let mut paths = Vec::new();
+        self.vrf_paths(vec![router], prefix, vrf, &mut paths)?;
+        Ok(paths)
+    }
+
+    /// Extend `path` (ending at the router where the VRF lookup happens) towards the next-hop of
+    /// the route selected in the VRF, and push all resulting paths to `paths`.
+    fn vrf_paths(
+        &self,
+        path: Vec<RouterId>,
+        prefix: P,
+        vrf: &str,
+        paths: &mut Vec<Vec<RouterId>>,
+    ) -> Result<(), NetworkError> {
+        let ingress = *path.last().unwrap();
+        let Some(route) = self
+            .get_internal_router(ingress)?
+            .bgp
+            .get_vrf_route(vrf, prefix)?
+        else {
+            return Err(NetworkError::ForwardingBlackHole(path));
+        };
+        let egress = route.route.next_hop;
+
+        let mut fw_state = None;
+        let mut stack = vec![path];
+        while let Some(path) = stack.pop() {
+            let cur = *path.last().unwrap();
+            let router = self.get_device(cur)?.internal();
+            if cur == egress {
+                match router {
+                    Some(r) if cur != ingress && r.bgp.get_vrf(vrf).is_some() => {
+                        self.vrf_paths(path, prefix, vrf, paths)?
+                    }
+                    Some(_) if cur != ingress => {
+                        let fw_state = fw_state.get_or_insert_with(|| self.get_forwarding_state());
+                        for tail in fw_state.get_paths(cur, prefix)? {
+                            paths.push(path.iter().chain(&tail[1..]).copied().collect());
+                        }
+                    }
+                    _ => paths.push(path),
+                }
+                continue;
+            }
+            let nhs = match router {
+                Some(r) if cur == ingress => r.get_vrf_next_hop(prefix, vrf)?,
+                Some(r) => r.ospf.get(egress).to_vec(),
+                None => Vec::new(),
+            };
+            if nhs.is_empty() {
+                return Err(NetworkError::ForwardingBlackHole(path));
+            }
+            for nh in nhs.into_iter().rev() {
+                if let Some(pos) = path.iter().position(|x| *x == nh) {
+                    return Err(NetworkError::ForwardingLoop {
+                        to_loop: path[..pos].to_vec(),
+                        first_loop: path[pos..].to_vec(),
+                    });
+                }
+                let mut next = path.clone();
+                next.push(nh);
+                stack.push(next);
+            }
+        }
+        Ok(())
+    }
+
+    /// Clear all flaps counted by the route flap damping of `router`, such that all suppressed
+    /// routes are used again (see [`Network::set_route_damping`]). This function runs the
+    /// simulation after updating the router.
+    pub fn clear_route_damping(&mut self, router: RouterId) -> Result<(), NetworkError> {
+        let events = self
+            .get_internal_router_mut(router)?
+            .bgp
+            .clear_route_damping()?;
+
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()
+    }
+
+    /// Configure `router` to resolve `next_hop` recursively via the BGP route it selects for
+    /// `prefix`, instead of via the IGP. The router looks up the next-hop of that route, and
+    /// repeats the process until it reaches a next-hop that is reachable in the IGP. Routes whose
+    /// next-hop cannot be resolved within the recursion limit (see
+    /// [`Network::set_recursion_limit`]), or whose resolution visits the same next-hop twice, are
+    /// invalid and ignored in the decision process. Setting `prefix` to `None` removes the
+    /// configuration. This function returns the old value, and runs the simulation after updating
+    /// the router.
+    pub fn set_next_hop_resolution(
+        &mut self,
+        router: RouterId,
+        next_hop: RouterId,
+        prefix: Option<P>,
+    ) -> Result<Option<P>, NetworkError> {
+        let (old, events) = self
+            .get_internal_router_mut(router)?
+            .bgp
+            .set_next_hop_resolution(next_hop, prefix)?;
+
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()?;
+        Ok(old)
+    }
+
+    /// Set the maximum number of recursive lookups that `router` performs to resolve a next-hop
+    /// (see [`Network::set_next_hop_resolution`]). By default, the limit is
+    /// [`DEFAULT_RECURSION_LIMIT`](crate::bgp::DEFAULT_RECURSION_LIMIT). This function returns the
+    /// old value, and runs the simulation after updating the router.
+    pub fn set_recursion_limit(
+        &mut self,
+        router: RouterId,
+        limit: usize,
+    ) -> Result<usize, NetworkError> {
+        let (old, events) = self
+            .get_internal_router_mut(router)?
+            .bgp
+            .set_recursion_limit(limit)?;
+
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()?;
+        Ok(old)
+    }
+
+    /// Configure the cluster ID of the route reflector `router`. When reflecting a route, the
+    /// router appends its cluster ID to the CLUSTER_LIST, and it ignores all routes received over
+    /// iBGP whose CLUSTER_LIST already contains its cluster ID. Routers that are not route
+    /// reflectors never check the CLUSTER_LIST. Redundant route reflectors of the same clients
+    /// should share a cluster ID. If `cluster_id` is `None`, then the router uses its index as
+    /// cluster ID (see [`BgpProcess::get_cluster_id`](crate::router::BgpProcess::get_cluster_id)).
+    /// This function returns the old value, and runs the simulation after updating the router.
+    pub fn set_cluster_id(
+        &mut self,
+        router: RouterId,
+        cluster_id: Option<u32>,
+    ) -> Result<Option<u32>, NetworkError> {
+        let (old, events) = self
+            .get_internal_router_mut(router)?
+            .bgp
+            .set_cluster_id(cluster_id)?;
+
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()?;
+        Ok(old)
+    }
+
+    /// Configure `router` as a route reflector, or remove that role. Only route reflectors can
+    /// have clients (see [`Network::set_rr_client`]). Removing the role turns all client sessions
+    /// of `router` into regular iBGP peer sessions (by resetting them). This function returns the
+    /// old value.
+    ///
+    /// Setting up a session of type [`BgpSessionType::IBgpClient`] with
+    /// [`Network::set_bgp_session`] automatically configures the source as a route reflector.
+    pub fn set_route_reflector(
+        &mut self,
+        router: RouterId,
+        route_reflector: bool,
+    ) -> Result<bool, NetworkError> {
+        let old = std::mem::replace(
+            &mut self.get_internal_router_mut(router)?.bgp.route_reflector,
+            route_reflector,
+        );
+        if route_reflector {
+            return Ok(old);
+        }
+
+        let clients: Vec<RouterId> = self
+            .bgp_sessions
+            .iter()
+            .filter(|((src, _), ty)| *src == router && **ty == Some(BgpSessionType::IBgpClient))
+            .map(|((_, dst), _)| *dst)
+            .collect();
+        if clients.is_empty() {
+            return Ok(old);
+        }
+        // reset the sessions, such that routes learned from the former clients are no longer
+        // treated as client routes.
+        for client in clients.iter() {
+            self._set_bgp_session(router, *client, None)?;
+        }
+        self.refresh_bgp_sessions()?;
+        for client in clients {
+            self._set_bgp_session(router, client, Some(BgpSessionType::IBgpPeer))?;
+        }
+
+        // refresh the active BGP sessions in the network
+        self.refresh_bgp_sessions()?;
+        self.do_queue_maybe_skip()?;
+        Ok(old)
+    }
+
+    /// Designate `client` as a route-reflector client of `rr`. This sets up (or replaces) the
+    /// iBGP session between both routers, such that `rr` reflects routes to and from `client`.
+    /// `rr` must be configured as a route reflector (see [`Network::set_route_reflector`]).
+    /// Otherwise, this function returns [`NetworkError::NotARouteReflector`].
+    pub fn set_rr_client(&mut self, rr: RouterId, client: RouterId) -> Result<(), NetworkError> {
+        if !self.get_internal_router(rr)?.bgp.is_route_reflector() {
+            return Err(NetworkError::NotARouteReflector(rr));
+        }
+        self.set_bgp_session(rr, client, Some(BgpSessionType::IBgpClient))
+    }
+
+    /// set the link weight to the desired value. `NetworkError::LinkNotFound` is returned if
+    /// the link does not exist, and `NetworkError::InvalidLinkWeight` is returned if the weight is
+    /// negative or NaN. Otherwise, the old link weight is returned. Note, that this
+    /// function only sets the *directed* link weight, and the other direction (from `target` to
+    /// `source`) is not affected.
+    ///
+    /// This function will also update the IGP forwarding table *and* run the simulation.
+    pub fn set_link_weight(
+        &mut self,
+        source: RouterId,
+        target: RouterId,
+        weight: LinkWeight,
+    ) -> Result<LinkWeight, NetworkError> {
+        // throw an error if the link does not exist.
+        self.net
+            .find_edge(source, target)
+            .ok_or(NetworkError::LinkNotFound(source, target))?;
+        if weight.is_nan() || weight < 0.0 {
+            return Err(NetworkError::InvalidLinkWeight(source, target, weight));
+        }
+
+        let observed = self.observe_best_routes(self.internal_indices().detach(), None);
+        let (events, old_weight) =
+            self.ospf
+                .set_weight(source, target, weight, &mut self.routers)?;
+        self.callbacks.emit(ConvergenceEvent::IgpRecomputed {
+            links: vec![(source, target)],
+        });
+        self.emit_best_route_changes(observed);
+
+        // update the forwarding tables and simulate the network.
+        self.enqueue_events(events);
+        self.refresh_bgp_sessions()?;
+        self.do_queue_maybe_skip()?;
+
+        Ok(old_weight)
+    }
+
+    /// Set many link weights simultaneously. `NetworkError::LinkNotFound` is returned if any link
+    /// does not exist, and `NetworkError::InvalidLinkWeight` if any weight is negative or NaN. In
+    /// both cases, no link weight is modified. Note, that this function only sets the *directed*
+    /// link weight, and the other direction (from `target` to `source`) is not affected.
+    ///
+    /// This function will also update the IGP forwarding table *and* run the simulation.
+    pub fn set_link_weights_from<I>(&mut self, weights: I) -> Result<(), NetworkError>
+    where
+        I: IntoIterator<Item = (RouterId, RouterId, LinkWeight)>,
+    {
+        self._set_link_weights_from(weights)?;
+        self.do_queue_maybe_skip()
+    }
+
+    /// Set many link weights simultaneously without running the simulation. See
+    /// [`Network::set_link_weights_from`].
+    fn _set_link_weights_from<I>(&mut self, weights: I) -> Result<(), NetworkError>
+    where
+        I: IntoIterator<Item = (RouterId, RouterId, LinkWeight)>,
+    {
+        let weights = weights.into_iter().collect::<Vec<_>>();
+        for (source, target, weight) in weights.iter() {
+            if self.net.find_edge(*source, *target).is_none() {
+                return Err(NetworkError::LinkNotFound(*source, *target));
+            }
+            if weight.is_nan() || *weight < 0.0 {
+                return Err(NetworkError::InvalidLinkWeight(*source, *target, *weight));
+            }
+        }
+
+        let links = weights.iter().map(|(a, b, _)| (*a, *b)).collect();
+        let observed = self.observe_best_routes(self.internal_indices().detach(), None);
+        let events = self
+            .ospf
+            .set_link_weights_from(weights, &mut self.routers)?;
+        self.callbacks
+            .emit(ConvergenceEvent::IgpRecomputed { links });
+        self.emit_best_route_changes(observed);
+
+        // update the forwarding tables.
+        self.enqueue_events(events);
+        self.refresh_bgp_sessions()
+    }
+
+    /// Fail the link between `a` and `b` now, and recover it automatically after `duration` units
+    /// of logical time. The logical time counts the events processed by the network (see
+    /// [`Network::progress`]). The link fails by setting its weight to infinity in both
+    /// directions, and it recovers by restoring the previous weights as soon as `duration` events
+    /// were processed. If the network converges earlier, the logical time advances to the
+    /// recovery time, and the recovery is applied immediately. Hence, when the network runs in
+    /// automatic simulation mode, this function returns once the network has converged after the
+    /// recovery. Use manual simulation (see [`InteractiveNetwork::manual_simulation`]) to observe
+    /// the network during the outage.
+    ///
+    /// Failing a link that is already failing temporarily reschedules its recovery. This function
+    /// returns the logical time at which the link recovers, and runs the simulation.
+    pub fn fail_link_for(
+        &mut self,
+        a: RouterId,
+        b: RouterId,
+        duration: usize,
+    ) -> Result<usize, NetworkError> {
+        let at = self.processed_events + duration;
+        let weights = match self
+            .scheduled_recoveries
+            .iter()
+            .position(|x| bundle_key(x.a, x.b) == bundle_key(a, b))
+        {
+            Some(pos) => {
+                let old = self.scheduled_recoveries.remove(pos);
+                if old.a == a {
+                    old.weights
+                } else {
+                    (old.weights.1, old.weights.0)
+                }
+            }
+            None => (self.get_link_weight(a, b)?, self.get_link_weight(b, a)?),
+        };
+        self._set_link_weights_from([(a, b, LinkWeight::INFINITY), (b, a, LinkWeight::INFINITY)])?;
+        self.scheduled_recoveries
+            .push(ScheduledRecovery { at, a, b, weights });
+        self.do_queue_maybe_skip()?;
+        Ok(at)
+    }
+
+    /// Fail all links in the shared risk link group `srlg` at once (see
+    /// [`Network::set_link_srlgs`]). Like in [`Network::reachability_under_single_failures`],
+    /// links between internal routers fail by setting their weight to infinity in both
+    /// directions, while links to external routers are removed (together with their SRLGs). The
+    /// network only starts to reconverge once all links have failed. This function returns the
+    /// failed links (with the smaller router-id first), and runs the simulation.
+    pub fn fail_srlg(&mut self, srlg: u32) -> Result<Vec<(RouterId, RouterId)>, NetworkError> {
+        let links = self.get_srlg_links(srlg);
+        let mut internal = Vec::new();
+        let mut external = Vec::new();
+        for (a, b) in links.iter().copied() {
+            if self.get_device(a)?.is_internal() && self.get_device(b)?.is_internal() {
+                internal.extend([(a, b, LinkWeight::INFINITY), (b, a, LinkWeight::INFINITY)]);
+            } else {
+                external.push((a, b));
+            }
+        }
+
+        // fail all links before simulating anything
+        let old_skip = self.skip_queue;
+        self.skip_queue = true;
+        let result = self._set_link_weights_from(internal).and_then(|_| {
+            external
+                .into_iter()
+                .try_for_each(|(a, b)| self.remove_link(a, b))
+        });
+        self.skip_queue = old_skip;
+        result?;
+
+        self.do_queue_maybe_skip()?;
+        Ok(links)
+    }
+
+    /// Check the resilience of the reachability of `prefix` against all single-link failures. For
+    /// each link in the network, this function fails the link, lets the network reconverge, checks
+    /// which internal routers can still reach `prefix`, and restores the link. Internal links fail
+    /// by setting their weight to infinity in both directions, while links to external routers are
+    /// removed and added again. The network reconverges incrementally from the current state for
+    /// each failure and each restoration.
+    ///
+    /// The function returns all links (with the smaller router-id first) whose failure prevents at
+    /// least one internal router from reaching `prefix` that could reach it before. Links that are
+    /// already failed (i.e., with infinite weights in both directions) are skipped. The network
+    /// is simulated in automatic mode, independent of [`InteractiveNetwork::manual_simulation`].
+    /// If the network does not converge after any failure, the error is returned immediately, and
+    /// the network is left in the state with that failure.
+    pub fn reachability_under_single_failures(
+        &mut self,
+        prefix: impl Into<P>,
+    ) -> Result<Vec<(RouterId, RouterId)>, NetworkError> {
+        let prefix = prefix.into();
+        let old_skip = self.skip_queue;
+        self.skip_queue = false;
+        let result = self._reachability_under_single_failures(prefix);
+        self.skip_queue = old_skip;
+        result
+    }
+
+    fn _reachability_under_single_failures(
+        &mut self,
+        prefix: P,
+    ) -> Result<Vec<(RouterId, RouterId)>, NetworkError> {
+        self.do_queue_maybe_skip()?;
+        let reachable = self.reachable_routers(prefix);
+        let links = self
+            .net
+            .edge_indices()
+            .filter_map(|e| self.net.edge_endpoints(e))
+            .map(|(a, b)| bundle_key(a, b))
+            .sorted()
+            .collect_vec();
+
+        let mut critical = Vec::new();
+        for (a, b) in links {
+            let broken = if self.get_device(a)?.is_internal() && self.get_device(b)?.is_internal() {
+                let weights = (self.get_link_weight(a, b)?, self.get_link_weight(b, a)?);
+                if weights.0.is_infinite() && weights.1.is_infinite() {
+                    continue;
+                }
+                self._set_link_weights_from([
+                    (a, b, LinkWeight::INFINITY),
+                    (b, a, LinkWeight::INFINITY),
+                ])?;
+                self.do_queue_maybe_skip()?;
+                let broken = !reachable.is_subset(&self.reachable_routers(prefix));
+                self._set_link_weights_from([(a, b, weights.0), (b, a, weights.1)])?;
+                self.do_queue_maybe_skip()?;
+                broken
+            } else {
+                let data = self.link_data.get(&(a, b)).cloned();
+                let srlgs = self.srlgs.get(&(a, b)).cloned();
+                self.remove_link(a, b)?;
+                let broken = !reachable.is_subset(&self.reachable_routers(prefix));
+                self.add_link(a, b)?;
+                if let Some(data) = data {
+                    self.link_data.insert((a, b), data);
+                }
+                if let Some(srlgs) = srlgs {
+                    self.srlgs.insert((a, b), srlgs);
+                }
+                broken
+            };
+            if broken {
+                critical.push((a, b));
+            }
+        }
+        Ok(critical)
+    }
+
+    /// Get all internal routers that have a valid forwarding path towards `prefix`.
+    fn reachable_routers(&self, prefix: P) -> HashSet<RouterId> {
+        let mut fw = self.get_forwarding_state();
+        self.internal_indices()
+            .filter(|r| {
+                fw.get_paths(*r, prefix)
+                    .is_ok_and(|paths| !paths.is_empty())
+            })
+            .collect()
+    }
+
+    /// Get all links that failed temporarily and did not recover yet (see
+    /// [`Network::fail_link_for`]).
+    pub fn scheduled_recoveries(&self) -> &[ScheduledRecovery] {
+        &self.scheduled_recoveries
+    }
+
+    /// Recover all temporarily failed links whose recovery time has passed. If `idle` is set (i.e.,
+    /// the queue is empty), then the logical time advances to the next recovery time. This
+    /// function does not run the simulation, and returns `true` if any link was recovered.
+    pub(crate) fn recover_scheduled_links(&mut self, idle: bool) -> Result<bool, NetworkError> {
+        // links that were removed in the meantime cannot recover.
+        let net = &self.net;
+        self.scheduled_recoveries
+            .retain(|x| net.find_edge(x.a, x.b).is_some());
+        if idle {
+            if let Some(next) = self.scheduled_recoveries.iter().map(|x| x.at).min() {
+                self.processed_events = self.processed_events.max(next);
+            }
+        }
+        let now = self.processed_events;
+        let (due, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.scheduled_recoveries)
+            .into_iter()
+            .partition(|x| x.at <= now);
+        self.scheduled_recoveries = pending;
+        if due.is_empty() {
+            return Ok(false);
+        }
+        self._set_link_weights_from(
+            due.into_iter()
+                .flat_map(|x| [(x.a, x.b, x.weights.0), (x.b, x.a, x.weights.1)]),
+        )?;
+        Ok(true)
+    }
+
+    /// Set the OSPF area of a specific link to the desired value. `NetworkError::LinkNotFound` is
+    /// returned if the link does not exist. Otherwise, the old OSPF area is returned. This function
+    /// sets the area of both links in both directions.
+    ///
+    /// This function will also update the IGP forwarding table *and* run the simulation.
+    pub fn set_ospf_area(
+        &mut self,
+        source: RouterId,
+        target: RouterId,
+        area: impl Into<OspfArea>,
+    ) -> Result<OspfArea, NetworkError> {
+        // throw an error if the link does not exist.
+        self.net
+            .find_edge(source, target)
+            .ok_or(NetworkError::LinkNotFound(source, target))?;
+
+        let (events, old_area) =
+            self.ospf
+                .set_area(source, target, area.into(), &mut self.routers)?;
+
+        // update the forwarding tables and simulate the network.
+        self.enqueue_events(events);
+        self.refresh_bgp_sessions()?;
+        self.do_queue_maybe_skip()?;
+
+        Ok(old_area)
+    }
+
+    /// Set the type of an OSPF area, and return the old type. Internal routers of a stub area
+    /// (i.e., routers that are only part of that area) no longer see all inter-area and external
+    /// routes. Instead, they use a default route towards the closest area border router (see
+    /// [`OspfAreaType`]). The backbone area cannot be configured as a stub area
+    /// (`NetworkError::BackboneAreaType`).
+    ///
+    /// This function will also update the IGP forwarding table *and* run the simulation.
+    pub fn set_area_type(
+        &mut self,
+        area: impl Into<OspfArea>,
+        area_type: OspfAreaType,
+    ) -> Result<OspfAreaType, NetworkError> {
+        let (events, old_type) =
+            self.ospf
+                .set_area_type(area.into(), area_type, &mut self.routers)?;
+
+        // update the forwarding tables and simulate the network.
+        self.enqueue_events(events);
+        self.refresh_bgp_sessions()?;
+        self.do_queue_maybe_skip()?;
+
+        Ok(old_type)
+    }
+
+    /// Set the route map on a router in the network. If a route-map with the chosen order already
+    /// exists, then it will be overwritten. The old route-map will be returned. This function will
+    /// run the simulation after updating the router.
+    ///
+    /// To remove a route map, use [`Network::remove_bgp_route_map`].
+    pub fn set_bgp_route_map(
+        &mut self,
+        router: RouterId,
+        neighbor: RouterId,
+        direction: RouteMapDirection,
+        route_map: RouteMap<P>,
+    ) -> Result<Option<RouteMap<P>>, NetworkError> {
+        let (old_map, events) = self
+            .get_internal_router_mut(router)?
+            .bgp
+            .set_route_map(neighbor, direction, route_map)?;
+
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()?;
+        Ok(old_map)
+    }
+
+    /// Remove the route map on a router in the network. The old route-map will be returned. This
+    /// function will run the simulation after updating the router.
+    ///
+    /// To add a route map, use [`Network::set_bgp_route_map`].
+    pub fn remove_bgp_route_map(
+        &mut self,
+        router: RouterId,
+        neighbor: RouterId,
+        direction: RouteMapDirection,
+        order: i16,
+    ) -> Result<Option<RouteMap<P>>, NetworkError> {
+        let (old_map, events) = self
+            .get_internal_router_mut(router)?
+            .bgp
+            .remove_route_map(neighbor, direction, order)?;
+
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()?;
+        Ok(old_map)
+    }
+
+    /// Modify several route-maps on a single device at once. The router will first update all
+    /// route-maps, than re-run route dissemination once, and trigger several events. This function
+    /// will run the simulation afterwards (unless the network is in manual simulation mode.
+    pub fn batch_update_route_maps(
+        &mut self,
+        router: RouterId,
+        updates: &[RouteMapEdit<P>],
+    ) -> Result<(), NetworkError> {
+        let events = self
+            .get_internal_router_mut(router)?
+            .bgp
+            .batch_update_route_maps(updates)?;
+
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()?;
+        Ok(())
+    }
+
+    /// Assign the LOCAL-PREF of routes that `router` receives from `neighbor` based on their
+    /// communities. Each entry of `map` maps a community to a LOCAL-PREF. The map is compiled into
+    /// incoming route-map items, which use the orders below [`COMMUNITY_LOCAL_PREF_ORDER_END`], so
+    /// they are applied before any other route-map item of that session. Each item continues with
+    /// the next one, such that routes that carry several communities get the highest of all mapped
+    /// LOCAL-PREFs, and subsequent route-map items still apply. Routes without any mapped community
+    /// are not modified.
+    ///
+    /// The items replace all items compiled from a previous map on the same session. Pass an
+    /// empty map to remove them. The map can contain at most `COMMUNITY_LOCAL_PREF_ORDER_END -
+    /// i16::MIN` entries. Otherwise, [`NetworkError::CommunityMapTooLarge`] is returned. If any
+    /// other incoming item on that session uses an order below [`COMMUNITY_LOCAL_PREF_ORDER_END`],
+    /// the function returns [`NetworkError::ReservedRouteMapOrder`] without modifying the router.
+    /// This function runs the simulation after updating the router.
+    pub fn set_community_localpref_map(
+        &mut self,
+        router: RouterId,
+        neighbor: RouterId,
+        map: HashMap<Community, u32>,
+    ) -> Result<(), NetworkError> {
+        let max_len = COMMUNITY_LOCAL_PREF_ORDER_END.abs_diff(i16::MIN) as usize;
+        if map.len() > max_len {
+            return Err(NetworkError::CommunityMapTooLarge(map.len()));
+        }
+        let direction = RouteMapDirection::Incoming;
+
+        // remove the items compiled from the previous map.
+        let mut edits = Vec::new();
+        for old in self
+            .get_internal_router(router)?
+            .bgp
+            .get_route_maps(neighbor, direction)
+            .iter()
+            .filter(|x| x.order < COMMUNITY_LOCAL_PREF_ORDER_END)
+        {
+            if !is_community_local_pref_item(old) {
+                return Err(NetworkError::ReservedRouteMapOrder(neighbor, old.order));
+            }
+            edits.push(RouteMapEdit {
+                neighbor,
+                direction,
+                old: Some(old.clone()),
+                new: None,
+            });
+        }
+
+        // the highest LOCAL-PREF is applied last.
+        let entries = map
+            .into_iter()
+            .sorted_by_key(|(community, local_pref)| (*local_pref, *community));
+        for (order, (community, local_pref)) in (i16::MIN..).zip(entries) {
+            edits.push(RouteMapEdit {
+                neighbor,
+                direction,
+                old: None,
+                new: Some(
+                    RouteMapBuilder::new()
+                        .order_sgn(order)
+                        .allow()
+                        .match_community(community)
+                        .set_local_pref(local_pref)
+                        .continue_next()
+                        .build(),
+                ),
+            });
+        }
+
+        self.batch_update_route_maps(router, &edits)
+    }
+
+    /// Prepend the own AS of `router` to routes that it advertises to `neighbor` based on their
+    /// action communities. Each entry of `map` maps a community to the number of times the AS is
+    /// prepended. Call this function for each export session on which the action should apply.
+    /// The map is compiled into outgoing route-map items, which use the orders below
+    /// [`COMMUNITY_PREPEND_ORDER_END`], so they are applied before any other route-map item of that
+    /// session. Each item continues with the next one, such that the prepending of all
+    /// communities of a route accumulates, and subsequent route-map items still apply. Routes
+    /// without any mapped community are not modified.
+    ///
+    /// The items replace all items compiled from a previous map on the same session. Pass an
+    /// empty map to remove them. The map can contain at most `COMMUNITY_PREPEND_ORDER_END -
+    /// i16::MIN` entries. Otherwise, [`NetworkError::CommunityMapTooLarge`] is returned. If any
+    /// other outgoing item on that session uses an order below [`COMMUNITY_PREPEND_ORDER_END`], the
+    /// function returns [`NetworkError::ReservedRouteMapOrder`] without modifying the router. This
+    /// function runs the simulation after updating the router.
+    pub fn set_community_prepend_map(
+        &mut self,
+        router: RouterId,
+        neighbor: RouterId,
+        map: HashMap<Community, usize>,
+    ) -> Result<(), NetworkError> {
+        let max_len = COMMUNITY_PREPEND_ORDER_END.abs_diff(i16::MIN) as usize;
+        if map.len() > max_len {
+            return Err(NetworkError::CommunityMapTooLarge(map.len()));
+        }
+        let direction = RouteMapDirection::Outgoing;
+        let r = self.get_internal_router(router)?;
+        let as_id = r.as_id();
+
+        // remove the items compiled from the previous map.
+        let mut edits = Vec::new();
+        for old in r
+            .bgp
+            .get_route_maps(neighbor, direction)
+            .iter()
+            .filter(|x| x.order < COMMUNITY_PREPEND_ORDER_END)
+        {
+            if !is_community_prepend_item(old) {
+                return Err(NetworkError::ReservedRouteMapOrder(neighbor, old.order));
+            }
+            edits.push(RouteMapEdit {
+                neighbor,
+                direction,
+                old: Some(old.clone()),
+                new: None,
+            });
+        }
+
+        let entries = map.into_iter().sorted();
+        for (order, (community, n)) in (i16::MIN..).zip(entries) {
+            edits.push(RouteMapEdit {
+                neighbor,
+                direction,
+                old: None,
+                new: Some(
+                    RouteMapBuilder::new()
+                        .order_sgn(order)
+                        .allow()
+                        .match_community(community)
+                        .set_as_path_prepend(as_id, n)
+                        .continue_next()
+                        .build(),
+                ),
+            });
+        }
+
+        self.batch_update_route_maps(router, &edits)
+    }
+
+    /// Update or remove a static route on some router. This function will not cuase any
+    /// convergence, as the change is local only.
+    pub fn set_static_route(
+        &mut self,
+        router: RouterId,
+        prefix: P,
+        route: Option<StaticRoute>,
+    ) -> Result<Option<StaticRoute>, NetworkError> {
+        Ok(self.get_internal_router_mut(router)?.sr.set(prefix, route))
+    }
+
+    /// Redistribute `prefix` into OSPF at `router`, which then acts as an ASBR for that prefix. All
+    /// internal routers learn the external route (as a type-5 LSA) with the given `metric_type` and
+    /// `metric`, and forward traffic towards the best ASBR if they have neither a static nor a BGP
+    /// route for the prefix (see [`Router::get_ospf_external_asbr`]). The ASBR itself forwards the
+    /// traffic using its own static or BGP route. Internal routers of stub and totally stubby
+    /// areas do not see the external route, and use the default route towards the closest area
+    /// border router instead. In a NSSA, the external route is only visible if the ASBR is inside
+    /// that NSSA (as a type-7 LSA). This function will not cause any convergence, and returns the
+    /// previous redistribution of `prefix` at `router`.
+    pub fn redistribute_into_ospf(
+        &mut self,
+        router: RouterId,
+        prefix: impl Into<P>,
+        metric_type: ExternalMetricType,
+        metric: LinkWeight,
+    ) -> Result<Option<(ExternalMetricType, LinkWeight)>, NetworkError> {
+        let prefix = prefix.into();
+        self.get_internal_router(router)?;
+        if !metric.is_finite() || metric < 0.0 {
+            return Err(NetworkError::InvalidLinkWeight(router, router, metric));
+        }
+        let mut old = None;
+        for r in self.internal_routers_mut() {
+            old = r
+                .ospf_external
+                .get_mut_or_default(prefix)
+                .insert(router, (metric_type, metric));
+        }
+        Ok(old)
+    }
+
+    /// Stop redistributing `prefix` into OSPF at `router` (see
+    /// [`Network::redistribute_into_ospf`]), and return the removed redistribution.
+    pub fn remove_ospf_redistribution(
+        &mut self,
+        router: RouterId,
+        prefix: impl Into<P>,
+    ) -> Result<Option<(ExternalMetricType, LinkWeight)>, NetworkError> {
+        let prefix = prefix.into();
+        self.get_internal_router(router)?;
+        let mut old = None;
+        for r in self.internal_routers_mut() {
+            if let Some(routes) = r.ospf_external.get_mut(&prefix) {
+                old = routes.remove(&router);
+                if routes.is_empty() {
+                    r.ospf_external.remove(&prefix);
+                }
+            }
+        }
+        Ok(old)
+    }
+
+    /// Enable or disable Load Balancing on a single device in the network.
+    pub fn set_load_balancing(
+        &mut self,
+        router: RouterId,
+        do_load_balancing: bool,
+    ) -> Result<bool, NetworkError> {
+        // update the device
+        let old_val = self
+            .get_internal_router_mut(router)?
+            .set_load_balancing(do_load_balancing);
+
+        Ok(old_val)
+    }
+
+    /// Enable or disable BGP multipath on a single router, and return the old value. If enabled,
+    /// the router forwards traffic towards all routes that are equally good as the selected route,
+    /// i.e., routes that tie in the weight, LOCAL-PREF, AS path length, MED, session type, and IGP
+    /// cost, and only differ in the final tiebreakers. The forwarding state then contains one next
+    /// hop for each of these routes (see [`crate::forwarding_state::ForwardingState::get_paths`]).
+    /// The selected (and advertised) route does not change. By default, multipath is disabled.
+    pub fn set_bgp_multipath(
+        &mut self,
+        router: RouterId,
+        multipath: bool,
+    ) -> Result<bool, NetworkError> {
+        Ok(self
+            .get_internal_router_mut(router)?
+            .bgp
+            .set_bgp_multipath(multipath))
+    }
+
+    /// Configure whether BGP multipath on `router` only combines routes received from the same
+    /// neighboring AS (the first AS in the AS path), and return the old value. By default, routes
+    /// from different neighboring ASes are combined (see [`Network::set_bgp_multipath`]).
+    pub fn set_bgp_multipath_same_as(
+        &mut self,
+        router: RouterId,
+        same_as: bool,
+    ) -> Result<bool, NetworkError> {
+        Ok(self
+            .get_internal_router_mut(router)?
+            .bgp
+            .set_bgp_multipath_same_as(same_as))
+    }
+
+    /// Enable RPKI origin validation on a router. The router will validate the origin of all
+    /// incoming routes using the ROA `table`, and store the result in [`BgpRibEntry::rpki`].
+    /// `Invalid` routes are treated according to `policy`. This function returns the old
+    /// configuration, and runs the simulation after updating the router.
+    ///
+    /// To disable origin validation, use [`Network::remove_rpki`].
+    pub fn set_rpki(
+        &mut self,
+        router: RouterId,
+        table: RoaTable<P>,
+        policy: RpkiPolicy,
+    ) -> Result<Option<(RoaTable<P>, RpkiPolicy)>, NetworkError> {
+        let (old, events) = self
+            .get_internal_router_mut(router)?
+            .bgp
+            .set_rpki(Some((table, policy)))?;
+
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()?;
+        Ok(old)
+    }
+
+    /// Enable soft RPKI origin validation on a router, modeling a cautious operator. Instead of
+    /// dropping `Invalid` routes, the router lowers their local-pref by `invalid_localpref_delta`,
+    /// and tags them with the community `tag` (see [`RpkiPolicy::SoftInvalid`]). Hence, an
+    /// `Invalid` route is only selected if no better alternative exists. This function returns the
+    /// old configuration, and runs the simulation after updating the router.
+    pub fn set_rpki_soft(
+        &mut self,
+        router: RouterId,
+        table: RoaTable<P>,
+        invalid_localpref_delta: u32,
+        tag: impl Into<Community>,
+    ) -> Result<Option<(RoaTable<P>, RpkiPolicy)>, NetworkError> {
+        self.set_rpki(
+            router,
+            table,
+            RpkiPolicy::SoftInvalid {
+                delta: invalid_localpref_delta,
+                community: tag.into(),
+            },
+        )
+    }
+
+    /// Disable RPKI origin validation on a router. This function returns the old configuration,
+    /// and runs the simulation after updating the router.
+    pub fn remove_rpki(
+        &mut self,
+        router: RouterId,
+    ) -> Result<Option<(RoaTable<P>, RpkiPolicy)>, NetworkError> {
+        let (old, events) = self.get_internal_router_mut(router)?.bgp.set_rpki(None)?;
+
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()?;
+        Ok(old)
+    }
+
+    /// Perform a local repair on `router` after its BGP next-hop `failed_nh` has failed (BGP
+    /// Prefix-Independent Convergence). The router immediately switches all prefixes that use
+    /// `failed_nh` to their precomputed backup route (see [`ForwardingState::backup_next_hop`]),
+    /// without waiting for BGP to reconverge. The router then advertises the new routes to its
+    /// peers, and the network is simulated as usual. This function returns the prefixes that
+    /// were switched to a backup route.
+    ///
+    /// The repair is only local. As soon as the router runs its decision process for an affected
+    /// prefix again, it selects the best route it knows.
+    pub fn local_protect(
+        &mut self,
+        router: RouterId,
+        failed_nh: RouterId,
+    ) -> Result<Vec<P>, NetworkError> {
+        let (switched, events) = self
+            .get_internal_router_mut(router)?
+            .bgp
+            .local_protect(failed_nh)?;
+
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()?;
+        Ok(switched)
+    }
+
+    /// Set the default attributes of routes that `router` receives from `neighbor`, or remove them
+    /// by passing `None`. The router fills all attributes of received routes that are not set with
+    /// the defaults, before applying any incoming route-map. This function returns the old value,
+    /// and runs the simulation after updating the router.
+    pub fn set_session_default_attributes(
+        &mut self,
+        router: RouterId,
+        neighbor: RouterId,
+        defaults: Option<BgpSessionDefaults>,
+    ) -> Result<Option<BgpSessionDefaults>, NetworkError> {
+        let (old, events) = self
+            .get_internal_router_mut(router)?
+            .bgp
+            .set_session_defaults(neighbor, defaults)?;
+
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()?;
+        Ok(old)
+    }
+
+    /// Enable or disable next-hop-self on the session from `router` to the iBGP peer `neighbor`,
+    /// and return the old value. With next-hop-self (the default), `router` sets the next-hop of
+    /// all routes learned over eBGP to itself before advertising them to `neighbor`. Without it,
+    /// `neighbor` receives the next-hop of the eBGP route, and it must resolve that next-hop in
+    /// the IGP. This function runs the simulation after updating the router.
+    pub fn set_next_hop_self(
+        &mut self,
+        router: RouterId,
+        neighbor: RouterId,
+        enabled: bool,
+    ) -> Result<bool, NetworkError> {
+        let (old, events) = self
+            .get_internal_router_mut(router)?
+            .bgp
+            .set_next_hop_self(neighbor, enabled)?;
+
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()?;
+        Ok(old)
     }
 
-    /// Set BGP sessions from an iterator.
-    pub fn set_bgp_session_from<I>(&mut self, sessions: I) -> Result<(), NetworkError>
-    where
-        I: IntoIterator<Item = (RouterId, RouterId, Option<BgpSessionType>)>,
-    {
-        for (source, target, session_type) in sessions.into_iter() {
-            self._set_bgp_session(source, target, session_type)?;
+    /// Enable optimal route reflection (ORR, RFC 9107) on the route reflector `rr` towards
+    /// `client`, or disable it by passing `None`. With ORR, `rr` selects the route that it
+    /// advertises to `client` from the IGP view of `igp_root` (typically, the client itself)
+    /// rather than its own. Hence, `client` learns the egress closest to `igp_root`, just as in an
+    /// iBGP full mesh, and avoids the suboptimal hot-potato routing caused by route reflection.
+    /// Only the comparison of IGP costs changes; all other steps of the decision process are
+    /// unchanged. The route selected by `rr` itself is not affected.
+    ///
+    /// `rr` keeps track of the IGP costs from `igp_root` whenever the IGP changes. This function
+    /// returns the old root, and runs the simulation after updating the router.
+    pub fn set_orr(
+        &mut self,
+        rr: RouterId,
+        client: RouterId,
+        igp_root: Option<RouterId>,
+    ) -> Result<Option<RouterId>, NetworkError> {
+        if let Some(root) = igp_root {
+            let igp_cost = self.orr_igp_cost(root)?;
+            let events = self
+                .get_internal_router_mut(rr)?
+                .bgp
+                .update_orr_igp(root, igp_cost)?;
+            self.enqueue_events(events);
         }
+        let (old, events) = self
+            .get_internal_router_mut(rr)?
+            .bgp
+            .set_orr(client, igp_root)?;
 
-        // refresh the active BGP sessions in the network
-        self.refresh_bgp_sessions()?;
-        self.do_queue_maybe_skip()
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()?;
+        Ok(old)
     }
 
-    /// set the link weight to the desired value. `NetworkError::LinkNotFound` is returned if
-    /// the link does not exist. Otherwise, the old link weight is returned. Note, that this
-    /// function only sets the *directed* link weight, and the other direction (from `target` to
-    /// `source`) is not affected.
-    ///
-    /// This function will also update the IGP forwarding table *and* run the simulation.
-    pub fn set_link_weight(
+    /// Get the IGP cost from the internal router `root` towards all other routers.
+    fn orr_igp_cost(&self, root: RouterId) -> Result<HashMap<RouterId, LinkWeight>, NetworkError> {
+        Ok(self
+            .get_internal_router(root)?
+            .ospf
+            .get_table()
+            .iter()
+            .map(|(r, (_, cost))| (*r, *cost))
+            .collect())
+    }
+
+    /// Drain traffic from the session between `router` and `neighbor` before shutting it down for
+    /// maintenance (graceful shutdown, RFC 8326). `router` tags all routes that it receives from
+    /// or advertises to `neighbor` with the [`GRACEFUL_SHUTDOWN`](crate::bgp::GRACEFUL_SHUTDOWN)
+    /// community. All routers lower the LOCAL-PREF of routes carrying that community to 0, such
+    /// that traffic moves to alternative paths (if any exist). Withdrawing the routes afterwards
+    /// causes no additional disruption. This function returns whether the session was already in
+    /// graceful shutdown, and runs the simulation after updating the router.
+    pub fn graceful_shutdown(
         &mut self,
-        source: RouterId,
-        target: RouterId,
-        weight: LinkWeight,
-    ) -> Result<LinkWeight, NetworkError> {
-        // throw an error if the link does not exist.
-        self.net
-            .find_edge(source, target)
-            .ok_or(NetworkError::LinkNotFound(source, target))?;
+        router: RouterId,
+        neighbor: RouterId,
+    ) -> Result<bool, NetworkError> {
+        let (old, events) = self
+            .get_internal_router_mut(router)?
+            .bgp
+            .set_graceful_shutdown(neighbor, true)?;
 
-        let (events, old_weight) =
-            self.ospf
-                .set_weight(source, target, weight, &mut self.routers)?;
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()?;
+        Ok(old)
+    }
+
+    /// Stop the graceful shutdown of the session between `router` and `neighbor` (see
+    /// [`Network::graceful_shutdown`]), and return whether the session was in graceful shutdown.
+    /// This function runs the simulation after updating the router.
+    pub fn end_graceful_shutdown(
+        &mut self,
+        router: RouterId,
+        neighbor: RouterId,
+    ) -> Result<bool, NetworkError> {
+        let (old, events) = self
+            .get_internal_router_mut(router)?
+            .bgp
+            .set_graceful_shutdown(neighbor, false)?;
 
-        // update the forwarding tables and simulate the network.
         self.enqueue_events(events);
-        self.refresh_bgp_sessions()?;
         self.do_queue_maybe_skip()?;
+        Ok(old)
+    }
 
-        Ok(old_weight)
+    /// Overwrite which attributes `router` propagates to `neighbor`, or restore the default by
+    /// passing `None`, and return the old value. By default, all attributes are propagated over
+    /// iBGP, while the MED and the LOCAL-PREF are not propagated over eBGP (see
+    /// [`AttributePropagation::for_session`]). This allows, e.g., stripping communities on a
+    /// specific eBGP session, or sending the MED to an external neighbor. This function runs the
+    /// simulation after updating the router.
+    pub fn set_attribute_propagation(
+        &mut self,
+        router: RouterId,
+        neighbor: RouterId,
+        propagation: Option<AttributePropagation>,
+    ) -> Result<Option<AttributePropagation>, NetworkError> {
+        let (old, events) = self
+            .get_internal_router_mut(router)?
+            .bgp
+            .set_attribute_propagation(neighbor, propagation)?;
+
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()?;
+        Ok(old)
     }
 
-    /// Set many link weights simultaneously. `NetworkError::LinkNotFound` is returned if any link
-    /// does not exist. Note, that this function only sets the *directed* link weight, and the other
-    /// direction (from `target` to `source`) is not affected.
+    /// Enable or disable the minimum route advertisement interval (MRAI) on the session from
+    /// `router` to `neighbor`, and return the old value. With MRAI enabled, `router` coalesces all
+    /// updates for the same prefix towards `neighbor` while an earlier update is still in flight.
+    /// Once delivered, `neighbor` receives only the most recent update. As the network has no
+    /// notion of time in general, the interval spans the time until the earlier update is
+    /// processed.
     ///
-    /// This function will also update the IGP forwarding table *and* run the simulation.
-    pub fn set_link_weights_from<I>(&mut self, weights: I) -> Result<(), NetworkError>
-    where
-        I: IntoIterator<Item = (RouterId, RouterId, LinkWeight)>,
-    {
-        let weights = weights.into_iter().collect::<Vec<_>>();
-        for (source, target, _) in weights.iter() {
-            if self.net.find_edge(*source, *target).is_none() {
-                return Err(NetworkError::LinkNotFound(*source, *target));
-            }
-        }
+    /// MRAI reduces the number of BGP messages and affects transient states, but not the converged
+    /// state.
+    pub fn set_mrai(
+        &mut self,
+        router: RouterId,
+        neighbor: RouterId,
+        enabled: bool,
+    ) -> Result<bool, NetworkError> {
+        Ok(self
+            .get_internal_router_mut(router)?
+            .bgp
+            .set_mrai(neighbor, enabled))
+    }
 
-        let events = self
-            .ospf
-            .set_link_weights_from(weights, &mut self.routers)?;
+    /// Set the options of the session from `router` to `neighbor`, or remove them by passing
+    /// `None`, and return the old value. The options, like the session password or the MTU, do not
+    /// affect the simulation. They are only included in the exported configuration.
+    pub fn set_session_options(
+        &mut self,
+        router: RouterId,
+        neighbor: RouterId,
+        options: Option<BgpSessionOptions>,
+    ) -> Result<Option<BgpSessionOptions>, NetworkError> {
+        Ok(self
+            .get_internal_router_mut(router)?
+            .bgp
+            .set_session_options(neighbor, options))
+    }
+
+    /// Define the peer-group `name` with the given `template`, or update its template if it is
+    /// already defined, and return the old template. All sessions that were configured from that
+    /// peer-group (see [`Network::add_session_from_group`]) are updated to the new template, and
+    /// the network converges once afterwards. Settings of member sessions that were changed
+    /// individually are overwritten by the template.
+    pub fn define_peer_group(
+        &mut self,
+        name: impl Into<String>,
+        template: BgpPeerGroup<P>,
+    ) -> Result<Option<BgpPeerGroup<P>>, NetworkError> {
+        let name = name.into();
+        let old = self.peer_groups.insert(name.clone(), template.clone());
+        let members = self
+            .peer_group_members
+            .iter()
+            .filter(|(_, group)| **group == name)
+            .map(|(session, _)| *session)
+            .sorted()
+            .collect_vec();
+
+        let old_skip = self.skip_queue;
+        self.skip_queue = true;
+        let result = members
+            .into_iter()
+            .try_for_each(|(router, neighbor)| self.apply_peer_group(router, neighbor, &template));
+        self.skip_queue = old_skip;
+        result?;
 
-        // update the forwarding tables and simulate the network.
-        self.enqueue_events(events);
         self.refresh_bgp_sessions()?;
         self.do_queue_maybe_skip()?;
-
-        Ok(())
+        Ok(old)
     }
 
-    /// Set the OSPF area of a specific link to the desired value. `NetworkError::LinkNotFound` is
-    /// returned if the link does not exist. Otherwise, the old OSPF area is returned. This function
-    /// sets the area of both links in both directions.
-    ///
-    /// This function will also update the IGP forwarding table *and* run the simulation.
-    pub fn set_ospf_area(
+    /// Configure the BGP session from the internal `router` to `neighbor` using the template of
+    /// the peer-group `group` (see [`Network::define_peer_group`]). This establishes the session
+    /// with the session type of the template, replaces all route-maps of `router` towards
+    /// `neighbor` with those of the template, and applies next-hop-self, the default attributes,
+    /// and the session options. The session remains a member of the peer-group until it is
+    /// removed. This function returns [`NetworkError::UnknownPeerGroup`] if the peer-group is not
+    /// defined, and runs the simulation once after configuring the session.
+    pub fn add_session_from_group(
         &mut self,
-        source: RouterId,
-        target: RouterId,
-        area: impl Into<OspfArea>,
-    ) -> Result<OspfArea, NetworkError> {
-        // throw an error if the link does not exist.
-        self.net
-            .find_edge(source, target)
-            .ok_or(NetworkError::LinkNotFound(source, target))?;
+        router: RouterId,
+        neighbor: RouterId,
+        group: &str,
+    ) -> Result<(), NetworkError> {
+        let template = self
+            .peer_groups
+            .get(group)
+            .cloned()
+            .ok_or_else(|| NetworkError::UnknownPeerGroup(group.to_string()))?;
+        self.get_internal_router(router)?;
 
-        let (events, old_area) =
-            self.ospf
-                .set_area(source, target, area.into(), &mut self.routers)?;
+        let old_skip = self.skip_queue;
+        self.skip_queue = true;
+        let result = self.apply_peer_group(router, neighbor, &template);
+        self.skip_queue = old_skip;
+        result?;
+        self.peer_group_members
+            .insert((router, neighbor), group.to_string());
 
-        // update the forwarding tables and simulate the network.
-        self.enqueue_events(events);
         self.refresh_bgp_sessions()?;
-        self.do_queue_maybe_skip()?;
+        self.do_queue_maybe_skip()
+    }
 
-        Ok(old_area)
+    /// Configure the session from `router` to `neighbor` according to the peer-group `template`,
+    /// without refreshing the BGP sessions and without running the simulation.
+    fn apply_peer_group(
+        &mut self,
+        router: RouterId,
+        neighbor: RouterId,
+        template: &BgpPeerGroup<P>,
+    ) -> Result<(), NetworkError> {
+        self._set_bgp_session(router, neighbor, Some(template.session_type))?;
+
+        let bgp = &mut self.get_internal_router_mut(router)?.bgp;
+        let edit = |direction, old, new| RouteMapEdit {
+            neighbor,
+            direction,
+            old,
+            new,
+        };
+        let updates = bgp
+            .get_route_maps(neighbor, RouteMapDirection::Incoming)
+            .iter()
+            .map(|m| edit(RouteMapDirection::Incoming, Some(m.clone()), None))
+            .chain(
+                bgp.get_route_maps(neighbor, RouteMapDirection::Outgoing)
+                    .iter()
+                    .map(|m| edit(RouteMapDirection::Outgoing, Some(m.clone()), None)),
+            )
+            .chain(
+                template
+                    .route_maps_in
+                    .iter()
+                    .map(|m| edit(RouteMapDirection::Incoming, None, Some(m.clone()))),
+            )
+            .chain(
+                template
+                    .route_maps_out
+                    .iter()
+                    .map(|m| edit(RouteMapDirection::Outgoing, None, Some(m.clone()))),
+            )
+            .collect_vec();
+
+        let mut events = bgp.batch_update_route_maps(&updates)?;
+        events.extend(bgp.set_next_hop_self(neighbor, template.next_hop_self)?.1);
+        events.extend(bgp.set_session_defaults(neighbor, template.defaults)?.1);
+        bgp.set_session_options(neighbor, template.options.clone());
+
+        self.enqueue_events(events);
+        Ok(())
     }
 
-    /// Set the route map on a router in the network. If a route-map with the chosen order already
-    /// exists, then it will be overwritten. The old route-map will be returned. This function will
-    /// run the simulation after updating the router.
+    /// Freeze `router`, such that it stops processing incoming events until it is thawed again
+    /// using [`Network::thaw_router`]. All events addressed to `router` are held back, and the
+    /// router keeps forwarding traffic according to its last forwarding table. This models a stuck
+    /// or overloaded route processor. Freezing a router that is already frozen has no effect.
     ///
-    /// To remove a route map, use [`Network::remove_bgp_route_map`].
-    pub fn set_bgp_route_map(
+    /// Only message passing is affected by freezing a router. Configuration changes are still
+    /// applied immediately, and with [`GlobalOspf`], the IGP state of the frozen router is still
+    /// updated atomically.
+    pub fn freeze_router(&mut self, router: RouterId) -> Result<(), NetworkError> {
+        self.get_internal_router(router)?;
+        self.frozen.entry(router).or_default();
+        Ok(())
+    }
+
+    /// Thaw a router that was frozen using [`Network::freeze_router`]. All events that were held
+    /// back are enqueued again (in the order in which they arrived), and the network is simulated.
+    /// Thawing a router that is not frozen has no effect.
+    pub fn thaw_router(&mut self, router: RouterId) -> Result<(), NetworkError> {
+        self.get_internal_router(router)?;
+        let Some(held) = self.frozen.remove(&router) else {
+            return Ok(());
+        };
+        self.enqueue_events(
+            held.into_iter()
+                .map(|e| e.with_priority(Default::default()))
+                .collect(),
+        );
+        self.do_queue_maybe_skip()
+    }
+
+    /// Returns `true` if `router` is frozen (see [`Network::freeze_router`]).
+    pub fn is_frozen(&self, router: RouterId) -> bool {
+        self.frozen.contains_key(&router)
+    }
+
+    /// Set the AS number that `router` presents to `neighbor` (`local-as`), or remove it by passing
+    /// `None`. This only affects eBGP sessions. The router prepends `asn` instead of its own AS
+    /// number to all routes advertised to `neighbor`, and uses it for AS-path loop detection on
+    /// routes received from `neighbor`. Internally, the router still uses its own AS number. This
+    /// function returns the old value, and runs the simulation after updating the router.
+    pub fn set_local_as(
         &mut self,
         router: RouterId,
         neighbor: RouterId,
-        direction: RouteMapDirection,
-        route_map: RouteMap<P>,
-    ) -> Result<Option<RouteMap<P>>, NetworkError> {
-        let (old_map, events) = self
+        asn: Option<AsId>,
+    ) -> Result<Option<AsId>, NetworkError> {
+        let (old, events) = self
             .get_internal_router_mut(router)?
             .bgp
-            .set_route_map(neighbor, direction, route_map)?;
+            .set_local_as(neighbor, asn)?;
 
         self.enqueue_events(events);
         self.do_queue_maybe_skip()?;
-        Ok(old_map)
+        Ok(old)
     }
 
-    /// Remove the route map on a router in the network. The old route-map will be returned. This
-    /// function will run the simulation after updating the router.
-    ///
-    /// To add a route map, use [`Network::set_bgp_route_map`].
-    pub fn remove_bgp_route_map(
+    /// Allow `router` to accept routes from the eBGP `neighbor` whose AS path contains its own AS
+    /// number (or the one configured with [`Network::set_local_as`]) up to `n` times
+    /// (`allowas-in`). This is required in hub-and-spoke topologies, where the spokes share the
+    /// same AS number. Setting `n` to 0 (the default) restores the standard AS-path loop
+    /// detection. This function returns the old value, and runs the simulation after updating the
+    /// router.
+    pub fn set_allowas_in(
         &mut self,
         router: RouterId,
         neighbor: RouterId,
-        direction: RouteMapDirection,
-        order: i16,
-    ) -> Result<Option<RouteMap<P>>, NetworkError> {
-        let (old_map, events) = self
+        n: usize,
+    ) -> Result<usize, NetworkError> {
+        let (old, events) = self
             .get_internal_router_mut(router)?
             .bgp
-            .remove_route_map(neighbor, direction, order)?;
+            .set_allowas_in(neighbor, n)?;
 
         self.enqueue_events(events);
         self.do_queue_maybe_skip()?;
-        Ok(old_map)
+        Ok(old)
     }
 
-    /// Modify several route-maps on a single device at once. The router will first update all
-    /// route-maps, than re-run route dissemination once, and trigger several events. This function
-    /// will run the simulation afterwards (unless the network is in manual simulation mode.
-    pub fn batch_update_route_maps(
+    /// Filter the routes that `router` exchanges with `neighbor` in the given `direction` by their
+    /// prefix (`distribute-list`). If `permit` is set, only routes for the given `prefixes` are
+    /// accepted (or advertised). Otherwise, routes for these prefixes are dropped. The
+    /// distribute-list is applied before any route-map of the same session and direction. It
+    /// replaces any distribute-list configured before. This function returns the old
+    /// distribute-list, and runs the simulation after updating the router.
+    pub fn set_distribute_list(
         &mut self,
         router: RouterId,
-        updates: &[RouteMapEdit<P>],
-    ) -> Result<(), NetworkError> {
-        let events = self
+        neighbor: RouterId,
+        direction: RouteMapDirection,
+        prefixes: impl IntoIterator<Item = P>,
+        permit: bool,
+    ) -> Result<Option<DistributeList<P>>, NetworkError> {
+        let list = DistributeList::new(prefixes, permit);
+        let (old, events) = self
             .get_internal_router_mut(router)?
             .bgp
-            .batch_update_route_maps(updates)?;
+            .set_distribute_list(neighbor, direction, Some(list))?;
 
         self.enqueue_events(events);
         self.do_queue_maybe_skip()?;
-        Ok(())
+        Ok(old)
     }
 
-    /// Update or remove a static route on some router. This function will not cuase any
-    /// convergence, as the change is local only.
-    pub fn set_static_route(
+    /// Remove the distribute-list that `router` applies on routes exchanged with `neighbor` in the
+    /// given `direction` (see [`Network::set_distribute_list`]). This function returns the old
+    /// distribute-list, and runs the simulation after updating the router.
+    pub fn remove_distribute_list(
         &mut self,
         router: RouterId,
-        prefix: P,
-        route: Option<StaticRoute>,
-    ) -> Result<Option<StaticRoute>, NetworkError> {
-        Ok(self.get_internal_router_mut(router)?.sr.set(prefix, route))
+        neighbor: RouterId,
+        direction: RouteMapDirection,
+    ) -> Result<Option<DistributeList<P>>, NetworkError> {
+        let (old, events) = self
+            .get_internal_router_mut(router)?
+            .bgp
+            .set_distribute_list(neighbor, direction, None)?;
+
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()?;
+        Ok(old)
     }
 
-    /// Enable or disable Load Balancing on a single device in the network.
-    pub fn set_load_balancing(
+    /// Set the BGP router-id of `router` (either an internal or an external router), or reset it
+    /// to its default value by passing `None` (see [`crate::bgp::default_bgp_id`]). All routers
+    /// use the router-id of the originator (or the neighbor) of a route as the tiebreaker in the
+    /// decision process that follows the IGP cost. This function returns the old value, and
+    /// runs the simulation after updating all routers.
+    pub fn set_router_id(
         &mut self,
         router: RouterId,
-        do_load_balancing: bool,
-    ) -> Result<bool, NetworkError> {
-        // update the device
-        let old_val = self
-            .get_internal_router_mut(router)?
-            .set_load_balancing(do_load_balancing);
+        id: Option<Ipv4Addr>,
+    ) -> Result<Option<Ipv4Addr>, NetworkError> {
+        self.get_device(router)?;
+        let mut old = None;
+        for r in self.internal_indices().detach() {
+            let (r_old, events) = self
+                .get_internal_router_mut(r)?
+                .bgp
+                .set_bgp_id(router, id)?;
+            old = r_old;
+            self.enqueue_events(events);
+        }
+        self.do_queue_maybe_skip()?;
+        Ok(old)
+    }
 
-        Ok(old_val)
+    /// Define the named AS-path access-list (`ip as-path access-list` in Cisco terms) on all
+    /// internal routers, or replace it if it already exists. Route-maps reference the list using
+    /// [`crate::route_map::RouteMapMatch::AsPathList`] (see
+    /// [`crate::route_map::RouteMapBuilder::match_as_path_list`]). The entries are checked in
+    /// order, and the first entry that matches the AS path decides whether the route is permitted.
+    /// Routes that match no entry are denied. Updating the list re-evaluates all route-maps that
+    /// reference it. This function returns the old list, and runs the simulation after updating
+    /// all routers.
+    pub fn define_as_path_list(
+        &mut self,
+        name: impl Into<String>,
+        entries: impl IntoIterator<Item = (RouteMapState, RouteMapMatchAsPath)>,
+    ) -> Result<Option<AsPathList>, NetworkError> {
+        let name = name.into();
+        let list = AsPathList::new(entries);
+        let mut old = None;
+        for r in self.internal_indices().detach() {
+            let (r_old, events) = self
+                .get_internal_router_mut(r)?
+                .bgp
+                .set_as_path_list(name.clone(), Some(list.clone()))?;
+            old = r_old;
+            self.enqueue_events(events);
+        }
+        self.do_queue_maybe_skip()?;
+        Ok(old)
     }
 
     /// Advertise an external route and let the network converge, The source must be a `RouterId`
@@ -728,12 +3449,12 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Network<P, Q, Ospf> {
         prefix: impl Into<P>,
         as_path: A,
         med: Option<u32>,
-        community: C,
+        communities: C,
     ) -> Result<(), NetworkError>
     where
         A: IntoIterator,
         A::Item: Into<AsId>,
-        C: IntoIterator<Item = u32>,
+        C: IntoIterator<Item = Community>,
     {
         let prefix: P = prefix.into();
         let as_path: Vec<AsId> = as_path.into_iter().map(|id| id.into()).collect();
@@ -743,18 +3464,97 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Network<P, Q, Ospf> {
             prefix,
             self.get_device(source)?.name()
         );
+        let r = self.get_external_router(source)?;
+        if r.is_receive_only() {
+            return Err(NetworkError::ReceiveOnly(source));
+        }
+        if r.is_route_server() {
+            return Err(NetworkError::RouteServer(source));
+        }
+        // insert the prefix into the hashset
+        self.known_prefixes.insert(prefix);
+
+        // initiate the advertisement
+        let (_, events) = self.get_external_router_mut(source)?.advertise_prefix(
+            prefix,
+            as_path,
+            med,
+            communities,
+        );
+
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()
+    }
+
+    /// Advertise an external route with a third-party next-hop, and let the network converge. This
+    /// function is equivalent to [`Network::advertise_external_route`], except that the route
+    /// carries `next_hop` instead of the address of `source`. The ingress router keeps that
+    /// next-hop, and it can only use the route if it can reach `next_hop` in the IGP. Unless
+    /// next-hop-self is enabled (see [`Network::set_next_hop_self`]), iBGP peers receive the route
+    /// with `next_hop` as well.
+    pub fn advertise_external_route_with_next_hop<A, C>(
+        &mut self,
+        source: RouterId,
+        prefix: impl Into<P>,
+        as_path: A,
+        med: Option<u32>,
+        communities: C,
+        next_hop: RouterId,
+    ) -> Result<(), NetworkError>
+    where
+        A: IntoIterator,
+        A::Item: Into<AsId>,
+        C: IntoIterator<Item = Community>,
+    {
+        let prefix: P = prefix.into();
+        let as_path: Vec<AsId> = as_path.into_iter().map(|id| id.into()).collect();
+        self.get_device(next_hop)?;
+        let r = self.get_external_router(source)?;
+        if r.is_receive_only() {
+            return Err(NetworkError::ReceiveOnly(source));
+        }
+        if r.is_route_server() {
+            return Err(NetworkError::RouteServer(source));
+        }
+
+        debug!(
+            "Advertise {} on {} with next-hop {}",
+            prefix,
+            self.get_device(source)?.name(),
+            self.get_device(next_hop)?.name(),
+        );
         // insert the prefix into the hashset
         self.known_prefixes.insert(prefix);
 
         // initiate the advertisement
-        let (_, events) = self
-            .get_external_router_mut(source)?
-            .advertise_prefix(prefix, as_path, med, community);
+        let route = BgpRoute::new(next_hop, prefix, as_path, med, communities);
+        let (_, events) = self.get_external_router_mut(source)?.advertise_route(route);
 
         self.enqueue_events(events);
         self.do_queue_maybe_skip()
     }
 
+    /// Configure whether the external router `router` is a receive-only route collector. A
+    /// receive-only router accepts all routes from its eBGP neighbors and stores them (see
+    /// [`ExternalRouter::get_received_routes`]), but it never advertises any route. Hence, it can
+    /// never be selected as an egress. When enabling this option, the router withdraws all routes
+    /// it currently advertises, and advertising new routes will fail with
+    /// [`NetworkError::ReceiveOnly`]. When disabling it, the router forgets all received routes.
+    /// This function returns the old value, and runs the simulation after updating the router.
+    pub fn set_receive_only(
+        &mut self,
+        router: RouterId,
+        receive_only: bool,
+    ) -> Result<bool, NetworkError> {
+        let (old, events) = self
+            .get_external_router_mut(router)?
+            .set_receive_only(receive_only);
+
+        self.enqueue_events(events);
+        self.do_queue_maybe_skip()?;
+        Ok(old)
+    }
+
     /// Withdraw an external route and let the network converge. The source must be a `RouterId` of
     /// an `ExternalRouter`. All current eBGP neighbors will receive a withdraw message.
     ///
@@ -777,6 +3577,97 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Network<P, Q, Ospf> {
         self.do_queue_maybe_skip()
     }
 
+    /// Reset the dynamic BGP state of the network, while keeping the topology, link weights, BGP
+    /// sessions, and the configuration of all routers. All external routers forget their
+    /// advertised routes (without sending any withdraw messages), all BGP tables are cleared,
+    /// and all events in the queue are dropped. Afterwards, the network is in the same state as
+    /// if it was only configured without advertising any route. Use this function to re-advertise
+    /// routes and converge from scratch without rebuilding the network.
+    pub fn reset(&mut self) {
+        self.queue.clear();
+        self.known_prefixes = Default::default();
+        self.mrai_pending.clear();
+        self.frozen.values_mut().for_each(|held| held.clear());
+        self.event_timeline = Default::default();
+        for r in self.routers.values_mut() {
+            match r {
+                NetworkDevice::InternalRouter(r) => r.bgp.reset(),
+                NetworkDevice::ExternalRouter(r) => r.reset(),
+            }
+        }
+    }
+
+    /// Warm-start the network from the converged state of `previous`, instead of converging from
+    /// scratch. Both networks must have the same routers, links, and configuration, and they may
+    /// only differ in the routes advertised by external routers. The network adopts the BGP and
+    /// OSPF state of `previous`, drops all events in its queue, and then only advertises (or
+    /// withdraws) the routes that differ from those in `previous`. Hence, the queue only carries
+    /// the deltas. Once the network has converged, it is in the same state as if it had converged
+    /// from scratch (as long as the converged state is unique).
+    ///
+    /// This function returns [`NetworkError::InvalidWarmStart`] if the topology or configuration
+    /// differs, or if `previous` has not yet converged.
+    pub fn warm_start(&mut self, previous: &Self) -> Result<(), NetworkError> {
+        let links = |net: &Self| -> HashSet<(RouterId, RouterId)> {
+            net.net
+                .edge_indices()
+                .filter_map(|e| net.net.edge_endpoints(e))
+                .collect()
+        };
+        if !previous.queue.is_empty()
+            || self.device_indices().collect::<HashSet<_>>()
+                != previous.device_indices().collect::<HashSet<_>>()
+            || links(self) != links(previous)
+            || self.get_config()? != previous.get_config()?
+            || self.internal_routers().any(|r| {
+                previous
+                    .get_internal_router(r.router_id())
+                    .map_or(true, |p| !r.bgp.config_eq(&p.bgp))
+            })
+        {
+            return Err(NetworkError::InvalidWarmStart);
+        }
+
+        // remember the routes that should be advertised
+        let target: Vec<(RouterId, Vec<BgpRoute<P>>)> = self
+            .external_routers()
+            .map(|r| {
+                let routes = r.get_advertised_routes().iter().map(|(_, r)| r.clone());
+                (r.router_id(), routes.collect())
+            })
+            .collect();
+
+        // adopt the state of the previous network
+        self.queue.clear();
+        self.mrai_pending.clear();
+        self.routers = previous.routers.clone();
+        self.ospf = previous.ospf.clone();
+        self.known_prefixes = previous.known_prefixes.clone();
+
+        // only advertise the differences
+        for (r, routes) in target {
+            for route in routes.iter() {
+                self.known_prefixes.insert(route.prefix);
+            }
+            let ext = self.get_external_router_mut(r)?;
+            let withdrawn = ext
+                .advertised_prefixes()
+                .filter(|p| !routes.iter().any(|route| route.prefix == **p))
+                .copied()
+                .collect::<Vec<_>>();
+            let mut events = Vec::new();
+            for p in withdrawn {
+                events.extend(ext.withdraw_prefix(p));
+            }
+            for route in routes {
+                events.extend(ext.advertise_route(route).1);
+            }
+            self.enqueue_events(events);
+        }
+
+        self.do_queue_maybe_skip()
+    }
+
     /// Remove a link from the network. The network will update the IGP forwarding table, and
     /// perform the BGP decision process, which will cause a convergence process. This function
     /// will also automatically handle the convergence process.
@@ -802,6 +3693,9 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Network<P, Q, Ospf> {
         let events = self
             .ospf
             .remove_link(router_a, router_b, &mut self.routers)?;
+        self.link_bundles.remove(&bundle_key(router_a, router_b));
+        self.link_data.remove(&bundle_key(router_a, router_b));
+        self.srlgs.remove(&bundle_key(router_a, router_b));
 
         self.enqueue_events(events);
         self.refresh_bgp_sessions()?;
@@ -836,7 +3730,16 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Network<P, Q, Ospf> {
 
         // remove the node from the list
         self.routers.remove(&router);
+        self.frozen.remove(&router);
         self.net.remove_node(router);
+        self.link_bundles
+            .retain(|(a, b), _| *a != router && *b != router);
+        self.router_data.remove(&router);
+        self.link_data
+            .retain(|(a, b), _| *a != router && *b != router);
+        self.srlgs.retain(|(a, b), _| *a != router && *b != router);
+        self.peer_group_members
+            .retain(|(a, b), _| *a != router && *b != router);
 
         // simulate all remaining events
         self.do_queue_maybe_skip()?;
@@ -854,6 +3757,21 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Network<P, Q, Ospf> {
     // * Local Functions *
     // *******************
 
+    /// Check that a link between `a` and `b` can be added to the network. Both routers must exist,
+    /// a router cannot be connected to itself, and two external routers cannot be connected.
+    fn check_new_link(&self, a: RouterId, b: RouterId) -> Result<(), NetworkError> {
+        let a_external = self.routers.get(&a).or_router_not_found(a)?.is_external();
+        let b_external = self.routers.get(&b).or_router_not_found(b)?.is_external();
+        if a == b {
+            return Err(NetworkError::CannotConnectToItself(a));
+        }
+        // ensure that an external router is only ever connected to a single internal one
+        if a_external && b_external {
+            return Err(NetworkError::CannotConnectExternalRouters(a, b));
+        }
+        Ok(())
+    }
+
     /// Private function that sets the session, but does not yet compute which sessions are actually
     /// active, and it does not run the queue
     fn _set_bgp_session(
@@ -864,6 +3782,9 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Network<P, Q, Ospf> {
     ) -> Result<(), NetworkError> {
         let is_source_external = self.get_device(source)?.is_external();
         let is_target_external = self.get_device(target)?.is_external();
+        if let (true, Some(ty)) = (source == target, session_type) {
+            return Err(NetworkError::InvalidBgpSessionType(source, target, ty));
+        }
         let (source_type, target_type) = match session_type {
             Some(BgpSessionType::IBgpPeer) => {
                 if is_source_external || is_target_external {
@@ -894,7 +3815,7 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Network<P, Q, Ospf> {
                 }
             }
             Some(BgpSessionType::EBgp) => {
-                if !(is_source_external || is_target_external) {
+                if is_source_external == is_target_external {
                     Err(NetworkError::InvalidBgpSessionType(
                         source,
                         target,
@@ -907,12 +3828,130 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Network<P, Q, Ospf> {
             None => Ok((None, None)),
         }?;
 
+        // a removed session is no longer a member of any peer-group.
+        if session_type.is_none() {
+            self.peer_group_members.remove(&(source, target));
+            self.peer_group_members.remove(&(target, source));
+        }
+
         // set the bgp sessions locally in the network.
         self.bgp_sessions.insert((source, target), source_type);
         self.bgp_sessions.insert((target, source), target_type);
+
+        // a router with clients is always a route reflector.
+        if session_type == Some(BgpSessionType::IBgpClient) {
+            self.get_internal_router_mut(source)?.bgp.route_reflector = true;
+        }
         Ok(())
     }
 
+    /// Record the selected routes of `routers`, either only for `prefix`, or for all prefixes (if
+    /// `prefix` is `None`). This function returns `None` if no callback is registered (see
+    /// [`Network::on_event`]). Pass the result to [`Network::emit_best_route_changes`] to notify
+    /// all callbacks about routes that have changed in the meantime.
+    pub(crate) fn observe_best_routes(
+        &self,
+        routers: impl IntoIterator<Item = RouterId>,
+        prefix: Option<P>,
+    ) -> Option<ObservedRoutes<P>> {
+        if self.callbacks.is_empty() {
+            return None;
+        }
+        let routes = routers
+            .into_iter()
+            .filter_map(|r| self.get_internal_router(r).ok())
+            .map(|r| {
+                let rib = match prefix {
+                    Some(p) => {
+                        let mut rib = P::Map::default();
+                        if let Some(entry) = r.bgp.get_rib().get(&p) {
+                            rib.insert(p, entry.clone());
+                        }
+                        rib
+                    }
+                    None => r.bgp.get_rib().clone(),
+                };
+                (r.router_id(), rib)
+            })
+            .collect();
+        Some(ObservedRoutes { prefix, routes })
+    }
+
+    /// Emit a [`ConvergenceEvent::BestRouteChanged`] for every selected route that has changed
+    /// since calling [`Network::observe_best_routes`]. Routes are considered equal if they were
+    /// learned from the same neighbor with the same attributes (ignoring the IGP cost).
+    pub(crate) fn emit_best_route_changes(&mut self, observed: Option<ObservedRoutes<P>>) {
+        let Some(observed) = observed else {
+            return;
+        };
+        for (router, old_rib) in observed.routes {
+            let Ok(r) = self.get_internal_router(router) else {
+                continue;
+            };
+            let new_rib = r.bgp.get_rib();
+            let prefixes = match observed.prefix {
+                Some(p) => vec![p],
+                None => old_rib
+                    .keys()
+                    .chain(new_rib.keys())
+                    .copied()
+                    .sorted()
+                    .dedup()
+                    .collect(),
+            };
+            let changes: Vec<_> = prefixes
+                .into_iter()
+                .filter_map(|prefix| {
+                    let old = old_rib.get(&prefix);
+                    let new = new_rib.get(&prefix);
+                    // ignore changes in the IGP cost towards the same route.
+                    let changed =
+                        old.map(|e| (&e.route, e.from_id)) != new.map(|e| (&e.route, e.from_id));
+                    changed.then(|| ConvergenceEvent::BestRouteChanged {
+                        router,
+                        prefix,
+                        old: old.cloned(),
+                        new: new.cloned(),
+                    })
+                })
+                .collect();
+            for event in changes {
+                self.callbacks.emit(event);
+            }
+        }
+    }
+
+    /// Returns `false` if `a` and `b` have a multihop eBGP session (see
+    /// [`Network::add_ebgp_multihop_session`]), and the internal router reaches the external one
+    /// only with more hops than the TTL of the session.
+    fn is_within_ebgp_ttl(&self, a: RouterId, b: RouterId) -> bool {
+        let (r, ext) = match (self.routers.get(&a), self.routers.get(&b)) {
+            (Some(NetworkDevice::InternalRouter(r)), Some(NetworkDevice::ExternalRouter(_))) => {
+                (r, b)
+            }
+            (Some(NetworkDevice::ExternalRouter(_)), Some(NetworkDevice::InternalRouter(r))) => {
+                (r, a)
+            }
+            _ => return true,
+        };
+        let Some(ttl) = r.bgp.get_ebgp_multihop(ext) else {
+            return true;
+        };
+        // follow the IGP towards the external router.
+        let mut cur = r.router_id();
+        for _ in 0..ttl {
+            let Some(NetworkDevice::InternalRouter(x)) = self.routers.get(&cur) else {
+                return false;
+            };
+            match x.ospf.get(ext).first() {
+                Some(nh) if *nh == ext => return true,
+                Some(nh) => cur = *nh,
+                None => return false,
+            }
+        }
+        false
+    }
+
     /// Check the connectivity for all BGP sessions, and enable or disable them accordingly. This
     /// function will enqueue events **without** executing them.
     pub(crate) fn refresh_bgp_sessions(&mut self) -> Result<(), NetworkError> {
@@ -924,14 +3963,31 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Network<P, Q, Ospf> {
                 (
                     *source,
                     *target,
-                    self.ospf
-                        .is_reachable(*source, *target, &self.routers)
-                        .then_some(*ty)
-                        .flatten(),
+                    (self.ospf.is_reachable(*source, *target, &self.routers)
+                        && self.is_within_ebgp_ttl(*source, *target))
+                    .then_some(*ty)
+                    .flatten(),
                 )
             })
             .collect();
 
+        // observe all routers whose sessions change
+        let observed = self.observe_best_routes(
+            effective_sessions
+                .iter()
+                .filter(|(source, target, ty)| match self.routers.get(source) {
+                    Some(NetworkDevice::InternalRouter(r)) => {
+                        r.bgp.get_session_type(*target) != *ty
+                    }
+                    _ => false,
+                })
+                .map(|(source, _, _)| *source)
+                .unique()
+                .collect_vec(),
+            None,
+        );
+        let mut session_events = Vec::new();
+
         for (source, target, ty) in effective_sessions {
             let target_name = self
                 .routers
@@ -951,6 +4007,14 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Network<P, Q, Ospf> {
                             "BGP session between {} and {target_name} {action}!",
                             r.name(),
                         );
+                        session_events.push(match ty {
+                            Some(session_type) => ConvergenceEvent::SessionEstablished {
+                                source,
+                                target,
+                                session_type,
+                            },
+                            None => ConvergenceEvent::SessionDown { source, target },
+                        });
                     }
                     r.bgp.set_session(target, ty)?.1
                 }
@@ -967,18 +4031,51 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Network<P, Q, Ospf> {
                             "BGP session between {} and {target_name} {action}!",
                             r.name(),
                         );
+                        session_events.push(if is_connected {
+                            ConvergenceEvent::SessionEstablished {
+                                source,
+                                target,
+                                session_type: BgpSessionType::EBgp,
+                            }
+                        } else {
+                            ConvergenceEvent::SessionDown { source, target }
+                        });
                     }
                     if is_connected {
                         r.establish_ebgp_session(target)?
                     } else {
-                        r.close_ebgp_session(target)?;
-                        Vec::new()
+                        r.close_ebgp_session(target)?
                     }
                 }
                 _ => Vec::new(),
             };
             self.enqueue_events(events);
         }
+
+        for event in session_events {
+            self.callbacks.emit(event);
+        }
+        self.emit_best_route_changes(observed);
+
+        self.refresh_orr()
+    }
+
+    /// Update the IGP costs from the roots of optimal route reflection on all route reflectors
+    /// (see [`Network::set_orr`]). This function will enqueue events **without** executing them.
+    fn refresh_orr(&mut self) -> Result<(), NetworkError> {
+        let roots = self
+            .internal_routers()
+            .flat_map(|r| r.bgp.orr.values().map(|root| (r.router_id(), *root)))
+            .unique()
+            .collect_vec();
+        for (rr, root) in roots {
+            let igp_cost = self.orr_igp_cost(root).unwrap_or_default();
+            let events = self
+                .get_internal_router_mut(rr)?
+                .bgp
+                .update_orr_igp(root, igp_cost)?;
+            self.enqueue_events(events);
+        }
         Ok(())
     }
 
@@ -997,9 +4094,28 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Network<P, Q, Ospf> {
         self.simulate()
     }
 
-    /// Enqueue the event
+    /// Enqueue the event. BGP updates on sessions with MRAI enabled are coalesced with the update
+    /// that is still in flight (if any).
     #[inline(always)]
     fn enqueue_event(&mut self, event: Event<P, Q::Priority>) {
+        if let Event::Bgp { src, dst, e, .. } = &event {
+            let mrai = self
+                .routers
+                .get(src)
+                .and_then(|r| r.as_ref().internal())
+                .is_some_and(|r| r.bgp.has_mrai(*dst));
+            if mrai {
+                match self.mrai_pending.entry((*src, *dst, e.prefix())) {
+                    Entry::Occupied(mut pending) => {
+                        pending.insert(e.clone());
+                        return;
+                    }
+                    Entry::Vacant(pending) => {
+                        pending.insert(e.clone());
+                    }
+                }
+            }
+        }
         self.queue.push(event, &self.routers, &self.net)
     }
 
@@ -1110,10 +4226,384 @@ impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Network<P, Q, Ospf> {
             stop_after: self.stop_after,
             queue: self.queue,
             skip_queue: self.skip_queue,
+            callbacks: self.callbacks,
+            link_bundles: self.link_bundles,
+            frozen: self.frozen,
+            mrai_pending: self.mrai_pending,
+            event_timeline: self.event_timeline,
+            router_data: self.router_data,
+            link_data: self.link_data,
+            processed_events: self.processed_events,
+            scheduled_recoveries: self.scheduled_recoveries,
+            srlgs: self.srlgs,
+            peer_groups: self.peer_groups,
+            peer_group_members: self.peer_group_members,
         })
     }
 }
 
+impl<P, Q, Ospf> Network<P, Q, Ospf>
+where
+    P: Prefix,
+    Q: EventQueue<P> + Clone,
+    Ospf: OspfImpl,
+{
+    /// Compute the churn of a configuration change, i.e., the number of BGP messages (updates and
+    /// withdraws) that the network exchanges until it converges after applying `change`. The
+    /// change is applied on a copy of the network, so `self` remains unmodified. Any event that
+    /// is still enqueued in `self` is processed before applying the change, and does not count
+    /// towards the churn.
+    ///
+    /// This function returns [`NetworkError::NoConvergence`] if the network does not converge
+    /// within the limit set by [`Network::set_msg_limit`].
+    pub fn churn_of(&self, change: &ConfigModifier<P>) -> Result<usize, NetworkError> {
+        let mut net = self.clone();
+        net.simulate()?;
+        net.manual_simulation();
+        net.apply_modifier(change)?;
+
+        let mut churn = 0;
+        let mut remaining_iter = net.stop_after;
+        'timeout: loop {
+            while !net.queue.is_empty() {
+                if let Some(rem) = remaining_iter {
+                    if rem == 0 {
+                        return Err(NetworkError::NoConvergence);
+                    }
+                    remaining_iter = Some(rem - 1);
+                }
+                match net.simulate_step()? {
+                    Some((_, Event::Bgp { .. })) => churn += 1,
+                    Some((_, Event::Ospf { .. })) => net.refresh_bgp_sessions()?,
+                    None => {}
+                }
+            }
+
+            // trigger the next timeout event if it exists.
+            if net.trigger_timeout()?.is_none() {
+                break 'timeout;
+            }
+        }
+
+        Ok(churn)
+    }
+
+    /// Compute the impact of withdrawing `prefix` at the external router `origin` (blast-radius
+    /// analysis). The withdrawal is performed on a copy of the network (dry-run), so `self` remains
+    /// unmodified. Any event that is still enqueued in `self` is processed before withdrawing the
+    /// route.
+    ///
+    /// The function returns a tuple of two sets of internal routers. The first set contains all
+    /// routers whose forwarding paths towards `prefix` change due to the withdrawal, e.g., because
+    /// they select a different egress. The second set contains all routers that can reach `prefix`
+    /// before, but no longer after the withdrawal. Every router of the second set is also contained
+    /// in the first set. Both sets are empty if `origin` does not advertise `prefix`.
+    ///
+    /// This function returns an error if `origin` is not an external router, or if the network
+    /// does not converge.
+    pub fn withdrawal_impact(
+        &self,
+        origin: RouterId,
+        prefix: impl Into<P>,
+    ) -> Result<(BTreeSet<RouterId>, BTreeSet<RouterId>), NetworkError> {
+        let prefix = prefix.into();
+        let mut net = self.clone();
+        net.skip_queue = false;
+        net.simulate()?;
+        let mut fw_before = net.get_forwarding_state();
+        let reachable_before = net.reachable_routers(prefix);
+
+        net.withdraw_external_route(origin, prefix)?;
+        let mut fw_after = net.get_forwarding_state();
+        let reachable_after = net.reachable_routers(prefix);
+
+        let changed = net
+            .internal_indices()
+            .filter(|r| fw_before.get_paths(*r, prefix) != fw_after.get_paths(*r, prefix))
+            .collect();
+        let unreachable = reachable_before
+            .difference(&reachable_after)
+            .copied()
+            .collect();
+
+        Ok((changed, unreachable))
+    }
+
+    /// Compute the prefixes that are rerouted when the link between `a` and `b` fails, i.e., the
+    /// prefixes for which the forwarding paths (and hence, possibly the egress) of at least one
+    /// internal router change. This answers the question of which traffic the link carries. The
+    /// failure is injected on a copy of the network, so `self` remains unmodified. Any event that
+    /// is still enqueued in `self` is processed before failing the link. Like in
+    /// [`Network::reachability_under_single_failures`], an internal link fails by setting its
+    /// weight to infinity in both directions, while a link to an external router is removed.
+    ///
+    /// This function returns [`NetworkError::LinkNotFound`] if the link does not exist, and an
+    /// error if the network does not converge.
+    pub fn prefixes_rerouted_by_failure(
+        &self,
+        a: RouterId,
+        b: RouterId,
+    ) -> Result<BTreeSet<P>, NetworkError> {
+        if self.net.find_edge(a, b).is_none() {
+            return Err(NetworkError::LinkNotFound(a, b));
+        }
+        let mut net = self.clone();
+        net.skip_queue = false;
+        net.simulate()?;
+
+        let routers = net.internal_indices().collect_vec();
+        let prefixes = net.get_known_prefixes().copied().collect_vec();
+        let paths = |net: &Self| {
+            let mut fw = net.get_forwarding_state();
+            prefixes
+                .iter()
+                .map(|p| routers.iter().map(|r| fw.get_paths(*r, *p)).collect_vec())
+                .collect_vec()
+        };
+
+        let before = paths(&net);
+        if net.get_device(a)?.is_internal() && net.get_device(b)?.is_internal() {
+            net._set_link_weights_from([
+                (a, b, LinkWeight::INFINITY),
+                (b, a, LinkWeight::INFINITY),
+            ])?;
+            net.do_queue_maybe_skip()?;
+        } else {
+            net.remove_link(a, b)?;
+        }
+        let after = paths(&net);
+
+        Ok(prefixes
+            .into_iter()
+            .zip(before.into_iter().zip(after))
+            .filter(|(_, (before, after))| before != after)
+            .map(|(p, _)| p)
+            .collect())
+    }
+
+    /// Reduce the network to a small sub-network in which `policy` is still violated, to simplify
+    /// debugging. The function greedily tries to remove routers, then links, and finally BGP
+    /// sessions. After each removal, the network is simulated until convergence, and the removal
+    /// is only kept if `policy` is still violated (delta-debugging). This process is repeated
+    /// until no more element can be removed. The router of the policy (see [`Policy::router`]) is
+    /// never removed. The reduction is performed on a copy of the network, so `self` remains
+    /// unmodified.
+    ///
+    /// This function returns `Ok(None)` if `policy` is satisfied in `self` (after processing all
+    /// enqueued events), and [`NetworkError::NoConvergence`] if `self` does not converge.
+    /// Removals for which the network does not converge are skipped.
+    pub fn reduce_for_policy<T: Policy<P>>(
+        &self,
+        policy: &T,
+    ) -> Result<Option<Self>, NetworkError> {
+        let violates = |net: &Self| policy.check(&mut net.get_forwarding_state()).is_err();
+
+        let mut net = self.clone();
+        net.simulate()?;
+        if !violates(&net) {
+            return Ok(None);
+        }
+
+        // try to apply `f` on a copy of `net`, and keep the result if the policy is still violated.
+        let try_remove = |net: &mut Self, f: &dyn Fn(&mut Self) -> Result<(), NetworkError>| {
+            let mut reduced = net.clone();
+            if f(&mut reduced).and_then(|_| reduced.simulate()).is_ok() && violates(&reduced) {
+                *net = reduced;
+                true
+            } else {
+                false
+            }
+        };
+
+        loop {
+            let mut changed = false;
+
+            let routers = net
+                .device_indices()
+                .filter(|r| Some(*r) != policy.router())
+                .collect_vec();
+            for r in routers {
+                changed |= try_remove(&mut net, &|n| n.remove_router(r));
+            }
+
+            let links = net
+                .net
+                .edge_indices()
+                .filter_map(|e| net.net.edge_endpoints(e))
+                .map(|(a, b)| bundle_key(a, b))
+                .unique()
+                .collect_vec();
+            for (a, b) in links {
+                changed |= try_remove(&mut net, &|n| n.remove_link(a, b));
+            }
+
+            let sessions = net.sessions().map(|s| (s.source, s.target)).collect_vec();
+            for (a, b) in sessions {
+                changed |= try_remove(&mut net, &|n| n.set_bgp_session(a, b, None));
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        Ok(Some(net))
+    }
+
+    /// Search for other stable states for `prefix` that the network may converge to, depending on
+    /// the order in which its BGP sessions come up. Policies with multiple stable states can cause
+    /// BGP wedgies: once the network is in an unintended stable state, it remains there, even
+    /// after the event that caused it is resolved.
+    ///
+    /// Starting from the converged state of `self`, the function re-establishes all sessions (see
+    /// [`Network::establish_sessions_in_order`]) in several orders: for each session (see
+    /// [`Network::sessions`]), it brings up that session first, and once more that session last,
+    /// while all other sessions come up in their usual order. The search is thus not exhaustive.
+    /// For each order, the function checks that the resulting state is a fixed point (see
+    /// [`Network::verify_fixed_point`]), and that some internal router selects a route for
+    /// `prefix` from a different neighbor than in `self`. Each distinct state is returned once, in
+    /// the order in which it was found. Orders for which the network does not converge within the
+    /// limit set by [`Network::set_msg_limit`] are skipped. All orders are simulated on copies in automatic mode, so `self` remains
+    /// unmodified.
+    ///
+    /// This function returns [`NetworkError::NoConvergence`] if `self` does not converge.
+    pub fn find_alternate_stable_states(
+        &self,
+        prefix: impl Into<P>,
+    ) -> Result<Vec<Self>, NetworkError> {
+        let prefix = prefix.into();
+        let selection = |net: &Self| {
+            net.internal_routers()
+                .map(|r| (r.router_id(), r.bgp.get_route(prefix).map(|e| e.from_id)))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        let mut net = self.clone();
+        net.simulate()?;
+        net.skip_queue = false;
+        let mut seen = vec![selection(&net)];
+
+        let sessions = net.sessions().map(|s| (s.source, s.target)).collect_vec();
+        let orders = (0..sessions.len()).flat_map(|i| {
+            let mut first = sessions.clone();
+            let s = first.remove(i);
+            let mut last = first.clone();
+            first.insert(0, s);
+            last.push(s);
+            [first, last]
+        });
+
+        let mut result = Vec::new();
+        for order in orders.unique() {
+            let mut alt = net.clone();
+            if alt.establish_sessions_in_order(&order).is_err()
+                || !alt.verify_fixed_point().is_empty()
+            {
+                continue;
+            }
+            let state = selection(&alt);
+            if !seen.contains(&state) {
+                seen.push(state);
+                result.push(alt);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Find a small change of link weights that makes `router` use `desired_egress` (i.e., the BGP
+    /// next-hop, see [`Network::alternative_egress`]) for `prefix`, based on hot-potato routing.
+    /// The function returns the new weights of all changed (directed) links, or `None` if no
+    /// change was found. If `router` already uses `desired_egress`, the result is empty.
+    ///
+    /// The search is bounded: It only considers changing the weight of a single link between two
+    /// internal routers. The magnitude of the change is the smallest whole number that makes the
+    /// IGP cost towards `desired_egress` strictly smaller than the IGP cost towards the current
+    /// egress (as seen by `router`). For each link, the function first tries to increase its
+    /// weight, and then to decrease it (if the weight remains positive). Each candidate is
+    /// verified by simulating the network on a copy, so `self` remains unmodified. The first
+    /// candidate (ordered by the link) for which `router` uses `desired_egress` is returned.
+    /// Hence, the search requires at most two simulations per link. The change is infeasible if
+    /// `router` knows no route via `desired_egress`, or if the routes differ in an attribute that
+    /// is compared before the IGP cost.
+    ///
+    /// This function returns [`NetworkError::NoConvergence`] if `self` does not converge.
+    /// Candidates for which the network does not converge are skipped.
+    #[allow(clippy::type_complexity)]
+    pub fn min_weight_change_for_egress(
+        &self,
+        router: RouterId,
+        prefix: impl Into<P>,
+        desired_egress: RouterId,
+    ) -> Result<Option<Vec<(RouterId, RouterId, LinkWeight)>>, NetworkError> {
+        let prefix = prefix.into();
+        let egress = |net: &Self| -> Result<Option<RouterId>, NetworkError> {
+            Ok(net
+                .get_internal_router(router)?
+                .bgp
+                .get_route(prefix)
+                .map(|e| e.route.next_hop))
+        };
+
+        let mut net = self.clone();
+        net.simulate()?;
+        net.skip_queue = false;
+        let Some(current) = egress(&net)? else {
+            return Ok(None);
+        };
+        if current == desired_egress {
+            return Ok(Some(Vec::new()));
+        }
+
+        // compute the IGP cost difference between both egresses
+        let routes = net
+            .get_internal_router(router)?
+            .bgp
+            .get_known_routes(prefix)?;
+        let cost = |nh: RouterId| {
+            routes
+                .iter()
+                .filter(|e| e.route.next_hop == nh)
+                .filter_map(|e| e.igp_cost)
+                .map(|c| c.into_inner())
+                .reduce(f64::min)
+        };
+        let (Some(cost_desired), Some(cost_current)) = (cost(desired_egress), cost(current)) else {
+            return Ok(None);
+        };
+        let delta = (cost_desired - cost_current).max(0.0).floor() + 1.0;
+
+        let links = net
+            .net
+            .edge_indices()
+            .filter_map(|e| net.net.edge_endpoints(e))
+            .filter(|(a, b)| {
+                net.get_device(*a).is_ok_and(|r| r.is_internal())
+                    && net.get_device(*b).is_ok_and(|r| r.is_internal())
+            })
+            .sorted()
+            .collect_vec();
+        for (a, b) in links {
+            let weight = net.get_link_weight(a, b)?;
+            if weight.is_infinite() {
+                continue;
+            }
+            let candidates = [weight + delta, weight - delta];
+            for new_weight in candidates.into_iter().filter(|w| *w > 0.0) {
+                let mut alt = net.clone();
+                if alt.set_link_weight(a, b, new_weight).is_err() {
+                    continue;
+                }
+                if egress(&alt)? == Some(desired_egress) {
+                    return Ok(Some(vec![(a, b, new_weight)]));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
 impl<P, Q, Ospf> Network<P, Q, Ospf>
 where
     P: Prefix,
@@ -1197,6 +4687,386 @@ where
     }
 }
 
+/// A link that failed temporarily, created by [`Network::fail_link_for`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledRecovery {
+    /// Logical time (number of processed events) at which the link recovers.
+    pub at: usize,
+    /// First endpoint of the link
+    pub a: RouterId,
+    /// Second endpoint of the link
+    pub b: RouterId,
+    /// Link weights from `a` to `b`, and from `b` to `a`, before the failure.
+    pub weights: (LinkWeight, LinkWeight),
+}
+
+/// Bundle of parallel links between two internal routers (link aggregation), created by
+/// [`Network::add_link_bundle`]. The bundle forms a single IGP adjacency, whose capacity is the
+/// combined capacity of all active members.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkBundle {
+    /// Capacity of each member link
+    pub(crate) capacities: Vec<f64>,
+    /// Whether each member link is active
+    pub(crate) active: Vec<bool>,
+    /// Link weights (in both directions) before all members have failed.
+    pub(crate) weights: Option<(LinkWeight, LinkWeight)>,
+}
+
+impl LinkBundle {
+    /// Number of member links (both active and failed ones).
+    pub fn num_members(&self) -> usize {
+        self.capacities.len()
+    }
+
+    /// Number of active member links.
+    pub fn num_active(&self) -> usize {
+        self.active.iter().filter(|x| **x).count()
+    }
+
+    /// Returns `true` if the member link is active, and `false` if it has failed or does not
+    /// exist.
+    pub fn is_active(&self, member: usize) -> bool {
+        self.active.get(member).copied().unwrap_or(false)
+    }
+
+    /// Get the capacity of each member link (both active and failed ones).
+    pub fn capacities(&self) -> &[f64] {
+        &self.capacities
+    }
+
+    /// Combined capacity of all active member links.
+    pub fn capacity(&self) -> f64 {
+        self.capacities
+            .iter()
+            .zip(self.active.iter())
+            .filter(|(_, active)| **active)
+            .map(|(c, _)| c)
+            .sum()
+    }
+}
+
+/// Key of a link bundle, where the first router is always the smaller one.
+fn bundle_key(a: RouterId, b: RouterId) -> (RouterId, RouterId) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// Selected routes of some routers, created by [`Network::observe_best_routes`].
+pub(crate) struct ObservedRoutes<P: Prefix> {
+    /// The observed prefix, or `None` if all prefixes were observed.
+    prefix: Option<P>,
+    /// The selected routes of each observed router.
+    routes: Vec<(RouterId, P::Map<BgpRibEntry<P>>)>,
+}
+
+/// Summary of a network, created by [`Network::summary`]. Use the
+/// [`NetworkFormatter`](crate::formatter::NetworkFormatter) to render it as a human-readable
+/// report.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NetworkSummary {
+    /// Number of internal routers
+    pub num_internal_routers: usize,
+    /// Number of external routers
+    pub num_external_routers: usize,
+    /// Number of (undirected) links
+    pub num_links: usize,
+    /// Number of configured eBGP sessions
+    pub num_ebgp_sessions: usize,
+    /// Number of configured iBGP sessions between two peers
+    pub num_ibgp_peer_sessions: usize,
+    /// Number of configured iBGP sessions between a route-reflector and its client
+    pub num_ibgp_client_sessions: usize,
+    /// Number of route-map items on all routers (in both directions)
+    pub num_route_maps: usize,
+    /// Number of known prefixes
+    pub num_prefixes: usize,
+    /// Number of events waiting in the queue
+    pub num_queued_events: usize,
+}
+
+/// Forwarding-aware betweenness of routers and links, created by
+/// [`Network::forwarding_centrality`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForwardingCentrality {
+    /// Number of pairs of source and prefix for which a forwarding path exists.
+    pub num_paths: usize,
+    /// Number of pairs of source and prefix whose paths traverse each router.
+    pub routers: HashMap<RouterId, usize>,
+    /// Number of pairs of source and prefix whose paths traverse each directed link.
+    pub links: HashMap<(RouterId, RouterId), usize>,
+}
+
+impl ForwardingCentrality {
+    /// Number of pairs of source and prefix whose paths traverse `router`.
+    pub fn router(&self, router: RouterId) -> usize {
+        self.routers.get(&router).copied().unwrap_or_default()
+    }
+
+    /// Number of pairs of source and prefix whose paths traverse the directed link from `a` to
+    /// `b`.
+    pub fn link(&self, a: RouterId, b: RouterId) -> usize {
+        self.links.get(&(a, b)).copied().unwrap_or_default()
+    }
+
+    /// Routers with the maximal centrality, ordered by their ID. The result is empty if no path
+    /// traverses any router.
+    pub fn most_central_routers(&self) -> Vec<RouterId> {
+        let Some(max) = self.routers.values().copied().max() else {
+            return Vec::new();
+        };
+        self.routers
+            .iter()
+            .filter(|(_, c)| **c == max)
+            .map(|(r, _)| *r)
+            .sorted()
+            .collect()
+    }
+}
+
+/// A single hop of a probe, created by [`Network::probe`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeHop<P: Prefix> {
+    /// The router traversed by the probe.
+    pub router: RouterId,
+    /// The BGP route selected by the router. This is `None` for external routers, and for internal
+    /// routers that do not know any BGP route (e.g., if they forward using a static route).
+    pub route: Option<BgpRibEntry<P>>,
+    /// The router to which the probe is forwarded next, or `None` for the last hop.
+    pub next_hop: Option<RouterId>,
+}
+
+/// The part of the network that influences the best-path selection for a single prefix, created
+/// by [`Network::policy_subgraph`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicySubgraph<P> {
+    /// The prefix for which the subgraph was computed.
+    pub prefix: P,
+    /// All routers that advertise or receive a route for the prefix.
+    pub routers: HashSet<RouterId>,
+    /// All BGP sessions over which a route for the prefix is advertised, as directed pairs from the
+    /// sender to the receiver.
+    pub sessions: HashSet<(RouterId, RouterId)>,
+    /// All route-maps configured on these sessions, described by the router on which they are
+    /// configured, the neighbor, and the direction.
+    pub route_maps: HashSet<(RouterId, RouterId, RouteMapDirection)>,
+}
+
+impl<P> PolicySubgraph<P> {
+    /// Returns `true` if `router` is part of the subgraph.
+    pub fn contains_router(&self, router: RouterId) -> bool {
+        self.routers.contains(&router)
+    }
+
+    /// Returns `true` if a route for the prefix is advertised on the session between `a` and `b`
+    /// (in any direction).
+    pub fn contains_session(&self, a: RouterId, b: RouterId) -> bool {
+        self.sessions.contains(&(a, b)) || self.sessions.contains(&(b, a))
+    }
+}
+
+/// Canonical form of a network, created by [`Network::canonicalize`]. Each section is a sorted
+/// list of human-readable lines, in which routers are referred to by their name. Two networks
+/// that only differ in the order in which they were constructed have the same canonical form.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CanonicalNetwork {
+    /// All routers with their AS number, and whether they are internal or external.
+    pub routers: Vec<String>,
+    /// All links. Links between internal routers are listed in both directions, with their weight
+    /// and OSPF area.
+    pub links: Vec<String>,
+    /// All configured BGP sessions. Sessions between a route reflector and its client are listed
+    /// from the route reflector to the client.
+    pub sessions: Vec<String>,
+    /// All route-map items.
+    pub route_maps: Vec<String>,
+    /// All static routes.
+    pub static_routes: Vec<String>,
+    /// All other BGP settings of internal routers, e.g., the local AS, RPKI, distribute-lists,
+    /// peer-groups, or VRFs.
+    pub bgp: Vec<String>,
+}
+
+impl CanonicalNetwork {
+    /// Iterate over all sections as pairs of the section name and its lines.
+    fn sections(&self) -> [(&'static str, &Vec<String>); 6] {
+        [
+            ("routers", &self.routers),
+            ("links", &self.links),
+            ("sessions", &self.sessions),
+            ("route-maps", &self.route_maps),
+            ("static routes", &self.static_routes),
+            ("bgp", &self.bgp),
+        ]
+    }
+
+    /// Sort all sections.
+    fn sort(&mut self) {
+        for section in [
+            &mut self.routers,
+            &mut self.links,
+            &mut self.sessions,
+            &mut self.route_maps,
+            &mut self.static_routes,
+            &mut self.bgp,
+        ] {
+            section.sort();
+            section.dedup();
+        }
+    }
+
+    /// Compute the differences from `self` to `other`. Each line that only exists in `self` is
+    /// reported as `- <section>: <line>`, and each line that only exists in `other` as
+    /// `+ <section>: <line>`. The result is empty if both canonical forms are equal.
+    pub fn diff(&self, other: &Self) -> Vec<String> {
+        let mut result = Vec::new();
+        for ((section, a), (_, b)) in self.sections().into_iter().zip(other.sections()) {
+            let a_lines: HashSet<&String> = a.iter().collect();
+            let b_lines: HashSet<&String> = b.iter().collect();
+            result.extend(
+                a.iter()
+                    .filter(|l| !b_lines.contains(l))
+                    .map(|l| format!("- {section}: {l}")),
+            );
+            result.extend(
+                b.iter()
+                    .filter(|l| !a_lines.contains(l))
+                    .map(|l| format!("+ {section}: {l}")),
+            );
+        }
+        result
+    }
+}
+
+/// Order the two names of an undirected link or session.
+fn sorted_pair(a: String, b: String) -> (String, String) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+impl std::fmt::Display for CanonicalNetwork {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (section, lines) in self.sections() {
+            writeln!(f, "{section}:")?;
+            for line in lines {
+                writeln!(f, "  {line}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Information about a configured BGP session, created by [`Network::sessions`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "P: for<'a> serde::Deserialize<'a>"))]
+pub struct SessionInfo<P: Prefix> {
+    /// Source of the session. For sessions with a route reflector, this is the route reflector.
+    pub source: RouterId,
+    /// Target of the session. For sessions with a route reflector, this is the client.
+    pub target: RouterId,
+    /// Type of the session, as seen from `source`.
+    pub session_type: BgpSessionType,
+    /// Whether the session is currently established on both endpoints. A configured session is
+    /// down if the endpoints cannot reach each other.
+    pub established: bool,
+    /// Route-maps that `source` applies on routes received from `target`.
+    pub source_incoming: Vec<RouteMap<P>>,
+    /// Route-maps that `source` applies on routes sent to `target`.
+    pub source_outgoing: Vec<RouteMap<P>>,
+    /// Route-maps that `target` applies on routes received from `source`.
+    pub target_incoming: Vec<RouteMap<P>>,
+    /// Route-maps that `target` applies on routes sent to `source`.
+    pub target_outgoing: Vec<RouteMap<P>>,
+}
+
+impl<P: Prefix> SessionInfo<P> {
+    /// Number of route-map items that both endpoints apply on the session (in both directions).
+    pub fn num_route_maps(&self) -> usize {
+        self.source_incoming.len()
+            + self.source_outgoing.len()
+            + self.target_incoming.len()
+            + self.target_outgoing.len()
+    }
+}
+
+impl NetworkSummary {
+    /// Returns `true` if no event is waiting in the queue.
+    pub fn is_converged(&self) -> bool {
+        self.num_queued_events == 0
+    }
+
+    /// Total number of BGP sessions
+    pub fn num_bgp_sessions(&self) -> usize {
+        self.num_ebgp_sessions + self.num_ibgp_peer_sessions + self.num_ibgp_client_sessions
+    }
+}
+
+/// Iterator that simulates the network step by step, yielding the progress `(processed, pending)`
+/// after each event (see [`Network::simulate_progress`]).
+#[derive(Debug)]
+pub struct SimulationProgress<'a, P: Prefix, Q, Ospf: OspfImpl> {
+    net: &'a mut Network<P, Q, Ospf>,
+    remaining_iter: Option<usize>,
+    done: bool,
+}
+
+impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> SimulationProgress<'_, P, Q, Ospf> {
+    /// Perform the next step, and return `Ok(None)` if the network has converged. Otherwise,
+    /// return whether the processed event changed the forwarding state.
+    fn next_step(&mut self) -> Result<Option<bool>, NetworkError> {
+        while self.net.queue.is_empty() {
+            // trigger the next timeout event if it exists, or recover the next failed link.
+            if self.net.trigger_timeout()?.is_none() && !self.net.recover_scheduled_links(true)? {
+                // remove unreachable OSPF LSAs
+                self.net
+                    .internal_routers_mut()
+                    .for_each(|r| r.ospf.remove_unreachable_lsas());
+                return Ok(None);
+            }
+        }
+
+        if let Some(rem) = self.remaining_iter {
+            if rem == 0 {
+                return Err(NetworkError::NoConvergence);
+            }
+            self.remaining_iter = Some(rem - 1);
+        }
+        let step = self.net.simulate_step()?;
+        if matches!(step, Some((_, Event::Ospf { .. }))) {
+            // OSPF event received! Check the BGP session state
+            self.net.refresh_bgp_sessions()?;
+        }
+        Ok(Some(step.is_some_and(|(update, _)| update.changed())))
+    }
+}
+
+impl<P: Prefix, Q: EventQueue<P>, Ospf: OspfImpl> Iterator for SimulationProgress<'_, P, Q, Ospf> {
+    type Item = Result<(usize, usize), NetworkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.next_step() {
+            Ok(Some(_)) => Some(Ok(self.net.progress())),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
 /// Iterator of all devices in the network.
 #[derive(Debug)]
 pub struct DeviceIndices<'a, P: Prefix, Ospf> {
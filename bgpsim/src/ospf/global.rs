@@ -25,6 +25,7 @@ use serde_with::{As, Same};
 use crate::{
     event::Event,
     ospf::{
+        compute_ospf_table,
         local::{
             database::{
                 compute_as_external_route, compute_inter_area_route, compute_intra_area_routes,
@@ -32,8 +33,8 @@ use crate::{
             },
             LinkType, Lsa, LsaData, LsaHeader, LsaKey, LsaType, RouterLsaLink,
         },
-        LinkWeight, NeighborhoodChange, OspfArea, OspfCoordinator, OspfImpl, OspfProcess,
-        EXTERNAL_LINK_WEIGHT,
+        LinkWeight, NeighborhoodChange, OspfArea, OspfAreaType, OspfCoordinator, OspfImpl,
+        OspfProcess, OspfStubView, EXTERNAL_LINK_WEIGHT,
     },
     types::{DeviceError, NetworkDevice, NetworkError, NetworkErrorOption, Prefix, RouterId},
 };
@@ -92,6 +93,9 @@ pub struct GlobalOspfCoordinator {
     #[serde(with = "As::<Vec<(Same, Vec<(Same, Same)>)>>")]
     pub(super) redistributed_paths:
         HashMap<(RouterId, OspfArea), BTreeMap<LsaKey, NotNan<LinkWeight>>>,
+    /// The type of all areas that are not `OspfAreaType::Normal`.
+    #[serde(default)]
+    pub(super) area_types: BTreeMap<OspfArea, OspfAreaType>,
 }
 
 /// The actions that must be performed when some updates occurr.
@@ -189,6 +193,29 @@ impl GlobalOspfCoordinator {
         &self.lsa_lists
     }
 
+    /// Get the type of the area in which `router` is an internal router. Area border routers (and
+    /// routers without any area) always use `OspfAreaType::Normal`.
+    pub fn get_area_type(&self, router: RouterId) -> OspfAreaType {
+        match self.membership.get(&router) {
+            Some(areas) if areas.len() == 1 => areas
+                .first()
+                .and_then(|area| self.area_types.get(area))
+                .copied()
+                .unwrap_or_default(),
+            _ => OspfAreaType::Normal,
+        }
+    }
+
+    /// Get the type and the LSA list of the stub area in which `router` is an internal router.
+    fn get_stub_area(&self, router: RouterId) -> Option<(OspfAreaType, &HashMap<LsaKey, Lsa>)> {
+        let areas = self.membership.get(&router)?;
+        if areas.len() != 1 {
+            return None;
+        }
+        let area = areas.first()?;
+        Some((*self.area_types.get(area)?, self.lsa_lists.get(area)?))
+    }
+
     /// Get a reference to each constructed Shortest-Path Tree for each router.
     pub fn get_spts(&self) -> &HashMap<RouterId, BTreeMap<OspfArea, HashMap<RouterId, SptNode>>> {
         &self.spts
@@ -241,6 +268,14 @@ impl GlobalOspfCoordinator {
             NeighborhoodChange::RemoveExternalNetwork { int, ext } => {
                 actions += self.set_external_link(int, ext, None);
             }
+            NeighborhoodChange::AreaType { area, area_type } => {
+                if area_type.is_stub() {
+                    self.area_types.insert(area, area_type);
+                } else {
+                    self.area_types.remove(&area);
+                }
+                actions.recompute_intra_area_routes(area);
+            }
             NeighborhoodChange::Batch(b) => {
                 for change in b {
                     actions += self.prepare_actions(change);
@@ -349,13 +384,14 @@ impl GlobalOspfCoordinator {
         let empty = HashMap::new();
         for &router in &modified_tables {
             let rib = self.ribs.get(&router).unwrap_or(&empty);
+            let stub_area = self.get_stub_area(router);
             // send the table to the process
             let r = routers
                 .get_mut(&router)
                 .or_router_not_found(router)?
                 .internal_or_err()?;
             events.append(&mut r.update_ospf(|ospf| {
-                ospf.update_table(rib, links, external_links);
+                ospf.update_table(rib, stub_area, links, external_links);
                 Ok((true, Vec::new()))
             })?);
         }
@@ -928,6 +964,9 @@ pub struct GlobalOspfProcess {
     pub(crate) ospf_table: HashMap<RouterId, (Vec<RouterId>, LinkWeight)>,
     /// Neighbors of that node. This updates with any IGP update
     pub(crate) neighbors: HashMap<RouterId, LinkWeight>,
+    /// View on external routes if the router is an internal router of a stub area.
+    #[serde(default)]
+    pub(crate) stub_view: Option<OspfStubView>,
 }
 
 impl GlobalOspfProcess {
@@ -935,6 +974,7 @@ impl GlobalOspfProcess {
     pub(crate) fn update_table(
         &mut self,
         rib: &HashMap<RouterId, OspfRibEntry>,
+        stub_area: Option<(OspfAreaType, &HashMap<LsaKey, Lsa>)>,
         links: &HashMap<RouterId, HashMap<RouterId, (LinkWeight, OspfArea)>>,
         external_links: &HashMap<RouterId, HashSet<RouterId>>,
     ) {
@@ -953,18 +993,7 @@ impl GlobalOspfProcess {
             )
             .collect();
 
-        self.ospf_table = rib
-            .iter()
-            .map(|(r, path)| {
-                (
-                    *r,
-                    (
-                        Vec::from_iter(path.fibs.iter().copied()),
-                        path.cost.into_inner(),
-                    ),
-                )
-            })
-            .collect();
+        (self.ospf_table, self.stub_view) = compute_ospf_table(self.router_id, rib, stub_area);
     }
 }
 
@@ -974,6 +1003,7 @@ impl OspfProcess for GlobalOspfProcess {
             router_id,
             ospf_table: Default::default(),
             neighbors: Default::default(),
+            stub_view: None,
         }
     }
 
@@ -981,6 +1011,10 @@ impl OspfProcess for GlobalOspfProcess {
         &self.ospf_table
     }
 
+    fn get_stub_view(&self) -> Option<&OspfStubView> {
+        self.stub_view.as_ref()
+    }
+
     fn get_neighbors(&self) -> &HashMap<RouterId, LinkWeight> {
         &self.neighbors
     }
@@ -40,7 +40,9 @@ use crate::{
 
 use self::{database::AreaDataStructure, neighbor::Neighbor};
 
-use super::{LinkWeight, NeighborhoodChange, OspfArea, OspfCoordinator, OspfImpl, OspfProcess};
+use super::{
+    LinkWeight, NeighborhoodChange, OspfArea, OspfAreaType, OspfCoordinator, OspfImpl, OspfProcess,
+};
 
 /// Global OSPF is the OSPF implementation that computes the resulting forwarding state atomically
 /// (by an imaginary central controller with global knowledge) and pushes the resulting state to the
@@ -56,7 +58,7 @@ impl OspfImpl for LocalOspf {
         coordinators: (Self::Coordinator, &mut super::global::GlobalOspfCoordinator),
         processes: HashMap<RouterId, (Self::Process, &mut super::global::GlobalOspfProcess)>,
     ) -> Result<(), NetworkError> {
-        let (_, global_coordinator) = coordinators;
+        let (local_coordinator, global_coordinator) = coordinators;
 
         // construct the datastructures
         let mut spts: HashMap<RouterId, BTreeMap<_, _>> = HashMap::new();
@@ -72,6 +74,7 @@ impl OspfImpl for LocalOspf {
 
             // write the OSPF and RIB tables
             global_p.ospf_table = local_p.table;
+            global_p.stub_view = local_p.stub_view;
             ribs.insert(router, local_p.areas.rib);
 
             // extend the external LSAs
@@ -136,6 +139,7 @@ impl OspfImpl for LocalOspf {
         global_coordinator.lsa_lists = lsa_lists;
         global_coordinator.external_lsas = external_lsas;
         global_coordinator.redistributed_paths = redistributed_paths;
+        global_coordinator.area_types = local_coordinator.area_types;
 
         Ok(())
     }
@@ -144,7 +148,10 @@ impl OspfImpl for LocalOspf {
         coordinators: (&mut Self::Coordinator, super::global::GlobalOspfCoordinator),
         processes: HashMap<RouterId, (&mut Self::Process, super::global::GlobalOspfProcess)>,
     ) -> Result<(), NetworkError> {
-        let (_, mut global_coordinator) = coordinators;
+        let (local_coordinator, mut global_coordinator) = coordinators;
+        local_coordinator
+            .area_types
+            .clone_from(&global_coordinator.area_types);
         for (router, (local_p, global_p)) in processes {
             // create the neighbors
             local_p.neighbor_links = global_p.neighbors;
@@ -209,6 +216,10 @@ impl OspfImpl for LocalOspf {
 
             // write the tables
             local_p.table = global_p.ospf_table;
+            local_p.stub_view = global_p.stub_view;
+            local_p
+                .area_types
+                .clone_from(&global_coordinator.area_types);
 
             // cleanup the tables by removing unreachable entries from all SLAs.
             local_p.remove_unreachable_lsas();
@@ -219,8 +230,13 @@ impl OspfImpl for LocalOspf {
 }
 
 /// The local OSPF oracle that simply forwards all requests to the appropriate routers.
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct LocalOspfCoordinator;
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LocalOspfCoordinator {
+    /// The type of all areas that are not `OspfAreaType::Normal`. They are pushed to each router
+    /// before handling a neighborhood change.
+    #[serde(default)]
+    area_types: BTreeMap<OspfArea, OspfAreaType>,
+}
 
 impl OspfCoordinator for LocalOspfCoordinator {
     type Process = LocalOspfProcess;
@@ -234,12 +250,35 @@ impl OspfCoordinator for LocalOspfCoordinator {
     ) -> Result<Vec<Event<P, T>>, NetworkError> {
         let mut events = Vec::new();
 
+        if let NeighborhoodChange::AreaType { area, area_type } = delta {
+            if area_type.is_stub() {
+                self.area_types.insert(area, area_type);
+            } else {
+                self.area_types.remove(&area);
+            }
+            // push the new area types to all routers
+            for r in routers
+                .values_mut()
+                .filter_map(|r| r.internal_or_err().ok())
+            {
+                let mut r_events =
+                    r.update_ospf(|ospf| Ok(ospf.set_area_types(&self.area_types)))?;
+                events.append(&mut r_events);
+            }
+            return Ok(events);
+        }
+
         for (r, change) in LocalNeighborhoodChange::from_global(delta) {
+            let area_types = &self.area_types;
             let mut r_events = routers
                 .get_mut(&r)
                 .or_router_not_found(r)?
                 .internal_or_err()?
-                .update_ospf(|ospf| ospf.handle_neighborhood_change(change))?;
+                .update_ospf(|ospf| {
+                    let (table_changed, _) = ospf.set_area_types::<P, T>(area_types);
+                    let (recompute_bgp, events) = ospf.handle_neighborhood_change(change)?;
+                    Ok((table_changed || recompute_bgp, events))
+                })?;
             events.append(&mut r_events);
         }
 
@@ -26,13 +26,15 @@ use crate::{
     formatter::NetworkFormatter,
     network::Network,
     ospf::{
+        compute_ospf_table,
         local::{
             database::OspfRib,
             lsa::{Lsa, LsaKey},
             neighbor::{Neighbor, NeighborActions, NeighborEvent},
             OspfEvent,
         },
-        LinkWeight, NeighborhoodChange, OspfArea, OspfImpl, OspfProcess,
+        LinkWeight, NeighborhoodChange, OspfArea, OspfAreaType, OspfImpl, OspfProcess,
+        OspfStubView,
     },
     types::{DeviceError, Prefix, RouterId},
 };
@@ -45,6 +47,9 @@ pub struct LocalOspfProcess {
     pub(super) areas: OspfRib,
     #[serde(with = "As::<Vec<(Same, Same)>>")]
     pub(super) table: HashMap<RouterId, (Vec<RouterId>, LinkWeight)>,
+    /// View on external routes if the router is an internal router of a stub area.
+    #[serde(default)]
+    pub(super) stub_view: Option<OspfStubView>,
     #[serde(with = "As::<Vec<(Same, Same)>>")]
     pub(super) neighbor_links: HashMap<RouterId, LinkWeight>,
     #[serde(with = "As::<Vec<(Same, Same)>>")]
@@ -53,6 +58,9 @@ pub struct LocalOspfProcess {
     /// introduce the new LSA into the table and flood it (if `Some`).
     #[serde(with = "As::<Vec<(Same, Same)>>")]
     track_max_age: BTreeMap<Option<OspfArea>, HashMap<LsaKey, Option<Lsa>>>,
+    /// The type of all areas that are not `OspfAreaType::Normal`.
+    #[serde(default)]
+    pub(super) area_types: BTreeMap<OspfArea, OspfAreaType>,
 }
 
 /// Neighborhood change event local to a specific router.
@@ -146,6 +154,8 @@ impl LocalNeighborhoodChange {
                     LocalNeighborhoodChange::SetExternalLink { ext, weight: None },
                 )]
             }
+            // area types are pushed to all routers directly by the coordinator.
+            NeighborhoodChange::AreaType { .. } => Vec::new(),
             NeighborhoodChange::Batch(v) => v
                 .into_iter()
                 .flat_map(LocalNeighborhoodChange::from_global)
@@ -163,6 +173,37 @@ impl LocalOspfProcess {
         &self.areas
     }
 
+    /// Update the configured area types, and recompute the forwarding table. The returned flag
+    /// indicates whether the forwarding table has changed.
+    pub(super) fn set_area_types<P: Prefix, T: Default>(
+        &mut self,
+        area_types: &BTreeMap<OspfArea, OspfAreaType>,
+    ) -> (bool, Vec<Event<P, T>>) {
+        if &self.area_types == area_types {
+            return (false, Vec::new());
+        }
+        self.area_types.clone_from(area_types);
+        let old_table = std::mem::take(&mut self.table);
+        self.update_table();
+        (self.table != old_table, Vec::new())
+    }
+
+    /// Recompute the forwarding table from the RIB. If the router is an internal router of a stub
+    /// area, hidden paths are replaced by the default route (see `OspfAreaType`).
+    fn update_table(&mut self) {
+        let stub_area = match self.areas.num_areas() {
+            1 => self.areas.areas().next().and_then(|area| {
+                Some((
+                    *self.area_types.get(&area)?,
+                    self.areas.get_lsa_list(Some(area))?,
+                ))
+            }),
+            _ => None,
+        };
+        (self.table, self.stub_view) =
+            compute_ospf_table(self.router_id, self.areas.get_rib(), stub_area);
+    }
+
     /// Handle a neighborhood change.
     pub(super) fn handle_neighborhood_change<P: Prefix, T: Default>(
         &mut self,
@@ -492,20 +533,7 @@ impl LocalOspfProcess {
             }
 
             // update the routing table
-            self.table = self
-                .areas
-                .get_rib()
-                .iter()
-                .map(|(r, path)| {
-                    (
-                        *r,
-                        (
-                            Vec::from_iter(path.fibs.iter().copied()),
-                            path.cost.into_inner(),
-                        ),
-                    )
-                })
-                .collect();
+            self.update_table();
         }
 
         // perform the flooding
@@ -783,9 +811,11 @@ impl OspfProcess for LocalOspfProcess {
             router_id,
             areas: OspfRib::new(router_id),
             table: Default::default(),
+            stub_view: None,
             neighbor_links: HashMap::new(),
             neighbors: BTreeMap::new(),
             track_max_age: BTreeMap::new(),
+            area_types: BTreeMap::new(),
         }
     }
 
@@ -793,6 +823,10 @@ impl OspfProcess for LocalOspfProcess {
         &self.table
     }
 
+    fn get_stub_view(&self) -> Option<&OspfStubView> {
+        self.stub_view.as_ref()
+    }
+
     fn get_neighbors(&self) -> &HashMap<RouterId, LinkWeight> {
         &self.neighbor_links
     }
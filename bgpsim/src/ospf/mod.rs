@@ -22,7 +22,7 @@ mod iterator;
 pub mod local;
 pub use iterator::*;
 
-use std::collections::{hash_map::Entry, HashMap, HashSet};
+use std::collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap, HashSet};
 
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
@@ -40,7 +40,7 @@ use crate::{
 };
 
 use global::GlobalOspfCoordinator;
-use local::OspfEvent;
+use local::{Lsa, LsaKey, OspfEvent, OspfRibEntry};
 
 pub use global::GlobalOspf;
 pub use local::LocalOspf;
@@ -134,6 +134,169 @@ impl From<isize> for OspfArea {
     }
 }
 
+/// The cost of the default route that area border routers advertise into stub areas.
+pub const STUB_DEFAULT_COST: LinkWeight = 1.0;
+
+/// Type of an OSPF area. The type determines which routes are visible to routers inside that area.
+/// Hidden routes are replaced by a default route towards the closest area border router (ABR).
+///
+/// The area type only affects *internal* routers of that area, i.e., routers that are only part of
+/// that single area. Area border routers always see all routes. The backbone area is always a
+/// normal area.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize,
+)]
+pub enum OspfAreaType {
+    /// Regular area. All intra-area, inter-area, and external routes are visible.
+    #[default]
+    Normal,
+    /// Stub area. External routes are replaced by the default route.
+    Stub,
+    /// Totally stubby area. Both inter-area and external routes are replaced by the default route.
+    TotallyStubby,
+    /// Not-so-stubby area (NSSA). External routes are replaced by the default route, unless they
+    /// were originated by an AS boundary router inside the area.
+    Nssa,
+}
+
+impl std::fmt::Display for OspfAreaType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OspfAreaType::Normal => f.write_str("normal"),
+            OspfAreaType::Stub => f.write_str("stub"),
+            OspfAreaType::TotallyStubby => f.write_str("totally stubby"),
+            OspfAreaType::Nssa => f.write_str("nssa"),
+        }
+    }
+}
+
+impl OspfAreaType {
+    /// Returns `true` if the area is not a normal area.
+    pub fn is_stub(&self) -> bool {
+        !matches!(self, OspfAreaType::Normal)
+    }
+
+    /// Check whether the router `router_id` (inside an area of this type) sees the path directly,
+    /// or if it must use the default route instead.
+    fn is_visible(
+        &self,
+        router_id: RouterId,
+        path: &OspfRibEntry,
+        rib: &HashMap<RouterId, OspfRibEntry>,
+    ) -> bool {
+        let external = path.keys.values().find(|k| k.is_external());
+        match (self, external) {
+            (OspfAreaType::Normal, _) => true,
+            // directly connected external networks are always visible
+            (_, Some(key)) if key.router == router_id => true,
+            (OspfAreaType::Nssa, Some(key)) => rib.get(&key.router).is_some_and(|p| !p.inter_area),
+            (_, Some(_)) => false,
+            (OspfAreaType::TotallyStubby, None) => !path.inter_area,
+            (_, None) => true,
+        }
+    }
+}
+
+/// OSPF forwarding table, mapping each reachable router to the next-hops and the cost towards it.
+pub(crate) type OspfTable = HashMap<RouterId, (Vec<RouterId>, LinkWeight)>;
+
+/// View of an internal router of a stub area on the routes redistributed into OSPF (see
+/// [`Network::redistribute_into_ospf`]). External routes of ASBRs that are hidden in the area are
+/// replaced by the default route towards the closest area border routers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OspfStubView {
+    /// Type of the stub area.
+    pub area_type: OspfAreaType,
+    /// Routers reachable over intra-area paths. In a NSSA, the routes redistributed by these
+    /// routers are visible (as type-7 LSAs).
+    pub intra_area: BTreeSet<RouterId>,
+    /// Next-hops and cost of the default route, or `None` if no area border router is reachable.
+    pub default: Option<(Vec<RouterId>, LinkWeight)>,
+}
+
+impl OspfStubView {
+    /// Returns `true` if the routes redistributed by `asbr` are visible in the stub area. Only the
+    /// routes of ASBRs inside a NSSA are visible in that NSSA.
+    pub fn is_external_visible(&self, asbr: RouterId) -> bool {
+        match self.area_type {
+            OspfAreaType::Normal => true,
+            OspfAreaType::Nssa => self.intra_area.contains(&asbr),
+            OspfAreaType::Stub | OspfAreaType::TotallyStubby => false,
+        }
+    }
+}
+
+/// Compute the OSPF forwarding table of `router_id` from its RIB. If the router is an internal
+/// router of a stub area, then `stub_area` contains the type of that area and its LSA list. All
+/// paths that are hidden in that area are replaced by the default route. The default route points
+/// towards the closest area border routers, and its cost is the cost towards them plus
+/// `STUB_DEFAULT_COST`. If no area border router is reachable, hidden paths are removed from the
+/// table. For internal routers of a stub area, the function also returns their view on external
+/// routes redistributed into OSPF.
+pub(crate) fn compute_ospf_table(
+    router_id: RouterId,
+    rib: &HashMap<RouterId, OspfRibEntry>,
+    stub_area: Option<(OspfAreaType, &HashMap<LsaKey, Lsa>)>,
+) -> (OspfTable, Option<OspfStubView>) {
+    let entry = |path: &OspfRibEntry| {
+        (
+            Vec::from_iter(path.fibs.iter().copied()),
+            path.cost.into_inner(),
+        )
+    };
+
+    let Some((area_type, lsa_list)) = stub_area.filter(|(t, _)| t.is_stub()) else {
+        return (
+            rib.iter().map(|(r, path)| (*r, entry(path))).collect(),
+            None,
+        );
+    };
+
+    // The area border routers are the ones advertising Summary-LSAs into the area.
+    let abrs: BTreeSet<RouterId> = lsa_list
+        .iter()
+        .filter(|(key, lsa)| key.is_summary() && !lsa.is_max_age())
+        .map(|(key, _)| key.router)
+        .collect();
+    let closest = abrs
+        .iter()
+        .filter_map(|abr| rib.get(abr))
+        .filter(|path| !path.inter_area)
+        .min_set_by_key(|path| path.cost);
+    let default = closest.first().map(|best| {
+        let fibs: BTreeSet<RouterId> = closest
+            .iter()
+            .flat_map(|p| p.fibs.iter().copied())
+            .collect();
+        (
+            Vec::from_iter(fibs),
+            best.cost.into_inner() + STUB_DEFAULT_COST,
+        )
+    });
+
+    let table = rib
+        .iter()
+        .filter_map(|(r, path)| {
+            if area_type.is_visible(router_id, path, rib) {
+                Some((*r, entry(path)))
+            } else {
+                default.clone().map(|default| (*r, default))
+            }
+        })
+        .collect();
+    let intra_area = rib
+        .iter()
+        .filter(|(_, path)| !path.inter_area)
+        .map(|(r, _)| *r)
+        .collect();
+    let view = OspfStubView {
+        area_type,
+        intra_area,
+        default,
+    };
+    (table, Some(view))
+}
+
 /// Structure that stores the global OSPF configuration.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct OspfNetwork<Ospf = GlobalOspfCoordinator> {
@@ -143,12 +306,16 @@ pub struct OspfNetwork<Ospf = GlobalOspfCoordinator> {
     #[serde(with = "As::<Vec<(Same, Vec<(Same, Same)>)>>")]
     pub(crate) links: HashMap<RouterId, HashMap<RouterId, (LinkWeight, OspfArea)>>,
     failures: HashSet<(RouterId, RouterId)>,
+    #[serde(default)]
+    area_types: BTreeMap<OspfArea, OspfAreaType>,
     pub(crate) coordinator: Ospf,
 }
 
 impl<Ospf> PartialEq for OspfNetwork<Ospf> {
     fn eq(&self, other: &Self) -> bool {
-        self.links == other.links && self.external_links == other.external_links
+        self.links == other.links
+            && self.external_links == other.external_links
+            && self.area_types == other.area_types
     }
 }
 
@@ -164,6 +331,7 @@ where
                 external_links: self.external_links,
                 links: self.links,
                 failures: self.failures,
+                area_types: self.area_types,
                 coordinator: Ospf2::default(),
             },
             self.coordinator,
@@ -342,6 +510,32 @@ where
             .unwrap_or(LinkWeight::INFINITY)
     }
 
+    pub(crate) fn set_area_type<P: Prefix, T: Default>(
+        &mut self,
+        area: OspfArea,
+        area_type: OspfAreaType,
+        routers: &mut HashMap<RouterId, NetworkDevice<P, Ospf::Process>>,
+    ) -> Result<(Vec<Event<P, T>>, OspfAreaType), NetworkError> {
+        if area.is_backbone() && area_type.is_stub() {
+            return Err(NetworkError::BackboneAreaType(area_type));
+        }
+
+        let old_type = if area_type.is_stub() {
+            self.area_types.insert(area, area_type)
+        } else {
+            self.area_types.remove(&area)
+        }
+        .unwrap_or_default();
+
+        let events = self.coordinator.update(
+            NeighborhoodChange::AreaType { area, area_type },
+            routers,
+            &self.links,
+            &self.external_links,
+        )?;
+        Ok((events, old_type))
+    }
+
     pub(crate) fn set_area<P: Prefix, T: Default>(
         &mut self,
         a: RouterId,
@@ -389,6 +583,15 @@ where
         self.links.get(&a).and_then(|x| x.get(&b)).map(|(_, a)| *a)
     }
 
+    /// Return the type of an OSPF area. Areas are `OspfAreaType::Normal` unless configured
+    /// otherwise.
+    pub fn get_area_type(&self, area: impl Into<OspfArea>) -> OspfAreaType {
+        self.area_types
+            .get(&area.into())
+            .copied()
+            .unwrap_or_default()
+    }
+
     pub(crate) fn remove_link<P: Prefix, T: Default>(
         &mut self,
         a: RouterId,
@@ -750,6 +953,13 @@ pub enum NeighborhoodChange {
         /// External router
         ext: RouterId,
     },
+    /// The type of an OSPF area has changed
+    AreaType {
+        /// The area whose type was changed
+        area: OspfArea,
+        /// The new type of that area
+        area_type: OspfAreaType,
+    },
     /// A batch of single updates, all done atomically
     Batch(Vec<NeighborhoodChange>),
 }
@@ -811,6 +1021,13 @@ pub trait OspfProcess:
         }
     }
 
+    /// Get the view on external routes redistributed into OSPF, if the router is an internal router
+    /// of a stub area. Otherwise, all external routes are visible. By default, the router is not
+    /// part of any stub area.
+    fn get_stub_view(&self) -> Option<&OspfStubView> {
+        None
+    }
+
     /// Get the IGP cost for reaching a given internal router.
     fn get_cost(&self, dst: RouterId) -> Option<LinkWeight> {
         self.get_table().get(&dst).map(|(_, w)| *w)
@@ -883,6 +1100,18 @@ pub trait OspfProcess:
     }
 }
 
+/// Metric type of an external route redistributed into OSPF (see
+/// [`Network::redistribute_into_ospf`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ExternalMetricType {
+    /// Type 1 external metric. The cost of the route is the external metric plus the internal cost
+    /// towards the ASBR. E1 routes are always preferred over E2 routes.
+    E1,
+    /// Type 2 external metric. The cost of the route is only the external metric. Ties are broken
+    /// by the internal cost towards the ASBR.
+    E2,
+}
+
 /// Target for a lookup into the IGP table
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum IgpTarget {
@@ -50,12 +50,17 @@
 
 use crate::{
     forwarding_state::ForwardingState,
+    network::Network,
+    ospf::OspfImpl,
     types::{NetworkError, Prefix, RouterId},
 };
 
 use itertools::iproduct;
 use serde::{Deserialize, Serialize};
-use std::error::Error;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    error::Error,
+};
 use thiserror::Error;
 
 /// Extendable trait for policies. Each type that implements `Policy` is something that can *at
@@ -72,6 +77,15 @@ pub trait Policy<P: Prefix> {
 
     /// Return the prefix for which the policy should apply.
     fn prefix(&self) -> Option<P>;
+
+    /// Return all forwarding entries (router and prefix) on which the outcome of the policy
+    /// depends in `fw_state`. The outcome can only change if the next-hops of any of these entries
+    /// change. Return `None` if the policy may depend on the entire forwarding state (which is the
+    /// default).
+    fn dependencies(&self, fw_state: &mut ForwardingState<P>) -> Option<HashSet<(RouterId, P)>> {
+        let _ = fw_state;
+        None
+    }
 }
 
 /// Condition that can be checked for either being true or false.
@@ -178,6 +192,328 @@ impl<P: Prefix> Policy<P> for FwPolicy<P> {
             FwPolicy::LoadBalancingEdgeDisjoint(_, p, _) => *p,
         })
     }
+
+    /// A forwarding policy only depends on the next-hops of all routers along the paths from
+    /// `router` towards `prefix`. Routers in a loop or the router that drops the traffic are
+    /// included.
+    fn dependencies(&self, fw_state: &mut ForwardingState<P>) -> Option<HashSet<(RouterId, P)>> {
+        let router = self.router()?;
+        let prefix = self.prefix()?;
+        let nodes = match fw_state.get_paths(router, prefix) {
+            Ok(paths) => paths.into_iter().flatten().collect(),
+            Err(NetworkError::ForwardingLoop {
+                to_loop,
+                first_loop,
+            }) => to_loop.into_iter().chain(first_loop).collect(),
+            Err(NetworkError::ForwardingBlackHole(path)) => path,
+            Err(_) => return None,
+        };
+        Some(nodes.into_iter().map(|r| (r, prefix)).collect())
+    }
+}
+
+/// Check all `policies` on the same forwarding state, and return the result of each policy (in the
+/// same order as `policies`). The forwarding state caches all paths, so checking many policies on
+/// the same state is much cheaper than checking them individually.
+pub fn check_all<P: Prefix, T: Policy<P>>(
+    fw_state: &mut ForwardingState<P>,
+    policies: &[T],
+) -> Vec<Result<(), T::Err>> {
+    policies.iter().map(|p| p.check(fw_state)).collect()
+}
+
+/// A policy whose outcome changed between two forwarding states (see [`recheck`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyChange<E> {
+    /// Index of the policy in the slice passed to [`recheck`].
+    pub index: usize,
+    /// Result of the policy on the previous state.
+    pub old: Result<(), E>,
+    /// Result of the policy on the new state.
+    pub new: Result<(), E>,
+}
+
+impl<E> PolicyChange<E> {
+    /// Returns `true` if the policy was violated before, but is now satisfied.
+    pub fn is_fixed(&self) -> bool {
+        self.old.is_err() && self.new.is_ok()
+    }
+
+    /// Returns `true` if the policy was satisfied before, but is now violated.
+    pub fn is_broken(&self) -> bool {
+        self.old.is_ok() && self.new.is_err()
+    }
+}
+
+/// Re-check `policies` after the forwarding state changed from `prev_state` to `new_state`, and
+/// return all policies that flipped from satisfied to violated, or vice versa (ordered by their
+/// index). Only the policies for which any of their [`Policy::dependencies`] (computed on
+/// `prev_state`) have changed are evaluated, all others cannot change their outcome. A dependency
+/// `(r, p)` has changed if the next-hops of `r` changed for any prefix that contains `p`.
+///
+/// The result is the same as comparing [`check_all`] on both states, but much cheaper if only few
+/// forwarding entries have changed (e.g., after a single link failure).
+pub fn recheck<P: Prefix, T: Policy<P>>(
+    prev_state: &mut ForwardingState<P>,
+    new_state: &mut ForwardingState<P>,
+    policies: &[T],
+) -> Vec<PolicyChange<T::Err>> {
+    let mut changed: HashMap<RouterId, Vec<P>> = HashMap::new();
+    for (router, prefix) in prev_state.changed_entries(new_state) {
+        changed.entry(router).or_default().push(prefix);
+    }
+    let mut result = Vec::new();
+    if changed.is_empty() {
+        return result;
+    }
+
+    for (index, policy) in policies.iter().enumerate() {
+        let affected = match policy.dependencies(prev_state) {
+            Some(deps) => deps.iter().any(|(r, p)| {
+                changed
+                    .get(r)
+                    .map(|prefixes| prefixes.iter().any(|q| q.contains(p)))
+                    .unwrap_or(false)
+            }),
+            None => true,
+        };
+        if !affected {
+            continue;
+        }
+        let old = policy.check(prev_state);
+        let new = policy.check(new_state);
+        if old.is_ok() != new.is_ok() {
+            result.push(PolicyChange { index, old, new });
+        }
+    }
+
+    result
+}
+
+/// Requirement of a [`PolicyRule`]. The requirement is instantiated as an [`FwPolicy`] for each
+/// router and each prefix matched by the rule.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Requirement {
+    /// The router must reach the prefix (see [`FwPolicy::Reachable`]).
+    Reachable,
+    /// The router must not reach the prefix (see [`FwPolicy::NotReachable`]).
+    NotReachable,
+    /// All paths must satisfy the path condition (see [`FwPolicy::PathCondition`]).
+    PathCondition(PathCondition),
+    /// Traffic must not run in a loop (see [`FwPolicy::LoopFree`]).
+    LoopFree,
+    /// There must be at least `k` paths (see [`FwPolicy::LoadBalancing`]).
+    LoadBalancing(usize),
+}
+
+impl Requirement {
+    /// Create the [`FwPolicy`] of this requirement for a specific router and prefix.
+    pub fn instantiate<P: Prefix>(&self, router: RouterId, prefix: P) -> FwPolicy<P> {
+        match self {
+            Self::Reachable => FwPolicy::Reachable(router, prefix),
+            Self::NotReachable => FwPolicy::NotReachable(router, prefix),
+            Self::PathCondition(c) => FwPolicy::PathCondition(router, prefix, c.clone()),
+            Self::LoopFree => FwPolicy::LoopFree(router, prefix),
+            Self::LoadBalancing(k) => FwPolicy::LoadBalancing(router, prefix, *k),
+        }
+    }
+}
+
+/// A rule of a policy catalog (see [`verify_catalog`]). The rule applies to all prefixes known in
+/// the network that are contained in `pattern`, and requires that `requirement` holds for each of
+/// the `routers`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(bound(deserialize = "P: for<'a> serde::Deserialize<'a>"))]
+pub struct PolicyRule<P: Prefix> {
+    /// Prefix pattern. The rule applies to all known prefixes `p` for which `pattern.contains(p)`.
+    pub pattern: P,
+    /// Routers for which the requirement must hold.
+    pub routers: Vec<RouterId>,
+    /// The requirement that must hold.
+    pub requirement: Requirement,
+}
+
+impl<P: Prefix> PolicyRule<P> {
+    /// Create a new rule.
+    pub fn new(
+        pattern: P,
+        routers: impl IntoIterator<Item = RouterId>,
+        requirement: Requirement,
+    ) -> Self {
+        Self {
+            pattern,
+            routers: routers.into_iter().collect(),
+            requirement,
+        }
+    }
+}
+
+/// Result of checking a single [`PolicyRule`] (see [`verify_catalog`]).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "P: for<'a> serde::Deserialize<'a>"))]
+pub struct RuleReport<P: Prefix> {
+    /// The rule that was checked.
+    pub rule: PolicyRule<P>,
+    /// All known prefixes that are matched by the rule's pattern (sorted).
+    pub prefixes: Vec<P>,
+    /// Number of individual policies that were checked, i.e., the number of prefixes times the
+    /// number of routers.
+    pub num_checked: usize,
+    /// Counterexamples, i.e., the errors of all policies that are violated.
+    pub violations: Vec<PolicyError<P>>,
+}
+
+impl<P: Prefix> RuleReport<P> {
+    /// Returns `true` if the rule is satisfied for all matched prefixes and routers. A rule that
+    /// does not match any prefix is trivially satisfied.
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Verify a catalog of rules on the current (converged) state of the network. Each rule is
+/// evaluated on all known prefixes that match its pattern. The prefixes of each pattern are only
+/// computed once, and all rules are checked on the same forwarding state, such that the forwarding
+/// paths are only computed once.
+///
+/// The function returns a report for each rule (in the same order as `rules`), containing all
+/// counterexamples.
+pub fn verify_catalog<P: Prefix, Q, Ospf: OspfImpl>(
+    net: &Network<P, Q, Ospf>,
+    rules: &[PolicyRule<P>],
+) -> Vec<RuleReport<P>> {
+    let mut fw_state = net.get_forwarding_state();
+
+    // group the known prefixes by the patterns
+    let mut groups: HashMap<P, Vec<P>> = HashMap::new();
+    for rule in rules {
+        groups.entry(rule.pattern).or_insert_with(|| {
+            let mut prefixes: Vec<P> = net
+                .get_known_prefixes()
+                .filter(|p| rule.pattern.contains(p))
+                .copied()
+                .collect();
+            prefixes.sort();
+            prefixes
+        });
+    }
+
+    rules
+        .iter()
+        .map(|rule| {
+            let prefixes = groups.get(&rule.pattern).cloned().unwrap_or_default();
+            let mut num_checked = 0;
+            let mut violations = Vec::new();
+            for prefix in prefixes.iter() {
+                for router in rule.routers.iter() {
+                    num_checked += 1;
+                    if let Err(e) = rule
+                        .requirement
+                        .instantiate(*router, *prefix)
+                        .check(&mut fw_state)
+                    {
+                        violations.push(e);
+                    }
+                }
+            }
+            RuleReport {
+                rule: rule.clone(),
+                prefixes,
+                num_checked,
+                violations,
+            }
+        })
+        .collect()
+}
+
+/// Report of the standard battery of checks performed by [`diagnose`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "P: for<'a> serde::Deserialize<'a>"))]
+pub struct Diagnosis<P: Prefix> {
+    /// All known prefixes that were checked (sorted).
+    pub prefixes: Vec<P>,
+    /// Number of pairs of internal routers and prefixes that were checked.
+    pub num_checked: usize,
+    /// Counterexamples for reachability, i.e., each internal router that cannot reach a prefix,
+    /// together with the reason (either a black hole or a forwarding loop).
+    pub unreachable: Vec<(RouterId, PolicyError<P>)>,
+    /// All distinct forwarding loops.
+    pub forwarding_loops: Vec<PolicyError<P>>,
+    /// All distinct black holes, each pointing to the router that drops the traffic.
+    pub black_holes: Vec<PolicyError<P>>,
+}
+
+impl<P: Prefix> Diagnosis<P> {
+    /// Returns `true` if all checks passed.
+    pub fn passed(&self) -> bool {
+        self.is_reachable() && self.is_loop_free() && self.is_black_hole_free()
+    }
+
+    /// Returns `true` if all internal routers can reach all known prefixes.
+    pub fn is_reachable(&self) -> bool {
+        self.unreachable.is_empty()
+    }
+
+    /// Returns `true` if no traffic runs in a forwarding loop.
+    pub fn is_loop_free(&self) -> bool {
+        self.forwarding_loops.is_empty()
+    }
+
+    /// Returns `true` if no traffic is dropped.
+    pub fn is_black_hole_free(&self) -> bool {
+        self.black_holes.is_empty()
+    }
+}
+
+/// Run a standard battery of checks on the current (converged) state of the network: every
+/// internal router must reach every known prefix, the traffic must not run in a forwarding loop,
+/// and it must not be dropped. The function checks [`FwPolicy::Reachable`] for all pairs of
+/// internal routers and known prefixes on the same forwarding state, and summarizes the
+/// counterexamples. Each forwarding loop and each black hole is only reported once, even if
+/// traffic from many routers is affected by it.
+pub fn diagnose<P: Prefix, Q, Ospf: OspfImpl>(net: &Network<P, Q, Ospf>) -> Diagnosis<P> {
+    let mut fw_state = net.get_forwarding_state();
+
+    let mut prefixes: Vec<P> = net.get_known_prefixes().copied().collect();
+    prefixes.sort();
+    let mut routers: Vec<RouterId> = net.internal_indices().collect();
+    routers.sort();
+
+    let mut num_checked = 0;
+    let mut unreachable = Vec::new();
+    let mut forwarding_loops = Vec::new();
+    let mut black_holes = Vec::new();
+    let mut seen_loops: HashSet<(P, BTreeSet<RouterId>)> = HashSet::new();
+    let mut seen_black_holes: HashSet<(RouterId, P)> = HashSet::new();
+
+    for (prefix, router) in iproduct!(prefixes.iter().copied(), routers.iter().copied()) {
+        num_checked += 1;
+        let Err(e) = FwPolicy::Reachable(router, prefix).check(&mut fw_state) else {
+            continue;
+        };
+        match &e {
+            PolicyError::BlackHole { router, prefix }
+                if seen_black_holes.insert((*router, *prefix)) =>
+            {
+                black_holes.push(e.clone())
+            }
+            PolicyError::ForwardingLoop { path, prefix }
+                if seen_loops.insert((*prefix, path.iter().copied().collect())) =>
+            {
+                forwarding_loops.push(e.clone())
+            }
+            _ => {}
+        }
+        unreachable.push((router, e));
+    }
+
+    Diagnosis {
+        prefixes,
+        num_checked,
+        unreachable,
+        forwarding_loops,
+        black_holes,
+    }
 }
 
 /// Condition on the path, which may be either to require that the path passes through a specirif
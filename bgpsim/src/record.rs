@@ -16,14 +16,20 @@
 //! Module to record actions on the network and extract a trace of how the forwarding state changes
 //! over time.
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    event::EventQueue,
+    bgp::BgpEvent,
+    config::{ConfigModifier, NetworkConfig},
+    event::{Event, EventQueue},
     forwarding_state::ForwardingState,
     interactive::InteractiveNetwork,
     network::Network,
-    types::{NetworkError, RouterId, SinglePrefix, StepUpdate},
+    ospf::{GlobalOspf, OspfImpl},
+    policies::Policy,
+    types::{AsId, Community, NetworkError, Prefix, RouterId, SinglePrefix, StepUpdate},
 };
 
 /// Extension trait that allows you to record events on the network. This is only available for
@@ -54,6 +60,16 @@ pub trait RecordNetwork<Q> {
         initial_trace: ConvergenceTrace,
         initial_time: Option<f64>,
     ) -> Result<ConvergenceRecording, NetworkError>;
+
+    /// Get the intervals during the last recorded run (see [`Network::event_timeline`]) in which
+    /// each internal router had no forwarding entry for `prefix`, i.e., in which it dropped all
+    /// traffic towards `prefix`. Each interval is given as `(start, end)`, using the time of the
+    /// [`EventTimeline`]. The recorded action happens at time 0. If the router still has no route
+    /// at the end of the run, then `end` is `None`. Routers that always had a route are omitted.
+    fn blackhole_durations(
+        &self,
+        prefix: SinglePrefix,
+    ) -> BTreeMap<RouterId, Vec<(f64, Option<f64>)>>;
 }
 
 impl<Q> RecordNetwork<Q> for Network<SinglePrefix, Q>
@@ -98,19 +114,50 @@ where
         initial_time: Option<f64>,
     ) -> Result<ConvergenceRecording, NetworkError> {
         let t = initial_time.unwrap_or_default();
+        let mut timeline = EventTimeline::default();
+        for r in self.internal_routers() {
+            timeline.update_fib(r.router_id(), 0.0, !r.get_next_hop(SinglePrefix).is_empty());
+        }
+        let mut rib_sizes = self.rib_sizes();
+        let mut state_size: usize = rib_sizes.values().sum();
+        timeline.state_size.push((0.0, state_size));
         while let Some((step, event)) = self.simulate_step()? {
+            let time = self.queue().get_time().map(|x| x - t);
+            let timeline_time = timeline.push(&event, time, !matches!(step, StepUpdate::Unchanged));
+            // only the router that processed the event can change its tables.
+            if let Ok(r) = self.get_internal_router(event.router()) {
+                let size = r.bgp.rib_size();
+                let old = rib_sizes.insert(event.router(), size).unwrap_or_default();
+                state_size = state_size + size - old;
+            }
+            timeline.state_size.push((timeline_time, state_size));
             match step {
                 StepUpdate::Unchanged => {}
                 StepUpdate::Single(delta) => {
-                    let time = self.queue().get_time().map(|x| x - t);
+                    timeline.update_fib(event.router(), timeline_time, !delta.new.is_empty());
                     trace.push((vec![(event.router(), delta.old, delta.new)], time.into()));
                 }
                 StepUpdate::Multiple => {
+                    for r in self.internal_routers() {
+                        let has_route = !r.get_next_hop(SinglePrefix).is_empty();
+                        timeline.update_fib(r.router_id(), timeline_time, has_route);
+                    }
                     log::warn!("Ignoring OSPF event that might changed multiple FIB entries!")
                 }
             }
         }
-        Ok(ConvergenceRecording::new(initial_fw_state, trace))
+        let num_events = timeline.len();
+        self.event_timeline = timeline;
+        let mut recording = ConvergenceRecording::new(initial_fw_state, trace);
+        recording.num_events = num_events;
+        Ok(recording)
+    }
+
+    fn blackhole_durations(
+        &self,
+        _prefix: SinglePrefix,
+    ) -> BTreeMap<RouterId, Vec<(f64, Option<f64>)>> {
+        self.event_timeline.blackholes.clone()
     }
 }
 
@@ -122,6 +169,7 @@ pub struct ConvergenceRecording {
     state: ForwardingState<SinglePrefix>,
     trace: ConvergenceTrace,
     pointer: usize,
+    num_events: usize,
 }
 
 impl ConvergenceRecording {
@@ -131,6 +179,7 @@ impl ConvergenceRecording {
             state: initial_fw_state,
             trace,
             pointer: 0,
+            num_events: 0,
         }
     }
 
@@ -144,6 +193,12 @@ impl ConvergenceRecording {
         &self.trace
     }
 
+    /// Get the number of events that were processed while recording. This includes events that did
+    /// not change the forwarding state.
+    pub fn num_events(&self) -> usize {
+        self.num_events
+    }
+
     /// Transform the recording into a trace.
     pub fn as_trace(self) -> ConvergenceTrace {
         self.trace
@@ -214,6 +269,51 @@ impl ConvergenceRecording {
         self.trace.is_empty()
     }
 
+    /// Check `policy` on the states during the convergence process, starting with the initial
+    /// state (position 0), and ending with the final state (position `self.len()`). The function
+    /// returns the position and the error of every state that violates the policy. The position of
+    /// the recording (see [`ConvergenceRecording::pos`]) is not changed.
+    ///
+    /// With [`TransientCheck::StablePoints`], a state is only evaluated if none of the forwarding
+    /// entries the policy depends on (see [`Policy::dependencies`]) change during the next `k`
+    /// steps. This ignores transient states caused by the ordering of changes within a single
+    /// logical step, while violations that persist for at least `k` steps are still reported.
+    pub fn check_transient<T: Policy<SinglePrefix>>(
+        &self,
+        policy: &T,
+        mode: TransientCheck,
+    ) -> Vec<(usize, T::Err)> {
+        let mut recording = self.clone();
+        while recording.back().is_some() {}
+
+        let mut violations = Vec::new();
+        for pos in 0..=recording.len() {
+            if pos > 0 {
+                recording.step();
+            }
+            let evaluate = match mode {
+                TransientCheck::EveryStep => true,
+                TransientCheck::StablePoints(k) => {
+                    let deps = policy.dependencies(recording.state());
+                    let end = (pos + k).min(recording.len());
+                    recording.trace[pos..end]
+                        .iter()
+                        .flat_map(|(deltas, _)| deltas)
+                        .all(|(r, _, _)| {
+                            deps.as_ref()
+                                .is_some_and(|deps| !deps.contains(&(*r, SinglePrefix)))
+                        })
+                }
+            };
+            if evaluate {
+                if let Err(e) = policy.check(recording.state()) {
+                    violations.push((pos, e));
+                }
+            }
+        }
+        violations
+    }
+
     /// Reverts to and releases the initial `ForwardingState` held by `self`, while consuming
     /// `self`. This function will also drop any timing information.
     pub fn into_initial_fw_state(self) -> ForwardingState<SinglePrefix> {
@@ -232,6 +332,18 @@ impl ConvergenceRecording {
     }
 }
 
+/// Which states of a [`ConvergenceRecording`] are evaluated by
+/// [`ConvergenceRecording::check_transient`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TransientCheck {
+    /// Evaluate the policy after every single step.
+    EveryStep,
+    /// Only evaluate the policy on states that are stable, i.e., where the forwarding entries the
+    /// policy depends on do not change for the given number of consecutive steps. The final state
+    /// is always stable.
+    StablePoints(usize),
+}
+
 /// This structure captures the essence of a trace, that is, the entire evolution of the forwarding
 /// state during the convergence process. It does not capture the initial or the final state, but it
 /// should be efficient to compare entries.
@@ -304,3 +416,249 @@ impl<T> AlwaysEq<T> {
 
 /// Forwarding state delta.
 pub type FwDelta = (RouterId, Vec<RouterId>, Vec<RouterId>);
+
+/// Timeline of all events processed during the last recording (see [`RecordNetwork`]), in a shape
+/// suitable to plot the convergence activity of each router. Obtain it using
+/// [`Network::event_timeline`] after a recorded run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EventTimeline {
+    entries: Vec<TimelineEntry>,
+    /// Intervals in which routers had no forwarding entry.
+    blackholes: BTreeMap<RouterId, Vec<(f64, Option<f64>)>>,
+    /// Total number of BGP table entries after each event.
+    #[serde(default)]
+    pub(crate) state_size: Vec<(f64, usize)>,
+}
+
+/// A single event processed during a recording.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimelineEntry {
+    /// Time when the event was processed. This is the (zero-normalized) time of the queue. If the
+    /// queue has no notion of time, the time is the number of events processed so far (including
+    /// this one), such that the recorded action happens at time 0.
+    pub time: f64,
+    /// The router that processed the event.
+    pub router: RouterId,
+    /// The router that sent the message.
+    pub src: RouterId,
+    /// The kind of message.
+    pub kind: TimelineEventKind,
+    /// Whether the event changed the forwarding state of the router.
+    pub fw_change: bool,
+}
+
+/// The kind of message processed by a router.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TimelineEventKind {
+    /// BGP Update message
+    BgpUpdate,
+    /// BGP Withdraw message
+    BgpWithdraw,
+    /// OSPF message
+    Ospf,
+}
+
+impl EventTimeline {
+    /// Append a processed event to the timeline, and return the time of the event.
+    fn push<T>(
+        &mut self,
+        event: &Event<SinglePrefix, T>,
+        time: Option<f64>,
+        fw_change: bool,
+    ) -> f64 {
+        let (src, kind) = match event {
+            Event::Bgp {
+                src,
+                e: BgpEvent::Update(_),
+                ..
+            } => (*src, TimelineEventKind::BgpUpdate),
+            Event::Bgp {
+                src,
+                e: BgpEvent::Withdraw(_),
+                ..
+            } => (*src, TimelineEventKind::BgpWithdraw),
+            Event::Ospf { src, .. } => (*src, TimelineEventKind::Ospf),
+        };
+        let time = time.unwrap_or(self.entries.len() as f64 + 1.0);
+        self.entries.push(TimelineEntry {
+            time,
+            router: event.router(),
+            src,
+            kind,
+            fw_change,
+        });
+        time
+    }
+
+    /// Record whether `router` has a forwarding entry at `time`. Intervals without a forwarding
+    /// entry that end at the time when they start are dropped.
+    fn update_fib(&mut self, router: RouterId, time: f64, has_route: bool) {
+        let intervals = self.blackholes.entry(router).or_default();
+        match (intervals.last_mut(), has_route) {
+            (Some((start, end @ None)), true) => {
+                if *start < time {
+                    *end = Some(time);
+                } else {
+                    intervals.pop();
+                }
+            }
+            (Some((_, None)), false) | (None, true) | (Some((_, Some(_))), true) => {}
+            (None, false) | (Some((_, Some(_))), false) => intervals.push((time, None)),
+        }
+        if intervals.is_empty() {
+            self.blackholes.remove(&router);
+        }
+    }
+
+    /// Get all entries in the order in which they were processed.
+    pub fn entries(&self) -> &[TimelineEntry] {
+        &self.entries
+    }
+
+    /// Get the number of events in the timeline.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the timeline is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Group the entries by the router that processed them. The entries of each router are in the
+    /// order in which they were processed.
+    pub fn by_router(&self) -> BTreeMap<RouterId, Vec<TimelineEntry>> {
+        let mut result: BTreeMap<RouterId, Vec<TimelineEntry>> = BTreeMap::new();
+        for entry in &self.entries {
+            result.entry(entry.router).or_default().push(*entry);
+        }
+        result
+    }
+}
+
+/// A single step of a scenario script that can be replayed on a network (see [`replay`] and
+/// [`resume`]). After each step, the network is simulated until it converges.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "P: for<'a> Deserialize<'a>"))]
+pub enum ScenarioStep<P: Prefix> {
+    /// Apply a configuration modifier.
+    Modify(ConfigModifier<P>),
+    /// Advertise a route at an external router.
+    Advertise {
+        /// The external router that advertises the route.
+        source: RouterId,
+        /// The advertised prefix.
+        prefix: P,
+        /// AS path of the route.
+        as_path: Vec<AsId>,
+        /// MED of the route.
+        med: Option<u32>,
+        /// Communities of the route.
+        #[serde(alias = "community")]
+        communities: Vec<Community>,
+    },
+    /// Withdraw a route from an external router.
+    Withdraw {
+        /// The external router that withdraws the route.
+        source: RouterId,
+        /// The withdrawn prefix.
+        prefix: P,
+    },
+}
+
+impl<P: Prefix> ScenarioStep<P> {
+    /// Apply the step on the network and let it converge.
+    pub fn apply<Q, Ospf>(&self, net: &mut Network<P, Q, Ospf>) -> Result<(), NetworkError>
+    where
+        Q: EventQueue<P>,
+        Ospf: OspfImpl,
+    {
+        match self {
+            ScenarioStep::Modify(modifier) => net.apply_modifier(modifier),
+            ScenarioStep::Advertise {
+                source,
+                prefix,
+                as_path,
+                med,
+                communities,
+            } => net.advertise_external_route(
+                *source,
+                *prefix,
+                as_path.iter().copied(),
+                *med,
+                communities.iter().copied(),
+            ),
+            ScenarioStep::Withdraw { source, prefix } => {
+                net.withdraw_external_route(*source, *prefix)
+            }
+        }
+    }
+}
+
+/// A converged network captured in the middle of a scenario, together with the position in the
+/// scenario log at which it was captured. Use [`resume`] to replay the remaining steps starting
+/// from the checkpoint instead of replaying the entire scenario.
+#[derive(Debug, Clone)]
+pub struct Checkpoint<P: Prefix, Q, Ospf: OspfImpl = GlobalOspf> {
+    net: Network<P, Q, Ospf>,
+    position: usize,
+}
+
+impl<P: Prefix, Q, Ospf> Checkpoint<P, Q, Ospf>
+where
+    Q: EventQueue<P> + Clone,
+    Ospf: OspfImpl,
+{
+    /// Capture the current state of `net`, after the first `position` steps of the scenario log
+    /// were applied. The network is simulated until it converges before it is captured.
+    pub fn new(net: &Network<P, Q, Ospf>, position: usize) -> Result<Self, NetworkError> {
+        let mut net = net.clone();
+        net.simulate()?;
+        Ok(Self { net, position })
+    }
+
+    /// Get the network captured by the checkpoint.
+    pub fn net(&self) -> &Network<P, Q, Ospf> {
+        &self.net
+    }
+
+    /// Get the number of steps of the scenario log that were applied before the checkpoint.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Get the steps of `log` that still need to be applied after the checkpoint.
+    pub fn remaining<'a>(&self, log: &'a [ScenarioStep<P>]) -> &'a [ScenarioStep<P>] {
+        &log[self.position.min(log.len())..]
+    }
+}
+
+/// Replay all steps in `log` on `net`, letting the network converge after each step.
+pub fn replay<P, Q, Ospf>(
+    net: &mut Network<P, Q, Ospf>,
+    log: &[ScenarioStep<P>],
+) -> Result<(), NetworkError>
+where
+    P: Prefix,
+    Q: EventQueue<P>,
+    Ospf: OspfImpl,
+{
+    log.iter().try_for_each(|step| step.apply(net))
+}
+
+/// Resume a scenario from `checkpoint` by replaying `remaining_log` on a copy of the captured
+/// network (see [`Checkpoint::remaining`]). The checkpoint itself remains untouched, such that it
+/// can be resumed multiple times.
+pub fn resume<P, Q, Ospf>(
+    checkpoint: &Checkpoint<P, Q, Ospf>,
+    remaining_log: &[ScenarioStep<P>],
+) -> Result<Network<P, Q, Ospf>, NetworkError>
+where
+    P: Prefix,
+    Q: EventQueue<P> + Clone,
+    Ospf: OspfImpl,
+{
+    let mut net = checkpoint.net.clone();
+    replay(&mut net, remaining_log)?;
+    Ok(net)
+}
@@ -18,14 +18,20 @@
 //! This module contains the necessary structures to build route maps for internal BGP routers.
 
 use crate::{
-    bgp::BgpRibEntry,
+    bgp::{BgpRibEntry, BgpRoute, BgpSessionType},
     ospf::LinkWeight,
-    types::{AsId, Prefix, PrefixSet, RouterId},
+    types::{AsId, Community, Prefix, PrefixSet, RouterId},
 };
 
+use itertools::Itertools;
 use ordered_float::NotNan;
 use serde::{Deserialize, Serialize};
-use std::{cmp::Ordering, fmt};
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+};
 
 /// # Main RouteMap structure
 /// A route map can match on a BGP route, to change some value of the route, or to bock it. Use the
@@ -44,7 +50,7 @@ use std::{cmp::Ordering, fmt};
 ///     .continue_next()
 ///     .build();
 /// ```
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Hash, Serialize, Deserialize)]
 #[serde(bound(deserialize = "P: for<'a> serde::Deserialize<'a>"))]
 pub struct RouteMap<P: Prefix> {
     /// In which order should the route maps be checked. Lower values mean that they are checked
@@ -82,8 +88,22 @@ impl<P: Prefix> RouteMap<P> {
     /// Apply the route map on a route (`BgpRibEntry<P>`). The funciton returns either None, if the
     /// route matched and the state of the `RouteMap` is set to `Deny`, or `Some(BgpRibEntry<P>)`, with
     /// the values modified as described, if the route matches.
-    pub fn apply(&self, mut route: BgpRibEntry<P>) -> (RouteMapFlow, Option<BgpRibEntry<P>>) {
-        match self.conds.iter().all(|c| c.matches(&route)) {
+    pub fn apply(&self, route: BgpRibEntry<P>) -> (RouteMapFlow, Option<BgpRibEntry<P>>) {
+        self.apply_with(route, &HashMap::new())
+    }
+
+    /// Apply the route map on a route (`BgpRibEntry<P>`), like [`RouteMap::apply`]. Named AS-path
+    /// access-lists (see [`RouteMapMatch::AsPathList`]) are looked up in `as_path_lists`.
+    pub fn apply_with(
+        &self,
+        mut route: BgpRibEntry<P>,
+        as_path_lists: &AsPathLists,
+    ) -> (RouteMapFlow, Option<BgpRibEntry<P>>) {
+        match self
+            .conds
+            .iter()
+            .all(|c| c.matches_with(&route, as_path_lists))
+        {
             true => {
                 if self.state.is_deny() {
                     // route is denied
@@ -122,13 +142,43 @@ impl<P: Prefix> RouteMap<P> {
     pub fn matches(&self, route: &BgpRibEntry<P>) -> bool {
         self.conds.iter().all(|c| c.matches(route))
     }
+
+    /// Returns wether the Route Map matches the given entry, looking up named AS-path access-lists
+    /// in `as_path_lists`.
+    pub fn matches_with(&self, route: &BgpRibEntry<P>, as_path_lists: &AsPathLists) -> bool {
+        self.conds
+            .iter()
+            .all(|c| c.matches_with(route, as_path_lists))
+    }
 }
 
 /// Trait that exposes a function to apply a sorted-list of route-maps on a route to transform it.
 pub trait RouteMapList<P: Prefix> {
     /// Apply the route to the sequence of route-maps. This sequence **must be sorted** by the
     /// route-map order.
-    fn apply(self, route: BgpRibEntry<P>) -> Option<BgpRibEntry<P>>;
+    fn apply(self, route: BgpRibEntry<P>) -> Option<BgpRibEntry<P>>
+    where
+        Self: Sized,
+    {
+        self.apply_with(route, &HashMap::new())
+    }
+
+    /// Apply the route to the sequence of route-maps, like [`RouteMapList::apply`]. Named AS-path
+    /// access-lists are looked up in `as_path_lists`.
+    fn apply_with(
+        self,
+        route: BgpRibEntry<P>,
+        as_path_lists: &AsPathLists,
+    ) -> Option<BgpRibEntry<P>>;
+
+    /// Apply the route to the sequence of route-maps, like [`RouteMapList::apply_with`]. In
+    /// addition, return the order of all route-map items that matched the route, in the order in
+    /// which they were applied.
+    fn apply_traced(
+        self,
+        route: BgpRibEntry<P>,
+        as_path_lists: &AsPathLists,
+    ) -> (Option<BgpRibEntry<P>>, Vec<i16>);
 }
 
 impl<'a, P, I> RouteMapList<P> for I
@@ -136,29 +186,316 @@ where
     P: Prefix + 'a,
     I: IntoIterator<Item = &'a RouteMap<P>>,
 {
-    fn apply(self, mut entry: BgpRibEntry<P>) -> Option<BgpRibEntry<P>> {
-        let mut wait_for = None;
-        for map in self {
-            if let Some(x) = wait_for {
-                match map.order.cmp(&x) {
-                    Ordering::Less => continue,
-                    Ordering::Equal => {}
-                    Ordering::Greater => return Some(entry),
-                }
+    fn apply_with(
+        self,
+        entry: BgpRibEntry<P>,
+        as_path_lists: &AsPathLists,
+    ) -> Option<BgpRibEntry<P>> {
+        apply_route_maps(self, entry, as_path_lists, None)
+    }
+
+    fn apply_traced(
+        self,
+        entry: BgpRibEntry<P>,
+        as_path_lists: &AsPathLists,
+    ) -> (Option<BgpRibEntry<P>>, Vec<i16>) {
+        let mut hits = Vec::new();
+        let entry = apply_route_maps(self, entry, as_path_lists, Some(&mut hits));
+        (entry, hits)
+    }
+}
+
+/// Apply the route to the sequence of route-maps. If `hits` is given, then push the order of each
+/// route-map item that matches the route.
+fn apply_route_maps<'a, P, I>(
+    maps: I,
+    entry: BgpRibEntry<P>,
+    as_path_lists: &AsPathLists,
+    hits: Option<&mut Vec<i16>>,
+) -> Option<BgpRibEntry<P>>
+where
+    P: Prefix + 'a,
+    I: IntoIterator<Item = &'a RouteMap<P>>,
+{
+    match eval_route_maps(maps, entry, as_path_lists, hits) {
+        Evaluation::Denied => None,
+        Evaluation::Exit(entry) | Evaluation::Continue(entry, _) => Some(entry),
+    }
+}
+
+/// Outcome of evaluating a sorted sequence of route-map items on a route.
+enum Evaluation<P: Prefix> {
+    /// The route was denied.
+    Denied,
+    /// The route was permitted, and the evaluation stopped before the end of the sequence.
+    Exit(BgpRibEntry<P>),
+    /// The route was permitted, and the evaluation continues after the end of the sequence, either
+    /// at the next item, or at the item with the given order.
+    Continue(BgpRibEntry<P>, Option<i16>),
+}
+
+/// Evaluate the sequence of route-maps on the route. If `hits` is given, then push the order of
+/// each route-map item that matches the route.
+fn eval_route_maps<'a, P, I>(
+    maps: I,
+    mut entry: BgpRibEntry<P>,
+    as_path_lists: &AsPathLists,
+    mut hits: Option<&mut Vec<i16>>,
+) -> Evaluation<P>
+where
+    P: Prefix + 'a,
+    I: IntoIterator<Item = &'a RouteMap<P>>,
+{
+    let mut wait_for = None;
+    for map in maps {
+        if let Some(x) = wait_for {
+            match map.order.cmp(&x) {
+                Ordering::Less => continue,
+                Ordering::Equal => {}
+                Ordering::Greater => return Evaluation::Exit(entry),
+            }
+        }
+        if let Some(hits) = hits.as_mut() {
+            if map.matches_with(&entry, as_path_lists) {
+                hits.push(map.order);
             }
-            match map.apply(entry) {
-                (cont, Some(e)) => {
-                    entry = e;
-                    match cont {
-                        RouteMapFlow::Exit => return Some(entry),
-                        RouteMapFlow::Continue => wait_for = None,
-                        RouteMapFlow::ContinueAt(x) => wait_for = Some(x),
-                    }
+        }
+        match map.apply_with(entry, as_path_lists) {
+            (cont, Some(e)) => {
+                entry = e;
+                match cont {
+                    RouteMapFlow::Exit => return Evaluation::Exit(entry),
+                    RouteMapFlow::Continue => wait_for = None,
+                    RouteMapFlow::ContinueAt(x) => wait_for = Some(x),
                 }
-                (_, None) => return None,
             }
+            (_, None) => return Evaluation::Denied,
+        }
+    }
+    Evaluation::Continue(entry, wait_for)
+}
+
+/// The effect of a single route-map item on a route that reaches it (see [`clause_effect`]).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "P: for<'a> serde::Deserialize<'a>"))]
+pub struct ClauseEffect<P: Prefix> {
+    /// The sample route before applying any route-map item.
+    pub route: BgpRibEntry<P>,
+    /// The route when it reaches the item, i.e., after it was modified by earlier items.
+    pub input: BgpRibEntry<P>,
+    /// Whether the item matches the route.
+    pub matched: bool,
+    /// The route after applying the item, or `None` if the item denies the route.
+    pub output: Option<BgpRibEntry<P>>,
+    /// How the evaluation continues after the item.
+    pub flow: RouteMapFlow,
+}
+
+/// Compute the effect of the item with order `seq` in the sequence of route-maps `map` on all
+/// `sample_routes`. The result contains one entry for each sample route that reaches the item,
+/// i.e., that is neither denied by an earlier item, nor stopped by an earlier item that exits or
+/// continues past the item. Sample routes that never reach the item are omitted. An item that no
+/// sample route reaches is shadowed by earlier items. If `map` has no item with order `seq`, then
+/// the result is empty. The sequence need not be sorted. Since no AS-path access-lists are known,
+/// [`RouteMapMatch::AsPathList`] never matches (see [`clause_effect_with`]).
+pub fn clause_effect<P: Prefix>(
+    map: &[RouteMap<P>],
+    seq: i16,
+    sample_routes: impl IntoIterator<Item = BgpRibEntry<P>>,
+) -> Vec<ClauseEffect<P>> {
+    clause_effect_with(map, seq, sample_routes, &HashMap::new())
+}
+
+/// Compute the effect of the item with order `seq` in the sequence of route-maps `map` on all
+/// `sample_routes`, like [`clause_effect`]. Named AS-path access-lists are looked up in
+/// `as_path_lists`.
+pub fn clause_effect_with<P: Prefix>(
+    map: &[RouteMap<P>],
+    seq: i16,
+    sample_routes: impl IntoIterator<Item = BgpRibEntry<P>>,
+    as_path_lists: &AsPathLists,
+) -> Vec<ClauseEffect<P>> {
+    let Some(clause) = map.iter().find(|m| m.order == seq) else {
+        return Vec::new();
+    };
+    let earlier = map
+        .iter()
+        .filter(|m| m.order < seq)
+        .sorted_by_key(|m| m.order)
+        .collect_vec();
+    sample_routes
+        .into_iter()
+        .filter_map(|route| {
+            let input = match eval_route_maps(
+                earlier.iter().copied(),
+                route.clone(),
+                as_path_lists,
+                None,
+            ) {
+                Evaluation::Continue(e, wait_for) if wait_for.is_none_or(|x| x == seq) => e,
+                _ => return None,
+            };
+            let matched = clause.matches_with(&input, as_path_lists);
+            let (flow, output) = clause.apply_with(input.clone(), as_path_lists);
+            Some(ClauseEffect {
+                route,
+                input,
+                matched,
+                output,
+                flow,
+            })
+        })
+        .collect()
+}
+
+/// A route on which two sequences of route-maps behave differently (see [`diff`]).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "P: for<'a> serde::Deserialize<'a>"))]
+pub struct RouteMapDelta<P: Prefix> {
+    /// The route before applying any route-map.
+    pub route: BgpRibEntry<P>,
+    /// The result of the first sequence of route-maps, or `None` if the route is denied.
+    pub a: Option<BgpRibEntry<P>>,
+    /// The result of the second sequence of route-maps, or `None` if the route is denied.
+    pub b: Option<BgpRibEntry<P>>,
+}
+
+impl<P: Prefix> RouteMapDelta<P> {
+    /// Returns `true` if the route is permitted by one sequence of route-maps, but denied by the
+    /// other. Otherwise, both permit the route, but apply different set actions.
+    pub fn is_permit_change(&self) -> bool {
+        self.a.is_some() != self.b.is_some()
+    }
+}
+
+/// Compare the behavior of two sequences of route-maps. Both sequences are applied on a
+/// representative set of routes (see [`representative_routes`]) derived from the match conditions
+/// of both sequences. The function returns all routes for which the sequences differ in whether
+/// they permit or deny the route, or in the attributes of the resulting route. An empty result
+/// means that no behavioral difference was found. The sequences need not be sorted.
+pub fn diff<P: Prefix>(a: &[RouteMap<P>], b: &[RouteMap<P>]) -> Vec<RouteMapDelta<P>> {
+    let base = default_route(a.iter().chain(b));
+    let routes = representative_routes(a.iter().chain(b), &base);
+    diff_with(a, b, routes, &HashMap::new())
+}
+
+/// Compare the behavior of two sequences of route-maps on the given `routes`, like [`diff`].
+/// Named AS-path access-lists are looked up in `as_path_lists`.
+pub fn diff_with<P: Prefix>(
+    a: &[RouteMap<P>],
+    b: &[RouteMap<P>],
+    routes: impl IntoIterator<Item = BgpRibEntry<P>>,
+    as_path_lists: &AsPathLists,
+) -> Vec<RouteMapDelta<P>> {
+    let a = a.iter().sorted_by_key(|m| m.order).collect_vec();
+    let b = b.iter().sorted_by_key(|m| m.order).collect_vec();
+    routes
+        .into_iter()
+        .filter_map(|route| {
+            let res_a = a.iter().copied().apply_with(route.clone(), as_path_lists);
+            let res_b = b.iter().copied().apply_with(route.clone(), as_path_lists);
+            (res_a != res_b).then_some(RouteMapDelta {
+                route,
+                a: res_a,
+                b: res_b,
+            })
+        })
+        .collect()
+}
+
+/// Generate a representative set of routes for the given route-maps, starting from `base`. The set
+/// contains `base`, a route for each individual match condition that satisfies that condition,
+/// and a route for each pair of route-map items that satisfies the conditions of both items (if
+/// they are not contradicting). Conditions on named AS-path access-lists are ignored.
+pub fn representative_routes<'a, P: Prefix + 'a>(
+    maps: impl IntoIterator<Item = &'a RouteMap<P>>,
+    base: &BgpRibEntry<P>,
+) -> Vec<BgpRibEntry<P>> {
+    let maps = maps.into_iter().collect_vec();
+    let mut routes = vec![base.clone()];
+    let mut push = |route: BgpRibEntry<P>| {
+        if !routes.contains(&route) {
+            routes.push(route);
+        }
+    };
+    for cond in maps.iter().flat_map(|m| &m.conds) {
+        let mut route = base.clone();
+        satisfy(cond, &mut route);
+        push(route);
+    }
+    for (i, m1) in maps.iter().enumerate() {
+        for m2 in &maps[i..] {
+            let mut route = base.clone();
+            m1.conds.iter().for_each(|c| satisfy(c, &mut route));
+            m2.conds.iter().for_each(|c| satisfy(c, &mut route));
+            push(route);
+        }
+    }
+    routes
+}
+
+/// Modify `route` such that it satisfies `cond`.
+fn satisfy<P: Prefix>(cond: &RouteMapMatch<P>, route: &mut BgpRibEntry<P>) {
+    match cond {
+        RouteMapMatch::Prefix(prefixes) => {
+            if let Some(p) = prefixes.iter().next() {
+                route.route.prefix = *p;
+            }
+        }
+        RouteMapMatch::AsPath(RouteMapMatchAsPath::Contains(as_id)) => {
+            if !route.route.as_path.contains(as_id) {
+                route.route.as_path.push(*as_id);
+            }
+        }
+        RouteMapMatch::AsPath(RouteMapMatchAsPath::Length(clause)) => {
+            let len = match clause {
+                RouteMapMatchClause::Range(x, _)
+                | RouteMapMatchClause::RangeExclusive(x, _)
+                | RouteMapMatchClause::Equal(x) => *x,
+            };
+            let fill = route.route.as_path.last().copied().unwrap_or(AsId(0));
+            route.route.as_path.resize(len, fill);
+        }
+        RouteMapMatch::NextHop(nh) => route.route.next_hop = *nh,
+        RouteMapMatch::Community(c) => {
+            route.route.communities.insert(*c);
         }
-        Some(entry)
+        RouteMapMatch::DenyCommunity(c) => {
+            route.route.communities.remove(c);
+        }
+        RouteMapMatch::Tag(tag) => route.tag = *tag,
+        RouteMapMatch::AsPathList(_) => {}
+    }
+}
+
+/// Generate a route with a prefix that is not matched by any of the route-maps.
+fn default_route<'a, P: Prefix + 'a>(
+    maps: impl IntoIterator<Item = &'a RouteMap<P>> + Clone,
+) -> BgpRibEntry<P> {
+    let matched = |p: &P| {
+        maps.clone()
+            .into_iter()
+            .flat_map(|m| &m.conds)
+            .any(|c| match c {
+                RouteMapMatch::Prefix(set) => set.contains(p),
+                _ => false,
+            })
+    };
+    let prefix = (0..256)
+        .map(P::from)
+        .find(|p| !matched(p))
+        .unwrap_or_else(|| P::from(0));
+    BgpRibEntry {
+        route: BgpRoute::new(0.into(), prefix, [AsId(0)], None, []),
+        from_type: BgpSessionType::EBgp,
+        from_id: 0.into(),
+        to_id: None,
+        igp_cost: None,
+        weight: 100,
+        bgp_id: None,
+        tag: 0,
+        dampable: false,
+        rpki: None,
     }
 }
 
@@ -184,6 +521,13 @@ where
 ///
 /// Use the functions [`Self::exit`], [`Self::continue_next`], or [`Self::continue_at`] to describe
 /// the contorl flow of the route map.
+///
+/// Incoming route-map items with an order below [`COMMUNITY_LOCAL_PREF_ORDER_END`] are reserved for
+/// the items compiled by
+/// [`Network::set_community_localpref_map`](crate::network::Network::set_community_localpref_map).
+/// Similarly, outgoing items with an order below [`COMMUNITY_PREPEND_ORDER_END`] are reserved for
+/// [`Network::set_community_prepend_map`](crate::network::Network::set_community_prepend_map).
+/// Compiling such a map fails while other items use that range.
 #[derive(Debug)]
 pub struct RouteMapBuilder<P: Prefix> {
     order: Option<i16>,
@@ -287,6 +631,13 @@ impl<P: Prefix> RouteMapBuilder<P> {
         self
     }
 
+    /// Add a match condition to the Route-Map, requiring that the as path is permitted by the
+    /// named AS-path access-list (see [`RouteMapMatch::AsPathList`]).
+    pub fn match_as_path_list(&mut self, name: impl Into<String>) -> &mut Self {
+        self.conds.push(RouteMapMatch::AsPathList(name.into()));
+        self
+    }
+
     /// Add a match condition to the Route-Map, matching on the next hop
     pub fn match_next_hop(&mut self, next_hop: RouterId) -> &mut Self {
         self.conds.push(RouteMapMatch::NextHop(next_hop));
@@ -294,14 +645,21 @@ impl<P: Prefix> RouteMapBuilder<P> {
     }
 
     /// Add a match condition to the Route-Map, matching on the community with exact value
-    pub fn match_community(&mut self, community: u32) -> &mut Self {
-        self.conds.push(RouteMapMatch::Community(community));
+    pub fn match_community(&mut self, community: impl Into<Community>) -> &mut Self {
+        self.conds.push(RouteMapMatch::Community(community.into()));
         self
     }
 
     /// Add a match condition to the Route-Map, matching on the absence of a community.
-    pub fn match_deny_community(&mut self, community: u32) -> &mut Self {
-        self.conds.push(RouteMapMatch::DenyCommunity(community));
+    pub fn match_deny_community(&mut self, community: impl Into<Community>) -> &mut Self {
+        self.conds
+            .push(RouteMapMatch::DenyCommunity(community.into()));
+        self
+    }
+
+    /// Add a match condition to the Route-Map, matching on the local tag of the route.
+    pub fn match_tag(&mut self, tag: u32) -> &mut Self {
+        self.conds.push(RouteMapMatch::Tag(tag));
         self
     }
 
@@ -342,6 +700,17 @@ impl<P: Prefix> RouteMapBuilder<P> {
         self
     }
 
+    /// Add a set expression, clamping the Local-Pref to the inclusive range `[min, max]`. Clamping
+    /// is applied after all other set expressions of the route-map.
+    pub fn clamp_local_pref(&mut self, min: u32, max: u32) -> &mut Self {
+        assert!(
+            min <= max,
+            "The minimum must not be larger than the maximum!"
+        );
+        self.set.push(RouteMapSet::ClampLocalPref(min, max));
+        self
+    }
+
     /// Add a set expression, overwriting the MED
     pub fn set_med(&mut self, med: u32) -> &mut Self {
         self.set.push(RouteMapSet::Med(Some(med)));
@@ -354,6 +723,31 @@ impl<P: Prefix> RouteMapBuilder<P> {
         self
     }
 
+    /// Add a set expression, clamping the MED to the inclusive range `[min, max]`. Clamping is
+    /// applied after all other set expressions of the route-map.
+    pub fn clamp_med(&mut self, min: u32, max: u32) -> &mut Self {
+        assert!(
+            min <= max,
+            "The minimum must not be larger than the maximum!"
+        );
+        self.set.push(RouteMapSet::ClampMed(min, max));
+        self
+    }
+
+    /// Add a set expression, prepending `asn` to the AS path `n` times.
+    pub fn set_as_path_prepend(&mut self, asn: impl Into<AsId>, n: usize) -> &mut Self {
+        self.set.push(RouteMapSet::AsPathPrepend(asn.into(), n));
+        self
+    }
+
+    /// Add a set expression, setting the local-pref to `base - factor * cost`, where `cost` is the
+    /// IGP cost towards the next-hop at import time (see [`RouteMapSet::LocalPrefFromIgpCost`]).
+    pub fn set_localpref_from_igp_cost(&mut self, base: u32, factor: u32) -> &mut Self {
+        self.set
+            .push(RouteMapSet::LocalPrefFromIgpCost(base, factor));
+        self
+    }
+
     /// Add a set expression, overwriting the Igp Cost to reach the next-hop
     pub fn set_igp_cost(&mut self, cost: LinkWeight) -> &mut Self {
         self.set.push(RouteMapSet::IgpCost(cost));
@@ -361,14 +755,28 @@ impl<P: Prefix> RouteMapBuilder<P> {
     }
 
     /// Add a set expression, overwriting the Community
-    pub fn set_community(&mut self, community: u32) -> &mut Self {
-        self.set.push(RouteMapSet::SetCommunity(community));
+    pub fn set_community(&mut self, community: impl Into<Community>) -> &mut Self {
+        self.set.push(RouteMapSet::SetCommunity(community.into()));
         self
     }
 
     /// Add a set expression, resetting the Community
-    pub fn remove_community(&mut self, community: u32) -> &mut Self {
-        self.set.push(RouteMapSet::DelCommunity(community));
+    pub fn remove_community(&mut self, community: impl Into<Community>) -> &mut Self {
+        self.set.push(RouteMapSet::DelCommunity(community.into()));
+        self
+    }
+
+    /// Add a set expression, setting the local tag of the route. The tag is never advertised to
+    /// any neighbor.
+    pub fn set_tag(&mut self, tag: u32) -> &mut Self {
+        self.set.push(RouteMapSet::Tag(tag));
+        self
+    }
+
+    /// Add a set expression, marking the route as subject to route flap damping (see
+    /// [`RouteMapSet::Dampen`]).
+    pub fn dampen(&mut self) -> &mut Self {
+        self.set.push(RouteMapSet::Dampen);
         self
     }
 
@@ -446,14 +854,17 @@ impl<P: Prefix> RouteMapBuilder<P> {
         let set = if state.is_deny() {
             vec![]
         } else {
-            self.set.clone()
+            // apply clamping actions after all others (the sort is stable)
+            let mut set = self.set.clone();
+            set.sort_by_key(RouteMapSet::is_clamp);
+            set
         };
         RouteMap::new(order, state, conds, set, self.flow)
     }
 }
 
 /// State of a route map, which can either be allow or deny
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum RouteMapState {
     /// Set the state to allow
     Allow,
@@ -483,26 +894,58 @@ pub enum RouteMapMatch<P: Prefix> {
     /// Matches on the Next Hop (exact value)
     NextHop(RouterId),
     /// Matches on the community (either not set, or set and matches a value or a range)
-    Community(u32),
+    Community(Community),
     /// Match on the absence of a given community.
-    DenyCommunity(u32),
+    DenyCommunity(Community),
+    /// Matches if the As Path is permitted by the named AS-path access-list. The route does not
+    /// match if the list is not defined.
+    AsPathList(String),
+    /// Matches on the local tag of the route (exact value).
+    Tag(u32),
+}
+
+impl<P: Prefix> Hash for RouteMapMatch<P> {
+    /// Hash the match statement. Prefixes are hashed in sorted order, which is consistent with
+    /// `PartialEq`.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Self::Prefix(prefixes) => prefixes.iter().sorted().for_each(|p| p.hash(state)),
+            Self::AsPath(clause) => clause.hash(state),
+            Self::NextHop(nh) => nh.hash(state),
+            Self::Community(com) | Self::DenyCommunity(com) => com.hash(state),
+            Self::Tag(tag) => tag.hash(state),
+            Self::AsPathList(name) => name.hash(state),
+        }
+    }
 }
 
 impl<P: Prefix> RouteMapMatch<P> {
-    /// Returns true if the `BgpRibEntry<P>` matches the expression
+    /// Returns true if the `BgpRibEntry<P>` matches the expression. Since no AS-path access-lists
+    /// are known, [`RouteMapMatch::AsPathList`] never matches.
     pub fn matches(&self, entry: &BgpRibEntry<P>) -> bool {
+        self.matches_with(entry, &HashMap::new())
+    }
+
+    /// Returns true if the `BgpRibEntry<P>` matches the expression, looking up named AS-path
+    /// access-lists in `as_path_lists`.
+    pub fn matches_with(&self, entry: &BgpRibEntry<P>, as_path_lists: &AsPathLists) -> bool {
         match self {
             Self::Prefix(prefixes) => prefixes.contains(&entry.route.prefix),
             Self::AsPath(clause) => clause.matches(&entry.route.as_path),
+            Self::AsPathList(name) => as_path_lists
+                .get(name)
+                .is_some_and(|list| list.permits(&entry.route.as_path)),
             Self::NextHop(nh) => entry.route.next_hop == *nh,
-            Self::Community(com) => entry.route.community.contains(com),
-            Self::DenyCommunity(com) => !entry.route.community.contains(com),
+            Self::Community(com) => entry.route.communities.contains(com),
+            Self::DenyCommunity(com) => !entry.route.communities.contains(com),
+            Self::Tag(tag) => entry.tag == *tag,
         }
     }
 }
 
 /// Generic RouteMapMatchClause to match on all, a range or on a specific element
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum RouteMapMatchClause<T> {
     /// Matches a range of values (inclusive)
     Range(T, T),
@@ -542,7 +985,7 @@ where
 }
 
 /// Clause to match on the as path
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum RouteMapMatchAsPath {
     /// Contains a specific AsId
     Contains(AsId),
@@ -571,6 +1014,63 @@ impl fmt::Display for RouteMapMatchAsPath {
     }
 }
 
+/// Route-map items with an order below this value are reserved for the items compiled from a
+/// community to LOCAL-PREF map (see
+/// [`Network::set_community_localpref_map`](crate::network::Network::set_community_localpref_map)).
+pub const COMMUNITY_LOCAL_PREF_ORDER_END: i16 = -16384;
+
+/// Check if `map` is an item compiled from a community to LOCAL-PREF map.
+pub(crate) fn is_community_local_pref_item<P: Prefix>(map: &RouteMap<P>) -> bool {
+    map.order < COMMUNITY_LOCAL_PREF_ORDER_END
+        && map.state == RouteMapState::Allow
+        && matches!(map.conds.as_slice(), [RouteMapMatch::Community(_)])
+        && matches!(map.set.as_slice(), [RouteMapSet::LocalPref(Some(_))])
+        && map.flow == RouteMapFlow::Continue
+}
+
+/// Outgoing route-map items with an order below this value are reserved for the items compiled
+/// from a community to AS-path prepending map (see
+/// [`Network::set_community_prepend_map`](crate::network::Network::set_community_prepend_map)).
+pub const COMMUNITY_PREPEND_ORDER_END: i16 = -16384;
+
+/// Check if `map` is an item compiled from a community to AS-path prepending map.
+pub(crate) fn is_community_prepend_item<P: Prefix>(map: &RouteMap<P>) -> bool {
+    map.order < COMMUNITY_PREPEND_ORDER_END
+        && map.state == RouteMapState::Allow
+        && matches!(map.conds.as_slice(), [RouteMapMatch::Community(_)])
+        && matches!(map.set.as_slice(), [RouteMapSet::AsPathPrepend(..)])
+        && map.flow == RouteMapFlow::Continue
+}
+
+/// Named AS-path access-lists, referenced by [`RouteMapMatch::AsPathList`].
+pub type AsPathLists = HashMap<String, AsPathList>;
+
+/// AS-path access-list (`ip as-path access-list` in Cisco terms). The entries are checked in
+/// order, and the first entry matching the AS path decides whether it is permitted. If no entry
+/// matches, the AS path is denied.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AsPathList {
+    /// Entries of the list, as pairs of the action and the clause matching the AS path.
+    pub entries: Vec<(RouteMapState, RouteMapMatchAsPath)>,
+}
+
+impl AsPathList {
+    /// Create a new AS-path access-list from its entries.
+    pub fn new(entries: impl IntoIterator<Item = (RouteMapState, RouteMapMatchAsPath)>) -> Self {
+        Self {
+            entries: entries.into_iter().collect(),
+        }
+    }
+
+    /// Returns true if the list permits the given AS path.
+    pub fn permits(&self, path: &[AsId]) -> bool {
+        self.entries
+            .iter()
+            .find(|(_, clause)| clause.matches(path))
+            .is_some_and(|(state, _)| state.is_allow())
+    }
+}
+
 /// Set action, if a route map matches
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum RouteMapSet {
@@ -586,9 +1086,50 @@ pub enum RouteMapSet {
     /// overwrite the distance attribute (IGP weight). This does not affect peers.
     IgpCost(LinkWeight),
     /// Set the community value
-    SetCommunity(u32),
+    SetCommunity(Community),
     /// Remove the community value
-    DelCommunity(u32),
+    DelCommunity(Community),
+    /// Clamp the local preference to the inclusive range `[min, max]` (a missing local preference
+    /// is treated as 100). Clamping actions are applied after all other set actions of the
+    /// route-map.
+    ClampLocalPref(u32, u32),
+    /// Clamp the MED to the inclusive range `[min, max]` (a missing MED is treated as 0). Clamping
+    /// actions are applied after all other set actions of the route-map.
+    ClampMed(u32, u32),
+    /// Prepend the given AS number the given number of times to the AS path. On outgoing eBGP
+    /// sessions, the prepended AS numbers appear after the own AS of the router.
+    AsPathPrepend(AsId, usize),
+    /// Set the local tag of the route. The tag is never advertised to any neighbor, but it can be
+    /// matched by subsequent route-maps of the same router, e.g., in an outgoing route-map of a
+    /// different session.
+    Tag(u32),
+    /// Set the local preference to `base - factor * cost`, where `cost` is the IGP cost towards
+    /// the next-hop (rounded to the nearest integer, and saturating at 0). Routes with closer
+    /// next-hops thus receive a higher local preference, similar to BGP cost communities. The IGP
+    /// cost is only known in incoming route-maps (or if it was set before). Otherwise, the local
+    /// preference is left unchanged.
+    LocalPrefFromIgpCost(u32, u32),
+    /// Mark the route as subject to route flap damping (see
+    /// [`Network::set_route_damping`](crate::network::Network::set_route_damping)). Only incoming
+    /// route-maps can mark a route, and the mark is never advertised to any neighbor.
+    Dampen,
+}
+
+impl Hash for RouteMapSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Self::NextHop(nh) => nh.hash(state),
+            Self::Weight(x) | Self::LocalPref(x) | Self::Med(x) => x.hash(state),
+            Self::IgpCost(w) => w.to_bits().hash(state),
+            Self::SetCommunity(c) | Self::DelCommunity(c) => c.hash(state),
+            Self::Tag(tag) => tag.hash(state),
+            Self::ClampLocalPref(min, max) | Self::ClampMed(min, max) => (min, max).hash(state),
+            Self::LocalPrefFromIgpCost(base, factor) => (base, factor).hash(state),
+            Self::Dampen => {}
+            Self::AsPathPrepend(asn, n) => (asn, n).hash(state),
+        }
+    }
 }
 
 impl RouteMapSet {
@@ -605,13 +1146,39 @@ impl RouteMapSet {
             Self::Med(med) => entry.route.med = Some(med.unwrap_or(0)),
             Self::IgpCost(w) => entry.igp_cost = Some(NotNan::new(*w).unwrap()),
             Self::SetCommunity(c) => {
-                entry.route.community.insert(*c);
+                entry.route.communities.insert(*c);
             }
             Self::DelCommunity(c) => {
-                entry.route.community.remove(c);
+                entry.route.communities.remove(c);
+            }
+            Self::ClampLocalPref(min, max) => {
+                entry.route.local_pref =
+                    Some(entry.route.local_pref.unwrap_or(100).clamp(*min, *max))
+            }
+            Self::ClampMed(min, max) => {
+                entry.route.med = Some(entry.route.med.unwrap_or(0).clamp(*min, *max))
+            }
+            Self::AsPathPrepend(asn, n) => {
+                entry
+                    .route
+                    .as_path
+                    .splice(0..0, std::iter::repeat_n(*asn, *n));
             }
+            Self::Tag(tag) => entry.tag = *tag,
+            Self::LocalPrefFromIgpCost(base, factor) => {
+                if let Some(cost) = entry.igp_cost {
+                    let penalty = (*factor as LinkWeight * cost.into_inner()).round();
+                    entry.route.local_pref = Some((*base as LinkWeight - penalty).max(0.0) as u32);
+                }
+            }
+            Self::Dampen => entry.dampable = true,
         }
     }
+
+    /// Returns `true` if the action clamps an attribute to a range.
+    pub fn is_clamp(&self) -> bool {
+        matches!(self, Self::ClampLocalPref(_, _) | Self::ClampMed(_, _))
+    }
 }
 
 /// Direction of the Route Map
@@ -654,7 +1221,9 @@ pub enum RouteMapFlow {
     /// If a route matches this route-map, apply the set actions and continue to the next entry in the list.
     Continue,
     /// If a route matches this route-map, apply the set actions and continue to the route-map with
-    /// the given index. If the index does not exist, then stop applying route-maps.
+    /// the given index. If the index does not exist, then stop applying route-maps. Jumps can only
+    /// go forward (see [`RouteMapBuilder::continue_at`]), which guarantees that applying a
+    /// sequence of route-maps always terminates. A backward jump stops applying route-maps.
     ContinueAt(i16),
 }
 
@@ -28,25 +28,32 @@
 //! BGP process of an internal router.
 
 use crate::{
-    bgp::{BgpEvent, BgpRibEntry, BgpRoute, BgpSessionType},
+    bgp::{
+        default_bgp_id, AttributePropagation, BgpEvent, BgpRibEntry, BgpRoute, BgpSessionDefaults,
+        BgpSessionOptions, BgpSessionType, DistributeList, RoaTable, RpkiPolicy, RpkiValidation,
+        Vrf, DEFAULT_RECURSION_LIMIT, GRACEFUL_SHUTDOWN, NO_ADVERTISE, NO_EXPORT,
+    },
     config::RouteMapEdit,
     event::Event,
     formatter::NetworkFormatter,
     network::Network,
     ospf::{LinkWeight, OspfImpl, OspfProcess},
     route_map::{
-        RouteMap,
+        AsPathList, AsPathLists, RouteMap,
         RouteMapDirection::{self, Incoming, Outgoing},
         RouteMapList,
     },
-    types::{AsId, DeviceError, Prefix, PrefixMap, PrefixSet, RouterId},
+    types::{AsId, Community, DeviceError, Prefix, PrefixMap, PrefixSet, RouterId, StableHasher},
 };
 use itertools::Itertools;
 use ordered_float::NotNan;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{HashMap, HashSet},
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::Write,
+    hash::{Hash, Hasher},
+    net::Ipv4Addr,
 };
 
 /// BGP Routing Process responsible for maintiaining all BGP tables, and performing route selection
@@ -76,8 +83,99 @@ pub struct BgpProcess<P: Prefix> {
     pub(crate) route_maps_in: HashMap<RouterId, Vec<RouteMap<P>>>,
     /// BGP Route-Maps for Output
     pub(crate) route_maps_out: HashMap<RouterId, Vec<RouteMap<P>>>,
+    /// Prefix filters for Input, applied before the route-maps.
+    pub(crate) distribute_lists_in: HashMap<RouterId, DistributeList<P>>,
+    /// Prefix filters for Output, applied before the route-maps.
+    pub(crate) distribute_lists_out: HashMap<RouterId, DistributeList<P>>,
     /// Set of known bgp prefixes
     pub(crate) known_prefixes: P::Set,
+    /// RPKI origin validation, containing the ROA table and the policy for invalid routes.
+    #[serde(default)]
+    pub(crate) rpki: Option<(RoaTable<P>, RpkiPolicy)>,
+    /// AS number presented to specific eBGP neighbors instead of the router's own AS (`local-as`).
+    #[serde(default)]
+    pub(crate) local_as: HashMap<RouterId, AsId>,
+    /// Number of times the local AS may appear in routes received from specific eBGP neighbors
+    /// (`allowas-in`).
+    #[serde(default)]
+    pub(crate) allowas_in: HashMap<RouterId, usize>,
+    /// TTL of multihop eBGP sessions with specific neighbors (`ebgp-multihop`).
+    #[serde(default)]
+    pub(crate) ebgp_multihop: HashMap<RouterId, u8>,
+    /// Root of the IGP view from which the best route is selected for specific clients (optimal
+    /// route reflection).
+    #[serde(default)]
+    pub(crate) orr: HashMap<RouterId, RouterId>,
+    /// IGP cost from each root used for optimal route reflection towards all other routers.
+    #[serde(default)]
+    pub(crate) orr_igp_cost: HashMap<RouterId, HashMap<RouterId, LinkWeight>>,
+    /// Whether the router is configured as a route reflector, i.e., whether it may have clients.
+    #[serde(default)]
+    pub(crate) route_reflector: bool,
+    /// Explicitly configured cluster ID of the route reflector.
+    #[serde(default)]
+    pub(crate) cluster_id: Option<u32>,
+    /// Explicitly configured BGP router-ids of all routers in the network.
+    #[serde(default)]
+    pub(crate) bgp_ids: HashMap<RouterId, Ipv4Addr>,
+    /// Default attributes of routes received from specific neighbors.
+    #[serde(default)]
+    pub(crate) session_defaults: HashMap<RouterId, BgpSessionDefaults>,
+    /// Options of sessions with specific neighbors that are only used by the exporters.
+    #[serde(default)]
+    pub(crate) session_options: HashMap<RouterId, BgpSessionOptions>,
+    /// Neighbors towards which updates are coalesced (MRAI).
+    #[serde(default)]
+    pub(crate) mrai: HashSet<RouterId>,
+    /// Whether the AS path length is ignored in the decision process (`bgp bestpath as-path
+    /// ignore`).
+    #[serde(default)]
+    pub(crate) as_path_ignore: bool,
+    /// Whether the router prefers the oldest of two equally good eBGP routes, instead of comparing
+    /// their BGP router-ids.
+    #[serde(default)]
+    pub(crate) prefer_oldest: bool,
+    /// Whether the router forwards traffic along all routes that are equally good as the selected
+    /// route (BGP multipath).
+    #[serde(default)]
+    pub(crate) multipath: bool,
+    /// Whether BGP multipath only combines routes received from the same neighboring AS.
+    #[serde(default)]
+    pub(crate) multipath_same_as: bool,
+    /// Neighbors towards which next-hop-self is disabled. By default, next-hop-self is enabled.
+    #[serde(default)]
+    pub(crate) no_next_hop_self: HashSet<RouterId>,
+    /// Neighbors whose sessions are in graceful shutdown. All routes exchanged with them are tagged
+    /// with the `GRACEFUL_SHUTDOWN` community.
+    #[serde(default)]
+    pub(crate) graceful_shutdown: HashSet<RouterId>,
+    /// Named AS-path access-lists that can be referenced by route-maps.
+    #[serde(default)]
+    pub(crate) as_path_lists: AsPathLists,
+    /// Attribute propagation towards specific neighbors that overwrites the default.
+    #[serde(default)]
+    pub(crate) attribute_propagation: HashMap<RouterId, AttributePropagation>,
+    /// Next-hops that are resolved recursively via the BGP route for the given prefix, instead of
+    /// the IGP.
+    #[serde(default)]
+    pub(crate) next_hop_resolution: HashMap<RouterId, P>,
+    /// Maximum number of recursive lookups performed to resolve a next-hop.
+    #[serde(default = "default_recursion_limit")]
+    pub(crate) recursion_limit: usize,
+    /// Number of flaps after which a dampable route is suppressed (route flap damping).
+    #[serde(default)]
+    pub(crate) damping: Option<u32>,
+    /// Number of flaps of dampable routes, for each prefix and neighbor.
+    #[serde(default)]
+    pub(crate) flaps: P::Map<HashMap<RouterId, u32>>,
+    /// VRFs hosted on the router, by name.
+    #[serde(default)]
+    pub(crate) vrfs: BTreeMap<String, Vrf<P>>,
+}
+
+/// Recursion limit of a BGP process that is deserialized without one.
+fn default_recursion_limit() -> usize {
+    DEFAULT_RECURSION_LIMIT
 }
 
 impl<P: Prefix> BgpProcess<P> {
@@ -93,7 +191,34 @@ impl<P: Prefix> BgpProcess<P> {
             rib_out: Default::default(),
             route_maps_in: Default::default(),
             route_maps_out: Default::default(),
+            distribute_lists_in: Default::default(),
+            distribute_lists_out: Default::default(),
             known_prefixes: Default::default(),
+            rpki: None,
+            local_as: Default::default(),
+            allowas_in: Default::default(),
+            ebgp_multihop: Default::default(),
+            orr: Default::default(),
+            orr_igp_cost: Default::default(),
+            route_reflector: false,
+            cluster_id: None,
+            bgp_ids: Default::default(),
+            session_defaults: Default::default(),
+            session_options: Default::default(),
+            mrai: Default::default(),
+            as_path_ignore: false,
+            prefer_oldest: false,
+            multipath: false,
+            multipath_same_as: false,
+            no_next_hop_self: Default::default(),
+            graceful_shutdown: Default::default(),
+            as_path_lists: Default::default(),
+            attribute_propagation: Default::default(),
+            next_hop_resolution: Default::default(),
+            recursion_limit: DEFAULT_RECURSION_LIMIT,
+            damping: None,
+            flaps: Default::default(),
+            vrfs: Default::default(),
         }
     }
 
@@ -101,9 +226,50 @@ impl<P: Prefix> BgpProcess<P> {
      * Getter Functions
      */
 
-    /// Get the next hop (RouterId) for the given prefix using longest-prefix-matching
+    /// Get the next hop (RouterId) for the given prefix using longest-prefix-matching. If the
+    /// next-hop of the selected route is resolved recursively, then this function returns the
+    /// resolved next-hop.
     pub fn get(&self, prefix: P) -> Option<RouterId> {
-        self.get_route(prefix).map(|rib| rib.route.next_hop)
+        self.get_route(prefix)
+            .and_then(|rib| self.resolve_next_hop(rib.route.next_hop))
+    }
+
+    /// Resolve `next_hop` to a router that is reachable in the IGP. If `next_hop` is configured to
+    /// be resolved via a prefix (see [`Network::set_next_hop_resolution`]), then the router looks
+    /// up the selected BGP route for that prefix, and continues with its next-hop. This function
+    /// returns `None` if the resolution requires more than the configured recursion limit, or if
+    /// it would visit the same next-hop twice (resolution loop). Next-hops that are neither
+    /// reachable in the IGP nor configured for recursive resolution are returned unchanged.
+    pub fn resolve_next_hop(&self, next_hop: RouterId) -> Option<RouterId> {
+        let mut nh = next_hop;
+        // next-hops visited before `nh`. This is only allocated for recursive resolution.
+        let mut visited = Vec::new();
+        loop {
+            if self.igp_cost.get(&nh).is_some_and(|c| c.is_finite()) {
+                return Some(nh);
+            }
+            let Some(prefix) = self.next_hop_resolution.get(&nh) else {
+                // only the next-hop of the route itself may be unresolved.
+                return visited.is_empty().then_some(nh);
+            };
+            visited.push(nh);
+            if visited.len() > self.recursion_limit {
+                log::debug!("Recursion limit reached while resolving next-hop {next_hop:?}");
+                return None;
+            }
+            nh = self.get_route(*prefix)?.route.next_hop;
+            if visited.contains(&nh) {
+                log::debug!("Resolution loop detected while resolving next-hop {next_hop:?}");
+                return None;
+            }
+        }
+    }
+
+    /// Returns `true` if the selected route for `prefix` may be used to resolve a next-hop.
+    pub(crate) fn is_resolution_prefix(&self, prefix: &P) -> bool {
+        self.next_hop_resolution
+            .values()
+            .any(|p| p.contains(prefix))
     }
 
     /// Get the currently selected rib for the given prefix using longest-prefix matching.
@@ -129,6 +295,66 @@ impl<P: Prefix> BgpProcess<P> {
         Ok(entries)
     }
 
+    /// Return all known bgp routes for a given prefix (using exact matching), ranked by the BGP
+    /// decision process from the most preferred to the least preferred route. The first route is
+    /// the one that is selected (once the router has processed all events).
+    pub fn rank_routes(&self, prefix: P) -> Vec<BgpRibEntry<P>> {
+        let mut entries: Vec<BgpRibEntry<P>> = self
+            .rib_in
+            .get(&prefix)
+            .map(|table| {
+                table
+                    .values()
+                    .filter_map(|e| self.process_rib_in_route(e.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        entries.sort_by(|a, b| self.cmp_routes(prefix, b, a));
+        entries
+    }
+
+    /// Count how many routes matched each route-map item configured for `neighbor` in the given
+    /// `direction`. The counts are computed on the current state of the RIBs, i.e., on the routes
+    /// after the last convergence. Incoming route-maps are evaluated on all routes received from
+    /// `neighbor`, and outgoing route-maps on all selected routes that the router would advertise
+    /// to `neighbor` (if permitted by the route-maps). Each route counts at most once per item,
+    /// and only items that the route actually reaches are counted (e.g., items after a matching
+    /// `deny` item are not). The result contains the order of each item, together with its count,
+    /// sorted by the order. Items without any hit are reported with a count of zero.
+    pub fn route_map_hits(
+        &self,
+        neighbor: RouterId,
+        direction: RouteMapDirection,
+    ) -> Vec<(i16, usize)> {
+        let maps = self.get_route_maps(neighbor, direction);
+        let mut hits: BTreeMap<i16, usize> = maps.iter().map(|m| (m.order, 0)).collect();
+        let routes: Vec<BgpRibEntry<P>> = match direction {
+            Incoming => self
+                .rib_in
+                .iter()
+                .filter_map(|(_, table)| table.get(&neighbor))
+                .filter_map(|e| self.prepare_rib_in_route(e.clone()))
+                .collect(),
+            Outgoing => match self.sessions.get(&neighbor) {
+                Some(ty) => self
+                    .rib
+                    .iter()
+                    .map(|(_, e)| e)
+                    .filter(|e| should_export_route(e.from_id, e.from_type, neighbor, *ty))
+                    .filter_map(|e| self.prepare_rib_out_route(e.clone(), neighbor).ok()?)
+                    .collect(),
+                None => Vec::new(),
+            },
+        };
+        for route in routes {
+            let (_, matched) = maps.apply_traced(route, &self.as_path_lists);
+            for order in matched {
+                *hits.entry(order).or_default() += 1;
+            }
+        }
+        hits.into_iter().collect()
+    }
+
     /// Returns an interator over all BGP sessions
     pub fn get_sessions(&self) -> &HashMap<RouterId, BgpSessionType> {
         &self.sessions
@@ -170,6 +396,14 @@ impl<P: Prefix> BgpProcess<P> {
         &self.rib_out
     }
 
+    /// Get the number of entries stored in all BGP tables, i.e., the number of routes in
+    /// `RIB_IN`, `RIB`, and `RIB_OUT`.
+    pub fn rib_size(&self) -> usize {
+        self.rib_in.values().map(|x| x.len()).sum::<usize>()
+            + self.rib.iter().count()
+            + self.rib_out.values().map(|x| x.len()).sum::<usize>()
+    }
+
     /// Get the processed BGP RIB table for all prefixes. This function will apply all incoming
     /// route-maps to all entries in `RIB_IN`, and return the current table from which the router
     /// has selected a route. Along with the routes, this function will also return a boolean wether
@@ -201,6 +435,20 @@ impl<P: Prefix> BgpProcess<P> {
             .collect()
     }
 
+    /// Get the distribute-list (prefix filter) applied on routes exchanged with `neighbor` in the
+    /// given direction.
+    pub fn get_distribute_list(
+        &self,
+        neighbor: RouterId,
+        direction: RouteMapDirection,
+    ) -> Option<&DistributeList<P>> {
+        match direction {
+            Incoming => &self.distribute_lists_in,
+            Outgoing => &self.distribute_lists_out,
+        }
+        .get(&neighbor)
+    }
+
     /// Get an iterator over all route-maps
     pub fn get_route_maps(
         &self,
@@ -216,6 +464,295 @@ impl<P: Prefix> BgpProcess<P> {
         .unwrap_or_default()
     }
 
+    /// Get the ROA table and the policy used for RPKI origin validation, if configured.
+    pub fn get_rpki(&self) -> Option<&(RoaTable<P>, RpkiPolicy)> {
+        self.rpki.as_ref()
+    }
+
+    /// Get the backup route for a prefix, i.e., the best known route whose next-hop differs from
+    /// the next-hop of the selected route. A router only knows a backup route if it learns the
+    /// prefix from multiple neighbors, e.g., in an iBGP full mesh. The backup route is used for
+    /// BGP Prefix-Independent Convergence (see [`Network::local_protect`]).
+    pub fn get_backup_route(&self, prefix: P) -> Option<BgpRibEntry<P>> {
+        let best = self.rib.get(&prefix)?;
+        self.rib_in
+            .get(&prefix)?
+            .values()
+            .filter(|e| e.from_id != best.from_id)
+            .filter_map(|e| self.process_rib_in_route(e.clone()))
+            .filter(|e| e.route.next_hop != best.route.next_hop)
+            .max_by(|a, b| self.cmp_routes(prefix, a, b))
+    }
+
+    /// Get the AS number that the router presents to `neighbor`. This is either the `local-as`
+    /// configured for that session, or the router's own AS number.
+    pub fn get_local_as(&self, neighbor: RouterId) -> AsId {
+        self.local_as.get(&neighbor).copied().unwrap_or(self.as_id)
+    }
+
+    /// Get the number of times the local AS may appear in the AS path of routes received from
+    /// `neighbor` (`allowas-in`). By default, this is 0.
+    pub fn get_allowas_in(&self, neighbor: RouterId) -> usize {
+        self.allowas_in.get(&neighbor).copied().unwrap_or_default()
+    }
+
+    /// Get the TTL of the multihop eBGP session with `neighbor`, or `None` if the session is not
+    /// a multihop session (see [`Network::add_ebgp_multihop_session`]).
+    pub fn get_ebgp_multihop(&self, neighbor: RouterId) -> Option<u8> {
+        self.ebgp_multihop.get(&neighbor).copied()
+    }
+
+    /// Get the root of the IGP view from which the router selects the best route advertised to
+    /// `client`, or `None` if optimal route reflection is disabled for `client` (see
+    /// [`Network::set_orr`]).
+    pub fn get_orr(&self, client: RouterId) -> Option<RouterId> {
+        self.orr.get(&client).copied()
+    }
+
+    /// Get the default attributes of routes received from `neighbor`.
+    pub fn get_session_defaults(&self, neighbor: RouterId) -> BgpSessionDefaults {
+        self.session_defaults
+            .get(&neighbor)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Get the options of the session with `neighbor` that are only used by the exporters.
+    pub fn get_session_options(&self, neighbor: RouterId) -> Option<&BgpSessionOptions> {
+        self.session_options.get(&neighbor)
+    }
+
+    /// Returns `true` if updates towards `neighbor` are coalesced using the minimum route
+    /// advertisement interval (MRAI).
+    pub fn has_mrai(&self, neighbor: RouterId) -> bool {
+        self.mrai.contains(&neighbor)
+    }
+
+    /// Returns `true` if the router ignores the AS path length when selecting the best route
+    /// (`bgp bestpath as-path ignore`).
+    pub fn get_bestpath_as_path_ignore(&self) -> bool {
+        self.as_path_ignore
+    }
+
+    /// Returns `true` if the router prefers the oldest of two equally good eBGP routes, instead of
+    /// comparing their BGP router-ids (see [`Network::set_prefer_oldest`]).
+    pub fn get_prefer_oldest(&self) -> bool {
+        self.prefer_oldest
+    }
+
+    /// Returns `true` if BGP multipath is enabled (see [`Network::set_bgp_multipath`]).
+    pub fn get_bgp_multipath(&self) -> bool {
+        self.multipath
+    }
+
+    /// Returns `true` if BGP multipath only combines routes received from the same neighboring AS
+    /// (see [`Network::set_bgp_multipath_same_as`]).
+    pub fn get_bgp_multipath_same_as(&self) -> bool {
+        self.multipath_same_as
+    }
+
+    /// Get the resolved next-hops of all routes for `prefix` that are equally good as the selected
+    /// route, using longest-prefix matching. Routes are equally good if they only differ in the
+    /// final tiebreakers of the decision process, i.e., if they tie in the weight, LOCAL-PREF, AS
+    /// path length, MED, session type, and IGP cost. If multipath is disabled, then this function
+    /// only returns the next-hop of the selected route (see [`BgpProcess::get`]). The result is
+    /// sorted and contains no duplicates.
+    pub fn get_multipath(&self, prefix: P) -> Vec<RouterId> {
+        let Some((p, best)) = self.rib.get_lpm(&prefix) else {
+            return Vec::new();
+        };
+        if !self.multipath {
+            return self
+                .resolve_next_hop(best.route.next_hop)
+                .into_iter()
+                .collect();
+        }
+        let neighbor_as = best.route.as_path.first();
+        self.rib_in
+            .get(p)
+            .into_iter()
+            .flat_map(|rib| rib.values())
+            .filter_map(|e| self.process_rib_in_route(e.clone()))
+            .filter(|e| e.cmp_bestpath_attributes(best, self.as_path_ignore) == Ordering::Equal)
+            .filter(|e| !self.multipath_same_as || e.route.as_path.first() == neighbor_as)
+            .filter_map(|e| self.resolve_next_hop(e.route.next_hop))
+            .chain(self.resolve_next_hop(best.route.next_hop))
+            .sorted()
+            .dedup()
+            .collect()
+    }
+
+    /// Get the prefix via which `next_hop` is resolved recursively, if configured (see
+    /// [`Network::set_next_hop_resolution`]).
+    pub fn get_next_hop_resolution(&self, next_hop: RouterId) -> Option<P> {
+        self.next_hop_resolution.get(&next_hop).copied()
+    }
+
+    /// Get the maximum number of recursive lookups performed to resolve a next-hop.
+    pub fn get_recursion_limit(&self) -> usize {
+        self.recursion_limit
+    }
+
+    /// Get the number of flaps after which a dampable route is suppressed, or `None` if route flap
+    /// damping is disabled (see [`Network::set_route_damping`]).
+    pub fn get_route_damping(&self) -> Option<u32> {
+        self.damping
+    }
+
+    /// Get the number of times the dampable route for `prefix` from `neighbor` has flapped.
+    pub fn get_flaps(&self, prefix: P, neighbor: RouterId) -> u32 {
+        self.flaps
+            .get(&prefix)
+            .and_then(|x| x.get(&neighbor))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns `true` if dampable routes for `prefix` from `neighbor` are suppressed, because the
+    /// route has flapped too often (see [`Network::set_route_damping`]).
+    pub fn is_suppressed(&self, prefix: P, neighbor: RouterId) -> bool {
+        self.damping
+            .is_some_and(|threshold| self.get_flaps(prefix, neighbor) >= threshold)
+    }
+
+    /// Get the VRF with the given name, if it exists.
+    pub fn get_vrf(&self, name: &str) -> Option<&Vrf<P>> {
+        self.vrfs.get(name)
+    }
+
+    /// Get all VRFs hosted on the router.
+    pub fn get_vrfs(&self) -> &BTreeMap<String, Vrf<P>> {
+        &self.vrfs
+    }
+
+    /// Get the name of the VRF to which `neighbor` is attached, or `None` if the neighbor belongs
+    /// to the global BGP table.
+    pub fn get_vrf_of(&self, neighbor: RouterId) -> Option<&str> {
+        self.vrfs
+            .iter()
+            .find(|(_, vrf)| vrf.neighbors.contains(&neighbor))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Get the table of selected routes in the VRF `name`. The table is computed from the routes
+    /// learned from the neighbors of the VRF, and the routes leaked from other VRFs (see [`Vrf`]).
+    pub fn get_vrf_rib(&self, name: &str) -> Result<P::Map<BgpRibEntry<P>>, DeviceError> {
+        let vrf = self
+            .vrfs
+            .get(name)
+            .ok_or_else(|| DeviceError::VrfNotFound(name.to_string()))?;
+        let leaking = self
+            .vrfs
+            .iter()
+            .filter(|(n, v)| *n != name && !v.export.is_disjoint(&vrf.import))
+            .map(|(_, v)| v)
+            .collect::<Vec<_>>();
+        let mut rib: P::Map<BgpRibEntry<P>> = Default::default();
+        for prefix in self.rib_in.keys() {
+            let leaked = leaking.iter().filter_map(|v| {
+                let mut route = self.select_vrf_route(v, *prefix, std::iter::empty())?;
+                route
+                    .route
+                    .communities
+                    .extend(v.export.iter().map(Community::from));
+                Some(route)
+            });
+            if let Some(route) = self.select_vrf_route(vrf, *prefix, leaked) {
+                rib.insert(*prefix, route);
+            }
+        }
+        Ok(rib)
+    }
+
+    /// Get the route selected for `prefix` in the VRF `name` using longest-prefix matching.
+    pub fn get_vrf_route(
+        &self,
+        name: &str,
+        prefix: P,
+    ) -> Result<Option<BgpRibEntry<P>>, DeviceError> {
+        Ok(self
+            .get_vrf_rib(name)?
+            .get_lpm(&prefix)
+            .map(|(_, route)| route.clone()))
+    }
+
+    /// Select the best route for `prefix` in `vrf` among the routes learned from its neighbors and
+    /// the `leaked` routes, after applying the route-maps of the VRF.
+    fn select_vrf_route(
+        &self,
+        vrf: &Vrf<P>,
+        prefix: P,
+        leaked: impl Iterator<Item = BgpRibEntry<P>>,
+    ) -> Option<BgpRibEntry<P>> {
+        self.rib_in
+            .get(&prefix)
+            .into_iter()
+            .flat_map(|rib| rib.values())
+            .filter(|e| vrf.neighbors.contains(&e.from_id))
+            .filter_map(|e| self.process_route(e.clone()))
+            .chain(leaked)
+            .filter_map(|e| vrf.route_maps.apply_with(e, &self.as_path_lists))
+            .max_by(|a, b| a.cmp_bestpath(b, self.as_path_ignore))
+    }
+
+    /// Returns `true` if the router sets the next-hop of routes learned over eBGP to itself before
+    /// advertising them to `neighbor` (next-hop-self). This is enabled by default.
+    pub fn get_next_hop_self(&self, neighbor: RouterId) -> bool {
+        !self.no_next_hop_self.contains(&neighbor)
+    }
+
+    /// Returns `true` if the session with `neighbor` is in graceful shutdown, i.e., all routes
+    /// exchanged with `neighbor` are tagged with the [`GRACEFUL_SHUTDOWN`] community.
+    pub fn get_graceful_shutdown(&self, neighbor: RouterId) -> bool {
+        self.graceful_shutdown.contains(&neighbor)
+    }
+
+    /// Get the attributes that the router propagates to `neighbor`. This is either the attribute
+    /// propagation configured for that session, or the default for the session type (see
+    /// [`AttributePropagation::for_session`]). Returns `None` if there is no session with
+    /// `neighbor`.
+    pub fn get_attribute_propagation(&self, neighbor: RouterId) -> Option<AttributePropagation> {
+        self.attribute_propagation
+            .get(&neighbor)
+            .copied()
+            .or_else(|| {
+                self.sessions
+                    .get(&neighbor)
+                    .map(|ty| AttributePropagation::for_session(*ty))
+            })
+    }
+
+    /// Get the named AS-path access-list, if it is defined.
+    pub fn get_as_path_list(&self, name: &str) -> Option<&AsPathList> {
+        self.as_path_lists.get(name)
+    }
+
+    /// Get all named AS-path access-lists.
+    pub fn get_as_path_lists(&self) -> &AsPathLists {
+        &self.as_path_lists
+    }
+
+    /// Returns `true` if the router is configured as a route reflector.
+    pub fn is_route_reflector(&self) -> bool {
+        self.route_reflector
+    }
+
+    /// Get the cluster ID that the router appends to the CLUSTER_LIST of reflected routes. This is
+    /// either the explicitly configured cluster ID (see [`Network::set_cluster_id`]), or the index
+    /// of the router.
+    pub fn get_cluster_id(&self) -> u32 {
+        self.cluster_id.unwrap_or(self.router_id.index() as u32)
+    }
+
+    /// Get the BGP router-id of any `router` in the network (including this one). This is either
+    /// the explicitly configured router-id, or the [`default_bgp_id`].
+    pub fn get_bgp_id(&self, router: RouterId) -> Ipv4Addr {
+        self.bgp_ids
+            .get(&router)
+            .copied()
+            .unwrap_or_else(|| default_bgp_id(router))
+    }
+
     /*
      * Configuration Functions
      */
@@ -386,6 +923,393 @@ impl<P: Prefix> BgpProcess<P> {
             .map(|events| (Some(old_map), events))
     }
 
+    /// Configure RPKI origin validation, or disable it by passing `None`. The router validates all
+    /// incoming routes using the ROA table, and treats `Invalid` routes according to the policy.
+    /// This function will return the old configuration, and all events triggered by this action.
+    pub(crate) fn set_rpki<T: Default>(
+        &mut self,
+        rpki: Option<(RoaTable<P>, RpkiPolicy)>,
+    ) -> UpdateOutcome<(RoaTable<P>, RpkiPolicy), P, T> {
+        let old = std::mem::replace(&mut self.rpki, rpki);
+        self.update_tables(true).map(|events| (old, events))
+    }
+
+    /// Set the AS number presented to `neighbor` (`local-as`), or remove it by passing `None`.
+    /// This only affects eBGP sessions: The router prepends that AS number instead of its own to
+    /// all routes advertised to `neighbor`, and rejects all routes from `neighbor` that contain it
+    /// in their AS path. This function will return the old value, and all events triggered by this
+    /// action.
+    pub(crate) fn set_local_as<T: Default>(
+        &mut self,
+        neighbor: RouterId,
+        local_as: Option<AsId>,
+    ) -> UpdateOutcome<AsId, P, T> {
+        let old = match local_as {
+            Some(asn) => self.local_as.insert(neighbor, asn),
+            None => self.local_as.remove(&neighbor),
+        };
+        self.update_tables(true).map(|events| (old, events))
+    }
+
+    /// Set the number of times the local AS may appear in the AS path of routes received from
+    /// `neighbor` (`allowas-in`). Setting it to 0 restores the default loop detection. This
+    /// function will return the old value, and all events triggered by this action.
+    pub(crate) fn set_allowas_in<T: Default>(
+        &mut self,
+        neighbor: RouterId,
+        n: usize,
+    ) -> Result<(usize, Vec<Event<P, T>>), DeviceError> {
+        let old = if n == 0 {
+            self.allowas_in.remove(&neighbor)
+        } else {
+            self.allowas_in.insert(neighbor, n)
+        };
+        self.update_tables(true)
+            .map(|events| (old.unwrap_or_default(), events))
+    }
+
+    /// Configure the eBGP session with `neighbor` as a multihop session with the given `ttl`, or
+    /// as a directly connected session by passing `None`. Routes learned over a multihop session
+    /// keep the IGP cost towards the neighbor. This function will return the old value, and all
+    /// events triggered by this action.
+    pub(crate) fn set_ebgp_multihop<T: Default>(
+        &mut self,
+        neighbor: RouterId,
+        ttl: Option<u8>,
+    ) -> UpdateOutcome<u8, P, T> {
+        let old = match ttl {
+            Some(ttl) => self.ebgp_multihop.insert(neighbor, ttl),
+            None => self.ebgp_multihop.remove(&neighbor),
+        };
+        self.update_tables(false).map(|events| (old, events))
+    }
+
+    /// Enable optimal route reflection towards `client`, selecting the best route from the IGP
+    /// view of `igp_root`, or disable it by passing `None`. The IGP cost from `igp_root` must be
+    /// provided using [`BgpProcess::update_orr_igp`]. This function will return the old value,
+    /// and all events triggered by this action.
+    pub(crate) fn set_orr<T: Default>(
+        &mut self,
+        client: RouterId,
+        igp_root: Option<RouterId>,
+    ) -> UpdateOutcome<RouterId, P, T> {
+        let old = match igp_root {
+            Some(root) => self.orr.insert(client, root),
+            None => self.orr.remove(&client),
+        };
+        let roots: HashSet<RouterId> = self.orr.values().copied().collect();
+        self.orr_igp_cost.retain(|root, _| roots.contains(root));
+        self.update_tables(true).map(|events| (old, events))
+    }
+
+    /// Update the IGP cost from `igp_root` towards all other routers, used for optimal route
+    /// reflection. This function returns all events triggered by this action.
+    pub(crate) fn update_orr_igp<T: Default>(
+        &mut self,
+        igp_root: RouterId,
+        igp_cost: HashMap<RouterId, LinkWeight>,
+    ) -> Result<Vec<Event<P, T>>, DeviceError> {
+        if self.orr_igp_cost.get(&igp_root) == Some(&igp_cost) {
+            return Ok(Vec::new());
+        }
+        self.orr_igp_cost.insert(igp_root, igp_cost);
+        if self.orr.values().any(|root| *root == igp_root) {
+            self.update_tables(true)
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    /// Set the distribute-list (prefix filter) of the session with `neighbor` in the given
+    /// direction, or remove it by passing `None`. This function will return the old value, and all
+    /// events triggered by this action.
+    pub(crate) fn set_distribute_list<T: Default>(
+        &mut self,
+        neighbor: RouterId,
+        direction: RouteMapDirection,
+        list: Option<DistributeList<P>>,
+    ) -> UpdateOutcome<DistributeList<P>, P, T> {
+        let lists = match direction {
+            Incoming => &mut self.distribute_lists_in,
+            Outgoing => &mut self.distribute_lists_out,
+        };
+        let old = match list {
+            Some(list) => lists.insert(neighbor, list),
+            None => lists.remove(&neighbor),
+        };
+        self.update_tables(true).map(|events| (old, events))
+    }
+
+    /// Set the default attributes of routes received from `neighbor`, or remove them by passing
+    /// `None`. This function will return the old value, and all events triggered by this action.
+    pub(crate) fn set_session_defaults<T: Default>(
+        &mut self,
+        neighbor: RouterId,
+        defaults: Option<BgpSessionDefaults>,
+    ) -> UpdateOutcome<BgpSessionDefaults, P, T> {
+        let old = match defaults {
+            Some(defaults) => self.session_defaults.insert(neighbor, defaults),
+            None => self.session_defaults.remove(&neighbor),
+        };
+        self.update_tables(true).map(|events| (old, events))
+    }
+
+    /// Set the options of the session with `neighbor`, or remove them by passing `None`. The
+    /// options do not affect the simulation. This function will return the old value.
+    pub(crate) fn set_session_options(
+        &mut self,
+        neighbor: RouterId,
+        options: Option<BgpSessionOptions>,
+    ) -> Option<BgpSessionOptions> {
+        match options {
+            Some(options) => self.session_options.insert(neighbor, options),
+            None => self.session_options.remove(&neighbor),
+        }
+    }
+
+    /// Enable or disable the minimum route advertisement interval (MRAI) towards `neighbor`. This
+    /// only affects how updates are delivered, and not the tables of the router. This function will
+    /// return the old value.
+    pub(crate) fn set_mrai(&mut self, neighbor: RouterId, enabled: bool) -> bool {
+        if enabled {
+            !self.mrai.insert(neighbor)
+        } else {
+            self.mrai.remove(&neighbor)
+        }
+    }
+
+    /// Configure whether the router ignores the AS path length when selecting the best route
+    /// (`bgp bestpath as-path ignore`). This function will return the old value, and all events
+    /// triggered by this action.
+    pub(crate) fn set_bestpath_as_path_ignore<T: Default>(
+        &mut self,
+        as_path_ignore: bool,
+    ) -> Result<(bool, Vec<Event<P, T>>), DeviceError> {
+        let old = std::mem::replace(&mut self.as_path_ignore, as_path_ignore);
+        self.update_tables(false).map(|events| (old, events))
+    }
+
+    /// Configure whether the router prefers the oldest of two equally good eBGP routes, instead
+    /// of comparing their BGP router-ids. This function will return the old value, and all events
+    /// triggered by this action.
+    pub(crate) fn set_prefer_oldest<T: Default>(
+        &mut self,
+        prefer_oldest: bool,
+    ) -> Result<(bool, Vec<Event<P, T>>), DeviceError> {
+        let old = std::mem::replace(&mut self.prefer_oldest, prefer_oldest);
+        self.update_tables(false).map(|events| (old, events))
+    }
+
+    /// Enable or disable BGP multipath, and return the old value. Multipath only changes the
+    /// forwarding state, not the selected route, so no events are triggered.
+    pub(crate) fn set_bgp_multipath(&mut self, multipath: bool) -> bool {
+        std::mem::replace(&mut self.multipath, multipath)
+    }
+
+    /// Configure whether BGP multipath only combines routes received from the same neighboring AS,
+    /// and return the old value.
+    pub(crate) fn set_bgp_multipath_same_as(&mut self, same_as: bool) -> bool {
+        std::mem::replace(&mut self.multipath_same_as, same_as)
+    }
+
+    /// Configure the cluster ID of the router. If `cluster_id` is `None`, then the router uses its
+    /// index as cluster ID. This function will return the old value, and all events triggered by
+    /// this action.
+    pub(crate) fn set_cluster_id<T: Default>(
+        &mut self,
+        cluster_id: Option<u32>,
+    ) -> UpdateOutcome<u32, P, T> {
+        let old = std::mem::replace(&mut self.cluster_id, cluster_id);
+        // the CLUSTER_LIST of all reflected routes changes.
+        self.update_tables(true).map(|events| (old, events))
+    }
+
+    /// Configure the prefix via which `next_hop` is resolved recursively. If `prefix` is `None`,
+    /// then `next_hop` is resolved in the IGP again. This function will return the old value, and
+    /// all events triggered by this action.
+    pub(crate) fn set_next_hop_resolution<T: Default>(
+        &mut self,
+        next_hop: RouterId,
+        prefix: Option<P>,
+    ) -> UpdateOutcome<P, P, T> {
+        let old = match prefix {
+            Some(prefix) => self.next_hop_resolution.insert(next_hop, prefix),
+            None => self.next_hop_resolution.remove(&next_hop),
+        };
+        self.update_tables(false).map(|events| (old, events))
+    }
+
+    /// Configure the maximum number of recursive lookups performed to resolve a next-hop. This
+    /// function will return the old value, and all events triggered by this action.
+    pub(crate) fn set_recursion_limit<T: Default>(
+        &mut self,
+        limit: usize,
+    ) -> Result<(usize, Vec<Event<P, T>>), DeviceError> {
+        let old = std::mem::replace(&mut self.recursion_limit, limit);
+        self.update_tables(false).map(|events| (old, events))
+    }
+
+    /// Enable or disable next-hop-self towards `neighbor`. This function will return the old
+    /// value, and all events triggered by this action.
+    pub(crate) fn set_next_hop_self<T: Default>(
+        &mut self,
+        neighbor: RouterId,
+        enabled: bool,
+    ) -> Result<(bool, Vec<Event<P, T>>), DeviceError> {
+        let old = self.get_next_hop_self(neighbor);
+        if enabled {
+            self.no_next_hop_self.remove(&neighbor);
+        } else {
+            self.no_next_hop_self.insert(neighbor);
+        }
+        self.update_tables(true).map(|events| (old, events))
+    }
+
+    /// Enable or disable graceful shutdown of the session with `neighbor`. This function will
+    /// return the old value, and all events triggered by this action.
+    pub(crate) fn set_graceful_shutdown<T: Default>(
+        &mut self,
+        neighbor: RouterId,
+        enabled: bool,
+    ) -> Result<(bool, Vec<Event<P, T>>), DeviceError> {
+        let old = if enabled {
+            !self.graceful_shutdown.insert(neighbor)
+        } else {
+            self.graceful_shutdown.remove(&neighbor)
+        };
+        self.update_tables(true).map(|events| (old, events))
+    }
+
+    /// Overwrite the attributes propagated to `neighbor`, or restore the default by passing `None`.
+    /// This function will return the old value, and all events triggered by this action.
+    pub(crate) fn set_attribute_propagation<T: Default>(
+        &mut self,
+        neighbor: RouterId,
+        propagation: Option<AttributePropagation>,
+    ) -> UpdateOutcome<AttributePropagation, P, T> {
+        let old = match propagation {
+            Some(propagation) => self.attribute_propagation.insert(neighbor, propagation),
+            None => self.attribute_propagation.remove(&neighbor),
+        };
+        self.update_tables(true).map(|events| (old, events))
+    }
+
+    /// Define (or update) the named AS-path access-list, or remove it by passing `None`. All
+    /// route-maps referencing the list are re-evaluated. This function will return the old value,
+    /// and all events triggered by this action.
+    pub(crate) fn set_as_path_list<T: Default>(
+        &mut self,
+        name: impl Into<String>,
+        list: Option<AsPathList>,
+    ) -> UpdateOutcome<AsPathList, P, T> {
+        let name = name.into();
+        let old = match list {
+            Some(list) => self.as_path_lists.insert(name, list),
+            None => self.as_path_lists.remove(&name),
+        };
+        self.update_tables(true).map(|events| (old, events))
+    }
+
+    /// Set the BGP router-id of `router` (which can be any router in the network), or reset it to
+    /// the [`default_bgp_id`] by passing `None`. The router-id of the originator (or the neighbor)
+    /// is used as a tiebreaker in the decision process. This function will return the old value,
+    /// and all events triggered by this action.
+    pub(crate) fn set_bgp_id<T: Default>(
+        &mut self,
+        router: RouterId,
+        bgp_id: Option<Ipv4Addr>,
+    ) -> UpdateOutcome<Ipv4Addr, P, T> {
+        let old = match bgp_id {
+            Some(id) => self.bgp_ids.insert(router, id),
+            None => self.bgp_ids.remove(&router),
+        };
+        self.update_tables(true).map(|events| (old, events))
+    }
+
+    /// Configure the number of flaps after which a dampable route is suppressed, or disable route
+    /// flap damping by passing `None`. This function will return the old value, and all events
+    /// triggered by this action.
+    pub(crate) fn set_route_damping<T: Default>(
+        &mut self,
+        threshold: Option<u32>,
+    ) -> UpdateOutcome<u32, P, T> {
+        let old = std::mem::replace(&mut self.damping, threshold);
+        self.update_tables(false).map(|events| (old, events))
+    }
+
+    /// Forget all flaps, such that all suppressed routes are used again (`clear ip bgp
+    /// dampening`). This function returns all events triggered by this action.
+    pub(crate) fn clear_route_damping<T: Default>(
+        &mut self,
+    ) -> Result<Vec<Event<P, T>>, DeviceError> {
+        self.flaps = Default::default();
+        self.update_tables(false)
+    }
+
+    /// Create, update or remove (if `vrf` is `None`) the VRF `name`. A neighbor can only be
+    /// attached to a single VRF, so it is detached from all other VRFs. This function will return
+    /// the old VRF, and all events triggered by this action.
+    pub(crate) fn set_vrf<T: Default>(
+        &mut self,
+        name: impl Into<String>,
+        vrf: Option<Vrf<P>>,
+    ) -> UpdateOutcome<Vrf<P>, P, T> {
+        let name = name.into();
+        let old = match vrf {
+            Some(vrf) => {
+                for (n, other) in self.vrfs.iter_mut() {
+                    if *n != name {
+                        other.neighbors.retain(|x| !vrf.neighbors.contains(x));
+                    }
+                }
+                self.vrfs.insert(name, vrf)
+            }
+            None => self.vrfs.remove(&name),
+        };
+        self.update_tables(true).map(|events| (old, events))
+    }
+
+    /// Clear all BGP tables and forget all known prefixes, while keeping the sessions and the
+    /// configuration. This does not generate any events.
+    pub(crate) fn reset(&mut self) {
+        self.rib_in = Default::default();
+        self.rib = Default::default();
+        self.rib_out = Default::default();
+        self.known_prefixes = Default::default();
+        self.flaps = Default::default();
+    }
+
+    /// Switch all prefixes whose selected route uses `failed_nh` as BGP next-hop to their backup
+    /// route (see [`BgpProcess::get_backup_route`]), without running the decision process. This
+    /// models BGP Prefix-Independent Convergence, where the router repairs its forwarding table
+    /// locally after detecting that the next-hop has failed. The router still advertises the new
+    /// routes to its peers. This function returns all prefixes that were switched, and all events
+    /// triggered by this action.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn local_protect<T: Default>(
+        &mut self,
+        failed_nh: RouterId,
+    ) -> Result<(Vec<P>, Vec<Event<P, T>>), DeviceError> {
+        let affected: Vec<P> = self
+            .rib
+            .iter()
+            .filter(|(_, e)| e.route.next_hop == failed_nh)
+            .map(|(p, _)| *p)
+            .collect();
+
+        let mut switched = Vec::new();
+        let mut events = Vec::new();
+        for prefix in affected {
+            if let Some(backup) = self.get_backup_route(prefix) {
+                self.rib.insert(prefix, backup);
+                events.append(&mut self.run_dissemination_for_prefix(prefix)?);
+                switched.push(prefix);
+            }
+        }
+
+        Ok((switched, events))
+    }
+
     /*
      * Update functions
      */
@@ -412,7 +1336,10 @@ impl<P: Prefix> BgpProcess<P> {
                     return Ok(vec![]);
                 }
             },
-            BgpEvent::Withdraw(prefix) => (self.remove_route(prefix, from), false),
+            BgpEvent::Withdraw(prefix) => {
+                self.record_flap(prefix, from);
+                (self.remove_route(prefix, from), false)
+            }
         };
         self.known_prefixes.insert(prefix);
 
@@ -424,7 +1351,12 @@ impl<P: Prefix> BgpProcess<P> {
         }?;
         if changed {
             // phase 3
-            self.run_dissemination_for_prefix(prefix)
+            let mut events = self.run_dissemination_for_prefix(prefix)?;
+            // routes whose next-hop is resolved via this prefix must be re-evaluated.
+            if self.is_resolution_prefix(&prefix) {
+                events.append(&mut self.update_tables(false)?);
+            }
+            Ok(events)
         } else {
             Ok(Vec::new())
         }
@@ -446,14 +1378,32 @@ impl<P: Prefix> BgpProcess<P> {
         force_dissemination: bool,
     ) -> Result<Vec<Event<P, T>>, DeviceError> {
         let mut events = Vec::new();
+        let prefixes = self.known_prefixes.iter().copied().collect::<Vec<_>>();
         // run the decision process
-        for prefix in self.known_prefixes.iter().copied().collect::<Vec<_>>() {
+        for prefix in prefixes.iter().copied() {
             let changed = self.run_decision_process_for_prefix(prefix)?;
             // if the decision process selected a new route, also run the dissemination process.
             if changed || force_dissemination {
                 events.append(&mut self.run_dissemination_for_prefix(prefix)?);
             }
         }
+        // With recursive next-hop resolution, a route may depend on the route selected for another
+        // prefix. Repeat the decision process until the tables no longer change, at most once for
+        // each level of recursion.
+        if !self.next_hop_resolution.is_empty() {
+            for _ in 0..self.recursion_limit {
+                let mut changed_any = false;
+                for prefix in prefixes.iter().copied() {
+                    if self.run_decision_process_for_prefix(prefix)? {
+                        changed_any = true;
+                        events.append(&mut self.run_dissemination_for_prefix(prefix)?);
+                    }
+                }
+                if !changed_any {
+                    break;
+                }
+            }
+        }
         Ok(events)
     }
 
@@ -487,6 +1437,66 @@ impl<P: Prefix> BgpProcess<P> {
         true
     }
 
+    /// Check if the tables for `prefix` are a fixed point of the BGP protocol, i.e., if
+    /// processing all routes again would not change anything. The function `received` returns
+    /// the route that a neighbor currently advertises to this router. The Adj-RIB-In must contain
+    /// exactly those routes, the selected route must be the best one in the Adj-RIB-In, and the
+    /// Adj-RIB-Out must contain the selected route as it is exported to each peer.
+    pub(crate) fn is_fixed_point(
+        &self,
+        prefix: P,
+        received: impl Fn(RouterId) -> Option<BgpRoute<P>>,
+    ) -> bool {
+        // Adj-RIB-In
+        let rib_in = self.rib_in.get(&prefix);
+        let neighbors: HashSet<RouterId> = self
+            .sessions
+            .keys()
+            .chain(rib_in.into_iter().flat_map(|x| x.keys()))
+            .copied()
+            .collect();
+        for neighbor in neighbors {
+            let exp = if self.sessions.contains_key(&neighbor) {
+                received(neighbor).filter(|r| r.originator_id != Some(self.router_id))
+            } else {
+                None
+            };
+            let acq = rib_in.and_then(|x| x.get(&neighbor)).map(|e| &e.route);
+            if exp.as_ref() != acq {
+                return false;
+            }
+        }
+
+        // Loc-RIB
+        if self.select_best_route(prefix).as_ref() != self.rib.get(&prefix) {
+            return false;
+        }
+
+        // Adj-RIB-Out
+        let rib_best = self.rib.get(&prefix);
+        let rib_out = self.rib_out.get(&prefix);
+        for (peer, peer_type) in self.sessions.iter() {
+            let exp = match rib_best {
+                Some(r) if should_export_route(r.from_id, r.from_type, *peer, *peer_type) => {
+                    match self.process_rib_out_route(r.clone(), *peer) {
+                        Ok(e) => e.map(|e| e.route),
+                        Err(_) => return false,
+                    }
+                }
+                _ => None,
+            };
+            let acq = rib_out.and_then(|x| x.get(peer)).map(|e| &e.route);
+            if exp.as_ref() != acq {
+                return false;
+            }
+        }
+        if rib_out.is_some_and(|x| x.keys().any(|peer| !self.sessions.contains_key(peer))) {
+            return false;
+        }
+
+        true
+    }
+
     /*
      * Private Functions
      */
@@ -514,7 +1524,10 @@ impl<P: Prefix> BgpProcess<P> {
             (None, None) => Ok(false),
             // otherwise, if the new route is better than the old one, we can replace it in any
             // case, even if the origin of both routes would be the same.
-            (old, Some(new)) if new > old => {
+            (old, Some(new))
+                if old
+                    .is_none_or(|old| self.cmp_routes(prefix, &new, old) == Ordering::Greater) =>
+            {
                 // replace the old with the better, new route
                 self.rib.insert(prefix, new);
                 Ok(true)
@@ -543,12 +1556,7 @@ impl<P: Prefix> BgpProcess<P> {
         let old_entry = self.rib.get(&prefix);
 
         // find the new best route
-        let new_entry = self.rib_in.get(&prefix).and_then(|rib| {
-            Iterator::max(
-                rib.values()
-                    .filter_map(|e| self.process_rib_in_route(e.clone())),
-            )
-        });
+        let new_entry = self.select_best_route(prefix);
 
         // check if the entry will get changed
         if new_entry.as_ref() != old_entry {
@@ -566,6 +1574,108 @@ impl<P: Prefix> BgpProcess<P> {
         }
     }
 
+    /// Select the best route for `prefix` among all routes in `self.rib_in`, without changing any
+    /// table.
+    fn select_best_route(&self, prefix: P) -> Option<BgpRibEntry<P>> {
+        self.rib_in.get(&prefix).and_then(|rib| {
+            rib.values()
+                .filter_map(|e| self.process_rib_in_route(e.clone()))
+                .max_by(|a, b| self.cmp_routes(prefix, a, b))
+        })
+    }
+
+    /// Select the best route for `prefix` as seen from `igp_root` (optimal route reflection). The
+    /// IGP cost of each route is replaced by the IGP cost from `igp_root` towards its next-hop.
+    /// Routes whose next-hop `igp_root` cannot reach are ignored.
+    fn select_orr_route(&self, prefix: P, igp_root: RouterId) -> Option<BgpRibEntry<P>> {
+        let igp_cost = self.orr_igp_cost.get(&igp_root)?;
+        self.rib_in.get(&prefix).and_then(|rib| {
+            rib.values()
+                .filter_map(|e| self.process_rib_in_route(e.clone()))
+                .filter_map(|mut e| {
+                    let cost = self
+                        .resolve_next_hop(e.route.next_hop)
+                        .and_then(|nh| igp_cost.get(&nh))
+                        .filter(|c| c.is_finite())?;
+                    e.igp_cost = Some(NotNan::new(*cost).unwrap());
+                    Some(e)
+                })
+                .max_by(|a, b| self.cmp_routes(prefix, a, b))
+        })
+    }
+
+    /// Compare two routes for `prefix` according to the decision process of this router. If
+    /// `prefer_oldest` is enabled and both routes are learned over eBGP, then the currently
+    /// selected route is preferred over an equally good route (up to the BGP router-id), as it was
+    /// installed before.
+    fn cmp_routes(&self, prefix: P, a: &BgpRibEntry<P>, b: &BgpRibEntry<P>) -> Ordering {
+        if self.prefer_oldest && a.from_type.is_ebgp() && b.from_type.is_ebgp() {
+            let installed = self.rib.get(&prefix).map(|e| e.from_id);
+            if a.from_id != b.from_id
+                && installed.is_some_and(|x| x == a.from_id || x == b.from_id)
+                && a.cmp_bestpath_attributes(b, self.as_path_ignore) == Ordering::Equal
+            {
+                return if installed == Some(a.from_id) {
+                    Ordering::Greater
+                } else {
+                    Ordering::Less
+                };
+            }
+        }
+        a.cmp_bestpath(b, self.as_path_ignore)
+    }
+
+    /// Process `route` as if the router received it from `from`, without changing any table. This
+    /// function returns `None` if the router would ignore or reject the route.
+    pub(crate) fn preview_incoming_route(
+        &self,
+        route: BgpRoute<P>,
+        from: RouterId,
+    ) -> Option<BgpRibEntry<P>> {
+        let from_type = *self.sessions.get(&from)?;
+        if route.originator_id == Some(self.router_id) {
+            return None;
+        }
+        self.process_rib_in_route(BgpRibEntry {
+            route,
+            from_type,
+            from_id: from,
+            to_id: None,
+            igp_cost: None,
+            weight: 100,
+            bgp_id: None,
+            tag: 0,
+            dampable: false,
+            rpki: None,
+        })
+    }
+
+    /// Returns `true` if the router advertises its selected route for `prefix` to `peer`, before
+    /// applying any distribute-list or route-map.
+    pub(crate) fn would_advertise(&self, prefix: P, peer: RouterId) -> bool {
+        match (self.rib.get(&prefix), self.sessions.get(&peer)) {
+            (Some(e), Some(ty)) => should_export_route(e.from_id, e.from_type, peer, *ty),
+            _ => false,
+        }
+    }
+
+    /// Get the route that the router would advertise to `to` if it selected `entry`, without
+    /// changing any table. This function returns `None` if the route would not be advertised.
+    pub(crate) fn preview_outgoing_route(
+        &self,
+        entry: &BgpRibEntry<P>,
+        to: RouterId,
+    ) -> Option<BgpRoute<P>> {
+        let to_type = *self.sessions.get(&to)?;
+        if !should_export_route(entry.from_id, entry.from_type, to, to_type) {
+            return None;
+        }
+        self.process_rib_out_route(entry.clone(), to)
+            .ok()
+            .flatten()
+            .map(|e| e.route)
+    }
+
     /// only run bgp route dissemination (phase 3) and return the events triggered by the dissemination
     fn run_dissemination_for_prefix<T: Default>(
         &mut self,
@@ -574,8 +1684,18 @@ impl<P: Prefix> BgpProcess<P> {
         let mut events = Vec::new();
 
         let rib_best = self.rib.get(&prefix);
+        // best routes as seen from the clients with optimal route reflection.
+        let orr_best: HashMap<RouterId, Option<BgpRibEntry<P>>> = self
+            .orr
+            .iter()
+            .map(|(client, root)| (*client, self.select_orr_route(prefix, *root)))
+            .collect();
 
         for (peer, peer_type) in self.sessions.iter() {
+            let rib_best = match orr_best.get(peer) {
+                Some(best) => best.as_ref(),
+                None => rib_best,
+            };
             // get the current route
             let current_route: Option<&BgpRibEntry<P>> =
                 self.rib_out.get(&prefix).and_then(|x| x.get(peer));
@@ -583,7 +1703,8 @@ impl<P: Prefix> BgpProcess<P> {
             // advertised
             let will_advertise = rib_best
                 .map(|r| should_export_route(r.from_id, r.from_type, *peer, *peer_type))
-                .unwrap_or(false);
+                .unwrap_or(false)
+                && self.get_vrf_of(*peer).is_none();
 
             // early exit if nothing will change
             if !will_advertise && current_route.is_none() {
@@ -671,6 +1792,10 @@ impl<P: Prefix> BgpProcess<P> {
             to_id: None,
             igp_cost: None,
             weight: 100,
+            bgp_id: None,
+            tag: 0,
+            dampable: false,
+            rpki: None,
         };
 
         let prefix = new_entry.route.prefix;
@@ -683,6 +1808,31 @@ impl<P: Prefix> BgpProcess<P> {
         Ok((prefix, true))
     }
 
+    /// Count a flap of the route for `prefix` from `neighbor` if route flap damping is enabled, and
+    /// if the route is marked as dampable by the incoming route-maps.
+    fn record_flap(&mut self, prefix: P, neighbor: RouterId) {
+        if self.damping.is_none() {
+            return;
+        }
+        let dampable = self
+            .rib_in
+            .get(&prefix)
+            .and_then(|table| table.get(&neighbor))
+            .and_then(|e| self.prepare_rib_in_route(e.clone()))
+            .and_then(|e| {
+                self.get_route_maps(neighbor, Incoming)
+                    .apply_with(e, &self.as_path_lists)
+            })
+            .is_some_and(|e| e.dampable);
+        if dampable {
+            *self
+                .flaps
+                .get_mut_or_default(prefix)
+                .entry(neighbor)
+                .or_default() += 1;
+        }
+    }
+
     /// remove an existing bgp route in bgp_rib_in and returns the prefix for which the route was
     /// inserted.
     fn remove_route(&mut self, prefix: P, from: RouterId) -> P {
@@ -692,18 +1842,77 @@ impl<P: Prefix> BgpProcess<P> {
         prefix
     }
 
-    /// process incoming routes from bgp_rib_in
-    fn process_rib_in_route(&self, mut entry: BgpRibEntry<P>) -> Option<BgpRibEntry<P>> {
-        // apply bgp_route_map_in
+    /// process incoming routes from bgp_rib_in for the global table. Routes learned from neighbors
+    /// that are attached to a VRF are ignored.
+    fn process_rib_in_route(&self, entry: BgpRibEntry<P>) -> Option<BgpRibEntry<P>> {
+        if self.get_vrf_of(entry.from_id).is_some() {
+            return None;
+        }
+        self.process_route(entry)
+    }
+
+    /// process incoming routes from bgp_rib_in, irrespective of the VRF of the neighbor.
+    fn process_route(&self, entry: BgpRibEntry<P>) -> Option<BgpRibEntry<P>> {
+        // a route with a third-party next-hop does not point to the neighbor that sent it.
+        let third_party_next_hop = entry.route.next_hop != entry.from_id;
         let neighbor = entry.from_id;
-        entry = match self.get_route_maps(neighbor, Incoming).apply(entry) {
+
+        // a route reflector ignores routes that were already reflected within its cluster (loop
+        // prevention, RFC 4456).
+        if self.route_reflector
+            && entry.from_type.is_ibgp()
+            && entry.route.cluster_list.contains(&self.get_cluster_id())
+        {
+            return None;
+        }
+
+        let mut entry = self.prepare_rib_in_route(entry)?;
+
+        // make the igp cost known to the incoming route-maps (it is reset if they change the
+        // next-hop).
+        entry.igp_cost = self
+            .resolve_next_hop(entry.route.next_hop)
+            .and_then(|nh| self.igp_cost.get(&nh))
+            .filter(|c| c.is_finite())
+            .map(|c| NotNan::new(*c).unwrap());
+
+        // apply bgp_route_map_in
+        entry = match self
+            .get_route_maps(neighbor, Incoming)
+            .apply_with(entry, &self.as_path_lists)
+        {
             Some(e) => e,
             None => return None,
         };
 
+        // ignore dampable routes that are suppressed
+        if entry.dampable && self.is_suppressed(entry.route.prefix, neighbor) {
+            return None;
+        }
+
+        // lower the preference of invalid routes
+        if entry.rpki == Some(RpkiValidation::Invalid) {
+            match self.rpki.as_ref() {
+                Some((_, RpkiPolicy::DepreferInvalid(local_pref))) => {
+                    entry.route.local_pref = Some(*local_pref);
+                }
+                Some((_, RpkiPolicy::SoftInvalid { delta, community })) => {
+                    let local_pref = entry.route.local_pref.unwrap_or(100);
+                    entry.route.local_pref = Some(local_pref.saturating_sub(*delta));
+                    entry.route.communities.insert(*community);
+                }
+                _ => {}
+            }
+        }
+
+        // least prefer routes that are drained for maintenance (RFC 8326)
+        if entry.route.communities.contains(&GRACEFUL_SHUTDOWN) {
+            entry.route.local_pref = Some(0);
+        }
+
         let igp_cost = self
-            .igp_cost
-            .get(&entry.route.next_hop)
+            .resolve_next_hop(entry.route.next_hop)
+            .and_then(|nh| self.igp_cost.get(&nh))
             .unwrap_or(&LinkWeight::INFINITY);
         if igp_cost.is_infinite() {
             return None;
@@ -712,27 +1921,144 @@ impl<P: Prefix> BgpProcess<P> {
         // compute the igp cost
         entry.igp_cost = Some(entry.igp_cost.unwrap_or(NotNan::new(*igp_cost).unwrap()));
 
-        // set the next hop to the egress from router if the message came from externally
-        if entry.from_type.is_ebgp() {
+        // set the next hop to the egress from router if the message came from externally, unless
+        // the route carries a third-party next-hop.
+        if entry.from_type.is_ebgp() && !third_party_next_hop {
             entry.route.next_hop = entry.from_id;
-            // set the cost to zero.
-            entry.igp_cost = Some(Default::default());
+            // set the cost to zero, unless the neighbor is reached over multiple hops.
+            if !self.ebgp_multihop.contains_key(&neighbor) {
+                entry.igp_cost = Some(Default::default());
+            }
         }
 
         // set the default values
         entry.route.apply_default();
 
+        // remember the router-id of the originator (or neighbor) for the decision process
+        entry.bgp_id = Some(self.get_bgp_id(entry.route.originator_id.unwrap_or(entry.from_id)));
+
         // set the to_id to None
         entry.to_id = None;
 
         Some(entry)
     }
 
+    /// Process an incoming route from bgp_rib_in up to (but excluding) the incoming route-maps.
+    /// This function returns `None` if the route is dropped before reaching the route-maps.
+    fn prepare_rib_in_route(&self, mut entry: BgpRibEntry<P>) -> Option<BgpRibEntry<P>> {
+        // AS-path loop detection, using the AS number presented to the neighbor. The AS number may
+        // appear as often as configured with `allowas-in`.
+        if entry.from_type.is_ebgp() {
+            let local_as = self.get_local_as(entry.from_id);
+            let count = entry
+                .route
+                .as_path
+                .iter()
+                .filter(|asn| **asn == local_as)
+                .count();
+            if count > self.get_allowas_in(entry.from_id) {
+                return None;
+            }
+        }
+
+        // perform origin validation before applying any route-map
+        if let Some((roas, policy)) = self.rpki.as_ref() {
+            let origin = entry.route.as_path.last().copied().unwrap_or(self.as_id);
+            let validation = roas.validate(entry.route.prefix, origin);
+            if validation == RpkiValidation::Invalid && *policy == RpkiPolicy::DropInvalid {
+                return None;
+            }
+            entry.rpki = Some(validation);
+        }
+
+        // fill in the default attributes of the session
+        let neighbor = entry.from_id;
+        if let Some(defaults) = self.session_defaults.get(&neighbor) {
+            defaults.apply(&mut entry.route);
+        }
+
+        // apply the distribute-list before the route-maps
+        if let Some(list) = self.distribute_lists_in.get(&neighbor) {
+            if !list.allows(&entry.route.prefix) {
+                return None;
+            }
+        }
+
+        // tag all routes received over a session in graceful shutdown
+        if self.graceful_shutdown.contains(&neighbor) {
+            entry.route.communities.insert(GRACEFUL_SHUTDOWN);
+        }
+
+        Some(entry)
+    }
+
     /// Process a route from bgp_rib for sending it to bgp peers, and storing it into bgp_rib_out.
     /// The entry is cloned and modified. This function will also modify the ORIGINATOR_ID and the
     /// CLUSTER_LIST if the route is "reflected". A route is reflected if the router forwards it
     /// from an internal router to another internal router.
     fn process_rib_out_route(
+        &self,
+        entry: BgpRibEntry<P>,
+        target_peer: RouterId,
+    ) -> Result<Option<BgpRibEntry<P>>, DeviceError> {
+        let Some(mut entry) = self.prepare_rib_out_route(entry, target_peer)? else {
+            return Ok(None);
+        };
+        let target_session_type = *self
+            .sessions
+            .get(&target_peer)
+            .ok_or(DeviceError::NoBgpSession(target_peer))?;
+
+        // apply bgp_route_map_out
+        entry = match self
+            .get_route_maps(target_peer, Outgoing)
+            .apply_with(entry, &self.as_path_lists)
+        {
+            Some(e) => e,
+            None => return Ok(None),
+        };
+
+        // get the peer type
+        entry.from_type = target_session_type;
+
+        let propagation = self
+            .attribute_propagation
+            .get(&target_peer)
+            .copied()
+            .unwrap_or_else(|| AttributePropagation::for_session(target_session_type));
+
+        // remove the attributes that are not propagated
+        if !propagation.local_pref {
+            entry.route.local_pref = None;
+        }
+        if !propagation.communities {
+            entry.route.communities.clear();
+        }
+
+        // tag all routes advertised over a session in graceful shutdown
+        if self.graceful_shutdown.contains(&target_peer) {
+            entry.route.communities.insert(GRACEFUL_SHUTDOWN);
+        }
+
+        // if the peer type is external, overwrite the next hop. Also, remove the ORIGINATOR_ID and
+        // the CLUSTER_LIST
+        if target_session_type.is_ebgp() {
+            entry.route.next_hop = self.router_id;
+            entry.route.originator_id = None;
+            entry.route.cluster_list = Vec::new();
+            entry
+                .route
+                .as_path
+                .insert(0, self.get_local_as(target_peer));
+        }
+
+        Ok(Some(entry))
+    }
+
+    /// Process a route from bgp_rib for sending it to `target_peer` up to (but excluding) the
+    /// outgoing route-maps. This function returns `None` if the route is dropped before reaching
+    /// the route-maps.
+    fn prepare_rib_out_route(
         &self,
         mut entry: BgpRibEntry<P>,
         target_peer: RouterId,
@@ -742,10 +2068,16 @@ impl<P: Prefix> BgpProcess<P> {
             .get(&target_peer)
             .ok_or(DeviceError::NoBgpSession(target_peer))?;
 
+        // the well-known communities NO_ADVERTISE and NO_EXPORT suppress the advertisement.
+        if entry.route.communities.contains(&NO_ADVERTISE)
+            || (target_session_type.is_ebgp() && entry.route.communities.contains(&NO_EXPORT))
+        {
+            return Ok(None);
+        }
+
         // before applying the route-map, set the next-hop to self if the route was learned over
-        // eBGP.
-        // TODO: add a configuration variable to control wether to change the next-hop.
-        if entry.from_type.is_ebgp() {
+        // eBGP (unless next-hop-self is disabled towards the target peer).
+        if entry.from_type.is_ebgp() && self.get_next_hop_self(target_peer) {
             entry.route.next_hop = self.router_id;
         }
 
@@ -755,39 +2087,120 @@ impl<P: Prefix> BgpProcess<P> {
             // route is to be reflected. Modify the ORIGINATOR_ID and the CLUSTER_LIST.
             entry.route.originator_id.get_or_insert(entry.from_id);
             // append self to the cluster_list
-            entry.route.cluster_list.push(self.router_id);
+            entry.route.cluster_list.push(self.get_cluster_id());
         }
 
         // set the to_id to the target peer
         entry.to_id = Some(target_peer);
 
-        // clear the MED for eBGP sessions before applying the route-maps
-        if target_session_type.is_ebgp() {
-            entry.route.med = None;
-        }
+        // the result of the origin validation is local to the RIB-In of the router
+        entry.rpki = None;
 
-        // apply bgp_route_map_out
-        entry = match self.get_route_maps(target_peer, Outgoing).apply(entry) {
-            Some(e) => e,
-            None => return Ok(None),
-        };
+        let propagation = self
+            .attribute_propagation
+            .get(&target_peer)
+            .copied()
+            .unwrap_or_else(|| AttributePropagation::for_session(target_session_type));
 
-        // get the peer type
-        entry.from_type = target_session_type;
+        // clear the MED (by default, for eBGP sessions) before applying the route-maps
+        if !propagation.med {
+            entry.route.med = None;
+        }
 
-        // if the peer type is external, overwrite the next hop and reset the local-pref. Also,
-        // remove the ORIGINATOR_ID and the CLUSTER_LIST
-        if target_session_type.is_ebgp() {
-            entry.route.next_hop = self.router_id;
-            entry.route.local_pref = None;
-            entry.route.originator_id = None;
-            entry.route.cluster_list = Vec::new();
-            entry.route.as_path.insert(0, self.as_id);
+        // apply the distribute-list before the route-maps
+        if let Some(list) = self.distribute_lists_out.get(&target_peer) {
+            if !list.allows(&entry.route.prefix) {
+                return Ok(None);
+            }
         }
 
         Ok(Some(entry))
     }
 
+    /*
+     * Configuration
+     */
+
+    /// Returns `true` if both processes have the same configuration, i.e., if they only differ in
+    /// their RIBs and the state derived from them.
+    pub(crate) fn config_eq(&self, other: &Self) -> bool {
+        self.sessions == other.sessions
+            && self.route_maps_in == other.route_maps_in
+            && self.route_maps_out == other.route_maps_out
+            && self.distribute_lists_in == other.distribute_lists_in
+            && self.distribute_lists_out == other.distribute_lists_out
+            && self.rpki == other.rpki
+            && self.local_as == other.local_as
+            && self.allowas_in == other.allowas_in
+            && self.ebgp_multihop == other.ebgp_multihop
+            && self.orr == other.orr
+            && self.route_reflector == other.route_reflector
+            && self.cluster_id == other.cluster_id
+            && self.as_path_ignore == other.as_path_ignore
+            && self.prefer_oldest == other.prefer_oldest
+            && self.multipath == other.multipath
+            && self.multipath_same_as == other.multipath_same_as
+            && self.next_hop_resolution == other.next_hop_resolution
+            && self.recursion_limit == other.recursion_limit
+            && self.damping == other.damping
+            && self.vrfs == other.vrfs
+            && self.no_next_hop_self == other.no_next_hop_self
+            && self.graceful_shutdown == other.graceful_shutdown
+            && self.as_path_lists == other.as_path_lists
+            && self.attribute_propagation == other.attribute_propagation
+            && self.bgp_ids == other.bgp_ids
+            && self.session_defaults == other.session_defaults
+            && self.session_options == other.session_options
+            && self.mrai == other.mrai
+    }
+
+    /// Compute a hash over the configuration of the process, i.e., over everything except the RIBs
+    /// and the state derived from them. The hash neither depends on the order in which the
+    /// configuration was applied, nor on the platform.
+    pub(crate) fn config_hash(&self) -> u64 {
+        fn sorted<K: Ord, V>(map: &HashMap<K, V>) -> Vec<(&K, &V)> {
+            map.iter().sorted_by(|(a, _), (b, _)| a.cmp(b)).collect()
+        }
+        let mut state = StableHasher::default();
+        sorted(&self.sessions).hash(&mut state);
+        sorted(&self.route_maps_in).hash(&mut state);
+        sorted(&self.route_maps_out).hash(&mut state);
+        sorted(&self.distribute_lists_in).hash(&mut state);
+        sorted(&self.distribute_lists_out).hash(&mut state);
+        self.rpki.hash(&mut state);
+        sorted(&self.local_as).hash(&mut state);
+        sorted(&self.allowas_in).hash(&mut state);
+        sorted(&self.ebgp_multihop).hash(&mut state);
+        sorted(&self.orr).hash(&mut state);
+        self.route_reflector.hash(&mut state);
+        self.cluster_id.hash(&mut state);
+        sorted(&self.bgp_ids).hash(&mut state);
+        sorted(&self.session_defaults).hash(&mut state);
+        sorted(&self.session_options).hash(&mut state);
+        self.mrai.iter().sorted().collect_vec().hash(&mut state);
+        self.as_path_ignore.hash(&mut state);
+        self.prefer_oldest.hash(&mut state);
+        self.multipath.hash(&mut state);
+        self.multipath_same_as.hash(&mut state);
+        self.no_next_hop_self
+            .iter()
+            .sorted()
+            .collect_vec()
+            .hash(&mut state);
+        self.graceful_shutdown
+            .iter()
+            .sorted()
+            .collect_vec()
+            .hash(&mut state);
+        sorted(&self.as_path_lists).hash(&mut state);
+        sorted(&self.attribute_propagation).hash(&mut state);
+        sorted(&self.next_hop_resolution).hash(&mut state);
+        self.recursion_limit.hash(&mut state);
+        self.damping.hash(&mut state);
+        self.vrfs.hash(&mut state);
+        state.finish()
+    }
+
     /*
      * Formatting Things
      */
@@ -833,11 +2246,7 @@ impl<'a, 'n, P: Prefix, Q, Ospf: OspfImpl> NetworkFormatter<'a, 'n, P, Q, Ospf>
 
 impl<P: Prefix + PartialEq> PartialEq for BgpProcess<P> {
     fn eq(&self, other: &Self) -> bool {
-        if !(self.sessions == other.sessions
-            && self.rib == other.rib
-            && self.route_maps_in == other.route_maps_in
-            && self.route_maps_out == other.route_maps_out)
-        {
+        if !(self.config_eq(other) && self.rib == other.rib) {
             return false;
         }
         let prefix_union = self.known_prefixes.union(&other.known_prefixes);
@@ -29,12 +29,14 @@
 
 use crate::{
     event::{Event, EventOutcome},
-    ospf::{global::GlobalOspfProcess, IgpTarget, OspfProcess},
+    ospf::{global::GlobalOspfProcess, ExternalMetricType, IgpTarget, LinkWeight, OspfProcess},
     types::{AsId, DeviceError, Prefix, PrefixMap, RouterId, StepUpdate},
 };
 use itertools::Itertools;
 use log::*;
+use ordered_float::NotNan;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 mod bgp_process;
 // mod ospf_process;
@@ -59,10 +61,14 @@ pub struct Router<P: Prefix, Ospf = GlobalOspfProcess> {
     pub sr: SrProcess<P>,
     /// The BGP routing process
     pub bgp: BgpProcess<P>,
+    /// External routes redistributed into OSPF, stored for each prefix and originating ASBR. Each
+    /// router in the OSPF domain knows all external routes.
+    #[serde(default)]
+    pub(crate) ospf_external: P::Map<BTreeMap<RouterId, (ExternalMetricType, LinkWeight)>>,
     /// Flag to tell if load balancing is enabled. If load balancing is enabled, then the router
     /// will load balance packets towards a destination if multiple paths exist with equal
-    /// cost. load balancing will only work within OSPF. BGP Additional Paths is not yet
-    /// implemented.
+    /// cost. load balancing will only work within OSPF. Load balancing across multiple BGP routes
+    /// is configured separately (see [`BgpProcess::get_multipath`]).
     pub(crate) do_load_balancing: bool,
 }
 
@@ -75,6 +81,7 @@ impl<P: Prefix, Ospf: Clone> Clone for Router<P, Ospf> {
             ospf: self.ospf.clone(),
             sr: self.sr.clone(),
             bgp: self.bgp.clone(),
+            ospf_external: self.ospf_external.clone(),
             do_load_balancing: self.do_load_balancing,
         }
     }
@@ -125,6 +132,7 @@ impl<P: Prefix, Ospf: OspfProcess> Router<P, Ospf> {
             ospf: Ospf::new(router_id),
             sr: SrProcess::new(),
             bgp: BgpProcess::new(router_id, as_id),
+            ospf_external: Default::default(),
             do_load_balancing: false,
         }
     }
@@ -139,9 +147,15 @@ impl<P: Prefix, Ospf: OspfProcess> Router<P, Ospf> {
             Event::Bgp { src, dst, e, .. } if dst == self.router_id => {
                 let prefix = e.prefix();
                 let old = self.get_next_hop(prefix);
+                let resolves_next_hops = self.bgp.is_resolution_prefix(&prefix);
                 let events = self.bgp.handle_event(src, e)?;
                 let new = self.get_next_hop(prefix);
-                Ok((StepUpdate::new(prefix, old, new), events))
+                if resolves_next_hops {
+                    // other prefixes may be resolved via this prefix.
+                    Ok((StepUpdate::Multiple, events))
+                } else {
+                    Ok((StepUpdate::new(prefix, old, new), events))
+                }
             }
             Event::Bgp { .. } => {
                 error!(
@@ -195,6 +209,7 @@ impl<P: Prefix, Ospf: OspfProcess> Router<P, Ospf> {
             .get_table()
             .keys()
             .chain(self.bgp.rib.keys())
+            .chain(self.ospf_external.keys())
             .unique()
             .copied()
             .collect();
@@ -208,22 +223,119 @@ impl<P: Prefix, Ospf: OspfProcess> Router<P, Ospf> {
         result
     }
 
+    /// Get the backup next-hop for a prefix, used for BGP Prefix-Independent Convergence. This is
+    /// the IGP next-hop towards the BGP next-hop of the backup route (see
+    /// [`BgpProcess::get_backup_route`]). Prefixes routed by a static route have no backup.
+    pub fn get_backup_next_hop(&self, prefix: P) -> Option<RouterId> {
+        if self.sr.get(prefix).is_some() {
+            return None;
+        }
+        let backup = self.bgp.get_backup_route(prefix)?;
+        self.ospf
+            .get(IgpTarget::Ospf(backup.route.next_hop))
+            .first()
+            .copied()
+    }
+
+    /// Get the backup next-hop of all prefixes that have one (see
+    /// [`Router::get_backup_next_hop`]).
+    pub fn get_backup_fib(&self) -> P::Map<RouterId> {
+        self.bgp
+            .rib
+            .keys()
+            .filter_map(|p| Some((*p, self.get_backup_next_hop(*p)?)))
+            .collect()
+    }
+
+    /// Get the ASBR towards which `prefix` is forwarded using an external OSPF route (see
+    /// [`crate::network::Network::redistribute_into_ospf`]). E1 routes are preferred over E2
+    /// routes. Among E1 routes, the router picks the lowest sum of external and internal cost.
+    /// Among E2 routes, it picks the lowest external metric, and breaks ties using the internal
+    /// cost. The ASBR that redistributes the prefix does not use the external route itself.
+    /// External routes are only used if neither a static route nor a BGP route exist. Internal
+    /// routers of a stub area only see the external routes that are visible in that area (see
+    /// [`OspfStubView`](crate::ospf::OspfStubView)), and use the default route for all others (see
+    /// [`Router::get_ospf_external_default`]).
+    pub fn get_ospf_external_asbr(&self, prefix: P) -> Option<RouterId> {
+        let (_, routes) = self.ospf_external.get_lpm(&prefix)?;
+        if routes.contains_key(&self.router_id) {
+            return None;
+        }
+        let stub_view = self.ospf.get_stub_view();
+        routes
+            .iter()
+            .filter(|(asbr, _)| stub_view.is_none_or(|v| v.is_external_visible(**asbr)))
+            .filter_map(|(asbr, (metric_type, metric))| {
+                let cost = self.ospf.get_cost(*asbr).filter(|c| c.is_finite())?;
+                let total = match metric_type {
+                    ExternalMetricType::E1 => metric + cost,
+                    ExternalMetricType::E2 => *metric,
+                };
+                let key = (
+                    *metric_type,
+                    NotNan::new(total).ok()?,
+                    NotNan::new(cost).ok()?,
+                );
+                Some((key, *asbr))
+            })
+            .min()
+            .map(|(_, asbr)| asbr)
+    }
+
+    /// Get the next-hops of the default route that an internal router of a stub area uses for
+    /// `prefix`, because all external OSPF routes for `prefix` are hidden in that area (see
+    /// [`Router::get_ospf_external_asbr`]). Returns `None` if the router is not an internal router
+    /// of a stub area, if an external route is visible, or if no external route exists at all.
+    pub fn get_ospf_external_default(&self, prefix: P) -> Option<&[RouterId]> {
+        let (_, routes) = self.ospf_external.get_lpm(&prefix)?;
+        let stub_view = self.ospf.get_stub_view()?;
+        if routes.contains_key(&self.router_id)
+            || routes
+                .keys()
+                .any(|asbr| stub_view.is_external_visible(*asbr))
+        {
+            return None;
+        }
+        stub_view.default.as_ref().map(|(nhs, _)| nhs.as_slice())
+    }
+
     /// Get the IGP next hop for a prefix. Prefixes are matched using longest prefix match.
     ///
     /// TODO make this function return a slice
     pub fn get_next_hop(&self, prefix: P) -> Vec<RouterId> {
-        // first, check sr, and then, check bgp. If both do not match, drop the traffic.
-        let target = if let Some(target) = self.sr.get(prefix) {
-            IgpTarget::from(target)
+        // with BGP multipath, forward traffic towards all equally good egress points.
+        if self.bgp.get_bgp_multipath() && self.sr.get(prefix).is_none() {
+            let egresses = self.bgp.get_multipath(prefix);
+            if egresses.len() > 1 {
+                return egresses
+                    .into_iter()
+                    .flat_map(|nh| {
+                        let nhs = self.ospf.get(IgpTarget::Ospf(nh));
+                        if self.do_load_balancing || nhs.is_empty() {
+                            nhs.to_vec()
+                        } else {
+                            vec![nhs[0]]
+                        }
+                    })
+                    .sorted()
+                    .dedup()
+                    .collect();
+            }
+        }
+
+        // first, check sr, and then, check bgp. If both do not match, use the external OSPF
+        // routes (or the default route of a stub area), or drop the traffic. Lookup the IGP
+        // target in the IGP process.
+        let nhs = if let Some(target) = self.sr.get(prefix) {
+            self.ospf.get(IgpTarget::from(target))
         } else if let Some(nh) = self.bgp.get(prefix) {
-            IgpTarget::Ospf(nh)
+            self.ospf.get(IgpTarget::Ospf(nh))
+        } else if let Some(asbr) = self.get_ospf_external_asbr(prefix) {
+            self.ospf.get(IgpTarget::Ospf(asbr))
         } else {
-            IgpTarget::Drop
+            self.get_ospf_external_default(prefix).unwrap_or_default()
         };
 
-        // lookup the IGP target in the IGP process
-        let nhs = self.ospf.get(target);
-
         // perform load balancing
         if self.do_load_balancing || nhs.is_empty() {
             nhs.to_vec()
@@ -232,6 +344,21 @@ impl<P: Prefix, Ospf: OspfProcess> Router<P, Ospf> {
         }
     }
 
+    /// Get the IGP next hop for a prefix in the VRF `vrf`, using the route selected in that VRF
+    /// (see [`BgpProcess::get_vrf_route`]). Prefixes are matched using longest prefix match.
+    pub fn get_vrf_next_hop(&self, prefix: P, vrf: &str) -> Result<Vec<RouterId>, DeviceError> {
+        let target = match self.bgp.get_vrf_route(vrf, prefix)? {
+            Some(route) => IgpTarget::Ospf(route.route.next_hop),
+            None => IgpTarget::Drop,
+        };
+        let nhs = self.ospf.get(target);
+        if self.do_load_balancing || nhs.is_empty() {
+            Ok(nhs.to_vec())
+        } else {
+            Ok(vec![nhs[0]])
+        }
+    }
+
     /// Execute a function on the ospf process. Then, update the BGP process if there was any
     /// change in OSPF.
     ///
@@ -265,6 +392,7 @@ impl<P: Prefix, Ospf: OspfProcess> Router<P, Ospf> {
                 ospf: Ospf2::new(self.router_id),
                 sr: self.sr,
                 bgp: self.bgp,
+                ospf_external: self.ospf_external,
                 do_load_balancing: self.do_load_balancing,
             },
             self.ospf,
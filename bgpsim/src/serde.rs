@@ -26,13 +26,13 @@ use crate::{
     event::EventQueue,
     network::Network,
     ospf::OspfImpl,
-    types::{AsId, NetworkDeviceRef, NetworkError, Prefix, PrefixMap, RouterId},
+    types::{AsId, Community, NetworkDeviceRef, NetworkError, Prefix, PrefixMap, RouterId},
 };
 
 const JSON_FIELD_NAME_NETWORK: &str = "net";
 const JSON_FIELD_NAME_CONFIG: &str = "config_nodes_routes";
 
-type ExportRoutes<P> = (RouterId, P, Vec<AsId>, Option<u32>, BTreeSet<u32>);
+type ExportRoutes<P> = (RouterId, P, Vec<AsId>, Option<u32>, BTreeSet<Community>);
 
 impl<P, Q, Ospf> Network<P, Q, Ospf>
 where
@@ -93,7 +93,7 @@ where
                         route.prefix,
                         route.as_path.clone(),
                         route.med,
-                        route.community.clone(),
+                        route.communities.clone(),
                     )
                 })
             })
@@ -246,8 +246,8 @@ where
             };
             net.apply_modifier(&ConfigModifier::Insert(expr))?;
         }
-        for (src, prefix, as_path, med, community) in routes.into_iter() {
-            net.advertise_external_route(src, prefix, as_path, med, community)?;
+        for (src, prefix, as_path, med, communities) in routes.into_iter() {
+            net.advertise_external_route(src, prefix, as_path, med, communities)?;
         }
         Ok(net)
     }
@@ -0,0 +1,184 @@
+// BgpSim: BGP Network Simulator written in Rust
+// Copyright 2022-2024 Tibor Schneider <sctibor@ethz.ch>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Module to capture snapshots of converged networks, and to compare them (e.g., for A/B testing
+//! two configurations on the same topology).
+//!
+//! ```
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use bgpsim::prelude::*;
+//! use bgpsim::policies::FwPolicy;
+//! use bgpsim::snapshot::Snapshot;
+//!
+//! let mut net: Network<SimplePrefix> = Network::default();
+//! let p = SimplePrefix::from(0);
+//! let r = net.add_router("r");
+//! let e = net.add_external_router("e", 1);
+//! net.add_link(r, e)?;
+//! net.set_bgp_session(r, e, Some(BgpSessionType::EBgp))?;
+//! net.advertise_external_route(e, p, [1], None, None)?;
+//!
+//! let a = Snapshot::capture(&net);
+//! net.withdraw_external_route(e, p)?;
+//! let b = Snapshot::capture(&net);
+//!
+//! let diff = Snapshot::compare(&a, &b, &[FwPolicy::Reachable(r, p)])?;
+//! assert_eq!(diff.policies.len(), 1);
+//! assert!(diff.policies[0].is_broken());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeSet;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    bgp::BgpRibEntry,
+    event::EventQueue,
+    forwarding_state::ForwardingState,
+    network::{Network, NetworkSummary},
+    ospf::OspfImpl,
+    policies::{recheck, Policy, PolicyChange},
+    types::{Prefix, PrefixMap, RouterId},
+};
+
+/// Snapshot of the state of a network. The snapshot contains the forwarding state, the selected
+/// BGP routes of all internal routers, and a summary of the network.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(deserialize = "P: for<'a> serde::Deserialize<'a>"))]
+pub struct Snapshot<P: Prefix> {
+    /// All routers (internal and external) of the network.
+    pub routers: BTreeSet<RouterId>,
+    /// All links of the network. The first router is always smaller than the second one.
+    pub links: BTreeSet<(RouterId, RouterId)>,
+    /// Summary of the network when capturing the snapshot.
+    pub summary: NetworkSummary,
+    /// The forwarding state of the network.
+    pub fw_state: ForwardingState<P>,
+    /// The selected BGP routes of each internal router.
+    pub rib: Vec<(RouterId, P::Map<BgpRibEntry<P>>)>,
+}
+
+impl<P: Prefix> Snapshot<P> {
+    /// Capture the current state of the network. Use [`NetworkSummary::is_converged`] on
+    /// `self.summary` to check if the network has converged when the snapshot was taken.
+    pub fn capture<Q: EventQueue<P>, Ospf: OspfImpl>(net: &Network<P, Q, Ospf>) -> Self {
+        let g = net.get_topology();
+        Self {
+            routers: net.device_indices().collect(),
+            links: g
+                .edge_indices()
+                .filter_map(|e| g.edge_endpoints(e))
+                .map(|(a, b)| if a < b { (a, b) } else { (b, a) })
+                .collect(),
+            summary: net.summary(),
+            fw_state: net.get_forwarding_state(),
+            rib: net
+                .internal_routers()
+                .map(|r| (r.router_id(), r.bgp.get_rib().clone()))
+                .sorted_by_key(|(r, _)| *r)
+                .collect(),
+        }
+    }
+
+    /// Returns `true` if both snapshots were taken on the same topology, i.e., on networks with
+    /// the same routers and the same links.
+    pub fn same_topology(&self, other: &Self) -> bool {
+        self.routers == other.routers && self.links == other.links
+    }
+
+    /// Compare two snapshots, and compute the differences in their forwarding state, their
+    /// selected BGP routes, and the outcome of `policies` (using [`recheck`]). Both snapshots must
+    /// be taken on the same topology.
+    pub fn compare<T: Policy<P>>(
+        a: &Self,
+        b: &Self,
+        policies: &[T],
+    ) -> Result<SnapshotDiff<P, T::Err>, SnapshotError> {
+        if a.routers != b.routers {
+            return Err(SnapshotError::DifferentRouters);
+        }
+        if a.links != b.links {
+            return Err(SnapshotError::DifferentLinks);
+        }
+
+        let fib = a
+            .fw_state
+            .changed_entries(&b.fw_state)
+            .into_iter()
+            .map(|(r, p)| {
+                (
+                    r,
+                    p,
+                    a.fw_state.get_next_hops(r, p).to_vec(),
+                    b.fw_state.get_next_hops(r, p).to_vec(),
+                )
+            })
+            .filter(|(_, _, a_nhs, b_nhs)| a_nhs != b_nhs)
+            .sorted_by(|x, y| (x.0, x.1).cmp(&(y.0, y.1)))
+            .collect();
+
+        let mut rib = Vec::new();
+        for ((r, a_rib), (_, b_rib)) in a.rib.iter().zip(b.rib.iter()) {
+            let prefixes = a_rib.keys().chain(b_rib.keys()).unique().sorted();
+            for p in prefixes {
+                let a_entry = a_rib.get(p);
+                let b_entry = b_rib.get(p);
+                if a_entry != b_entry {
+                    rib.push((*r, *p, a_entry.cloned(), b_entry.cloned()));
+                }
+            }
+        }
+
+        let policies = recheck(&mut a.fw_state.clone(), &mut b.fw_state.clone(), policies);
+
+        Ok(SnapshotDiff { fib, rib, policies })
+    }
+}
+
+/// Differences between two snapshots, computed by [`Snapshot::compare`].
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::type_complexity)]
+pub struct SnapshotDiff<P: Prefix, E> {
+    /// Changed forwarding entries, as a sorted list of `(router, prefix, old_nhs, new_nhs)`. An
+    /// empty list of next-hops means that the router drops the traffic.
+    pub fib: Vec<(RouterId, P, Vec<RouterId>, Vec<RouterId>)>,
+    /// Changed selected routes of internal routers, as a sorted list of
+    /// `(router, prefix, old_route, new_route)`.
+    pub rib: Vec<(RouterId, P, Option<BgpRibEntry<P>>, Option<BgpRibEntry<P>>)>,
+    /// Policies that flipped from satisfied to violated, or vice versa.
+    pub policies: Vec<PolicyChange<E>>,
+}
+
+impl<P: Prefix, E> SnapshotDiff<P, E> {
+    /// Returns `true` if both snapshots are equivalent.
+    pub fn is_empty(&self) -> bool {
+        self.fib.is_empty() && self.rib.is_empty() && self.policies.is_empty()
+    }
+}
+
+/// Error when comparing two snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum SnapshotError {
+    /// The snapshots were taken on networks with different routers.
+    #[error("The snapshots contain different routers.")]
+    DifferentRouters,
+    /// The snapshots were taken on networks with different links.
+    #[error("The snapshots contain different links.")]
+    DifferentLinks,
+}
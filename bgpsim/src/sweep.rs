@@ -0,0 +1,137 @@
+// BgpSim: BGP Network Simulator written in Rust
+// Copyright 2022-2024 Tibor Schneider <sctibor@ethz.ch>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Module for parameter sweeps over independent scenarios.
+//!
+//! Each scenario clones the base network, applies a single parameter value, simulates the network
+//! until it converges, and evaluates a metric on the converged network. Scenarios are fully
+//! isolated, as each one operates on its own copy of the network (including the event queue).
+//! With the `rayon` feature, [`sweep`] runs all scenarios in parallel.
+//!
+//! ```
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use bgpsim::prelude::*;
+//! use bgpsim::sweep::sweep_sequential;
+//!
+//! let mut net: Network<SimplePrefix> = Network::default();
+//! let r1 = net.add_router("r1");
+//! let r2 = net.add_router("r2");
+//! net.add_link(r1, r2)?;
+//!
+//! let results = sweep_sequential(
+//!     &net,
+//!     [1.0, 5.0, 10.0],
+//!     |net, w| net.set_link_weight(r1, r2, *w).map(|_| ()),
+//!     |net| net.get_link_weight(r1, r2).unwrap(),
+//! );
+//! assert_eq!(results[1], (5.0, Ok(5.0)));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    event::EventQueue,
+    interactive::InteractiveNetwork,
+    network::Network,
+    ospf::OspfImpl,
+    types::{NetworkError, Prefix},
+};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// Run a single scenario on a copy of `net`.
+fn run_scenario<P, Q, Ospf, V, M, F, G>(
+    net: &Network<P, Q, Ospf>,
+    value: &V,
+    apply: &F,
+    metric: &G,
+) -> Result<M, NetworkError>
+where
+    P: Prefix,
+    Q: EventQueue<P> + Clone,
+    Ospf: OspfImpl,
+    F: Fn(&mut Network<P, Q, Ospf>, &V) -> Result<(), NetworkError>,
+    G: Fn(&Network<P, Q, Ospf>) -> M,
+{
+    let mut net = net.clone();
+    apply(&mut net, value)?;
+    net.simulate()?;
+    Ok(metric(&net))
+}
+
+/// Perform a parameter sweep sequentially. For each parameter in `values`, clone `net`, modify it
+/// using `apply`, simulate it until convergence, and compute the `metric`. The result contains
+/// each parameter value together with the computed metric (in the same order as `values`), or the
+/// error that occurred during that scenario.
+///
+/// See [`sweep`] for a parallel version (requires the feature `rayon`).
+pub fn sweep_sequential<P, Q, Ospf, V, M, F, G>(
+    net: &Network<P, Q, Ospf>,
+    values: impl IntoIterator<Item = V>,
+    apply: F,
+    metric: G,
+) -> Vec<(V, Result<M, NetworkError>)>
+where
+    P: Prefix,
+    Q: EventQueue<P> + Clone,
+    Ospf: OspfImpl,
+    F: Fn(&mut Network<P, Q, Ospf>, &V) -> Result<(), NetworkError>,
+    G: Fn(&Network<P, Q, Ospf>) -> M,
+{
+    values
+        .into_iter()
+        .map(|v| {
+            let result = run_scenario(net, &v, &apply, &metric);
+            (v, result)
+        })
+        .collect()
+}
+
+/// Perform a parameter sweep in parallel using [rayon](https://docs.rs/rayon). For each parameter
+/// in `values`, clone `net`, modify it using `apply`, simulate it until convergence, and compute
+/// the `metric`. The result contains each parameter value together with the computed metric (in
+/// the same order as `values`), or the error that occurred during that scenario.
+///
+/// The result is identical to [`sweep_sequential`], as long as `apply` and `metric` are
+/// deterministic.
+#[cfg(feature = "rayon")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+pub fn sweep<P, Q, Ospf, V, M, F, G>(
+    net: &Network<P, Q, Ospf>,
+    values: impl IntoIterator<Item = V>,
+    apply: F,
+    metric: G,
+) -> Vec<(V, Result<M, NetworkError>)>
+where
+    P: Prefix,
+    Q: EventQueue<P> + Clone,
+    Ospf: OspfImpl,
+    Network<P, Q, Ospf>: Sync,
+    V: Send,
+    M: Send,
+    F: Fn(&mut Network<P, Q, Ospf>, &V) -> Result<(), NetworkError> + Sync,
+    G: Fn(&Network<P, Q, Ospf>) -> M + Sync,
+{
+    values
+        .into_iter()
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|v| {
+            let result = run_scenario(net, &v, &apply, &metric);
+            (v, result)
+        })
+        .collect()
+}
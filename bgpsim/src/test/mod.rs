@@ -103,6 +103,7 @@ macro_rules! test_bad_route {
     };
 }
 
+mod test_bgp;
 mod test_builder;
 mod test_config;
 #[cfg(feature = "export")]
@@ -113,7 +114,9 @@ mod test_network;
 mod test_network_complete;
 mod test_network_config;
 mod test_ospf;
+mod test_policies;
 mod test_record;
+mod test_repl;
 #[cfg(all(feature = "topology_zoo", feature = "rand", feature = "rand_queue"))]
 mod test_roland;
 mod test_route_map;
@@ -122,5 +125,7 @@ mod test_router;
 mod test_save_restore;
 #[cfg(feature = "rand")]
 mod test_serde;
+mod test_snapshot;
+mod test_sweep;
 #[cfg(feature = "topology_zoo")]
 mod test_topology_zoo;
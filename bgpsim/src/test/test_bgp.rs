@@ -0,0 +1,2567 @@
+// BgpSim: BGP Network Simulator written in Rust
+// Copyright 2022-2024 Tibor Schneider <sctibor@ethz.ch>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test BGP features that change how routes are processed on a single router.
+
+#[generic_tests::define]
+mod t {
+
+    use std::{
+        collections::{HashMap, HashSet},
+        net::Ipv4Addr,
+    };
+
+    use itertools::Itertools;
+    use lazy_static::lazy_static;
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        bgp::{
+            AttributePropagation, BgpEvent, BgpPeerGroup, BgpRibEntry, BgpSessionDefaults,
+            BgpSessionType::*, DistributeList, RoaTable, RpkiPolicy, RpkiValidation,
+            DEFAULT_RECURSION_LIMIT, GRACEFUL_SHUTDOWN, NO_ADVERTISE, NO_EXPORT,
+        },
+        config::{ConfigExpr, ConfigModifier, PolicyLibrary},
+        event::{BasicEventQueue, Event, EventQueue},
+        formatter::{show_ip_bgp, show_ip_route, NetworkFormatter},
+        interactive::InteractiveNetwork,
+        network::{Network, INTERNAL_AS},
+        ospf::{GlobalOspf, LinkWeight},
+        route_map::{
+            RouteMapBuilder,
+            RouteMapDirection::{Incoming, Outgoing},
+            RouteMapMatchAsPath::Contains,
+            RouteMapState::*,
+        },
+        types::{
+            AsId, Community, DeviceError, Ipv4Prefix, NetworkError, Prefix, PrefixMap, RouterId,
+            SimplePrefix,
+        },
+    };
+
+    lazy_static! {
+        static ref E1: RouterId = 0.into();
+        static ref R1: RouterId = 1.into();
+        static ref R2: RouterId = 2.into();
+        static ref E2: RouterId = 3.into();
+    }
+
+    type Net<P> = Network<P, BasicEventQueue<P>, GlobalOspf>;
+
+    /// # Test network
+    ///
+    /// ```text
+    /// E1 ---- R1 ---- R2 ---- E2
+    /// ```
+    ///
+    /// R1 and R2 are iBGP peers, and both have an eBGP session with their external neighbor.
+    fn get_test_net<P: Prefix>() -> Net<P> {
+        let mut net = Net::<P>::default();
+
+        assert_eq!(*E1, net.add_external_router("E1", AsId(65101)));
+        assert_eq!(*R1, net.add_router("R1"));
+        assert_eq!(*R2, net.add_router("R2"));
+        assert_eq!(*E2, net.add_external_router("E2", AsId(65102)));
+
+        net.add_link(*E1, *R1).unwrap();
+        net.add_link(*R1, *R2).unwrap();
+        net.add_link(*R2, *E2).unwrap();
+
+        net.set_link_weight(*R1, *R2, 1.0).unwrap();
+        net.set_link_weight(*R2, *R1, 1.0).unwrap();
+
+        net.set_bgp_session(*E1, *R1, Some(EBgp)).unwrap();
+        net.set_bgp_session(*E2, *R2, Some(EBgp)).unwrap();
+        net.set_bgp_session(*R1, *R2, Some(IBgpPeer)).unwrap();
+
+        net
+    }
+
+    fn paths<P: Prefix>(net: &Net<P>, router: RouterId, prefix: P) -> Vec<Vec<RouterId>> {
+        net.get_forwarding_state()
+            .get_paths(router, prefix)
+            .unwrap_or_default()
+    }
+
+    /// E1 advertises the prefix with an invalid origin but a shorter AS path, while E2 advertises
+    /// it with a valid origin.
+    fn get_rpki_net<P: Prefix>() -> (Net<P>, P, RoaTable<P>) {
+        let mut net = get_test_net::<P>();
+        let p = P::from(0);
+        net.advertise_external_route(*E1, p, [65101, 666], None, None)
+            .unwrap();
+        net.advertise_external_route(*E2, p, [65102, 65103, 100], None, None)
+            .unwrap();
+        let roas = RoaTable::from_iter([(p, 100)]);
+        (net, p, roas)
+    }
+
+    #[test]
+    fn rpki_validate<P: Prefix>() {
+        let p = P::from(0);
+        let roas = RoaTable::from_iter([(p, 100)]);
+        assert_eq!(roas.validate(p, AsId(100)), RpkiValidation::Valid);
+        assert_eq!(roas.validate(p, AsId(666)), RpkiValidation::Invalid);
+        assert_eq!(
+            RoaTable::<P>::new().validate(p, AsId(100)),
+            RpkiValidation::NotFound
+        );
+    }
+
+    #[test]
+    fn rpki_drop_invalid<P: Prefix>() {
+        let (mut net, p, roas) = get_rpki_net::<P>();
+        assert_eq!(paths(&net, *R1, p), vec![vec![*R1, *E1]]);
+
+        net.set_rpki(*R1, roas, RpkiPolicy::DropInvalid).unwrap();
+        assert_eq!(paths(&net, *R1, p), vec![vec![*R1, *R2, *E2]]);
+        let r1 = net.get_internal_router(*R1).unwrap();
+        assert!(r1
+            .bgp
+            .get_known_routes(p)
+            .unwrap()
+            .iter()
+            .all(|e| e.from_id != *E1));
+        assert_eq!(
+            r1.bgp.get_route(p).unwrap().rpki,
+            Some(RpkiValidation::Valid)
+        );
+
+        // without an alternative, the router drops the traffic
+        net.withdraw_external_route(*E2, p).unwrap();
+        assert_eq!(
+            net.get_forwarding_state().get_paths(*R1, p),
+            Err(NetworkError::ForwardingBlackHole(vec![*R1]))
+        );
+
+        // disabling RPKI restores the route
+        net.remove_rpki(*R1).unwrap();
+        assert_eq!(paths(&net, *R1, p), vec![vec![*R1, *E1]]);
+    }
+
+    #[test]
+    fn rpki_deprefer_invalid<P: Prefix>() {
+        let (mut net, p, roas) = get_rpki_net::<P>();
+
+        net.set_rpki(*R1, roas, RpkiPolicy::DepreferInvalid(50))
+            .unwrap();
+        assert_eq!(paths(&net, *R1, p), vec![vec![*R1, *R2, *E2]]);
+        let invalid = net
+            .get_internal_router(*R1)
+            .unwrap()
+            .bgp
+            .get_known_routes(p)
+            .unwrap()
+            .into_iter()
+            .find(|e| e.from_id == *E1)
+            .unwrap();
+        assert_eq!(invalid.rpki, Some(RpkiValidation::Invalid));
+        assert_eq!(invalid.route.local_pref, Some(50));
+
+        // the invalid route is still used if it is the only one.
+        net.withdraw_external_route(*E2, p).unwrap();
+        assert_eq!(paths(&net, *R1, p), vec![vec![*R1, *E1]]);
+    }
+
+    #[test]
+    fn rpki_soft_invalid<P: Prefix>() {
+        let (mut net, p, roas) = get_rpki_net::<P>();
+        net.set_bgp_route_map(
+            *R1,
+            *E1,
+            Incoming,
+            RouteMapBuilder::new()
+                .order(10)
+                .allow()
+                .set_local_pref(120)
+                .build(),
+        )
+        .unwrap();
+        assert_eq!(paths(&net, *R1, p), vec![vec![*R1, *E1]]);
+
+        // the delta is applied relative to the local-pref set by the route-map
+        net.set_rpki_soft(*R1, roas, 30, 666).unwrap();
+        assert_eq!(paths(&net, *R1, p), vec![vec![*R1, *R2, *E2]]);
+        let invalid = net
+            .get_internal_router(*R1)
+            .unwrap()
+            .bgp
+            .get_known_routes(p)
+            .unwrap()
+            .into_iter()
+            .find(|e| e.from_id == *E1)
+            .unwrap();
+        assert_eq!(invalid.rpki, Some(RpkiValidation::Invalid));
+        assert_eq!(invalid.route.local_pref, Some(90));
+        assert!(invalid.route.communities.contains(&Community::from(666)));
+
+        // the invalid route still wins if it is the only one, and it keeps its tag.
+        net.withdraw_external_route(*E2, p).unwrap();
+        assert_eq!(paths(&net, *R1, p), vec![vec![*R1, *E1]]);
+        assert_eq!(paths(&net, *R2, p), vec![vec![*R2, *R1, *E1]]);
+        let r2 = net.get_internal_router(*R2).unwrap();
+        assert!(r2
+            .bgp
+            .get_route(p)
+            .unwrap()
+            .route
+            .communities
+            .contains(&Community::from(666)));
+    }
+
+    #[test]
+    fn rpki_tag_only<P: Prefix>() {
+        let (mut net, p, roas) = get_rpki_net::<P>();
+
+        net.set_rpki(*R1, roas, RpkiPolicy::Tag).unwrap();
+        assert_eq!(paths(&net, *R1, p), vec![vec![*R1, *E1]]);
+        let r1 = net.get_internal_router(*R1).unwrap();
+        assert_eq!(
+            r1.bgp.get_route(p).unwrap().rpki,
+            Some(RpkiValidation::Invalid)
+        );
+        // R2 does not perform origin validation.
+        let r2 = net.get_internal_router(*R2).unwrap();
+        assert_eq!(r2.bgp.get_route(p).unwrap().rpki, None);
+    }
+
+    #[test]
+    fn local_as_prepended<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        let p = P::from(0);
+        net.advertise_external_route(*E2, p, [65102, 100], None, None)
+            .unwrap();
+
+        let as_path = |net: &Net<P>| {
+            net.get_internal_router(*R1)
+                .unwrap()
+                .bgp
+                .get_rib_out()
+                .get(&p)
+                .and_then(|x| x.get(&*E1))
+                .map(|e| e.route.as_path.clone())
+        };
+
+        let old = net.set_local_as(*R1, *E1, Some(AsId(65001))).unwrap();
+        assert_eq!(old, None);
+        assert_eq!(
+            as_path(&net),
+            Some(vec![AsId(65001), AsId(65102), AsId(100)])
+        );
+
+        // internally, the router still uses its own AS.
+        let r2 = net.get_internal_router(*R2).unwrap();
+        assert_eq!(r2.bgp.get_local_as(*E2), INTERNAL_AS);
+
+        let old = net.set_local_as(*R1, *E1, None).unwrap();
+        assert_eq!(old, Some(AsId(65001)));
+        assert_eq!(
+            as_path(&net),
+            Some(vec![INTERNAL_AS, AsId(65102), AsId(100)])
+        );
+    }
+
+    #[test]
+    fn local_as_loop_detection<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        let p1 = P::from(1);
+        let p2 = P::from(2);
+        net.set_local_as(*R2, *E2, Some(AsId(65001))).unwrap();
+        net.advertise_external_route(*E2, p1, [65102, 65001, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(*E2, p2, [65102, INTERNAL_AS.0, 100], None, None)
+            .unwrap();
+
+        // the route containing the presented AS is rejected.
+        assert_eq!(
+            net.get_forwarding_state().get_paths(*R2, p1),
+            Err(NetworkError::ForwardingBlackHole(vec![*R2]))
+        );
+        // the route containing the real AS is accepted.
+        assert_eq!(paths(&net, *R1, p2), vec![vec![*R1, *R2, *E2]]);
+
+        // without local-as, the router uses its own AS for loop detection.
+        net.set_local_as(*R2, *E2, None).unwrap();
+        assert_eq!(paths(&net, *R1, p1), vec![vec![*R1, *R2, *E2]]);
+        assert_eq!(
+            net.get_forwarding_state().get_paths(*R2, p2),
+            Err(NetworkError::ForwardingBlackHole(vec![*R2]))
+        );
+    }
+
+    #[test]
+    fn allowas_in<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        let p1 = P::from(1);
+        let p2 = P::from(2);
+        net.advertise_external_route(*E2, p1, [65102, INTERNAL_AS.0, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(
+            *E2,
+            p2,
+            [65102, INTERNAL_AS.0, 100, INTERNAL_AS.0, 200],
+            None,
+            None,
+        )
+        .unwrap();
+        let black_hole = Err(NetworkError::ForwardingBlackHole(vec![*R2]));
+
+        // by default, routes containing the own AS are rejected.
+        assert_eq!(net.get_forwarding_state().get_paths(*R2, p1), black_hole);
+        assert_eq!(net.get_forwarding_state().get_paths(*R2, p2), black_hole);
+
+        // allow the own AS to appear once.
+        let old = net.set_allowas_in(*R2, *E2, 1).unwrap();
+        assert_eq!(old, 0);
+        assert_eq!(
+            net.get_internal_router(*R2)
+                .unwrap()
+                .bgp
+                .get_allowas_in(*E2),
+            1
+        );
+        assert_eq!(paths(&net, *R1, p1), vec![vec![*R1, *R2, *E2]]);
+        assert_eq!(net.get_forwarding_state().get_paths(*R2, p2), black_hole);
+
+        // allow the own AS to appear twice.
+        net.set_allowas_in(*R2, *E2, 2).unwrap();
+        assert_eq!(paths(&net, *R1, p2), vec![vec![*R1, *R2, *E2]]);
+
+        // restore the default loop detection.
+        let old = net.set_allowas_in(*R2, *E2, 0).unwrap();
+        assert_eq!(old, 2);
+        assert_eq!(net.get_forwarding_state().get_paths(*R2, p1), black_hole);
+        assert_eq!(net.get_forwarding_state().get_paths(*R2, p2), black_hole);
+    }
+
+    #[test]
+    fn distribute_list<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        let p1 = P::from(1);
+        let p2 = P::from(2);
+        net.advertise_external_route(*E2, p1, [65102, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(*E2, p2, [65102, 200], None, None)
+            .unwrap();
+
+        let rib_in = |net: &Net<P>, p: P| {
+            net.get_internal_router(*R1)
+                .unwrap()
+                .bgp
+                .get_rib_in()
+                .get(&p)
+                .is_some_and(|x| x.contains_key(&*R2))
+        };
+
+        // the route-map permits all routes, but the distribute-list is applied first.
+        net.set_bgp_route_map(
+            *R2,
+            *R1,
+            Outgoing,
+            RouteMapBuilder::new().order(10).allow().build(),
+        )
+        .unwrap();
+        let old = net
+            .set_distribute_list(*R2, *R1, Outgoing, [p1], false)
+            .unwrap();
+        assert_eq!(old, None);
+        assert!(!rib_in(&net, p1));
+        assert!(rib_in(&net, p2));
+        assert_eq!(paths(&net, *R1, p2), vec![vec![*R1, *R2, *E2]]);
+        assert_eq!(
+            net.get_forwarding_state().get_paths(*R1, p1),
+            Err(NetworkError::ForwardingBlackHole(vec![*R1]))
+        );
+
+        // an incoming distribute-list that permits only p1 drops p2 at R2.
+        net.set_distribute_list(*R2, *E2, Incoming, [p1], true)
+            .unwrap();
+        assert_eq!(paths(&net, *R2, p1), vec![vec![*R2, *E2]]);
+        assert_eq!(
+            net.get_forwarding_state().get_paths(*R2, p2),
+            Err(NetworkError::ForwardingBlackHole(vec![*R2]))
+        );
+
+        // removing the distribute-lists restores all routes.
+        net.remove_distribute_list(*R2, *E2, Incoming).unwrap();
+        let old = net.remove_distribute_list(*R2, *R1, Outgoing).unwrap();
+        assert_eq!(old, Some(DistributeList::new([p1], false)));
+        assert!(rib_in(&net, p1));
+        assert_eq!(paths(&net, *R1, p1), vec![vec![*R1, *R2, *E2]]);
+        assert_eq!(paths(&net, *R1, p2), vec![vec![*R1, *R2, *E2]]);
+    }
+
+    #[test]
+    fn pic_local_protect<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        let p = P::from(0);
+        net.advertise_external_route(*E1, p, [65101, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(*E2, p, [65102, 100], None, None)
+            .unwrap();
+
+        assert_eq!(paths(&net, *R1, p), vec![vec![*R1, *E1]]);
+        let fw_state = net.get_forwarding_state();
+        assert_eq!(fw_state.backup_next_hop(*R1, p), Some(*R2));
+        assert_eq!(fw_state.backup_next_hop(*R2, p), Some(*R1));
+
+        net.manual_simulation();
+
+        // R1 does not use R2 as a next-hop
+        assert_eq!(net.local_protect(*R1, *R2).unwrap(), vec![]);
+        assert!(net.queue().is_empty());
+
+        // the forwarding table is switched immediately, before BGP has converged.
+        assert_eq!(net.local_protect(*R1, *E1).unwrap(), vec![p]);
+        assert!(!net.queue().is_empty());
+        assert_eq!(paths(&net, *R1, p), vec![vec![*R1, *R2, *E2]]);
+
+        // eventually, the control plane learns about the failure
+        net.withdraw_external_route(*E1, p).unwrap();
+        net.simulate().unwrap();
+        assert_eq!(paths(&net, *R1, p), vec![vec![*R1, *R2, *E2]]);
+        let fw_state = net.get_forwarding_state();
+        assert_eq!(fw_state.backup_next_hop(*R1, p), None);
+        assert_eq!(fw_state.backup_next_hop(*R2, p), None);
+    }
+
+    #[test]
+    fn route_reflector_clients<P: Prefix>() {
+        // star topology around the route reflector R0, with clients C1 and C2, and non-clients N1
+        // and N2.
+        let mut net = Net::<P>::default();
+        let r0 = net.add_router("R0");
+        let c1 = net.add_router("C1");
+        let c2 = net.add_router("C2");
+        let n1 = net.add_router("N1");
+        let n2 = net.add_router("N2");
+        let ec = net.add_external_router("Ec", AsId(65101));
+        let en = net.add_external_router("En", AsId(65102));
+        for r in [c1, c2, n1, n2] {
+            net.add_link(r0, r).unwrap();
+            net.set_bgp_session(r0, r, Some(IBgpPeer)).unwrap();
+        }
+        net.add_link(c1, ec).unwrap();
+        net.add_link(n1, en).unwrap();
+        net.set_bgp_session(c1, ec, Some(EBgp)).unwrap();
+        net.set_bgp_session(n1, en, Some(EBgp)).unwrap();
+
+        // R0 is not yet a route reflector
+        assert_eq!(
+            net.set_rr_client(r0, c1),
+            Err(NetworkError::NotARouteReflector(r0))
+        );
+        assert_eq!(net.set_route_reflector(r0, true), Ok(false));
+        net.set_rr_client(r0, c1).unwrap();
+        net.set_rr_client(r0, c2).unwrap();
+        assert!(net
+            .get_internal_router(r0)
+            .unwrap()
+            .bgp
+            .is_route_reflector());
+
+        let p1 = P::from(1);
+        let p2 = P::from(2);
+        net.advertise_external_route(ec, p1, [65101], None, None)
+            .unwrap();
+        net.advertise_external_route(en, p2, [65102], None, None)
+            .unwrap();
+
+        // routes from clients are reflected to everyone
+        assert_eq!(paths(&net, c2, p1), vec![vec![c2, r0, c1, ec]]);
+        assert_eq!(paths(&net, n1, p1), vec![vec![n1, r0, c1, ec]]);
+        assert_eq!(paths(&net, n2, p1), vec![vec![n2, r0, c1, ec]]);
+        // routes from non-clients are only reflected to clients
+        assert_eq!(paths(&net, c1, p2), vec![vec![c1, r0, n1, en]]);
+        assert_eq!(paths(&net, c2, p2), vec![vec![c2, r0, n1, en]]);
+        assert_eq!(
+            net.get_forwarding_state().get_paths(n2, p2),
+            Err(NetworkError::ForwardingBlackHole(vec![n2]))
+        );
+
+        // removing the role turns all clients into regular peers.
+        assert_eq!(net.set_route_reflector(r0, false), Ok(true));
+        let rr = net.get_internal_router(r0).unwrap();
+        assert!(!rr.bgp.is_route_reflector());
+        assert_eq!(rr.bgp.get_session_type(c1), Some(IBgpPeer));
+        assert_eq!(
+            net.get_forwarding_state().get_paths(c2, p1),
+            Err(NetworkError::ForwardingBlackHole(vec![c2]))
+        );
+        assert_eq!(
+            net.get_forwarding_state().get_paths(c1, p2),
+            Err(NetworkError::ForwardingBlackHole(vec![c1]))
+        );
+        assert_eq!(
+            net.set_rr_client(r0, c2),
+            Err(NetworkError::NotARouteReflector(r0))
+        );
+    }
+
+    #[test]
+    fn router_id_tiebreaker<P: Prefix>() {
+        // R0 learns two routes that are equal in all attributes (including the IGP cost) from
+        // B1 and B2.
+        let mut net = Net::<P>::default();
+        let r0 = net.add_router("R0");
+        let b1 = net.add_router("B1");
+        let b2 = net.add_router("B2");
+        let e1 = net.add_external_router("E1", AsId(65101));
+        let e2 = net.add_external_router("E2", AsId(65102));
+        net.add_link(r0, b1).unwrap();
+        net.add_link(r0, b2).unwrap();
+        net.add_link(b1, e1).unwrap();
+        net.add_link(b2, e2).unwrap();
+        net.set_bgp_session(r0, b1, Some(IBgpPeer)).unwrap();
+        net.set_bgp_session(r0, b2, Some(IBgpPeer)).unwrap();
+        net.set_bgp_session(b1, b2, Some(IBgpPeer)).unwrap();
+        net.set_bgp_session(b1, e1, Some(EBgp)).unwrap();
+        net.set_bgp_session(b2, e2, Some(EBgp)).unwrap();
+
+        let p = P::from(0);
+        net.advertise_external_route(e1, p, [65101, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(e2, p, [65102, 100], None, None)
+            .unwrap();
+
+        // by default, the router-id is derived from the router index.
+        assert_eq!(paths(&net, r0, p), vec![vec![r0, b1, e1]]);
+
+        let id1 = Ipv4Addr::new(10, 0, 0, 2);
+        let id2 = Ipv4Addr::new(10, 0, 0, 1);
+        assert_eq!(net.set_router_id(b1, Some(id1)), Ok(None));
+        assert_eq!(net.set_router_id(b2, Some(id2)), Ok(None));
+        assert_eq!(paths(&net, r0, p), vec![vec![r0, b2, e2]]);
+        let r = net.get_internal_router(r0).unwrap();
+        assert_eq!(r.bgp.get_route(p).unwrap().bgp_id, Some(id2));
+        assert_eq!(r.bgp.get_bgp_id(b1), id1);
+
+        // a new router knows all configured router-ids
+        let r3 = net.add_router("R3");
+        net.add_link(r3, r0).unwrap();
+        net.set_bgp_session(r3, b1, Some(IBgpPeer)).unwrap();
+        net.set_bgp_session(r3, b2, Some(IBgpPeer)).unwrap();
+        assert_eq!(paths(&net, r3, p), vec![vec![r3, r0, b2, e2]]);
+
+        // resetting the router-id of B1 flips the winner again.
+        assert_eq!(net.set_router_id(b1, None), Ok(Some(id1)));
+        assert_eq!(paths(&net, r0, p), vec![vec![r0, b1, e1]]);
+        assert_eq!(paths(&net, r3, p), vec![vec![r3, r0, b1, e1]]);
+    }
+
+    #[test]
+    fn rank_routes<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        let e3 = net.add_external_router("E3", AsId(65103));
+        net.add_link(*R1, e3).unwrap();
+        net.set_bgp_session(*R1, e3, Some(EBgp)).unwrap();
+
+        let p = P::from(0);
+        net.advertise_external_route(*E1, p, [65101, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(*E2, p, [65102, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(e3, p, [65103, 200, 100], None, None)
+            .unwrap();
+
+        let ranking = |net: &Net<P>| -> Vec<RouterId> {
+            net.rank_routes(*R1, p)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.from_id)
+                .collect()
+        };
+
+        // E1 and R2 have the shortest AS path, and R1 prefers eBGP over iBGP.
+        assert_eq!(ranking(&net), vec![*E1, *R2, e3]);
+        let best = net
+            .get_internal_router(*R1)
+            .unwrap()
+            .bgp
+            .get_route(p)
+            .cloned();
+        assert_eq!(net.rank_routes(*R1, p).unwrap().first().cloned(), best);
+
+        // the local preference is compared first. R2 then selects the route from R1 and no longer
+        // advertises its own route to R1.
+        net.set_bgp_route_map(
+            *R1,
+            e3,
+            Incoming,
+            RouteMapBuilder::new()
+                .order(10)
+                .allow()
+                .set_local_pref(200)
+                .build(),
+        )
+        .unwrap();
+        assert_eq!(ranking(&net), vec![e3, *E1]);
+        let best = net
+            .get_internal_router(*R1)
+            .unwrap()
+            .bgp
+            .get_route(p)
+            .cloned();
+        assert_eq!(net.rank_routes(*R1, p).unwrap().first().cloned(), best);
+
+        // R2 prefers the route from R1 due to its higher local preference.
+        assert_eq!(
+            net.rank_routes(*R2, p)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.from_id)
+                .collect::<Vec<_>>(),
+            vec![*R1, *E2]
+        );
+    }
+
+    #[test]
+    fn session_default_attributes<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        let p = P::from(0);
+        let defaults = BgpSessionDefaults {
+            local_pref: Some(50),
+            med: Some(100),
+        };
+        assert_eq!(
+            net.set_session_default_attributes(*R1, *E1, Some(defaults)),
+            Ok(None)
+        );
+
+        let route = |net: &Net<P>| {
+            net.get_internal_router(*R1)
+                .unwrap()
+                .bgp
+                .get_route(p)
+                .map(|e| (e.route.med, e.route.local_pref))
+        };
+
+        // the route inherits the session defaults
+        net.advertise_external_route(*E1, p, [65101], None, None)
+            .unwrap();
+        assert_eq!(route(&net), Some((Some(100), Some(50))));
+
+        // an explicit MED overrides the session default
+        net.advertise_external_route(*E1, p, [65101], Some(10), None)
+            .unwrap();
+        assert_eq!(route(&net), Some((Some(10), Some(50))));
+
+        // removing the defaults falls back to the regular default values
+        assert_eq!(
+            net.set_session_default_attributes(*R1, *E1, None),
+            Ok(Some(defaults))
+        );
+        net.advertise_external_route(*E1, p, [65101], None, None)
+            .unwrap();
+        assert_eq!(route(&net), Some((Some(0), Some(100))));
+    }
+
+    #[test]
+    fn mrai_coalesces_updates<P: Prefix>() {
+        let p = P::from(0);
+
+        // advertise a burst of three updates at E1, and count the updates from R1 to R2.
+        let burst = |mrai: bool| {
+            let mut net = get_test_net::<P>();
+            assert_eq!(net.set_mrai(*R1, *R2, mrai), Ok(false));
+            assert_eq!(
+                net.get_internal_router(*R1).unwrap().bgp.has_mrai(*R2),
+                mrai
+            );
+            net.manual_simulation();
+            net.advertise_external_route(*E1, p, [65101], None, None)
+                .unwrap();
+            net.advertise_external_route(*E1, p, [65101, 100], None, None)
+                .unwrap();
+            net.advertise_external_route(*E1, p, [65101, 100, 200], None, None)
+                .unwrap();
+            let mut updates = 0;
+            while let Some((_, event)) = net.simulate_step().unwrap() {
+                if matches!(event, Event::Bgp { src, e: BgpEvent::Update(_), .. } if src == *R1) {
+                    updates += 1;
+                }
+            }
+            (updates, net)
+        };
+
+        let (updates, net) = burst(false);
+        let (updates_mrai, net_mrai) = burst(true);
+        assert_eq!(updates, 3);
+        assert_eq!(updates_mrai, 1);
+
+        // the converged state is the same
+        assert_eq!(net.get_forwarding_state(), net_mrai.get_forwarding_state());
+        assert_eq!(
+            net_mrai
+                .get_internal_router(*R2)
+                .unwrap()
+                .bgp
+                .get_route(p)
+                .map(|e| e.route.as_path.clone()),
+            Some(vec![AsId(65101), AsId(100), AsId(200)])
+        );
+    }
+
+    #[test]
+    fn bestpath_as_path_ignore<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        let p = P::from(0);
+        net.advertise_external_route(*E1, p, [65101, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(*E2, p, [65102, 200, 100], None, None)
+            .unwrap();
+
+        // R2 prefers the shorter AS path via R1
+        assert_eq!(paths(&net, *R2, p), vec![vec![*R2, *R1, *E1]]);
+
+        // Both routes tie on the AS path length, so R2 prefers the eBGP route.
+        assert_eq!(net.set_bestpath_as_path_ignore(*R2, true), Ok(false));
+        assert!(net
+            .get_internal_router(*R2)
+            .unwrap()
+            .bgp
+            .get_bestpath_as_path_ignore());
+        assert_eq!(paths(&net, *R2, p), vec![vec![*R2, *E2]]);
+        assert_eq!(paths(&net, *R1, p), vec![vec![*R1, *E1]]);
+        assert_eq!(
+            net.get_internal_router(*R2)
+                .unwrap()
+                .bgp
+                .rank_routes(p)
+                .into_iter()
+                .map(|e| e.from_id)
+                .collect::<Vec<_>>(),
+            vec![*E2, *R1]
+        );
+
+        // reset to the default behavior
+        assert_eq!(net.set_bestpath_as_path_ignore(*R2, false), Ok(true));
+        assert_eq!(paths(&net, *R2, p), vec![vec![*R2, *R1, *E1]]);
+    }
+
+    #[test]
+    fn prefer_oldest<P: Prefix>() {
+        let p = P::from(0);
+        for prefer_oldest in [false, true] {
+            let mut net = get_test_net::<P>();
+            let e3 = net.add_external_router("E3", AsId(65103));
+            net.add_link(*R2, e3).unwrap();
+            net.set_bgp_session(e3, *R2, Some(EBgp)).unwrap();
+            assert_eq!(net.set_prefer_oldest(*R2, prefer_oldest), Ok(false));
+            assert_eq!(
+                net.get_internal_router(*R2)
+                    .unwrap()
+                    .bgp
+                    .get_prefer_oldest(),
+                prefer_oldest
+            );
+
+            // R2 first learns the route from E3.
+            net.advertise_external_route(e3, p, [65103, 100], None, None)
+                .unwrap();
+            assert_eq!(paths(&net, *R2, p), vec![vec![*R2, e3]]);
+
+            // The route from E2 is equally good, but E2 has the lower router-id. It only displaces
+            // the installed route if R2 compares the router-ids.
+            net.advertise_external_route(*E2, p, [65102, 100], None, None)
+                .unwrap();
+            if prefer_oldest {
+                assert_eq!(paths(&net, *R2, p), vec![vec![*R2, e3]]);
+            } else {
+                assert_eq!(paths(&net, *R2, p), vec![vec![*R2, *E2]]);
+            }
+
+            // Once the oldest route disappears, R2 selects the remaining one.
+            net.withdraw_external_route(e3, p).unwrap();
+            assert_eq!(paths(&net, *R2, p), vec![vec![*R2, *E2]]);
+        }
+
+        // Disabling the option re-runs the decision process.
+        let mut net = get_test_net::<P>();
+        let e3 = net.add_external_router("E3", AsId(65103));
+        net.add_link(*R2, e3).unwrap();
+        net.set_bgp_session(e3, *R2, Some(EBgp)).unwrap();
+        net.set_prefer_oldest(*R2, true).unwrap();
+        net.advertise_external_route(e3, p, [65103, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(*E2, p, [65102, 100], None, None)
+            .unwrap();
+        assert_eq!(paths(&net, *R2, p), vec![vec![*R2, e3]]);
+        assert_eq!(net.set_prefer_oldest(*R2, false), Ok(true));
+        assert_eq!(paths(&net, *R2, p), vec![vec![*R2, *E2]]);
+    }
+
+    #[test]
+    fn recursive_next_hop<P: Prefix>() {
+        // R2 resolves E3 via p1, and E4 via p2. E2 advertises x with next-hop E3 and p1 with
+        // next-hop E4, while p2 is learned from E1. Neither E3 nor E4 is reachable in the IGP.
+        let x = P::from(0);
+        let p1 = P::from(1);
+        let p2 = P::from(2);
+        let mut net = get_test_net::<P>();
+        let e3 = net.add_external_router("E3", AsId(65103));
+        let e4 = net.add_external_router("E4", AsId(65104));
+        assert_eq!(net.set_next_hop_resolution(*R2, e3, Some(p1)), Ok(None));
+        assert_eq!(net.set_next_hop_resolution(*R2, e4, Some(p2)), Ok(None));
+        let route = |net: &Net<P>, p: P| {
+            net.get_internal_router(*R2)
+                .unwrap()
+                .bgp
+                .get_route(p)
+                .map(|e| e.route.next_hop)
+        };
+
+        // The routes are advertised in reverse order, so x only becomes valid once p2 is known.
+        net.advertise_external_route_with_next_hop(*E2, x, [65102], None, None, e3)
+            .unwrap();
+        net.advertise_external_route_with_next_hop(*E2, p1, [65102], None, None, e4)
+            .unwrap();
+        assert_eq!(route(&net, x), None);
+        assert_eq!(route(&net, p1), None);
+        net.advertise_external_route(*E1, p2, [65101], None, None)
+            .unwrap();
+
+        // Two levels of recursion: x -> E3 -> p1 -> E4 -> p2 -> R1.
+        assert_eq!(route(&net, x), Some(e3));
+        assert_eq!(route(&net, p1), Some(e4));
+        let bgp = &net.get_internal_router(*R2).unwrap().bgp;
+        assert_eq!(bgp.get_next_hop_resolution(e3), Some(p1));
+        assert_eq!(bgp.resolve_next_hop(e3), Some(*R1));
+        assert_eq!(bgp.get(x), Some(*R1));
+        let fw_state = net.get_forwarding_state();
+        assert_eq!(fw_state.get_next_hops(*R2, x), &[*R1]);
+        assert_eq!(fw_state.get_next_hops(*R2, p1), &[*R1]);
+
+        // A limit of a single lookup only suffices to resolve p1.
+        assert_eq!(net.set_recursion_limit(*R2, 1), Ok(DEFAULT_RECURSION_LIMIT));
+        assert_eq!(route(&net, x), None);
+        assert_eq!(route(&net, p1), Some(e4));
+        assert!(net.get_forwarding_state().get_next_hops(*R2, x).is_empty());
+        assert_eq!(net.set_recursion_limit(*R2, DEFAULT_RECURSION_LIMIT), Ok(1));
+        assert_eq!(route(&net, x), Some(e3));
+
+        // Resolving E4 via x creates a resolution loop: x -> E3 -> p1 -> E4 -> x.
+        assert_eq!(net.set_next_hop_resolution(*R2, e4, Some(x)), Ok(Some(p2)));
+        assert_eq!(route(&net, x), None);
+        assert_eq!(route(&net, p1), None);
+        assert_eq!(net.get_internal_router(*R1).unwrap().bgp.get(x), None);
+
+        // Removing the loop makes both routes valid again.
+        assert_eq!(net.set_next_hop_resolution(*R2, e4, Some(p2)), Ok(Some(x)));
+        assert_eq!(route(&net, x), Some(e3));
+        assert_eq!(route(&net, p1), Some(e4));
+    }
+
+    #[test]
+    fn third_party_next_hop<P: Prefix>() {
+        // E1 advertises a route with the third-party next-hop E3. Initially, R2 and R3 are not
+        // connected, so R1 cannot resolve that next-hop.
+        //
+        // E1 ---- R1 ---- R2 .... R3 ---- E3
+        let mut net = Net::<P>::default();
+        let e1 = net.add_external_router("E1", AsId(65101));
+        let r1 = net.add_router("R1");
+        let r2 = net.add_router("R2");
+        let r3 = net.add_router("R3");
+        let e3 = net.add_external_router("E3", AsId(65103));
+        net.add_link(e1, r1).unwrap();
+        net.add_link(r1, r2).unwrap();
+        net.add_link(r3, e3).unwrap();
+        net.set_link_weight(r1, r2, 1.0).unwrap();
+        net.set_link_weight(r2, r1, 1.0).unwrap();
+        net.set_bgp_session(e1, r1, Some(EBgp)).unwrap();
+        net.set_bgp_session(r1, r2, Some(IBgpPeer)).unwrap();
+        net.set_bgp_session(r1, r3, Some(IBgpPeer)).unwrap();
+        net.set_bgp_session(r2, r3, Some(IBgpPeer)).unwrap();
+        assert_eq!(net.set_next_hop_self(r1, r2, false), Ok(true));
+        assert_eq!(net.set_next_hop_self(r1, r3, false), Ok(true));
+        assert!(!net
+            .get_internal_router(r1)
+            .unwrap()
+            .bgp
+            .get_next_hop_self(r2));
+
+        let p = P::from(0);
+        net.advertise_external_route_with_next_hop(e1, p, [65101], None, None, e3)
+            .unwrap();
+        let next_hop = |net: &Net<P>, r: RouterId| net.get_internal_router(r).unwrap().bgp.get(p);
+
+        // the next-hop is unresolvable, so the route is invalid.
+        assert_eq!(next_hop(&net, r1), None);
+        assert_eq!(next_hop(&net, r2), None);
+        assert_eq!(next_hop(&net, r3), None);
+
+        // connect R2 and R3, such that the next-hop becomes resolvable.
+        net.add_link(r2, r3).unwrap();
+        net.set_link_weight(r2, r3, 1.0).unwrap();
+        net.set_link_weight(r3, r2, 1.0).unwrap();
+        assert_eq!(next_hop(&net, r1), Some(e3));
+        assert_eq!(next_hop(&net, r2), Some(e3));
+        // traffic is forwarded towards E3 along the IGP path.
+        let fw = |net: &Net<P>, r: RouterId| net.get_internal_router(r).unwrap().get_next_hop(p);
+        assert_eq!(fw(&net, r1), vec![r2]);
+        assert_eq!(fw(&net, r2), vec![r3]);
+        assert_eq!(fw(&net, r3), vec![e3]);
+
+        // with next-hop-self, R2 receives the route with R1 as the next-hop.
+        assert_eq!(net.set_next_hop_self(r1, r2, true), Ok(false));
+        assert_eq!(next_hop(&net, r2), Some(r1));
+        assert_eq!(next_hop(&net, r3), Some(e3));
+
+        // once the next-hop becomes unreachable again, the route is invalid.
+        net.set_link_weight(r2, r3, LinkWeight::INFINITY).unwrap();
+        assert_eq!(next_hop(&net, r1), None);
+        assert_eq!(next_hop(&net, r2), None);
+    }
+
+    #[test]
+    fn churn_of<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        // R2 prefers the routes from E1 for all prefixes, and advertises them to E2.
+        for i in 0..20 {
+            net.advertise_external_route(*E1, P::from(i), [65101], None, None)
+                .unwrap();
+            net.advertise_external_route(*E2, P::from(i), [65102, 100], None, None)
+                .unwrap();
+        }
+        let before = net.clone();
+
+        // deny a single prefix from E1
+        let single_prefix = ConfigModifier::Insert(ConfigExpr::BgpRouteMap {
+            router: *R1,
+            neighbor: *E1,
+            direction: Incoming,
+            map: RouteMapBuilder::new()
+                .order(10)
+                .deny()
+                .match_prefix(P::from(0))
+                .build(),
+        });
+        // remove the session between R1 and R2
+        let core_session = ConfigModifier::Remove(ConfigExpr::BgpSession {
+            source: *R1,
+            target: *R2,
+            session_type: IBgpPeer,
+        });
+
+        assert_eq!(net.churn_of(&single_prefix), Ok(4));
+        assert_eq!(net.churn_of(&core_session), Ok(20));
+        assert!(net == before);
+    }
+
+    #[test]
+    fn clamp_local_pref<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        let p = P::from(0);
+        net.advertise_external_route(*E1, p, [65101, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(*E2, p, [65102, 100], None, None)
+            .unwrap();
+
+        // clamping is applied after setting the local-pref, even though it is added first.
+        net.set_bgp_route_map(
+            *R1,
+            *E1,
+            Incoming,
+            RouteMapBuilder::new()
+                .order(10)
+                .allow()
+                .clamp_local_pref(50, 150)
+                .set_local_pref(300)
+                .build(),
+        )
+        .unwrap();
+        let attrs = |net: &Net<P>, router: RouterId| {
+            net.rank_routes(router, p)
+                .unwrap()
+                .into_iter()
+                .map(|e| (e.from_id, e.route.local_pref, e.route.med))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(attrs(&net, *R1), vec![(*E1, Some(150), Some(0))]);
+        assert_eq!(paths(&net, *R2, p), vec![vec![*R2, *R1, *E1]]);
+
+        // without clamping, the route from E1 would still be preferred.
+        net.set_bgp_route_map(
+            *R2,
+            *E2,
+            Incoming,
+            RouteMapBuilder::new()
+                .order(10)
+                .allow()
+                .set_local_pref(200)
+                .clamp_med(10, 20)
+                .build(),
+        )
+        .unwrap();
+        assert_eq!(
+            attrs(&net, *R1),
+            vec![(*R2, Some(200), Some(10)), (*E1, Some(150), Some(0))]
+        );
+        assert_eq!(paths(&net, *R1, p), vec![vec![*R1, *R2, *E2]]);
+    }
+
+    #[test]
+    fn shared_as_path_list<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        let p0 = P::from(0);
+        let p1 = P::from(1);
+        // both eBGP sessions deny routes permitted by the same list
+        for (router, neighbor) in [(*R1, *E1), (*R2, *E2)] {
+            net.set_bgp_route_map(
+                router,
+                neighbor,
+                Incoming,
+                RouteMapBuilder::new()
+                    .order(10)
+                    .deny()
+                    .match_as_path_list("filter")
+                    .build(),
+            )
+            .unwrap();
+        }
+        net.advertise_external_route(*E1, p0, [65101, 666], None, None)
+            .unwrap();
+        net.advertise_external_route(*E2, p1, [65102, 666], None, None)
+            .unwrap();
+
+        // the list is not yet defined, so no route is denied
+        assert_eq!(paths(&net, *R1, p0), vec![vec![*R1, *E1]]);
+        assert_eq!(paths(&net, *R1, p1), vec![vec![*R1, *R2, *E2]]);
+
+        assert_eq!(
+            net.define_as_path_list("filter", [(Allow, Contains(AsId(999)))]),
+            Ok(None)
+        );
+        assert_eq!(paths(&net, *R2, p0), vec![vec![*R2, *R1, *E1]]);
+        assert_eq!(paths(&net, *R1, p1), vec![vec![*R1, *R2, *E2]]);
+
+        // the first matching entry decides
+        net.define_as_path_list(
+            "filter",
+            [(Deny, Contains(AsId(65102))), (Allow, Contains(AsId(666)))],
+        )
+        .unwrap();
+        assert_eq!(paths(&net, *R1, p0), Vec::<Vec<RouterId>>::new());
+        assert_eq!(paths(&net, *R2, p0), Vec::<Vec<RouterId>>::new());
+        assert_eq!(paths(&net, *R1, p1), vec![vec![*R1, *R2, *E2]]);
+
+        // updating the list re-filters both sessions
+        net.define_as_path_list("filter", [(Allow, Contains(AsId(666)))])
+            .unwrap();
+        assert_eq!(paths(&net, *R1, p0), Vec::<Vec<RouterId>>::new());
+        assert_eq!(paths(&net, *R1, p1), Vec::<Vec<RouterId>>::new());
+        assert_eq!(paths(&net, *R2, p1), Vec::<Vec<RouterId>>::new());
+
+        // an empty list permits nothing
+        net.define_as_path_list("filter", []).unwrap();
+        assert_eq!(paths(&net, *R2, p0), vec![vec![*R2, *R1, *E1]]);
+        assert_eq!(paths(&net, *R1, p1), vec![vec![*R1, *R2, *E2]]);
+    }
+
+    #[test]
+    fn policy_library<P: Prefix>() {
+        let p0 = P::from(0);
+        let p1 = P::from(1);
+        let p2 = P::from(2);
+
+        // author the policy on the first network, and capture it from the session.
+        let mut net_a = get_test_net::<P>();
+        net_a
+            .define_as_path_list("bad", [(Allow, Contains(AsId(666)))])
+            .unwrap();
+        for map in [
+            RouteMapBuilder::new()
+                .order(10)
+                .deny()
+                .match_as_path_list("bad")
+                .build(),
+            RouteMapBuilder::new()
+                .order(20)
+                .allow()
+                .set_local_pref(200)
+                .build(),
+        ] {
+            net_a.set_bgp_route_map(*R1, *E1, Incoming, map).unwrap();
+        }
+        net_a
+            .set_distribute_list(*R1, *E1, Incoming, [p2], false)
+            .unwrap();
+        let mut library = PolicyLibrary::new();
+        library
+            .capture_session("customer", &net_a, *R1, *E1, Incoming)
+            .unwrap();
+        assert_eq!(library.route_maps["customer"].len(), 2);
+        assert!(library.prefix_lists.contains_key("customer"));
+        assert!(library.as_path_lists.contains_key("bad"));
+
+        // the library survives serialization.
+        let library = PolicyLibrary::<P>::from_json_str(&library.as_json_str()).unwrap();
+
+        // load the library into a second network by name.
+        let mut net_b = get_test_net::<P>();
+        assert_eq!(
+            library.attach_route_map(&mut net_b, *R1, *E1, Incoming, "unknown"),
+            Err(NetworkError::UnknownPolicy("unknown".to_string()))
+        );
+        library
+            .attach_route_map(&mut net_b, *R1, *E1, Incoming, "customer")
+            .unwrap();
+        library
+            .attach_prefix_list(&mut net_b, *R1, *E1, Incoming, "customer")
+            .unwrap();
+        let bgp_a = &net_a.get_internal_router(*R1).unwrap().bgp;
+        let bgp_b = &net_b.get_internal_router(*R1).unwrap().bgp;
+        assert_eq!(
+            bgp_a.get_route_maps(*E1, Incoming),
+            bgp_b.get_route_maps(*E1, Incoming)
+        );
+        assert_eq!(
+            bgp_a.get_distribute_list(*E1, Incoming),
+            bgp_b.get_distribute_list(*E1, Incoming)
+        );
+        assert_eq!(bgp_a.get_as_path_lists(), bgp_b.get_as_path_lists());
+
+        // both networks filter the same routes.
+        for net in [&mut net_a, &mut net_b] {
+            net.advertise_external_route(*E1, p0, [65101, 666], None, None)
+                .unwrap();
+            net.advertise_external_route(*E1, p1, [65101], None, None)
+                .unwrap();
+            net.advertise_external_route(*E1, p2, [65101], None, None)
+                .unwrap();
+        }
+        for net in [&net_a, &net_b] {
+            assert_eq!(paths(net, *R1, p0), Vec::<Vec<RouterId>>::new());
+            assert_eq!(paths(net, *R2, p1), vec![vec![*R2, *R1, *E1]]);
+            assert_eq!(paths(net, *R1, p2), Vec::<Vec<RouterId>>::new());
+            let r2 = &net.get_internal_router(*R2).unwrap().bgp;
+            assert_eq!(r2.get_route(p1).unwrap().route.local_pref, Some(200));
+        }
+    }
+
+    #[test]
+    fn community_localpref_map<P: Prefix>() {
+        let p = P::from(0);
+        let mut net = get_test_net::<P>();
+        let tiers = HashMap::from([(Community::from(100), 50), (Community::from(200), 300)]);
+        net.set_community_localpref_map(*R1, *E1, tiers.clone())
+            .unwrap();
+        net.set_community_localpref_map(*R2, *E2, tiers).unwrap();
+        let local_pref = |net: &Net<P>, r: RouterId| {
+            net.get_internal_router(r)
+                .unwrap()
+                .bgp
+                .get_route(p)
+                .and_then(|e| e.route.local_pref)
+        };
+
+        // E2 advertises the route in the higher tier.
+        net.advertise_external_route(*E1, p, [65101, 100], None, [100].map(Community::from))
+            .unwrap();
+        net.advertise_external_route(*E2, p, [65102, 100], None, [200].map(Community::from))
+            .unwrap();
+        assert_eq!(local_pref(&net, *R1), Some(300));
+        assert_eq!(paths(&net, *R1, p), vec![vec![*R1, *R2, *E2]]);
+        assert_eq!(paths(&net, *R2, p), vec![vec![*R2, *E2]]);
+
+        // Swapping the communities swaps the selection.
+        net.advertise_external_route(*E1, p, [65101, 100], None, [200].map(Community::from))
+            .unwrap();
+        net.advertise_external_route(*E2, p, [65102, 100], None, [100].map(Community::from))
+            .unwrap();
+        assert_eq!(local_pref(&net, *R2), Some(300));
+        assert_eq!(paths(&net, *R1, p), vec![vec![*R1, *E1]]);
+        assert_eq!(paths(&net, *R2, p), vec![vec![*R2, *R1, *E1]]);
+
+        // A route with several communities gets the highest tier, and routes without any mapped
+        // community keep the default.
+        net.advertise_external_route(
+            *E2,
+            p,
+            [65102, 100],
+            None,
+            [100, 200, 300].map(Community::from),
+        )
+        .unwrap();
+        net.advertise_external_route(*E1, p, [65101, 100], None, [300].map(Community::from))
+            .unwrap();
+        assert_eq!(local_pref(&net, *R1), Some(300));
+        assert_eq!(paths(&net, *R1, p), vec![vec![*R1, *R2, *E2]]);
+
+        // Subsequent route-map items still apply, and the map can be replaced.
+        net.set_bgp_route_map(
+            *R1,
+            *E1,
+            Incoming,
+            RouteMapBuilder::new()
+                .order(10)
+                .allow()
+                .match_community(300)
+                .set_local_pref(500)
+                .build(),
+        )
+        .unwrap();
+        assert_eq!(paths(&net, *R2, p), vec![vec![*R2, *R1, *E1]]);
+        net.set_community_localpref_map(*R2, *E2, HashMap::new())
+            .unwrap();
+        assert_eq!(
+            net.get_internal_router(*R2)
+                .unwrap()
+                .bgp
+                .get_route_maps(*E2, Incoming),
+            &[]
+        );
+        net.set_bgp_route_map(
+            *R1,
+            *E1,
+            Incoming,
+            RouteMapBuilder::new().order(10).allow().build(),
+        )
+        .unwrap();
+        net.set_community_localpref_map(*R1, *E1, HashMap::from([(Community::from(300), 20)]))
+            .unwrap();
+        assert_eq!(local_pref(&net, *R1), Some(100));
+        assert_eq!(paths(&net, *R1, p), vec![vec![*R1, *R2, *E2]]);
+
+        // user items in the reserved range are neither removed nor overwritten.
+        let user_item = RouteMapBuilder::new()
+            .order_sgn(i16::MIN)
+            .allow()
+            .match_community(300)
+            .set_weight(10)
+            .continue_next()
+            .build();
+        net.set_bgp_route_map(*R2, *E2, Incoming, user_item.clone())
+            .unwrap();
+        assert_eq!(
+            net.set_community_localpref_map(*R2, *E2, HashMap::from([(Community::from(300), 20)])),
+            Err(NetworkError::ReservedRouteMapOrder(*E2, i16::MIN))
+        );
+        assert_eq!(
+            net.get_internal_router(*R2)
+                .unwrap()
+                .bgp
+                .get_route_maps(*E2, Incoming),
+            &[user_item]
+        );
+    }
+
+    #[test]
+    fn compare_decision_processes<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        let e3 = net.add_external_router("E3", AsId(65101));
+        net.add_link(*R2, e3).unwrap();
+        net.set_bgp_session(*R2, e3, Some(EBgp)).unwrap();
+
+        let p_med = P::from(0);
+        let p_path = P::from(1);
+        let p_single = P::from(2);
+
+        // E1 and E3 are in the same AS, so the MED decides for `p_med`.
+        net.advertise_external_route(*E1, p_med, [65101, 100], Some(20), None)
+            .unwrap();
+        net.advertise_external_route(e3, p_med, [65101, 100], Some(10), None)
+            .unwrap();
+        // For `p_path`, the AS path length decides before the MED is considered.
+        net.advertise_external_route(*E1, p_path, [65101, 100], Some(10), None)
+            .unwrap();
+        net.advertise_external_route(e3, p_path, [65101, 200, 100], Some(20), None)
+            .unwrap();
+        net.advertise_external_route(*E1, p_single, [65101, 100], None, None)
+            .unwrap();
+
+        let standard = |a: &BgpRibEntry<P>, b: &BgpRibEntry<P>| a.cmp(b);
+        let ignore_med = |a: &BgpRibEntry<P>, b: &BgpRibEntry<P>| {
+            let mut a = a.clone();
+            let mut b = b.clone();
+            a.route.med = None;
+            b.route.med = None;
+            a.cmp(&b)
+        };
+
+        // comparing a process with itself never yields any difference.
+        assert!(net
+            .compare_decision_processes(standard, standard)
+            .is_empty());
+
+        // Only R1 knows both routes for `p_med`. R2 only learns the route from E3, since R1 does
+        // not advertise routes learned over iBGP back to R2.
+        let diff = net.compare_decision_processes(standard, ignore_med);
+        assert_eq!(diff.len(), 1);
+        let (router, prefix, a, b) = &diff[0];
+        assert_eq!((*router, *prefix), (*R1, p_med));
+        assert_eq!(a.from_id, *R2);
+        assert_eq!(b.from_id, *E1);
+
+        // The network was not reconverged.
+        assert_eq!(paths(&net, *R1, p_med), vec![vec![*R1, *R2, e3]]);
+
+        // The result is symmetric.
+        let diff_rev = net.compare_decision_processes(ignore_med, standard);
+        assert_eq!(diff_rev.len(), 1);
+        assert_eq!(diff_rev[0].2, diff[0].3);
+        assert_eq!(diff_rev[0].3, diff[0].2);
+    }
+
+    #[test]
+    fn route_server<P: Prefix>() {
+        let p = P::from(0);
+        let mut net = get_test_net::<P>();
+        // R1 and R2 are members of an IXP in different ASes that only peer with the route server.
+        net.set_bgp_session(*R1, *R2, None).unwrap();
+        let rs = net.add_route_server("RS", AsId(65000), []).unwrap();
+        assert!(net.get_external_router(rs).unwrap().is_route_server());
+        net.add_link(rs, *R1).unwrap();
+        net.add_link(rs, *R2).unwrap();
+        net.set_bgp_session_from([(rs, *R1, Some(EBgp)), (rs, *R2, Some(EBgp))])
+            .unwrap();
+        net.set_local_as(*R1, rs, Some(AsId(100))).unwrap();
+        net.set_local_as(*R2, rs, Some(AsId(200))).unwrap();
+
+        net.advertise_external_route(*E1, p, [65101], None, None)
+            .unwrap();
+
+        // R2 learns the route from the route server with the original next-hop and AS path.
+        let r2_route = |net: &Net<P>| {
+            net.get_internal_router(*R2)
+                .unwrap()
+                .bgp
+                .get_route(p)
+                .cloned()
+        };
+        let route = r2_route(&net).unwrap();
+        assert_eq!(route.from_id, rs);
+        assert_eq!(route.route.next_hop, *R1);
+        assert_eq!(route.route.as_path, vec![AsId(100), AsId(65101)]);
+        assert!(!route.route.as_path.contains(&AsId(65000)));
+        assert_eq!(paths(&net, *R2, p), vec![vec![*R2, *R1, *E1]]);
+
+        // the route server does not reflect the route back to R1
+        assert_eq!(paths(&net, *R1, p), vec![vec![*R1, *E1]]);
+        assert!(net
+            .get_internal_router(*R1)
+            .unwrap()
+            .bgp
+            .get_known_routes(p)
+            .unwrap()
+            .iter()
+            .all(|e| e.from_id != rs));
+
+        // the route learned from the route server is propagated further as usual.
+        let e2_route = net
+            .get_internal_router(*R2)
+            .unwrap()
+            .bgp
+            .get_rib_out()
+            .get(&p)
+            .and_then(|x| x.get(&*E2))
+            .map(|e| e.route.as_path.clone());
+        assert_eq!(e2_route, Some(vec![INTERNAL_AS, AsId(100), AsId(65101)]));
+
+        // a route server cannot originate routes.
+        assert_eq!(
+            net.advertise_external_route(rs, p, [65000], None, None),
+            Err(NetworkError::RouteServer(rs))
+        );
+
+        // withdrawing the route removes it from R2, and so does closing the session to R1.
+        net.withdraw_external_route(*E1, p).unwrap();
+        assert_eq!(r2_route(&net), None);
+        net.advertise_external_route(*E1, p, [65101], None, None)
+            .unwrap();
+        assert_eq!(paths(&net, *R2, p), vec![vec![*R2, *R1, *E1]]);
+        net.set_bgp_session(rs, *R1, None).unwrap();
+        assert_eq!(r2_route(&net), None);
+
+        net.set_bgp_session(rs, *R1, Some(EBgp)).unwrap();
+        assert_eq!(paths(&net, *R2, p), vec![vec![*R2, *R1, *E1]]);
+
+        // a new peer immediately receives all reflected routes.
+        net.set_bgp_session(rs, *R2, None).unwrap();
+        assert_eq!(r2_route(&net), None);
+        net.set_bgp_session(rs, *R2, Some(EBgp)).unwrap();
+        assert_eq!(paths(&net, *R2, p), vec![vec![*R2, *R1, *E1]]);
+    }
+
+    #[test]
+    fn route_map_hits<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        let prefixes = [P::from(0), P::from(1), P::from(2)];
+        for map in [
+            RouteMapBuilder::new()
+                .order(10)
+                .deny()
+                .match_community(666)
+                .build(),
+            RouteMapBuilder::new()
+                .order(20)
+                .allow()
+                .set_local_pref(200)
+                .exit()
+                .build(),
+            // unreachable, since the previous item matches all remaining routes.
+            RouteMapBuilder::new()
+                .order(30)
+                .allow()
+                .match_prefix(prefixes[2])
+                .build(),
+        ] {
+            net.set_bgp_route_map(*R1, *E1, Incoming, map).unwrap();
+        }
+        net.set_bgp_route_map(
+            *R1,
+            *R2,
+            Outgoing,
+            RouteMapBuilder::new().order(10).allow().set_med(5).build(),
+        )
+        .unwrap();
+
+        net.advertise_external_route(*E1, prefixes[0], [65101], None, [666].map(Community::from))
+            .unwrap();
+        net.advertise_external_route(*E1, prefixes[1], [65101], None, [666].map(Community::from))
+            .unwrap();
+        net.advertise_external_route(*E1, prefixes[2], [65101], None, None)
+            .unwrap();
+
+        let filtered = |net: &Net<P>| {
+            let r1 = &net.get_internal_router(*R1).unwrap().bgp;
+            prefixes
+                .iter()
+                .filter(|p| {
+                    r1.get_known_routes(**p)
+                        .unwrap()
+                        .iter()
+                        .all(|e| e.from_id != *E1)
+                })
+                .count()
+        };
+
+        let hits = net.route_map_hits(*R1, *E1, Incoming).unwrap();
+        assert_eq!(hits, vec![(10, 2), (20, 1), (30, 0)]);
+        assert_eq!(hits[0].1, filtered(&net));
+        assert_eq!(net.route_map_hits(*R1, *R2, Outgoing), Ok(vec![(10, 1)]));
+        assert_eq!(net.route_map_hits(*R1, *R2, Incoming), Ok(vec![]));
+
+        // the counters are recomputed after the network converges again.
+        net.withdraw_external_route(*E1, prefixes[1]).unwrap();
+        net.advertise_external_route(*E1, prefixes[0], [65101], None, None)
+            .unwrap();
+        let hits = net.route_map_hits(*R1, *E1, Incoming).unwrap();
+        assert_eq!(hits, vec![(10, 0), (20, 2), (30, 0)]);
+        assert_eq!(net.route_map_hits(*R1, *R2, Outgoing), Ok(vec![(10, 2)]));
+        assert_eq!(
+            net.route_map_hits(*E1, *R1, Incoming),
+            Err(NetworkError::DeviceIsExternalRouter(*E1))
+        );
+    }
+
+    #[test]
+    fn establish_sessions_in_order<P: Prefix>() {
+        let p0 = P::from(0);
+        let p1 = P::from(1);
+        let sessions = [(*E1, *R1), (*R1, *R2), (*R2, *E2)];
+        let selected = |net: &Net<P>| {
+            net.internal_routers()
+                .flat_map(|r| {
+                    [p0, p1].map(|p| (r.router_id(), p, r.bgp.get_route(p).map(|e| e.from_id)))
+                })
+                .sorted()
+                .collect::<Vec<_>>()
+        };
+
+        // A safe policy converges to the same state for any order.
+        let mut net = get_test_net::<P>();
+        net.advertise_external_route(*E1, p0, [65101, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(*E2, p0, [65102, 65103, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(*E2, p1, [65102, 200], None, None)
+            .unwrap();
+        let expected = selected(&net);
+        assert_eq!(paths(&net, *R2, p0), vec![vec![*R2, *R1, *E1]]);
+        for order in sessions.iter().copied().permutations(3) {
+            let mut n = net.clone();
+            n.establish_sessions_in_order(&order).unwrap();
+            assert_eq!(selected(&n), expected);
+        }
+
+        // Sessions that are not configured cannot be established.
+        assert_eq!(
+            net.clone().establish_sessions_in_order(&[(*E1, *R2)]),
+            Err(NetworkError::DeviceError(DeviceError::NoBgpSession(*R2)))
+        );
+
+        // In the DISAGREE gadget, both routers prefer the route from the other. The outcome
+        // depends on which external session comes up first.
+        let mut net = get_test_net::<P>();
+        for (r, other) in [(*R1, *R2), (*R2, *R1)] {
+            net.set_bgp_route_map(
+                r,
+                other,
+                Incoming,
+                RouteMapBuilder::new()
+                    .order(10)
+                    .allow()
+                    .set_local_pref(200)
+                    .build(),
+            )
+            .unwrap();
+        }
+        net.advertise_external_route(*E1, p0, [65101, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(*E2, p0, [65102, 100], None, None)
+            .unwrap();
+
+        let mut n = net.clone();
+        n.establish_sessions_in_order(&sessions).unwrap();
+        assert_eq!(paths(&n, *R1, p0), vec![vec![*R1, *E1]]);
+        assert_eq!(paths(&n, *R2, p0), vec![vec![*R2, *R1, *E1]]);
+
+        let mut n = net.clone();
+        n.establish_sessions_in_order(&[(*R2, *E2), (*R1, *R2), (*E1, *R1)])
+            .unwrap();
+        assert_eq!(paths(&n, *R1, p0), vec![vec![*R1, *R2, *E2]]);
+        assert_eq!(paths(&n, *R2, p0), vec![vec![*R2, *E2]]);
+    }
+
+    #[test]
+    fn find_alternate_stable_states<P: Prefix>() {
+        let p = P::from(0);
+
+        // A safe policy has only a single stable state.
+        let mut net = get_test_net::<P>();
+        net.advertise_external_route(*E1, p, [65101, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(*E2, p, [65102, 65103, 100], None, None)
+            .unwrap();
+        assert!(net.find_alternate_stable_states(p).unwrap().is_empty());
+
+        // The 3/4 wedgie: E1 is the primary and E3 the backup link of the same customer. R2 prefers
+        // routes from its customer R3 over routes from its peer R1, while R3 only uses the backup
+        // if it has no other route.
+        //
+        // E1 ---- R1 ---- R2 ---- R3 ---- E3
+        let mut net = Net::<P>::default();
+        let e1 = net.add_external_router("E1", AsId(65101));
+        let r1 = net.add_router("R1");
+        let r2 = net.add_router("R2");
+        let r3 = net.add_router("R3");
+        let e3 = net.add_external_router("E3", AsId(65101));
+        for (a, b) in [(e1, r1), (r1, r2), (r2, r3), (r3, e3)] {
+            net.add_link(a, b).unwrap();
+        }
+        for (a, b) in [(r1, r2), (r2, r3)] {
+            net.set_link_weight(a, b, 1.0).unwrap();
+            net.set_link_weight(b, a, 1.0).unwrap();
+        }
+        net.set_bgp_session(e1, r1, Some(EBgp)).unwrap();
+        net.set_bgp_session(e3, r3, Some(EBgp)).unwrap();
+        net.set_bgp_session(r2, r1, Some(IBgpClient)).unwrap();
+        net.set_bgp_session(r2, r3, Some(IBgpClient)).unwrap();
+        for (r, neighbor, lp) in [(r1, e1, 300), (r2, r1, 100), (r2, r3, 200), (r3, e3, 50)] {
+            net.set_bgp_route_map(
+                r,
+                neighbor,
+                Incoming,
+                RouteMapBuilder::new()
+                    .order(10)
+                    .allow()
+                    .set_local_pref(lp)
+                    .build(),
+            )
+            .unwrap();
+        }
+        net.advertise_external_route(e1, p, [65101, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(e3, p, [65101, 100], None, None)
+            .unwrap();
+        // some orders oscillate forever, as R2 and R3 keep switching between both routes.
+        net.set_msg_limit(Some(1000));
+
+        // intended state: everyone uses the primary link.
+        assert_eq!(paths(&net, r3, p), vec![vec![r3, r2, r1, e1]]);
+        let old = net.clone();
+
+        // unintended state: R3 uses the backup, and R2 prefers it over the primary.
+        let alt = net.find_alternate_stable_states(p).unwrap();
+        assert_eq!(net, old);
+        assert_eq!(alt.len(), 1);
+        assert_eq!(alt[0].verify_fixed_point(), vec![]);
+        assert_eq!(paths(&alt[0], r1, p), vec![vec![r1, e1]]);
+        assert_eq!(paths(&alt[0], r2, p), vec![vec![r2, r3, e3]]);
+        assert_eq!(paths(&alt[0], r3, p), vec![vec![r3, e3]]);
+    }
+
+    #[test]
+    fn optimal_route_reflection<P: Prefix>() {
+        let p = P::from(0);
+        //   E1      E2
+        //   |       |
+        //   R1      R2
+        //    \1   3/ \1
+        //      RR --3-- C
+        let mut net = Net::<P>::default();
+        let e1 = net.add_external_router("E1", AsId(65101));
+        let e2 = net.add_external_router("E2", AsId(65102));
+        let r1 = net.add_router("R1");
+        let r2 = net.add_router("R2");
+        let rr = net.add_router("RR");
+        let c = net.add_router("C");
+        net.add_links_from([(e1, r1), (e2, r2), (r1, rr), (r2, rr), (r2, c), (rr, c)])
+            .unwrap();
+        for (a, b, w) in [(r1, rr, 1.0), (r2, rr, 3.0), (r2, c, 1.0), (rr, c, 3.0)] {
+            net.set_link_weight(a, b, w).unwrap();
+            net.set_link_weight(b, a, w).unwrap();
+        }
+        net.set_bgp_session(e1, r1, Some(EBgp)).unwrap();
+        net.set_bgp_session(e2, r2, Some(EBgp)).unwrap();
+        let mut full_mesh = net.clone();
+        for client in [r1, r2, c] {
+            net.set_bgp_session(rr, client, Some(IBgpClient)).unwrap();
+        }
+        net.advertise_external_route(e1, p, [65101, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(e2, p, [65102, 100], None, None)
+            .unwrap();
+
+        // the client uses the egress closest to the route reflector
+        assert_eq!(paths(&net, rr, p), vec![vec![rr, r1, e1]]);
+        assert_eq!(paths(&net, c, p), vec![vec![c, rr, r1, e1]]);
+
+        // with ORR, the client uses the egress closest to itself, as in a full mesh
+        assert_eq!(net.set_orr(rr, c, Some(c)), Ok(None));
+        assert_eq!(net.get_internal_router(rr).unwrap().bgp.get_orr(c), Some(c));
+        assert_eq!(paths(&net, rr, p), vec![vec![rr, r1, e1]]);
+        assert_eq!(paths(&net, c, p), vec![vec![c, r2, e2]]);
+
+        full_mesh
+            .set_bgp_session_from(
+                [(r1, r2), (r1, rr), (r1, c), (r2, rr), (r2, c), (rr, c)]
+                    .map(|(a, b)| (a, b, Some(IBgpPeer))),
+            )
+            .unwrap();
+        full_mesh
+            .advertise_external_route(e1, p, [65101, 100], None, None)
+            .unwrap();
+        full_mesh
+            .advertise_external_route(e2, p, [65102, 100], None, None)
+            .unwrap();
+        assert_eq!(paths(&full_mesh, c, p), paths(&net, c, p));
+
+        // the route reflector follows changes in the IGP view of the client
+        net.set_link_weight(c, r2, 10.0).unwrap();
+        assert_eq!(paths(&net, c, p), vec![vec![c, rr, r1, e1]]);
+        net.set_link_weight(c, r2, 1.0).unwrap();
+        assert_eq!(paths(&net, c, p), vec![vec![c, r2, e2]]);
+
+        // disabling ORR restores the original behavior
+        assert_eq!(net.set_orr(rr, c, None), Ok(Some(c)));
+        assert_eq!(paths(&net, c, p), vec![vec![c, rr, r1, e1]]);
+    }
+
+    #[test]
+    fn cluster_id<P: Prefix>() {
+        let p = P::from(0);
+        // E1 -- R1 is a client of both RR1 and RR2, which are iBGP peers. C is also a client of
+        // both route reflectors.
+        let mut net = Net::<P>::default();
+        let e1 = net.add_external_router("E1", AsId(65101));
+        let r1 = net.add_router("R1");
+        let rr1 = net.add_router("RR1");
+        let rr2 = net.add_router("RR2");
+        let c = net.add_router("C");
+        net.add_links_from([
+            (e1, r1),
+            (r1, rr1),
+            (r1, rr2),
+            (rr1, rr2),
+            (rr1, c),
+            (rr2, c),
+        ])
+        .unwrap();
+        net.set_bgp_session(e1, r1, Some(EBgp)).unwrap();
+        net.set_bgp_session(rr1, rr2, Some(IBgpPeer)).unwrap();
+        for rr in [rr1, rr2] {
+            for client in [r1, c] {
+                net.set_bgp_session(rr, client, Some(IBgpClient)).unwrap();
+            }
+        }
+        net.advertise_external_route(e1, p, [65101, 100], None, None)
+            .unwrap();
+
+        let known = |net: &Net<P>, r: RouterId| {
+            let bgp = &net.get_internal_router(r).unwrap().bgp;
+            let mut routes = bgp.get_known_routes(p).unwrap();
+            routes.sort_by_key(|e| e.from_id);
+            routes
+                .into_iter()
+                .map(|e| (e.from_id, e.route.cluster_list))
+                .collect_vec()
+        };
+
+        // by default, each route reflector uses its own index as cluster ID.
+        let id1 = rr1.index() as u32;
+        let id2 = rr2.index() as u32;
+        assert_eq!(
+            net.get_internal_router(rr1).unwrap().bgp.get_cluster_id(),
+            id1
+        );
+        assert_eq!(known(&net, rr2), vec![(r1, vec![]), (rr1, vec![id1])]);
+        assert_eq!(known(&net, c), vec![(rr1, vec![id1]), (rr2, vec![id2])]);
+        assert_eq!(paths(&net, c, p), vec![vec![c, rr1, r1, e1]]);
+
+        // with a shared cluster ID, the route reflectors ignore each other's reflected routes.
+        assert_eq!(net.set_cluster_id(rr1, Some(10)), Ok(None));
+        assert_eq!(net.set_cluster_id(rr2, Some(10)), Ok(None));
+        assert_eq!(known(&net, rr1), vec![(r1, vec![])]);
+        assert_eq!(known(&net, rr2), vec![(r1, vec![])]);
+        assert_eq!(known(&net, c), vec![(rr1, vec![10]), (rr2, vec![10])]);
+        assert_eq!(paths(&net, c, p), vec![vec![c, rr1, r1, e1]]);
+
+        // the ORIGINATOR_ID refers to the client that learned the route over eBGP.
+        let c_route = net
+            .get_internal_router(c)
+            .unwrap()
+            .bgp
+            .get_route(p)
+            .unwrap();
+        assert_eq!(c_route.route.originator_id, Some(r1));
+
+        // removing the cluster ID restores the original behavior.
+        assert_eq!(net.set_cluster_id(rr2, None), Ok(Some(10)));
+        assert_eq!(known(&net, rr1), vec![(r1, vec![]), (rr2, vec![id2])]);
+        assert_eq!(known(&net, rr2), vec![(r1, vec![]), (rr1, vec![10])]);
+
+        // clients do not check the CLUSTER_LIST, even if a cluster ID equals their index.
+        let c_id = c.index() as u32;
+        assert_eq!(net.set_cluster_id(rr1, Some(c_id)), Ok(Some(10)));
+        assert_eq!(net.set_cluster_id(rr2, Some(c_id)), Ok(None));
+        assert_eq!(known(&net, c), vec![(rr1, vec![c_id]), (rr2, vec![c_id])]);
+        assert_eq!(paths(&net, c, p), vec![vec![c, rr1, r1, e1]]);
+    }
+
+    #[test]
+    fn peer_group<P: Prefix>() {
+        let p = P::from(0);
+        //        E1
+        //        |
+        //        RR
+        //      / | \
+        //    C1  C2  C3
+        let mut net = Net::<P>::default();
+        let e1 = net.add_external_router("E1", AsId(65101));
+        let rr = net.add_router("RR");
+        let clients = [
+            net.add_router("C1"),
+            net.add_router("C2"),
+            net.add_router("C3"),
+        ];
+        net.add_link(e1, rr).unwrap();
+        for c in clients {
+            net.add_link(rr, c).unwrap();
+            net.set_link_weight(rr, c, 1.0).unwrap();
+            net.set_link_weight(c, rr, 1.0).unwrap();
+        }
+        net.set_bgp_session(e1, rr, Some(EBgp)).unwrap();
+        net.advertise_external_route(e1, p, [65101, 100], None, None)
+            .unwrap();
+
+        let communities = |net: &Net<P>| {
+            clients
+                .iter()
+                .map(|c| {
+                    net.get_internal_router(*c)
+                        .unwrap()
+                        .bgp
+                        .get_route(p)
+                        .map(|r| r.route.communities.iter().copied().collect_vec())
+                })
+                .collect_vec()
+        };
+
+        let mut template = BgpPeerGroup::new(IBgpClient);
+        template.route_maps_out = vec![RouteMapBuilder::new()
+            .order(10)
+            .allow()
+            .set_community(10)
+            .build()];
+        assert_eq!(net.define_peer_group("clients", template.clone()), Ok(None));
+        assert_eq!(
+            net.add_session_from_group(rr, clients[0], "unknown"),
+            Err(NetworkError::UnknownPeerGroup("unknown".to_string()))
+        );
+        for c in clients {
+            net.add_session_from_group(rr, c, "clients").unwrap();
+            assert_eq!(net.get_peer_group_of(rr, c), Some("clients"));
+            assert_eq!(
+                net.get_internal_router(rr).unwrap().bgp.get_session_type(c),
+                Some(IBgpClient)
+            );
+        }
+        assert_eq!(communities(&net), vec![Some(vec![Community::from(10)]); 3]);
+
+        // changing the export route-map of the peer-group changes the advertisements to all members.
+        let old = template.clone();
+        template.route_maps_out = vec![RouteMapBuilder::new()
+            .order(10)
+            .allow()
+            .set_community(20)
+            .build()];
+        assert_eq!(
+            net.define_peer_group("clients", template.clone()),
+            Ok(Some(old))
+        );
+        assert_eq!(communities(&net), vec![Some(vec![Community::from(20)]); 3]);
+        for c in clients {
+            assert_eq!(
+                net.get_internal_router(rr)
+                    .unwrap()
+                    .bgp
+                    .get_route_maps(c, Outgoing),
+                template.route_maps_out.as_slice()
+            );
+        }
+
+        template.route_maps_out = vec![RouteMapBuilder::new().order(10).deny().build()];
+        net.define_peer_group("clients", template).unwrap();
+        assert_eq!(communities(&net), vec![None; 3]);
+
+        // removing the session also removes it from the peer-group.
+        net.set_bgp_session(rr, clients[0], None).unwrap();
+        assert_eq!(net.get_peer_group_of(rr, clients[0]), None);
+        net.define_peer_group("clients", BgpPeerGroup::new(IBgpClient))
+            .unwrap();
+        assert_eq!(communities(&net), vec![None, Some(vec![]), Some(vec![])]);
+    }
+
+    #[test]
+    fn policy_subgraph<P: Prefix>() {
+        let p = P::from(0);
+        let mut net = get_test_net::<P>();
+        // add an unrelated branch: R3 only peers with R2, and hence, it never learns routes from R1.
+        let r3 = net.add_router("R3");
+        let e3 = net.add_external_router("E3", AsId(65103));
+        net.add_link(*R2, r3).unwrap();
+        net.add_link(r3, e3).unwrap();
+        net.set_link_weight(*R2, r3, 1.0).unwrap();
+        net.set_link_weight(r3, *R2, 1.0).unwrap();
+        net.set_bgp_session(*R2, r3, Some(IBgpPeer)).unwrap();
+        net.set_bgp_session(r3, e3, Some(EBgp)).unwrap();
+        for (r, n) in [(*R1, *E1), (*R2, *E2), (r3, e3)] {
+            net.set_bgp_route_map(
+                r,
+                n,
+                Incoming,
+                RouteMapBuilder::new()
+                    .order(10)
+                    .allow()
+                    .set_local_pref(50)
+                    .build(),
+            )
+            .unwrap();
+        }
+        net.set_bgp_route_map(
+            *R2,
+            *E2,
+            Outgoing,
+            RouteMapBuilder::new().order(10).allow().set_med(10).build(),
+        )
+        .unwrap();
+
+        net.advertise_external_route(*E1, p, [65101, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(e3, P::from(1), [65103, 200], None, None)
+            .unwrap();
+
+        let sub = net.policy_subgraph(p);
+        assert_eq!(sub.prefix, p);
+        assert_eq!(sub.routers, HashSet::from([*E1, *R1, *R2]));
+        assert_eq!(sub.sessions, HashSet::from([(*E1, *R1), (*R1, *R2)]));
+        assert_eq!(sub.route_maps, HashSet::from([(*R1, *E1, Incoming)]));
+        assert!(sub.contains_session(*R2, *R1));
+        assert!(!sub.contains_router(r3));
+        assert!(!sub.contains_router(*E2));
+
+        // a session that filters the route is still part of the subgraph.
+        net.set_bgp_route_map(
+            *R1,
+            *R2,
+            Outgoing,
+            RouteMapBuilder::new().order(10).deny().build(),
+        )
+        .unwrap();
+        assert_eq!(paths(&net, *R2, p), Vec::<Vec<RouterId>>::new());
+        let sub = net.policy_subgraph(p);
+        assert_eq!(sub.routers, HashSet::from([*E1, *R1, *R2]));
+        assert_eq!(
+            sub.route_maps,
+            HashSet::from([(*R1, *E1, Incoming), (*R1, *R2, Outgoing)])
+        );
+
+        // an unknown prefix has an empty subgraph.
+        assert!(net.policy_subgraph(P::from(2)).routers.is_empty());
+    }
+
+    #[test]
+    fn attribute_propagation<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        let e3 = net.add_external_router("E3", AsId(65103));
+        net.add_link(*R2, e3).unwrap();
+        net.set_bgp_session(*R2, e3, Some(EBgp)).unwrap();
+
+        let p = P::from(0);
+        net.advertise_external_route(*E1, p, [65101, 100], Some(5), [10, 20].map(Community::from))
+            .unwrap();
+
+        let sent = |net: &Net<P>, neighbor: RouterId| {
+            net.get_internal_router(*R2)
+                .unwrap()
+                .bgp
+                .get_rib_out()
+                .get(&p)
+                .and_then(|x| x.get(&neighbor))
+                .map(|e| {
+                    (
+                        e.route.med,
+                        e.route.communities.iter().copied().collect::<Vec<_>>(),
+                    )
+                })
+                .unwrap()
+        };
+
+        // by default, communities are propagated over eBGP, but the MED is not.
+        assert_eq!(
+            sent(&net, *E2),
+            (None, vec![Community::from(10), Community::from(20)])
+        );
+        assert_eq!(
+            sent(&net, e3),
+            (None, vec![Community::from(10), Community::from(20)])
+        );
+        assert_eq!(
+            net.get_internal_router(*R2)
+                .unwrap()
+                .bgp
+                .get_attribute_propagation(*R1),
+            Some(AttributePropagation::for_session(IBgpPeer))
+        );
+
+        // strip communities towards E2 only
+        let strip = AttributePropagation {
+            communities: false,
+            ..AttributePropagation::for_session(EBgp)
+        };
+        assert_eq!(
+            net.set_attribute_propagation(*R2, *E2, Some(strip)),
+            Ok(None)
+        );
+        assert_eq!(sent(&net, *E2), (None, vec![]));
+        assert_eq!(
+            sent(&net, e3),
+            (None, vec![Community::from(10), Community::from(20)])
+        );
+
+        // carry the MED towards E3
+        let carry_med = AttributePropagation {
+            med: true,
+            ..AttributePropagation::for_session(EBgp)
+        };
+        net.set_attribute_propagation(*R2, e3, Some(carry_med))
+            .unwrap();
+        assert_eq!(sent(&net, *E2), (None, vec![]));
+        assert_eq!(
+            sent(&net, e3),
+            (Some(5), vec![Community::from(10), Community::from(20)])
+        );
+
+        // restore the default
+        assert_eq!(
+            net.set_attribute_propagation(*R2, *E2, None),
+            Ok(Some(strip))
+        );
+        assert_eq!(
+            sent(&net, *E2),
+            (None, vec![Community::from(10), Community::from(20)])
+        );
+    }
+
+    #[test]
+    fn community_prepend_map<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        let e3 = net.add_external_router("E3", AsId(65103));
+        net.add_link(*R2, e3).unwrap();
+        net.set_bgp_session(*R2, e3, Some(EBgp)).unwrap();
+
+        // the customer E1 requests prepending once with community 1, and twice with community 2.
+        // The action only applies towards E2.
+        net.set_community_prepend_map(
+            *R2,
+            *E2,
+            HashMap::from([(Community::from(1), 1), (Community::from(2), 2)]),
+        )
+        .unwrap();
+
+        let p = P::from(0);
+        let sent = |net: &Net<P>, neighbor: RouterId| {
+            net.get_internal_router(*R2)
+                .unwrap()
+                .bgp
+                .get_rib_out()
+                .get(&p)
+                .and_then(|x| x.get(&neighbor))
+                .map(|e| e.route.as_path.clone())
+                .unwrap()
+        };
+
+        // untagged routes are not prepended.
+        net.advertise_external_route(*E1, p, [65101], None, None)
+            .unwrap();
+        assert_eq!(sent(&net, *E2), vec![INTERNAL_AS, AsId(65101)]);
+        assert_eq!(sent(&net, e3), vec![INTERNAL_AS, AsId(65101)]);
+
+        // a tagged route is only prepended on the targeted session.
+        net.advertise_external_route(*E1, p, [65101], None, [2].map(Community::from))
+            .unwrap();
+        assert_eq!(
+            sent(&net, *E2),
+            vec![INTERNAL_AS, INTERNAL_AS, INTERNAL_AS, AsId(65101)]
+        );
+        assert_eq!(sent(&net, e3), vec![INTERNAL_AS, AsId(65101)]);
+
+        // the map can be replaced and removed.
+        net.set_community_prepend_map(*R2, *E2, HashMap::from([(Community::from(2), 1)]))
+            .unwrap();
+        assert_eq!(sent(&net, *E2), vec![INTERNAL_AS, INTERNAL_AS, AsId(65101)]);
+        net.set_community_prepend_map(*R2, *E2, HashMap::new())
+            .unwrap();
+        assert_eq!(sent(&net, *E2), vec![INTERNAL_AS, AsId(65101)]);
+        assert_eq!(
+            net.get_internal_router(*R2)
+                .unwrap()
+                .bgp
+                .get_route_maps(*E2, Outgoing),
+            &[]
+        );
+
+        // user items in the reserved range are neither removed nor overwritten.
+        let user_item = RouteMapBuilder::new()
+            .order_sgn(i16::MIN)
+            .allow()
+            .match_community(2)
+            .set_med(10)
+            .continue_next()
+            .build();
+        net.set_bgp_route_map(*R2, *E2, Outgoing, user_item.clone())
+            .unwrap();
+        assert_eq!(
+            net.set_community_prepend_map(*R2, *E2, HashMap::from([(Community::from(2), 1)])),
+            Err(NetworkError::ReservedRouteMapOrder(*E2, i16::MIN))
+        );
+        assert_eq!(
+            net.get_internal_router(*R2)
+                .unwrap()
+                .bgp
+                .get_route_maps(*E2, Outgoing),
+            &[user_item]
+        );
+    }
+
+    #[test]
+    fn well_known_communities<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        let p = P::from(0);
+        let known = |net: &Net<P>, router: RouterId| {
+            net.get_internal_router(router)
+                .unwrap()
+                .bgp
+                .get_route(p)
+                .is_some()
+        };
+        let sent_to_e2 = |net: &Net<P>| {
+            net.get_internal_router(*R2)
+                .unwrap()
+                .bgp
+                .get_rib_out()
+                .get(&p)
+                .is_some_and(|x| x.contains_key(&*E2))
+        };
+
+        // NO_EXPORT routes are propagated over iBGP, but not over eBGP.
+        net.advertise_external_route(*E1, p, [65101], None, [NO_EXPORT])
+            .unwrap();
+        assert!(known(&net, *R1));
+        assert!(known(&net, *R2));
+        assert!(!sent_to_e2(&net));
+
+        // NO_ADVERTISE routes are not advertised at all.
+        net.advertise_external_route(*E1, p, [65101], None, [NO_ADVERTISE])
+            .unwrap();
+        assert!(known(&net, *R1));
+        assert!(!known(&net, *R2));
+        assert!(!sent_to_e2(&net));
+
+        // without any well-known community, the route is advertised to E2.
+        net.advertise_external_route(*E1, p, [65101], None, [])
+            .unwrap();
+        assert!(known(&net, *R2));
+        assert!(sent_to_e2(&net));
+
+        // a route-map can tag routes with NO_EXPORT.
+        net.set_bgp_route_map(
+            *R1,
+            *E1,
+            Incoming,
+            RouteMapBuilder::new()
+                .order(10)
+                .allow()
+                .set_community(NO_EXPORT)
+                .build(),
+        )
+        .unwrap();
+        assert!(known(&net, *R2));
+        assert!(!sent_to_e2(&net));
+        net.remove_bgp_route_map(*R1, *E1, Incoming, 10).unwrap();
+        assert!(sent_to_e2(&net));
+
+        // communities are formatted as `asn:value`.
+        net.advertise_external_route(*E1, p, [65101], None, [NO_EXPORT, Community(100, 10)])
+            .unwrap();
+        let route = &net
+            .get_internal_router(*R2)
+            .unwrap()
+            .bgp
+            .get_route(p)
+            .unwrap()
+            .route;
+        assert!(route
+            .fmt(&net)
+            .ends_with(", community: 100:10;65535:65281 }"));
+    }
+
+    #[test]
+    fn legacy_community_is_not_well_known<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        let p = P::from(0);
+
+        // community `0` is `0:0`, not GRACEFUL_SHUTDOWN, and does not lower the local-pref.
+        net.set_bgp_route_map(
+            *R1,
+            *E1,
+            Incoming,
+            RouteMapBuilder::new()
+                .order(10)
+                .allow()
+                .set_community(0)
+                .build(),
+        )
+        .unwrap();
+        net.advertise_external_route(*E1, p, [65101], None, [])
+            .unwrap();
+        let route = &net
+            .get_internal_router(*R1)
+            .unwrap()
+            .bgp
+            .get_route(p)
+            .unwrap()
+            .route;
+        assert!(route.communities.contains(&Community(0, 0)));
+        assert!(!route.communities.contains(&GRACEFUL_SHUTDOWN));
+        assert_eq!(route.local_pref, Some(100));
+    }
+
+    #[test]
+    fn route_tag<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        let e3 = net.add_external_router("E3", AsId(65103));
+        net.add_link(*R2, e3).unwrap();
+        net.set_bgp_session(*R2, e3, Some(EBgp)).unwrap();
+
+        // R2 tags all routes from E2 on import, and does not export tagged routes to E3.
+        net.set_bgp_route_map(
+            *R2,
+            *E2,
+            Incoming,
+            RouteMapBuilder::new().order(10).allow().set_tag(7).build(),
+        )
+        .unwrap();
+        net.set_bgp_route_map(
+            *R2,
+            e3,
+            Outgoing,
+            RouteMapBuilder::new().order(10).deny().match_tag(7).build(),
+        )
+        .unwrap();
+
+        let p_e1 = P::from(0);
+        let p_e2 = P::from(1);
+        net.advertise_external_route(*E1, p_e1, [65101], None, None)
+            .unwrap();
+        net.advertise_external_route(*E2, p_e2, [65102], None, None)
+            .unwrap();
+
+        let r2 = &net.get_internal_router(*R2).unwrap().bgp;
+        assert_eq!(r2.get_route(p_e2).unwrap().tag, 7);
+        assert_eq!(r2.get_route(p_e1).unwrap().tag, 0);
+        let sent = |p: P, neighbor: RouterId| {
+            r2.get_rib_out()
+                .get(&p)
+                .is_some_and(|x| x.contains_key(&neighbor))
+        };
+        assert!(sent(p_e1, e3));
+        assert!(!sent(p_e2, e3));
+        assert!(sent(p_e1, *E2));
+        assert!(sent(p_e2, *R1));
+
+        // the tag never leaves R2.
+        let r1 = &net.get_internal_router(*R1).unwrap().bgp;
+        assert_eq!(r1.get_route(p_e2).unwrap().tag, 0);
+        assert_eq!(paths(&net, *R1, p_e2), vec![vec![*R1, *R2, *E2]]);
+    }
+
+    #[test]
+    fn local_pref_from_igp_cost<P: Prefix>() {
+        // R0 is connected to both R1 (cost 1) and R2 (cost 5, but reached via R1 with cost 2), and
+        // has an iBGP session with both. R1 and R2 only know their own eBGP route.
+        let mut net = get_test_net::<P>();
+        net.set_bgp_session(*R1, *R2, None).unwrap();
+        let r0 = net.add_router("R0");
+        net.add_link(r0, *R1).unwrap();
+        net.add_link(r0, *R2).unwrap();
+        net.set_link_weight(r0, *R1, 1.0).unwrap();
+        net.set_link_weight(*R1, r0, 1.0).unwrap();
+        net.set_link_weight(r0, *R2, 5.0).unwrap();
+        net.set_link_weight(*R2, r0, 5.0).unwrap();
+        net.set_bgp_session(r0, *R1, Some(IBgpPeer)).unwrap();
+        net.set_bgp_session(r0, *R2, Some(IBgpPeer)).unwrap();
+        for r in [*R1, *R2] {
+            net.set_bgp_route_map(
+                r0,
+                r,
+                Incoming,
+                RouteMapBuilder::new()
+                    .order(10)
+                    .allow()
+                    .set_localpref_from_igp_cost(200, 10)
+                    .build(),
+            )
+            .unwrap();
+        }
+
+        // The route via E2 has a shorter AS path.
+        let p = P::from(0);
+        let lp = |net: &Net<P>, from: RouterId| {
+            let r0_bgp = &net.get_internal_router(r0).unwrap().bgp;
+            r0_bgp
+                .get_known_routes(p)
+                .unwrap()
+                .into_iter()
+                .find(|e| e.from_id == from)
+                .and_then(|e| e.route.local_pref)
+        };
+        net.advertise_external_route(*E1, p, [65101, 1, 2], None, None)
+            .unwrap();
+        net.advertise_external_route(*E2, p, [65102], None, None)
+            .unwrap();
+
+        assert_eq!(lp(&net, *R1), Some(190));
+        assert_eq!(lp(&net, *R2), Some(180));
+        assert_eq!(paths(&net, r0, p), vec![vec![r0, *R1, *E1]]);
+
+        // R2 becomes closer than R1 (which is now reached via R2 with cost 2).
+        net.set_link_weight(r0, *R1, 10.0).unwrap();
+        net.set_link_weight(r0, *R2, 1.0).unwrap();
+        assert_eq!(lp(&net, *R1), Some(180));
+        assert_eq!(lp(&net, *R2), Some(190));
+        assert_eq!(paths(&net, r0, p), vec![vec![r0, *R2, *E2]]);
+    }
+
+    #[test]
+    fn bgp_multipath<P: Prefix>() {
+        // R0 is connected to both R1 and R2 with equal cost, and has an iBGP session with both.
+        let mut net = get_test_net::<P>();
+        let r0 = net.add_router("R0");
+        net.add_link(r0, *R1).unwrap();
+        net.add_link(r0, *R2).unwrap();
+        for r in [*R1, *R2] {
+            net.set_link_weight(r0, r, 1.0).unwrap();
+            net.set_link_weight(r, r0, 1.0).unwrap();
+            net.set_bgp_session(r0, r, Some(IBgpPeer)).unwrap();
+        }
+
+        let p = P::from(0);
+        net.advertise_external_route(*E1, p, [65101, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(*E2, p, [65102, 100], None, None)
+            .unwrap();
+
+        let via_r1 = vec![r0, *R1, *E1];
+        let via_r2 = vec![r0, *R2, *E2];
+        let r0_paths = |net: &Net<P>| paths(net, r0, p).into_iter().sorted().collect_vec();
+        let single = r0_paths(&net);
+        assert_eq!(single.len(), 1);
+
+        // both routes are equally good up to the BGP router-id.
+        assert!(!net.set_bgp_multipath(r0, true).unwrap());
+        assert_eq!(r0_paths(&net), vec![via_r1.clone(), via_r2.clone()]);
+        assert_eq!(
+            net.get_forwarding_state().get_next_hops(r0, p),
+            &[*R1, *R2].into_iter().sorted().collect_vec()
+        );
+        // the selected route does not change, and other routers prefer their eBGP route.
+        let best = net.get_internal_router(r0).unwrap().bgp.get(p);
+        assert_eq!(best.map(|nh| vec![r0, nh]), Some(single[0][..2].to_vec()));
+        assert_eq!(paths(&net, *R1, p), vec![vec![*R1, *E1]]);
+
+        // routes from different neighboring ASes are not combined anymore.
+        assert!(!net.set_bgp_multipath_same_as(r0, true).unwrap());
+        assert_eq!(r0_paths(&net), single);
+        net.advertise_external_route(*E2, p, [65101, 100], None, None)
+            .unwrap();
+        assert_eq!(r0_paths(&net), vec![via_r1.clone(), via_r2.clone()]);
+
+        // routes with a higher IGP cost are not used.
+        net.set_link_weight(r0, *R2, 2.0).unwrap();
+        assert_eq!(r0_paths(&net), vec![via_r1.clone()]);
+        net.set_link_weight(r0, *R2, 1.0).unwrap();
+
+        // disabling multipath restores the single path.
+        assert!(net.set_bgp_multipath(r0, false).unwrap());
+        assert_eq!(r0_paths(&net).len(), 1);
+    }
+
+    #[test]
+    fn route_damping<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+
+        // R1 only dampens customer routes from E1, marked with community 1.
+        net.set_bgp_route_map(
+            *R1,
+            *E1,
+            Incoming,
+            RouteMapBuilder::new()
+                .order(10)
+                .allow()
+                .match_community(1)
+                .dampen()
+                .build(),
+        )
+        .unwrap();
+        assert_eq!(net.set_route_damping(*R1, Some(2)), Ok(None));
+
+        let p = P::from(0);
+        let q = P::from(1);
+        for _ in 0..2 {
+            net.advertise_external_route(*E1, p, [65101], None, [1].map(Community::from))
+                .unwrap();
+            net.advertise_external_route(*E1, q, [65101], None, None)
+                .unwrap();
+            net.withdraw_external_route(*E1, p).unwrap();
+            net.withdraw_external_route(*E1, q).unwrap();
+        }
+        net.advertise_external_route(*E1, p, [65101], None, [1].map(Community::from))
+            .unwrap();
+        net.advertise_external_route(*E1, q, [65101], None, None)
+            .unwrap();
+
+        // p is suppressed after two flaps, while q keeps propagating.
+        let r1 = &net.get_internal_router(*R1).unwrap().bgp;
+        assert_eq!(r1.get_flaps(p, *E1), 2);
+        assert_eq!(r1.get_flaps(q, *E1), 0);
+        assert!(r1.is_suppressed(p, *E1));
+        assert!(r1.get_route(p).is_none());
+        assert!(net
+            .get_internal_router(*R2)
+            .unwrap()
+            .bgp
+            .get_route(p)
+            .is_none());
+        assert_eq!(paths(&net, *R2, q), vec![vec![*R2, *R1, *E1]]);
+
+        // clearing the flaps makes p available again.
+        net.clear_route_damping(*R1).unwrap();
+        assert!(!net
+            .get_internal_router(*R1)
+            .unwrap()
+            .bgp
+            .is_suppressed(p, *E1));
+        assert_eq!(paths(&net, *R2, p), vec![vec![*R2, *R1, *E1]]);
+    }
+
+    #[test]
+    fn ebgp_multihop<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        let p = P::from(0);
+        net.set_bgp_session(*R1, *R2, None).unwrap();
+        net.advertise_external_route(*E1, p, [65101], None, None)
+            .unwrap();
+
+        // E1 is two hops away from R2, so a TTL of 1 does not suffice.
+        net.add_ebgp_multihop_session(*R2, *E1, 1).unwrap();
+        let r2 = &net.get_internal_router(*R2).unwrap().bgp;
+        assert_eq!(r2.get_ebgp_multihop(*E1), Some(1));
+        assert_eq!(r2.get_session_type(*E1), None);
+        assert!(r2.get_route(p).is_none());
+
+        net.add_ebgp_multihop_session(*R2, *E1, 2).unwrap();
+        let r2 = &net.get_internal_router(*R2).unwrap().bgp;
+        assert_eq!(r2.get_session_type(*E1), Some(EBgp));
+        let route = r2.get_route(p).unwrap();
+        assert_eq!(route.from_id, *E1);
+        assert_eq!(route.route.next_hop, *E1);
+        assert!(route.igp_cost.unwrap().into_inner() > 0.0);
+        assert_eq!(paths(&net, *R2, p), vec![vec![*R2, *R1, *E1]]);
+
+        // cutting the path towards E1 invalidates the route.
+        net.remove_link(*R1, *R2).unwrap();
+        let r2 = &net.get_internal_router(*R2).unwrap().bgp;
+        assert_eq!(r2.get_session_type(*E1), None);
+        assert!(r2.get_route(p).is_none());
+
+        // the session comes back once E1 is reachable again.
+        net.add_link(*R1, *R2).unwrap();
+        net.set_link_weight(*R1, *R2, 1.0).unwrap();
+        net.set_link_weight(*R2, *R1, 1.0).unwrap();
+        assert_eq!(paths(&net, *R2, p), vec![vec![*R2, *R1, *E1]]);
+
+        // external routers cannot be the local end of a multihop session.
+        assert_eq!(
+            net.add_ebgp_multihop_session(*E2, *E1, 2),
+            Err(NetworkError::DeviceIsExternalRouter(*E2))
+        );
+    }
+
+    #[test]
+    fn vrf<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        let p = P::from(0);
+        let q = P::from(1);
+
+        // R1 hosts two VRFs: red towards E1, and blue towards R2 (and E2 behind it).
+        net.add_vrf(*R1, "red").unwrap();
+        net.add_vrf(*R1, "blue").unwrap();
+        net.set_vrf_neighbor(*R1, "red", *E1).unwrap();
+        net.set_vrf_neighbor(*R1, "blue", *R2).unwrap();
+        assert_eq!(
+            net.set_vrf_neighbor(*R1, "green", *E1),
+            Err(NetworkError::DeviceError(DeviceError::VrfNotFound(
+                "green".to_string()
+            )))
+        );
+
+        net.advertise_external_route(*E1, p, [65101, 65103], None, None)
+            .unwrap();
+        net.advertise_external_route(*E2, p, [65102], None, None)
+            .unwrap();
+        net.advertise_external_route(*E1, q, [65101], None, None)
+            .unwrap();
+
+        // both VRFs forward p to different egresses, while the global table has no route.
+        assert_eq!(
+            net.get_vrf_paths(*R1, p, Some("red")),
+            Ok(vec![vec![*R1, *E1]])
+        );
+        assert_eq!(
+            net.get_vrf_paths(*R1, p, Some("blue")),
+            Ok(vec![vec![*R1, *R2, *E2]])
+        );
+        assert_eq!(
+            net.get_vrf_paths(*R1, p, None),
+            Err(NetworkError::ForwardingBlackHole(vec![*R1]))
+        );
+        assert_eq!(paths(&net, *R2, p), vec![vec![*R2, *E2]]);
+        assert_eq!(
+            net.get_vrf_paths(*R1, q, Some("blue")),
+            Err(NetworkError::ForwardingBlackHole(vec![*R1]))
+        );
+
+        // leak the routes of red into blue.
+        net.set_vrf_route_targets(*R1, "red", [], [100]).unwrap();
+        net.set_vrf_route_targets(*R1, "blue", [100], []).unwrap();
+        let r1 = &net.get_internal_router(*R1).unwrap().bgp;
+        let leaked = r1.get_vrf_route("blue", q).unwrap().unwrap();
+        assert_eq!(leaked.from_id, *E1);
+        assert!(leaked.route.communities.contains(&Community::from(100)));
+        assert!(r1.get_vrf_route("red", q).unwrap().is_some());
+        assert_eq!(
+            net.get_vrf_paths(*R1, q, Some("blue")),
+            Ok(vec![vec![*R1, *E1]])
+        );
+        // blue still prefers its own (shorter) route for p.
+        assert_eq!(
+            net.get_vrf_paths(*R1, p, Some("blue")),
+            Ok(vec![vec![*R1, *R2, *E2]])
+        );
+
+        // removing the VRFs moves the routes back into the global table.
+        net.set_vrf(*R1, "red", None).unwrap();
+        net.set_vrf(*R1, "blue", None).unwrap();
+        assert_eq!(paths(&net, *R1, q), vec![vec![*R1, *E1]]);
+        assert_eq!(paths(&net, *R2, q), vec![vec![*R2, *R1, *E1]]);
+    }
+
+    #[test]
+    fn show_commands<P: Prefix>() {
+        let mut net = get_test_net::<P>();
+        let p0 = P::from(0);
+        let p1 = P::from(1);
+        net.advertise_external_route(*E1, p0, [65101, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(*E2, p0, [65102], None, None)
+            .unwrap();
+        net.advertise_external_route(*E1, p1, [65101], None, None)
+            .unwrap();
+
+        // group the table by prefix (the network is only shown in the first line of each prefix).
+        let bgp = show_ip_bgp(&net, *R1).unwrap();
+        let mut table: Vec<(String, Vec<&str>)> = Vec::new();
+        for l in bgp.lines().filter(|l| l.starts_with('*')) {
+            match l[3..21].trim() {
+                "" => table.last_mut().unwrap().1.push(l),
+                p => table.push((p.to_string(), vec![l])),
+            }
+        }
+        assert_eq!(
+            table.iter().map(|(p, _)| p.clone()).collect::<Vec<_>>(),
+            vec![p0.to_string(), p1.to_string()]
+        );
+        let (l0, l1) = (&table[0].1, &table[1].1);
+
+        // R1 prefers the shorter AS path via R2 over its own eBGP route.
+        assert_eq!(l0.len(), 2);
+        assert_eq!(l0.iter().filter(|l| l.starts_with("*>")).count(), 1);
+        assert!(l0[0].starts_with("*>i"));
+        assert!(l0[0].contains(" R2 "));
+        assert!(l0[0].ends_with(" 100    100 65102 i"));
+        assert!(l0[1].starts_with("*  "));
+        assert!(l0[1].contains(" E1 "));
+        assert!(l0[1].ends_with(" 65101 100 i"));
+        assert_eq!(l1.len(), 1);
+        assert!(l1[0].starts_with("*> "));
+
+        // the routing table contains the IGP next-hops.
+        let route = show_ip_route(&net, *R1).unwrap();
+        assert!(route.contains(&format!("B     {p0} [200/0] via R2\n")));
+        assert!(route.contains(&format!("B     {p1} [20/0] via E1\n")));
+
+        assert!(show_ip_bgp(&net, *E1).is_err());
+    }
+
+    #[instantiate_tests(<SimplePrefix>)]
+    mod simple {}
+
+    #[instantiate_tests(<Ipv4Prefix>)]
+    mod ipv4 {}
+}
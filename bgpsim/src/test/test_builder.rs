@@ -15,6 +15,8 @@
 
 #[generic_tests::define]
 mod t {
+    use std::collections::HashMap;
+
     use crate::{
         builder::*,
         event::BasicEventQueue as Queue,
@@ -401,6 +403,37 @@ mod t {
         }
     }
 
+    #[test]
+    fn test_detect_route_leaks<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = Network::<P, Queue<P>, Ospf>::new(Queue::new());
+        let r = net.add_router("R");
+        let provider = net.add_external_router("Provider", AsId(1));
+        let peer = net.add_external_router("Peer", AsId(2));
+        let customer = net.add_external_router("Customer", AsId(3));
+        for ext in [provider, peer, customer] {
+            net.add_link(r, ext).unwrap();
+            net.set_bgp_session(r, ext, Some(BgpSessionType::EBgp))
+                .unwrap();
+        }
+        let lut = HashMap::from([
+            (provider, GaoRexfordPeerType::Provider),
+            (peer, GaoRexfordPeerType::Peer),
+            (customer, GaoRexfordPeerType::Customer),
+        ]);
+
+        let p = P::from(0);
+        net.advertise_external_route(provider, p, [1, 100], None, None)
+            .unwrap();
+
+        // without any export policy, the route from the provider is leaked to the peer.
+        assert_eq!(net.detect_route_leaks(&lut), vec![(r, peer, p)]);
+
+        // Gao-Rexford policies prevent the leak.
+        net.build_gao_rexford_policies(GaoRexfordPeerType::lookup, &lut)
+            .unwrap();
+        assert_eq!(net.detect_route_leaks(&lut), vec![]);
+    }
+
     fn assert_igp_reachability<P: Prefix, Q, Ospf: OspfImpl>(net: &Network<P, Q, Ospf>) {
         for src in net.internal_indices() {
             let r = net.get_device(src).unwrap().unwrap_internal();
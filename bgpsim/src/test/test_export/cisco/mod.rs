@@ -138,7 +138,7 @@ exit
             .generate_command(&net, &mut ip, Insert(cmd2a.clone()))
             .unwrap(),
         "\
-ip community-list standard neighbor-R0_ext_4-in-32780-cl permit 65535:100
+ip community-list standard neighbor-R0_ext_4-in-32780-cl permit 0:100
 route-map neighbor-R0_ext_4-in permit 32780
   match community neighbor-R0_ext_4-in-32780-cl
   set local-preference 200
@@ -164,10 +164,10 @@ exit
             .generate_command(&net, &mut ip, Insert(cmd3.clone()))
             .unwrap(),
         "\
-ip community-list standard neighbor-R0_ext_4-in-32781-cl permit 65535:200
+ip community-list standard neighbor-R0_ext_4-in-32781-cl permit 0:200
 route-map neighbor-R0_ext_4-in permit 32781
   match community neighbor-R0_ext_4-in-32781-cl
-  set community additive 65535:300
+  set community additive 0:300
   continue 32788
 exit
 route-map neighbor-R0_ext_4-in permit 32780
@@ -200,7 +200,7 @@ exit
         "\
 no ip community-list standard neighbor-R0_ext_4-in-32780-cl
 no route-map neighbor-R0_ext_4-in permit 32780
-ip community-list standard neighbor-R0_ext_4-in-32780-cl permit 65535:100
+ip community-list standard neighbor-R0_ext_4-in-32780-cl permit 0:100
 route-map neighbor-R0_ext_4-in deny 32780
   match community neighbor-R0_ext_4-in-32780-cl
 exit
@@ -250,3 +250,49 @@ fn generate_external_config_withdraw() {
     assert_str_eq!(cfg, include_str!("external_config_withdraw"));
     assert_str_eq!(cmd, include_str!("external_config_withdraw_cmd"))
 }
+
+#[test]
+fn generate_config_session_options() {
+    let (int, ext) = super::generate_config_session_options(Target, false);
+    assert!(!int.contains("password"));
+    assert!(!int.contains("mtu"));
+    assert!(!ext.contains("password"));
+    assert!(!ext.contains("mtu"));
+
+    let (int, ext) = super::generate_config_session_options(Target, true);
+    assert!(int.contains(
+        "  neighbor 10.192.0.2 remote-as 4\n    update-source Ethernet8/4\n    password s3cr3t\n"
+    ));
+    assert!(int.contains("  ip address 10.192.0.1/30\n  ip ospf cost 1\n  ip router ospf 10 area 0\n  ip ospf dead-interval 5\n  ip ospf hello-interval 1\n  mtu 9000\n  no shutdown\n"));
+    assert_eq!(int.matches("password").count(), 1);
+    assert_eq!(int.matches("mtu").count(), 1);
+    assert!(ext.contains("    update-source Ethernet8/1\n    password s3cr3t\n"));
+    assert!(ext.contains("/30\n  mtu 9000\n  no shutdown\n"));
+}
+
+#[test]
+fn generate_config_peer_group() {
+    let cfg = super::generate_config_peer_group(Target);
+    assert!(cfg.contains("  template peer ibgp\n    password s3cr3t\n"));
+    assert_eq!(cfg.matches("    inherit peer ibgp\n").count(), 3);
+}
+
+#[test]
+fn generate_config_as_path_list() {
+    let cfg = super::generate_config_as_path_list(Target);
+    assert!(cfg.contains(
+        "\
+ip as-path access-list neighbor-R0_ext_4-in-32778-asl deny _666_
+ip as-path access-list neighbor-R0_ext_4-in-32778-asl permit _100_
+route-map neighbor-R0_ext_4-in permit 32778
+  match as-path neighbor-R0_ext_4-in-32778-asl
+"
+    ));
+}
+
+#[test]
+fn generate_config_route_tag() {
+    let cfg = super::generate_config_route_tag(Target);
+    assert!(cfg.contains("route-map neighbor-R0_ext_4-in permit 32778\n  set tag 7\n"));
+    assert!(cfg.contains("route-map neighbor-R1-out deny 32778\n  match tag 7\n"));
+}
@@ -14,6 +14,7 @@
 // limitations under the License.
 
 use crate::{
+    bgp::BgpSessionOptions,
     builder::NetworkBuilder,
     event::BasicEventQueue,
     export::{Addressor, ExaBgpCfgGen, ExternalCfgGen},
@@ -204,3 +205,27 @@ fn script_2n_2p_withdraw_pec() {
     let script = gen.withdraw_route(&net, &mut ip, 0.into()).unwrap();
     assert_eq!(script, include_str!("config_2n_2p_withdraw_pec.py"));
 }
+
+#[test]
+fn config_1n_session_options() {
+    let num_neighbors = 1;
+    let mut net = get_test_net::<SimplePrefix>(num_neighbors);
+    let ext: RouterId = (num_neighbors as u32).into();
+    let options = BgpSessionOptions {
+        password: Some(String::from("s3cr3t")),
+        mtu: Some(9000),
+    };
+    net.set_session_options(0.into(), ext, Some(options))
+        .unwrap();
+    let mut ip = addressor(&net);
+
+    let mut gen = ExaBgpCfgGen::new(&net, ext).unwrap();
+    let cfg = gen.generate_config(&net, &mut ip).unwrap();
+    assert_eq!(
+        cfg,
+        include_str!("config_1n.ini").replace(
+            "    peer-as 65535;\n",
+            "    peer-as 65535;\n    md5-password \"s3cr3t\";\n"
+        )
+    );
+}
@@ -16,6 +16,8 @@
 use pretty_assertions::assert_str_eq;
 
 use crate::export::cisco_frr_generators::Target::Frr as Target;
+use crate::export::ExportError;
+use crate::route_map::RouteMapBuilder;
 use crate::types::{NonOverlappingPrefix, Prefix, SimplePrefix, SinglePrefix};
 
 #[generic_tests::define]
@@ -76,3 +78,81 @@ fn generate_internal_config_route_reflector() {
         include_str!("internal_config_route_reflection")
     );
 }
+
+#[test]
+fn generate_config_session_options() {
+    let (int, ext) = super::generate_config_session_options(Target, false);
+    assert!(!int.contains("password"));
+    assert!(!ext.contains("password"));
+
+    let (int, ext) = super::generate_config_session_options(Target, true);
+    assert!(int.contains(
+        "  neighbor 10.192.0.2 update-source eth4\n  neighbor 10.192.0.2 password s3cr3t\n"
+    ));
+    assert_eq!(int.matches("password").count(), 1);
+    assert!(ext
+        .lines()
+        .any(|l| l.starts_with("  neighbor ") && l.ends_with(" password s3cr3t")));
+    // the MTU cannot be configured on FRR.
+    assert!(!int.contains("mtu"));
+    assert!(!ext.contains("mtu"));
+}
+
+#[test]
+fn generate_config_as_path_list() {
+    let cfg = super::generate_config_as_path_list(Target);
+    assert!(cfg.contains(
+        "\
+bgp as-path access-list neighbor-R0_ext_4-in-32778-asl deny _666_
+bgp as-path access-list neighbor-R0_ext_4-in-32778-asl permit _100_
+route-map neighbor-R0_ext_4-in permit 32778
+  match as-path neighbor-R0_ext_4-in-32778-asl
+"
+    ));
+}
+
+#[test]
+fn generate_config_peer_group() {
+    let cfg = super::generate_config_peer_group(Target);
+    assert!(cfg.contains("  neighbor ibgp peer-group\n  neighbor ibgp password s3cr3t\n"));
+    assert!(cfg.contains("    neighbor ibgp next-hop-self\n"));
+    assert_eq!(cfg.matches(" peer-group ibgp\n").count(), 3);
+    // the eBGP session is not part of the peer-group.
+    assert!(cfg.contains("  neighbor 10.192.0.2 update-source eth4\n"));
+    assert!(!cfg.contains("  neighbor 10.192.0.2 peer-group"));
+}
+
+#[test]
+fn generate_config_route_tag() {
+    let cfg = super::generate_config_route_tag(Target);
+    assert!(cfg.contains("route-map neighbor-R0_ext_4-in permit 32778\n  set tag 7\n"));
+    assert!(cfg.contains("route-map neighbor-R1-out deny 32778\n  match tag 7\n"));
+}
+
+#[test]
+fn generate_config_unsupported_route_map_actions() {
+    let generate = |map| super::generate_config_route_map(Target, map);
+    assert!(matches!(
+        generate(RouteMapBuilder::new().allow().order(10).clamp_local_pref(50, 150).build()),
+        Err(ExportError::InternalCfgGenError(r, _)) if r == 0.into()
+    ));
+    assert!(matches!(
+        generate(RouteMapBuilder::new().allow().order(10).clamp_med(0, 10).build()),
+        Err(ExportError::InternalCfgGenError(r, _)) if r == 0.into()
+    ));
+    assert!(matches!(
+        generate(
+            RouteMapBuilder::new()
+                .allow()
+                .order(10)
+                .set_localpref_from_igp_cost(200, 10)
+                .build()
+        ),
+        Err(ExportError::InternalCfgGenError(r, _)) if r == 0.into()
+    ));
+    assert!(matches!(
+        generate(RouteMapBuilder::new().allow().order(10).dampen().build()),
+        Err(ExportError::InternalCfgGenError(r, _)) if r == 0.into()
+    ));
+    assert!(generate(RouteMapBuilder::new().allow().order(10).set_med(10).build()).is_ok());
+}
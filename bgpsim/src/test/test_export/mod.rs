@@ -16,21 +16,24 @@
 use bgpsim_macros::prefix;
 
 use crate::{
+    bgp::{BgpPeerGroup, BgpSessionOptions, BgpSessionType},
     builder::{constant_link_weight, NetworkBuilder},
     event::BasicEventQueue,
     export::{
         cisco_frr_generators::Target, Addressor, CiscoFrrCfgGen, DefaultAddressor,
-        DefaultAddressorBuilder, ExternalCfgGen, InternalCfgGen,
+        DefaultAddressorBuilder, ExportError, ExternalCfgGen, InternalCfgGen,
     },
     network::Network,
     ospf::OspfImpl,
-    route_map::{RouteMapBuilder, RouteMapDirection},
+    route_map::{RouteMap, RouteMapBuilder, RouteMapDirection, RouteMapMatchAsPath, RouteMapState},
     types::{NonOverlappingPrefix, Prefix, SimplePrefix},
 };
 
 mod cisco;
 mod exabgp;
 mod frr;
+mod networkx;
+mod reachability;
 
 fn iface_names(target: Target) -> Vec<String> {
     match target {
@@ -82,6 +85,157 @@ fn generate_internal_config_route_reflector(target: Target) -> String {
     InternalCfgGen::generate_config(&mut cfg_gen, &net, &mut ip).unwrap()
 }
 
+/// Generate the configuration of router 0 and of the external router 4 connected to it, optionally
+/// setting a session password and an MTU on the session from router 0 to router 4.
+fn generate_config_session_options(target: Target, set_options: bool) -> (String, String) {
+    let mut net: Network<SimplePrefix, _> =
+        NetworkBuilder::build_complete_graph(BasicEventQueue::new(), 4);
+    net.build_external_routers(|_, _| vec![0.into(), 1.into()], ())
+        .unwrap();
+    net.build_link_weights(constant_link_weight, 100.0).unwrap();
+    net.build_ibgp_full_mesh().unwrap();
+    net.build_ebgp_sessions().unwrap();
+    if set_options {
+        let options = BgpSessionOptions {
+            password: Some(String::from("s3cr3t")),
+            mtu: Some(9000),
+        };
+        net.set_session_options(0.into(), 4.into(), Some(options))
+            .unwrap();
+    }
+
+    let mut ip = addressor(&net);
+
+    let mut cfg_gen = CiscoFrrCfgGen::new(&net, 0.into(), target, iface_names(target)).unwrap();
+    let int = InternalCfgGen::generate_config(&mut cfg_gen, &net, &mut ip).unwrap();
+    let mut cfg_gen = CiscoFrrCfgGen::new(&net, 4.into(), target, iface_names(target)).unwrap();
+    let ext = ExternalCfgGen::generate_config(&mut cfg_gen, &net, &mut ip).unwrap();
+
+    (int, ext)
+}
+
+/// Generate the configuration of router 0, where all iBGP sessions of router 0 are configured
+/// from the peer-group `ibgp`, which sets a session password.
+fn generate_config_peer_group(target: Target) -> String {
+    let mut net: Network<SimplePrefix, _> =
+        NetworkBuilder::build_complete_graph(BasicEventQueue::new(), 4);
+    net.build_external_routers(|_, _| vec![0.into(), 1.into()], ())
+        .unwrap();
+    net.build_link_weights(constant_link_weight, 100.0).unwrap();
+    net.build_ibgp_full_mesh().unwrap();
+    net.build_ebgp_sessions().unwrap();
+    let mut template = BgpPeerGroup::new(BgpSessionType::IBgpPeer);
+    template.options = Some(BgpSessionOptions {
+        password: Some(String::from("s3cr3t")),
+        mtu: None,
+    });
+    net.define_peer_group("ibgp", template).unwrap();
+    for n in 1..4 {
+        net.add_session_from_group(0.into(), n.into(), "ibgp")
+            .unwrap();
+    }
+
+    let mut ip = addressor(&net);
+
+    let mut cfg_gen = CiscoFrrCfgGen::new(&net, 0.into(), target, iface_names(target)).unwrap();
+    InternalCfgGen::generate_config(&mut cfg_gen, &net, &mut ip).unwrap()
+}
+
+/// Generate the configuration of router 0, whose incoming route-map towards the external router 4
+/// matches on the named AS-path access-list `no-transit`.
+fn generate_config_as_path_list(target: Target) -> String {
+    let mut net: Network<SimplePrefix, _> =
+        NetworkBuilder::build_complete_graph(BasicEventQueue::new(), 4);
+    net.build_external_routers(|_, _| vec![0.into(), 1.into()], ())
+        .unwrap();
+    net.build_link_weights(constant_link_weight, 100.0).unwrap();
+    net.build_ibgp_full_mesh().unwrap();
+    net.build_ebgp_sessions().unwrap();
+    net.define_as_path_list(
+        "no-transit",
+        [
+            (
+                RouteMapState::Deny,
+                RouteMapMatchAsPath::Contains(666.into()),
+            ),
+            (
+                RouteMapState::Allow,
+                RouteMapMatchAsPath::Contains(100.into()),
+            ),
+        ],
+    )
+    .unwrap();
+    net.set_bgp_route_map(
+        0.into(),
+        4.into(),
+        RouteMapDirection::Incoming,
+        RouteMapBuilder::new()
+            .allow()
+            .order(10)
+            .match_as_path_list("no-transit")
+            .build(),
+    )
+    .unwrap();
+
+    let mut ip = addressor(&net);
+
+    let mut cfg_gen = CiscoFrrCfgGen::new(&net, 0.into(), target, iface_names(target)).unwrap();
+    InternalCfgGen::generate_config(&mut cfg_gen, &net, &mut ip).unwrap()
+}
+
+/// Generate the configuration of router 0, which tags all routes received from the external router
+/// 4, and does not advertise tagged routes to router 1.
+fn generate_config_route_tag(target: Target) -> String {
+    let mut net: Network<SimplePrefix, _> =
+        NetworkBuilder::build_complete_graph(BasicEventQueue::new(), 4);
+    net.build_external_routers(|_, _| vec![0.into(), 1.into()], ())
+        .unwrap();
+    net.build_link_weights(constant_link_weight, 100.0).unwrap();
+    net.build_ibgp_full_mesh().unwrap();
+    net.build_ebgp_sessions().unwrap();
+    net.set_bgp_route_map(
+        0.into(),
+        4.into(),
+        RouteMapDirection::Incoming,
+        RouteMapBuilder::new().allow().order(10).set_tag(7).build(),
+    )
+    .unwrap();
+    net.set_bgp_route_map(
+        0.into(),
+        1.into(),
+        RouteMapDirection::Outgoing,
+        RouteMapBuilder::new().deny().order(10).match_tag(7).build(),
+    )
+    .unwrap();
+
+    let mut ip = addressor(&net);
+
+    let mut cfg_gen = CiscoFrrCfgGen::new(&net, 0.into(), target, iface_names(target)).unwrap();
+    InternalCfgGen::generate_config(&mut cfg_gen, &net, &mut ip).unwrap()
+}
+
+/// Generate the configuration of router 0, whose only route-map is `map` applied to routes received
+/// from the external router 4.
+fn generate_config_route_map(
+    target: Target,
+    map: RouteMap<SimplePrefix>,
+) -> Result<String, ExportError> {
+    let mut net: Network<SimplePrefix, _> =
+        NetworkBuilder::build_complete_graph(BasicEventQueue::new(), 4);
+    net.build_external_routers(|_, _| vec![0.into(), 1.into()], ())
+        .unwrap();
+    net.build_link_weights(constant_link_weight, 100.0).unwrap();
+    net.build_ibgp_full_mesh().unwrap();
+    net.build_ebgp_sessions().unwrap();
+    net.set_bgp_route_map(0.into(), 4.into(), RouteMapDirection::Incoming, map)
+        .unwrap();
+
+    let mut ip = addressor(&net);
+
+    let mut cfg_gen = CiscoFrrCfgGen::new(&net, 0.into(), target, iface_names(target)).unwrap();
+    InternalCfgGen::generate_config(&mut cfg_gen, &net, &mut ip)
+}
+
 fn net_for_route_maps<P: Prefix>() -> Network<P, BasicEventQueue<P>> {
     let mut net: Network<P, _> = NetworkBuilder::build_complete_graph(BasicEventQueue::new(), 4);
     net.build_external_routers(|_, _| vec![0.into(), 1.into()], ())
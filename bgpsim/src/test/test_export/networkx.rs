@@ -0,0 +1,82 @@
+// BgpSim: BGP Network Simulator written in Rust
+// Copyright 2022-2024 Tibor Schneider <sctibor@ethz.ch>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde_json::Value;
+
+use crate::{
+    bgp::BgpSessionType::*,
+    event::BasicEventQueue,
+    export::networkx_json,
+    network::Network,
+    types::{AsId, SimplePrefix},
+};
+use pretty_assertions::assert_eq;
+
+#[test]
+fn networkx_node_link() {
+    let mut net: Network<SimplePrefix, _> = Network::new(BasicEventQueue::new());
+    let e1 = net.add_external_router("E1", AsId(65101));
+    let r1 = net.add_router("R1");
+    let r2 = net.add_router("R2");
+    let r3 = net.add_router("R3");
+    net.add_link(e1, r1).unwrap();
+    net.add_link(r1, r2).unwrap();
+    net.add_link(r2, r3).unwrap();
+    net.add_link(r1, r3).unwrap();
+    net.set_link_weight(r1, r2, 5.0).unwrap();
+    net.set_bgp_session(r1, r2, Some(IBgpClient)).unwrap();
+    net.set_bgp_session(r1, r3, Some(IBgpClient)).unwrap();
+
+    let json: Value = serde_json::from_str(&networkx_json(&net)).unwrap();
+    assert_eq!(json["directed"], true);
+    assert_eq!(json["multigraph"], false);
+
+    let nodes = json["nodes"].as_array().unwrap();
+    assert_eq!(nodes.len(), 4);
+    let names: Vec<(u64, &str)> = nodes
+        .iter()
+        .map(|n| (n["id"].as_u64().unwrap(), n["name"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            (e1.index() as u64, "E1"),
+            (r1.index() as u64, "R1"),
+            (r2.index() as u64, "R2"),
+            (r3.index() as u64, "R3")
+        ]
+    );
+    assert_eq!(nodes[0]["role"], "external");
+    assert_eq!(nodes[0]["as_id"], 65101);
+    assert_eq!(nodes[1]["role"], "internal");
+    assert_eq!(nodes[1]["route_reflector"], true);
+    assert_eq!(nodes[2]["route_reflector"], false);
+
+    // one edge in each direction of every link
+    let edges = json["edges"].as_array().unwrap();
+    assert_eq!(edges.len(), 8);
+    let ids: Vec<&Value> = nodes.iter().map(|n| &n["id"]).collect();
+    assert!(edges
+        .iter()
+        .all(|e| ids.contains(&&e["source"]) && ids.contains(&&e["target"])));
+    let weight = |a: usize, b: usize| {
+        edges
+            .iter()
+            .find(|e| e["source"] == a && e["target"] == b)
+            .map(|e| e["weight"].as_f64().unwrap())
+    };
+    assert_eq!(weight(r1.index(), r2.index()), Some(5.0));
+    assert_eq!(weight(r2.index(), r1.index()), Some(100.0));
+}
@@ -0,0 +1,136 @@
+// BgpSim: BGP Network Simulator written in Rust
+// Copyright 2022-2024 Tibor Schneider <sctibor@ethz.ch>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde_json::Value;
+
+use crate::{
+    bgp::BgpSessionType::*,
+    event::BasicEventQueue,
+    export::{reachability_spec, reachability_spec_json, ExpectedReachability},
+    network::Network,
+    route_map::{RouteMapBuilder, RouteMapDirection::Incoming},
+    types::{AsId, Ipv4Prefix},
+};
+use pretty_assertions::assert_eq;
+
+#[test]
+fn reachability_test_suite() {
+    let mut net: Network<Ipv4Prefix, _> = Network::new(BasicEventQueue::new());
+    let e1 = net.add_external_router("E1", AsId(65101));
+    let r1 = net.add_router("R1");
+    let r2 = net.add_router("R2");
+    let r3 = net.add_router("R3");
+    let e3 = net.add_external_router("E3", AsId(65103));
+    net.add_links_from([(e1, r1), (r1, r2), (r2, r3), (r3, e3)])
+        .unwrap();
+    net.set_link_weight(r1, r2, 1.0).unwrap();
+    net.set_link_weight(r2, r1, 1.0).unwrap();
+    net.set_link_weight(r2, r3, 2.0).unwrap();
+    net.set_link_weight(r3, r2, 2.0).unwrap();
+    net.set_bgp_session(e1, r1, Some(EBgp)).unwrap();
+    net.set_bgp_session(e3, r3, Some(EBgp)).unwrap();
+    net.set_bgp_session(r2, r1, Some(IBgpClient)).unwrap();
+    net.set_bgp_session(r2, r3, Some(IBgpClient)).unwrap();
+
+    let p0 = Ipv4Prefix::from(0);
+    let p1 = Ipv4Prefix::from(1);
+    // R3 does not accept p0 from the route reflector
+    net.set_bgp_route_map(
+        r3,
+        r2,
+        Incoming,
+        RouteMapBuilder::new()
+            .order(10)
+            .deny()
+            .match_prefix(p0)
+            .build(),
+    )
+    .unwrap();
+    net.advertise_external_route(e1, p0, [65101], None, None)
+        .unwrap();
+    net.advertise_external_route(e1, p1, [65101, 100], None, None)
+        .unwrap();
+    net.advertise_external_route(e3, p1, [65103, 100], None, None)
+        .unwrap();
+
+    let spec = reachability_spec(&net);
+    let num_prefixes = net.get_known_prefixes().count();
+    assert_eq!(spec.len(), net.internal_indices().count() * num_prefixes);
+    assert_eq!(spec.len(), 6);
+
+    // the assertions match the simulated forwarding state
+    let mut fw = net.get_forwarding_state();
+    for (assertion, (r, p)) in spec.iter().zip(
+        [r1, r2, r3]
+            .into_iter()
+            .flat_map(|r| [p0, p1].map(|p| (r, p))),
+    ) {
+        assert_eq!(assertion.router, net.get_device(r).unwrap().name());
+        assert_eq!(assertion.prefix, p);
+        match (&assertion.expect, fw.get_paths(r, p)) {
+            (ExpectedReachability::Reachable { paths, .. }, Ok(sim)) => {
+                let sim = sim
+                    .into_iter()
+                    .map(|path| {
+                        path.into_iter()
+                            .map(|x| net.get_device(x).unwrap().name().to_string())
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(paths, &sim);
+            }
+            (ExpectedReachability::BlackHole { at }, Err(_)) => assert_eq!(at, "R3"),
+            (expect, sim) => panic!("{expect:?} does not match {sim:?}"),
+        }
+    }
+
+    let egress = |router: &str, prefix: Ipv4Prefix| {
+        spec.iter()
+            .find(|a| a.router == router && a.prefix == prefix)
+            .map(|a| a.expect.clone())
+            .unwrap()
+    };
+    assert_eq!(
+        egress("R2", p0),
+        ExpectedReachability::Reachable {
+            egress: vec!["R1".to_string()],
+            paths: vec![vec!["R2".to_string(), "R1".to_string(), "E1".to_string()]],
+        }
+    );
+    assert_eq!(
+        egress("R3", p1),
+        ExpectedReachability::Reachable {
+            egress: vec!["R3".to_string()],
+            paths: vec![vec!["R3".to_string(), "E3".to_string()]],
+        }
+    );
+    assert_eq!(
+        egress("R3", p0),
+        ExpectedReachability::BlackHole {
+            at: "R3".to_string()
+        }
+    );
+
+    // the JSON contains the same assertions
+    let json: Value = serde_json::from_str(&reachability_spec_json(&net).unwrap()).unwrap();
+    let json = json.as_array().unwrap();
+    assert_eq!(json.len(), 6);
+    assert_eq!(json[0]["router"], "R1");
+    assert_eq!(json[0]["prefix"], p0.to_string());
+    assert_eq!(json[0]["expect"]["kind"], "reachable");
+    assert_eq!(json[0]["expect"]["egress"][0], "R1");
+    assert_eq!(json[4]["expect"]["kind"], "black_hole");
+    assert_eq!(json[4]["expect"]["at"], "R3");
+}
@@ -13,12 +13,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::{BTreeSet, HashMap};
+
 use crate::{
     bgp::BgpSessionType::*,
     config::{Config, ConfigExpr::*, NetworkConfig},
+    forwarding_state::ForwardingState,
     network::Network,
     route_map::*,
-    types::{AsId, Ipv4Prefix, Prefix, SimplePrefix},
+    types::{AsId, Ipv4Prefix, Prefix, PrefixMap, RouterId, SimplePrefix},
 };
 
 #[generic_tests::define]
@@ -284,6 +287,375 @@ mod t {
         }
     }
 
+    #[test]
+    fn hash_equivalent_forwarding_state<P: Prefix>() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        fn hash<P: Prefix>(state: &ForwardingState<P>) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            state.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let mut net = Network::<P, _, GlobalOspf>::default();
+        let r1 = net.add_router("r1");
+        let r2 = net.add_router("r2");
+        let r3 = net.add_router("r3");
+        let r4 = net.add_router("r4");
+        let e = net.add_external_router("e", AsId(65100));
+        net.add_link(r1, r2).unwrap();
+        net.add_link(r1, r3).unwrap();
+        net.add_link(r2, r4).unwrap();
+        net.add_link(r3, r4).unwrap();
+        net.add_link(r4, e).unwrap();
+        net.set_link_weight(r1, r3, 5.0).unwrap();
+        net.set_bgp_session(r1, r4, Some(IBgpPeer)).unwrap();
+        net.set_bgp_session(r4, e, Some(EBgp)).unwrap();
+        net.advertise_external_route(e, P::from(0), [AsId(65100)], None, None)
+            .unwrap();
+
+        // forward r1 -> r2 -> r4
+        net.set_link_weight(r1, r2, 1.0).unwrap();
+        let a = net.get_forwarding_state();
+        // still forward r1 -> r2 -> r4
+        net.set_link_weight(r1, r2, 2.0).unwrap();
+        let b = net.get_forwarding_state();
+        // forward r1 -> r3 -> r4
+        net.set_link_weight(r1, r2, 10.0).unwrap();
+        let c = net.get_forwarding_state();
+
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        assert_ne!(a, c);
+        assert_ne!(hash(&a), hash(&c));
+    }
+
+    #[test]
+    fn equivalent_to_router_fib<P: Prefix>() {
+        let mut net = Network::<P, _, GlobalOspf>::default();
+        let r1 = net.add_router("r1");
+        let r2 = net.add_router("r2");
+        let r3 = net.add_router("r3");
+        let r4 = net.add_router("r4");
+        let e = net.add_external_router("e", AsId(65100));
+        net.add_links_from([(r1, r2), (r1, r3), (r2, r4), (r3, r4), (r4, e)])
+            .unwrap();
+        let routers = [r1, r2, r3, r4];
+        net.set_bgp_session_from(routers.iter().enumerate().flat_map(|(i, a)| {
+            routers[i + 1..]
+                .iter()
+                .map(move |b| (*a, *b, Some(IBgpPeer)))
+        }))
+        .unwrap();
+        net.set_bgp_session(r4, e, Some(EBgp)).unwrap();
+        let prefixes = [P::from(0), P::from(1), P::from(2)];
+        for p in prefixes {
+            net.advertise_external_route(e, p, [AsId(65100)], None, None)
+                .unwrap();
+        }
+
+        let sorted = |nhs: &[_]| nhs.iter().copied().collect::<BTreeSet<_>>();
+
+        // the forwarding state contains exactly the FIB of each router.
+        let mut state = net.get_forwarding_state();
+        for r in net.internal_routers() {
+            for (p, nhs) in r.get_fib().iter() {
+                assert_eq!(sorted(state.get_next_hops(r.router_id(), *p)), sorted(nhs));
+            }
+        }
+        assert_eq!(
+            state.get_paths(r1, prefixes[1]).unwrap(),
+            vec![vec![r1, r2, r4, e]]
+        );
+
+        // updating the forwarding state changes only the updated entry.
+        let original = state.clone();
+        state.update(r1, prefixes[0], vec![r3]);
+        assert_eq!(state.get_next_hops(r1, prefixes[0]), &[r3]);
+        assert_eq!(
+            state.get_paths(r1, prefixes[0]).unwrap(),
+            vec![vec![r1, r3, r4, e]]
+        );
+        assert_eq!(
+            state.changed_entries(&original),
+            [(r1, prefixes[0])].into_iter().collect()
+        );
+        state.update(
+            r1,
+            prefixes[0],
+            original.get_next_hops(r1, prefixes[0]).to_vec(),
+        );
+        assert_eq!(state, original);
+        state.update(r1, prefixes[0], vec![]);
+        assert!(state.is_black_hole(r1, prefixes[0]));
+
+        // the serialized forwarding state contains the FIB of each router.
+        let json = serde_json::to_string(&original).unwrap();
+        let restored: ForwardingState<P> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, original);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let raw: HashMap<RouterId, P::Map<Vec<RouterId>>> =
+            serde_json::from_value(value["state"].clone()).unwrap();
+        for r in net.internal_routers() {
+            assert_eq!(raw[&r.router_id()], r.get_fib());
+        }
+    }
+
+    #[test]
+    fn paths_disjoint<P: Prefix>() {
+        let mut net = Network::<P, _, GlobalOspf>::default();
+        let r0 = net.add_router("r0");
+        let r1 = net.add_router("r1");
+        let r2 = net.add_router("r2");
+        let r3 = net.add_router("r3");
+        let e2 = net.add_external_router("e2", AsId(65102));
+        let e3 = net.add_external_router("e3", AsId(65103));
+        net.add_links_from([(r0, r1), (r1, r2), (r1, r3), (r2, e2), (r3, e3)])
+            .unwrap();
+        let routers = [r0, r1, r2, r3];
+        net.set_bgp_session_from(routers.iter().enumerate().flat_map(|(i, a)| {
+            routers[i + 1..]
+                .iter()
+                .map(move |b| (*a, *b, Some(IBgpPeer)))
+        }))
+        .unwrap();
+        net.set_bgp_session(r2, e2, Some(EBgp)).unwrap();
+        net.set_bgp_session(r3, e3, Some(EBgp)).unwrap();
+
+        let pa = P::from(1);
+        let pb = P::from(2);
+        net.advertise_external_route(e2, pa, [AsId(65102)], None, None)
+            .unwrap();
+        net.advertise_external_route(e3, pb, [AsId(65103)], None, None)
+            .unwrap();
+
+        let mut state = net.get_forwarding_state();
+
+        // r1 reaches both prefixes over disjoint paths
+        let d = state.paths_disjoint(r1, pa, pb).unwrap();
+        assert!(d.is_node_disjoint());
+        assert!(d.is_link_disjoint());
+
+        // r0 reaches both prefixes over the link r0 -> r1
+        let d = state.paths_disjoint(r0, pa, pb).unwrap();
+        assert_eq!(d.shared_nodes, BTreeSet::from([r1]));
+        assert_eq!(d.shared_links, BTreeSet::from([(r0, r1)]));
+
+        // the paths towards the same prefix are not disjoint
+        let d = state.paths_disjoint(r1, pa, pa).unwrap();
+        assert_eq!(d.shared_nodes, BTreeSet::from([r2, e2]));
+        assert_eq!(d.shared_links, BTreeSet::from([(r1, r2), (r2, e2)]));
+        assert!(d.shared_srlgs.is_empty());
+
+        // the links r1 -- r2 and r1 -- r3 share a risk, so the paths of r1 are not truly disjoint.
+        net.set_link_srlgs(r1, r2, [5]).unwrap();
+        net.set_link_srlgs(r3, r1, [5, 6]).unwrap();
+        net.set_link_srlgs(r0, r1, [6]).unwrap();
+        let d = state
+            .paths_srlg_disjoint(r1, pa, pb, net.get_srlgs())
+            .unwrap();
+        assert!(d.is_link_disjoint());
+        assert!(!d.is_srlg_disjoint());
+        assert_eq!(d.shared_srlgs, BTreeSet::from([5]));
+        assert!(state.paths_disjoint(r1, pa, pb).unwrap().is_srlg_disjoint());
+        net.set_link_srlgs(r1, r2, []).unwrap();
+        let d = state
+            .paths_srlg_disjoint(r1, pa, pb, net.get_srlgs())
+            .unwrap();
+        assert!(d.is_srlg_disjoint());
+    }
+
+    #[test]
+    fn link_loads<P: Prefix>() {
+        let mut net = Network::<P, _, GlobalOspf>::default();
+        let r0 = net.add_router("r0");
+        let r1 = net.add_router("r1");
+        let r2 = net.add_router("r2");
+        let r3 = net.add_router("r3");
+        let e3 = net.add_external_router("e3", AsId(65103));
+        net.add_links_from([(r0, r1), (r0, r2), (r1, r3), (r2, r3), (r3, e3)])
+            .unwrap();
+        let routers = [r0, r1, r2, r3];
+        net.set_bgp_session_from(routers.iter().enumerate().flat_map(|(i, a)| {
+            routers[i + 1..]
+                .iter()
+                .map(move |b| (*a, *b, Some(IBgpPeer)))
+        }))
+        .unwrap();
+        net.set_bgp_session(r3, e3, Some(EBgp)).unwrap();
+        net.set_load_balancing(r0, true).unwrap();
+
+        let p = P::from(1);
+        net.advertise_external_route(e3, p, [AsId(65103)], None, None)
+            .unwrap();
+        let mut state = net.get_forwarding_state();
+
+        // a single demand loads exactly the links on its path
+        assert_eq!(
+            state.link_loads(&[(r1, p, 4.0)]).unwrap(),
+            HashMap::from([((r1, r3), 4.0), ((r3, e3), 4.0)])
+        );
+
+        // r0 splits its demand evenly between r1 and r2
+        assert_eq!(
+            state.link_loads(&[(r0, p, 10.0), (r1, p, 4.0)]).unwrap(),
+            HashMap::from([
+                ((r0, r1), 5.0),
+                ((r0, r2), 5.0),
+                ((r1, r3), 9.0),
+                ((r2, r3), 5.0),
+                ((r3, e3), 14.0),
+            ])
+        );
+
+        // traffic towards an unknown prefix is dropped immediately
+        assert_eq!(
+            state.link_loads(&[(r0, P::from(2), 10.0)]).unwrap(),
+            HashMap::new()
+        );
+    }
+
+    #[test]
+    fn physical_path<P: Prefix>() {
+        let mut net = Network::<P, _, GlobalOspf>::default();
+        let r0 = net.add_router("r0");
+        let r1 = net.add_router("r1");
+        let r2 = net.add_router("r2");
+        let r3 = net.add_router("r3");
+        let e3 = net.add_external_router("e3", AsId(65103));
+        net.add_links_from([(r0, r1), (r0, r2), (r1, r3), (r2, r3), (r3, e3)])
+            .unwrap();
+        net.set_link_weight(r0, r1, 500.0).unwrap();
+        let routers = [r0, r1, r2, r3];
+        net.set_bgp_session_from(routers.iter().enumerate().flat_map(|(i, a)| {
+            routers[i + 1..]
+                .iter()
+                .map(move |b| (*a, *b, Some(IBgpPeer)))
+        }))
+        .unwrap();
+        net.set_bgp_session(r3, e3, Some(EBgp)).unwrap();
+
+        let p = P::from(1);
+        net.advertise_external_route(e3, p, [AsId(65103)], None, None)
+            .unwrap();
+
+        // r0 learns the route over iBGP with r3 as its egress.
+        let route = net.get_internal_router(r0).unwrap().bgp.get_route(p);
+        assert_eq!(route.map(|e| e.route.next_hop), Some(r3));
+
+        // the traffic follows the IGP path towards r3, and then exits towards e3.
+        let mut state = net.get_forwarding_state();
+        assert_eq!(
+            state.physical_path(r0, p).unwrap(),
+            vec![vec![(r0, r2), (r2, r3), (r3, e3)]]
+        );
+        assert_eq!(state.physical_path(r3, p).unwrap(), vec![vec![(r3, e3)]]);
+        assert!(state.physical_path(r0, P::from(2)).is_err());
+
+        // with equal IGP costs and load balancing, r0 uses both paths towards r3.
+        net.set_link_weight(r0, r1, 100.0).unwrap();
+        net.set_load_balancing(r0, true).unwrap();
+        let mut state = net.get_forwarding_state();
+        assert_eq!(
+            state
+                .physical_path(r0, p)
+                .unwrap()
+                .into_iter()
+                .collect::<BTreeSet<_>>(),
+            BTreeSet::from([
+                vec![(r0, r1), (r1, r3), (r3, e3)],
+                vec![(r0, r2), (r2, r3), (r3, e3)],
+            ])
+        );
+    }
+
+    #[test]
+    fn max_disjoint_paths<P: Prefix>() {
+        let mut net = Network::<P, _, GlobalOspf>::default();
+        let r0 = net.add_router("r0");
+        let r1 = net.add_router("r1");
+        let r2 = net.add_router("r2");
+        let r3 = net.add_router("r3");
+        let e3 = net.add_external_router("e3", AsId(65103));
+        net.add_links_from([(r0, r1), (r0, r2), (r1, r3), (r2, r3), (r3, e3)])
+            .unwrap();
+        let routers = [r0, r1, r2, r3];
+        net.set_bgp_session_from(routers.iter().enumerate().flat_map(|(i, a)| {
+            routers[i + 1..]
+                .iter()
+                .map(move |b| (*a, *b, Some(IBgpPeer)))
+        }))
+        .unwrap();
+        net.set_bgp_session(r3, e3, Some(EBgp)).unwrap();
+        net.set_load_balancing(r0, true).unwrap();
+
+        let p = P::from(1);
+        net.advertise_external_route(e3, p, [AsId(65103)], None, None)
+            .unwrap();
+
+        // r0 load-balances over two link-disjoint paths towards r3. Both use the link towards e3.
+        let state = net.get_forwarding_state();
+        assert_eq!(state.max_disjoint_paths(r0, p, r3), 2);
+        assert_eq!(state.max_disjoint_paths(r0, p, e3), 1);
+        assert_eq!(state.max_disjoint_paths(r1, p, r3), 1);
+        assert_eq!(state.max_disjoint_paths(r1, p, r2), 0);
+        assert_eq!(state.max_disjoint_paths(r3, p, r3), 0);
+        assert_eq!(state.max_disjoint_paths(r0, P::from(2), r3), 0);
+
+        // without the link r0 -- r1, only a single path remains.
+        net.remove_link(r0, r1).unwrap();
+        let state = net.get_forwarding_state();
+        assert_eq!(state.max_disjoint_paths(r0, p, r3), 1);
+    }
+
+    #[test]
+    fn unreachable_prefixes<P: Prefix>() {
+        let mut net = Network::<P, _, GlobalOspf>::default();
+        let r0 = net.add_router("r0");
+        let r1 = net.add_router("r1");
+        let r2 = net.add_router("r2");
+        let e0 = net.add_external_router("e0", AsId(65100));
+        let e2 = net.add_external_router("e2", AsId(65102));
+        net.add_links_from([(r0, r1), (r1, r2), (r0, e0), (r2, e2)])
+            .unwrap();
+        let routers = [r0, r1, r2];
+        net.set_bgp_session_from(routers.iter().enumerate().flat_map(|(i, a)| {
+            routers[i + 1..]
+                .iter()
+                .map(move |b| (*a, *b, Some(IBgpPeer)))
+        }))
+        .unwrap();
+        net.set_bgp_session(r0, e0, Some(EBgp)).unwrap();
+        net.set_bgp_session(r2, e2, Some(EBgp)).unwrap();
+
+        // p0 is only advertised by e0, p2 only by e2, and p02 by both.
+        let p0 = P::from(0);
+        let p2 = P::from(2);
+        let p02 = P::from(3);
+        net.advertise_external_route(e0, p0, [65100], None, None)
+            .unwrap();
+        net.advertise_external_route(e2, p2, [65102], None, None)
+            .unwrap();
+        net.advertise_external_route(e0, p02, [65100], None, None)
+            .unwrap();
+        net.advertise_external_route(e2, p02, [65102], None, None)
+            .unwrap();
+
+        let mut state = net.get_forwarding_state();
+        for r in routers {
+            assert_eq!(state.unreachable_prefixes(r), Vec::<P>::new());
+        }
+
+        // after cutting r2 from r1, r2 can only reach the prefixes advertised by e2, and r0 and r1
+        // can only reach those advertised by e0.
+        net.remove_link(r1, r2).unwrap();
+        let mut state = net.get_forwarding_state();
+        assert_eq!(state.unreachable_prefixes(r2), vec![p0]);
+        assert_eq!(state.unreachable_prefixes(r0), vec![p2]);
+        assert_eq!(state.unreachable_prefixes(r1), vec![p2]);
+    }
+
     #[instantiate_tests(<SimplePrefix>)]
     mod simple {}
 
@@ -398,4 +770,54 @@ mod ipv4 {
             vec![vec![r2, r3, r4, e4]]
         );
     }
+
+    #[test]
+    fn compressible_entries() {
+        let mut net: Network<_, BasicEventQueue<Ipv4Prefix>> = Network::new(Default::default());
+        let r1 = net.add_router("R1");
+        let r2 = net.add_router("R2");
+        let r3 = net.add_router("R3");
+        net.add_links_from([(r1, r2), (r1, r3)]).unwrap();
+
+        let p0 = prefix!("10.0.0.0/24" as);
+        let p1 = prefix!("10.0.1.0/24" as);
+        let p2 = prefix!("10.0.2.0/24" as);
+        net.set_static_route(r1, p0, Some(StaticRoute::Direct(r2)))
+            .unwrap();
+        net.set_static_route(r1, p2, Some(StaticRoute::Direct(r2)))
+            .unwrap();
+
+        // 10.0.0.0/24 and 10.0.2.0/24 are not siblings
+        let state = net.get_forwarding_state();
+        assert_eq!(state.fib_entries(r1), vec![(p0, vec![r2]), (p2, vec![r2])]);
+        assert_eq!(state.compressible_entries(r1), 0);
+
+        // two sibling /24s with the same next-hop collapse into one /23.
+        net.set_static_route(r1, p1, Some(StaticRoute::Direct(r2)))
+            .unwrap();
+        assert_eq!(net.get_forwarding_state().compressible_entries(r1), 1);
+
+        // siblings with different next-hops cannot be merged
+        net.set_static_route(r1, p1, Some(StaticRoute::Direct(r3)))
+            .unwrap();
+        assert_eq!(net.get_forwarding_state().compressible_entries(r1), 0);
+
+        // a covered entry with the same next-hop as its parent is redundant.
+        net.set_static_route(
+            r1,
+            prefix!("10.0.0.128/25" as),
+            Some(StaticRoute::Direct(r2)),
+        )
+        .unwrap();
+        net.set_static_route(
+            r1,
+            prefix!("10.0.1.128/25" as),
+            Some(StaticRoute::Direct(r2)),
+        )
+        .unwrap();
+        let state = net.get_forwarding_state();
+        assert_eq!(state.fib_entries(r1).len(), 5);
+        assert_eq!(state.compressible_entries(r1), 1);
+        assert_eq!(state.compressible_entries(r2), 0);
+    }
 }
@@ -18,6 +18,8 @@
 #[generic_tests::define]
 mod t {
 
+    use std::collections::BTreeSet;
+
     use lazy_static::lazy_static;
 
     use crate::ospf::{GlobalOspf, LocalOspf, OspfImpl};
@@ -282,6 +284,255 @@ mod t {
         test_route!(net, rr, p, [rr, *R3, *R1, *E1]);
     }
 
+    #[test]
+    fn timed_recovery<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_topo::<P, Ospf>();
+        net.build_link_weights(link_weights, ()).unwrap();
+        net.build_ebgp_sessions().unwrap();
+        net.build_ibgp_full_mesh().unwrap();
+
+        let p = P::from(0);
+        net.advertise_external_route(*E1, p, [1], None, None)
+            .unwrap();
+        net.set_link_weight(*R3, *R4, LinkWeight::INFINITY).unwrap();
+        net.set_link_weight(*R4, *R3, LinkWeight::INFINITY).unwrap();
+        test_route!(net, *R3, p, [*R3, *R1, *E1]);
+
+        // In automatic simulation, the network converges, waits for the recovery, and converges
+        // again.
+        let at = net.fail_link_for(*R1, *R3, 10).unwrap();
+        assert!(net.progress().0 >= at);
+        assert!(net.scheduled_recoveries().is_empty());
+        assert_eq!(net.get_link_weight(*R1, *R3), Ok(1.0));
+        test_route!(net, *R3, p, [*R3, *R1, *E1]);
+
+        // In manual simulation, the outage can be observed step by step.
+        net.manual_simulation();
+        let start = net.progress().0;
+        let at = net.fail_link_for(*R1, *R3, 5).unwrap();
+        assert_eq!(at, start + 5);
+        assert_eq!(net.scheduled_recoveries().len(), 1);
+        test_bad_route!(black_hole, net, *R3, p, [*R3]);
+
+        let mut unreachable = false;
+        loop {
+            let now = net.progress().0;
+            let failed = net.get_link_weight(*R1, *R3) == Ok(LinkWeight::INFINITY);
+            assert_eq!(failed, now < at);
+            assert_eq!(
+                net.get_link_weight(*R3, *R1).unwrap().is_infinite(),
+                now < at
+            );
+            if now < at && net.get_forwarding_state().get_paths(*R3, p).is_err() {
+                unreachable = true;
+            }
+            if net.simulate_step().unwrap().is_none() {
+                break;
+            }
+        }
+        assert!(unreachable);
+        assert!(net.progress().0 > at);
+        assert!(net.scheduled_recoveries().is_empty());
+        test_route!(net, *R1, p, [*R1, *E1]);
+        test_route!(net, *R3, p, [*R3, *R1, *E1]);
+    }
+
+    #[test]
+    fn srlg_failure<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_topo::<P, Ospf>();
+        net.build_link_weights(link_weights, ()).unwrap();
+        net.build_ebgp_sessions().unwrap();
+        net.build_ibgp_full_mesh().unwrap();
+
+        let p = P::from(0);
+        net.advertise_external_route(*E1, p, [1], None, None)
+            .unwrap();
+        net.set_link_srlgs(*R3, *R1, [7]).unwrap();
+        net.set_link_srlgs(*R2, *R4, [7, 8]).unwrap();
+        net.set_link_srlgs(*R1, *E1, [8]).unwrap();
+        assert_eq!(net.get_link_srlgs(*R4, *R2), [7, 8].into_iter().collect());
+        assert_eq!(net.get_srlg_links(7), vec![(*R1, *R3), (*R2, *R4)]);
+        assert_eq!(
+            net.set_link_srlgs(*R1, *R4, [7]),
+            Err(NetworkError::LinkNotFound(*R1, *R4))
+        );
+        test_route!(net, *R3, p, [*R3, *R1, *E1]);
+        test_route!(net, *R4, p, [*R4, *R2, *R1, *E1]);
+
+        // all links in the group fail at once, partitioning the network.
+        assert_eq!(net.fail_srlg(7), Ok(vec![(*R1, *R3), (*R2, *R4)]));
+        for (a, b) in [(*R1, *R3), (*R3, *R1), (*R2, *R4), (*R4, *R2)] {
+            assert_eq!(net.get_link_weight(a, b), Ok(LinkWeight::INFINITY));
+        }
+        assert_eq!(net.get_link_weight(*R3, *R4), Ok(5.0));
+        test_route!(net, *R2, p, [*R2, *R1, *E1]);
+        test_bad_route!(black_hole, net, *R3, p, [*R3]);
+        test_bad_route!(black_hole, net, *R4, p, [*R4]);
+
+        // links to external routers are removed.
+        assert_eq!(net.fail_srlg(8), Ok(vec![(*E1, *R1), (*R2, *R4)]));
+        assert!(net.get_topology().find_edge(*R1, *E1).is_none());
+        assert_eq!(net.get_srlg_links(8), vec![(*R2, *R4)]);
+        test_bad_route!(black_hole, net, *R1, p, [*R1]);
+        assert_eq!(net.fail_srlg(9), Ok(vec![]));
+    }
+
+    #[test]
+    fn prefixes_rerouted_by_failure<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_topo::<P, Ospf>();
+        net.build_link_weights(link_weights, ()).unwrap();
+        net.set_link_weight(*R3, *R4, 10.0).unwrap();
+        net.set_link_weight(*R4, *R3, 10.0).unwrap();
+        net.build_ebgp_sessions().unwrap();
+        net.build_ibgp_full_mesh().unwrap();
+
+        // p is reachable via E1 (primary for R1 and R3) and E4 (primary for R2 and R4), while q is
+        // only reachable via E4. The link R3 -- R4 is only a backup.
+        let p = P::from(0);
+        let q = P::from(1);
+        net.advertise_external_route(*E4, q, [4, 5], None, None)
+            .unwrap();
+        net.advertise_external_route(*E1, p, [1], None, None)
+            .unwrap();
+        net.advertise_external_route(*E4, p, [4], None, None)
+            .unwrap();
+        test_route!(net, *R3, p, [*R3, *R1, *E1]);
+        test_route!(net, *R2, p, [*R2, *R4, *E4]);
+        if p != q {
+            test_route!(net, *R3, q, [*R3, *R1, *R2, *R4, *E4]);
+        }
+        let old = net.clone();
+
+        assert_eq!(
+            net.prefixes_rerouted_by_failure(*R1, *E1),
+            Ok(BTreeSet::from([p]))
+        );
+        assert_eq!(
+            net.prefixes_rerouted_by_failure(*R2, *R4),
+            Ok(BTreeSet::from([p, q]))
+        );
+        assert_eq!(
+            net.prefixes_rerouted_by_failure(*R3, *R4),
+            Ok(BTreeSet::new())
+        );
+        assert_eq!(
+            net.prefixes_rerouted_by_failure(*R1, *R4),
+            Err(NetworkError::LinkNotFound(*R1, *R4))
+        );
+
+        // the rerouted prefixes are exactly those forwarded over the link before the failure.
+        let mut fw = net.get_forwarding_state();
+        for (a, b) in net.get_topology().edge_indices().map(|e| {
+            let (a, b) = net.get_topology().edge_endpoints(e).unwrap();
+            (a.min(b), a.max(b))
+        }) {
+            let carried: BTreeSet<P> = [p, q]
+                .into_iter()
+                .filter(|x| {
+                    net.internal_indices().any(|r| {
+                        fw.get_paths(r, *x).unwrap_or_default().iter().any(|path| {
+                            path.windows(2)
+                                .any(|w| (w[0].min(w[1]), w[0].max(w[1])) == (a, b))
+                        })
+                    })
+                })
+                .collect();
+            assert_eq!(net.prefixes_rerouted_by_failure(a, b), Ok(carried));
+        }
+        assert!(net == old);
+    }
+
+    #[test]
+    fn single_failure_sweep<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_topo::<P, Ospf>();
+        net.build_link_weights(link_weights, ()).unwrap();
+        net.build_ebgp_sessions().unwrap();
+        net.build_ibgp_full_mesh().unwrap();
+
+        let p = P::from(0);
+        net.advertise_external_route(*E1, p, [1], None, None)
+            .unwrap();
+        net.advertise_external_route(*E4, p, [4], None, None)
+            .unwrap();
+
+        // p is dual-homed, so no single link failure disconnects any router.
+        assert_eq!(net.reachability_under_single_failures(p), Ok(vec![]));
+
+        // the network is restored after the sweep.
+        test_route!(net, *R1, p, [*R1, *E1]);
+        test_route!(net, *R2, p, [*R2, *R4, *E4]);
+        test_route!(net, *R3, p, [*R3, *R1, *E1]);
+        test_route!(net, *R4, p, [*R4, *E4]);
+        assert_eq!(net.get_link_weight(*R1, *R2), Ok(5.0));
+
+        // once p is single-homed, losing the link to E1 disconnects all routers.
+        net.withdraw_external_route(*E4, p).unwrap();
+        assert_eq!(
+            net.reachability_under_single_failures(p),
+            Ok(vec![(*E1, *R1)])
+        );
+        test_route!(net, *R3, p, [*R3, *R1, *E1]);
+
+        // a router that is only connected over a single link depends on that link.
+        let r5 = net.add_router("R5");
+        net.add_link(*R4, r5).unwrap();
+        net.set_link_weight(*R4, r5, 1.0).unwrap();
+        net.set_link_weight(r5, *R4, 1.0).unwrap();
+        for r in [*R1, *R2, *R3, *R4] {
+            net.set_bgp_session(r, r5, Some(BgpSessionType::IBgpPeer))
+                .unwrap();
+        }
+        assert_eq!(
+            net.reachability_under_single_failures(p),
+            Ok(vec![(*E1, *R1), (*R4, r5)])
+        );
+        net.advertise_external_route(*E4, p, [4], None, None)
+            .unwrap();
+        assert_eq!(
+            net.reachability_under_single_failures(p),
+            Ok(vec![(*R4, r5)])
+        );
+        test_route!(net, r5, p, [r5, *R4, *E4]);
+    }
+
+    #[test]
+    fn frozen_router<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_topo::<P, Ospf>();
+        net.build_link_weights(link_weights, ()).unwrap();
+        net.build_ebgp_sessions().unwrap();
+        net.build_ibgp_full_mesh().unwrap();
+
+        let p = P::from(0);
+        net.advertise_external_route(*E1, p, [1], None, None)
+            .unwrap();
+        net.advertise_external_route(*E4, p, [4], None, None)
+            .unwrap();
+
+        test_route!(net, *R2, p, [*R2, *R4, *E4]);
+        test_route!(net, *R4, p, [*R4, *E4]);
+
+        net.freeze_router(*R2).unwrap();
+        assert!(net.is_frozen(*R2));
+        net.withdraw_external_route(*E4, p).unwrap();
+
+        // all other routers reconverge around R2
+        test_route!(net, *R1, p, [*R1, *E1]);
+        test_route!(net, *R3, p, [*R3, *R1, *E1]);
+        let r4_route = net.get_internal_router(*R4).unwrap().bgp.get_route(p);
+        assert_eq!(r4_route.map(|e| e.route.next_hop), Some(*R1));
+
+        // R2 still uses its stale state
+        let r2 = net.get_internal_router(*R2).unwrap();
+        assert_eq!(r2.bgp.get_route(p).map(|e| e.route.next_hop), Some(*R4));
+        assert_eq!(r2.get_next_hop(p), vec![*R4]);
+
+        net.thaw_router(*R2).unwrap();
+        assert!(!net.is_frozen(*R2));
+        test_route!(net, *R1, p, [*R1, *E1]);
+        test_route!(net, *R2, p, [*R2, *R1, *E1]);
+        test_route!(net, *R3, p, [*R3, *R1, *E1]);
+    }
+
     #[instantiate_tests(<SinglePrefix, GlobalOspf>)]
     mod single_global {}
 
@@ -18,18 +18,25 @@
 #[generic_tests::define]
 mod t {
 
-    use std::collections::{BTreeMap, BTreeSet};
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        sync::{Arc, Mutex},
+    };
 
     use crate::{
-        bgp::{BgpRoute, BgpSessionType::*},
+        bgp::{BgpRoute, BgpSessionType::*, GRACEFUL_SHUTDOWN},
         builder::{constant_link_weight, equal_preferences, NetworkBuilder},
         config::{ConfigExpr::IgpLinkWeight, NetworkConfig},
-        event::BasicEventQueue,
-        network::Network,
-        ospf::{global::GlobalOspf, local::LocalOspf, OspfImpl, OspfProcess},
+        event::{BasicEventQueue, ConvergenceEvent, EventQueue},
+        formatter::NetworkFormatter,
+        forwarding_state::ForwardingState,
+        interactive::InteractiveNetwork,
+        network::{Network, SessionInfo, INTERNAL_AS},
+        ospf::{global::GlobalOspf, local::LocalOspf, LinkWeight, OspfImpl, OspfProcess},
         prelude::BgpSessionType,
         route_map::{
-            RouteMap, RouteMapDirection::*, RouteMapFlow::*, RouteMapSet as Set, RouteMapState::*,
+            RouteMap, RouteMapBuilder, RouteMapDirection::*, RouteMapFlow::*, RouteMapSet as Set,
+            RouteMapState::*,
         },
         router::StaticRoute::*,
         types::{
@@ -861,6 +868,26 @@ mod t {
         );
     }
 
+    #[test]
+    fn igp_ecmp_width<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_net_bgp_load_balancing::<P, Ospf>();
+
+        // R1 reaches R4 via R2 and R3 with the same cost of 3.
+        assert_eq!(net.igp_ecmp_width(*R1, *R4).unwrap(), 2);
+        assert_eq!(net.igp_ecmp_width(*R1, *R3).unwrap(), 1);
+        assert_eq!(net.igp_ecmp_width(*R1, *R1).unwrap(), 0);
+
+        // the width does not depend on load balancing being enabled.
+        net.set_load_balancing(*R1, true).unwrap();
+        assert_eq!(net.igp_ecmp_width(*R1, *R4).unwrap(), 2);
+
+        // the path via R2 becomes more expensive
+        net.set_link_weight(*R1, *R2, 3.0).unwrap();
+        assert_eq!(net.igp_ecmp_width(*R1, *R4).unwrap(), 1);
+
+        assert!(net.igp_ecmp_width(*E1, *R4).is_err());
+    }
+
     #[test]
     fn test_static_route_load_balancing<P: Prefix, Ospf: OspfImpl>() {
         let mut net = get_test_net_bgp_load_balancing::<P, Ospf>();
@@ -963,7 +990,7 @@ mod t {
             next_hop: *E1,
             local_pref: None,
             med: None,
-            community: Default::default(),
+            communities: Default::default(),
             originator_id: None,
             cluster_list: Vec::new(),
         };
@@ -973,7 +1000,7 @@ mod t {
             next_hop: *R1,
             local_pref: Some(100),
             med: Some(0),
-            community: Default::default(),
+            communities: Default::default(),
             originator_id: None,
             cluster_list: Vec::new(),
         };
@@ -983,7 +1010,7 @@ mod t {
             next_hop: *E4,
             local_pref: None,
             med: None,
-            community: Default::default(),
+            communities: Default::default(),
             originator_id: None,
             cluster_list: Vec::new(),
         };
@@ -993,13 +1020,13 @@ mod t {
             next_hop: *R4,
             local_pref: Some(100),
             med: Some(0),
-            community: Default::default(),
+            communities: Default::default(),
             originator_id: None,
             cluster_list: Vec::new(),
         };
         let route_r42 = BgpRoute {
             originator_id: Some(*R4),
-            cluster_list: vec![*R2],
+            cluster_list: vec![R2.index() as u32],
             ..route_r4.clone()
         };
         assert_eq!(BTreeMap::from_iter(state.incoming(*E1)), btreemap! {});
@@ -1039,7 +1066,7 @@ mod t {
             next_hop: *E4,
             local_pref: None,
             med: None,
-            community: Default::default(),
+            communities: Default::default(),
             originator_id: None,
             cluster_list: Vec::new(),
         };
@@ -1049,13 +1076,13 @@ mod t {
             next_hop: *R4,
             local_pref: Some(100),
             med: Some(0),
-            community: Default::default(),
+            communities: Default::default(),
             originator_id: None,
             cluster_list: Vec::new(),
         };
         let route_r42 = BgpRoute {
             originator_id: Some(*R4),
-            cluster_list: vec![*R2],
+            cluster_list: vec![R2.index() as u32],
             ..route_r4.clone()
         };
         let route_r421 = BgpRoute {
@@ -1064,7 +1091,7 @@ mod t {
             next_hop: *R1,
             local_pref: None,
             med: None,
-            community: Default::default(),
+            communities: Default::default(),
             originator_id: None,
             cluster_list: Vec::new(),
         };
@@ -1137,7 +1164,7 @@ mod t {
             next_hop: *E1,
             local_pref: None,
             med: None,
-            community: Default::default(),
+            communities: Default::default(),
             originator_id: None,
             cluster_list: Vec::new(),
         };
@@ -1147,7 +1174,7 @@ mod t {
             next_hop: *R1,
             local_pref: Some(100),
             med: Some(0),
-            community: Default::default(),
+            communities: Default::default(),
             originator_id: None,
             cluster_list: Vec::new(),
         };
@@ -1157,7 +1184,7 @@ mod t {
             next_hop: *E4,
             local_pref: None,
             med: None,
-            community: Default::default(),
+            communities: Default::default(),
             originator_id: None,
             cluster_list: Vec::new(),
         };
@@ -1167,13 +1194,13 @@ mod t {
             next_hop: *R4,
             local_pref: Some(100),
             med: Some(0),
-            community: Default::default(),
+            communities: Default::default(),
             originator_id: None,
             cluster_list: Vec::new(),
         };
         let route_r42 = BgpRoute {
             originator_id: Some(*R4),
-            cluster_list: vec![*R2],
+            cluster_list: vec![R2.index() as u32],
             ..route_r4.clone()
         };
         assert_eq!(
@@ -1216,7 +1243,7 @@ mod t {
             next_hop: *E4,
             local_pref: None,
             med: None,
-            community: Default::default(),
+            communities: Default::default(),
             originator_id: None,
             cluster_list: Vec::new(),
         };
@@ -1226,13 +1253,13 @@ mod t {
             next_hop: *R4,
             local_pref: Some(100),
             med: Some(0),
-            community: Default::default(),
+            communities: Default::default(),
             originator_id: None,
             cluster_list: Vec::new(),
         };
         let route_r42 = BgpRoute {
             originator_id: Some(*R4),
-            cluster_list: vec![*R2],
+            cluster_list: vec![R2.index() as u32],
             ..route_r4.clone()
         };
         let route_r421 = BgpRoute {
@@ -1241,7 +1268,7 @@ mod t {
             next_hop: *R1,
             local_pref: None,
             med: Some(0),
-            community: Default::default(),
+            communities: Default::default(),
             originator_id: None,
             cluster_list: Vec::new(),
         };
@@ -1356,6 +1383,1273 @@ mod t {
         );
     }
 
+    #[test]
+    fn summary<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_net_bgp::<P, Ospf>();
+        let p = P::from(0);
+
+        net.set_bgp_session(*R1, *R2, Some(IBgpClient)).unwrap();
+        net.set_bgp_route_map(
+            *R1,
+            *E1,
+            Incoming,
+            RouteMap::new(10, Allow, vec![], vec![Set::LocalPref(Some(50))], Continue),
+        )
+        .unwrap();
+        net.set_bgp_route_map(
+            *R1,
+            *E1,
+            Incoming,
+            RouteMap::new(20, Allow, vec![], vec![], Continue),
+        )
+        .unwrap();
+        net.set_bgp_route_map(
+            *R4,
+            *R2,
+            Outgoing,
+            RouteMap::new(10, Allow, vec![], vec![], Continue),
+        )
+        .unwrap();
+        net.advertise_external_route(*E1, p, [65101], None, None)
+            .unwrap();
+        net.advertise_external_route(*E4, p, [65104], None, None)
+            .unwrap();
+
+        let summary = net.summary();
+        assert_eq!(summary.num_internal_routers, 4);
+        assert_eq!(summary.num_external_routers, 2);
+        assert_eq!(summary.num_links, 7);
+        assert_eq!(summary.num_ebgp_sessions, 2);
+        assert_eq!(summary.num_ibgp_peer_sessions, 5);
+        assert_eq!(summary.num_ibgp_client_sessions, 1);
+        assert_eq!(summary.num_bgp_sessions(), 8);
+        assert_eq!(summary.num_route_maps, 3);
+        assert_eq!(summary.num_prefixes, 1);
+        assert!(summary.is_converged());
+
+        let report = summary.fmt(&net);
+        assert!(report.contains("4 internal, 2 external"));
+        assert!(report.contains("2 eBGP, 5 iBGP peer, 1 iBGP client"));
+        assert!(report.ends_with("converged"));
+    }
+
+    #[test]
+    fn sessions<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_net_bgp::<P, Ospf>();
+
+        net.set_bgp_session(*R2, *R1, Some(IBgpClient)).unwrap();
+        net.set_bgp_route_map(
+            *R1,
+            *E1,
+            Incoming,
+            RouteMap::new(10, Allow, vec![], vec![], Continue),
+        )
+        .unwrap();
+        net.set_bgp_route_map(
+            *R1,
+            *E1,
+            Incoming,
+            RouteMap::new(20, Allow, vec![], vec![], Continue),
+        )
+        .unwrap();
+        net.set_bgp_route_map(
+            *R4,
+            *R2,
+            Outgoing,
+            RouteMap::new(10, Allow, vec![], vec![], Continue),
+        )
+        .unwrap();
+
+        let session = |source: RouterId, target: RouterId, ty, established| SessionInfo::<P> {
+            source,
+            target,
+            session_type: ty,
+            established,
+            source_incoming: vec![],
+            source_outgoing: vec![],
+            target_incoming: vec![],
+            target_outgoing: vec![],
+        };
+
+        let sessions = net.sessions().collect::<Vec<_>>();
+        assert_eq!(
+            sessions
+                .iter()
+                .map(|s| (s.source, s.target, s.session_type, s.established))
+                .collect::<Vec<_>>(),
+            vec![
+                (*E1, *R1, EBgp, true),
+                (*R1, *R3, IBgpPeer, true),
+                (*R1, *R4, IBgpPeer, true),
+                (*R2, *R1, IBgpClient, true),
+                (*R2, *R3, IBgpPeer, true),
+                (*R2, *R4, IBgpPeer, true),
+                (*R3, *R4, IBgpPeer, true),
+                (*R4, *E4, EBgp, true),
+            ]
+        );
+        assert_eq!(
+            sessions
+                .iter()
+                .map(|s| s.num_route_maps())
+                .collect::<Vec<_>>(),
+            vec![2, 0, 0, 0, 0, 1, 0, 0]
+        );
+
+        // the route-maps are reported per endpoint and direction
+        assert_eq!(
+            sessions[0],
+            SessionInfo {
+                target_incoming: vec![
+                    RouteMap::new(10, Allow, vec![], vec![], Continue),
+                    RouteMap::new(20, Allow, vec![], vec![], Continue),
+                ],
+                ..session(*E1, *R1, EBgp, true)
+            }
+        );
+        assert_eq!(
+            sessions[5],
+            SessionInfo {
+                target_outgoing: vec![RouteMap::new(10, Allow, vec![], vec![], Continue)],
+                ..session(*R2, *R4, IBgpPeer, true)
+            }
+        );
+        assert_eq!(sessions[1], session(*R1, *R3, IBgpPeer, true));
+        assert_eq!(net.sessions().count(), net.summary().num_bgp_sessions());
+
+        // disconnect R4 from all other internal routers
+        net.set_link_weight(*R2, *R4, LinkWeight::INFINITY).unwrap();
+        net.set_link_weight(*R4, *R2, LinkWeight::INFINITY).unwrap();
+        net.set_link_weight(*R3, *R4, LinkWeight::INFINITY).unwrap();
+        net.set_link_weight(*R4, *R3, LinkWeight::INFINITY).unwrap();
+
+        assert_eq!(
+            net.sessions()
+                .filter(|s| !s.established)
+                .map(|s| (s.source, s.target))
+                .collect::<Vec<_>>(),
+            vec![(*R1, *R4), (*R2, *R4), (*R3, *R4)]
+        );
+    }
+
+    #[test]
+    fn link_bundle<P: Prefix, Ospf: OspfImpl>() {
+        // R1 reaches R4 over R2 and R3 with equal cost, where R1 and R2 are connected by a bundle
+        // of two links.
+        let mut net = get_test_net::<P, Ospf>();
+        net.remove_link(*R2, *R3).unwrap();
+        net.add_link_bundle(*R1, *R2, [10.0, 10.0]).unwrap();
+        net.build_ibgp_full_mesh().unwrap();
+        net.set_bgp_session(*R4, *E4, Some(EBgp)).unwrap();
+        net.set_load_balancing(*R1, true).unwrap();
+        let p = P::from(0);
+        net.advertise_external_route(*E4, p, [65104], None, None)
+            .unwrap();
+
+        let share = |net: &Network<P, BasicEventQueue<P>, Ospf>| {
+            let mut share = net.get_forwarding_state().get_traffic_share(*R1, p);
+            share.sort_by_key(|(r, _)| *r);
+            share
+        };
+
+        assert_eq!(net.get_link_bundle(*R2, *R1).unwrap().capacity(), 20.0);
+        assert_eq!(share(&net), vec![(*R2, 2.0 / 3.0), (*R3, 1.0 / 3.0)]);
+
+        // failing one member keeps the adjacency up, but changes the traffic share.
+        assert_eq!(net.set_link_bundle_member(*R1, *R2, 0, false), Ok(true));
+        assert_eq!(net.get_link_weight(*R1, *R2), Ok(100.0));
+        assert_eq!(net.get_link_bundle(*R1, *R2).unwrap().capacity(), 10.0);
+        assert_eq!(share(&net), vec![(*R2, 0.5), (*R3, 0.5)]);
+
+        // failing the last member tears down the adjacency.
+        assert_eq!(net.set_link_bundle_member(*R1, *R2, 1, false), Ok(true));
+        assert_eq!(share(&net), vec![(*R3, 1.0)]);
+
+        // recovering a member restores the adjacency
+        assert_eq!(net.set_link_bundle_member(*R1, *R2, 0, true), Ok(false));
+        assert_eq!(net.get_link_weight(*R2, *R1), Ok(100.0));
+        assert_eq!(share(&net), vec![(*R2, 0.5), (*R3, 0.5)]);
+
+        assert_eq!(
+            net.set_link_bundle_member(*R1, *R2, 2, false),
+            Err(NetworkError::LinkBundleMemberNotFound(*R1, *R2, 2))
+        );
+        assert_eq!(
+            net.add_link_bundle(*R1, *R3, []),
+            Err(NetworkError::EmptyLinkBundle(*R1, *R3))
+        );
+    }
+
+    /// The route from E2 is preferred at R1. R1 reaches R2 over two equal-cost paths, but R3
+    /// forwards the traffic to its own egress E3.
+    ///
+    /// ```text
+    /// R1 --2-- R2 ---- E2
+    ///  |      /
+    ///  1    1
+    ///  |  /
+    /// R3 ---- E3
+    /// ```
+    #[test]
+    fn min_cut_to_prefix<P: Prefix, Ospf: OspfImpl>() {
+        let mut net: Network<P, BasicEventQueue<P>, Ospf> = Network::default();
+        let p = P::from(0);
+        let r1 = net.add_router("R1");
+        let r2 = net.add_router("R2");
+        let r3 = net.add_router("R3");
+        let e2 = net.add_external_router("E2", AsId(65102));
+        let e3 = net.add_external_router("E3", AsId(65103));
+        for (a, b, w) in [(r1, r2, 2.0), (r1, r3, 1.0), (r2, r3, 1.0)] {
+            net.add_link(a, b).unwrap();
+            net.set_link_weight(a, b, w).unwrap();
+            net.set_link_weight(b, a, w).unwrap();
+        }
+        net.add_link(r2, e2).unwrap();
+        net.add_link(r3, e3).unwrap();
+        net.build_ibgp_full_mesh().unwrap();
+        net.set_bgp_session(r2, e2, Some(EBgp)).unwrap();
+        net.set_bgp_session(r3, e3, Some(EBgp)).unwrap();
+        net.set_bgp_route_map(
+            r1,
+            r2,
+            Incoming,
+            RouteMapBuilder::new()
+                .order(10)
+                .allow()
+                .set_local_pref(200)
+                .build(),
+        )
+        .unwrap();
+
+        // nothing is advertised yet
+        assert_eq!(net.min_cut_to_prefix(r1, p), Ok(0));
+
+        // the only egress is E2. Without load balancing, R1 forwards over a single path.
+        net.advertise_external_route(e2, p, [65102], None, None)
+            .unwrap();
+        assert_eq!(net.min_cut_to_prefix(r1, p), Ok(1));
+        assert_eq!(net.min_cut_to_prefix(r3, p), Ok(1));
+
+        // with load balancing, R1 uses two paths towards E2, but both traverse R2 -- E2.
+        net.set_load_balancing(r1, true).unwrap();
+        assert_eq!(
+            net.get_forwarding_state().get_paths(r1, p).unwrap().len(),
+            2
+        );
+        assert_eq!(net.min_cut_to_prefix(r1, p), Ok(1));
+        net.set_load_balancing(r1, false).unwrap();
+
+        // E3 is a second egress, but R1 and R2 still only forward towards E2. Although R1 has two
+        // link-disjoint physical paths towards the egresses, a single link failure disconnects it
+        // in the forwarding graph.
+        net.advertise_external_route(e3, p, [65103], None, None)
+            .unwrap();
+        assert_eq!(
+            net.get_forwarding_state().get_paths(r1, p),
+            Ok(vec![vec![r1, r2, e2]])
+        );
+        assert_eq!(net.min_cut_to_prefix(r1, p), Ok(1));
+        assert_eq!(net.min_cut_to_prefix(r2, p), Ok(1));
+
+        // with load balancing, R3 deflects the traffic to its own egress E3.
+        net.set_load_balancing(r1, true).unwrap();
+        assert_eq!(net.min_cut_to_prefix(r1, p), Ok(2));
+
+        assert_eq!(
+            net.min_cut_to_prefix(e2, p),
+            Err(NetworkError::DeviceIsExternalRouter(e2))
+        );
+    }
+
+    /// All traffic leaves the network at E1, so R1 is the only transit router.
+    ///
+    /// ```text
+    ///         R2
+    ///         |
+    /// E1 --- R1 --- R3
+    ///         |
+    ///         R4
+    /// ```
+    #[test]
+    fn forwarding_centrality<P: Prefix, Ospf: OspfImpl>() {
+        let mut net: Network<P, BasicEventQueue<P>, Ospf> = Network::default();
+        let r1 = net.add_router("R1");
+        let r2 = net.add_router("R2");
+        let r3 = net.add_router("R3");
+        let r4 = net.add_router("R4");
+        let e1 = net.add_external_router("E1", AsId(65101));
+        net.add_link(e1, r1).unwrap();
+        for r in [r2, r3, r4] {
+            net.add_link(r1, r).unwrap();
+            net.set_bgp_session(r1, r, Some(BgpSessionType::IBgpClient))
+                .unwrap();
+        }
+        net.build_link_weights(constant_link_weight, 100.0).unwrap();
+        net.set_bgp_session(e1, r1, Some(BgpSessionType::EBgp))
+            .unwrap();
+        let p = P::from(0);
+
+        // nothing is advertised yet
+        assert_eq!(net.forwarding_centrality([p]), Default::default());
+
+        net.advertise_external_route(e1, p, [65101], None, None)
+            .unwrap();
+        let centrality = net.forwarding_centrality([p]);
+        assert_eq!(centrality.num_paths, 4);
+        assert_eq!(centrality.router(r1), 3);
+        for r in [r2, r3, r4, e1] {
+            assert_eq!(centrality.router(r), 0);
+        }
+        assert_eq!(centrality.most_central_routers(), vec![r1]);
+        assert_eq!(centrality.link(r1, e1), 4);
+        assert_eq!(centrality.link(r2, r1), 1);
+        assert_eq!(centrality.link(r1, r2), 0);
+
+        // the computation is scoped to the given prefixes.
+        assert_eq!(net.forwarding_centrality([]), Default::default());
+    }
+
+    #[test]
+    fn probe<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_net_bgp::<P, Ospf>();
+        let p = P::from(0);
+
+        assert_eq!(
+            net.probe(*R1, p),
+            Err(NetworkError::ForwardingBlackHole(vec![*R1]))
+        );
+
+        net.advertise_external_route(*E1, p, [65101, 65102, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(*E4, p, [65104, 100], None, None)
+            .unwrap();
+
+        let hops = net.probe(*R1, p).unwrap();
+        assert_eq!(
+            hops.iter().map(|h| h.router).collect::<Vec<_>>(),
+            vec![*R1, *R3, *R2, *R4, *E4]
+        );
+        // R1 prefers the route from E4 due to its AS path, while R3 and R2 simply follow the IGP
+        // towards R4.
+        for hop in &hops[..3] {
+            let route = hop.route.as_ref().unwrap();
+            assert_eq!(route.route.next_hop, *R4);
+            assert_eq!(route.from_id, *R4);
+        }
+        assert_eq!(hops[3].route.as_ref().unwrap().from_id, *E4);
+        assert_eq!(hops[4].route, None);
+
+        // the hops are consistent: each next-hop is the following hop, and the BGP next-hop of
+        // each route lies further along the path.
+        for src in [*R1, *R2, *R3, *R4] {
+            let hops = net.probe(src, p).unwrap();
+            assert_eq!(hops.last().unwrap().next_hop, None);
+            for (i, hop) in hops.iter().enumerate().rev().skip(1) {
+                assert_eq!(hop.next_hop, Some(hops[i + 1].router));
+                let nh = hop.route.as_ref().unwrap().route.next_hop;
+                assert!(hops[i + 1..].iter().any(|h| h.router == nh));
+            }
+        }
+    }
+
+    #[test]
+    fn canonicalize<P: Prefix, Ospf: OspfImpl>() {
+        // build the same network twice, but in a different order.
+        let build = |reverse: bool| {
+            let mut net: Network<P, BasicEventQueue<P>, Ospf> = Network::default();
+            let (r1, r2, e1) = if reverse {
+                let e1 = net.add_external_router("E1", AsId(65101));
+                let r2 = net.add_router("R2");
+                let r1 = net.add_router("R1");
+                (r1, r2, e1)
+            } else {
+                let r1 = net.add_router("R1");
+                let r2 = net.add_router("R2");
+                let e1 = net.add_external_router("E1", AsId(65101));
+                (r1, r2, e1)
+            };
+            let links = if reverse {
+                [(r2, r1), (e1, r1)]
+            } else {
+                [(r1, e1), (r1, r2)]
+            };
+            for (a, b) in links {
+                net.add_link(a, b).unwrap();
+            }
+            net.set_link_weight(r1, r2, 5.0).unwrap();
+            net.set_link_weight(r2, r1, 3.0).unwrap();
+            let (a, b) = if reverse { (r2, r1) } else { (r1, r2) };
+            net.set_bgp_session(a, b, Some(IBgpPeer)).unwrap();
+            net.set_bgp_session(e1, r1, Some(EBgp)).unwrap();
+            net.set_bgp_route_map(
+                r1,
+                e1,
+                Incoming,
+                RouteMapBuilder::new()
+                    .order(10)
+                    .allow()
+                    .set_local_pref(200)
+                    .build(),
+            )
+            .unwrap();
+            net.set_static_route(r2, P::from(0), Some(Indirect(r1)))
+                .unwrap();
+            net
+        };
+        let net_a = build(false);
+        let mut net_b = build(true);
+        assert_eq!(net_a.canonicalize(), net_b.canonicalize());
+        assert!(net_a.canonicalize().diff(&net_b.canonicalize()).is_empty());
+        assert_eq!(
+            net_a.canonicalize().sessions,
+            vec!["E1 -- R1: eBGP".to_string(), "R1 -- R2: iBGP".to_string()]
+        );
+
+        // a single differing weight shows up as a single changed line.
+        let r1 = net_b.get_router_id("R1").unwrap();
+        let r2 = net_b.get_router_id("R2").unwrap();
+        net_b.set_link_weight(r2, r1, 4.0).unwrap();
+        assert_ne!(net_a.canonicalize(), net_b.canonicalize());
+        assert_eq!(
+            net_a.canonicalize().diff(&net_b.canonicalize()),
+            vec![
+                "- links: R2 -> R1: weight 3, Backbone".to_string(),
+                "+ links: R2 -> R1: weight 4, Backbone".to_string(),
+            ]
+        );
+
+        // BGP settings that are not part of the `Config` are also included.
+        let mut net_c = build(false);
+        let r1 = net_c.get_router_id("R1").unwrap();
+        let e1 = net_c.get_router_id("E1").unwrap();
+        net_c.set_local_as(r1, e1, Some(AsId(65500))).unwrap();
+        net_c.set_bgp_multipath(r1, true).unwrap();
+        assert_eq!(
+            net_a.canonicalize().diff(&net_c.canonicalize()),
+            vec![
+                "+ bgp: R1: local-as AS65500 to E1".to_string(),
+                "+ bgp: R1: multipath".to_string(),
+            ]
+        );
+    }
+
+    /// Anycast: three external routers of the same AS advertise the same prefix with identical
+    /// attributes at different border routers. Every internal router picks the egress with the
+    /// smallest IGP cost (hot-potato routing).
+    ///
+    /// ```text
+    /// E1            E3            E5
+    /// |             |             |
+    /// R1 -1- R2 -2- R3 -2- R4 -1- R5
+    /// ```
+    #[test]
+    fn anycast_nearest_egress<P: Prefix, Ospf: OspfImpl>() {
+        let mut net: Network<P, BasicEventQueue<P>, Ospf> = Network::default();
+        let r1 = net.add_router("R1");
+        let r2 = net.add_router("R2");
+        let r3 = net.add_router("R3");
+        let r4 = net.add_router("R4");
+        let r5 = net.add_router("R5");
+        let e1 = net.add_external_router("E1", AsId(65100));
+        let e3 = net.add_external_router("E3", AsId(65100));
+        let e5 = net.add_external_router("E5", AsId(65100));
+
+        for (a, b, w) in [(r1, r2, 1.0), (r2, r3, 2.0), (r3, r4, 2.0), (r4, r5, 1.0)] {
+            net.add_link(a, b).unwrap();
+            net.set_link_weight(a, b, w).unwrap();
+            net.set_link_weight(b, a, w).unwrap();
+        }
+        for (e, r) in [(e1, r1), (e3, r3), (e5, r5)] {
+            net.add_link(e, r).unwrap();
+            net.set_bgp_session(e, r, Some(EBgp)).unwrap();
+        }
+        let routers = [r1, r2, r3, r4, r5];
+        for (i, a) in routers.iter().enumerate() {
+            for b in &routers[i + 1..] {
+                net.set_bgp_session(*a, *b, Some(IBgpPeer)).unwrap();
+            }
+        }
+
+        let p = P::from(0);
+        for e in [e1, e3, e5] {
+            net.advertise_external_route(e, p, [65100, 100], None, None)
+                .unwrap();
+        }
+
+        test_route!(net, r1, p, [r1, e1]);
+        test_route!(net, r2, p, [r2, r1, e1]);
+        test_route!(net, r3, p, [r3, e3]);
+        test_route!(net, r4, p, [r4, r5, e5]);
+        test_route!(net, r5, p, [r5, e5]);
+
+        // withdrawing the nearest origin moves R1 and R2 to the next-closest egress.
+        net.withdraw_external_route(e1, p).unwrap();
+        test_route!(net, r1, p, [r1, r2, r3, e3]);
+        test_route!(net, r2, p, [r2, r3, e3]);
+        test_route!(net, r4, p, [r4, r5, e5]);
+
+        net.withdraw_external_route(e5, p).unwrap();
+        test_route!(net, r4, p, [r4, r3, e3]);
+        test_route!(net, r5, p, [r5, r4, r3, e3]);
+
+        // re-advertising the origin restores the nearest egress.
+        net.advertise_external_route(e1, p, [65100, 100], None, None)
+            .unwrap();
+        test_route!(net, r2, p, [r2, r1, e1]);
+        test_route!(net, r5, p, [r5, r4, r3, e3]);
+    }
+
+    #[test]
+    fn min_weight_change_for_egress<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_net_bgp::<P, Ospf>();
+        let p = P::from(0);
+        net.advertise_external_route(*E1, p, [65101, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(*E4, p, [65104, 100], None, None)
+            .unwrap();
+        test_route!(net, *R2, p, [*R2, *R4, *E4]);
+        let old = net.clone();
+
+        // R2 already uses R4
+        assert_eq!(
+            net.min_weight_change_for_egress(*R2, p, *R4),
+            Ok(Some(vec![]))
+        );
+
+        // R2 reaches R1 with cost 2 and R4 with cost 1. Increasing R2 -> R4 to 3 flips the egress.
+        let change = net.min_weight_change_for_egress(*R2, p, *R1).unwrap();
+        assert_eq!(change, Some(vec![(*R2, *R4, 3.0)]));
+        assert!(net == old);
+        for (a, b, w) in change.unwrap() {
+            net.set_link_weight(a, b, w).unwrap();
+        }
+        test_route!(net, *R2, p, [*R2, *R3, *R1, *E1]);
+
+        // hot-potato routing cannot overrule a longer AS path.
+        let mut net = old;
+        net.advertise_external_route(*E1, p, [65101, 65102, 100], None, None)
+            .unwrap();
+        assert_eq!(net.min_weight_change_for_egress(*R2, p, *R1), Ok(None));
+    }
+
+    #[test]
+    fn withdrawal_impact<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_net_bgp::<P, Ospf>();
+        let p = P::from(0);
+        net.advertise_external_route(*E1, p, [65101, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(*E4, p, [65104, 100], None, None)
+            .unwrap();
+        test_route!(net, *R1, p, [*R1, *E1]);
+        test_route!(net, *R2, p, [*R2, *R4, *E4]);
+        test_route!(net, *R3, p, [*R3, *R1, *E1]);
+        test_route!(net, *R4, p, [*R4, *E4]);
+        let old = net.clone();
+
+        // E1 is a redundant origin. R1 and R3 change their egress, but nobody is disconnected.
+        assert_eq!(
+            net.withdrawal_impact(*E1, p),
+            Ok((btreeset! {*R1, *R3}, btreeset! {}))
+        );
+        assert!(net == old);
+
+        // E4 is the only remaining origin. Withdrawing it black-holes everyone.
+        net.withdraw_external_route(*E1, p).unwrap();
+        let all = btreeset! {*R1, *R2, *R3, *R4};
+        assert_eq!(net.withdrawal_impact(*E4, p), Ok((all.clone(), all)));
+
+        // withdrawing a route that is not advertised has no impact.
+        assert_eq!(
+            net.withdrawal_impact(*E1, p),
+            Ok((btreeset! {}, btreeset! {}))
+        );
+        assert!(net.withdrawal_impact(*R1, p).is_err());
+    }
+
+    #[test]
+    fn verify_fixed_point<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_net_bgp::<P, Ospf>();
+        let p = P::from(0);
+
+        net.set_bgp_session(*R1, *R2, Some(IBgpClient)).unwrap();
+        net.advertise_external_route(*E1, p, [65101, 100], None, None)
+            .unwrap();
+        net.advertise_external_route(*E4, p, [65104, 100], None, None)
+            .unwrap();
+        assert_eq!(net.verify_fixed_point(), vec![]);
+
+        // an update waiting in the queue is not yet processed by its receiver.
+        net.manual_simulation();
+        net.withdraw_external_route(*E1, p).unwrap();
+        assert_eq!(net.verify_fixed_point(), vec![(*R1, p)]);
+        net.simulate().unwrap();
+        assert_eq!(net.verify_fixed_point(), vec![]);
+
+        // corrupt the selected route of R2
+        net.get_internal_router_mut(*R2)
+            .unwrap()
+            .bgp
+            .rib
+            .get_mut(&p)
+            .unwrap()
+            .route
+            .next_hop = *R3;
+        assert_eq!(net.verify_fixed_point(), vec![(*R2, p)]);
+    }
+
+    #[test]
+    fn misuse_errors<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_net::<P, Ospf>();
+        let p = P::from(0);
+        let x: RouterId = 99.into();
+
+        // unknown routers
+        assert_eq!(net.add_link(*R1, x), Err(NetworkError::DeviceNotFound(x)));
+        assert_eq!(
+            net.add_links_from([(*R1, x)]),
+            Err(NetworkError::DeviceNotFound(x))
+        );
+        assert_eq!(
+            net.set_bgp_session(*R1, x, Some(IBgpPeer)),
+            Err(NetworkError::DeviceNotFound(x))
+        );
+        assert_eq!(
+            net.set_link_weight(*R1, x, 1.0),
+            Err(NetworkError::LinkNotFound(*R1, x))
+        );
+        assert_eq!(
+            net.advertise_external_route(x, p, [1], None, None),
+            Err(NetworkError::DeviceNotFound(x))
+        );
+        assert_eq!(net.remove_router(x), Err(NetworkError::DeviceNotFound(x)));
+
+        // invalid links
+        assert_eq!(
+            net.add_link(*R1, *R1),
+            Err(NetworkError::CannotConnectToItself(*R1))
+        );
+        assert_eq!(
+            net.add_links_from([(*R2, *R2)]),
+            Err(NetworkError::CannotConnectToItself(*R2))
+        );
+        assert_eq!(
+            net.add_links_from([(*R1, *R4), (*E1, *E4)]),
+            Err(NetworkError::CannotConnectExternalRouters(*E1, *E4))
+        );
+        assert!(net.get_link_weight(*R1, *R4).is_err());
+        assert_eq!(
+            net.set_link_weight(*R1, *R2, -1.0),
+            Err(NetworkError::InvalidLinkWeight(*R1, *R2, -1.0))
+        );
+        assert_eq!(
+            net.set_link_weights_from([(*R1, *R3, 1.0), (*R1, *R2, f64::NAN)]),
+            Err(NetworkError::InvalidLinkWeight(*R1, *R2, f64::NAN))
+        );
+        assert_eq!(net.get_link_weight(*R1, *R3), Ok(100.0));
+
+        // invalid sessions
+        assert_eq!(
+            net.set_bgp_session(*R1, *R1, Some(IBgpPeer)),
+            Err(NetworkError::InvalidBgpSessionType(*R1, *R1, IBgpPeer))
+        );
+        assert_eq!(
+            net.set_bgp_session(*E1, *E4, Some(EBgp)),
+            Err(NetworkError::InvalidBgpSessionType(*E1, *E4, EBgp))
+        );
+        assert_eq!(
+            net.set_bgp_session(*R1, *R2, Some(EBgp)),
+            Err(NetworkError::InvalidBgpSessionType(*R1, *R2, EBgp))
+        );
+
+        // wrong kind of router
+        assert_eq!(
+            net.advertise_external_route(*R1, p, [1], None, None),
+            Err(NetworkError::DeviceIsInternalRouter(*R1))
+        );
+        assert_eq!(
+            net.set_load_balancing(*E1, true),
+            Err(NetworkError::DeviceIsExternalRouter(*E1))
+        );
+    }
+
+    #[test]
+    fn convergence_events<P: Prefix + Send + 'static, Ospf: OspfImpl>() {
+        let mut net = get_test_net_igp::<P, Ospf>();
+        let p = P::from(0);
+
+        let events: Arc<Mutex<Vec<ConvergenceEvent<P>>>> = Default::default();
+        let log = events.clone();
+        net.on_event(Box::new(move |e| log.lock().unwrap().push(e.clone())));
+        let take = || std::mem::take(&mut *events.lock().unwrap());
+
+        // all routers select a new best route
+        net.advertise_external_route(*E1, p, [65101], None, None)
+            .unwrap();
+        let changes: BTreeMap<RouterId, RouterId> = take()
+            .into_iter()
+            .map(|e| match e {
+                ConvergenceEvent::BestRouteChanged {
+                    router,
+                    prefix,
+                    old: None,
+                    new: Some(new),
+                } if prefix == p => (router, new.from_id),
+                e => panic!("Unexpected event: {e:?}"),
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            btreemap! {*R1 => *E1, *R2 => *R1, *R3 => *R1, *R4 => *R1}
+        );
+
+        // the session breaks down, and all routers lose their route
+        net.set_bgp_session(*R1, *E1, None).unwrap();
+        let events = take();
+        assert_eq!(
+            events[0],
+            ConvergenceEvent::SessionDown {
+                source: *E1,
+                target: *R1
+            }
+        );
+        let lost: BTreeSet<RouterId> = events[1..]
+            .iter()
+            .map(|e| match e {
+                ConvergenceEvent::BestRouteChanged {
+                    router, new: None, ..
+                } => *router,
+                e => panic!("Unexpected event: {e:?}"),
+            })
+            .collect();
+        assert_eq!(lost, btreeset! {*R1, *R2, *R3, *R4});
+
+        // the session is established again
+        net.set_bgp_session(*R1, *E1, Some(EBgp)).unwrap();
+        let events = take();
+        assert_eq!(
+            events[0],
+            ConvergenceEvent::SessionEstablished {
+                source: *E1,
+                target: *R1,
+                session_type: EBgp
+            }
+        );
+        assert_eq!(events.len(), 5);
+
+        // the IGP is recomputed, without changing any best route.
+        net.set_link_weight(*R1, *R2, 1.0).unwrap();
+        assert_eq!(
+            take(),
+            vec![ConvergenceEvent::IgpRecomputed {
+                links: vec![(*R1, *R2)]
+            }]
+        );
+
+        // callbacks are not cloned
+        let mut net_clone = net.clone();
+        net_clone.withdraw_external_route(*E1, p).unwrap();
+        assert!(take().is_empty());
+    }
+
+    #[test]
+    fn reset<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_net_bgp::<P, Ospf>();
+        let mut fresh = get_test_net_bgp::<P, Ospf>();
+        let p = P::from(0);
+
+        // sweep over different advertisements, resetting the network after each iteration
+        for i in 0..3u32 {
+            net.advertise_external_route(*E1, P::from(i), [65101, 65200 + i], None, None)
+                .unwrap();
+            net.advertise_external_route(*E4, P::from(i + 1), [65104], None, None)
+                .unwrap();
+            net.reset();
+            assert_eq!(net.get_known_prefixes().count(), 0);
+        }
+
+        for n in [&mut net, &mut fresh] {
+            n.advertise_external_route(*E1, p, [65101, 65201], None, None)
+                .unwrap();
+            n.advertise_external_route(*E4, p, [65104, 65201], None, None)
+                .unwrap();
+        }
+
+        test_route!(net, *R2, p, [*R2, *R4, *E4]);
+        assert!(net.get_forwarding_state() == fresh.get_forwarding_state());
+        assert!(net == fresh);
+    }
+
+    #[test]
+    fn warm_start<P: Prefix, Ospf: OspfImpl>() {
+        let p = P::from(0);
+        let q = P::from(1);
+
+        let mut previous = get_test_net_bgp::<P, Ospf>();
+        previous
+            .advertise_external_route(*E1, p, [65101, 65201], None, None)
+            .unwrap();
+        previous
+            .advertise_external_route(*E4, p, [65104, 65201], None, None)
+            .unwrap();
+        previous
+            .advertise_external_route(*E1, q, [65101], None, None)
+            .unwrap();
+
+        // E4 withdraws p, and starts advertising q.
+        let mut cold = get_test_net_bgp::<P, Ospf>();
+        let mut warm = get_test_net_bgp::<P, Ospf>();
+        warm.manual_simulation();
+        for n in [&mut cold, &mut warm] {
+            n.advertise_external_route(*E1, p, [65101, 65201], None, None)
+                .unwrap();
+            n.advertise_external_route(*E1, q, [65101], None, None)
+                .unwrap();
+            n.advertise_external_route(*E4, q, [65104], None, None)
+                .unwrap();
+        }
+
+        // only the withdraw of p and the update of q are sent from E4 to R4 (with a single prefix,
+        // the update replaces the old route).
+        warm.warm_start(&previous).unwrap();
+        assert_eq!(warm.queue().len(), if p == q { 1 } else { 2 });
+        warm.simulate().unwrap();
+        warm.auto_simulation();
+
+        test_route!(warm, *R4, q, [*R4, *E4]);
+        assert!(warm.get_forwarding_state() == cold.get_forwarding_state());
+        assert!(warm == cold);
+
+        // cannot warm-start from a different configuration.
+        let mut other = previous.clone();
+        other.set_bgp_multipath(*R1, true).unwrap();
+        assert_eq!(cold.warm_start(&other), Err(NetworkError::InvalidWarmStart));
+        previous.set_link_weight(*R1, *R2, 100.0).unwrap();
+        assert_eq!(
+            cold.warm_start(&previous),
+            Err(NetworkError::InvalidWarmStart)
+        );
+    }
+
+    #[test]
+    fn topology_and_config_hash<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_net_bgp::<P, Ospf>();
+        let mut other = get_test_net_bgp::<P, Ospf>();
+        let topo = net.topology_hash();
+        let config = net.config_hash().unwrap();
+        assert_eq!(other.topology_hash(), topo);
+        assert_eq!(other.config_hash().unwrap(), config);
+
+        // the hashes do not depend on the state of the network
+        net.advertise_external_route(*E1, P::from(0), [65101, 65201], None, None)
+            .unwrap();
+        assert_eq!(net.topology_hash(), topo);
+        assert_eq!(net.config_hash().unwrap(), config);
+
+        // the hashes do not depend on the order of the configuration
+        let map = |prefixes: [u32; 2]| {
+            RouteMapBuilder::new()
+                .order(10)
+                .deny()
+                .match_prefix(P::from(prefixes[0]))
+                .match_prefix(P::from(prefixes[1]))
+                .build()
+        };
+        net.set_bgp_route_map(*R1, *E1, Incoming, map([1, 2]))
+            .unwrap();
+        net.set_link_weight(*R2, *R4, 5.0).unwrap();
+        other.set_link_weight(*R2, *R4, 5.0).unwrap();
+        other
+            .set_bgp_route_map(*R1, *E1, Incoming, map([2, 1]))
+            .unwrap();
+        let config = net.config_hash().unwrap();
+        assert_eq!(other.config_hash().unwrap(), config);
+
+        // any change to the configuration changes the config hash, but not the topology hash
+        other.set_link_weight(*R2, *R4, 6.0).unwrap();
+        assert_ne!(other.config_hash().unwrap(), config);
+        other.set_link_weight(*R2, *R4, 5.0).unwrap();
+        assert_eq!(other.config_hash().unwrap(), config);
+        other.set_bgp_session(*R2, *R4, None).unwrap();
+        assert_ne!(other.config_hash().unwrap(), config);
+        assert_eq!(other.topology_hash(), topo);
+
+        // BGP settings that are not part of the `Config` also change the config hash
+        let mut other = net.clone();
+        other.set_bgp_multipath(*R1, true).unwrap();
+        assert_ne!(other.config_hash().unwrap(), config);
+        let mut other = net.clone();
+        other.set_local_as(*R1, *E1, Some(AsId(65500))).unwrap();
+        assert_ne!(other.config_hash().unwrap(), config);
+        other.set_local_as(*R1, *E1, None).unwrap();
+        assert_eq!(other.config_hash().unwrap(), config);
+
+        // any change to the topology changes the topology hash
+        let mut other = net.clone();
+        other.add_link(*R1, *R4).unwrap();
+        assert_ne!(other.topology_hash(), topo);
+        let mut other = net.clone();
+        other.add_router("R5");
+        assert_ne!(other.topology_hash(), topo);
+    }
+
+    #[test]
+    fn graceful_shutdown<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_net_bgp::<P, Ospf>();
+        let p = P::from(0);
+        net.advertise_external_route(*E1, p, [65101, 65201], None, None)
+            .unwrap();
+        net.advertise_external_route(*E4, p, [65104, 65201], None, None)
+            .unwrap();
+        test_route!(net, *R1, p, [*R1, *E1]);
+        test_route!(net, *R4, p, [*R4, *E4]);
+
+        // drain the session between R1 and E1
+        assert!(!net.graceful_shutdown(*R1, *E1).unwrap());
+        let route = net
+            .get_internal_router(*R1)
+            .unwrap()
+            .bgp
+            .get_known_routes(p)
+            .unwrap()
+            .into_iter()
+            .find(|e| e.from_id == *E1)
+            .unwrap();
+        assert!(route.route.communities.contains(&GRACEFUL_SHUTDOWN));
+        assert_eq!(route.route.local_pref, Some(0));
+        test_route!(net, *R1, p, [*R1, *R3, *R2, *R4, *E4]);
+        test_route!(net, *R2, p, [*R2, *R4, *E4]);
+        test_route!(net, *R3, p, [*R3, *R2, *R4, *E4]);
+        test_route!(net, *R4, p, [*R4, *E4]);
+
+        // withdrawing the drained route does not change the forwarding state of any internal
+        // router.
+        let fw_state = net.get_forwarding_state();
+        net.withdraw_external_route(*E1, p).unwrap();
+        assert!(fw_state
+            .changed_entries(&net.get_forwarding_state())
+            .into_iter()
+            .all(|(r, _)| r == *E1));
+
+        // ending the graceful shutdown restores the original state.
+        net.advertise_external_route(*E1, p, [65101, 65201], None, None)
+            .unwrap();
+        assert!(net.end_graceful_shutdown(*R1, *E1).unwrap());
+        test_route!(net, *R1, p, [*R1, *E1]);
+    }
+
+    #[test]
+    fn inter_as_path<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_net_bgp::<P, Ospf>();
+        let p = P::from(0);
+
+        // AS 65201 is a customer of both AS 65101 and AS 65104 (via AS 65204).
+        net.advertise_external_route(*E1, p, [65101, 65201], None, None)
+            .unwrap();
+        net.advertise_external_route(*E4, p, [65104, 65204, 65201], None, None)
+            .unwrap();
+
+        let path = net.inter_as_path(*R4, p).unwrap();
+        assert_eq!(
+            path,
+            vec![
+                (INTERNAL_AS, vec![*R4, *R2, *R3, *R1]),
+                (AsId(65101), vec![*E1]),
+                (AsId(65201), vec![]),
+            ]
+        );
+
+        // the AS sequence matches the AS path of the selected route.
+        let route = net
+            .get_internal_router(*R4)
+            .unwrap()
+            .bgp
+            .get_route(p)
+            .unwrap()
+            .route
+            .clone();
+        assert_eq!(
+            path.iter().map(|(as_id, _)| *as_id).collect::<Vec<_>>(),
+            std::iter::once(INTERNAL_AS)
+                .chain(route.as_path)
+                .collect::<Vec<_>>()
+        );
+
+        // an external router only reaches itself.
+        assert_eq!(
+            net.inter_as_path(*E4, p).unwrap(),
+            vec![(AsId(65104), vec![*E4])]
+        );
+
+        // report a break if traffic cannot be forwarded.
+        net.withdraw_external_route(*E1, p).unwrap();
+        net.withdraw_external_route(*E4, p).unwrap();
+        assert_eq!(
+            net.inter_as_path(*R4, p),
+            Err(NetworkError::ForwardingBlackHole(vec![*R4]))
+        );
+    }
+
+    #[test]
+    fn simulate_progress<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_net_bgp::<P, Ospf>();
+        let (processed, pending) = net.progress();
+        assert_eq!(pending, 0);
+
+        net.manual_simulation();
+        net.advertise_external_route(*E1, P::from(0), [65101, 65201], None, None)
+            .unwrap();
+        net.advertise_external_route(*E4, P::from(0), [65104, 65201], None, None)
+            .unwrap();
+        assert_eq!(net.progress().0, processed);
+        assert!(net.progress().1 > 0);
+
+        let steps = net
+            .simulate_progress()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert!(!steps.is_empty());
+        // each step processes exactly one event
+        for (i, (p, _)) in steps.iter().enumerate() {
+            assert_eq!(*p, processed + i + 1);
+        }
+        assert_eq!(steps.last().unwrap().1, 0);
+        assert_eq!(net.progress(), (processed + steps.len(), 0));
+        test_route!(net, *R4, P::from(0), [*R4, *E4]);
+
+        // the iterator is empty once the network has converged.
+        assert_eq!(net.simulate_progress().count(), 0);
+    }
+
+    #[test]
+    fn user_data<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_net_bgp::<P, Ospf>();
+        net.set_router_data(*R1, &("edge", 42u32)).unwrap();
+        net.set_link_data(*R2, *R4, &vec![1.5, 2.5]).unwrap();
+        assert!(net.set_router_data(RouterId::from(100), &1).is_err());
+        assert!(net.set_link_data(*R1, *R4, &1).is_err());
+
+        // the data is not affected by the simulation
+        net.advertise_external_route(*E1, P::from(0), [65101, 65201], None, None)
+            .unwrap();
+        assert_eq!(
+            net.get_router_data::<(String, u32)>(*R1).unwrap(),
+            Some(("edge".to_string(), 42))
+        );
+        assert_eq!(
+            net.get_link_data::<Vec<f64>>(*R4, *R2).unwrap(),
+            Some(vec![1.5, 2.5])
+        );
+        assert_eq!(net.get_router_data::<u32>(*R2).unwrap(), None);
+        assert!(net.get_router_data::<u32>(*R1).is_err());
+
+        // the data is kept when cloning the network
+        let mut other = net.clone();
+        assert_eq!(
+            other.get_router_data::<(String, u32)>(*R1).unwrap(),
+            Some(("edge".to_string(), 42))
+        );
+        assert_eq!(
+            other.get_link_data::<Vec<f64>>(*R2, *R4).unwrap(),
+            Some(vec![1.5, 2.5])
+        );
+
+        // the data is removed together with the link or the router
+        other.remove_link(*R2, *R4).unwrap();
+        assert_eq!(other.get_link_data::<Vec<f64>>(*R2, *R4).unwrap(), None);
+        other.remove_router(*R1).unwrap();
+        assert_eq!(other.get_router_data::<(String, u32)>(*R1).unwrap(), None);
+        assert!(net.remove_router_data(*R1).is_some());
+        assert_eq!(net.get_router_data::<(String, u32)>(*R1).unwrap(), None);
+    }
+
+    #[test]
+    fn alternative_egress<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_net_bgp::<P, Ospf>();
+        let p = P::from(0);
+        net.advertise_external_route(*E1, p, [65101, 65201], None, None)
+            .unwrap();
+        net.advertise_external_route(*E4, p, [65104, 65201], None, None)
+            .unwrap();
+        test_route!(net, *R2, p, [*R2, *R4, *E4]);
+        assert_eq!(net.alternative_egress(*R1, p), Ok(Some(*R4)));
+        assert_eq!(net.alternative_egress(*R2, p), Ok(Some(*R1)));
+        assert_eq!(net.alternative_egress(*R4, p), Ok(Some(*R1)));
+        assert!(net.alternative_egress(*E1, p).is_err());
+
+        // failing the primary egress of R2 makes it select the reported alternative.
+        let mut failed = net.clone();
+        failed.withdraw_external_route(*E4, p).unwrap();
+        let route = failed.get_internal_router(*R2).unwrap().bgp.get_route(p);
+        assert_eq!(route.map(|e| e.route.next_hop), Some(*R1));
+        test_route!(failed, *R2, p, [*R2, *R3, *R1, *E1]);
+        assert_eq!(failed.alternative_egress(*R2, p), Ok(None));
+
+        // R2 only learns the best route of its route reflector R4, and knows no alternative.
+        net.set_bgp_session(*R1, *R2, None).unwrap();
+        net.set_bgp_session(*R2, *R3, None).unwrap();
+        net.set_bgp_session(*R4, *R2, Some(IBgpClient)).unwrap();
+        test_route!(net, *R2, p, [*R2, *R4, *E4]);
+        assert_eq!(net.alternative_egress(*R2, p), Ok(None));
+        assert_eq!(net.alternative_egress(*R4, p), Ok(Some(*R1)));
+    }
+
+    #[test]
+    fn receive_only<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_net_bgp::<P, Ospf>();
+        let p = P::from(0);
+        let c = net.add_external_router("C", AsId(65200));
+        net.add_link(*R2, c).unwrap();
+        net.set_bgp_session(*R2, c, Some(EBgp)).unwrap();
+
+        // the collector stops advertising its routes once it becomes receive-only
+        net.advertise_external_route(c, p, [65200], None, None)
+            .unwrap();
+        test_route!(net, *R2, p, [*R2, c]);
+        assert_eq!(net.set_receive_only(c, true), Ok(false));
+        assert!(net.get_external_router(c).unwrap().is_receive_only());
+        assert!(net.get_forwarding_state().is_black_hole(*R2, p));
+        assert_eq!(
+            net.advertise_external_route(c, p, [65200], None, None),
+            Err(NetworkError::ReceiveOnly(c))
+        );
+        assert_ne!(NetworkError::ReceiveOnly(c), NetworkError::ReceiveOnly(*E1));
+
+        net.advertise_external_route(*E1, p, [65101, 65201], None, None)
+            .unwrap();
+        net.advertise_external_route(*E4, p, [65104, 65201], None, None)
+            .unwrap();
+
+        // no internal router selects the collector as an egress
+        let fw_state = net.get_forwarding_state();
+        for r in [*R1, *R2, *R3, *R4] {
+            assert!(!fw_state.get_nodes_along_paths(r, p).contains(&c));
+        }
+        test_route!(net, *R2, p, [*R2, *R4, *E4]);
+
+        // the collector holds exactly the route that R2 offers
+        let offered = net
+            .get_internal_router(*R2)
+            .unwrap()
+            .bgp
+            .get_rib_out()
+            .get(&p)
+            .and_then(|table| table.get(&c))
+            .map(|e| e.route.clone());
+        assert!(offered.is_some());
+        let collector = net.get_external_router(c).unwrap();
+        assert_eq!(collector.get_received_route(*R2, p).cloned(), offered);
+        assert_eq!(collector.get_received_routes(*R1), None);
+
+        // withdrawals are reflected as well
+        net.withdraw_external_route(*E1, p).unwrap();
+        net.withdraw_external_route(*E4, p).unwrap();
+        let collector = net.get_external_router(c).unwrap();
+        assert_eq!(collector.get_received_route(*R2, p), None);
+
+        assert_eq!(net.set_receive_only(c, false), Ok(true));
+        net.advertise_external_route(c, p, [65200], None, None)
+            .unwrap();
+        test_route!(net, *R2, p, [*R2, c]);
+    }
+
+    #[test]
+    fn simulate_until<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_net_bgp::<P, Ospf>();
+        let p = P::from(0);
+        net.advertise_external_route(*E1, p, [65101, 65201], None, None)
+            .unwrap();
+        net.advertise_external_route(*E4, p, [65104, 65201], None, None)
+            .unwrap();
+        test_route!(net, *R2, p, [*R2, *R4, *E4]);
+
+        let has_loop = |fw_state: &mut ForwardingState<P>| {
+            [*R1, *R2, *R3, *R4].into_iter().any(|r| {
+                matches!(
+                    fw_state.get_paths(r, p),
+                    Err(NetworkError::ForwardingLoop { .. })
+                )
+            })
+        };
+
+        // the predicate holds on the converged state, so nothing is simulated.
+        assert!(net
+            .simulate_until(|_| true)
+            .unwrap()
+            .is_some_and(|(s, _)| s == 0));
+
+        // After E4 withdraws its route, R4 immediately switches to the route of R1 (via R2), while
+        // R2 still forwards towards R4.
+        net.manual_simulation();
+        net.withdraw_external_route(*E4, p).unwrap();
+        assert!(!has_loop(&mut net.get_forwarding_state()));
+        let (step, mut fw_state) = net.simulate_until(has_loop).unwrap().unwrap();
+        assert_eq!(step, 1);
+        assert_eq!(
+            fw_state.get_paths(*R4, p),
+            Err(NetworkError::ForwardingLoop {
+                to_loop: vec![],
+                first_loop: vec![*R4, *R2],
+            })
+        );
+        assert_eq!(fw_state, net.get_forwarding_state());
+        assert!(!net.queue().is_empty());
+
+        // resuming the simulation resolves the loop, and the predicate no longer holds.
+        assert!(net.simulate_until(|fw| !has_loop(fw)).unwrap().is_some());
+        assert_eq!(net.simulate_until(has_loop), Ok(None));
+        test_route!(net, *R4, p, [*R4, *R2, *R3, *R1, *E1]);
+        assert!(net.queue().is_empty());
+    }
+
+    #[test]
+    fn forwarding_dot<P: Prefix, Ospf: OspfImpl>() {
+        let mut net = get_test_net_bgp::<P, Ospf>();
+        let p = P::from(0);
+        net.advertise_external_route(*E1, p, [65101, 65201], None, None)
+            .unwrap();
+        net.advertise_external_route(*E4, p, [65104, 65201], None, None)
+            .unwrap();
+        test_route!(net, *R2, p, [*R2, *R4, *E4]);
+        test_route!(net, *R3, p, [*R3, *R1, *E1]);
+
+        let edge = |a: RouterId, b: RouterId| format!("  {} -> {} [", a.index(), b.index());
+        let line = |dot: &str, a: RouterId, b: RouterId| {
+            dot.lines()
+                .find(|l| l.starts_with(&edge(a, b)) && !l.contains("dir=none"))
+                .map(|l| l.to_string())
+        };
+        let fill = |dot: &str, r: RouterId| {
+            dot.lines()
+                .find(|l| l.starts_with(&format!("  {} [", r.index())))
+                .and_then(|l| l.split("fillcolor=").nth(1))
+                .map(|c| c.trim_end_matches("];").to_string())
+        };
+
+        // the plain graph contains the forwarding edges, but no BGP attributes.
+        let dot = net.forwarding_dot(p);
+        assert!(dot.starts_with("digraph {\n"));
+        assert!(line(&dot, *R2, *R4).is_some());
+        assert!(line(&dot, *R4, *R2).is_none());
+        assert!(!dot.contains("local-pref"));
+        assert_eq!(fill(&dot, *R1), None);
+
+        // the annotated graph labels the egress-adjacent edges with the selected route.
+        let dot = net.forwarding_dot_annotated(p);
+        for (r, e) in [(*R1, *E1), (*R4, *E4)] {
+            assert!(line(&dot, r, e)
+                .unwrap()
+                .contains("label=\"local-pref: 100\\nas-path: 2\""));
+        }
+        assert!(line(&dot, *R3, *R1).unwrap().contains("local-pref: 100"));
+
+        // routers are colored by their egress.
+        assert!(fill(&dot, *R1).is_some());
+        assert_eq!(fill(&dot, *R1), fill(&dot, *R3));
+        assert_eq!(fill(&dot, *R2), fill(&dot, *R4));
+        assert_ne!(fill(&dot, *R1), fill(&dot, *R2));
+        assert_eq!(fill(&dot, *E1), None);
+    }
+
     #[instantiate_tests(<SinglePrefix, GlobalOspf>)]
     mod single_global_ospf {}
 
@@ -15,7 +15,7 @@
 
 use crate::{
     bgp::BgpSessionType::*,
-    event::EventQueue,
+    event::{BasicEventQueue, EventQueue},
     network::Network,
     types::{AsId, NetworkError, Prefix, RouterId, SimplePrefix},
 };
@@ -474,6 +474,104 @@ mod t {
         assert!(last_advertisement == Err(NetworkError::NoConvergence));
     }
 
+    /// Setup the gadget of `test_bad_gadget`, where the weight between ri and bi is `own`, and the
+    /// weight between ri and bi+1 is `other`. The prefix is advertised by all external routers, but
+    /// the network is not simulated. Returns `[b0, b1, b2, r0, r1, r2]`.
+    fn setup_gadget<P, Ospf>(
+        net: &mut Network<P, BasicEventQueue<P>, Ospf>,
+        own: f64,
+        other: f64,
+    ) -> [RouterId; 6]
+    where
+        P: Prefix,
+        Ospf: OspfImpl,
+    {
+        use crate::interactive::InteractiveNetwork;
+
+        let e0 = net.add_external_router("E0", AsId(65100));
+        let e1 = net.add_external_router("E1", AsId(65101));
+        let e2 = net.add_external_router("E2", AsId(65102));
+        let b0 = net.add_router("B0");
+        let b1 = net.add_router("B1");
+        let b2 = net.add_router("B2");
+        let r0 = net.add_router("R0");
+        let r1 = net.add_router("R1");
+        let r2 = net.add_router("R2");
+
+        net.add_link(e0, b0).unwrap();
+        net.add_link(e1, b1).unwrap();
+        net.add_link(e2, b2).unwrap();
+        for (a, b, weight) in [
+            (b0, r0, own),
+            (b1, r1, own),
+            (b2, r2, own),
+            (r0, b1, other),
+            (r1, b2, other),
+            (r2, b0, other),
+        ] {
+            net.add_link(a, b).unwrap();
+            net.set_link_weight(a, b, weight).unwrap();
+            net.set_link_weight(b, a, weight).unwrap();
+        }
+        net.set_bgp_session(r0, b0, Some(IBgpClient)).unwrap();
+        net.set_bgp_session(r1, b1, Some(IBgpClient)).unwrap();
+        net.set_bgp_session(r2, b2, Some(IBgpClient)).unwrap();
+        net.set_bgp_session(r0, r1, Some(IBgpPeer)).unwrap();
+        net.set_bgp_session(r0, r2, Some(IBgpPeer)).unwrap();
+        net.set_bgp_session(r1, r2, Some(IBgpPeer)).unwrap();
+        net.set_bgp_session(b0, e0, Some(EBgp)).unwrap();
+        net.set_bgp_session(b1, e1, Some(EBgp)).unwrap();
+        net.set_bgp_session(b2, e2, Some(EBgp)).unwrap();
+
+        net.manual_simulation();
+        for e in [e0, e1, e2] {
+            net.advertise_external_route(e, P::from(0), [AsId(0), AsId(1)], None, None)
+                .unwrap();
+        }
+
+        [b0, b1, b2, r0, r1, r2]
+    }
+
+    #[test]
+    fn dispute_wheel_bad_gadget<P: Prefix, Ospf: OspfImpl>() {
+        let mut net: Network<P, _, Ospf> = Network::default();
+        let [b0, b1, b2, r0, r1, r2] = setup_gadget(&mut net, 5.0, 1.0);
+
+        let wheel = net.detect_dispute_wheel().unwrap();
+        assert_eq!(wheel.prefix, P::from(0));
+        assert_eq!(wheel.pivots.len(), 3);
+        // every pivot prefers the route of the next pivot over its own client
+        for (i, pivot) in wheel.pivots.iter().enumerate() {
+            let next = wheel.pivots[(i + 1) % 3];
+            let (b, e) = match *pivot {
+                x if x == r0 => (b0, 0.into()),
+                x if x == r1 => (b1, 1.into()),
+                x if x == r2 => (b2, 2.into()),
+                x => panic!("unexpected pivot {x:?}"),
+            };
+            assert_eq!(wheel.spokes[i], vec![*pivot, b, e]);
+            assert_eq!(wheel.rims[i], vec![*pivot, next]);
+        }
+        assert_eq!(
+            wheel.routers().len(),
+            9,
+            "all routers participate in the wheel"
+        );
+    }
+
+    #[test]
+    fn dispute_wheel_safe<P: Prefix, Ospf: OspfImpl>() {
+        use crate::interactive::InteractiveNetwork;
+
+        let mut net: Network<P, _, Ospf> = Network::default();
+        setup_gadget(&mut net, 1.0, 5.0);
+        assert_eq!(net.detect_dispute_wheel(), None);
+
+        // the safe gadget converges
+        net.auto_simulation();
+        net.simulate().unwrap();
+    }
+
     #[test]
     fn change_ibgp_topology_1<P: Prefix, Ospf: OspfImpl>() {
         // Example from L. Vanbever bgpmig_ton, figure 1
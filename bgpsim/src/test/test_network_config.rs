@@ -20,7 +20,7 @@ use crate::{
     interactive::InteractiveNetwork,
     network::Network,
     route_map::{RouteMapBuilder, RouteMapDirection::*},
-    types::{AsId, NetworkError, Prefix, RouterId, SimplePrefix},
+    types::{AsId, Community, NetworkError, Prefix, RouterId, SimplePrefix},
 };
 
 #[cfg(feature = "rand_queue")]
@@ -932,7 +932,7 @@ mod t {
             RouteMapBuilder::new().order(100).deny().build(),
         )
         .unwrap();
-        net.advertise_external_route(ex, p, [100, 100, 1], None, [10])
+        net.advertise_external_route(ex, p, [100, 100, 1], None, [10].map(Community::from))
             .unwrap();
         net.manual_simulation();
 
@@ -1007,6 +1007,74 @@ mod t {
         assert!(net.weak_eq(&net2));
     }
 
+    #[test]
+    fn apply_config_changes<P: Prefix>() {
+        let mut net: Network<P, BasicEventQueue<P>> = Network::default();
+        let prefix = P::from(0);
+
+        let (e0, b0, r0, r1, b1, e1) = setup_simple(&mut net);
+        net.advertise_external_route(e0, prefix, vec![AsId(1), AsId(2), AsId(3)], None, None)
+            .unwrap();
+        net.advertise_external_route(e1, prefix, vec![AsId(1), AsId(2), AsId(3)], None, None)
+            .unwrap();
+
+        let changes = vec![
+            Update {
+                from: link_weight!(r0, r1, 1.0),
+                to: link_weight!(r0, r1, 10.0),
+            },
+            Update {
+                from: bgp_session!(r0, b0, IBgpClient),
+                to: bgp_session!(b0, r0, IBgpPeer),
+            },
+            Insert(bgp_session!(b0, b1, IBgpPeer)),
+            Insert(ConfigExpr::BgpRouteMap {
+                router: b1,
+                neighbor: e1,
+                direction: Incoming,
+                map: RouteMapBuilder::new()
+                    .order(10)
+                    .allow()
+                    .set_local_pref(200)
+                    .build(),
+            }),
+        ];
+
+        // apply the changes one-by-one, and let the network converge after each of them
+        let mut sequential = net.clone();
+        for modifier in changes.iter() {
+            sequential.apply_modifier(modifier).unwrap();
+            assert!(sequential.queue().is_empty());
+        }
+
+        // apply the changes as a batch, without converging in between.
+        let mut batch = net.clone();
+        batch.manual_simulation();
+        batch.apply_config_changes(&changes).unwrap();
+        assert!(!batch.queue().is_empty());
+        batch.simulate().unwrap();
+
+        // apply the changes as a batch in automatic simulation mode
+        net.apply_config_changes(&changes).unwrap();
+        assert!(net.queue().is_empty());
+
+        pretty_assertions::assert_eq!(
+            sequential.get_config().unwrap(),
+            batch.get_config().unwrap()
+        );
+        pretty_assertions::assert_eq!(sequential.get_config().unwrap(), net.get_config().unwrap());
+        assert_eq!(
+            sequential.get_forwarding_state(),
+            batch.get_forwarding_state()
+        );
+        assert_eq!(
+            sequential.get_forwarding_state(),
+            net.get_forwarding_state()
+        );
+        test_route!(batch, b0, prefix, [b0, r0, r1, b1, e1]);
+        test_route!(batch, r0, prefix, [r0, r1, b1, e1]);
+    }
+
     #[instantiate_tests(<SinglePrefix>)]
     mod single {}
 
@@ -24,8 +24,9 @@ use crate::{
     network::Network,
     ospf::{
         local::{Lsa, LsaKey},
-        GlobalOspf, LocalOspf, OspfArea, OspfImpl,
+        ExternalMetricType, GlobalOspf, LocalOspf, OspfArea, OspfAreaType, OspfImpl,
     },
+    router::StaticRoute,
     types::{AsId, NetworkError, RouterId, SimplePrefix as Prefix},
 };
 use itertools::Itertools;
@@ -535,6 +536,228 @@ mod t {
         );
     }
 
+    #[test]
+    fn stub_area<Ospf: OspfImpl>() {
+        // setup logger
+        let _ = env_logger::try_init();
+
+        let mut net: Network<Prefix, BasicEventQueue<Prefix>, Ospf> = Network::default();
+        let s = net.add_router("S");
+        let a1 = net.add_router("A1");
+        let a2 = net.add_router("A2");
+        let b = net.add_router("B");
+        let e = net.add_external_router("E", AsId(100));
+        net.add_links_from([(s, a1), (s, a2), (a1, b), (a2, b), (b, e)])
+            .unwrap();
+        for (x, y, w) in [(s, a1, 10.0), (s, a2, 50.0), (a1, b, 100.0), (a2, b, 10.0)] {
+            net.set_link_weight(x, y, w).unwrap();
+            net.set_link_weight(y, x, w).unwrap();
+        }
+        net.set_ospf_area(s, a1, 1).unwrap();
+        net.set_ospf_area(s, a2, 1).unwrap();
+        net.build_ibgp_full_mesh().unwrap();
+        net.build_ebgp_sessions().unwrap();
+        let p = Prefix::from(0);
+        net.advertise_external_route(e, p, [100, 200], None, None)
+            .unwrap();
+
+        // S reaches the next-hop B via A2 (cost 60 instead of 110).
+        let mut state = net.get_forwarding_state();
+        assert_eq!(state.get_paths(s, p), Ok(vec![vec![s, a2, b, e]]));
+
+        // In a stub area, S still sees the inter-area route towards B.
+        assert_eq!(
+            net.set_area_type(1, OspfAreaType::Stub),
+            Ok(OspfAreaType::Normal)
+        );
+        let mut state = net.get_forwarding_state();
+        assert_eq!(state.get_paths(s, p), Ok(vec![vec![s, a2, b, e]]));
+
+        // In a totally stubby area, S resolves B using the default route via the closest ABR.
+        assert_eq!(
+            net.set_area_type(1, OspfAreaType::TotallyStubby),
+            Ok(OspfAreaType::Stub)
+        );
+        assert_eq!(
+            net.ospf_network().get_area_type(1),
+            OspfAreaType::TotallyStubby
+        );
+        let mut state = net.get_forwarding_state();
+        assert_eq!(state.get_paths(s, p), Ok(vec![vec![s, a1, b, e]]));
+        assert_eq!(state.get_paths(a2, p), Ok(vec![vec![a2, b, e]]));
+
+        // The backbone area cannot be a stub area
+        assert_eq!(
+            net.set_area_type(OspfArea::BACKBONE, OspfAreaType::Stub),
+            Err(NetworkError::BackboneAreaType(OspfAreaType::Stub))
+        );
+
+        // revert the area type
+        net.set_area_type(1, OspfAreaType::Normal).unwrap();
+        let mut state = net.get_forwarding_state();
+        assert_eq!(state.get_paths(s, p), Ok(vec![vec![s, a2, b, e]]));
+    }
+
+    #[test]
+    fn external_metric_type<Ospf: OspfImpl>() {
+        // setup logger
+        let _ = env_logger::try_init();
+
+        let mut net: Network<Prefix, BasicEventQueue<Prefix>, Ospf> = Network::default();
+        let s = net.add_router("S");
+        let a1 = net.add_router("A1");
+        let a2 = net.add_router("A2");
+        let e1 = net.add_external_router("E1", AsId(100));
+        let e2 = net.add_external_router("E2", AsId(200));
+        net.add_links_from([(s, a1), (s, a2), (a1, e1), (a2, e2)])
+            .unwrap();
+        for (x, y, w) in [(s, a1, 1.0), (s, a2, 5.0)] {
+            net.set_link_weight(x, y, w).unwrap();
+            net.set_link_weight(y, x, w).unwrap();
+        }
+        let p = Prefix::from(0);
+        net.advertise_external_route(e1, p, [100], None, None)
+            .unwrap();
+        net.advertise_external_route(e2, p, [200], None, None)
+            .unwrap();
+        net.set_static_route(a1, p, Some(StaticRoute::Direct(e1)))
+            .unwrap();
+        net.set_static_route(a2, p, Some(StaticRoute::Direct(e2)))
+            .unwrap();
+
+        // E2 routes with the same external metric tie, and S picks the nearest ASBR.
+        assert_eq!(
+            net.redistribute_into_ospf(a1, p, ExternalMetricType::E2, 10.0),
+            Ok(None)
+        );
+        net.redistribute_into_ospf(a2, p, ExternalMetricType::E2, 10.0)
+            .unwrap();
+        let mut state = net.get_forwarding_state();
+        assert_eq!(state.get_paths(s, p), Ok(vec![vec![s, a1, e1]]));
+
+        // With E2, only the external metric matters.
+        net.redistribute_into_ospf(a2, p, ExternalMetricType::E2, 8.0)
+            .unwrap();
+        let mut state = net.get_forwarding_state();
+        assert_eq!(state.get_paths(s, p), Ok(vec![vec![s, a2, e2]]));
+
+        // With E1, the internal cost is added (11 via A1 and 13 via A2).
+        net.redistribute_into_ospf(a1, p, ExternalMetricType::E1, 10.0)
+            .unwrap();
+        assert_eq!(
+            net.redistribute_into_ospf(a2, p, ExternalMetricType::E1, 8.0),
+            Ok(Some((ExternalMetricType::E2, 8.0)))
+        );
+        let mut state = net.get_forwarding_state();
+        assert_eq!(state.get_paths(s, p), Ok(vec![vec![s, a1, e1]]));
+        assert_eq!(
+            net.get_internal_router(s)
+                .unwrap()
+                .get_ospf_external_asbr(p),
+            Some(a1)
+        );
+
+        // E1 routes are preferred over E2 routes.
+        net.redistribute_into_ospf(a1, p, ExternalMetricType::E2, 1.0)
+            .unwrap();
+        let mut state = net.get_forwarding_state();
+        assert_eq!(state.get_paths(s, p), Ok(vec![vec![s, a2, e2]]));
+
+        // routers added later also know the external routes.
+        let t = net.add_router("T");
+        net.add_link(t, s).unwrap();
+        net.set_link_weight(t, s, 1.0).unwrap();
+        net.set_link_weight(s, t, 1.0).unwrap();
+        let mut state = net.get_forwarding_state();
+        assert_eq!(state.get_paths(t, p), Ok(vec![vec![t, s, a2, e2]]));
+
+        // remove the redistribution at A2
+        assert_eq!(
+            net.remove_ospf_redistribution(a2, p),
+            Ok(Some((ExternalMetricType::E1, 8.0)))
+        );
+        let mut state = net.get_forwarding_state();
+        assert_eq!(state.get_paths(s, p), Ok(vec![vec![s, a1, e1]]));
+    }
+
+    #[test]
+    fn external_routes_in_stub_area<Ospf: OspfImpl>() {
+        // setup logger
+        let _ = env_logger::try_init();
+
+        let mut net: Network<Prefix, BasicEventQueue<Prefix>, Ospf> = Network::default();
+        let s = net.add_router("S");
+        let n = net.add_router("N");
+        let a1 = net.add_router("A1");
+        let a2 = net.add_router("A2");
+        let b = net.add_router("B");
+        let e1 = net.add_external_router("E1", AsId(100));
+        let e2 = net.add_external_router("E2", AsId(200));
+        net.add_links_from([(s, n), (s, a1), (s, a2), (a1, b), (a2, b), (b, e1), (n, e2)])
+            .unwrap();
+        for (x, y, w) in [
+            (s, n, 1.0),
+            (s, a1, 10.0),
+            (s, a2, 50.0),
+            (a1, b, 100.0),
+            (a2, b, 10.0),
+        ] {
+            net.set_link_weight(x, y, w).unwrap();
+            net.set_link_weight(y, x, w).unwrap();
+        }
+        net.set_ospf_area(s, n, 1).unwrap();
+        net.set_ospf_area(s, a1, 1).unwrap();
+        net.set_ospf_area(s, a2, 1).unwrap();
+        let p = Prefix::from(0);
+        net.advertise_external_route(e1, p, [100], None, None)
+            .unwrap();
+        net.advertise_external_route(e2, p, [200], None, None)
+            .unwrap();
+        net.set_static_route(b, p, Some(StaticRoute::Direct(e1)))
+            .unwrap();
+        net.set_static_route(n, p, Some(StaticRoute::Direct(e2)))
+            .unwrap();
+        net.redistribute_into_ospf(b, p, ExternalMetricType::E2, 10.0)
+            .unwrap();
+
+        // In a normal area, S uses the external route of B (cost 60 via A2).
+        let mut state = net.get_forwarding_state();
+        assert_eq!(state.get_paths(s, p), Ok(vec![vec![s, a2, b, e1]]));
+
+        // In all stub areas, the external route of B is replaced by the default route via the
+        // closest ABR.
+        for area_type in [
+            OspfAreaType::Stub,
+            OspfAreaType::TotallyStubby,
+            OspfAreaType::Nssa,
+        ] {
+            net.set_area_type(1, area_type).unwrap();
+            let router = net.get_internal_router(s).unwrap();
+            assert_eq!(router.get_ospf_external_asbr(p), None);
+            assert_eq!(router.get_ospf_external_default(p), Some([a1].as_slice()));
+            let mut state = net.get_forwarding_state();
+            assert_eq!(state.get_paths(s, p), Ok(vec![vec![s, a1, b, e1]]));
+            assert_eq!(state.get_paths(a2, p), Ok(vec![vec![a2, b, e1]]));
+        }
+
+        // N redistributes the prefix inside the NSSA (as a type-7 LSA), which is visible to S.
+        net.redistribute_into_ospf(n, p, ExternalMetricType::E2, 20.0)
+            .unwrap();
+        let mut state = net.get_forwarding_state();
+        assert_eq!(state.get_paths(s, p), Ok(vec![vec![s, n, e2]]));
+        assert_eq!(state.get_paths(a1, p), Ok(vec![vec![a1, b, e1]]));
+
+        // In a stub area, the external route of N is hidden as well.
+        net.set_area_type(1, OspfAreaType::Stub).unwrap();
+        let mut state = net.get_forwarding_state();
+        assert_eq!(state.get_paths(s, p), Ok(vec![vec![s, a1, b, e1]]));
+
+        // In a normal area, S picks the external route with the lowest metric again.
+        net.set_area_type(1, OspfAreaType::Normal).unwrap();
+        let mut state = net.get_forwarding_state();
+        assert_eq!(state.get_paths(s, p), Ok(vec![vec![s, a2, b, e1]]));
+    }
+
     #[instantiate_tests(<GlobalOspf>)]
     mod global {}
 
@@ -0,0 +1,286 @@
+// BgpSim: BGP Network Simulator written in Rust
+// Copyright 2022-2024 Tibor Schneider <sctibor@ethz.ch>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the policy checks on entire networks.
+
+use crate::{
+    bgp::BgpSessionType::*,
+    event::BasicEventQueue,
+    network::Network,
+    ospf::GlobalOspf,
+    policies::*,
+    route_map::{RouteMapBuilder, RouteMapDirection::Incoming},
+    router::StaticRoute,
+    types::{AsId, Ipv4Prefix, RouterId},
+};
+use itertools::iproduct;
+use pretty_assertions::assert_eq;
+
+type Net = Network<Ipv4Prefix, BasicEventQueue<Ipv4Prefix>, GlobalOspf>;
+
+fn p(s: &str) -> Ipv4Prefix {
+    s.parse().unwrap()
+}
+
+/// # Test network
+///
+/// ```text
+/// E1 ---- R1 ---- R2 ---- E2
+/// ```
+///
+/// - E1 advertises `10.0.0.0/16`.
+/// - E2 advertises `10.1.0.0/16` and `20.0.0.0/16`, but R2 drops `20.0.0.0/16`.
+fn get_test_net() -> (Net, [RouterId; 4]) {
+    let mut net = Net::default();
+    let e1 = net.add_external_router("E1", AsId(65101));
+    let r1 = net.add_router("R1");
+    let r2 = net.add_router("R2");
+    let e2 = net.add_external_router("E2", AsId(65102));
+
+    net.add_link(e1, r1).unwrap();
+    net.add_link(r1, r2).unwrap();
+    net.add_link(r2, e2).unwrap();
+
+    net.set_link_weight(r1, r2, 1.0).unwrap();
+    net.set_link_weight(r2, r1, 1.0).unwrap();
+
+    net.set_bgp_session(e1, r1, Some(EBgp)).unwrap();
+    net.set_bgp_session(e2, r2, Some(EBgp)).unwrap();
+    net.set_bgp_session(r1, r2, Some(IBgpPeer)).unwrap();
+
+    net.set_bgp_route_map(
+        r2,
+        e2,
+        Incoming,
+        RouteMapBuilder::new()
+            .order(10)
+            .deny()
+            .match_prefix(p("20.0.0.0/16"))
+            .build(),
+    )
+    .unwrap();
+    net.set_bgp_route_map(
+        r2,
+        e2,
+        Incoming,
+        RouteMapBuilder::new().order(20).allow().build(),
+    )
+    .unwrap();
+
+    net.advertise_external_route(e1, p("10.0.0.0/16"), [65101, 65200], None, None)
+        .unwrap();
+    net.advertise_external_route(e2, p("10.1.0.0/16"), [65102, 65201], None, None)
+        .unwrap();
+    net.advertise_external_route(e2, p("20.0.0.0/16"), [65102, 65202], None, None)
+        .unwrap();
+
+    (net, [e1, r1, r2, e2])
+}
+
+#[test]
+fn verify_catalog_mixed() {
+    let (net, [_e1, r1, r2, _e2]) = get_test_net();
+
+    let rules = vec![
+        PolicyRule::new(p("10.0.0.0/8"), [r1, r2], Requirement::Reachable),
+        PolicyRule::new(p("0.0.0.0/0"), [r1], Requirement::NotReachable),
+        PolicyRule::new(p("20.0.0.0/8"), [r1, r2], Requirement::NotReachable),
+        PolicyRule::new(
+            p("10.0.0.0/16"),
+            [r2],
+            Requirement::PathCondition(PathCondition::Node(r1)),
+        ),
+        PolicyRule::new(p("30.0.0.0/8"), [r1, r2], Requirement::Reachable),
+    ];
+
+    let report = verify_catalog(&net, &rules);
+    assert_eq!(report.len(), rules.len());
+
+    // all prefixes in 10.0.0.0/8 are reachable from both routers
+    assert_eq!(report[0].prefixes, vec![p("10.0.0.0/16"), p("10.1.0.0/16")]);
+    assert_eq!(report[0].num_checked, 4);
+    assert!(report[0].passed());
+
+    // only 20.0.0.0/16 is isolated. The two others are counterexamples
+    assert_eq!(report[1].prefixes.len(), 3);
+    assert_eq!(report[1].num_checked, 3);
+    assert!(!report[1].passed());
+    assert_eq!(report[1].violations.len(), 2);
+    assert!(report[1].violations.iter().all(|e| matches!(
+        e,
+        PolicyError::UnallowedPathExists { router, .. } if *router == r1
+    )));
+
+    // the dropped prefix is isolated
+    assert_eq!(report[2].prefixes, vec![p("20.0.0.0/16")]);
+    assert!(report[2].passed());
+
+    // waypoint
+    assert!(report[3].passed());
+
+    // rules that match nothing are trivially satisfied.
+    assert_eq!(report[4].prefixes, vec![]);
+    assert_eq!(report[4].num_checked, 0);
+    assert!(report[4].passed());
+}
+
+#[test]
+fn check_all_same_order() {
+    let (net, [_e1, r1, r2, _e2]) = get_test_net();
+    let policies = vec![
+        FwPolicy::Reachable(r1, p("10.1.0.0/16")),
+        FwPolicy::Reachable(r2, p("20.0.0.0/16")),
+        FwPolicy::LoopFree(r2, p("20.0.0.0/16")),
+    ];
+    let mut fw_state = net.get_forwarding_state();
+    let result = check_all(&mut fw_state, &policies);
+    assert!(result[0].is_ok());
+    assert!(result[1].is_err());
+    assert!(result[2].is_ok());
+}
+
+#[test]
+fn recheck_equivalent_to_full() {
+    let (mut net, [e1, r1, r2, e2]) = get_test_net();
+    let prefixes = [p("10.0.0.0/16"), p("10.1.0.0/16"), p("20.0.0.0/16")];
+    let policies: Vec<FwPolicy<Ipv4Prefix>> = iproduct!([r1, r2], prefixes)
+        .flat_map(|(r, p)| {
+            [
+                FwPolicy::Reachable(r, p),
+                FwPolicy::NotReachable(r, p),
+                FwPolicy::LoopFree(r, p),
+                FwPolicy::PathCondition(r, p, PathCondition::Node(e1)),
+            ]
+        })
+        .collect();
+
+    let mut prev_state = net.get_forwarding_state();
+    net.withdraw_external_route(e2, p("10.1.0.0/16")).unwrap();
+    let mut new_state = net.get_forwarding_state();
+
+    let full: Vec<usize> = check_all(&mut prev_state.clone(), &policies)
+        .into_iter()
+        .zip(check_all(&mut new_state.clone(), &policies))
+        .enumerate()
+        .filter(|(_, (old, new))| old.is_ok() != new.is_ok())
+        .map(|(i, _)| i)
+        .collect();
+
+    let changes = recheck(&mut prev_state, &mut new_state, &policies);
+    let incremental: Vec<usize> = changes.iter().map(|c| c.index).collect();
+    assert_eq!(incremental, full);
+
+    // For both routers, `Reachable` breaks, while `NotReachable` and the (now vacuous) path
+    // condition become satisfied.
+    assert_eq!(changes.len(), 6);
+    assert_eq!(changes.iter().filter(|c| c.is_broken()).count(), 2);
+    assert_eq!(changes.iter().filter(|c| c.is_fixed()).count(), 4);
+
+    // nothing changes without any difference in the forwarding state
+    assert_eq!(
+        recheck(&mut new_state.clone(), &mut new_state, &policies),
+        vec![]
+    );
+}
+
+#[test]
+fn reduce_for_policy() {
+    let (mut net, [e1, r1, r2, e2]) = get_test_net();
+
+    // add a second path between r1 and r2 that is not used.
+    let r3 = net.add_router("R3");
+    let r4 = net.add_router("R4");
+    net.add_link(r1, r3).unwrap();
+    net.add_link(r3, r4).unwrap();
+    net.add_link(r4, r2).unwrap();
+    for r in [r1, r2] {
+        net.set_bgp_session(r, r3, Some(IBgpPeer)).unwrap();
+        net.set_bgp_session(r, r4, Some(IBgpPeer)).unwrap();
+    }
+    net.set_bgp_session(r3, r4, Some(IBgpPeer)).unwrap();
+
+    // satisfied policies cannot be reduced
+    let policy = FwPolicy::Reachable(r1, p("10.1.0.0/16"));
+    assert!(net.reduce_for_policy(&policy).unwrap().is_none());
+
+    // traffic from r1 to e2 does not traverse e1
+    let policy = FwPolicy::PathCondition(r1, p("10.1.0.0/16"), PathCondition::Node(e1));
+    assert!(policy.check(&mut net.get_forwarding_state()).is_err());
+    let reduced = net.reduce_for_policy(&policy).unwrap().unwrap();
+    assert!(policy.check(&mut reduced.get_forwarding_state()).is_err());
+
+    // only the path from r1 to e2 remains
+    let mut routers = reduced.device_indices().collect::<Vec<_>>();
+    routers.sort();
+    assert_eq!(routers, vec![r1, r2, e2]);
+    let mut sessions = reduced
+        .sessions()
+        .map(|s| (s.source, s.target))
+        .collect::<Vec<_>>();
+    sessions.sort();
+    assert_eq!(sessions, vec![(r1, r2), (r2, e2)]);
+    assert!(reduced.get_topology().find_edge(r1, r2).is_some());
+    assert!(reduced.get_topology().find_edge(r2, e2).is_some());
+
+    // the original network is not modified
+    assert_eq!(net.device_indices().count(), 6);
+}
+
+#[test]
+fn diagnose_battery() {
+    let (mut net, [_e1, r1, r2, e2]) = get_test_net();
+    net.remove_bgp_route_map(r2, e2, Incoming, 10).unwrap();
+
+    // the healthy network passes all checks
+    let report = diagnose(&net);
+    assert_eq!(
+        report.prefixes,
+        vec![p("10.0.0.0/16"), p("10.1.0.0/16"), p("20.0.0.0/16")]
+    );
+    assert_eq!(report.num_checked, 6);
+    assert!(report.passed());
+
+    // r2 drops the traffic towards 10.1.0.0/16, which affects both r1 and r2.
+    net.set_static_route(r2, p("10.1.0.0/16"), Some(StaticRoute::Drop))
+        .unwrap();
+    let report = diagnose(&net);
+    assert!(!report.passed());
+    assert!(!report.is_reachable());
+    assert!(report.is_loop_free());
+    assert_eq!(
+        report.black_holes,
+        vec![PolicyError::BlackHole {
+            router: r2,
+            prefix: p("10.1.0.0/16")
+        }]
+    );
+    assert_eq!(
+        report
+            .unreachable
+            .iter()
+            .map(|(r, _)| *r)
+            .collect::<Vec<_>>(),
+        vec![r1, r2]
+    );
+
+    // r2 sends the traffic back to r1, which creates a single forwarding loop.
+    net.set_static_route(r2, p("10.1.0.0/16"), Some(StaticRoute::Direct(r1)))
+        .unwrap();
+    let report = diagnose(&net);
+    assert!(report.is_black_hole_free());
+    assert!(!report.is_loop_free());
+    assert_eq!(report.forwarding_loops.len(), 1);
+    assert_eq!(report.unreachable.len(), 2);
+}
@@ -17,9 +17,14 @@
 
 use crate::{
     bgp::BgpSessionType::*,
+    config::{ConfigExpr, ConfigModifier},
     event::EventQueue,
     network::Network,
-    record::RecordNetwork,
+    policies::FwPolicy,
+    record::{
+        replay, resume, Checkpoint, ConvergenceRecording, RecordNetwork, ScenarioStep,
+        TimelineEventKind, TransientCheck,
+    },
     types::{AsId, NetworkError, RouterId, SinglePrefix as P},
 };
 
@@ -214,3 +219,234 @@ fn test_simple_deterministic() {
         vec![vec![b1, r1, r0, b0, e0]]
     );
 }
+
+#[test]
+fn test_event_timeline() {
+    let mut net: Network<P, _> = Network::default();
+    let prefix = P::from(0);
+
+    let (e0, _, _, _, b1, e1) = setup_simple(&mut net).unwrap();
+
+    net.advertise_external_route(e0, prefix, vec![AsId(1), AsId(2), AsId(3)], None, None)
+        .unwrap();
+    assert!(net.event_timeline().is_empty());
+
+    let rec = net
+        .record(|n| n.advertise_external_route(e1, prefix, vec![AsId(4), AsId(5)], None, None))
+        .unwrap();
+
+    let timeline = net.event_timeline();
+    assert_eq!(timeline.len(), rec.num_events());
+    assert!(timeline
+        .entries()
+        .windows(2)
+        .all(|w| w[0].time <= w[1].time));
+
+    // all forwarding changes (except the initial one) are caused by an event in the timeline
+    assert_eq!(
+        timeline.entries().iter().filter(|e| e.fw_change).count(),
+        rec.len() - 1
+    );
+
+    // the first event is the update from e1 to b1
+    let first = timeline.entries()[0];
+    assert_eq!(
+        (first.router, first.src, first.kind),
+        (b1, e1, TimelineEventKind::BgpUpdate)
+    );
+
+    // grouping by router keeps all events
+    assert_eq!(
+        timeline.by_router().values().map(Vec::len).sum::<usize>(),
+        timeline.len()
+    );
+}
+
+#[test]
+fn test_check_transient_stable_points() {
+    let mut net: Network<P, _> = Network::default();
+    let prefix = P::from(0);
+
+    let (e0, b0, r0, r1, b1, e1) = setup_simple(&mut net).unwrap();
+    net.advertise_external_route(e0, prefix, vec![AsId(1), AsId(2)], None, None)
+        .unwrap();
+
+    // r0 briefly points towards r1 (creating a loop) and is immediately fixed within the same
+    // logical step. Later, the loop persists while b1 changes its next-hop twice.
+    let trace = vec![
+        (vec![(r0, vec![b0], vec![r1])], Some(1.0).into()),
+        (vec![(r0, vec![r1], vec![b0])], Some(1.0).into()),
+        (vec![(b1, vec![r1], vec![e1])], Some(2.0).into()),
+        (vec![(r0, vec![b0], vec![r1])], Some(3.0).into()),
+        (vec![(b1, vec![e1], vec![r1])], Some(4.0).into()),
+        (vec![(b1, vec![r1], vec![e1])], Some(5.0).into()),
+        (vec![(r0, vec![r1], vec![b0])], Some(6.0).into()),
+    ];
+    let rec = ConvergenceRecording::new(net.get_forwarding_state(), trace);
+    let policy = FwPolicy::LoopFree(r1, prefix);
+
+    let positions = |mode| {
+        rec.check_transient(&policy, mode)
+            .into_iter()
+            .map(|(pos, _)| pos)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(positions(TransientCheck::EveryStep), vec![1, 4, 5, 6]);
+    // only the loop that persists for two steps is reported
+    assert_eq!(positions(TransientCheck::StablePoints(2)), vec![4]);
+    assert_eq!(
+        positions(TransientCheck::StablePoints(3)),
+        Vec::<usize>::new()
+    );
+    assert_eq!(rec.pos(), 0);
+}
+
+#[test]
+fn test_blackhole_durations() {
+    let mut net: Network<P, _> = Network::default();
+    let prefix = P::from(0);
+
+    let (e0, _b0, r0, r1, b1, e1) = setup_simple(&mut net).unwrap();
+
+    net.advertise_external_route(e0, prefix, vec![AsId(1), AsId(2), AsId(3)], None, None)
+        .unwrap();
+    net.advertise_external_route(e1, prefix, vec![AsId(4), AsId(5)], None, None)
+        .unwrap();
+    assert!(net.blackhole_durations(prefix).is_empty());
+
+    // withdrawing the preferred route causes b1, r1, and r0 to drop traffic, until the route from
+    // e0 reaches them. b0 directly switches to e0. The exact times depend on the order in which
+    // concurrent messages are processed, but the withdraw reaches b1 first, and the new route
+    // reaches b1 last.
+    net.record(|n| n.withdraw_external_route(e1, prefix))
+        .unwrap();
+    let blackholes = net.blackhole_durations(prefix);
+    assert_eq!(
+        blackholes.keys().copied().collect::<Vec<_>>(),
+        vec![r0, r1, b1]
+    );
+    let interval = |r: RouterId| match blackholes[&r].as_slice() {
+        [(start, Some(end))] => (*start, *end),
+        x => panic!("unexpected intervals of {r:?}: {x:?}"),
+    };
+    let (b1_start, b1_end) = interval(b1);
+    let (r1_start, r1_end) = interval(r1);
+    let (r0_start, r0_end) = interval(r0);
+    assert_eq!(b1_start, 1.0);
+    assert!(b1_start < r1_start && r1_start < r0_start && r0_start < r0_end);
+    assert!(r0_end < r1_end && r1_end < b1_end);
+
+    // withdrawing the last route causes all routers to drop traffic until the end.
+    net.record(|n| n.withdraw_external_route(e0, prefix))
+        .unwrap();
+    let blackholes = net.blackhole_durations(prefix);
+    assert_eq!(blackholes.len(), 4);
+    assert!(blackholes
+        .values()
+        .all(|intervals| intervals.len() == 1 && intervals[0].1.is_none()));
+}
+
+#[test]
+fn test_resume_from_checkpoint() {
+    let mut net: Network<P, _> = Network::default();
+    let prefix = P::from(0);
+    let (e0, _b0, r0, r1, _b1, e1) = setup_simple(&mut net).unwrap();
+    let initial = net.clone();
+
+    let log = vec![
+        ScenarioStep::Advertise {
+            source: e0,
+            prefix,
+            as_path: vec![AsId(1), AsId(2), AsId(3)],
+            med: None,
+            communities: vec![],
+        },
+        ScenarioStep::Advertise {
+            source: e1,
+            prefix,
+            as_path: vec![AsId(4), AsId(5)],
+            med: None,
+            communities: vec![],
+        },
+        ScenarioStep::Modify(ConfigModifier::Update {
+            from: ConfigExpr::IgpLinkWeight {
+                source: r0,
+                target: r1,
+                weight: 1.0,
+            },
+            to: ConfigExpr::IgpLinkWeight {
+                source: r0,
+                target: r1,
+                weight: 10.0,
+            },
+        }),
+        ScenarioStep::Withdraw { source: e1, prefix },
+        ScenarioStep::Advertise {
+            source: e1,
+            prefix,
+            as_path: vec![AsId(4)],
+            med: None,
+            communities: vec![],
+        },
+    ];
+
+    // full replay, capturing a checkpoint after the first two steps
+    let mut full = initial.clone();
+    replay(&mut full, &log[..2]).unwrap();
+    let checkpoint = Checkpoint::new(&full, 2).unwrap();
+    replay(&mut full, &log[2..]).unwrap();
+
+    // the full replay from the start yields the same state
+    let mut from_start = initial;
+    replay(&mut from_start, &log).unwrap();
+    assert!(full == from_start);
+
+    // resuming from the checkpoint yields the same state as the full replay
+    assert_eq!(checkpoint.position(), 2);
+    assert_eq!(checkpoint.remaining(&log), &log[2..]);
+    let resumed = resume(&checkpoint, checkpoint.remaining(&log)).unwrap();
+    assert!(resumed == full);
+    assert_eq!(
+        resumed.get_forwarding_state().get_paths(r0, prefix),
+        full.get_forwarding_state().get_paths(r0, prefix),
+    );
+
+    // the checkpoint is not modified, and can be resumed again.
+    assert!(resume(&checkpoint, checkpoint.remaining(&log)).unwrap() == full);
+    assert!(resume(&checkpoint, &[]).unwrap() != full);
+}
+
+#[test]
+fn test_state_size_timeline() {
+    let mut net: Network<P, _> = Network::default();
+    let prefix = P::from(0);
+    let (e0, _b0, _r0, _r1, _b1, e1) = setup_simple(&mut net).unwrap();
+    assert!(net.state_size_timeline().is_empty());
+
+    net.advertise_external_route(e0, prefix, vec![AsId(1), AsId(2), AsId(3)], None, None)
+        .unwrap();
+    net.advertise_external_route(e1, prefix, vec![AsId(4), AsId(5)], None, None)
+        .unwrap();
+    let before: usize = net.rib_sizes().values().sum();
+
+    // withdrawing the preferred route causes routers to explore the alternative route, while they
+    // still hold the old one.
+    let rec = net
+        .record(|n| n.withdraw_external_route(e1, prefix))
+        .unwrap();
+    let after: usize = net.rib_sizes().values().sum();
+    let timeline = net.state_size_timeline();
+    assert_eq!(timeline.len(), rec.num_events() + 1);
+    assert_eq!(timeline.first(), Some(&(0.0, before)));
+    assert_eq!(timeline.last().unwrap().1, after);
+    let peak = timeline.iter().map(|(_, s)| *s).max().unwrap();
+    assert!(peak > after);
+    assert!(timeline.windows(2).all(|w| w[0].0 < w[1].0));
+
+    // withdrawing the last route removes all entries.
+    net.record(|n| n.withdraw_external_route(e0, prefix))
+        .unwrap();
+    assert_eq!(net.state_size_timeline().last().unwrap().1, 0);
+    assert!(net.rib_sizes().values().all(|s| *s == 0));
+}
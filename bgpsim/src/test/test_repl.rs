@@ -0,0 +1,189 @@
+// BgpSim: BGP Network Simulator written in Rust
+// Copyright 2022-2024 Tibor Schneider <sctibor@ethz.ch>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the command loop of the interactive mode.
+
+use crate::{
+    bgp::BgpSessionType::*,
+    event::{BasicEventQueue, EventQueue},
+    interactive::{
+        repl::{execute, run, Command, ReplError},
+        InteractiveNetwork,
+    },
+    network::Network,
+    ospf::GlobalOspf,
+    types::{AsId, NetworkError, RouterId, SimplePrefix},
+};
+use pretty_assertions::assert_eq;
+
+type Net = Network<SimplePrefix, BasicEventQueue<SimplePrefix>, GlobalOspf>;
+
+/// # Test network
+///
+/// ```text
+/// E1 ---- R1 ---- R2 ---- E2
+/// ```
+fn get_test_net() -> (Net, [RouterId; 4]) {
+    let mut net = Net::default();
+    let e1 = net.add_external_router("E1", AsId(65101));
+    let r1 = net.add_router("R1");
+    let r2 = net.add_router("R2");
+    let e2 = net.add_external_router("E2", AsId(65102));
+
+    net.add_link(e1, r1).unwrap();
+    net.add_link(r1, r2).unwrap();
+    net.add_link(r2, e2).unwrap();
+
+    net.set_link_weight(r1, r2, 1.0).unwrap();
+    net.set_link_weight(r2, r1, 1.0).unwrap();
+
+    net.set_bgp_session(e1, r1, Some(EBgp)).unwrap();
+    net.set_bgp_session(e2, r2, Some(EBgp)).unwrap();
+    net.set_bgp_session(r1, r2, Some(IBgpPeer)).unwrap();
+
+    (net, [e1, r1, r2, e2])
+}
+
+fn parse(s: &str) -> Result<Command<SimplePrefix>, ReplError> {
+    s.parse()
+}
+
+#[test]
+fn parse_commands() {
+    let p = SimplePrefix::from(0);
+    let p_str = p.to_string();
+    assert_eq!(
+        parse(&format!("advertise E1 {p_str} 65101 100")),
+        Ok(Command::Advertise {
+            router: "E1".to_string(),
+            prefix: p,
+            as_path: vec![AsId(65101), AsId(100)],
+        })
+    );
+    assert_eq!(
+        parse(&format!("  withdraw   E1 {p_str} ")),
+        Ok(Command::Withdraw {
+            router: "E1".to_string(),
+            prefix: p,
+        })
+    );
+    assert_eq!(
+        parse("fail R1 R2"),
+        Ok(Command::Fail("R1".to_string(), "R2".to_string()))
+    );
+    assert_eq!(parse("step"), Ok(Command::Step(1)));
+    assert_eq!(parse("step 5"), Ok(Command::Step(5)));
+    assert_eq!(
+        parse(&format!("trace R1 {p_str}")),
+        Ok(Command::Trace {
+            router: "R1".to_string(),
+            prefix: p,
+        })
+    );
+    assert_eq!(parse("rib R2"), Ok(Command::Rib("R2".to_string())));
+    assert_eq!(parse(&format!("check {p_str}")), Ok(Command::Check(p)));
+
+    assert_eq!(parse(""), Err(ReplError::EmptyCommand));
+    assert_eq!(
+        parse("simulate"),
+        Err(ReplError::UnknownCommand("simulate".to_string()))
+    );
+    assert_eq!(
+        parse("fail R1"),
+        Err(ReplError::MissingArgument("fail", "router"))
+    );
+    assert_eq!(
+        parse("rib R1 R2"),
+        Err(ReplError::UnexpectedArgument("R2".to_string()))
+    );
+    assert_eq!(
+        parse("trace R1 foo"),
+        Err(ReplError::InvalidPrefix("foo".to_string()))
+    );
+    assert_eq!(
+        parse("step x"),
+        Err(ReplError::InvalidNumber("x".to_string()))
+    );
+}
+
+#[test]
+fn execute_commands() {
+    let (mut net, [e1, r1, r2, e2]) = get_test_net();
+    let p = SimplePrefix::from(0);
+    let p_str = p.to_string();
+    net.manual_simulation();
+
+    // advertise only enqueues events in manual simulation.
+    let cmd = parse(&format!("advertise E1 {p_str} 65101 100")).unwrap();
+    assert_eq!(execute(&mut net, &cmd).unwrap(), "1 events queued");
+    assert!(net.get_external_router(e1).unwrap().has_active_route(p));
+
+    // step processes a single event.
+    let out = execute(&mut net, &parse("step").unwrap()).unwrap();
+    assert!(out.starts_with("BGP Event: E1 -> R1"));
+    assert!(out.ends_with("X --> E1"));
+    assert_eq!(net.queue().len(), 1);
+    let out = execute(&mut net, &parse("step 10").unwrap()).unwrap();
+    assert!(out.ends_with("The queue is empty."));
+    assert!(net.queue().is_empty());
+
+    // trace, rib, and check inspect the network.
+    let trace = parse(&format!("trace R2 {p_str}")).unwrap();
+    assert_eq!(execute(&mut net, &trace).unwrap(), "R2 -> R1 -> E1");
+    let rib = execute(&mut net, &parse("rib R2").unwrap()).unwrap();
+    assert!(rib.contains("as_path: [65101, 100]"));
+    assert_eq!(rib.lines().count(), 1);
+    let check = parse(&format!("check {p_str}")).unwrap();
+    assert_eq!(
+        execute(&mut net, &check).unwrap(),
+        format!("All routers reach {p_str}.")
+    );
+
+    // fail removes the link
+    net.auto_simulation();
+    execute(&mut net, &parse("fail R1 R2").unwrap()).unwrap();
+    assert!(net.get_topology().find_edge(r1, r2).is_none());
+    assert!(execute(&mut net, &check)
+        .unwrap()
+        .starts_with("R2: Black hole"));
+
+    // withdraw removes the route
+    let cmd = parse(&format!("withdraw E1 {p_str}")).unwrap();
+    execute(&mut net, &cmd).unwrap();
+    assert!(!net.get_external_router(e1).unwrap().has_active_route(p));
+    assert!(!net.get_external_router(e2).unwrap().has_active_route(p));
+
+    // unknown routers result in an error.
+    assert_eq!(
+        execute(&mut net, &parse("rib R3").unwrap()),
+        Err(ReplError::Network(NetworkError::DeviceNameNotFound(
+            "R3".to_string()
+        )))
+    );
+}
+
+#[test]
+fn run_commands() {
+    let (mut net, _) = get_test_net();
+    let p_str = SimplePrefix::from(0).to_string();
+    let input =
+        format!("# comment\n\nadvertise E2 {p_str} 65102\ntrace R1 {p_str}\nfoo\nexit\nrib R1\n");
+    let mut output = Vec::new();
+    run(&mut net, input.as_bytes(), &mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "0 events queued\nR1 -> R2 -> E2\nError: Unknown command: foo\n"
+    );
+}
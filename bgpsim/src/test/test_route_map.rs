@@ -15,6 +15,7 @@
 
 use maplit::btreeset;
 use ordered_float::NotNan;
+use std::collections::HashMap;
 
 use crate::{
     bgp::{BgpRibEntry, BgpRoute, BgpSessionType::*},
@@ -22,7 +23,7 @@ use crate::{
         RouteMapFlow::*, RouteMapMatch as Match, RouteMapMatchAsPath as AClause,
         RouteMapMatchClause as Clause, RouteMapSet as Set, RouteMapState::*, *,
     },
-    types::{AsId, Ipv4Prefix, Prefix, SimplePrefix, SinglePrefix},
+    types::{AsId, Community, Ipv4Prefix, Prefix, SimplePrefix, SinglePrefix},
 };
 
 #[generic_tests::define]
@@ -38,7 +39,7 @@ mod t1 {
                 next_hop: 0.into(),
                 local_pref: Some(1),
                 med: Some(10),
-                community: Default::default(),
+                communities: Default::default(),
                 originator_id: None,
                 cluster_list: Vec::new(),
             },
@@ -47,6 +48,10 @@ mod t1 {
             to_id: None,
             igp_cost: Some(NotNan::new(10.0).unwrap()),
             weight: 100,
+            bgp_id: None,
+            tag: 0,
+            dampable: false,
+            rpki: None,
         };
 
         // Next Hop
@@ -201,7 +206,13 @@ mod t1 {
         );
 
         assert_eq!(
-            RouteMap::<P>::new(10, Deny, vec![Match::Community(0)], vec![], Continue),
+            RouteMap::<P>::new(
+                10,
+                Deny,
+                vec![Match::Community(Community::from(0))],
+                vec![],
+                Continue
+            ),
             RouteMapBuilder::<P>::new()
                 .order(10)
                 .deny()
@@ -273,7 +284,13 @@ mod t1 {
         );
 
         assert_eq!(
-            RouteMap::<P>::new(10, Allow, vec![], vec![Set::SetCommunity(10)], Continue),
+            RouteMap::<P>::new(
+                10,
+                Allow,
+                vec![],
+                vec![Set::SetCommunity(Community::from(10))],
+                Continue
+            ),
             RouteMapBuilder::<P>::new()
                 .order(10)
                 .allow()
@@ -282,7 +299,13 @@ mod t1 {
         );
 
         assert_eq!(
-            RouteMap::<P>::new(10, Allow, vec![], vec![Set::DelCommunity(10)], Continue),
+            RouteMap::<P>::new(
+                10,
+                Allow,
+                vec![],
+                vec![Set::DelCommunity(Community::from(10))],
+                Continue
+            ),
             RouteMapBuilder::<P>::new()
                 .order(10)
                 .allow()
@@ -300,7 +323,7 @@ mod t1 {
                 next_hop: 0.into(),
                 local_pref: None,
                 med: None,
-                community: Default::default(),
+                communities: Default::default(),
                 originator_id: None,
                 cluster_list: Vec::new(),
             },
@@ -309,6 +332,10 @@ mod t1 {
             to_id: None,
             igp_cost: Some(NotNan::new(10.0).unwrap()),
             weight: 100,
+            bgp_id: None,
+            tag: 0,
+            dampable: false,
+            rpki: None,
         };
 
         let rms = vec![
@@ -342,8 +369,8 @@ mod t1 {
         ];
 
         assert_eq!(
-            rms.apply(entry).unwrap().route.community,
-            btreeset! {10, 20, 30}
+            rms.apply(entry).unwrap().route.communities,
+            btreeset! {Community::from(10), Community::from(20), Community::from(30)}
         );
     }
 
@@ -356,7 +383,7 @@ mod t1 {
                 next_hop: 0.into(),
                 local_pref: None,
                 med: None,
-                community: Default::default(),
+                communities: Default::default(),
                 originator_id: None,
                 cluster_list: Vec::new(),
             },
@@ -365,6 +392,10 @@ mod t1 {
             to_id: None,
             igp_cost: Some(NotNan::new(10.0).unwrap()),
             weight: 100,
+            bgp_id: None,
+            tag: 0,
+            dampable: false,
+            rpki: None,
         };
 
         let rms = vec![
@@ -398,11 +429,81 @@ mod t1 {
         ];
 
         assert_eq!(
-            rms.apply(entry).unwrap().route.community,
-            btreeset! {10, 30, 40}
+            rms.apply(entry).unwrap().route.communities,
+            btreeset! {Community::from(10), Community::from(30), Community::from(40)}
         );
     }
 
+    #[test]
+    fn control_flow_continue_at_accumulates<P: Prefix>() {
+        let entry = BgpRibEntry {
+            route: BgpRoute {
+                prefix: P::from(0),
+                as_path: vec![AsId(0)],
+                next_hop: 0.into(),
+                local_pref: None,
+                med: None,
+                communities: Default::default(),
+                originator_id: None,
+                cluster_list: Vec::new(),
+            },
+            from_type: IBgpClient,
+            from_id: 0.into(),
+            to_id: None,
+            igp_cost: Some(NotNan::new(10.0).unwrap()),
+            weight: 100,
+            bgp_id: None,
+            tag: 0,
+            dampable: false,
+            rpki: None,
+        };
+
+        let rms = vec![
+            RouteMapBuilder::<P>::new()
+                .order(10)
+                .allow()
+                .set_local_pref(200)
+                .continue_at(30)
+                .build(),
+            RouteMapBuilder::<P>::new().order(20).deny().build(),
+            RouteMapBuilder::<P>::new()
+                .order(30)
+                .allow()
+                .set_community(10)
+                .exit()
+                .build(),
+            RouteMapBuilder::<P>::new()
+                .order(40)
+                .allow()
+                .set_community(20)
+                .build(),
+        ];
+
+        // both set actions are applied, and the deny clause in between is skipped.
+        let route = rms.apply(entry.clone()).unwrap().route;
+        assert_eq!(route.local_pref, Some(200));
+        assert_eq!(route.communities, btreeset! {Community::from(10)});
+
+        // a backward jump stops applying route-maps.
+        let rms = vec![
+            RouteMap::new(
+                10,
+                Allow,
+                vec![],
+                vec![Set::LocalPref(Some(200))],
+                ContinueAt(5),
+            ),
+            RouteMapBuilder::<P>::new()
+                .order(20)
+                .allow()
+                .set_community(10)
+                .build(),
+        ];
+        let route = rms.apply(entry).unwrap().route;
+        assert_eq!(route.local_pref, Some(200));
+        assert_eq!(route.communities, btreeset! {});
+    }
+
     #[test]
     fn control_flow_continue_at_miss<P: Prefix>() {
         let entry = BgpRibEntry {
@@ -412,7 +513,7 @@ mod t1 {
                 next_hop: 0.into(),
                 local_pref: None,
                 med: None,
-                community: Default::default(),
+                communities: Default::default(),
                 originator_id: None,
                 cluster_list: Vec::new(),
             },
@@ -421,6 +522,10 @@ mod t1 {
             to_id: None,
             igp_cost: Some(NotNan::new(10.0).unwrap()),
             weight: 100,
+            bgp_id: None,
+            tag: 0,
+            dampable: false,
+            rpki: None,
         };
 
         let rms = vec![
@@ -453,7 +558,10 @@ mod t1 {
                 .build(),
         ];
 
-        assert_eq!(rms.apply(entry).unwrap().route.community, btreeset! {10});
+        assert_eq!(
+            rms.apply(entry).unwrap().route.communities,
+            btreeset! {Community::from(10)}
+        );
     }
 
     #[test]
@@ -465,7 +573,7 @@ mod t1 {
                 next_hop: 0.into(),
                 local_pref: None,
                 med: None,
-                community: Default::default(),
+                communities: Default::default(),
                 originator_id: None,
                 cluster_list: Vec::new(),
             },
@@ -474,6 +582,10 @@ mod t1 {
             to_id: None,
             igp_cost: Some(NotNan::new(10.0).unwrap()),
             weight: 100,
+            bgp_id: None,
+            tag: 0,
+            dampable: false,
+            rpki: None,
         };
 
         let rms = vec![
@@ -506,7 +618,10 @@ mod t1 {
                 .build(),
         ];
 
-        assert_eq!(rms.apply(entry).unwrap().route.community, btreeset! {10});
+        assert_eq!(
+            rms.apply(entry).unwrap().route.communities,
+            btreeset! {Community::from(10)}
+        );
     }
 
     #[instantiate_tests(<SinglePrefix>)]
@@ -532,7 +647,7 @@ mod t2 {
                 next_hop: 0.into(),
                 local_pref: None,
                 med: None,
-                community: Default::default(),
+                communities: Default::default(),
                 originator_id: None,
                 cluster_list: Vec::new(),
             },
@@ -541,6 +656,10 @@ mod t2 {
             to_id: None,
             igp_cost: Some(NotNan::new(10.0).unwrap()),
             weight: 100,
+            bgp_id: None,
+            tag: 0,
+            dampable: false,
+            rpki: None,
         };
 
         // Match on NextHop
@@ -653,15 +772,21 @@ mod t2 {
         assert!(map.apply(entry).1.is_some());
 
         // Match on Community, exact
-        let map = RouteMap::new(10, Deny, vec![Match::Community(0)], vec![], Continue);
+        let map = RouteMap::new(
+            10,
+            Deny,
+            vec![Match::Community(Community::from(0))],
+            vec![],
+            Continue,
+        );
         let mut entry = default_entry;
-        entry.route.community = Default::default();
+        entry.route.communities = Default::default();
         assert_eq!(map.apply(entry.clone()).0, Continue);
         assert!(map.apply(entry.clone()).1.is_some());
-        entry.route.community.insert(1);
+        entry.route.communities.insert(Community::from(1));
         assert_eq!(map.apply(entry.clone()).0, Continue);
         assert!(map.apply(entry.clone()).1.is_some());
-        entry.route.community.insert(0);
+        entry.route.communities.insert(Community::from(0));
         assert_eq!(map.apply(entry.clone()).0, Exit);
         assert!(map.apply(entry).1.is_none());
     }
@@ -675,7 +800,7 @@ mod t2 {
                 next_hop: 0.into(),
                 local_pref: None,
                 med: None,
-                community: Default::default(),
+                communities: Default::default(),
                 originator_id: None,
                 cluster_list: Vec::new(),
             },
@@ -684,6 +809,10 @@ mod t2 {
             to_id: None,
             igp_cost: Some(NotNan::new(10.0).unwrap()),
             weight: 100,
+            bgp_id: None,
+            tag: 0,
+            dampable: false,
+            rpki: None,
         };
 
         // And Clause
@@ -745,6 +874,176 @@ mod t2 {
         );
     }
 
+    #[test]
+    fn policy_diff<P: Prefix>() {
+        let deny = |order: i16, p: u32| {
+            RouteMapBuilder::<P>::new()
+                .order_sgn(order)
+                .deny()
+                .match_prefix(p.into())
+                .build()
+        };
+        let set_lp = |order: i16, lp: u32| {
+            RouteMapBuilder::<P>::new()
+                .order_sgn(order)
+                .allow()
+                .match_prefix(1.into())
+                .set_local_pref(lp)
+                .build()
+        };
+        let set_med = RouteMapBuilder::<P>::new()
+            .order(30)
+            .allow()
+            .match_community(5)
+            .set_med(10)
+            .build();
+        let a = vec![deny(10, 0), set_lp(20, 200), set_med.clone()];
+
+        // reordering independent clauses has no effect
+        let b = vec![set_med.clone(), deny(20, 0), set_lp(10, 200)];
+        assert_eq!(diff(&a, &b), vec![]);
+        assert_eq!(diff(&a, &a), vec![]);
+
+        // changing the denied prefix changes which routes are permitted
+        let b = vec![deny(10, 2), set_lp(20, 200), set_med.clone()];
+        let delta = diff(&a, &b);
+        assert!(!delta.is_empty());
+        assert!(delta.iter().all(|d| d.is_permit_change()));
+        assert!(delta
+            .iter()
+            .any(|d| d.route.route.prefix == P::from(0) && d.a.is_none() && d.b.is_some()));
+        assert!(delta
+            .iter()
+            .any(|d| d.route.route.prefix == P::from(2) && d.a.is_some() && d.b.is_none()));
+
+        // changing a set action changes the resulting route.
+        let b = vec![deny(10, 0), set_lp(20, 300), set_med.clone()];
+        let delta = diff(&a, &b);
+        assert!(!delta.is_empty());
+        assert!(delta.iter().all(|d| !d.is_permit_change()
+            && d.route.route.prefix == P::from(1)
+            && d.a.as_ref().unwrap().route.local_pref == Some(200)
+            && d.b.as_ref().unwrap().route.local_pref == Some(300)));
+
+        // reordering dependent clauses shows a difference for routes that match both.
+        let deny_com = |order: i16| {
+            RouteMapBuilder::<P>::new()
+                .order_sgn(order)
+                .deny()
+                .match_community(5)
+                .build()
+        };
+        let set_lp_exit = |order: i16| {
+            RouteMapBuilder::<P>::new()
+                .order_sgn(order)
+                .allow()
+                .match_prefix(1.into())
+                .set_local_pref(200)
+                .exit()
+                .build()
+        };
+        let a = vec![deny_com(10), set_lp_exit(20)];
+        let b = vec![deny_com(20), set_lp_exit(10)];
+        let delta = diff(&a, &b);
+        assert_eq!(delta.len(), 1);
+        assert_eq!(delta[0].route.route.prefix, P::from(1));
+        assert!(delta[0]
+            .route
+            .route
+            .communities
+            .contains(&Community::from(5)));
+        assert!(delta[0].a.is_none());
+    }
+
+    #[test]
+    fn clause_shadowing<P: Prefix>() {
+        let entry = |p: u32| BgpRibEntry {
+            route: BgpRoute {
+                prefix: P::from(p),
+                as_path: vec![AsId(0)],
+                next_hop: 0.into(),
+                local_pref: None,
+                med: None,
+                communities: Default::default(),
+                originator_id: None,
+                cluster_list: Vec::new(),
+            },
+            from_type: IBgpClient,
+            from_id: 0.into(),
+            to_id: None,
+            igp_cost: Some(NotNan::new(10.0).unwrap()),
+            weight: 100,
+            bgp_id: None,
+            tag: 0,
+            dampable: false,
+            rpki: None,
+        };
+        let samples = || (0..3).map(entry);
+
+        let deny_0 = RouteMapBuilder::<P>::new()
+            .order(10)
+            .deny()
+            .match_prefix(0.into())
+            .build();
+        let set_lp = RouteMapBuilder::<P>::new()
+            .order(20)
+            .allow()
+            .match_prefix(1.into())
+            .set_local_pref(200)
+            .build();
+        let catch_all = RouteMapBuilder::<P>::new()
+            .order(15)
+            .allow()
+            .set_med(5)
+            .exit()
+            .build();
+
+        // all routes but the denied one reach the clause, but only one matches.
+        let effect = clause_effect(&[set_lp.clone(), deny_0.clone()], 20, samples());
+        assert_eq!(effect.len(), 2);
+        assert!(effect.iter().all(|e| e.route.route.prefix != P::from(0)));
+        let hit = effect.iter().find(|e| e.matched).unwrap();
+        assert_eq!(hit.route.route.prefix, P::from(1));
+        assert_eq!(hit.input, hit.route);
+        assert_eq!(hit.output.as_ref().unwrap().route.local_pref, Some(200));
+        let miss = effect.iter().find(|e| !e.matched).unwrap();
+        assert_eq!(miss.output.as_ref(), Some(&miss.input));
+
+        // earlier clauses modify the route before it reaches the clause
+        let set_med = RouteMapBuilder::<P>::new()
+            .order(15)
+            .allow()
+            .set_med(5)
+            .continue_next()
+            .build();
+        let effect = clause_effect(&[deny_0.clone(), set_med, set_lp.clone()], 20, samples());
+        assert_eq!(effect.len(), 2);
+        assert!(effect.iter().all(|e| e.input.route.med == Some(5)));
+
+        // a catch-all clause shadows the later clause
+        let effect = clause_effect(&[deny_0.clone(), catch_all, set_lp.clone()], 20, samples());
+        assert_eq!(effect, vec![]);
+        assert_eq!(
+            clause_effect(&[deny_0, set_lp.clone()], 30, samples()),
+            vec![]
+        );
+
+        // named AS-path access-lists are only known in `clause_effect_with`
+        let deny_list = RouteMapBuilder::<P>::new()
+            .order(10)
+            .deny()
+            .match_as_path_list("own")
+            .build();
+        let as_path_lists = HashMap::from([(
+            "own".to_string(),
+            AsPathList::new([(Allow, AClause::Contains(AsId(0)))]),
+        )]);
+        let effect = clause_effect(&[deny_list.clone(), set_lp.clone()], 20, samples());
+        assert_eq!(effect.len(), 3);
+        let effect = clause_effect_with(&[deny_list, set_lp], 20, samples(), &as_path_lists);
+        assert_eq!(effect, vec![]);
+    }
+
     #[instantiate_tests(<SimplePrefix>)]
     mod simple {}
 
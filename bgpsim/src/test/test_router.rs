@@ -20,7 +20,7 @@ use crate::{
     event::Event,
     external_router::*,
     router::*,
-    types::{AsId, Ipv4Prefix, Prefix, SimplePrefix, SinglePrefix},
+    types::{AsId, Community, Ipv4Prefix, Prefix, SimplePrefix, SinglePrefix},
 };
 
 use maplit::{hashmap, hashset};
@@ -74,7 +74,7 @@ mod t2 {
                     next_hop: 100.into(),
                     local_pref: None,
                     med: None,
-                    community: Default::default(),
+                    communities: Default::default(),
                     originator_id: None,
                     cluster_list: Vec::new(),
                 }),
@@ -120,7 +120,7 @@ mod t2 {
                     next_hop: 11.into(),
                     local_pref: Some(50),
                     med: None,
-                    community: Default::default(),
+                    communities: Default::default(),
                     originator_id: None,
                     cluster_list: Vec::new(),
                 }),
@@ -170,7 +170,7 @@ mod t2 {
                     next_hop: 10.into(),
                     local_pref: None,
                     med: None,
-                    community: Default::default(),
+                    communities: Default::default(),
                     originator_id: None,
                     cluster_list: Vec::new(),
                 }),
@@ -211,7 +211,7 @@ mod t2 {
                     next_hop: 5.into(),
                     local_pref: Some(150),
                     med: None,
-                    community: Default::default(),
+                    communities: Default::default(),
                     originator_id: None,
                     cluster_list: Vec::new(),
                 }),
@@ -389,7 +389,7 @@ mod t1 {
                     next_hop: 0.into(),
                     local_pref: None,
                     med: None,
-                    community: Default::default(),
+                    communities: Default::default(),
                     originator_id: None,
                     cluster_list: Vec::new(),
                 }),
@@ -414,7 +414,7 @@ mod t1 {
 
         // advertise route
         let (_, events) =
-            r.advertise_prefix::<(), Option<u32>>(P::from(0), vec![AsId(0)], None, None);
+            r.advertise_prefix::<(), Option<Community>>(P::from(0), vec![AsId(0)], None, None);
 
         // check that no event was created
         assert_eq!(events.len(), 0);
@@ -436,7 +436,7 @@ mod t1 {
                     next_hop: 0.into(),
                     local_pref: None,
                     med: None,
-                    community: Default::default(),
+                    communities: Default::default(),
                     originator_id: None,
                     cluster_list: Vec::new(),
                 }),
@@ -444,7 +444,7 @@ mod t1 {
         );
 
         // first, remove the neighbor, then stop advertising
-        r.close_ebgp_session(1.into()).unwrap();
+        r.close_ebgp_session::<()>(1.into()).unwrap();
 
         // then, withdraw the session
         let events = r.withdraw_prefix::<()>(P::from(0));
@@ -21,6 +21,7 @@ mod t {
     use serde_json::Value;
 
     use crate::{
+        bgp::DEFAULT_RECURSION_LIMIT,
         builder::{
             best_others_equal_preferences, extend_to_k_external_routers,
             uniform_integer_link_weight, NetworkBuilder,
@@ -108,6 +109,59 @@ mod t {
         assert!(restored.weak_eq(&net));
     }
 
+    #[test]
+    fn import_without_bgp_settings<P: Prefix, Ospf: OspfImpl>() {
+        /// Remove all BGP settings and router-local route attributes that may be missing from
+        /// networks serialized by older versions.
+        fn strip(value: &mut Value) {
+            match value {
+                Value::Object(obj) => {
+                    if obj.contains_key("rib_in") {
+                        for key in [
+                            "rpki",
+                            "local_as",
+                            "allowas_in",
+                            "route_reflector",
+                            "bgp_ids",
+                            "session_defaults",
+                            "session_options",
+                            "mrai",
+                            "as_path_ignore",
+                            "prefer_oldest",
+                            "no_next_hop_self",
+                            "graceful_shutdown",
+                            "as_path_lists",
+                            "attribute_propagation",
+                            "next_hop_resolution",
+                            "recursion_limit",
+                        ] {
+                            assert!(obj.remove(key).is_some(), "{key} is not serialized");
+                        }
+                    }
+                    if obj.contains_key("from_type") {
+                        obj.remove("rpki");
+                    }
+                    obj.values_mut().for_each(strip);
+                }
+                Value::Array(values) => values.iter_mut().for_each(strip),
+                _ => {}
+            }
+        }
+
+        let net = get_net::<P, Ospf>();
+        let mut json_obj: Value = serde_json::from_str(&net.as_json_str()).unwrap();
+        strip(&mut json_obj);
+        let modified_json_str = serde_json::to_string(&json_obj).unwrap();
+        assert!(net.as_json_str().contains("\"recursion_limit\""));
+        assert!(!modified_json_str.contains("\"recursion_limit\""));
+        let restored: Network<P, _, Ospf> =
+            Network::from_json_str(&modified_json_str, BasicEventQueue::default).unwrap();
+        assert!(restored.weak_eq(&net));
+        for r in restored.internal_routers() {
+            assert_eq!(r.bgp.get_recursion_limit(), DEFAULT_RECURSION_LIMIT);
+        }
+    }
+
     #[instantiate_tests(<SinglePrefix, GlobalOspf>)]
     mod single_global {}
 
@@ -18,7 +18,7 @@ use crate::{
     event::BasicEventQueue,
     network::Network,
     ospf::{GlobalOspf, LocalOspf, OspfImpl},
-    types::{Ipv4Prefix, Prefix, SimplePrefix, SinglePrefix},
+    types::{Community, Ipv4Prefix, Prefix, SimplePrefix, SinglePrefix},
 };
 use serde_json::{from_str, to_string_pretty};
 
@@ -70,3 +70,23 @@ mod t {
     #[instantiate_tests(<Ipv4Prefix, LocalOspf>)]
     mod ipv4_local {}
 }
+
+#[test]
+fn community_format() {
+    assert_eq!(Community::from(10), Community(0, 10));
+    assert_eq!(Community::from(0xFFFF_FF01), Community(65535, 65281));
+    assert_eq!("100:10".parse::<Community>().unwrap(), Community(100, 10));
+    assert_eq!("10".parse::<Community>().unwrap(), Community(0, 10));
+    assert!("100:".parse::<Community>().is_err());
+    assert_eq!(Community(100, 10).to_string(), "100:10");
+
+    // old files store communities as plain numbers.
+    assert_eq!(from_str::<Community>("10").unwrap(), Community(0, 10));
+    assert_eq!(from_str::<Community>("65536").unwrap(), Community(1, 0));
+    assert_eq!(
+        from_str::<Community>("[100,10]").unwrap(),
+        Community(100, 10)
+    );
+    let json = serde_json::to_string(&Community(100, 10)).unwrap();
+    assert_eq!(from_str::<Community>(&json).unwrap(), Community(100, 10));
+}
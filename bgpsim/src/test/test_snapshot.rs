@@ -0,0 +1,119 @@
+// BgpSim: BGP Network Simulator written in Rust
+// Copyright 2022-2024 Tibor Schneider <sctibor@ethz.ch>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test capturing and comparing snapshots.
+
+use crate::{
+    bgp::BgpSessionType::*,
+    event::BasicEventQueue,
+    network::Network,
+    ospf::GlobalOspf,
+    policies::{FwPolicy, PathCondition},
+    route_map::{RouteMapBuilder, RouteMapDirection::Incoming},
+    snapshot::{Snapshot, SnapshotError},
+    types::{AsId, RouterId, SimplePrefix},
+};
+use pretty_assertions::assert_eq;
+
+type Net = Network<SimplePrefix, BasicEventQueue<SimplePrefix>, GlobalOspf>;
+
+/// # Test network
+///
+/// ```text
+/// E1 ---- R1 ---- R2 ---- E2
+/// ```
+///
+/// Both E1 and E2 advertise the same prefix.
+fn get_test_net() -> (Net, [RouterId; 4]) {
+    let mut net = Net::default();
+    let e1 = net.add_external_router("E1", AsId(65101));
+    let r1 = net.add_router("R1");
+    let r2 = net.add_router("R2");
+    let e2 = net.add_external_router("E2", AsId(65102));
+
+    net.add_link(e1, r1).unwrap();
+    net.add_link(r1, r2).unwrap();
+    net.add_link(r2, e2).unwrap();
+
+    net.set_link_weight(r1, r2, 1.0).unwrap();
+    net.set_link_weight(r2, r1, 1.0).unwrap();
+
+    net.set_bgp_session(e1, r1, Some(EBgp)).unwrap();
+    net.set_bgp_session(e2, r2, Some(EBgp)).unwrap();
+    net.set_bgp_session(r1, r2, Some(IBgpPeer)).unwrap();
+
+    let p = SimplePrefix::from(0);
+    net.advertise_external_route(e1, p, [65101, 100], None, None)
+        .unwrap();
+    net.advertise_external_route(e2, p, [65102, 100], None, None)
+        .unwrap();
+
+    (net, [e1, r1, r2, e2])
+}
+
+#[test]
+fn compare_policy_change() {
+    let (mut net, [e1, r1, r2, _e2]) = get_test_net();
+    let p = SimplePrefix::from(0);
+    let policies = [
+        FwPolicy::Reachable(r1, p),
+        FwPolicy::PathCondition(r1, p, PathCondition::Node(r2)),
+    ];
+
+    let a = Snapshot::capture(&net);
+    assert!(a.summary.is_converged());
+    assert!(Snapshot::compare(&a, &a, &policies).unwrap().is_empty());
+
+    // prefer the route from E2 on R1
+    net.set_bgp_route_map(
+        r1,
+        e1,
+        Incoming,
+        RouteMapBuilder::new()
+            .order(10)
+            .allow()
+            .set_local_pref(50)
+            .build(),
+    )
+    .unwrap();
+    let b = Snapshot::capture(&net);
+    assert!(a.same_topology(&b));
+
+    let diff = Snapshot::compare(&a, &b, &policies).unwrap();
+    assert_eq!(diff.fib, vec![(r1, p, vec![e1], vec![r2])]);
+
+    assert_eq!(diff.rib.len(), 1);
+    let (router, prefix, old, new) = &diff.rib[0];
+    assert_eq!((*router, *prefix), (r1, p));
+    assert_eq!(old.as_ref().unwrap().from_id, e1);
+    assert_eq!(new.as_ref().unwrap().from_id, r2);
+
+    assert_eq!(diff.policies.len(), 1);
+    assert_eq!(diff.policies[0].index, 1);
+    assert!(diff.policies[0].is_fixed());
+}
+
+#[test]
+fn compare_different_topology() {
+    let (mut net, _) = get_test_net();
+    let a = Snapshot::capture(&net);
+    net.add_router("R3");
+    let b = Snapshot::capture(&net);
+    assert!(!a.same_topology(&b));
+    assert_eq!(
+        Snapshot::compare::<FwPolicy<SimplePrefix>>(&a, &b, &[]).unwrap_err(),
+        SnapshotError::DifferentRouters
+    );
+}
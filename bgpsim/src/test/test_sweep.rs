@@ -0,0 +1,144 @@
+// BgpSim: BGP Network Simulator written in Rust
+// Copyright 2022-2024 Tibor Schneider <sctibor@ethz.ch>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test parameter sweeps.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    bgp::BgpSessionType::*,
+    event::BasicEventQueue,
+    network::Network,
+    ospf::GlobalOspf,
+    sweep::*,
+    types::{AsId, NetworkError, RouterId, SimplePrefix},
+};
+use pretty_assertions::assert_eq;
+
+type Net = Network<SimplePrefix, BasicEventQueue<SimplePrefix>, GlobalOspf>;
+
+/// # Test network
+///
+/// ```text
+///        R1
+///    w /    \ 10
+///     R2    R3
+///     |      |
+///     E2    E3
+/// ```
+///
+/// All internal routers are in an iBGP full mesh, and both E2 and E3 advertise the same prefix.
+fn get_test_net() -> (Net, [RouterId; 5]) {
+    let mut net = Net::default();
+    let r1 = net.add_router("R1");
+    let r2 = net.add_router("R2");
+    let r3 = net.add_router("R3");
+    let e2 = net.add_external_router("E2", AsId(65102));
+    let e3 = net.add_external_router("E3", AsId(65103));
+
+    net.add_link(r1, r2).unwrap();
+    net.add_link(r1, r3).unwrap();
+    net.add_link(r2, e2).unwrap();
+    net.add_link(r3, e3).unwrap();
+
+    net.set_link_weight(r1, r3, 10.0).unwrap();
+    net.set_link_weight(r3, r1, 10.0).unwrap();
+
+    net.set_bgp_session(r1, r2, Some(IBgpPeer)).unwrap();
+    net.set_bgp_session(r1, r3, Some(IBgpPeer)).unwrap();
+    net.set_bgp_session(r2, r3, Some(IBgpPeer)).unwrap();
+    net.set_bgp_session(r2, e2, Some(EBgp)).unwrap();
+    net.set_bgp_session(r3, e3, Some(EBgp)).unwrap();
+
+    let p = SimplePrefix::from(0);
+    net.advertise_external_route(e2, p, [65102, 100], None, None)
+        .unwrap();
+    net.advertise_external_route(e3, p, [65103, 100], None, None)
+        .unwrap();
+
+    (net, [r1, r2, r3, e2, e3])
+}
+
+/// Number of internal routers that use each egress.
+fn egress_distribution(net: &Net) -> BTreeMap<RouterId, usize> {
+    let p = SimplePrefix::from(0);
+    let mut fw_state = net.get_forwarding_state();
+    let mut result = BTreeMap::new();
+    for r in net.internal_indices() {
+        for path in fw_state.get_paths(r, p).unwrap() {
+            *result.entry(*path.last().unwrap()).or_default() += 1;
+        }
+    }
+    result
+}
+
+fn set_weight(net: &mut Net, w: &f64) -> Result<(), NetworkError> {
+    let r1 = net.get_router_id("R1")?;
+    let r2 = net.get_router_id("R2")?;
+    net.set_link_weight(r1, r2, *w)?;
+    net.set_link_weight(r2, r1, *w)?;
+    Ok(())
+}
+
+#[test]
+fn sweep_link_weight() {
+    let (net, [_, _, _, e2, e3]) = get_test_net();
+    let result = sweep_sequential(
+        &net,
+        [1.0, 5.0, 15.0, 20.0],
+        set_weight,
+        egress_distribution,
+    );
+
+    let expected = vec![
+        (1.0, BTreeMap::from([(e2, 2), (e3, 1)])),
+        (5.0, BTreeMap::from([(e2, 2), (e3, 1)])),
+        (15.0, BTreeMap::from([(e2, 1), (e3, 2)])),
+        (20.0, BTreeMap::from([(e2, 1), (e3, 2)])),
+    ];
+    let result: Vec<_> = result.into_iter().map(|(w, r)| (w, r.unwrap())).collect();
+    assert_eq!(result, expected);
+
+    // the base network is not modified (R1 reaches R2 with the default link weight).
+    assert_eq!(
+        egress_distribution(&net),
+        BTreeMap::from([(e2, 1), (e3, 2)])
+    );
+}
+
+#[test]
+fn sweep_error() {
+    let (net, [_, _, _, e2, e3]) = get_test_net();
+    let result = sweep_sequential(
+        &net,
+        [e2, e3],
+        |net, e| net.set_link_weight(*e, *e, 1.0).map(|_| ()),
+        |_| (),
+    );
+    assert_eq!(result.len(), 2);
+    assert!(result.iter().all(|(_, r)| r.is_err()));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn sweep_parallel_equals_sequential() {
+    let (net, _) = get_test_net();
+    let weights: Vec<f64> = (1..=30).map(|w| w as f64).collect();
+    let seq = sweep_sequential(&net, weights.clone(), set_weight, egress_distribution);
+    let par = sweep(&net, weights, set_weight, egress_distribution);
+    let seq: Vec<_> = seq.into_iter().map(|(w, r)| (w, r.unwrap())).collect();
+    let par: Vec<_> = par.into_iter().map(|(w, r)| (w, r.unwrap())).collect();
+    assert_eq!(seq, par);
+}
@@ -162,7 +162,8 @@ impl TopologyZooParser {
                 .get_device(dst)
                 .map(|x| x.is_internal())
                 .unwrap_or(false);
-            if src_internal || dst_internal {
+            // some topologies contain self-loops, which are ignored.
+            if (src_internal || dst_internal) && src != dst {
                 links.push((src, dst));
             }
         }
@@ -1728,8 +1728,8 @@ pub enum TopologyZoo {
     /// - 110 routers
     /// - 105 internal routers
     /// - 5 external routers
-    /// - 148 edges
-    /// - 142 edges connecting two internal routers.
+    /// - 146 edges
+    /// - 141 edges connecting two internal routers.
     ///
     /// <img src="http://topology-zoo.org/maps/Interoute.jpg" alt="--- No image available ---" width="400"/>
     Interoute,
@@ -3412,7 +3412,7 @@ impl TopologyZoo {
             Self::Intellifiber => 95,
             Self::Internetmci => 33,
             Self::Internode => 77,
-            Self::Interoute => 148,
+            Self::Interoute => 146,
             Self::Intranetwork => 51,
             Self::Ion => 146,
             Self::IowaStatewideFiberMap => 41,
@@ -3679,7 +3679,7 @@ impl TopologyZoo {
             Self::Intellifiber => 95,
             Self::Internetmci => 33,
             Self::Internode => 31,
-            Self::Interoute => 142,
+            Self::Interoute => 141,
             Self::Intranetwork => 51,
             Self::Ion => 146,
             Self::IowaStatewideFiberMap => 38,
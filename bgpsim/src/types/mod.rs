@@ -17,7 +17,7 @@
 
 use crate::formatter::NetworkFormatter;
 use crate::ospf::local::LsaKey;
-use crate::ospf::OspfImpl;
+use crate::ospf::{LinkWeight, OspfAreaType, OspfImpl};
 use crate::{
     bgp::BgpSessionType, external_router::ExternalRouter, network::Network, router::Router,
 };
@@ -92,9 +92,122 @@ where
     }
 }
 
+/// BGP community, written as `asn:value`. A community given as a single 32-bit number `x` is
+/// interpreted as its RFC 1997 encoding, i.e., as `(x >> 16):(x & 0xFFFF)`. Small numbers thus
+/// never collide with the well-known communities in `65535:*`.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(from = "CommunityRepr")]
+pub struct Community(pub u32, pub u32);
+
+/// Serialized representation of a [`Community`]. Communities used to be serialized as a single
+/// number, which is still accepted.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CommunityRepr {
+    Pair(u32, u32),
+    Value(u32),
+}
+
+impl From<CommunityRepr> for Community {
+    fn from(x: CommunityRepr) -> Self {
+        match x {
+            CommunityRepr::Pair(asn, value) => Self(asn, value),
+            CommunityRepr::Value(value) => value.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for Community {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.0, self.1)
+    }
+}
+
+impl std::str::FromStr for Community {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().split_once(':') {
+            Some((asn, value)) => Ok(Self(asn.trim().parse()?, value.trim().parse()?)),
+            None => Ok(s.trim().parse::<u32>()?.into()),
+        }
+    }
+}
+
+impl From<u32> for Community {
+    fn from(x: u32) -> Self {
+        Self(x >> 16, x & 0xFFFF)
+    }
+}
+
+impl From<(u32, u32)> for Community {
+    fn from((asn, value): (u32, u32)) -> Self {
+        Self(asn, value)
+    }
+}
+
+impl<T> From<&T> for Community
+where
+    T: Into<Community> + Copy,
+{
+    fn from(x: &T) -> Self {
+        (*x).into()
+    }
+}
+
 /// IGP Network graph
 pub type PhysicalNetwork = StableGraph<(), (), Undirected, IndexType>;
 
+/// Hasher computing a 64-bit FNV-1a hash. In contrast to the `DefaultHasher` of the standard
+/// library, the result does not depend on the platform (integers are always hashed as 64-bit
+/// little-endian numbers), and can be stored, e.g., alongside a snapshot.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl StableHasher {
+    /// Compute the hash of a single value.
+    pub(crate) fn hash_one<T: std::hash::Hash + ?Sized>(x: &T) -> u64 {
+        let mut hasher = Self::default();
+        x.hash(&mut hasher);
+        std::hash::Hasher::finish(&hasher)
+    }
+}
+
+impl std::hash::Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64)
+    }
+}
+
 /// A series of FwDeltas
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum StepUpdate<P> {
@@ -222,6 +335,7 @@ pub enum ConfigError {
 #[serde(bound(
     deserialize = "P: for<'a> serde::Deserialize<'a>, Ospf: for<'a> serde::Deserialize<'a>"
 ))]
+#[allow(clippy::large_enum_variant)]
 pub enum NetworkDevice<P: Prefix, Ospf> {
     /// Internal router
     InternalRouter(Router<P, Ospf>),
@@ -463,6 +577,9 @@ pub enum DeviceError {
     /// OSPF Neighborhood does not exists.
     #[error("Routers {0:?} and {1:?} are not OSPF neighbors.")]
     NotAnOspfNeighbor(RouterId, RouterId),
+    /// The VRF does not exist on the router.
+    #[error("VRF {0} does not exist")]
+    VrfNotFound(String),
 }
 
 /// Network Errors
@@ -489,12 +606,27 @@ pub enum NetworkError {
     /// Cannot configure an external link
     #[error("External links cannot be configured using OSPF: {0:?} and {1:?}")]
     CannotConfigureExternalLink(RouterId, RouterId),
+    /// Cannot connect a router to itself
+    #[error("Cannot connect a router to itself: {0:?}")]
+    CannotConnectToItself(RouterId),
+    /// Link weights must not be negative or NaN
+    #[error("Invalid link weight for {0:?} -> {1:?}: {2}")]
+    InvalidLinkWeight(RouterId, RouterId, LinkWeight),
+    /// The backbone area cannot be configured as a stub area
+    #[error("The backbone area cannot be a {0} area")]
+    BackboneAreaType(OspfAreaType),
     /// Device must be an external router, but an internal router was passed
     #[error("Netowrk device cannot be an internal router: {0:?}")]
     DeviceIsInternalRouter(RouterId),
     /// Device name is not present in the topology
     #[error("Link does not exist: {0:?} -- {1:?}")]
     LinkNotFound(RouterId, RouterId),
+    /// A link bundle must have at least one member.
+    #[error("Link bundle without any member: {0:?} -- {1:?}")]
+    EmptyLinkBundle(RouterId, RouterId),
+    /// The link bundle has no member with the given index.
+    #[error("Link bundle {0:?} -- {1:?} has no member {2}")]
+    LinkBundleMemberNotFound(RouterId, RouterId, usize),
     /// Forwarding loop detected.
     ///
     /// The forwarding path can be constructed by calling
@@ -517,12 +649,39 @@ pub enum NetworkError {
         "Inconsistent BGP Session: both source {0:?} and target: {1:?} treat the other as client."
     )]
     InconsistentBgpSession(RouterId, RouterId),
+    /// The router is not configured as a route reflector, and thus, cannot have any clients.
+    #[error("Router {0:?} is not a route reflector")]
+    NotARouteReflector(RouterId),
+    /// The external router is a receive-only route collector, and cannot advertise any route.
+    #[error("Router {0:?} is receive-only and cannot advertise routes")]
+    ReceiveOnly(RouterId),
+    /// The external router is a route server, and cannot originate any route.
+    #[error("Router {0:?} is a route server and cannot originate routes")]
+    RouteServer(RouterId),
+    /// The community to LOCAL-PREF map has too many entries to be compiled into route-map items.
+    #[error("The community to LOCAL-PREF map has too many entries: {0}")]
+    CommunityMapTooLarge(usize),
+    /// A route-map item configured on the session with the given neighbor uses an order that is
+    /// reserved for the items compiled from a community map.
+    #[error(
+        "Route-map item {1} on the session with {0:?} uses an order reserved for community maps"
+    )]
+    ReservedRouteMapOrder(RouterId, i16),
+    /// The policy library does not contain a policy with the given name.
+    #[error("Policy {0} is not defined in the library")]
+    UnknownPolicy(String),
+    /// The peer-group is not defined.
+    #[error("Peer-group {0} is not defined")]
+    UnknownPeerGroup(String),
     /// Convergence Problem
     #[error("Network cannot converge in the given time!")]
     NoConvergence,
     /// The BGP table is invalid
     #[error("Invalid BGP table for router {0:?}")]
     InvalidBgpTable(RouterId),
+    /// Cannot warm-start from the given network
+    #[error("Cannot warm-start from a network with a different topology or configuration")]
+    InvalidWarmStart,
     /// Inconsistent OSPF State
     #[error("The OSPF distributed OSPF state is inconsistent for the LSA {0:?}")]
     InconsistentOspfState(LsaKey),
@@ -547,6 +706,16 @@ impl PartialEq for NetworkError {
             (Self::DeviceIsExternalRouter(l0), Self::DeviceIsExternalRouter(r0)) => l0 == r0,
             (Self::DeviceIsInternalRouter(l0), Self::DeviceIsInternalRouter(r0)) => l0 == r0,
             (Self::LinkNotFound(l0, l1), Self::LinkNotFound(r0, r1)) => l0 == r0 && l1 == r1,
+            (Self::CannotConnectToItself(l0), Self::CannotConnectToItself(r0)) => l0 == r0,
+            (Self::InvalidLinkWeight(l0, l1, l2), Self::InvalidLinkWeight(r0, r1, r2)) => {
+                l0 == r0 && l1 == r1 && (l2 == r2 || (l2.is_nan() && r2.is_nan()))
+            }
+            (Self::BackboneAreaType(l0), Self::BackboneAreaType(r0)) => l0 == r0,
+            (Self::EmptyLinkBundle(l0, l1), Self::EmptyLinkBundle(r0, r1)) => l0 == r0 && l1 == r1,
+            (
+                Self::LinkBundleMemberNotFound(l0, l1, l2),
+                Self::LinkBundleMemberNotFound(r0, r1, r2),
+            ) => l0 == r0 && l1 == r1 && l2 == r2,
             (
                 Self::ForwardingLoop {
                     to_loop: l0,
@@ -564,6 +733,15 @@ impl PartialEq for NetworkError {
             (Self::InconsistentBgpSession(l0, l1), Self::InconsistentBgpSession(r0, r1)) => {
                 l0 == r0 && l1 == r1
             }
+            (Self::NotARouteReflector(l0), Self::NotARouteReflector(r0)) => l0 == r0,
+            (Self::RouteServer(l0), Self::RouteServer(r0)) => l0 == r0,
+            (Self::ReceiveOnly(l0), Self::ReceiveOnly(r0)) => l0 == r0,
+            (Self::UnknownPolicy(l0), Self::UnknownPolicy(r0)) => l0 == r0,
+            (Self::UnknownPeerGroup(l0), Self::UnknownPeerGroup(r0)) => l0 == r0,
+            (Self::CommunityMapTooLarge(l0), Self::CommunityMapTooLarge(r0)) => l0 == r0,
+            (Self::ReservedRouteMapOrder(l0, l1), Self::ReservedRouteMapOrder(r0, r1)) => {
+                l0 == r0 && l1 == r1
+            }
             (Self::InvalidBgpTable(l0), Self::InvalidBgpTable(r0)) => l0 == r0,
             (Self::JsonError(l), Self::JsonError(r)) => l.to_string() == r.to_string(),
             _ => core::mem::discriminant(self) == core::mem::discriminant(other),